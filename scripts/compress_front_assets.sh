@@ -0,0 +1,22 @@
+#!/usr/bin/env bash
+#
+# Pre-compresses the built frontend assets (src/front-ltx/www/pkg): next to every .wasm and .js file, a .br (Brotli)
+# and a .gz (gzip) copy. The API server sends the one the browser accepts, without compressing on each request.
+#
+# Usage: ./scripts/compress_front_assets.sh [pkg directory]
+set -euo pipefail
+
+PKG_DIR="${1:-src/front-ltx/www/pkg}"
+
+if ! command -v brotli >/dev/null 2>&1; then
+  echo "brotli is not installed: only gzip copies are made" >&2
+fi
+
+find "$PKG_DIR" -type f \( -name '*.wasm' -o -name '*.js' \) | while read -r asset; do
+  gzip -9 -k -f "$asset"
+  if command -v brotli >/dev/null 2>&1; then
+    brotli -q 11 -k -f "$asset"
+  fi
+done
+
+echo "Pre-compressed the frontend assets in ${PKG_DIR}"
@@ -37,6 +37,16 @@ done
 
 echo "Database is accepting connections!"
 
+# api-ltx applies the migrations itself when RUN_MIGRATIONS is set
+RUN_MIGRATIONS="${RUN_MIGRATIONS:-}"
+case "${RUN_MIGRATIONS,,}" in
+  1|true|yes|y)
+    echo "RUN_MIGRATIONS is set: api-ltx will run the migrations"
+    echo "Starting API server..."
+    exec api-ltx
+    ;;
+esac
+
 # Run diesel setup (creates database if needed, sets up diesel metadata)
 echo "Setting up diesel database..."
 if diesel database setup --locked-schema; then
@@ -0,0 +1,2 @@
+-- W3C traceparent of the request that created the job, so the worker can continue the same trace
+ALTER TABLE job_state ADD COLUMN trace_context TEXT;
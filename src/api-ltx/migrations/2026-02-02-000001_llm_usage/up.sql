@@ -0,0 +1,10 @@
+-- Tokens used by the LLM calls of each job, for enforcing the daily token budget
+CREATE TABLE llm_usage (
+    job_id UUID PRIMARY KEY REFERENCES job_state (job_id) ON DELETE CASCADE,
+    prompt_tokens BIGINT NOT NULL,
+    completion_tokens BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Create index for summing today's usage
+CREATE INDEX llm_usage_created_at_idx ON llm_usage (created_at);
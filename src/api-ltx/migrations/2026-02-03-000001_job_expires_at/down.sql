@@ -0,0 +1,3 @@
+ALTER TABLE job_state DROP COLUMN IF EXISTS expires_at;
+-- Postgres can't drop an enum value: 'expired' stays in job_status, expired jobs are marked failed instead
+UPDATE job_state SET status = 'failure' WHERE status = 'expired';
@@ -0,0 +1,3 @@
+-- Optional deadline: a job still queued past it is marked 'expired' instead of being worked on
+ALTER TYPE job_status ADD VALUE IF NOT EXISTS 'expired';
+ALTER TABLE job_state ADD COLUMN expires_at TIMESTAMPTZ;
@@ -0,0 +1,2 @@
+-- Why a failed job failed, as a FailureKind code (e.g. 'timeout', 'http_status:406')
+ALTER TABLE job_state ADD COLUMN failure_kind TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS source;
+DROP TYPE IF EXISTS llms_txt_source;
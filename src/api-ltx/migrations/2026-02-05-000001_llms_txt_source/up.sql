@@ -0,0 +1,3 @@
+-- Where an llms.txt came from: generated by the LLM, or imported from the website's own /llms.txt
+CREATE TYPE llms_txt_source AS ENUM ('generated', 'origin');
+ALTER TABLE llms_txt ADD COLUMN source llms_txt_source NOT NULL DEFAULT 'generated';
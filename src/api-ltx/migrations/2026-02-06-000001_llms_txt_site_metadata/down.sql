@@ -0,0 +1,4 @@
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS favicon_url;
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS canonical_url;
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS description;
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS title;
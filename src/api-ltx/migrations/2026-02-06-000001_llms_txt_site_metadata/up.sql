@@ -0,0 +1,5 @@
+-- What the page says about itself, so websites can be listed by name instead of by URL
+ALTER TABLE llms_txt ADD COLUMN title TEXT;
+ALTER TABLE llms_txt ADD COLUMN description TEXT;
+ALTER TABLE llms_txt ADD COLUMN canonical_url TEXT;
+ALTER TABLE llms_txt ADD COLUMN favicon_url TEXT;
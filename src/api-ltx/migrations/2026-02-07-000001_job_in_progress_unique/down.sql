@@ -0,0 +1 @@
+DROP INDEX IF EXISTS job_state_one_in_progress_per_url;
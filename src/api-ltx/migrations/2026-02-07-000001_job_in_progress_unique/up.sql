@@ -0,0 +1,20 @@
+-- Before the index existed, concurrent requests could queue several jobs for one URL.
+-- Keep one in-progress job per URL (preferring the one a worker is running, then the oldest) and expire the rest.
+UPDATE job_state
+SET status = 'expired'
+WHERE job_id IN (
+    SELECT job_id FROM (
+        SELECT
+            job_id,
+            ROW_NUMBER() OVER (
+                PARTITION BY url
+                ORDER BY (status = 'running') DESC, created_at ASC, job_id ASC
+            ) AS rank
+        FROM job_state
+        WHERE status IN ('queued', 'running')
+    ) AS in_progress
+    WHERE rank > 1
+);
+
+-- At most one queued or running job per URL. Job creation inserts with ON CONFLICT DO NOTHING against it.
+CREATE UNIQUE INDEX job_state_one_in_progress_per_url ON job_state (url) WHERE status IN ('queued', 'running');
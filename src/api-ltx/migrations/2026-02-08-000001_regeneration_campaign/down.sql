@@ -0,0 +1 @@
+DROP TABLE regeneration_campaign;
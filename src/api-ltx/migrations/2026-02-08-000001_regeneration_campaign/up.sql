@@ -0,0 +1,15 @@
+-- Bulk re-generation of stored llms.txt files (e.g. after the prompt or the model is upgraded).
+-- The filters that selected the URLs are kept alongside the progress of enqueuing their Update jobs.
+CREATE TABLE regeneration_campaign (
+    campaign_id UUID PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    domain TEXT,
+    older_than TIMESTAMPTZ,
+    provider llms_txt_source,
+    quality_below INT4,
+    total INT4 NOT NULL,
+    enqueued INT4 NOT NULL DEFAULT 0,
+    skipped INT4 NOT NULL DEFAULT 0,
+    failed INT4 NOT NULL DEFAULT 0
+);
@@ -0,0 +1,2 @@
+ALTER TABLE regeneration_campaign DROP COLUMN IF EXISTS outdated_prompt;
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS prompt_version;
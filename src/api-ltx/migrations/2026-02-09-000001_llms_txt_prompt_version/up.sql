@@ -0,0 +1,4 @@
+-- Version of the prompts an llms.txt was generated with (NULL when no prompt was used, or it's not known),
+-- so that the ones generated with older prompts can be re-generated
+ALTER TABLE llms_txt ADD COLUMN prompt_version TEXT;
+ALTER TABLE regeneration_campaign ADD COLUMN outdated_prompt BOOLEAN NOT NULL DEFAULT false;
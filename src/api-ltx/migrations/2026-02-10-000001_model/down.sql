@@ -0,0 +1,2 @@
+ALTER TABLE llms_txt DROP COLUMN IF EXISTS model;
+ALTER TABLE job_state DROP COLUMN IF EXISTS model;
@@ -0,0 +1,4 @@
+-- The LLM a job asked for (NULL: the worker's configured model), and the one that generated an llms.txt
+-- (NULL when it wasn't generated, or it's not known)
+ALTER TABLE job_state ADD COLUMN model TEXT;
+ALTER TABLE llms_txt ADD COLUMN model TEXT;
@@ -0,0 +1,7 @@
+-- What the worker logged while working on each job (capped in size), for debugging a job without the server logs
+CREATE TABLE job_logs (
+    job_id UUID PRIMARY KEY REFERENCES job_state (job_id) ON DELETE CASCADE,
+    log TEXT NOT NULL,
+    truncated BOOLEAN NOT NULL DEFAULT false,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
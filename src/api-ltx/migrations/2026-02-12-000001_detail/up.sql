@@ -0,0 +1,4 @@
+-- How detailed a job's llms.txt should be (brief, standard or comprehensive), and the level an llms.txt was generated
+-- at (NULL when it wasn't generated, or it's not known)
+ALTER TABLE job_state ADD COLUMN detail TEXT NOT NULL DEFAULT 'standard';
+ALTER TABLE llms_txt ADD COLUMN detail TEXT;
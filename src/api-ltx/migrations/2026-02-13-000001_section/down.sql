@@ -0,0 +1,2 @@
+ALTER TABLE job_state DROP COLUMN IF EXISTS section_url;
+ALTER TABLE job_state DROP COLUMN IF EXISTS section;
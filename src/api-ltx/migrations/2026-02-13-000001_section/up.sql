@@ -0,0 +1,4 @@
+-- Update jobs that regenerate only one H2 section of the llms.txt (NULL: the whole file), and the page to regenerate
+-- it from (NULL: the job's URL)
+ALTER TABLE job_state ADD COLUMN section TEXT;
+ALTER TABLE job_state ADD COLUMN section_url TEXT;
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS crawl_schedule;
+ALTER TABLE job_state DROP COLUMN IF EXISTS crawl_max_pages;
+ALTER TABLE job_state DROP COLUMN IF EXISTS crawl_max_depth;
@@ -0,0 +1,16 @@
+-- Jobs that crawl the website from their URL, and generate the llms.txt from every page found (NULL: only the URL's
+-- page is downloaded)
+ALTER TABLE job_state ADD COLUMN crawl_max_depth INT4;
+ALTER TABLE job_state ADD COLUMN crawl_max_pages INT4;
+
+-- Recurring full-site re-crawls of a URL. Instead of checking whether the URL's page changed, cron enqueues a crawl
+-- job for it whenever next_run_at has passed, and moves next_run_at interval_s seconds later.
+CREATE TABLE crawl_schedule (
+    url TEXT PRIMARY KEY,
+    interval_s INT8 NOT NULL,
+    max_depth INT4 NOT NULL,
+    max_pages INT4 NOT NULL,
+    next_run_at TIMESTAMPTZ NOT NULL,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+-- Which hosts jobs can be created for: glob patterns of hosts that are blocked, or, once there's an allowed one, the
+-- only ones allowed
+CREATE TABLE domain_policy (
+    pattern TEXT PRIMARY KEY,
+    rule TEXT NOT NULL CHECK (rule IN ('allow', 'block')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
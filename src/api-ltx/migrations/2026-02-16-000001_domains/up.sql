@@ -0,0 +1,9 @@
+-- Credentials of the domains that are behind a login (e.g. internal documentation portals), encrypted with the
+-- CREDENTIALS_KEY: the worker sends them with its requests to the domain
+CREATE TABLE domains (
+    domain TEXT PRIMARY KEY,
+    credential_kind TEXT NOT NULL CHECK (credential_kind IN ('basic', 'bearer', 'cookie')),
+    credential TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
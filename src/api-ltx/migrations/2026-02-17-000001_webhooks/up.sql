@@ -0,0 +1,35 @@
+-- Endpoints that are notified of finished jobs. The secret signs every payload (HMAC-SHA256) so the receiver can check
+-- it came from us: it's encrypted with the SECRETS_KEYS, like the domains' credentials.
+CREATE TABLE webhooks (
+    webhook_id UUID PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One event to deliver to one webhook. The worker sends it once next_attempt_at has passed, and retries failed
+-- attempts with backoff until it gives up (status 'failed').
+CREATE TABLE webhook_deliveries (
+    delivery_id UUID PRIMARY KEY,
+    webhook_id UUID NOT NULL REFERENCES webhooks (webhook_id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('pending', 'delivered', 'failed')),
+    attempts INT4 NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX webhook_deliveries_due_idx ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+CREATE INDEX webhook_deliveries_webhook_idx ON webhook_deliveries (webhook_id, created_at);
+
+-- Every attempt to deliver: the receiver's response code, or why there was none (e.g. a timeout)
+CREATE TABLE webhook_delivery_attempts (
+    delivery_id UUID NOT NULL REFERENCES webhook_deliveries (delivery_id) ON DELETE CASCADE,
+    attempt INT4 NOT NULL,
+    status_code INT4,
+    error TEXT,
+    attempted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (delivery_id, attempt)
+);
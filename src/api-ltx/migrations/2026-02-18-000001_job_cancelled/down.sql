@@ -0,0 +1,2 @@
+-- Postgres can't drop an enum value: 'cancelled' stays in job_status, cancelled jobs are marked failed instead
+UPDATE job_state SET status = 'failure' WHERE status = 'cancelled';
@@ -0,0 +1,2 @@
+-- Jobs cancelled through the API: queued ones are never claimed, running ones are abandoned by their worker
+ALTER TYPE job_status ADD VALUE IF NOT EXISTS 'cancelled';
@@ -0,0 +1,18 @@
+-- The second factor of the login: a TOTP secret (RFC 6238), encrypted with the SECRETS_KEYS like the other secrets.
+-- There's one password, so there's at most one row. It's pending until a code from the authenticator app confirms
+-- the enrollment (confirmed_at): only then do logins need a code. last_used_step is the time step of the last code
+-- that was accepted, so a code can't be replayed.
+CREATE TABLE totp (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    secret TEXT NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    last_used_step BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One-time codes that log in without the authenticator app. Only their SHA-256 is kept.
+CREATE TABLE totp_recovery_codes (
+    code_hash TEXT PRIMARY KEY,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,13 @@
+-- Keys that authenticate scripts and other services (e.g. a remote CLI) as an `Authorization: Bearer` header, in place
+-- of a login session. Only their SHA-256 is kept: a key is shown once, when it's created. prefix is the start of the
+-- key, so it can be told apart in listings. A revoked key (revoked_at) doesn't authenticate anymore, but is kept so
+-- listings show when it was revoked.
+CREATE TABLE api_keys (
+    key_id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    key_hash TEXT NOT NULL UNIQUE,
+    prefix TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,3 @@
+ALTER TABLE llms_txt
+    DROP COLUMN preview_truncated,
+    DROP COLUMN preview;
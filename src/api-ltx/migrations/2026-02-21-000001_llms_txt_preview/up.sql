@@ -0,0 +1,22 @@
+-- The start of every llms.txt (its first 20 lines, up to 2000 characters, without trailing newlines), so that listings
+-- don't read the whole llms.txt of every website: `data_model_ltx::models::llms_txt_preview`. preview_truncated is
+-- whether the llms.txt is longer than that. Failures have no preview, and neither do the rows stored compressed
+-- (starting with the codec marker): their preview is computed when they're listed.
+ALTER TABLE llms_txt
+    ADD COLUMN preview TEXT,
+    ADD COLUMN preview_truncated BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE llms_txt
+SET preview = p.preview,
+    preview_truncated = length(p.preview) < length(p.llms_txt)
+FROM (
+    SELECT job_id,
+           llms_txt,
+           left(array_to_string((string_to_array(llms_txt, E'\n'))[1:20], E'\n'), 2000) AS preview
+    FROM (
+        SELECT job_id, rtrim(result_data, E'\n') AS llms_txt
+        FROM llms_txt
+        WHERE result_status = 'ok' AND result_data NOT LIKE E'\x01br:%'
+    ) AS plain
+) AS p
+WHERE llms_txt.job_id = p.job_id;
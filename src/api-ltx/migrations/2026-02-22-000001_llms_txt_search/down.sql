@@ -0,0 +1,2 @@
+DROP INDEX llms_txt_search_vector_idx;
+ALTER TABLE llms_txt DROP COLUMN search_vector;
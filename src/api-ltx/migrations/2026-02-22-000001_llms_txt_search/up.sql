@@ -0,0 +1,15 @@
+-- Full-text search of the llms.txt files (GET /api/search): the `english` tsvector of a record's URL and llms.txt.
+-- Records stored compressed can't be read here, so it's computed from the decompressed llms.txt by whoever inserts
+-- the record (`data_model_ltx::search::index_llms_txt`). This fills it in for the records stored as is. The few
+-- stored compressed before it get their URL and their preview indexed, until their website's next generation.
+-- Failures aren't indexed.
+ALTER TABLE llms_txt ADD COLUMN search_vector TSVECTOR;
+
+UPDATE llms_txt
+SET search_vector = to_tsvector(
+    'english',
+    url || E'\n' || CASE WHEN result_data LIKE E'\x01br:%' THEN coalesce(preview, '') ELSE result_data END
+)
+WHERE result_status = 'ok';
+
+CREATE INDEX llms_txt_search_vector_idx ON llms_txt USING GIN (search_vector);
@@ -0,0 +1 @@
+ALTER TABLE job_state DROP COLUMN attempts;
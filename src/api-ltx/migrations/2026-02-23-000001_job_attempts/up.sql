@@ -0,0 +1,2 @@
+-- Which attempt at a generation a job is: 1, then one more for each retry of a failed job (POST /api/job/retry)
+ALTER TABLE job_state ADD COLUMN attempts INTEGER NOT NULL DEFAULT 1;
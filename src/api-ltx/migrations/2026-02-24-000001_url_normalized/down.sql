@@ -0,0 +1,7 @@
+DROP INDEX job_state_url_normalized_idx;
+DROP INDEX job_state_one_in_progress_per_url;
+CREATE UNIQUE INDEX job_state_one_in_progress_per_url ON job_state (url) WHERE status IN ('queued', 'running');
+DROP INDEX llms_txt_url_normalized_idx;
+ALTER TABLE job_state DROP COLUMN url_normalized;
+ALTER TABLE llms_txt DROP COLUMN url_normalized;
+DROP FUNCTION normalize_url(TEXT);
@@ -0,0 +1,52 @@
+-- Records are looked up by URL however it's spelled: `https://Example.com:443?b=2&a=1` is `https://example.com/?a=1&b=2`.
+-- The key of a URL lowercases its scheme and host, drops the scheme's default port and an empty query, makes an empty
+-- path `/`, and sorts the query's parameters by name (parameters with the same name keep their order). Anything that
+-- isn't `scheme://authority...` is its own key.
+CREATE FUNCTION normalize_url(url TEXT) RETURNS TEXT
+LANGUAGE SQL IMMUTABLE STRICT PARALLEL SAFE
+AS $$
+    SELECT CASE WHEN m.p IS NULL THEN url ELSE
+        lower(m.p[1]) || '://' || coalesce(m.p[2], '') ||
+        CASE
+            WHEN lower(m.p[1]) = 'http' AND m.p[3] LIKE '%:80' THEN lower(left(m.p[3], -3))
+            WHEN lower(m.p[1]) = 'https' AND m.p[3] LIKE '%:443' THEN lower(left(m.p[3], -4))
+            WHEN m.p[3] LIKE '%:' THEN lower(left(m.p[3], -1))
+            ELSE lower(m.p[3])
+        END ||
+        CASE WHEN m.p[4] = '' AND lower(m.p[1]) IN ('http', 'https') THEN '/' ELSE m.p[4] END ||
+        coalesce('?' || (
+            SELECT string_agg(q.param, '&' ORDER BY split_part(q.param, '=', 1) COLLATE "C", q.position)
+            FROM unnest(string_to_array(substr(m.p[5], 2), '&')) WITH ORDINALITY AS q(param, position)
+            WHERE q.param <> ''
+        ), '') ||
+        coalesce(m.p[6], '')
+    END
+    FROM regexp_match(url, '^([A-Za-z][A-Za-z0-9+.-]*)://([^/?#@]*@)?([^/?#]*)([^?#]*)(\?[^#]*)?(#.*)?$') AS m(p)
+$$;
+
+ALTER TABLE llms_txt ADD COLUMN url_normalized TEXT NOT NULL GENERATED ALWAYS AS (normalize_url(url)) STORED;
+ALTER TABLE job_state ADD COLUMN url_normalized TEXT NOT NULL GENERATED ALWAYS AS (normalize_url(url)) STORED;
+
+CREATE INDEX llms_txt_url_normalized_idx ON llms_txt (url_normalized, created_at DESC);
+
+-- At most one queued or running job per URL, however it's spelled. Spellings could each have had one: keep one per
+-- URL (preferring the one a worker is running, then the oldest) and expire the rest.
+UPDATE job_state
+SET status = 'expired'
+WHERE job_id IN (
+    SELECT job_id FROM (
+        SELECT
+            job_id,
+            ROW_NUMBER() OVER (
+                PARTITION BY url_normalized
+                ORDER BY (status = 'running') DESC, created_at ASC, job_id ASC
+            ) AS rank
+        FROM job_state
+        WHERE status IN ('queued', 'running')
+    ) AS in_progress
+    WHERE rank > 1
+);
+
+DROP INDEX job_state_one_in_progress_per_url;
+CREATE UNIQUE INDEX job_state_one_in_progress_per_url ON job_state (url_normalized) WHERE status IN ('queued', 'running');
+CREATE INDEX job_state_url_normalized_idx ON job_state (url_normalized);
@@ -0,0 +1 @@
+DROP INDEX job_state_created_at_idx;
@@ -0,0 +1,2 @@
+-- GET /api/jobs lists every job, newest first, a page at a time.
+CREATE INDEX job_state_created_at_idx ON job_state (created_at DESC, job_id);
@@ -0,0 +1,12 @@
+-- The archived records go back where they came from.
+INSERT INTO llms_txt (
+    job_id, url, result_data, result_status, created_at, html_compress, html_checksum, source, title, description,
+    canonical_url, favicon_url, prompt_version, model, detail
+)
+SELECT
+    job_id, url, result_data, result_status, created_at, html_compress, html_checksum, source, title, description,
+    canonical_url, favicon_url, prompt_version, model, detail
+FROM llms_txt_archive
+ON CONFLICT (job_id) DO NOTHING;
+
+DROP TABLE llms_txt_archive;
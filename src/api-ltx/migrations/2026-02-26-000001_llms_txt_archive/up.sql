@@ -0,0 +1,26 @@
+-- Archive tier of the llms.txt records. A record is superseded once its website has a newer successful one: the
+-- worker moves superseded records older than LLMS_TXT_ARCHIVE_AFTER here (see `data_model_ltx::archive`), so that
+-- llms_txt only grows with the number of websites, and its listings, dedup checks and cron scans stay fast.
+-- The same columns, but nothing to list or search them by: no preview, nor search vector.
+CREATE TABLE llms_txt_archive (
+    job_id UUID PRIMARY KEY,
+    url TEXT NOT NULL,
+    url_normalized TEXT NOT NULL,
+    result_data TEXT NOT NULL,
+    result_status result_status NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    html_compress BYTEA NOT NULL,
+    html_checksum VARCHAR(32) NOT NULL,
+    source llms_txt_source NOT NULL,
+    title TEXT,
+    description TEXT,
+    canonical_url TEXT,
+    favicon_url TEXT,
+    prompt_version TEXT,
+    model TEXT,
+    detail TEXT,
+    archived_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- A website's history (GET /api/llm_txt/history) and its removal read it by URL.
+CREATE INDEX llms_txt_archive_url_normalized_idx ON llms_txt_archive (url_normalized, created_at DESC);
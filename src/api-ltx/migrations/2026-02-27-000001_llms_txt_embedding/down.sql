@@ -0,0 +1 @@
+DROP TABLE IF EXISTS llms_txt_embedding;
@@ -0,0 +1,22 @@
+-- Embeddings of the llms.txt files, for semantic search (GET /api/search/semantic): the worker embeds every website's
+-- latest llms.txt with `EMBEDDING_MODEL` (see `data_model_ltx::embeddings`). They're pgvector vectors: without the
+-- extension, or the privilege to create it, there's no table, and semantic search is off.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'vector') THEN
+        CREATE EXTENSION IF NOT EXISTS vector;
+        CREATE TABLE llms_txt_embedding (
+            job_id UUID PRIMARY KEY REFERENCES llms_txt (job_id) ON DELETE CASCADE,
+            model TEXT NOT NULL,
+            embedding vector(1536) NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        CREATE INDEX llms_txt_embedding_idx ON llms_txt_embedding USING hnsw (embedding vector_cosine_ops);
+    ELSE
+        RAISE NOTICE 'pgvector is not installed: semantic search is off';
+    END IF;
+EXCEPTION
+    WHEN insufficient_privilege THEN
+        RAISE NOTICE 'Not allowed to create the pgvector extension: semantic search is off';
+END
+$$;
@@ -0,0 +1 @@
+ALTER TABLE job_state DROP COLUMN started_at;
@@ -0,0 +1,3 @@
+-- When a worker claimed the job, i.e. since when it's been running: POST /api/admin/jobs/purge_stale finds the jobs
+-- whose worker died mid-run by it. Jobs that were running before this have none: their created_at stands in for it.
+ALTER TABLE job_state ADD COLUMN started_at TIMESTAMPTZ;
@@ -1,35 +1,46 @@
 use axum::{
-    Json,
-    extract::State,
+    Extension, Json,
+    extract::{ConnectInfo, State},
     http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
+use chrono::Utc;
 use core_ltx::AuthConfig;
+use core_ltx::db::DbPool;
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::time::{Duration, sleep};
-use tracing::{debug, warn};
+use tracing::{debug, error, info, warn};
+use utoipa::ToSchema;
 
 use super::password::verify_password;
 use super::session::{
     create_logout_cookie, create_session_cookie, generate_session_token, parse_session_cookie, validate_session_token,
 };
+use super::throttle::{LOGIN_THROTTLE, Throttled};
+use super::two_factor::{SecondFactor, confirmed_second_factor, verify_second_factor};
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     password: String,
+    /// A code of the authenticator app, or a recovery code. Needed once two-factor authentication is enabled.
+    #[serde(default)]
+    code: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
     success: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct AuthCheckResponse {
     auth_enabled: bool,
     authenticated: bool,
+    /// Whether read-only endpoints are available without logging in.
+    public_reads: bool,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -42,15 +53,54 @@ pub enum AuthError {
 
     #[error("Password error: {0}")]
     PasswordError(String),
+
+    #[error("Too many failed login attempts: retry in {}s", .0.retry_after().as_secs())]
+    TooManyAttempts(Throttled),
+
+    #[error("A code of the authenticator app (or a recovery code) is required")]
+    SecondFactorRequired,
+
+    #[error("Invalid code")]
+    InvalidSecondFactor,
+
+    #[error("Second factor error: {0}")]
+    SecondFactorError(String),
 }
 
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
+        if let AuthError::TooManyAttempts(throttled) = self {
+            // rounded up: retrying right at Retry-After is allowed
+            let retry_after = throttled.retry_after().as_secs_f64().ceil() as u64;
+            let body = Json(serde_json::json!({
+                "error": "Too many failed login attempts",
+                "retry_after_s": retry_after,
+            }));
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                body,
+            )
+                .into_response();
+        }
+
+        if let AuthError::SecondFactorRequired | AuthError::InvalidSecondFactor = self {
+            // the login page asks for a code when it gets these
+            let body = Json(serde_json::json!({
+                "error": self.to_string(),
+                "second_factor_required": true,
+            }));
+            return (StatusCode::UNAUTHORIZED, body).into_response();
+        }
+
         let (status, message) = match self {
             AuthError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid credentials"),
-            AuthError::SessionError(_) | AuthError::PasswordError(_) => {
-                (StatusCode::INTERNAL_SERVER_ERROR, "Authentication error")
-            }
+            AuthError::SessionError(_)
+            | AuthError::PasswordError(_)
+            | AuthError::TooManyAttempts(_)
+            | AuthError::SecondFactorRequired
+            | AuthError::InvalidSecondFactor
+            | AuthError::SecondFactorError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Authentication error"),
         };
 
         let body = Json(serde_json::json!({
@@ -62,12 +112,32 @@ impl IntoResponse for AuthError {
 }
 
 /// POST /api/auth/login
-/// Authenticates user with password, enforces minimum 1-second response time
+/// Authenticates user with password, enforces minimum 1-second response time.
+/// Failed attempts are throttled per client IP address and overall (see `LoginThrottle`): a throttled attempt gets a
+/// 429 with a Retry-After header, without checking the password. Every attempt is logged with the `audit` target.
+/// Once two-factor authentication is enabled, the right password alone gets a 401 with `second_factor_required`: the
+/// login is sent again with a `code`. A wrong code counts as a failed attempt.
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    request_body = LoginRequest,
+    security(()),
+    responses(
+        (status = 200, description = "Logged in: the session cookie is set", body = LoginResponse),
+        (status = 401, description = "Invalid password, or a code is needed"),
+        (status = 429, description = "Too many failed attempts: retry after Retry-After seconds"),
+    )
+)]
 pub async fn post_login(
-    State(auth_config): State<Arc<Option<AuthConfig>>>,
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
     Json(request): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, AuthError> {
     let start = Instant::now();
+    let client = connect_info.map(|Extension(ConnectInfo(addr))| addr.ip());
+    let client_name = client.map_or_else(|| "unknown address".to_string(), |ip| ip.to_string());
 
     // Get auth config (should always be Some when this handler is reachable)
     let config = auth_config
@@ -75,8 +145,25 @@ pub async fn post_login(
         .as_ref()
         .ok_or_else(|| AuthError::SessionError("Auth not configured".to_string()))?;
 
-    let is_valid = verify_password(&request.password, &config.password_hash)
-        .map_err(|e| AuthError::PasswordError(e.to_string()))?;
+    if let Err(throttled) = LOGIN_THROTTLE.check(client, start) {
+        warn!(
+            target: "audit",
+            "Login attempt from {} rejected: {} must wait {:?}",
+            client_name,
+            match throttled {
+                Throttled::Client(_) => "the client",
+                Throttled::Account(_) => "every client",
+            },
+            throttled.retry_after()
+        );
+        return Err(AuthError::TooManyAttempts(throttled));
+    }
+
+    // the attempt is settled below, or released when it's neither a failure nor a success
+    let is_valid = verify_password(&request.password, &config.password_hash).map_err(|e| {
+        LOGIN_THROTTLE.release(client);
+        AuthError::PasswordError(e.to_string())
+    })?;
 
     // Ensure minimum 1 second elapsed (timing attack protection)
     let elapsed = start.elapsed();
@@ -85,16 +172,48 @@ pub async fn post_login(
     }
 
     if !is_valid {
-        warn!("Failed login attempt");
+        record_failed_login(client, &client_name, "wrong password");
         return Err(AuthError::InvalidCredentials);
     }
 
+    let second_factor = confirmed_second_factor(&pool).await.map_err(|e| {
+        LOGIN_THROTTLE.release(client);
+        AuthError::SecondFactorError(e.to_string())
+    })?;
+    if let Some(second_factor) = second_factor {
+        let Some(code) = request.code.as_deref().filter(|code| !code.trim().is_empty()) else {
+            LOGIN_THROTTLE.release(client);
+            info!(target: "audit", "Right password from {}: asking for the second factor", client_name);
+            return Err(AuthError::SecondFactorRequired);
+        };
+        let verified = verify_second_factor(&pool, &second_factor, code, Utc::now())
+            .await
+            .map_err(|e| {
+                LOGIN_THROTTLE.release(client);
+                AuthError::SecondFactorError(e.to_string())
+            })?;
+        match verified {
+            None => {
+                record_failed_login(client, &client_name, "wrong code");
+                return Err(AuthError::InvalidSecondFactor);
+            }
+            Some(SecondFactor::RecoveryCode { left }) => warn!(
+                target: "audit",
+                "Login from {} with a recovery code ({} left)",
+                client_name,
+                left
+            ),
+            Some(SecondFactor::Totp) => {}
+        }
+    }
+
+    LOGIN_THROTTLE.record_success(client);
+    info!(target: "audit", "Successful login from {}", client_name);
+
     let token = generate_session_token(&config.session_secret).map_err(|e| AuthError::SessionError(e.to_string()))?;
 
     let cookie = create_session_cookie(&token, config.session_duration_seconds);
 
-    debug!("Successful login");
-
     Ok((
         StatusCode::OK,
         [(header::SET_COOKIE, cookie.to_string())],
@@ -102,8 +221,44 @@ pub async fn post_login(
     ))
 }
 
+/// Counts a failed login for throttling, and logs it (and the lockouts it started).
+fn record_failed_login(client: Option<IpAddr>, client_name: &str, reason: &str) {
+    let lockouts = LOGIN_THROTTLE.record_failure(client, Instant::now());
+    match lockouts.client {
+        Some(lockout) => error!(
+            target: "audit",
+            "Too many failed logins from {}: locked out for {:?}",
+            client_name,
+            lockout
+        ),
+        None => warn!(
+            target: "audit",
+            "Failed login attempt from {} ({}, {} in a row)",
+            client_name,
+            reason,
+            client.map_or(0, |ip| LOGIN_THROTTLE.failures(ip))
+        ),
+    }
+    if let Some(lockout) = lockouts.account {
+        error!(
+            target: "audit",
+            "Too many failed logins overall: every login is locked out for {:?}",
+            lockout
+        );
+    }
+}
+
 /// POST /api/auth/logout
 /// Clears the session cookie
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    security(()),
+    responses(
+        (status = 200, description = "Logged out: the session cookie is cleared"),
+    )
+)]
 pub async fn post_logout() -> impl IntoResponse {
     let cookie = create_logout_cookie();
     debug!("User logged out");
@@ -116,11 +271,21 @@ pub async fn post_logout() -> impl IntoResponse {
 
 /// GET /api/auth/check
 /// Returns authentication status
+#[utoipa::path(
+    get,
+    path = "/api/auth/check",
+    tag = "auth",
+    security(()),
+    responses(
+        (status = 200, description = "Whether authentication is enabled, and the request authenticated", body = AuthCheckResponse),
+    )
+)]
 pub async fn get_check(
     State(auth_config): State<Arc<Option<AuthConfig>>>,
     headers: axum::http::HeaderMap,
 ) -> impl IntoResponse {
     let auth_enabled = auth_config.is_some();
+    let public_reads = auth_config.as_ref().as_ref().is_some_and(|config| config.public_reads);
 
     let authenticated = if let Some(config) = auth_config.as_ref() {
         // Check if valid session cookie exists
@@ -140,5 +305,6 @@ pub async fn get_check(
     Json(AuthCheckResponse {
         auth_enabled,
         authenticated,
+        public_reads,
     })
 }
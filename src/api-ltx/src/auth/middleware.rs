@@ -1,26 +1,40 @@
 use axum::{
     Json,
     extract::{Request, State},
-    http::{StatusCode, header},
+    http::{HeaderMap, StatusCode, header},
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use chrono::Utc;
 use core_ltx::AuthConfig;
+use core_ltx::db::DbPool;
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, error};
 
 use super::session::{parse_session_cookie, validate_session_token};
+use crate::routes::api_keys::authenticate_api_key;
+
+/// What `require_auth` checks requests with: the auth config for session cookies, and the database for API keys.
+#[derive(Clone)]
+pub struct AuthState {
+    pub auth_config: Arc<Option<AuthConfig>>,
+    pub pool: DbPool,
+}
+
+/// The token of an `Authorization: Bearer <token>` header.
+pub fn parse_bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    let (scheme, token) = value.split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
 
 /// Middleware to require authentication when enabled
 /// If auth is disabled, requests pass through immediately
-/// If auth is enabled, validates session cookie
-pub async fn require_auth(
-    State(auth_config): State<Arc<Option<AuthConfig>>>,
-    request: Request,
-    next: Next,
-) -> Result<Response, Response> {
+/// If auth is enabled, validates session cookie, or else an API key sent as an `Authorization: Bearer` header
+pub async fn require_auth(State(state): State<AuthState>, request: Request, next: Next) -> Result<Response, Response> {
     // If auth is not enabled, pass through immediately
-    let config = match auth_config.as_ref() {
+    let config = match state.auth_config.as_ref() {
         Some(cfg) => cfg,
         None => {
             debug!("Auth not enabled, passing request through");
@@ -40,6 +54,17 @@ pub async fn require_auth(
         false
     };
 
+    let is_authenticated = match parse_bearer_token(request.headers()) {
+        Some(api_key) if !is_authenticated => match authenticate_api_key(&state.pool, api_key, Utc::now()).await {
+            Ok(valid) => valid,
+            Err(e) => {
+                error!("Failed to check an API key: {:?}", e);
+                return Err(e.into_response());
+            }
+        },
+        _ => is_authenticated,
+    };
+
     if is_authenticated {
         debug!("Request authenticated");
         Ok(next.run(request).await)
@@ -2,6 +2,10 @@ pub mod handlers;
 pub mod middleware;
 pub mod password;
 pub mod session;
+pub mod throttle;
+pub mod totp;
+pub mod two_factor;
 
 pub use handlers::{get_check, post_login, post_logout};
-pub use middleware::require_auth;
+pub use middleware::{AuthState, require_auth};
+pub use two_factor::{get_totp, post_totp_confirm, post_totp_disable, post_totp_enroll, post_totp_recovery_codes};
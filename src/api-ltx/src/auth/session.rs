@@ -6,7 +6,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 type HmacSha256 = Hmac<Sha256>;
 
-const COOKIE_NAME: &str = "llm_web_index_session";
+pub(crate) const COOKIE_NAME: &str = "llm_web_index_session";
 
 #[derive(Debug, thiserror::Error)]
 pub enum SessionError {
@@ -0,0 +1,384 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+/// Throttling of every login to this server.
+pub static LOGIN_THROTTLE: LazyLock<LoginThrottle> = LazyLock::new(|| LoginThrottle::new(ThrottlePolicy::default()));
+
+/// Clients whose failures are tracked at once. Past it, the clients that stopped failing are forgotten early.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// How failed logins slow down the next attempts, either from one client or overall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureLimits {
+    /// Wait after the first failure. It doubles with every further failure.
+    pub base_delay: Duration,
+    /// Longest wait between attempts, short of a lockout.
+    pub max_delay: Duration,
+    /// Failures in a row that lock logins out.
+    pub lockout_after: u32,
+    /// How long a lockout lasts.
+    pub lockout: Duration,
+}
+
+impl FailureLimits {
+    /// Wait after the `failures`-th failure in a row.
+    pub fn delay(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+        let factor = 2_u32.saturating_pow(failures.min(31) - 1);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Limits of the login endpoint. There's a single shared password, so the "account" is every login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottlePolicy {
+    /// Failures from one IP address
+    pub per_client: FailureLimits,
+    /// Failures from every IP address: slows down attacks spread over many addresses
+    pub account: FailureLimits,
+    /// Failures are forgotten after this long without one.
+    pub forget_after: Duration,
+}
+
+impl Default for ThrottlePolicy {
+    fn default() -> Self {
+        Self {
+            per_client: FailureLimits {
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(60),
+                lockout_after: 10,
+                lockout: Duration::from_secs(15 * 60),
+            },
+            account: FailureLimits {
+                base_delay: Duration::from_millis(250),
+                max_delay: Duration::from_secs(10),
+                lockout_after: 100,
+                lockout: Duration::from_secs(15 * 60),
+            },
+            forget_after: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+/// Failed logins in a row, of a client or overall.
+#[derive(Debug, Default, Clone, Copy)]
+struct Failures {
+    count: u32,
+    /// Attempts allowed but not known to have failed or succeeded yet: they count as failures until they are
+    pending: u32,
+    last: Option<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl Failures {
+    /// How long until the next attempt is allowed, if it isn't yet.
+    fn wait(&self, limits: &FailureLimits, now: Instant) -> Option<Duration> {
+        if let Some(until) = self.locked_until
+            && until > now
+        {
+            return Some(until - now);
+        }
+        let ready = self.last? + limits.delay(self.count.saturating_add(self.pending));
+        (ready > now).then(|| ready - now)
+    }
+
+    /// Counts an attempt that's allowed at `now` as a failure, until it's known.
+    fn reserve(&mut self, now: Instant) {
+        self.pending += 1;
+        self.last = Some(now);
+    }
+
+    fn release(&mut self) {
+        self.pending = self.pending.saturating_sub(1);
+    }
+
+    fn is_stale(&self, forget_after: Duration, now: Instant) -> bool {
+        let locked = self.locked_until.is_some_and(|until| until > now);
+        !locked && self.last.is_none_or(|last| now.duration_since(last) >= forget_after)
+    }
+
+    /// Counts a failure. Returns the length of the lockout it started, if it did.
+    fn fail(&mut self, limits: &FailureLimits, forget_after: Duration, now: Instant) -> Option<Duration> {
+        self.release();
+        if self.is_stale(forget_after, now) {
+            *self = Self::default();
+        }
+        self.count += 1;
+        self.last = Some(now);
+        if self.count >= limits.lockout_after {
+            // the count starts over once the lockout is over: a client gets another round of attempts
+            *self = Self {
+                count: 0,
+                pending: self.pending,
+                last: None,
+                locked_until: Some(now + limits.lockout),
+            };
+            return Some(limits.lockout);
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    clients: HashMap<IpAddr, Failures>,
+    account: Failures,
+}
+
+/// Why a login attempt isn't allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttled {
+    /// The client failed too recently, or is locked out
+    Client(Duration),
+    /// Logins failed too often overall, or are locked out
+    Account(Duration),
+}
+
+impl Throttled {
+    /// How long until a login can be attempted again.
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            Self::Client(wait) | Self::Account(wait) => *wait,
+        }
+    }
+}
+
+/// Lockouts that a failure started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Lockouts {
+    pub client: Option<Duration>,
+    pub account: Option<Duration>,
+}
+
+/// Tracks failed logins, per client IP address and overall, to slow down guessing the password: after every failure,
+/// the next attempt has to wait twice as long as after the previous one, and too many failures in a row lock logins
+/// out for a while. A successful login clears the client's failures.
+///
+/// An allowed attempt counts as a failure while it's in progress, so concurrent attempts can't all get past the wait:
+/// it's settled with `record_failure` or `record_success`, or dropped with `release` when it's neither.
+///
+/// The state is in memory: it's per API server, and starts over when the server restarts.
+#[derive(Debug)]
+pub struct LoginThrottle {
+    policy: ThrottlePolicy,
+    state: Mutex<ThrottleState>,
+}
+
+impl LoginThrottle {
+    pub fn new(policy: ThrottlePolicy) -> Self {
+        Self {
+            policy,
+            state: Mutex::new(ThrottleState::default()),
+        }
+    }
+
+    /// Whether a login attempt from the client (None when its address is unknown) is allowed at `now`. An allowed
+    /// attempt is reserved: the next ones wait as if it failed, until it's recorded or released.
+    pub fn check(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Throttled> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(wait) = client
+            .and_then(|ip| state.clients.get(&ip))
+            .and_then(|failures| failures.wait(&self.policy.per_client, now))
+        {
+            return Err(Throttled::Client(wait));
+        }
+        if let Some(wait) = state.account.wait(&self.policy.account, now) {
+            return Err(Throttled::Account(wait));
+        }
+
+        if let Some(ip) = client {
+            self.prune(&mut state, now);
+            state.clients.entry(ip).or_default().reserve(now);
+        }
+        state.account.reserve(now);
+        Ok(())
+    }
+
+    /// Counts a failed login from the client, settling its attempt. Returns the lockouts it started.
+    pub fn record_failure(&self, client: Option<IpAddr>, now: Instant) -> Lockouts {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let forget_after = self.policy.forget_after;
+        self.prune(&mut state, now);
+        let client = client.and_then(|ip| {
+            let failures = state.clients.entry(ip).or_default();
+            failures.fail(&self.policy.per_client, forget_after, now)
+        });
+        let account = state.account.fail(&self.policy.account, forget_after, now);
+        Lockouts { client, account }
+    }
+
+    /// Number of failures in a row of the client, e.g. for logging.
+    pub fn failures(&self, client: IpAddr) -> u32 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.clients.get(&client).map_or(0, |failures| failures.count)
+    }
+
+    /// Forgets the client's failures after it logged in. The overall failures are left to expire: the other clients
+    /// may still be guessing.
+    pub fn record_success(&self, client: Option<IpAddr>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(ip) = client {
+            state.clients.remove(&ip);
+        }
+        state.account.release();
+    }
+
+    /// Drops the client's attempt without counting it, e.g. when it's asked for a second factor, or the server failed.
+    pub fn release(&self, client: Option<IpAddr>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(failures) = client.and_then(|ip| state.clients.get_mut(&ip)) {
+            failures.release();
+        }
+        state.account.release();
+    }
+
+    fn prune(&self, state: &mut ThrottleState, now: Instant) {
+        if state.clients.len() >= MAX_TRACKED_CLIENTS {
+            let forget_after = self.policy.forget_after;
+            state
+                .clients
+                .retain(|_, failures| !failures.is_stale(forget_after, now));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ThrottlePolicy {
+        ThrottlePolicy {
+            per_client: FailureLimits {
+                base_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(8),
+                lockout_after: 5,
+                lockout: Duration::from_secs(60),
+            },
+            account: FailureLimits {
+                base_delay: Duration::from_millis(100),
+                max_delay: Duration::from_secs(1),
+                lockout_after: 8,
+                lockout: Duration::from_secs(120),
+            },
+            forget_after: Duration::from_secs(300),
+        }
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let limits = policy().per_client;
+        assert_eq!(limits.delay(0), Duration::ZERO);
+        assert_eq!(limits.delay(1), Duration::from_secs(1));
+        assert_eq!(limits.delay(2), Duration::from_secs(2));
+        assert_eq!(limits.delay(3), Duration::from_secs(4));
+        assert_eq!(limits.delay(10), Duration::from_secs(8));
+        assert_eq!(limits.delay(u32::MAX), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_client_delay_and_lockout() {
+        let throttle = LoginThrottle::new(policy());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "198.51.100.1".parse().unwrap();
+        let start = Instant::now();
+        assert!(throttle.check(Some(ip), start).is_ok());
+
+        assert_eq!(throttle.record_failure(Some(ip), start), Lockouts::default());
+        assert_eq!(
+            throttle.check(Some(ip), start),
+            Err(Throttled::Client(Duration::from_secs(1)))
+        );
+        // other clients only wait for the (shorter) overall delay
+        assert_eq!(
+            throttle.check(Some(other), start),
+            Err(Throttled::Account(Duration::from_millis(100)))
+        );
+        assert!(throttle.check(Some(other), start + Duration::from_millis(100)).is_ok());
+        assert!(throttle.check(Some(ip), start + Duration::from_secs(1)).is_ok());
+
+        let mut now = start;
+        for _ in 1..4 {
+            now += Duration::from_secs(10);
+            assert_eq!(throttle.record_failure(Some(ip), now).client, None);
+        }
+        assert_eq!(throttle.failures(ip), 4);
+        now += Duration::from_secs(10);
+        assert_eq!(
+            throttle.record_failure(Some(ip), now).client,
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            throttle.check(Some(ip), now + Duration::from_secs(30)),
+            Err(Throttled::Client(Duration::from_secs(30)))
+        );
+        assert!(throttle.check(Some(ip), now + Duration::from_secs(60)).is_ok());
+    }
+
+    #[test]
+    fn test_attempt_in_progress_counts_as_failure() {
+        let throttle = LoginThrottle::new(policy());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        assert!(throttle.check(Some(ip), now).is_ok());
+        assert_eq!(
+            throttle.check(Some(ip), now),
+            Err(Throttled::Client(Duration::from_secs(1)))
+        );
+
+        // a release drops the attempt without counting it
+        throttle.release(Some(ip));
+        assert_eq!(throttle.failures(ip), 0);
+        assert!(throttle.check(Some(ip), now).is_ok());
+        throttle.record_success(Some(ip));
+        assert!(throttle.check(Some(ip), now).is_ok());
+    }
+
+    #[test]
+    fn test_success_clears_client_failures() {
+        let throttle = LoginThrottle::new(policy());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        throttle.record_failure(Some(ip), now);
+        throttle.record_failure(Some(ip), now);
+        throttle.record_success(Some(ip));
+        assert_eq!(throttle.failures(ip), 0);
+        assert!(throttle.check(Some(ip), now + Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_failures_are_forgotten() {
+        let throttle = LoginThrottle::new(policy());
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        for _ in 0..3 {
+            throttle.record_failure(Some(ip), now);
+        }
+        let later = now + Duration::from_secs(300);
+        throttle.record_failure(Some(ip), later);
+        assert_eq!(throttle.failures(ip), 1);
+    }
+
+    #[test]
+    fn test_account_lockout_spans_clients() {
+        let throttle = LoginThrottle::new(policy());
+        let now = Instant::now();
+        let mut lockouts = Lockouts::default();
+        for i in 0..8u8 {
+            lockouts = throttle.record_failure(Some(IpAddr::from([192, 0, 2, i])), now);
+        }
+        assert_eq!(lockouts.account, Some(Duration::from_secs(120)));
+        assert_eq!(
+            throttle.check(Some("203.0.113.7".parse().unwrap()), now),
+            Err(Throttled::Account(Duration::from_secs(120)))
+        );
+        // without an address, only the overall limits apply
+        assert_eq!(
+            throttle.check(None, now + Duration::from_secs(60)),
+            Err(Throttled::Account(Duration::from_secs(60)))
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Time-based one-time passwords (RFC 6238), as authenticator apps compute them: HMAC-SHA1, 6 digits, 30-second steps.
+
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Seconds a code is valid for.
+pub const PERIOD_SECS: i64 = 30;
+
+/// Digits of a code.
+pub const DIGITS: usize = 6;
+
+/// Codes of this many steps before or after the current one are accepted too, for clocks that drift.
+const SKEW_STEPS: i64 = 1;
+
+/// Bytes of a secret: the length of an HMAC-SHA1, as RFC 4226 recommends.
+const SECRET_BYTES: usize = 20;
+
+/// Recovery codes handed out at once.
+pub const RECOVERY_CODES: usize = 10;
+
+/// Characters of recovery codes: lowercase letters and digits, without the ones that are easy to mix up (0/o, 1/l).
+const RECOVERY_ALPHABET: &[u8; 32] = b"abcdefghijkmnpqrstuvwxyz23456789";
+
+/// Name of this service in authenticator apps.
+pub const ISSUER: &str = "llm-web-index";
+
+/// A new random secret, base32-encoded (how authenticator apps take it).
+pub fn generate_secret() -> String {
+    let bytes: [u8; SECRET_BYTES] = rand::random();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// The secret's bytes, if it's base32 (case and spaces don't matter).
+pub fn decode_secret(secret: &str) -> Option<Vec<u8>> {
+    let normalized: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    BASE32_NOPAD.decode(normalized.trim_end_matches('=').as_bytes()).ok()
+}
+
+/// URI that enrolls the secret in an authenticator app, e.g. as a QR code.
+pub fn otpauth_uri(secret: &str, account: &str) -> String {
+    let encode = |value: &str| url::form_urlencoded::byte_serialize(value.as_bytes()).collect::<String>();
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = encode(ISSUER),
+        account = encode(account),
+        secret = secret,
+        digits = DIGITS,
+        period = PERIOD_SECS,
+    )
+}
+
+/// The time step of a Unix timestamp.
+pub fn step_at(unix_secs: i64) -> i64 {
+    unix_secs.div_euclid(PERIOD_SECS)
+}
+
+/// The code of a time step (the HOTP of RFC 4226, with the step as its counter).
+pub fn code_at_step(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let truncated =
+        u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+    format!("{:0width$}", truncated % 10_u32.pow(DIGITS as u32), width = DIGITS)
+}
+
+/// Whether the input looks like a TOTP code rather than a recovery code: 6 digits, maybe with spaces.
+pub fn is_totp_code(code: &str) -> bool {
+    let digits: Vec<char> = code.chars().filter(|c| !c.is_whitespace()).collect();
+    digits.len() == DIGITS && digits.iter().all(|c| c.is_ascii_digit())
+}
+
+/// The time step the code is for, if it's the code of the current step (or one next to it) and it's after the last
+/// step whose code was used: a code can't be used twice.
+pub fn verify(secret: &[u8], code: &str, now_step: i64, last_used_step: Option<i64>) -> Option<i64> {
+    if !is_totp_code(code) {
+        return None;
+    }
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    (now_step - SKEW_STEPS..=now_step + SKEW_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| constant_time_eq(code_at_step(secret, *step).as_bytes(), code.as_bytes()))
+}
+
+/// New recovery codes, e.g. `k3tq9-xw2mf`: 50 random bits each.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES)
+        .map(|_| {
+            let bytes: [u8; 10] = rand::random();
+            let chars: String = bytes
+                .iter()
+                .map(|byte| RECOVERY_ALPHABET[(byte & 0x1f) as usize] as char)
+                .collect();
+            format!("{}-{}", &chars[..5], &chars[5..])
+        })
+        .collect()
+}
+
+/// How a recovery code is stored: the hex SHA-256 of the code, ignoring case, dashes and spaces.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    Sha256::digest(normalized.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The SHA-1 secret of RFC 6238's test vectors.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        // the RFC's 8-digit codes, cut to their last 6 digits
+        for (time, code) in [
+            (59, "287082"),
+            (1111111109, "081804"),
+            (1111111111, "050471"),
+            (1234567890, "005924"),
+            (2000000000, "279037"),
+        ] {
+            assert_eq!(code_at_step(RFC_SECRET, step_at(time)), code, "at {}", time);
+        }
+    }
+
+    #[test]
+    fn test_verify_window_and_replay() {
+        let step = step_at(1234567890);
+        let code = code_at_step(RFC_SECRET, step);
+        assert_eq!(verify(RFC_SECRET, &code, step, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, &code, step + 1, None), Some(step));
+        assert_eq!(verify(RFC_SECRET, &code, step + 2, None), None);
+        assert_eq!(verify(RFC_SECRET, "005 924", step, None), Some(step));
+        // used already
+        assert_eq!(verify(RFC_SECRET, &code, step, Some(step)), None);
+        assert_eq!(verify(RFC_SECRET, "123456", step, None), None);
+        assert_eq!(verify(RFC_SECRET, "k3tq9-xw2mf", step, None), None);
+    }
+
+    #[test]
+    fn test_secret_round_trip() {
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(decode_secret(&secret).unwrap().len(), SECRET_BYTES);
+        assert_eq!(decode_secret(&secret.to_lowercase()), decode_secret(&secret));
+        assert!(decode_secret("not base32!").is_none());
+    }
+
+    #[test]
+    fn test_otpauth_uri() {
+        assert_eq!(
+            otpauth_uri("JBSWY3DPEHPK3PXP", "admin@llms.example.com"),
+            "otpauth://totp/llm-web-index:admin%40llms.example.com?secret=JBSWY3DPEHPK3PXP&issuer=llm-web-index&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+
+    #[test]
+    fn test_recovery_codes() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODES);
+        for code in &codes {
+            assert_eq!(code.len(), 11);
+            assert!(!is_totp_code(code));
+        }
+        assert_eq!(hash_recovery_code("K3TQ9-XW2MF"), hash_recovery_code(" k3tq9xw2mf "));
+        assert_ne!(hash_recovery_code(&codes[0]), hash_recovery_code(&codes[1]));
+    }
+}
@@ -0,0 +1,429 @@
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use core_ltx::AuthConfig;
+use core_ltx::db::{DbPool, PoolError};
+use data_model_ltx::models::{TotpRecoveryCode, TotpSecret};
+use data_model_ltx::schema::{totp, totp_recovery_codes};
+use data_model_ltx::secrets::{Keyring, SecretsError};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use super::totp::{
+    decode_secret, generate_recovery_codes, generate_secret, hash_recovery_code, is_totp_code, otpauth_uri, step_at,
+    verify,
+};
+
+/// Account name of the password in authenticator apps.
+const ACCOUNT: &str = "admin";
+
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFactorError {
+    #[error("Authentication is not enabled")]
+    AuthDisabled,
+
+    #[error("Two-factor authentication is already enabled: disable it first")]
+    AlreadyEnabled,
+
+    #[error("Two-factor authentication is not enabled")]
+    NotEnrolled,
+
+    #[error("Invalid code")]
+    InvalidCode,
+
+    #[error("Secrets error: {0}")]
+    Secrets(#[from] SecretsError),
+
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl From<PoolError> for TwoFactorError {
+    fn from(error: PoolError) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+impl From<diesel::result::Error> for TwoFactorError {
+    fn from(error: diesel::result::Error) -> Self {
+        Self::Database(error.to_string())
+    }
+}
+
+impl IntoResponse for TwoFactorError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            TwoFactorError::AuthDisabled | TwoFactorError::InvalidCode => StatusCode::BAD_REQUEST,
+            TwoFactorError::AlreadyEnabled => StatusCode::CONFLICT,
+            TwoFactorError::NotEnrolled => StatusCode::NOT_FOUND,
+            TwoFactorError::Secrets(SecretsError::MissingKey) => StatusCode::SERVICE_UNAVAILABLE,
+            TwoFactorError::Secrets(_) | TwoFactorError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match &self {
+            TwoFactorError::Database(_) => "Two-factor authentication error".to_string(),
+            error => error.to_string(),
+        };
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CodeRequest {
+    /// A code of the authenticator app, or a recovery code
+    #[serde(default)]
+    code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpStatusResponse {
+    /// Logins need a code
+    enabled: bool,
+    /// Enrolled, but not confirmed with a code yet
+    pending: bool,
+    /// Recovery codes that weren't used yet
+    recovery_codes_left: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnrollResponse {
+    /// The base32 secret, for authenticator apps that can't scan the URI
+    secret: String,
+    otpauth_uri: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// Only shown this once: they're stored hashed
+    recovery_codes: Vec<String>,
+}
+
+/// How a login's second factor was verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecondFactor {
+    Totp,
+    /// A recovery code, which can't be used again. `left` are still unused.
+    RecoveryCode {
+        left: i64,
+    },
+}
+
+/// The login's TOTP secret, confirmed or not.
+async fn load(conn: &mut AsyncPgConnection) -> Result<Option<TotpSecret>, diesel::result::Error> {
+    totp::table
+        .select(TotpSecret::as_select())
+        .first::<TotpSecret>(conn)
+        .await
+        .optional()
+}
+
+/// The login's TOTP secret, when logins need a code.
+pub async fn confirmed_second_factor(pool: &DbPool) -> Result<Option<TotpSecret>, TwoFactorError> {
+    let mut conn = pool.get().await?;
+    Ok(load(&mut conn).await?.filter(TotpSecret::is_confirmed))
+}
+
+/// Checks a code of the authenticator app, or a recovery code, and uses it up: neither works twice.
+/// None when it's wrong (or used already).
+pub async fn verify_second_factor(
+    pool: &DbPool,
+    second_factor: &TotpSecret,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<SecondFactor>, TwoFactorError> {
+    let mut conn = pool.get().await?;
+    if is_totp_code(code) {
+        let Some(step) = verify_totp(second_factor, code, now)? else {
+            return Ok(None);
+        };
+        // unless a concurrent login used a code of this step already
+        let used = diesel::update(totp::table.filter(totp::last_used_step.is_null().or(totp::last_used_step.lt(step))))
+            .set(totp::last_used_step.eq(step))
+            .execute(&mut conn)
+            .await?;
+        return Ok((used == 1).then_some(SecondFactor::Totp));
+    }
+
+    let used = diesel::update(
+        totp_recovery_codes::table
+            .find(hash_recovery_code(code))
+            .filter(totp_recovery_codes::used_at.is_null()),
+    )
+    .set(totp_recovery_codes::used_at.eq(now))
+    .execute(&mut conn)
+    .await?;
+    if used == 0 {
+        return Ok(None);
+    }
+    let left = recovery_codes_left(&mut conn).await?;
+    Ok(Some(SecondFactor::RecoveryCode { left }))
+}
+
+/// The time step of the code, if it's a valid code of the secret that wasn't used yet.
+fn verify_totp(second_factor: &TotpSecret, code: &str, now: DateTime<Utc>) -> Result<Option<i64>, TwoFactorError> {
+    let keyring = Keyring::from_env()?;
+    let secret = second_factor.decrypt_secret(&keyring)?;
+    let secret = decode_secret(&secret).ok_or(SecretsError::Malformed)?;
+    Ok(verify(
+        &secret,
+        code,
+        step_at(now.timestamp()),
+        second_factor.last_used_step,
+    ))
+}
+
+async fn recovery_codes_left(conn: &mut AsyncPgConnection) -> Result<i64, diesel::result::Error> {
+    totp_recovery_codes::table
+        .filter(totp_recovery_codes::used_at.is_null())
+        .count()
+        .get_result::<i64>(conn)
+        .await
+}
+
+/// Replaces the recovery codes with new ones, returned in plain text.
+async fn replace_recovery_codes(
+    conn: &mut AsyncPgConnection,
+    now: DateTime<Utc>,
+) -> Result<Vec<String>, diesel::result::Error> {
+    let codes = generate_recovery_codes();
+    let stored: Vec<TotpRecoveryCode> = codes
+        .iter()
+        .map(|code| TotpRecoveryCode {
+            code_hash: hash_recovery_code(code),
+            used_at: None,
+            created_at: now,
+        })
+        .collect();
+    diesel::delete(totp_recovery_codes::table).execute(conn).await?;
+    diesel::insert_into(totp_recovery_codes::table)
+        .values(&stored)
+        .execute(conn)
+        .await?;
+    Ok(codes)
+}
+
+fn require_auth_enabled(auth_config: &Option<AuthConfig>) -> Result<(), TwoFactorError> {
+    auth_config.as_ref().map(|_| ()).ok_or(TwoFactorError::AuthDisabled)
+}
+
+/// GET /api/auth/totp
+/// Whether logins need a code, and how many recovery codes are left
+#[utoipa::path(
+    get,
+    path = "/api/auth/totp",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Whether logins need a code", body = TotpStatusResponse),
+        (status = 400, description = "Authentication is not enabled"),
+    )
+)]
+pub async fn get_totp(
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+) -> Result<impl IntoResponse, TwoFactorError> {
+    require_auth_enabled(&auth_config)?;
+    let mut conn = pool.get().await?;
+    let second_factor = load(&mut conn).await?;
+    let enabled = second_factor.as_ref().is_some_and(TotpSecret::is_confirmed);
+    Ok(Json(TotpStatusResponse {
+        enabled,
+        pending: second_factor.is_some() && !enabled,
+        recovery_codes_left: if enabled {
+            recovery_codes_left(&mut conn).await?
+        } else {
+            0
+        },
+    }))
+}
+
+/// POST /api/auth/totp/enroll
+/// Starts (or restarts) enrolling an authenticator app. Logins don't need a code until it's confirmed.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp/enroll",
+    tag = "auth",
+    responses(
+        (status = 201, description = "The secret to add to an authenticator app", body = EnrollResponse),
+        (status = 409, description = "Two-factor authentication is already enabled"),
+        (status = 503, description = "The server has no (valid) SECRETS_KEYS"),
+    )
+)]
+pub async fn post_totp_enroll(
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+) -> Result<impl IntoResponse, TwoFactorError> {
+    require_auth_enabled(&auth_config)?;
+    let keyring = Keyring::from_env()?;
+    let mut conn = pool.get().await?;
+    if load(&mut conn)
+        .await?
+        .is_some_and(|second_factor| second_factor.is_confirmed())
+    {
+        return Err(TwoFactorError::AlreadyEnabled);
+    }
+
+    let secret = generate_secret();
+    let pending = TotpSecret::new(&secret, &keyring, Utc::now());
+    // a pending enrollment is replaced, but never a confirmed one
+    diesel::insert_into(totp::table)
+        .values(&pending)
+        .on_conflict(totp::id)
+        .do_update()
+        .set((
+            totp::secret.eq(&pending.secret),
+            totp::last_used_step.eq(None::<i64>),
+            totp::created_at.eq(pending.created_at),
+        ))
+        .filter(totp::confirmed_at.is_null())
+        .execute(&mut conn)
+        .await?;
+
+    info!(target: "audit", "Two-factor authentication: enrollment started");
+    Ok((
+        StatusCode::CREATED,
+        Json(EnrollResponse {
+            otpauth_uri: otpauth_uri(&secret, ACCOUNT),
+            secret,
+        }),
+    ))
+}
+
+/// POST /api/auth/totp/confirm
+/// Confirms the enrollment with a code of the authenticator app: from then on, logins need a code.
+/// Returns the recovery codes.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp/confirm",
+    tag = "auth",
+    request_body = CodeRequest,
+    responses(
+        (status = 200, description = "Enabled: the recovery codes, shown only this once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code"),
+        (status = 404, description = "No enrollment was started"),
+        (status = 409, description = "Two-factor authentication is already enabled"),
+    )
+)]
+pub async fn post_totp_confirm(
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<impl IntoResponse, TwoFactorError> {
+    require_auth_enabled(&auth_config)?;
+    let mut conn = pool.get().await?;
+    let pending = load(&mut conn).await?.ok_or(TwoFactorError::NotEnrolled)?;
+    if pending.is_confirmed() {
+        return Err(TwoFactorError::AlreadyEnabled);
+    }
+    let now = Utc::now();
+    let step = verify_totp(&pending, &request.code, now)?.ok_or(TwoFactorError::InvalidCode)?;
+
+    let recovery_codes = conn
+        .transaction(|conn| {
+            async move {
+                let confirmed = diesel::update(totp::table.filter(totp::confirmed_at.is_null()))
+                    .set((totp::confirmed_at.eq(now), totp::last_used_step.eq(step)))
+                    .execute(conn)
+                    .await?;
+                if confirmed == 0 {
+                    return Err(TwoFactorError::AlreadyEnabled);
+                }
+                Ok(replace_recovery_codes(conn, now).await?)
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+    warn!(target: "audit", "Two-factor authentication enabled: logins now need a code");
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// POST /api/auth/totp/recovery_codes
+/// Replaces the recovery codes, e.g. once most of them were used. Needs a code.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp/recovery_codes",
+    tag = "auth",
+    request_body = CodeRequest,
+    responses(
+        (status = 200, description = "The new recovery codes, shown only this once", body = RecoveryCodesResponse),
+        (status = 400, description = "Invalid code"),
+        (status = 404, description = "Two-factor authentication is not enabled"),
+    )
+)]
+pub async fn post_totp_recovery_codes(
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<impl IntoResponse, TwoFactorError> {
+    require_auth_enabled(&auth_config)?;
+    let second_factor = confirmed_second_factor(&pool)
+        .await?
+        .ok_or(TwoFactorError::NotEnrolled)?;
+    let now = Utc::now();
+    if verify_second_factor(&pool, &second_factor, &request.code, now)
+        .await?
+        .is_none()
+    {
+        return Err(TwoFactorError::InvalidCode);
+    }
+
+    let mut conn = pool.get().await?;
+    let recovery_codes = conn
+        .transaction(|conn| async move { replace_recovery_codes(conn, now).await }.scope_boxed())
+        .await?;
+
+    warn!(target: "audit", "Two-factor authentication: recovery codes replaced");
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// POST /api/auth/totp/disable
+/// Stops requiring a code at login, forgetting the secret and the recovery codes. Needs a code, unless the enrollment
+/// wasn't confirmed.
+#[utoipa::path(
+    post,
+    path = "/api/auth/totp/disable",
+    tag = "auth",
+    request_body = CodeRequest,
+    responses(
+        (status = 204, description = "Disabled: logins only need the password"),
+        (status = 400, description = "Invalid code"),
+        (status = 404, description = "Two-factor authentication is not enabled"),
+    )
+)]
+pub async fn post_totp_disable(
+    State(pool): State<DbPool>,
+    Extension(auth_config): Extension<Arc<Option<AuthConfig>>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<impl IntoResponse, TwoFactorError> {
+    require_auth_enabled(&auth_config)?;
+    let mut conn = pool.get().await?;
+    let second_factor = load(&mut conn).await?.ok_or(TwoFactorError::NotEnrolled)?;
+    if second_factor.is_confirmed()
+        && verify_second_factor(&pool, &second_factor, &request.code, Utc::now())
+            .await?
+            .is_none()
+    {
+        return Err(TwoFactorError::InvalidCode);
+    }
+
+    conn.transaction(|conn| {
+        async move {
+            diesel::delete(totp_recovery_codes::table).execute(conn).await?;
+            diesel::delete(totp::table).execute(conn).await?;
+            Ok::<_, diesel::result::Error>(())
+        }
+        .scope_boxed()
+    })
+    .await?;
+
+    warn!(target: "audit", "Two-factor authentication disabled: logins only need the password");
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,421 @@
+//! Backup & restore of the job_state and llms_txt tables.
+//!
+//! The records of the archive tier (see `data_model_ltx::archive`) are backed up as llms_txt rows too.
+//!
+//! A backup is a JSON Lines file:
+//!   - a header line with the format version and when the backup was taken
+//!   - one line per row, tagged with its table (compressed HTML is base64-encoded)
+//!   - a footer line with the row counts and the SHA-256 of every line before it
+//!
+//! The footer lets a restore detect truncated or corrupted backups before anything is committed.
+
+use std::io::{BufRead, Write};
+
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use data_model_ltx::changes::notify_llms_txt_changed;
+use data_model_ltx::models::{
+    ArchivedLlmsTxt, JobState, LlmsTxt, LlmsTxtSource, ResultData, ResultStatus, llms_txt_preview,
+};
+use data_model_ltx::schema::{job_state, llms_txt, llms_txt_archive};
+use data_model_ltx::search::index_llms_txt;
+
+/// Version of the backup file format. Bump when the format changes incompatibly.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Number of rows read from (or written to) the database at a time.
+const BATCH_SIZE: i64 = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BackupError {
+    #[error("Database error: {0}")]
+    Db(#[from] diesel::result::Error),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid backup record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Corrupt backup: {0}")]
+    Corrupt(String),
+}
+
+/// An llms_txt row, with the compressed HTML as base64 so that it's compact in JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LlmsTxtRow {
+    job_id: Uuid,
+    url: String,
+    result_data: String,
+    result_status: ResultStatus,
+    created_at: DateTime<Utc>,
+    html_compress: String,
+    html_checksum: String,
+    /// Missing in backups made before imported llms.txt files existed.
+    #[serde(default)]
+    source: LlmsTxtSource,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    canonical_url: Option<String>,
+    #[serde(default)]
+    favicon_url: Option<String>,
+    #[serde(default)]
+    prompt_version: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    detail: Option<String>,
+}
+
+impl From<LlmsTxt> for LlmsTxtRow {
+    fn from(row: LlmsTxt) -> Self {
+        Self {
+            job_id: row.job_id,
+            url: row.url,
+            result_data: row.result_data.into_text(),
+            result_status: row.result_status,
+            created_at: row.created_at,
+            html_compress: BASE64.encode(&row.html_compress),
+            html_checksum: row.html_checksum,
+            source: row.source,
+            title: row.title,
+            description: row.description,
+            canonical_url: row.canonical_url,
+            favicon_url: row.favicon_url,
+            prompt_version: row.prompt_version,
+            model: row.model,
+            detail: row.detail,
+        }
+    }
+}
+
+impl TryFrom<LlmsTxtRow> for LlmsTxt {
+    type Error = BackupError;
+
+    fn try_from(row: LlmsTxtRow) -> Result<Self, Self::Error> {
+        let html_compress = BASE64
+            .decode(&row.html_compress)
+            .map_err(|e| BackupError::Corrupt(format!("html_compress of job {} is not base64: {}", row.job_id, e)))?;
+        // not backed up: it's the start of the llms.txt
+        let (preview, preview_truncated) = match row.result_status {
+            ResultStatus::Ok => {
+                let (preview, truncated) = llms_txt_preview(&row.result_data);
+                (Some(preview), truncated)
+            }
+            ResultStatus::Error => (None, false),
+        };
+        Ok(LlmsTxt {
+            job_id: row.job_id,
+            url: row.url,
+            result_data: ResultData::new(row.result_data),
+            result_status: row.result_status,
+            created_at: row.created_at,
+            html_compress,
+            html_checksum: row.html_checksum,
+            source: row.source,
+            title: row.title,
+            description: row.description,
+            canonical_url: row.canonical_url,
+            favicon_url: row.favicon_url,
+            prompt_version: row.prompt_version,
+            model: row.model,
+            detail: row.detail,
+            preview,
+            preview_truncated,
+        })
+    }
+}
+
+/// One line of a backup file.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum Record {
+    Header {
+        version: u32,
+        created_at: DateTime<Utc>,
+    },
+    JobState(JobState),
+    LlmsTxt(LlmsTxtRow),
+    Footer {
+        job_state_rows: u64,
+        llms_txt_rows: u64,
+        sha256: String,
+    },
+}
+
+/// Number of rows backed up, or restored, per table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RowCounts {
+    pub job_state: u64,
+    pub llms_txt: u64,
+}
+
+/// Outcome of a restore.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RestoreSummary {
+    /// Rows in the backup.
+    pub backed_up: RowCounts,
+    /// Rows that were inserted. Rows already in the database (by job ID) are left as-is.
+    pub inserted: RowCounts,
+}
+
+/// Writes lines while hashing them, so that the footer can carry the checksum of the whole file.
+struct HashingWriter<W> {
+    out: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn write_record(&mut self, record: &Record) -> Result<(), BackupError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.hasher.update(&line);
+        self.out.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Writes a consistent snapshot of the job_state and llms_txt tables to `out`.
+///
+/// All rows are read in one read-only, repeatable-read transaction, so jobs created while the
+/// backup runs are either entirely in it or entirely absent. Rows are written in batches as they're read.
+pub async fn backup<W: Write + Send>(conn: &mut AsyncPgConnection, out: W) -> Result<RowCounts, BackupError> {
+    conn.build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run(|conn| {
+            async move {
+                let mut writer = HashingWriter {
+                    out,
+                    hasher: Sha256::new(),
+                };
+                writer.write_record(&Record::Header {
+                    version: FORMAT_VERSION,
+                    created_at: Utc::now(),
+                })?;
+
+                let mut counts = RowCounts::default();
+                let mut after: Option<Uuid> = None;
+                loop {
+                    let mut query = job_state::table
+                        .select(JobState::as_select())
+                        .order(job_state::job_id.asc())
+                        .limit(BATCH_SIZE)
+                        .into_boxed();
+                    if let Some(after) = after {
+                        query = query.filter(job_state::job_id.gt(after));
+                    }
+                    let rows: Vec<JobState> = query.load(conn).await?;
+                    let Some(last) = rows.last() else { break };
+                    after = Some(last.job_id);
+                    for row in rows {
+                        writer.write_record(&Record::JobState(row))?;
+                        counts.job_state += 1;
+                    }
+                }
+
+                let mut after: Option<Uuid> = None;
+                loop {
+                    let mut query = llms_txt::table
+                        .select(LlmsTxt::as_select())
+                        .order(llms_txt::job_id.asc())
+                        .limit(BATCH_SIZE)
+                        .into_boxed();
+                    if let Some(after) = after {
+                        query = query.filter(llms_txt::job_id.gt(after));
+                    }
+                    let rows: Vec<LlmsTxt> = query.load(conn).await?;
+                    let Some(last) = rows.last() else { break };
+                    after = Some(last.job_id);
+                    for row in rows {
+                        writer.write_record(&Record::LlmsTxt(row.into()))?;
+                        counts.llms_txt += 1;
+                    }
+                }
+
+                // archived records are backed up like the others: restored, they're archived again
+                let mut after: Option<Uuid> = None;
+                loop {
+                    let mut query = llms_txt_archive::table
+                        .select(ArchivedLlmsTxt::as_select())
+                        .order(llms_txt_archive::job_id.asc())
+                        .limit(BATCH_SIZE)
+                        .into_boxed();
+                    if let Some(after) = after {
+                        query = query.filter(llms_txt_archive::job_id.gt(after));
+                    }
+                    let rows: Vec<ArchivedLlmsTxt> = query.load(conn).await?;
+                    let Some(last) = rows.last() else { break };
+                    after = Some(last.job_id);
+                    for row in rows {
+                        writer.write_record(&Record::LlmsTxt(LlmsTxt::from(row).into()))?;
+                        counts.llms_txt += 1;
+                    }
+                }
+
+                let sha256 = format!("{:x}", writer.hasher.clone().finalize());
+                writer.write_record(&Record::Footer {
+                    job_state_rows: counts.job_state,
+                    llms_txt_rows: counts.llms_txt,
+                    sha256,
+                })?;
+                writer.out.flush()?;
+                Ok(counts)
+            }
+            .scope_boxed()
+        })
+        .await
+}
+
+/// Restores a backup written by `backup`. Rows whose job ID already exists are skipped.
+///
+/// Everything happens in one transaction: if the backup is truncated, corrupt (its checksum doesn't
+/// match), or from an unknown format version, nothing is restored.
+pub async fn restore<R: BufRead + Send>(conn: &mut AsyncPgConnection, input: R) -> Result<RestoreSummary, BackupError> {
+    conn.transaction(|conn| {
+        async move {
+            let mut hasher = Sha256::new();
+            let mut summary = RestoreSummary::default();
+            let mut job_states: Vec<JobState> = Vec::new();
+            let mut llms_txts: Vec<LlmsTxt> = Vec::new();
+            let mut header_seen = false;
+
+            for (index, line) in input.lines().enumerate() {
+                let line = line?;
+                let record: Record = serde_json::from_str(&line)?;
+                match record {
+                    Record::Header { version, .. } => {
+                        if index != 0 {
+                            return Err(BackupError::Corrupt(format!("unexpected header on line {}", index + 1)));
+                        }
+                        if version != FORMAT_VERSION {
+                            return Err(BackupError::Corrupt(format!(
+                                "unsupported format version {} (expected {})",
+                                version, FORMAT_VERSION
+                            )));
+                        }
+                        header_seen = true;
+                    }
+                    _ if !header_seen => {
+                        return Err(BackupError::Corrupt("missing header".to_string()));
+                    }
+                    Record::JobState(row) => {
+                        summary.backed_up.job_state += 1;
+                        job_states.push(row);
+                        if job_states.len() as i64 >= BATCH_SIZE {
+                            summary.inserted.job_state += insert_job_states(conn, &mut job_states).await?;
+                        }
+                    }
+                    Record::LlmsTxt(row) => {
+                        summary.backed_up.llms_txt += 1;
+                        llms_txts.push(row.try_into()?);
+                        if llms_txts.len() as i64 >= BATCH_SIZE {
+                            summary.inserted.llms_txt += insert_llms_txts(conn, &mut llms_txts).await?;
+                        }
+                    }
+                    Record::Footer {
+                        job_state_rows,
+                        llms_txt_rows,
+                        sha256,
+                    } => {
+                        let actual = format!("{:x}", hasher.finalize());
+                        if actual != sha256 {
+                            return Err(BackupError::Corrupt(format!(
+                                "checksum mismatch: footer has {} but the contents hash to {}",
+                                sha256, actual
+                            )));
+                        }
+                        if job_state_rows != summary.backed_up.job_state || llms_txt_rows != summary.backed_up.llms_txt
+                        {
+                            return Err(BackupError::Corrupt("row counts don't match the footer".to_string()));
+                        }
+                        summary.inserted.job_state += insert_job_states(conn, &mut job_states).await?;
+                        summary.inserted.llms_txt += insert_llms_txts(conn, &mut llms_txts).await?;
+                        notify_llms_txt_changed(conn, None).await?;
+                        return Ok(summary);
+                    }
+                }
+                hasher.update(line.as_bytes());
+                hasher.update(b"\n");
+            }
+            Err(BackupError::Corrupt(
+                "missing footer: the backup is truncated".to_string(),
+            ))
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+async fn insert_job_states(conn: &mut AsyncPgConnection, rows: &mut Vec<JobState>) -> Result<u64, BackupError> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let inserted = diesel::insert_into(job_state::table)
+        .values(&*rows)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+    rows.clear();
+    Ok(inserted as u64)
+}
+
+async fn insert_llms_txts(conn: &mut AsyncPgConnection, rows: &mut Vec<LlmsTxt>) -> Result<u64, BackupError> {
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let inserted = diesel::insert_into(llms_txt::table)
+        .values(&*rows)
+        .on_conflict_do_nothing()
+        .execute(conn)
+        .await?;
+    // the records that were already there are indexed again, from the same llms.txt
+    index_llms_txt(conn, rows).await?;
+    rows.clear();
+    Ok(inserted as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_model_ltx::models::LlmsTxtResult;
+
+    fn sample(job_id: Uuid) -> LlmsTxt {
+        LlmsTxt::from_result(
+            job_id,
+            "https://example.com".to_string(),
+            LlmsTxtResult::Ok {
+                llms_txt: "# Example".to_string(),
+            },
+            vec![0, 1, 2, 255],
+            "abc".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_llms_txt_row_round_trip() {
+        let job_id = Uuid::new_v4();
+        let json = serde_json::to_string(&Record::LlmsTxt(sample(job_id).into())).unwrap();
+        assert!(json.starts_with(r#"{"record":"llms_txt","#));
+        assert!(json.contains(r#""html_compress":"AAEC/w==""#));
+
+        let Record::LlmsTxt(parsed) = serde_json::from_str(&json).unwrap() else {
+            panic!("expected an llms_txt record");
+        };
+        assert_eq!(LlmsTxt::try_from(parsed).unwrap(), sample(job_id));
+    }
+
+    #[test]
+    fn test_invalid_base64_is_corrupt() {
+        let mut row = LlmsTxtRow::from(sample(Uuid::new_v4()));
+        row.html_compress = "not base64!".to_string();
+        assert!(matches!(LlmsTxt::try_from(row), Err(BackupError::Corrupt(_))));
+    }
+}
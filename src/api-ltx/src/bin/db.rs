@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use api_ltx::backup::{self, BackupError};
+use clap::{Parser, Subcommand};
+use core_ltx::get_db_pool;
+
+/// Backs up and restores the llms.txt database (jobs and generated llms.txt files).
+///
+/// Backups are checksummed JSON Lines files. To keep them in S3 (or anywhere else), pipe them:
+///   db backup | aws s3 cp - s3://bucket/llms-txt.jsonl
+///   aws s3 cp s3://bucket/llms-txt.jsonl - | db restore
+#[derive(Parser)]
+#[command(name = "db", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write a consistent snapshot of the database.
+    Backup {
+        /// Where to write the backup. Use "-" for STDOUT.
+        #[arg(short, long, default_value = "-")]
+        output: PathBuf,
+    },
+    /// Restore a backup. Jobs already in the database are kept as-is.
+    Restore {
+        /// Backup to restore. Use "-" for STDIN.
+        #[arg(default_value = "-")]
+        input: PathBuf,
+    },
+}
+
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+#[tokio::main]
+async fn main() {
+    let _ = dotenvy::dotenv();
+    let cli = Cli::parse();
+
+    let pool = get_db_pool().await;
+    let mut conn = match pool.get().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("[ERROR] Failed to connect to the database: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let result: Result<String, BackupError> = match cli.command {
+        Command::Backup { output } => {
+            let counts = if is_stdio(&output) {
+                backup::backup(&mut conn, BufWriter::new(io::stdout())).await
+            } else {
+                match File::create(&output) {
+                    Ok(file) => backup::backup(&mut conn, BufWriter::new(file)).await,
+                    Err(e) => Err(e.into()),
+                }
+            };
+            counts.map(|counts| {
+                format!(
+                    "Backed up {} jobs and {} llms.txt files",
+                    counts.job_state, counts.llms_txt
+                )
+            })
+        }
+        Command::Restore { input } => {
+            let summary = if is_stdio(&input) {
+                backup::restore(&mut conn, BufReader::new(io::stdin())).await
+            } else {
+                match File::open(&input) {
+                    Ok(file) => backup::restore(&mut conn, BufReader::new(file)).await,
+                    Err(e) => Err(e.into()),
+                }
+            };
+            summary.map(|summary| {
+                format!(
+                    "Restored {} of {} jobs and {} of {} llms.txt files (the rest already existed)",
+                    summary.inserted.job_state,
+                    summary.backed_up.job_state,
+                    summary.inserted.llms_txt,
+                    summary.backed_up.llms_txt
+                )
+            })
+        }
+    };
+
+    match result {
+        Ok(message) => eprintln!("[OK] {}", message),
+        Err(e) => {
+            eprintln!("[ERROR] {}", e);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use api_ltx::mcp::handle_line;
+use core_ltx::get_db_pool;
+
+/// MCP server over STDIO: one JSON-RPC message per line on STDIN, responses on STDOUT.
+///
+/// Reads the database directly, so it needs `DATABASE_URL`. For example, in Claude Desktop's config:
+///   "mcpServers": { "llm-web-index": { "command": "/path/to/mcp", "env": { "DATABASE_URL": "postgres://..." } } }
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+
+    // STDOUT carries the protocol: logs must go to STDERR
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "api_ltx=info".into()),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let pool = get_db_pool().await;
+    tracing::info!("MCP server ready on STDIO");
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::error!("Failed to read from STDIN: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&pool, &line).await {
+            let written = async {
+                stdout.write_all(response.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await
+            };
+            if let Err(e) = written.await {
+                tracing::error!("Failed to write to STDOUT: {}", e);
+                break;
+            }
+        }
+    }
+    tracing::info!("STDIN closed: MCP server exiting");
+}
@@ -1,2 +1,4 @@
 pub mod auth;
+pub mod backup;
+pub mod mcp;
 pub mod routes;
@@ -2,8 +2,9 @@ use std::net::SocketAddr;
 
 use core_ltx::{
     common::env_check::check_non_empty_env_vars, get_api_base_url, get_auth_config, get_db_pool, get_tls_config,
-    is_auth_enabled, setup_logging,
+    is_auth_enabled, setup_telemetry,
 };
+use data_model_ltx::migrations::run_migrations_if_enabled;
 use tracing::info;
 
 use api_ltx::routes;
@@ -21,7 +22,7 @@ async fn main() {
         check_non_empty_env_vars(&["AUTH_PASSWORD_HASH", "SESSION_SECRET"]);
     }
 
-    setup_logging("api_ltx=debug,tower_http=debug");
+    let _telemetry = setup_telemetry("api-ltx", "api_ltx=debug,tower_http=debug");
 
     // Load authentication configuration
     let auth_config = get_auth_config();
@@ -35,8 +36,16 @@ async fn main() {
     let tls_config = get_tls_config().await;
     info!("TLS: ENABLED");
 
+    // Fail-fast check: the queue backpressure thresholds are read on the first job submission otherwise
+    let thresholds = *routes::job_state::QUEUE_THRESHOLDS;
+    info!(
+        "Queue backpressure: from {} queued jobs or an estimated {:?} wait",
+        thresholds.depth, thresholds.wait
+    );
+
+    run_migrations_if_enabled().await;
     let pool = get_db_pool().await;
-    let app = routes::router(auth_config).with_state(pool);
+    let app = routes::router(auth_config, pool);
 
     let addr = get_api_base_url()
         .parse::<SocketAddr>()
@@ -45,7 +54,8 @@ async fn main() {
     info!("Starting HTTPS server on https://{}", addr);
 
     axum_server::bind_rustls(addr, tls_config)
-        .serve(app.into_make_service())
+        // the client's address is used to throttle failed logins
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
@@ -0,0 +1,399 @@
+//! Model Context Protocol (MCP) server, so that LLM clients (Claude Desktop, IDE agents, ...) can use the index.
+//!
+//! Messages are JSON-RPC 2.0. They're served over HTTP at `POST /mcp` and over STDIO by the `mcp` binary.
+//! Three tools are exposed:
+//!   - `get_llms_txt`: the llms.txt of a website
+//!   - `search`: find websites whose URL or llms.txt mentions a query
+//!   - `request_generation`: queue a job to generate (or regenerate) a website's llms.txt
+
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use core_ltx::canonical_url;
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{JobIdResponse, JobRequestPayload, ResultData, ResultStatus};
+use data_model_ltx::schema::llms_txt;
+
+use crate::routes::job_state::in_progress_jobs;
+use crate::routes::llms_txt::{NewJobError, fetch_llms_txt, new_llms_txt_generate_job, update_llms_txt_generation};
+
+/// Newest MCP revision this server implements. Older clients are answered with their own revision.
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2024-11-05", "2025-03-26", PROTOCOL_VERSION];
+
+/// Maximum number of websites returned by `search`.
+const MAX_SEARCH_RESULTS: usize = 10;
+
+/// Characters of llms.txt content shown around a search match.
+const SNIPPET_CHARS: usize = 240;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcRequest {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response.
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct UrlArgs {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchArgs {
+    query: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ToolError {
+    #[error("Unknown tool: {0}")]
+    UnknownTool(String),
+    #[error("Invalid arguments: {0}")]
+    InvalidArguments(#[from] serde_json::Error),
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Database error: {0}")]
+    Db(#[from] diesel::result::Error),
+    #[error("Database connection error: {0}")]
+    Pool(#[from] core_ltx::db::PoolError),
+}
+
+/// Handles one JSON-RPC message. Returns the response, or None for notifications.
+pub async fn handle_message(pool: &DbPool, message: Value) -> Option<Value> {
+    let request: JsonRpcRequest = match serde_json::from_value(message) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, INVALID_REQUEST, &e.to_string())),
+    };
+    if request.jsonrpc != "2.0" {
+        return request
+            .id
+            .map(|id| error_response(id, INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+    }
+    let Some(id) = request.id else {
+        // notifications/initialized, notifications/cancelled, ...: nothing to do
+        tracing::trace!("MCP notification: {}", request.method);
+        return None;
+    };
+
+    let result = match request.method.as_str() {
+        "initialize" => initialize(&request.params),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tools() }),
+        "tools/call" => match serde_json::from_value::<ToolCall>(request.params) {
+            Ok(call) => call_tool(pool, call).await,
+            Err(e) => return Some(error_response(id, INVALID_PARAMS, &e.to_string())),
+        },
+        method => {
+            return Some(error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("Unknown method: {}", method),
+            ));
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Handles one line of the STDIO transport. Returns the line to write back, if any.
+pub async fn handle_line(pool: &DbPool, line: &str) -> Option<String> {
+    let response = match serde_json::from_str::<Value>(line) {
+        Ok(message) => handle_message(pool, message).await?,
+        Err(e) => error_response(Value::Null, PARSE_ERROR, &e.to_string()),
+    };
+    Some(response.to_string())
+}
+
+/// POST /mcp - MCP over HTTP. Responses are plain JSON: this server never streams.
+#[utoipa::path(
+    post,
+    path = "/mcp",
+    tag = "mcp",
+    request_body(content = Value, description = "A JSON-RPC request or notification"),
+    responses(
+        (status = 200, description = "The JSON-RPC response", body = Value),
+        (status = 202, description = "The message was a notification: there's no response"),
+    )
+)]
+pub async fn post_mcp(State(pool): State<DbPool>, Json(message): Json<Value>) -> Response {
+    match handle_message(&pool, message).await {
+        Some(response) => (StatusCode::OK, Json(response)).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let protocol_version = match requested {
+        Some(version) if SUPPORTED_PROTOCOL_VERSIONS.contains(&version) => version,
+        _ => PROTOCOL_VERSION,
+    };
+    json!({
+        "protocolVersion": protocol_version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "llm-web-index", "version": env!("CARGO_PKG_VERSION") },
+        "instructions": "An index of llms.txt files: concise, LLM-friendly summaries of websites. \
+            Use get_llms_txt to read a website's llms.txt, search to find indexed websites, \
+            and request_generation to index a website that's missing.",
+    })
+}
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "get_llms_txt",
+            "description": "Get the llms.txt file of a website: a Markdown summary of the site and links to its key pages.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "url": { "type": "string", "description": "The website's URL, e.g. https://example.com" } },
+                "required": ["url"],
+            },
+        },
+        {
+            "name": "search",
+            "description": "Find indexed websites whose URL or llms.txt contains the query (case-insensitive).",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "query": { "type": "string", "description": "Text to look for" } },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "request_generation",
+            "description": "Queue a job to generate a website's llms.txt, or to regenerate it if it's already indexed. \
+                Generation takes about a minute; then use get_llms_txt.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "url": { "type": "string", "description": "The website's URL, e.g. https://example.com" } },
+                "required": ["url"],
+            },
+        },
+    ])
+}
+
+/// Runs a tool. Tool failures are reported in the result (`isError`) so that the LLM can see and react to them.
+async fn call_tool(pool: &DbPool, call: ToolCall) -> Value {
+    let outcome = match call.name.as_str() {
+        "get_llms_txt" => match serde_json::from_value::<UrlArgs>(call.arguments) {
+            Ok(args) => get_llms_txt(pool, &args.url).await,
+            Err(e) => Err(e.into()),
+        },
+        "search" => match serde_json::from_value::<SearchArgs>(call.arguments) {
+            Ok(args) => search(pool, &args.query).await,
+            Err(e) => Err(e.into()),
+        },
+        "request_generation" => match serde_json::from_value::<UrlArgs>(call.arguments) {
+            Ok(args) => request_generation(pool, &args.url).await,
+            Err(e) => Err(e.into()),
+        },
+        name => Err(ToolError::UnknownTool(name.to_string())),
+    };
+    let (text, is_error) = match outcome {
+        Ok(text) => (text, false),
+        Err(e) => {
+            tracing::warn!("MCP tool '{}' failed: {}", call.name, e);
+            (e.to_string(), true)
+        }
+    };
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+async fn get_llms_txt(pool: &DbPool, url: &str) -> Result<String, ToolError> {
+    let mut conn = pool.get().await?;
+    match fetch_llms_txt(&mut conn, url).await {
+        Ok(record) => Ok(record.result_data.into_text()),
+        Err(diesel::result::Error::NotFound) => {
+            let in_progress = in_progress_jobs(&mut conn, url).await?;
+            if in_progress.is_empty() {
+                Ok(format!(
+                    "{} is not indexed yet. Use request_generation to generate its llms.txt.",
+                    url
+                ))
+            } else {
+                Ok(format!(
+                    "The llms.txt of {} is being generated (job {}). Try again in a minute.",
+                    url, in_progress[0]
+                ))
+            }
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn search(pool: &DbPool, query: &str) -> Result<String, ToolError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok("The query is empty.".to_string());
+    }
+    let mut conn = pool.get().await?;
+    let pattern = format!("%{}%", escape_like(query));
+    // newest first, so that the first match for each URL is its most recent llms.txt.
+    // Compressed llms.txt can't be matched in SQL: they're matched below, once decompressed.
+    let matches: Vec<(String, ResultData)> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .filter(
+            llms_txt::url
+                .ilike(&pattern)
+                .or(llms_txt::result_data.ilike(&pattern))
+                .or(llms_txt::result_data.like(ResultData::compressed_like_pattern())),
+        )
+        .order((llms_txt::created_at.desc(), llms_txt::job_id.asc()))
+        .select((llms_txt::url, llms_txt::result_data))
+        .limit(MAX_SEARCH_RESULTS as i64 * 20)
+        .load(&mut conn)
+        .await?;
+
+    let lowercase_query = query.to_lowercase();
+    let mut seen = std::collections::HashSet::new();
+    let results: Vec<String> = matches
+        .into_iter()
+        .map(|(url, result_data)| (url, result_data.is_compressed(), result_data.into_text()))
+        .filter(|(url, compressed, llms_txt)| {
+            !compressed
+                || url.to_lowercase().contains(&lowercase_query)
+                || llms_txt.to_lowercase().contains(&lowercase_query)
+        })
+        .map(|(url, _, llms_txt)| (url, llms_txt))
+        .filter(|(url, _)| seen.insert(url.clone()))
+        .take(MAX_SEARCH_RESULTS)
+        .map(|(url, llms_txt)| format!("## {}\n\n{}", url, snippet(&llms_txt, query)))
+        .collect();
+
+    if results.is_empty() {
+        Ok(format!("No indexed website matches '{}'.", query))
+    } else {
+        Ok(results.join("\n\n"))
+    }
+}
+
+async fn request_generation(pool: &DbPool, url: &str) -> Result<String, ToolError> {
+    let url = canonical_url(url)
+        .map_err(|e| ToolError::InvalidUrl(e.to_string()))?
+        .to_string();
+    let mut conn = pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            let (created, verb) = match fetch_llms_txt(conn, &url).await {
+                Ok(existing) => (
+                    update_llms_txt_generation(conn, &existing, &JobRequestPayload::new(&url), None).await,
+                    "Regenerating",
+                ),
+                Err(diesel::result::Error::NotFound) => (
+                    new_llms_txt_generate_job(conn, &JobRequestPayload::new(&url)).await,
+                    "Generating",
+                ),
+                Err(e) => return Err(e.into()),
+            };
+            match created {
+                Ok(JobIdResponse { job_id, .. }) => Ok(format!(
+                    "{} the llms.txt of {} (job {}). Use get_llms_txt in about a minute.",
+                    verb, url, job_id
+                )),
+                // don't pile up jobs for a website that's already being worked on
+                Err(NewJobError::JobsInProgress(in_progress)) => Ok(match in_progress.first() {
+                    Some(job_id) => format!("{} is already being generated (job {}).", url, job_id),
+                    None => format!("{} is already being generated.", url),
+                }),
+                // only low-priority jobs are turned away, and these aren't
+                Err(NewJobError::QueueBusy(_)) => Ok(format!("The queue is backed up: try {} again later.", url)),
+                Err(NewJobError::DomainNotAllowed(reason)) => Ok(format!("{} can't be indexed: {}.", url, reason)),
+                Err(NewJobError::Db(e)) => Err(e.into()),
+            }
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Escapes LIKE wildcards, so that the query is matched literally.
+pub(crate) fn escape_like(query: &str) -> String {
+    query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Part of `text` around the first (case-insensitive) occurrence of `query`, or its beginning if there's none.
+fn snippet(text: &str, query: &str) -> String {
+    let lowercase = text.to_lowercase();
+    // lowercasing can change byte lengths: only trust the position when it didn't
+    let start = match lowercase.find(&query.to_lowercase()) {
+        Some(position) if lowercase.len() == text.len() => text[..position].chars().count(),
+        _ => 0,
+    };
+    let start = start.saturating_sub(SNIPPET_CHARS / 4);
+    let total = text.chars().count();
+    let excerpt: String = text.chars().skip(start).take(SNIPPET_CHARS).collect();
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if start + SNIPPET_CHARS < total { "…" } else { "" };
+    format!("{}{}{}", prefix, excerpt.trim(), suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done\\"), "100\\%\\_done\\\\");
+    }
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("# Example\n\n> A site", "site"), "# Example\n\n> A site");
+
+        let text = format!("{}needle{}", "a".repeat(500), "b".repeat(500));
+        let excerpt = snippet(&text, "NEEDLE");
+        assert!(excerpt.starts_with('…') && excerpt.ends_with('…'));
+        assert!(excerpt.contains("needle"));
+    }
+
+    #[test]
+    fn test_initialize_negotiates_version() {
+        let result = initialize(&json!({ "protocolVersion": "2024-11-05" }));
+        assert_eq!(result["protocolVersion"], "2024-11-05");
+        let result = initialize(&json!({ "protocolVersion": "1999-01-01" }));
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSION);
+        assert!(result["capabilities"]["tools"].is_object());
+    }
+
+    #[test]
+    fn test_tools_have_schemas() {
+        let tools = tools();
+        let names: Vec<&str> = tools
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| {
+                assert_eq!(tool["inputSchema"]["type"], "object");
+                tool["name"].as_str().unwrap()
+            })
+            .collect();
+        assert_eq!(names, ["get_llms_txt", "search", "request_generation"]);
+    }
+}
@@ -0,0 +1,160 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{
+    ApiKey, ApiKeyError, ApiKeyIdPayload, ApiKeyPayload, ApiKeySummary, ApiKeysResponse, CreatedApiKeyResponse,
+};
+use data_model_ltx::schema::api_keys;
+
+/// How a key is stored: the hex SHA-256 of the key.
+pub fn hash_api_key(key: &str) -> String {
+    Sha256::digest(key.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Whether the key is one that wasn't revoked. If it is, it's marked as used at `now`.
+pub async fn authenticate_api_key(pool: &DbPool, key: &str, now: DateTime<Utc>) -> Result<bool, ApiKeyError> {
+    let mut conn = pool.get().await?;
+    let used = diesel::update(
+        api_keys::table
+            .filter(api_keys::key_hash.eq(hash_api_key(key)))
+            .filter(api_keys::revoked_at.is_null()),
+    )
+    .set(api_keys::last_used_at.eq(now))
+    .execute(&mut conn)
+    .await?;
+    Ok(used == 1)
+}
+
+// GET /api/admin/api_keys - The API keys (never the keys themselves)
+#[utoipa::path(
+    get,
+    path = "/api/admin/api_keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every API key", body = ApiKeysResponse),
+        (status = 500, description = "The server failed", body = ApiKeyError),
+    )
+)]
+pub async fn get_api_keys(State(pool): State<DbPool>) -> Result<impl IntoResponse, ApiKeyError> {
+    let mut conn = pool.get().await?;
+    let keys = api_keys::table
+        .order((api_keys::created_at.desc(), api_keys::key_id.asc()))
+        .select(ApiKey::as_select())
+        .load::<ApiKey>(&mut conn)
+        .await?;
+    Ok((
+        StatusCode::OK,
+        Json(ApiKeysResponse {
+            api_keys: keys.into_iter().map(ApiKeySummary::from).collect(),
+        }),
+    ))
+}
+
+// POST /api/admin/api_keys - Create an API key, that's returned this once
+#[utoipa::path(
+    post,
+    path = "/api/admin/api_keys",
+    tag = "admin",
+    request_body = ApiKeyPayload,
+    responses(
+        (status = 201, description = "The API key, with the key itself", body = CreatedApiKeyResponse),
+        (status = 400, description = "The key has no name", body = ApiKeyError),
+        (status = 500, description = "The server failed", body = ApiKeyError),
+    )
+)]
+pub async fn post_api_key(
+    State(pool): State<DbPool>,
+    Json(payload): Json<ApiKeyPayload>,
+) -> Result<impl IntoResponse, ApiKeyError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(ApiKeyError::InvalidRequest("The API key needs a name".to_string()));
+    }
+    let mut conn = pool.get().await?;
+
+    let key = ApiKey::generate_key();
+    let created = diesel::insert_into(api_keys::table)
+        .values(&ApiKey {
+            key_id: Uuid::new_v4(),
+            name: name.to_string(),
+            key_hash: hash_api_key(&key),
+            prefix: key[..ApiKey::PREFIX_LEN].to_string(),
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked_at: None,
+        })
+        .returning(ApiKey::as_returning())
+        .get_result::<ApiKey>(&mut conn)
+        .await?;
+
+    tracing::info!(target: "audit", "API keys: created '{}' ({})", created.name, created.key_id);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiKeyResponse {
+            api_key: ApiKeySummary::from(created),
+            key,
+        }),
+    ))
+}
+
+// DELETE /api/admin/api_keys - Revoke an API key: it doesn't authenticate requests anymore
+#[utoipa::path(
+    delete,
+    path = "/api/admin/api_keys",
+    tag = "admin",
+    params(ApiKeyIdPayload),
+    responses(
+        (status = 204, description = "The API key was revoked"),
+        (status = 404, description = "There's no unrevoked API key with the key_id", body = ApiKeyError),
+        (status = 500, description = "The server failed", body = ApiKeyError),
+    )
+)]
+pub async fn delete_api_key(
+    State(pool): State<DbPool>,
+    Query(payload): Query<ApiKeyIdPayload>,
+) -> Result<impl IntoResponse, ApiKeyError> {
+    let mut conn = pool.get().await?;
+
+    // kept, so the listing shows when it was revoked
+    let revoked = diesel::update(
+        api_keys::table
+            .find(payload.key_id)
+            .filter(api_keys::revoked_at.is_null()),
+    )
+    .set(api_keys::revoked_at.eq(Utc::now()))
+    .execute(&mut conn)
+    .await?;
+    if revoked == 0 {
+        return Err(ApiKeyError::UnknownId);
+    }
+
+    tracing::warn!(target: "audit", "API keys: revoked {}", payload.key_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_keys_are_hashed_apart() {
+        let key = ApiKey::generate_key();
+        assert!(key.starts_with("ltx_"));
+        assert_eq!(key.len(), 4 + 64);
+        assert_eq!(hash_api_key(&key).len(), 64);
+        assert_eq!(hash_api_key(&key), hash_api_key(&format!(" {key}\n")));
+        assert_ne!(hash_api_key(&key), hash_api_key(&ApiKey::generate_key()));
+    }
+}
@@ -0,0 +1,303 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use core_ltx::lint::quality_score;
+use core_ltx::llms::prompt_version;
+use data_model_ltx::models::{
+    CampaignError, CampaignIdPayload, JobRequestPayload, LlmsTxtSource, RegenerateAllPayload, RegenerationCampaign,
+    ResultData, ResultStatus,
+};
+use data_model_ltx::schema::{llms_txt, regeneration_campaign};
+
+use crate::routes::llms_txt::{NewJobError, fetch_llms_txt, update_llms_txt_generation};
+
+/// Jobs enqueued per batch, unless the request says otherwise.
+const DEFAULT_BATCH_SIZE: u32 = 50;
+/// Wait between batches, unless the request says otherwise.
+const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The latest llms.txt of a URL, with what the filters look at.
+struct Candidate {
+    url: String,
+    result_data: ResultData,
+    created_at: DateTime<Utc>,
+    source: LlmsTxtSource,
+    prompt_version: Option<String>,
+}
+
+impl RegenerateAllPayload {
+    fn matches(&self, candidate: &Candidate) -> bool {
+        self.domain
+            .as_deref()
+            .is_none_or(|domain| is_on_domain(&candidate.url, domain))
+            && self
+                .older_than
+                .is_none_or(|older_than| candidate.created_at < older_than)
+            && self.provider.is_none_or(|provider| candidate.source == provider)
+            && self
+                .quality_below
+                .is_none_or(|below| quality_score(&candidate.result_data.text()) < below)
+            && (!self.outdated_prompt || candidate.has_outdated_prompt())
+    }
+}
+
+impl Candidate {
+    /// Generated with other prompts than this server's. Unknown versions (from before they were recorded) count.
+    fn has_outdated_prompt(&self) -> bool {
+        self.source == LlmsTxtSource::Generated && self.prompt_version.as_deref() != Some(prompt_version())
+    }
+}
+
+/// Whether the URL's host is `domain`, or one of its subdomains.
+fn is_on_domain(url: &str, domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(|host| host.to_ascii_lowercase()))
+        .is_some_and(|host| host == domain || host.ends_with(&format!(".{}", domain)))
+}
+
+/// URLs whose most recent successful llms.txt matches the filters, in the order of their normalized URLs.
+async fn matching_urls(
+    conn: &mut AsyncPgConnection,
+    filters: &RegenerateAllPayload,
+) -> Result<Vec<String>, diesel::result::Error> {
+    let latest = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .distinct_on(llms_txt::url_normalized)
+        .order((llms_txt::url_normalized.asc(), llms_txt::created_at.desc()))
+        .select((
+            llms_txt::url,
+            llms_txt::result_data,
+            llms_txt::created_at,
+            llms_txt::source,
+            llms_txt::prompt_version,
+        ))
+        .load::<(String, ResultData, DateTime<Utc>, LlmsTxtSource, Option<String>)>(conn)
+        .await?;
+
+    Ok(latest
+        .into_iter()
+        .map(|(url, result_data, created_at, source, prompt_version)| Candidate {
+            url,
+            result_data,
+            created_at,
+            source,
+            prompt_version,
+        })
+        .filter(|candidate| filters.matches(candidate))
+        .map(|candidate| candidate.url)
+        .collect())
+}
+
+// POST /api/admin/regenerate_all - Start re-generating every llms.txt that matches the filters
+#[utoipa::path(
+    post,
+    path = "/api/admin/regenerate_all",
+    tag = "admin",
+    request_body = RegenerateAllPayload,
+    responses(
+        (status = 202, description = "The campaign started", body = RegenerationCampaign),
+        (status = 400, description = "A filter or throttling setting is out of range", body = CampaignError),
+        (status = 500, description = "The server failed", body = CampaignError),
+    )
+)]
+pub async fn post_regenerate_all(
+    State(pool): State<DbPool>,
+    Json(payload): Json<RegenerateAllPayload>,
+) -> Result<impl IntoResponse, CampaignError> {
+    if payload.quality_below.is_some_and(|below| below > 100) {
+        return Err(CampaignError::InvalidRequest(
+            "quality_below must be between 0 and 100".to_string(),
+        ));
+    }
+    let batch_size = payload.batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+    if batch_size == 0 {
+        return Err(CampaignError::InvalidRequest(
+            "batch_size must be at least 1".to_string(),
+        ));
+    }
+    let batch_interval = payload
+        .batch_interval_s
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_BATCH_INTERVAL);
+
+    let mut conn = pool.get().await?;
+    let urls = matching_urls(&mut conn, &payload).await?;
+
+    let campaign = RegenerationCampaign {
+        campaign_id: Uuid::new_v4(),
+        created_at: Utc::now(),
+        finished_at: None,
+        domain: payload.domain.clone(),
+        older_than: payload.older_than,
+        provider: payload.provider,
+        quality_below: payload.quality_below.map(i32::from),
+        outdated_prompt: payload.outdated_prompt,
+        total: urls.len() as i32,
+        enqueued: 0,
+        skipped: 0,
+        failed: 0,
+    };
+    diesel::insert_into(regeneration_campaign::table)
+        .values(&campaign)
+        .execute(&mut conn)
+        .await?;
+
+    tracing::info!(
+        "Campaign {}: re-generating {} llms.txt file(s), {} every {:?}",
+        campaign.campaign_id,
+        urls.len(),
+        batch_size,
+        batch_interval
+    );
+    tokio::spawn(run_campaign(
+        pool.clone(),
+        campaign.campaign_id,
+        urls,
+        batch_size as usize,
+        batch_interval,
+    ));
+
+    Ok((StatusCode::ACCEPTED, Json(campaign)))
+}
+
+/// Enqueues an Update job for each URL, a batch at a time, recording progress after every batch.
+/// URLs that already have a queued or running job are skipped: that job brings them up to date.
+async fn run_campaign(pool: DbPool, campaign_id: Uuid, urls: Vec<String>, batch_size: usize, interval: Duration) {
+    let mut batches = urls.chunks(batch_size).peekable();
+    while let Some(batch) = batches.next() {
+        let (mut enqueued, mut skipped, mut failed) = (0, 0, 0);
+        match pool.get().await {
+            Ok(mut conn) => {
+                for url in batch {
+                    match enqueue_update(&mut conn, url).await {
+                        Ok(()) => enqueued += 1,
+                        Err(NewJobError::JobsInProgress(_)) => skipped += 1,
+                        Err(e) => {
+                            tracing::warn!("Campaign {}: couldn't enqueue '{}': {}", campaign_id, url, e);
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Campaign {}: couldn't enqueue a batch: {}", campaign_id, e);
+                failed = batch.len() as i32;
+            }
+        }
+        let finished_at = batches.peek().is_none().then(Utc::now);
+        if let Err(e) = record_progress(&pool, campaign_id, enqueued, skipped, failed, finished_at).await {
+            tracing::error!("Campaign {}: couldn't record progress: {:?}", campaign_id, e);
+        }
+        if finished_at.is_none() {
+            tokio::time::sleep(interval).await;
+        }
+    }
+    if urls.is_empty()
+        && let Err(e) = record_progress(&pool, campaign_id, 0, 0, 0, Some(Utc::now())).await
+    {
+        tracing::error!("Campaign {}: couldn't record progress: {:?}", campaign_id, e);
+    }
+    tracing::info!("Campaign {}: finished", campaign_id);
+}
+
+/// Creates an Update job from the URL's current llms.txt.
+async fn enqueue_update(conn: &mut AsyncPgConnection, url: &str) -> Result<(), NewJobError> {
+    let llms_txt = fetch_llms_txt(conn, url).await?;
+    update_llms_txt_generation(conn, &llms_txt, &JobRequestPayload::new(url), None).await?;
+    Ok(())
+}
+
+async fn record_progress(
+    pool: &DbPool,
+    campaign_id: Uuid,
+    enqueued: i32,
+    skipped: i32,
+    failed: i32,
+    finished_at: Option<DateTime<Utc>>,
+) -> Result<(), CampaignError> {
+    let mut conn = pool.get().await?;
+    diesel::update(regeneration_campaign::table.find(campaign_id))
+        .set((
+            regeneration_campaign::enqueued.eq(regeneration_campaign::enqueued + enqueued),
+            regeneration_campaign::skipped.eq(regeneration_campaign::skipped + skipped),
+            regeneration_campaign::failed.eq(regeneration_campaign::failed + failed),
+            regeneration_campaign::finished_at.eq(finished_at),
+        ))
+        .execute(&mut conn)
+        .await?;
+    Ok(())
+}
+
+// GET /api/admin/regenerate_all - Get the progress of a campaign
+#[utoipa::path(
+    get,
+    path = "/api/admin/regenerate_all",
+    tag = "admin",
+    params(CampaignIdPayload),
+    responses(
+        (status = 200, description = "The campaign's progress", body = RegenerationCampaign),
+        (status = 404, description = "There's no campaign with the campaign_id", body = CampaignError),
+        (status = 500, description = "The server failed", body = CampaignError),
+    )
+)]
+pub async fn get_regenerate_all(
+    State(pool): State<DbPool>,
+    Query(payload): Query<CampaignIdPayload>,
+) -> Result<impl IntoResponse, CampaignError> {
+    let mut conn = pool.get().await?;
+
+    let campaign = regeneration_campaign::table
+        .find(payload.campaign_id)
+        .select(RegenerationCampaign::as_select())
+        .first::<RegenerationCampaign>(&mut conn)
+        .await?;
+
+    tracing::trace!(
+        "Success: campaign {} has enqueued {}/{}",
+        campaign.campaign_id,
+        campaign.enqueued,
+        campaign.total
+    );
+    Ok((StatusCode::OK, Json(campaign)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_on_domain() {
+        assert!(is_on_domain("https://example.com/docs", "example.com"));
+        assert!(is_on_domain("https://docs.Example.com/", "example.com."));
+        assert!(!is_on_domain("https://notexample.com/", "example.com"));
+        assert!(!is_on_domain("https://example.com.evil.io/", "example.com"));
+        assert!(!is_on_domain("not a url", "example.com"));
+    }
+
+    #[test]
+    fn test_has_outdated_prompt() {
+        let candidate = |source, prompt_version: Option<&str>| Candidate {
+            url: "https://example.com".to_string(),
+            result_data: "# Example".into(),
+            created_at: Utc::now(),
+            source,
+            prompt_version: prompt_version.map(str::to_string),
+        };
+        assert!(!candidate(LlmsTxtSource::Generated, Some(prompt_version())).has_outdated_prompt());
+        assert!(candidate(LlmsTxtSource::Generated, Some("0123456789ab")).has_outdated_prompt());
+        assert!(candidate(LlmsTxtSource::Generated, None).has_outdated_prompt());
+        assert!(!candidate(LlmsTxtSource::Origin, None).has_outdated_prompt());
+    }
+}
@@ -0,0 +1,192 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use core_ltx::db::DbPool;
+use core_ltx::is_valid_url;
+use data_model_ltx::models::{CrawlSchedule, CrawlScheduleError, CrawlSchedulePayload, UrlPayload};
+use data_model_ltx::schema::crawl_schedule;
+
+use crate::routes::domain_policy::domain_violation;
+use crate::routes::llms_txt::{MAX_CRAWL_DEPTH, MAX_CRAWL_PAGES};
+
+/// Shortest time between two scheduled crawls of a website: a crawl downloads up to `MAX_CRAWL_PAGES` pages.
+const MIN_CRAWL_INTERVAL_S: u64 = 60 * 60;
+
+/// What's wrong with the requested schedule, if anything.
+fn check_schedule(payload: &CrawlSchedulePayload) -> Result<(), String> {
+    is_valid_url(&payload.url).map_err(|e| format!("url: {}", e))?;
+    if payload.interval_s < MIN_CRAWL_INTERVAL_S || payload.interval_s > i64::MAX as u64 {
+        return Err(format!("interval_s must be at least {}", MIN_CRAWL_INTERVAL_S));
+    }
+    if payload.crawl.max_depth > MAX_CRAWL_DEPTH {
+        return Err(format!("max_depth must be at most {}", MAX_CRAWL_DEPTH));
+    }
+    if !(1..=MAX_CRAWL_PAGES).contains(&payload.crawl.max_pages) {
+        return Err(format!("max_pages must be between 1 and {}", MAX_CRAWL_PAGES));
+    }
+    Ok(())
+}
+
+// PUT /api/crawl_schedule - Re-crawl a website on a schedule, or change its schedule
+#[utoipa::path(
+    put,
+    path = "/api/crawl_schedule",
+    tag = "crawl schedules",
+    request_body = CrawlSchedulePayload,
+    responses(
+        (status = 200, description = "The schedule was changed", body = CrawlSchedule),
+        (status = 201, description = "The website is re-crawled from now on", body = CrawlSchedule),
+        (status = 400, description = "The URL, the interval, or a crawl limit is invalid", body = CrawlScheduleError),
+        (status = 500, description = "The server failed", body = CrawlScheduleError),
+    )
+)]
+pub async fn put_crawl_schedule(
+    State(pool): State<DbPool>,
+    Json(payload): Json<CrawlSchedulePayload>,
+) -> Result<impl IntoResponse, CrawlScheduleError> {
+    check_schedule(&payload).map_err(CrawlScheduleError::InvalidRequest)?;
+    let mut conn = pool.get().await?;
+    if let Some(reason) = domain_violation(&mut conn, &payload.url).await? {
+        return Err(CrawlScheduleError::InvalidRequest(reason));
+    }
+
+    let existing = crawl_schedule::table
+        .find(&payload.url)
+        .select(CrawlSchedule::as_select())
+        .first::<CrawlSchedule>(&mut conn)
+        .await
+        .optional()?;
+    let now = Utc::now();
+    let mut schedule = CrawlSchedule {
+        url: payload.url.clone(),
+        interval_s: payload.interval_s as i64,
+        max_depth: payload.crawl.max_depth as i32,
+        max_pages: payload.crawl.max_pages as i32,
+        next_run_at: now,
+        last_run_at: existing.as_ref().and_then(|existing| existing.last_run_at),
+        created_at: existing.as_ref().map_or(now, |existing| existing.created_at),
+    };
+    // a new schedule crawls at the next cron cycle; a changed one keeps counting from its last crawl
+    if let Some(last_run_at) = schedule.last_run_at {
+        schedule.next_run_at = schedule.next_run_after(last_run_at);
+    }
+
+    diesel::insert_into(crawl_schedule::table)
+        .values(&schedule)
+        .on_conflict(crawl_schedule::url)
+        .do_update()
+        .set((
+            crawl_schedule::interval_s.eq(schedule.interval_s),
+            crawl_schedule::max_depth.eq(schedule.max_depth),
+            crawl_schedule::max_pages.eq(schedule.max_pages),
+            crawl_schedule::next_run_at.eq(schedule.next_run_at),
+        ))
+        .execute(&mut conn)
+        .await?;
+
+    tracing::trace!(
+        "Success: '{}' is crawled every {}s, next at {}",
+        schedule.url,
+        schedule.interval_s,
+        schedule.next_run_at
+    );
+    let status = if existing.is_some() {
+        StatusCode::OK
+    } else {
+        StatusCode::CREATED
+    };
+    Ok((status, Json(schedule)))
+}
+
+// GET /api/crawl_schedule - Get the crawl schedule of a website
+#[utoipa::path(
+    get,
+    path = "/api/crawl_schedule",
+    tag = "crawl schedules",
+    params(UrlPayload),
+    responses(
+        (status = 200, description = "The website's crawl schedule", body = CrawlSchedule),
+        (status = 404, description = "The URL has no crawl schedule", body = CrawlScheduleError),
+        (status = 500, description = "The server failed", body = CrawlScheduleError),
+    )
+)]
+pub async fn get_crawl_schedule(
+    State(pool): State<DbPool>,
+    Query(payload): Query<UrlPayload>,
+) -> Result<impl IntoResponse, CrawlScheduleError> {
+    let mut conn = pool.get().await?;
+
+    let schedule = crawl_schedule::table
+        .find(&payload.url)
+        .select(CrawlSchedule::as_select())
+        .first::<CrawlSchedule>(&mut conn)
+        .await?;
+
+    Ok((StatusCode::OK, Json(schedule)))
+}
+
+// DELETE /api/crawl_schedule - Stop re-crawling a website: cron goes back to checking its page for changes
+#[utoipa::path(
+    delete,
+    path = "/api/crawl_schedule",
+    tag = "crawl schedules",
+    params(UrlPayload),
+    responses(
+        (status = 204, description = "The schedule was removed"),
+        (status = 404, description = "The URL has no crawl schedule", body = CrawlScheduleError),
+        (status = 500, description = "The server failed", body = CrawlScheduleError),
+    )
+)]
+pub async fn delete_crawl_schedule(
+    State(pool): State<DbPool>,
+    Query(payload): Query<UrlPayload>,
+) -> Result<impl IntoResponse, CrawlScheduleError> {
+    let mut conn = pool.get().await?;
+
+    let deleted = diesel::delete(crawl_schedule::table.find(&payload.url))
+        .execute(&mut conn)
+        .await?;
+    if deleted == 0 {
+        return Err(CrawlScheduleError::UnknownUrl);
+    }
+
+    tracing::trace!("Success: '{}' is no longer crawled on a schedule", payload.url);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use data_model_ltx::models::Crawl;
+
+    use super::*;
+
+    fn payload(interval_s: u64, max_depth: u32, max_pages: u32) -> CrawlSchedulePayload {
+        CrawlSchedulePayload {
+            url: "https://example.com".to_string(),
+            interval_s,
+            crawl: Crawl { max_depth, max_pages },
+        }
+    }
+
+    #[test]
+    fn test_check_schedule() {
+        assert!(check_schedule(&payload(86_400, 2, 50)).is_ok());
+        assert!(check_schedule(&payload(60, 2, 50)).is_err());
+        assert!(check_schedule(&payload(86_400, MAX_CRAWL_DEPTH + 1, 50)).is_err());
+        assert!(check_schedule(&payload(86_400, 2, 0)).is_err());
+        assert!(check_schedule(&payload(86_400, 2, MAX_CRAWL_PAGES + 1)).is_err());
+        assert!(
+            check_schedule(&CrawlSchedulePayload {
+                url: "not a url".to_string(),
+                ..payload(86_400, 2, 50)
+            })
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,448 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use axum::{
+    Extension, Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use utoipa::{IntoParams, ToSchema};
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{AppError, GetLlmTxtError, LlmsTxt, ResultData, ResultStatus};
+use data_model_ltx::schema::llms_txt;
+
+use crate::mcp::escape_like;
+use crate::routes::read_cache::ReadCache;
+
+const INDEX_NAME: &str = "llm-web-index";
+const INDEX_DESCRIPTION: &str = "Generated llms.txt files of indexed websites";
+
+/// The directory document: every website with an llms.txt in the index, and where to get it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Directory {
+    pub name: String,
+    pub description: String,
+    pub sites: Vec<DirectoryEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DirectoryEntry {
+    /// The website the llms.txt was generated for.
+    pub url: String,
+    /// The llms.txt's H1, or else the page's `<title>`.
+    pub title: Option<String>,
+    /// The llms.txt's blockquote summary, or else the page's meta description.
+    pub summary: Option<String>,
+    /// The page's canonical URL, if it declares one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    /// The website's icon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub favicon_url: Option<String>,
+    /// Version of the prompts the llms.txt was generated with. Absent for llms.txt files the website publishes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// Link to the llms.txt in this service: `GET` returns `{"content": "<llms.txt>"}`.
+    pub llms_txt: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DirectoryQuery {
+    /// `json` or `markdown` (also `md`). Overrides the `Accept` header.
+    pub format: Option<String>,
+}
+
+/// GET /.well-known/llms-directory - Lists every indexed website with a link to its llms.txt.
+///
+/// JSON by default. Markdown when asked for with `?format=markdown` or `Accept: text/markdown`.
+#[utoipa::path(
+    get,
+    path = "/.well-known/llms-directory",
+    tag = "discovery",
+    params(DirectoryQuery),
+    responses(
+        (status = 200, description = "Every indexed website, in JSON or markdown", body = Directory, content_type = ["application/json", "text/markdown"]),
+    )
+)]
+pub async fn get_directory(
+    State(pool): State<DbPool>,
+    Query(query): Query<DirectoryQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let mut conn = pool.get().await?;
+    let sites = directory_entries(&mut conn, base_url(&headers).as_deref()).await?;
+    let directory = Directory {
+        name: INDEX_NAME.to_string(),
+        description: INDEX_DESCRIPTION.to_string(),
+        sites,
+    };
+
+    tracing::trace!("Success: directory lists {} websites", directory.sites.len());
+    if wants_markdown(query.format.as_deref(), &headers) {
+        Ok((
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            render_markdown(&directory),
+        )
+            .into_response())
+    } else {
+        Ok((StatusCode::OK, Json(directory)).into_response())
+    }
+}
+
+/// GET /llms.txt - The index's own llms.txt: every indexed website, under an H2 of its domain
+///
+/// Makes the service itself discoverable by LLMs, like any website with an llms.txt. Each link is a website's
+/// llms.txt, as a file. It's rendered again once the llms.txt records change (see `ReadCache`).
+#[utoipa::path(
+    get,
+    path = "/llms.txt",
+    tag = "discovery",
+    responses(
+        (status = 200, description = "The index's llms.txt", body = String, content_type = "text/plain"),
+        (status = 500, description = "The server failed", body = AppError),
+    )
+)]
+pub async fn get_index_llms_txt(
+    State(pool): State<DbPool>,
+    Extension(cache): Extension<ReadCache>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let base_url = base_url(&headers);
+    let key = base_url.clone().unwrap_or_default();
+    let generation = cache.generation(&pool);
+    let cached = match generation {
+        Some(_) => cache.index_llms_txt(&key).await,
+        None => None,
+    };
+    let llms_txt = match cached {
+        Some(llms_txt) => llms_txt,
+        None => {
+            let mut conn = pool.get().await?;
+            let sites = directory_entries(&mut conn, base_url.as_deref()).await?;
+            let llms_txt = Arc::new(render_index_llms_txt(&sites));
+            if let Some(generation) = generation {
+                cache.insert_index_llms_txt(generation, key, llms_txt.clone()).await;
+            }
+            llms_txt
+        }
+    };
+
+    tracing::trace!("Success: rendered the index's llms.txt");
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        llms_txt.as_str().to_string(),
+    )
+        .into_response())
+}
+
+/// GET /llms/{domain}/llms.txt - The latest llms.txt generated for any page of the domain, as a file
+///
+/// Makes the service a mirror of llms.txt files, that tools can fetch like they would from the website itself. The
+/// domain has to be the page's host exactly: `docs.example.com` isn't `example.com`.
+#[utoipa::path(
+    get,
+    path = "/llms/{domain}/llms.txt",
+    tag = "discovery",
+    params(("domain" = String, Path, description = "Host of the website, e.g. docs.example.com")),
+    responses(
+        (status = 200, description = "The domain's latest llms.txt", body = String, content_type = "text/plain"),
+        (status = 404, description = "No llms.txt was generated for a page of the domain", body = GetLlmTxtError),
+        (status = 500, description = "The server failed", body = GetLlmTxtError),
+    )
+)]
+pub async fn get_domain_llms_txt(
+    State(pool): State<DbPool>,
+    Path(domain): Path<String>,
+) -> Result<Response, GetLlmTxtError> {
+    let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    if domain.is_empty() {
+        return Err(GetLlmTxtError::NotGenerated);
+    }
+    let mut conn = pool.get().await?;
+
+    // narrowed down in SQL, then the host is compared exactly: the pattern also matches e.g. example.com.evil.io
+    let pattern = format!("%://{}%", escape_like(&domain));
+    let candidates: Vec<(String, ResultData, DateTime<Utc>)> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .filter(llms_txt::url.ilike(&pattern))
+        .order((llms_txt::created_at.desc(), llms_txt::job_id.asc()))
+        .select((llms_txt::url, llms_txt::result_data, llms_txt::created_at))
+        .load(&mut conn)
+        .await?;
+    let (url, result_data, created_at) = candidates
+        .into_iter()
+        .find(|(url, _, _)| host(url).is_some_and(|host| host == domain))
+        .ok_or(GetLlmTxtError::NotGenerated)?;
+
+    tracing::trace!("Success: llms.txt of '{}' is the one of {}", domain, url);
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (
+                header::LAST_MODIFIED,
+                created_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            ),
+        ],
+        result_data.into_text(),
+    )
+        .into_response())
+}
+
+/// The latest successful generation of each website, in URL order, linked from `base_url`.
+async fn directory_entries(
+    conn: &mut AsyncPgConnection,
+    base_url: Option<&str>,
+) -> Result<Vec<DirectoryEntry>, diesel::result::Error> {
+    let latest: Vec<LlmsTxt> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .distinct_on(llms_txt::url_normalized)
+        .order((llms_txt::url_normalized.asc(), llms_txt::created_at.desc()))
+        .select(LlmsTxt::as_select())
+        .load(conn)
+        .await?;
+
+    Ok(latest
+        .into_iter()
+        .map(|record| {
+            let content = record.result_data.text();
+            DirectoryEntry {
+                title: title(&content).or(record.title),
+                summary: summary(&content).or(record.description),
+                canonical_url: record.canonical_url,
+                favicon_url: record.favicon_url,
+                prompt_version: record.prompt_version,
+                llms_txt: llms_txt_link(base_url, &record.url),
+                url: record.url,
+                updated_at: record.created_at,
+            }
+        })
+        .collect())
+}
+
+/// The URL's host, lowercase.
+fn host(url: &str) -> Option<String> {
+    Url::parse(url).ok().and_then(|url| {
+        url.host_str()
+            .map(|host| host.trim_end_matches('.').to_ascii_lowercase())
+    })
+}
+
+fn wants_markdown(format: Option<&str>, headers: &HeaderMap) -> bool {
+    match format.map(|f| f.trim().to_ascii_lowercase()) {
+        Some(format) => format == "markdown" || format == "md",
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/markdown")),
+    }
+}
+
+/// Links are absolute when the request names the host, so the document can be used on its own.
+/// The server only speaks HTTPS.
+fn base_url(headers: &HeaderMap) -> Option<String> {
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    Url::parse(&format!("https://{}", host))
+        .ok()
+        .map(|url| url.as_str().trim_end_matches('/').to_string())
+}
+
+fn llms_txt_link(base_url: Option<&str>, site_url: &str) -> String {
+    let query: String = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("url", site_url)
+        .finish();
+    format!("{}/api/llm_txt?{}", base_url.unwrap_or_default(), query)
+}
+
+fn title(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+fn summary(content: &str) -> Option<String> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix('>'))
+        .map(|summary| summary.trim().to_string())
+        .filter(|summary| !summary.is_empty())
+}
+
+/// The index's llms.txt: a title, a summary, and an H2 per domain with a link to the llms.txt (as a file) of each of
+/// its websites.
+fn render_index_llms_txt(sites: &[DirectoryEntry]) -> String {
+    let mut domains: BTreeMap<String, Vec<&DirectoryEntry>> = BTreeMap::new();
+    for site in sites {
+        domains
+            .entry(host(&site.url).unwrap_or_else(|| site.url.clone()))
+            .or_default()
+            .push(site);
+    }
+
+    let mut markdown = format!("# {}\n\n> {}\n\n", INDEX_NAME, INDEX_DESCRIPTION);
+    markdown.push_str(&format!(
+        "{} websites of {} domains are indexed. Each link is a website's llms.txt.\n",
+        sites.len(),
+        domains.len()
+    ));
+    for (domain, sites) in domains {
+        markdown.push_str(&format!("\n## {}\n\n", domain));
+        for site in sites {
+            let name = site.title.as_deref().unwrap_or(&site.url).replace(['[', ']'], "");
+            markdown.push_str(&format!("- [{}]({}&format=raw)", name, site.llms_txt));
+            match &site.summary {
+                Some(summary) => markdown.push_str(&format!(": {}\n", summary)),
+                None => markdown.push('\n'),
+            }
+        }
+    }
+    markdown
+}
+
+/// The directory in llms.txt style: a title, a summary and one link per website.
+fn render_markdown(directory: &Directory) -> String {
+    let mut markdown = format!("# {}\n\n> {}\n\n", directory.name, directory.description);
+    markdown.push_str(&format!(
+        "{} websites are indexed. Each link returns JSON: `{{\"content\": \"<llms.txt>\"}}`.\n\n## Sites\n\n",
+        directory.sites.len()
+    ));
+    for site in directory.sites.iter() {
+        let name = site.title.as_deref().unwrap_or(&site.url).replace(['[', ']'], "");
+        markdown.push_str(&format!("- [{}]({}): {}", name, site.llms_txt, site.url));
+        if let Some(summary) = &site.summary {
+            markdown.push_str(&format!(" - {}", summary));
+        }
+        markdown.push_str(&format!(
+            " (updated {})\n",
+            site.updated_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+        ));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn test_title_and_summary() {
+        let content = "# Example Docs\n\n> Guides and API reference.\n\n## Docs\n- [A](https://example.com/a)";
+        assert_eq!(title(content).as_deref(), Some("Example Docs"));
+        assert_eq!(summary(content).as_deref(), Some("Guides and API reference."));
+        assert_eq!(title("## Only sections"), None);
+        assert_eq!(summary("# Title"), None);
+    }
+
+    #[test]
+    fn test_links() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(base_url(&headers), None);
+        assert_eq!(
+            llms_txt_link(None, "https://example.com/docs?a=1"),
+            "/api/llm_txt?url=https%3A%2F%2Fexample.com%2Fdocs%3Fa%3D1"
+        );
+        headers.insert(header::HOST, HeaderValue::from_static("index.example.org:3000"));
+        let base_url = base_url(&headers);
+        assert_eq!(base_url.as_deref(), Some("https://index.example.org:3000"));
+        assert_eq!(
+            llms_txt_link(base_url.as_deref(), "https://example.com"),
+            "https://index.example.org:3000/api/llm_txt?url=https%3A%2F%2Fexample.com"
+        );
+    }
+
+    #[test]
+    fn test_host() {
+        assert_eq!(
+            host("https://Docs.Example.com./guide").as_deref(),
+            Some("docs.example.com")
+        );
+        assert_eq!(host("http://example.com:8080").as_deref(), Some("example.com"));
+        assert_eq!(host("not a url"), None);
+    }
+
+    #[test]
+    fn test_wants_markdown() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_markdown(None, &headers));
+        assert!(wants_markdown(Some("md"), &headers));
+        headers.insert(header::ACCEPT, HeaderValue::from_static("text/markdown, */*"));
+        assert!(wants_markdown(None, &headers));
+        assert!(!wants_markdown(Some("json"), &headers));
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let directory = Directory {
+            name: "llm-web-index".to_string(),
+            description: "Generated llms.txt files of indexed websites".to_string(),
+            sites: vec![DirectoryEntry {
+                url: "https://example.com".to_string(),
+                title: Some("Example [Docs]".to_string()),
+                summary: Some("Guides.".to_string()),
+                canonical_url: None,
+                favicon_url: None,
+                prompt_version: None,
+                llms_txt: "/api/llm_txt?url=https%3A%2F%2Fexample.com".to_string(),
+                updated_at: DateTime::UNIX_EPOCH,
+            }],
+        };
+        let markdown = render_markdown(&directory);
+        assert!(markdown.starts_with("# llm-web-index\n\n> Generated llms.txt files of indexed websites\n"));
+        assert!(markdown.contains(
+            "- [Example Docs](/api/llm_txt?url=https%3A%2F%2Fexample.com): https://example.com - Guides. \
+             (updated 1970-01-01T00:00:00Z)\n"
+        ));
+    }
+
+    #[test]
+    fn test_render_index_llms_txt() {
+        let site = |url: &str, title: Option<&str>, summary: Option<&str>| DirectoryEntry {
+            url: url.to_string(),
+            title: title.map(str::to_string),
+            summary: summary.map(str::to_string),
+            canonical_url: None,
+            favicon_url: None,
+            prompt_version: None,
+            llms_txt: llms_txt_link(None, url),
+            updated_at: DateTime::UNIX_EPOCH,
+        };
+        let sites = vec![
+            site("https://example.com/blog", None, None),
+            site("https://example.com/docs", Some("Example [Docs]"), Some("Guides.")),
+            site("https://docs.rs", Some("Docs.rs"), None),
+        ];
+        let llms_txt = render_index_llms_txt(&sites);
+        assert!(llms_txt.starts_with(
+            "# llm-web-index\n\n> Generated llms.txt files of indexed websites\n\n\
+             3 websites of 2 domains are indexed. Each link is a website's llms.txt.\n"
+        ));
+        // the domains in order, each with its websites
+        let docs_rs = llms_txt.find("\n## docs.rs\n\n").unwrap();
+        let example = llms_txt.find("\n## example.com\n\n").unwrap();
+        assert!(docs_rs < example);
+        assert!(llms_txt.contains(
+            "## example.com\n\n\
+             - [https://example.com/blog](/api/llm_txt?url=https%3A%2F%2Fexample.com%2Fblog&format=raw)\n\
+             - [Example Docs](/api/llm_txt?url=https%3A%2F%2Fexample.com%2Fdocs&format=raw): Guides.\n"
+        ));
+        assert_eq!(
+            render_index_llms_txt(&[]),
+            "# llm-web-index\n\n> Generated llms.txt files of indexed websites\n\n\
+             0 websites of 0 domains are indexed. Each link is a website's llms.txt.\n"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::sync::LazyLock;
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use core_ltx::db::DbPool;
+use core_ltx::domain_policy::{DownloadGuard, private_hosts_allowed};
+use data_model_ltx::domain_policy::policy_entries;
+use data_model_ltx::models::{
+    DomainPolicyEntry, DomainPolicyError, DomainPolicyPayload, DomainPolicyResponse, PatternPayload, to_domain_policy,
+};
+use data_model_ltx::schema::domain_policy;
+
+/// `ALLOW_PRIVATE_HOSTS`: read from the environment once, on first use.
+static PRIVATE_HOSTS_ALLOWED: LazyLock<bool> = LazyLock::new(private_hosts_allowed);
+
+/// Why no job can be created for the URL, if none can: the domain policy doesn't allow its host, or (unless
+/// `ALLOW_PRIVATE_HOSTS`) the host is on a private network, so a job for it would reach into the server's network.
+///
+/// URLs that don't parse are left to fail in the worker, like before there was a policy.
+pub(crate) async fn domain_violation(
+    conn: &mut AsyncPgConnection,
+    url: &str,
+) -> Result<Option<String>, diesel::result::Error> {
+    let Ok(url) = url::Url::parse(url) else {
+        return Ok(None);
+    };
+    let guard = DownloadGuard {
+        policy: to_domain_policy(&policy_entries(conn).await?),
+        private_hosts_allowed: *PRIVATE_HOSTS_ALLOWED,
+    };
+    Ok(guard.check(&url).await.err())
+}
+
+/// What's wrong with the pattern, if anything: it has to be a host, optionally with `*` wildcards.
+fn check_pattern(pattern: &str) -> Result<(), String> {
+    let valid = !pattern.is_empty()
+        && pattern
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '*' | ':' | '[' | ']'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "'{}' isn't a host pattern: use letters, digits, '.', '-', and '*' wildcards, e.g. *.example.com",
+            pattern
+        ))
+    }
+}
+
+// GET /api/admin/domain_policy - The patterns of hosts that can, or can't, be indexed
+#[utoipa::path(
+    get,
+    path = "/api/admin/domain_policy",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every pattern of the domain policy", body = DomainPolicyResponse),
+        (status = 500, description = "The server failed", body = DomainPolicyError),
+    )
+)]
+pub async fn get_domain_policy(State(pool): State<DbPool>) -> Result<impl IntoResponse, DomainPolicyError> {
+    let mut conn = pool.get().await?;
+    let entries = policy_entries(&mut conn).await?;
+    Ok((
+        StatusCode::OK,
+        Json(DomainPolicyResponse {
+            entries,
+            private_hosts_allowed: *PRIVATE_HOSTS_ALLOWED,
+        }),
+    ))
+}
+
+// PUT /api/admin/domain_policy - Allow or block the hosts that match a pattern
+#[utoipa::path(
+    put,
+    path = "/api/admin/domain_policy",
+    tag = "admin",
+    request_body = DomainPolicyPayload,
+    responses(
+        (status = 200, description = "The pattern was set", body = DomainPolicyEntry),
+        (status = 400, description = "The pattern isn't a glob of hosts", body = DomainPolicyError),
+        (status = 500, description = "The server failed", body = DomainPolicyError),
+    )
+)]
+pub async fn put_domain_policy(
+    State(pool): State<DbPool>,
+    Json(payload): Json<DomainPolicyPayload>,
+) -> Result<impl IntoResponse, DomainPolicyError> {
+    let pattern = payload.pattern.trim().to_ascii_lowercase();
+    check_pattern(&pattern).map_err(DomainPolicyError::InvalidRequest)?;
+    let mut conn = pool.get().await?;
+
+    let entry = DomainPolicyEntry {
+        pattern,
+        rule: payload.rule.as_str().to_string(),
+        created_at: Utc::now(),
+    };
+    diesel::insert_into(domain_policy::table)
+        .values(&entry)
+        .on_conflict(domain_policy::pattern)
+        .do_update()
+        .set(domain_policy::rule.eq(&entry.rule))
+        .execute(&mut conn)
+        .await?;
+
+    tracing::info!("Domain policy: '{}' set to {}", entry.pattern, entry.rule);
+    Ok((StatusCode::OK, Json(entry)))
+}
+
+// DELETE /api/admin/domain_policy - Remove a pattern from the domain policy
+#[utoipa::path(
+    delete,
+    path = "/api/admin/domain_policy",
+    tag = "admin",
+    params(PatternPayload),
+    responses(
+        (status = 204, description = "The pattern was removed"),
+        (status = 404, description = "The domain policy has no such pattern", body = DomainPolicyError),
+        (status = 500, description = "The server failed", body = DomainPolicyError),
+    )
+)]
+pub async fn delete_domain_policy(
+    State(pool): State<DbPool>,
+    Query(payload): Query<PatternPayload>,
+) -> Result<impl IntoResponse, DomainPolicyError> {
+    let mut conn = pool.get().await?;
+
+    let pattern = payload.pattern.trim().to_ascii_lowercase();
+    let deleted = diesel::delete(domain_policy::table.find(&pattern))
+        .execute(&mut conn)
+        .await?;
+    if deleted == 0 {
+        return Err(DomainPolicyError::UnknownPattern);
+    }
+
+    tracing::info!("Domain policy: removed '{}'", pattern);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_pattern() {
+        assert!(check_pattern("*.example.com").is_ok());
+        assert!(check_pattern("docs.example-site.org").is_ok());
+        assert!(check_pattern("").is_err());
+        assert!(check_pattern("https://example.com/").is_err());
+        assert!(check_pattern("example.com/docs").is_err());
+    }
+}
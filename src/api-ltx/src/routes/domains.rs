@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::{HeaderValue, StatusCode},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use core_ltx::credentials::Credential;
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{
+    DomainCredential, DomainCredentialPayload, DomainError, DomainPayload, DomainSummary, DomainsResponse,
+};
+use data_model_ltx::schema::domains;
+use data_model_ltx::secrets::Keyring;
+
+/// What's wrong with the domain or its credential, if anything. The domain is a host name or an IPv4 address, without
+/// a port: the credential is sent to every port of the host.
+fn check_credential(domain: &str, credential: &Credential) -> Result<(), String> {
+    let valid_domain = !domain.is_empty()
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'));
+    if !valid_domain {
+        return Err(format!(
+            "'{}' isn't a host: use the host of the website's URLs (no scheme or port), e.g. docs.example.com",
+            domain
+        ));
+    }
+    let empty = match credential {
+        Credential::Basic { username, .. } => username.is_empty(),
+        Credential::Bearer { token } => token.is_empty(),
+        Credential::Cookie { cookie } => cookie.is_empty(),
+    };
+    if empty {
+        return Err(format!("the {} credential is empty", credential.kind()));
+    }
+    let (_, value) = credential.header();
+    if HeaderValue::from_str(&value).is_err() {
+        return Err(format!(
+            "the {} credential can't be sent in a header: it has control characters",
+            credential.kind()
+        ));
+    }
+    Ok(())
+}
+
+// GET /api/admin/domains - The domains that have a credential (never the credential itself)
+#[utoipa::path(
+    get,
+    path = "/api/admin/domains",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every domain that has a credential", body = DomainsResponse),
+        (status = 500, description = "The server failed", body = DomainError),
+    )
+)]
+pub async fn get_domains(State(pool): State<DbPool>) -> Result<impl IntoResponse, DomainError> {
+    let mut conn = pool.get().await?;
+    let domains = domains::table
+        .order(domains::domain.asc())
+        .select(DomainCredential::as_select())
+        .load::<DomainCredential>(&mut conn)
+        .await?;
+    Ok((
+        StatusCode::OK,
+        Json(DomainsResponse {
+            domains: domains.into_iter().map(DomainSummary::from).collect(),
+        }),
+    ))
+}
+
+// PUT /api/admin/domains - Set the credential the worker authenticates to a domain with
+#[utoipa::path(
+    put,
+    path = "/api/admin/domains",
+    tag = "admin",
+    request_body = DomainCredentialPayload,
+    responses(
+        (status = 200, description = "The credential was set", body = DomainSummary),
+        (status = 400, description = "The domain isn't a host, or the credential is empty", body = DomainError),
+        (status = 503, description = "The server has no (valid) SECRETS_KEYS", body = DomainError),
+        (status = 500, description = "The server failed", body = DomainError),
+    )
+)]
+pub async fn put_domain(
+    State(pool): State<DbPool>,
+    Json(payload): Json<DomainCredentialPayload>,
+) -> Result<impl IntoResponse, DomainError> {
+    let domain = payload.domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    check_credential(&domain, &payload.credential).map_err(DomainError::InvalidRequest)?;
+    let keyring = Keyring::from_env()?;
+    let mut conn = pool.get().await?;
+
+    let entry = DomainCredential::new(domain, &payload.credential, &keyring, Utc::now());
+    let created = diesel::insert_into(domains::table)
+        .values(&entry)
+        .on_conflict(domains::domain)
+        .do_update()
+        .set((
+            domains::credential_kind.eq(&entry.credential_kind),
+            domains::credential.eq(&entry.credential),
+            domains::updated_at.eq(entry.updated_at),
+        ))
+        .returning(DomainCredential::as_returning())
+        .get_result::<DomainCredential>(&mut conn)
+        .await?;
+
+    tracing::info!(
+        "Domains: '{}' now has a {} credential",
+        created.domain,
+        created.credential_kind
+    );
+    Ok((StatusCode::OK, Json(DomainSummary::from(created))))
+}
+
+// DELETE /api/admin/domains - Stop authenticating to a domain
+#[utoipa::path(
+    delete,
+    path = "/api/admin/domains",
+    tag = "admin",
+    params(DomainPayload),
+    responses(
+        (status = 204, description = "The credential was removed"),
+        (status = 404, description = "The domain has no credential", body = DomainError),
+        (status = 500, description = "The server failed", body = DomainError),
+    )
+)]
+pub async fn delete_domain(
+    State(pool): State<DbPool>,
+    Query(payload): Query<DomainPayload>,
+) -> Result<impl IntoResponse, DomainError> {
+    let mut conn = pool.get().await?;
+
+    let domain = payload.domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    let deleted = diesel::delete(domains::table.find(&domain)).execute(&mut conn).await?;
+    if deleted == 0 {
+        return Err(DomainError::UnknownDomain);
+    }
+
+    tracing::info!("Domains: removed the credential of '{}'", domain);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_credential() {
+        let bearer = |token: &str| Credential::Bearer {
+            token: token.to_string(),
+        };
+        assert!(check_credential("docs.internal.example", &bearer("t0k3n")).is_ok());
+        assert!(check_credential("10.0.0.7", &bearer("t0k3n")).is_ok());
+        assert!(check_credential("localhost:8080", &bearer("t0k3n")).is_err());
+        assert!(check_credential("", &bearer("t0k3n")).is_err());
+        assert!(check_credential("https://docs.example.com/", &bearer("t0k3n")).is_err());
+        assert!(check_credential("*.example.com", &bearer("t0k3n")).is_err());
+        assert!(check_credential("docs.example.com", &bearer("")).is_err());
+        assert!(check_credential("docs.example.com", &bearer("t0k3n\r\nX-Injected: 1")).is_err());
+    }
+}
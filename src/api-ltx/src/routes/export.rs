@@ -0,0 +1,405 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use flate2::{Compression, write::GzEncoder};
+use futures_util::{StreamExt, stream};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use url::Url;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{AppError, ExportRecord, LlmsTxtSource, ResultData, ResultStatus};
+use data_model_ltx::schema::llms_txt;
+
+/// Number of websites read from the database at a time.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Number of llms.txt files decompressed at the same time.
+const EXPORT_DECOMPRESSIONS: usize = 8;
+
+/// Number of decompressed llms.txt files waiting to be archived, and of archive chunks waiting to be sent: when the
+/// client reads slowly, the database is read slowly too, so memory stays bounded.
+const ENTRIES_BUFFER: usize = 64;
+const CHUNKS_BUFFER: usize = 16;
+
+/// Size of the chunks of the response body.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Name of the archive's index: a line of metadata per llms.txt, with its path (see `ExportRecord`).
+pub(crate) const INDEX_PATH: &str = "index.ndjson";
+
+/// What GET /api/export sends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// A tar.gz archive of the llms.txt files, at `<host>/<path>/llms.txt`, with their metadata in `index.ndjson`
+    #[default]
+    Tar,
+    /// Newline-delimited JSON: a line per website, with its metadata and its llms.txt
+    Ndjson,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `tar` (the default) or `ndjson`
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// A website's latest llms.txt, without its HTML.
+#[derive(Queryable)]
+struct ExportRow {
+    job_id: Uuid,
+    url: String,
+    url_normalized: String,
+    result_data: ResultData,
+    created_at: DateTime<Utc>,
+    source: LlmsTxtSource,
+    title: Option<String>,
+    description: Option<String>,
+    canonical_url: Option<String>,
+    favicon_url: Option<String>,
+    prompt_version: Option<String>,
+    model: Option<String>,
+    detail: Option<String>,
+}
+
+impl ExportRow {
+    /// With its llms.txt decompressed.
+    fn into_record(self) -> ExportRecord {
+        ExportRecord {
+            url: self.url,
+            job_id: self.job_id,
+            created_at: self.created_at,
+            source: self.source,
+            title: self.title,
+            description: self.description,
+            canonical_url: self.canonical_url,
+            favicon_url: self.favicon_url,
+            prompt_version: self.prompt_version,
+            model: self.model,
+            detail: self.detail.and_then(|detail| detail.parse().ok()),
+            llms_txt: Some(self.result_data.into_text()),
+            path: None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum ExportError {
+    #[error("Database error: {0}")]
+    Db(#[from] diesel::result::Error),
+    #[error("Decompression failed: {0}")]
+    Decompression(#[from] tokio::task::JoinError),
+    #[error("The client went away")]
+    Closed,
+}
+
+// GET /api/export - Every website's latest llms.txt and its metadata, as a streamed tar.gz archive or NDJSON
+#[utoipa::path(
+    get,
+    path = "/api/export",
+    tag = "llms.txt",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "The latest llms.txt of every website with its metadata: a tar.gz archive with the llms.txt files at <host>/<path>/llms.txt and their metadata in index.ndjson, or NDJSON with a line per website", body = Vec<u8>, content_type = ["application/gzip", "application/x-ndjson"]),
+        (status = 400, description = "The format is neither tar nor ndjson", body = String),
+        (status = 500, description = "The server failed", body = String),
+    )
+)]
+pub async fn get_export(
+    State(pool): State<DbPool>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.get().await?;
+    let (entries, entries_rx) = mpsc::channel::<Result<ExportRecord, String>>(ENTRIES_BUFFER);
+    let (chunks, chunks_rx) = mpsc::channel::<io::Result<Bytes>>(CHUNKS_BUFFER);
+
+    // The database is read, and the llms.txt files decompressed, on the runtime; the archive is compressed (or the
+    // lines serialized) on a blocking thread. The body is sent as it's made.
+    tokio::spawn(async move {
+        if let Err(e) = read_entries(&mut conn, &entries).await {
+            tracing::warn!("[SKIP] Export stopped: {}", e);
+            // the archive is left unfinished, so the client can tell it's incomplete
+            let _ = entries.send(Err(e.to_string())).await;
+        }
+    });
+    let format = query.format;
+    tokio::task::spawn_blocking(move || {
+        let out = ChunkWriter {
+            chunks: chunks.clone(),
+            buffer: Vec::with_capacity(CHUNK_BYTES),
+        };
+        let written = match format {
+            ExportFormat::Tar => write_archive(entries_rx, out),
+            ExportFormat::Ndjson => write_ndjson(entries_rx, out),
+        };
+        match written {
+            Ok(count) => tracing::trace!("Success: exported {} llms.txt files", count),
+            Err(e) => {
+                tracing::warn!("[SKIP] Export failed: {}", e);
+                let _ = chunks.blocking_send(Err(e));
+            }
+        }
+    });
+    let body = stream::unfold(chunks_rx, |mut chunks_rx| async move {
+        chunks_rx.recv().await.map(|chunk| (chunk, chunks_rx))
+    });
+
+    let (content_type, extension) = match format {
+        ExportFormat::Tar => ("application/gzip", "tar.gz"),
+        ExportFormat::Ndjson => ("application/x-ndjson", "ndjson"),
+    };
+    let filename = format!("llms-txt-export-{}.{}", Utc::now().format("%Y-%m-%d"), extension);
+    Ok((
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(body),
+    ))
+}
+
+/// Sends the latest llms.txt of every website, in normalized URL order, decompressed, with its metadata.
+///
+/// Like a backup, everything is read in one read-only, repeatable-read transaction, so the export is a consistent
+/// snapshot however long it takes. Websites are read a page at a time, after the last normalized URL of the previous
+/// page.
+async fn read_entries(
+    conn: &mut AsyncPgConnection,
+    entries: &mpsc::Sender<Result<ExportRecord, String>>,
+) -> Result<(), ExportError> {
+    conn.build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run(|conn| {
+            async move {
+                let mut after = String::new();
+                loop {
+                    let rows: Vec<ExportRow> = llms_txt::table
+                        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+                        .filter(llms_txt::url_normalized.gt(&after))
+                        .distinct_on(llms_txt::url_normalized)
+                        .order((llms_txt::url_normalized.asc(), llms_txt::created_at.desc()))
+                        .limit(EXPORT_PAGE_SIZE)
+                        .select((
+                            llms_txt::job_id,
+                            llms_txt::url,
+                            llms_txt::url_normalized,
+                            llms_txt::result_data,
+                            llms_txt::created_at,
+                            llms_txt::source,
+                            llms_txt::title,
+                            llms_txt::description,
+                            llms_txt::canonical_url,
+                            llms_txt::favicon_url,
+                            llms_txt::prompt_version,
+                            llms_txt::model,
+                            llms_txt::detail,
+                        ))
+                        .load(conn)
+                        .await?;
+                    let Some(last) = rows.last() else { break };
+                    after = last.url_normalized.clone();
+
+                    let mut decompressed = stream::iter(rows)
+                        .map(|row| tokio::task::spawn_blocking(move || row.into_record()))
+                        .buffered(EXPORT_DECOMPRESSIONS);
+                    while let Some(entry) = decompressed.next().await {
+                        entries.send(Ok(entry?)).await.map_err(|_| ExportError::Closed)?;
+                    }
+                }
+                Ok(())
+            }
+            .scope_boxed()
+        })
+        .await
+}
+
+/// Archives the llms.txt files as they come, then their metadata as `index.ndjson`, and sends the archive a chunk at
+/// a time. Returns the number of llms.txt files.
+fn write_archive(mut entries: mpsc::Receiver<Result<ExportRecord, String>>, out: ChunkWriter) -> io::Result<usize> {
+    let mut archive = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+    let mut paths = HashSet::new();
+    // only the metadata is kept until the end
+    let mut index = Vec::new();
+    while let Some(entry) = entries.blocking_recv() {
+        let mut record = entry.map_err(io::Error::other)?;
+        let content = record.llms_txt.take().unwrap_or_default();
+        let path = unique_path(archive_path(&record.url), &mut paths);
+        append_file(&mut archive, &path, content.as_bytes(), record.created_at)?;
+        record.path = Some(path);
+        serde_json::to_writer(&mut index, &record)?;
+        index.push(b'\n');
+    }
+    let count = paths.len();
+    append_file(&mut archive, INDEX_PATH, &index, Utc::now())?;
+    archive.into_inner()?.finish()?.flush()?;
+    Ok(count)
+}
+
+fn append_file<W: Write>(
+    archive: &mut tar::Builder<W>,
+    path: &str,
+    content: &[u8],
+    modified: DateTime<Utc>,
+) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(modified.timestamp().max(0) as u64);
+    archive.append_data(&mut header, path, content)
+}
+
+/// Sends the records as they come, a line of JSON each, a chunk at a time. Returns the number of records.
+fn write_ndjson(mut entries: mpsc::Receiver<Result<ExportRecord, String>>, mut out: ChunkWriter) -> io::Result<usize> {
+    let mut count = 0;
+    while let Some(entry) = entries.blocking_recv() {
+        let record = entry.map_err(io::Error::other)?;
+        serde_json::to_writer(&mut out, &record)?;
+        out.write_all(b"\n")?;
+        count += 1;
+    }
+    out.flush()?;
+    Ok(count)
+}
+
+/// Sends what's written to it as chunks of the response body.
+struct ChunkWriter {
+    chunks: mpsc::Sender<io::Result<Bytes>>,
+    buffer: Vec<u8>,
+}
+
+impl ChunkWriter {
+    fn send(&mut self) -> io::Result<()> {
+        let chunk = Bytes::from(std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_BYTES)));
+        self.chunks
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, ExportError::Closed.to_string()))
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_BYTES {
+            self.send()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.send()?;
+        }
+        Ok(())
+    }
+}
+
+/// Where the website's llms.txt is in the archive: `<host>/<path>/llms.txt`, e.g. `docs.example.com/guide/llms.txt`.
+/// Characters that aren't safe in file names are replaced with `_`.
+fn archive_path(url: &str) -> String {
+    let Ok(url) = Url::parse(url) else {
+        return format!("{}/llms.txt", file_name(url));
+    };
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}_{}", host, port),
+        (Some(host), None) => host.to_string(),
+        (None, _) => "_".to_string(),
+    };
+    let mut segments = vec![file_name(&host.to_ascii_lowercase())];
+    segments.extend(
+        url.path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty())
+            .map(file_name),
+    );
+    segments.push("llms.txt".to_string());
+    segments.join("/")
+}
+
+fn file_name(segment: &str) -> String {
+    let name: String = segment
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    match name.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => name,
+    }
+}
+
+/// The path, or, when another website already has it (e.g. `http://` and `https://` of the same one), the first of
+/// `llms-2.txt`, `llms-3.txt`, ... next to it that's free.
+fn unique_path(path: String, paths: &mut HashSet<String>) -> String {
+    if paths.insert(path.clone()) {
+        return path;
+    }
+    let stem = path.strip_suffix("llms.txt").unwrap_or(&path).to_string();
+    (2..)
+        .map(|n| format!("{}llms-{}.txt", stem, n))
+        .find(|candidate| paths.insert(candidate.clone()))
+        .expect("there's always a free path")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_path() {
+        assert_eq!(archive_path("https://example.com"), "example.com/llms.txt");
+        assert_eq!(
+            archive_path("https://Docs.Example.com/guide/"),
+            "docs.example.com/guide/llms.txt"
+        );
+        assert_eq!(
+            archive_path("http://localhost:8080/a%20b/../c?q=1"),
+            "localhost_8080/c/llms.txt"
+        );
+        assert_eq!(archive_path("https://example.com/a%20b"), "example.com/a_20b/llms.txt");
+        assert_eq!(archive_path("not a url"), "not_a_url/llms.txt");
+    }
+
+    #[test]
+    fn test_unique_path() {
+        let mut paths = HashSet::new();
+        assert_eq!(
+            unique_path("example.com/llms.txt".to_string(), &mut paths),
+            "example.com/llms.txt"
+        );
+        assert_eq!(
+            unique_path("example.com/llms.txt".to_string(), &mut paths),
+            "example.com/llms-2.txt"
+        );
+        assert_eq!(
+            unique_path("example.com/llms.txt".to_string(), &mut paths),
+            "example.com/llms-3.txt"
+        );
+        assert_eq!(
+            unique_path("other.com/llms.txt".to_string(), &mut paths),
+            "other.com/llms.txt"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{AppError, ResultData, ResultStatus};
+use data_model_ltx::schema::llms_txt;
+
+/// Number of llms.txt generations considered for the feed. Unchanged regenerations are left out,
+/// so the feed can have fewer entries.
+const FEED_SIZE: i64 = 50;
+
+/// One successful llms.txt generation.
+#[derive(Debug, Clone, Queryable)]
+struct Generation {
+    job_id: Uuid,
+    url: String,
+    url_normalized: String,
+    result_data: ResultData,
+    created_at: DateTime<Utc>,
+}
+
+/// A feed entry: a generation, and what changed since the previous one for the same website.
+#[derive(Debug)]
+struct FeedEntry {
+    generation: Generation,
+    summary: String,
+}
+
+/// GET /feed.xml - Atom feed of recently generated or updated llms.txt files
+#[utoipa::path(
+    get,
+    path = "/feed.xml",
+    tag = "discovery",
+    responses(
+        (status = 200, description = "Atom feed of the latest llms.txt files", body = String, content_type = "application/atom+xml"),
+    )
+)]
+pub async fn get_feed(State(pool): State<DbPool>) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.get().await?;
+
+    let recent: Vec<Generation> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .order(llms_txt::created_at.desc())
+        .limit(FEED_SIZE)
+        .select((
+            llms_txt::job_id,
+            llms_txt::url,
+            llms_txt::url_normalized,
+            llms_txt::result_data,
+            llms_txt::created_at,
+        ))
+        .load(&mut conn)
+        .await?;
+
+    // every generation of these websites, however their URL was spelled, newest first, to compare each entry with its
+    // predecessor
+    let keys: Vec<&str> = recent
+        .iter()
+        .map(|generation| generation.url_normalized.as_str())
+        .collect();
+    let history: Vec<Generation> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .filter(llms_txt::url_normalized.eq_any(keys))
+        .order(llms_txt::created_at.desc())
+        .select((
+            llms_txt::job_id,
+            llms_txt::url,
+            llms_txt::url_normalized,
+            llms_txt::result_data,
+            llms_txt::created_at,
+        ))
+        .load(&mut conn)
+        .await?;
+    let mut by_url: HashMap<&str, Vec<&Generation>> = HashMap::new();
+    for generation in history.iter() {
+        by_url
+            .entry(generation.url_normalized.as_str())
+            .or_default()
+            .push(generation);
+    }
+
+    let entries: Vec<FeedEntry> = recent
+        .iter()
+        .filter_map(|generation| {
+            let previous = by_url
+                .get(generation.url_normalized.as_str())
+                .and_then(|generations| generations.iter().find(|g| g.created_at < generation.created_at))
+                .map(|previous| previous.result_data.text());
+            change_summary(previous.as_deref(), &generation.result_data.text()).map(|summary| FeedEntry {
+                generation: generation.clone(),
+                summary,
+            })
+        })
+        .collect();
+
+    tracing::trace!("Success: feed has {} entries", entries.len());
+    Ok((
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")],
+        render_atom(&entries),
+    ))
+}
+
+/// Describes how `current` differs from the `previous` llms.txt of the same website.
+/// None when nothing changed: a regeneration that produced the same file isn't news.
+fn change_summary(previous: Option<&str>, current: &str) -> Option<String> {
+    let Some(previous) = previous else {
+        return Some("First llms.txt generated.".to_string());
+    };
+    if previous == current {
+        return None;
+    }
+
+    // lines are compared as multisets: moved lines don't count as changes
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for line in current.lines().filter(|line| !line.trim().is_empty()) {
+        *counts.entry(line).or_default() += 1;
+    }
+    for line in previous.lines().filter(|line| !line.trim().is_empty()) {
+        *counts.entry(line).or_default() -= 1;
+    }
+    let added: i64 = counts.values().filter(|n| **n > 0).sum();
+    let removed: i64 = -counts.values().filter(|n| **n < 0).sum::<i64>();
+
+    let plural = |n: i64| if n == 1 { "" } else { "s" };
+    Some(match (added, removed) {
+        (0, 0) => "Updated: whitespace and ordering changes.".to_string(),
+        (added, 0) => format!("Updated: {} line{} added.", added, plural(added)),
+        (0, removed) => format!("Updated: {} line{} removed.", removed, plural(removed)),
+        (added, removed) => format!("Updated: {} line{} added, {} removed.", added, plural(added), removed),
+    })
+}
+
+fn render_atom(entries: &[FeedEntry]) -> String {
+    let updated = entries
+        .iter()
+        .map(|entry| entry.generation.created_at)
+        .max()
+        .unwrap_or(DateTime::UNIX_EPOCH);
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str("  <id>urn:llm-web-index:feed</id>\n");
+    xml.push_str("  <title>llms.txt updates</title>\n");
+    xml.push_str("  <subtitle>Recently generated or updated llms.txt files</subtitle>\n");
+    xml.push_str(&format!("  <updated>{}</updated>\n", rfc3339(updated)));
+    xml.push_str("  <author><name>llm-web-index</name></author>\n");
+    for entry in entries {
+        let generation = &entry.generation;
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>urn:uuid:{}</id>\n", generation.job_id));
+        xml.push_str(&format!("    <title>{}</title>\n", escape_xml(&generation.url)));
+        xml.push_str(&format!("    <link href=\"{}\"/>\n", escape_xml(&generation.url)));
+        xml.push_str(&format!("    <updated>{}</updated>\n", rfc3339(generation.created_at)));
+        xml.push_str(&format!("    <summary>{}</summary>\n", escape_xml(&entry.summary)));
+        xml.push_str(&format!(
+            "    <content type=\"text\">{}</content>\n",
+            escape_xml(&generation.result_data.text())
+        ));
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn rfc3339(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // control characters other than tab & newlines aren't allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_summary() {
+        assert_eq!(change_summary(None, "# A").unwrap(), "First llms.txt generated.");
+        assert_eq!(change_summary(Some("# A\n- x"), "# A\n- x"), None);
+        assert_eq!(
+            change_summary(Some("# A\n- x"), "# A\n- x\n- y").unwrap(),
+            "Updated: 1 line added."
+        );
+        assert_eq!(
+            change_summary(Some("# A\n- x\n- y"), "# B\n- y").unwrap(),
+            "Updated: 1 line added, 2 removed."
+        );
+        assert_eq!(
+            change_summary(Some("- x\n- y"), "- y\n\n- x").unwrap(),
+            "Updated: whitespace and ordering changes."
+        );
+    }
+
+    #[test]
+    fn test_render_atom_escapes() {
+        let entry = FeedEntry {
+            generation: Generation {
+                job_id: Uuid::nil(),
+                url: "https://example.com/?a=1&b=2".to_string(),
+                url_normalized: "https://example.com/?a=1&b=2".to_string(),
+                result_data: "# <Example>\u{0}".into(),
+                created_at: DateTime::UNIX_EPOCH,
+            },
+            summary: "First llms.txt generated.".to_string(),
+        };
+        let xml = render_atom(&[entry]);
+        assert!(xml.contains("<link href=\"https://example.com/?a=1&amp;b=2\"/>"));
+        assert!(xml.contains("<content type=\"text\"># &lt;Example&gt;</content>"));
+        assert!(xml.contains("<updated>1970-01-01T00:00:00Z</updated>"));
+        assert!(xml.contains("<id>urn:uuid:00000000-0000-0000-0000-000000000000</id>"));
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+use std::io::Read;
+
+use axum::{
+    body::Bytes,
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::dsl::{exists, max};
+use diesel::prelude::*;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use url::Url;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::changes::notify_llms_txt_changed;
+use data_model_ltx::models::{
+    ExportRecord, ImportError, ImportResponse, JobKind, JobState, JobStatus, LlmsTxt, LlmsTxtResult, ResultStatus,
+};
+use data_model_ltx::schema::{job_state, llms_txt, llms_txt_archive};
+use data_model_ltx::search::index_llms_txt;
+use data_model_ltx::urls::normalize_url;
+
+use crate::routes::export::{ExportFormat, INDEX_PATH};
+
+/// Largest import accepted, compressed or not.
+pub const IMPORT_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+/// Most bytes read out of a tar.gz import: a small archive can decompress to a lot more.
+const IMPORT_MAX_DECOMPRESSED_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Number of imported llms.txt files indexed for search at a time.
+const INDEX_BATCH_SIZE: usize = 500;
+
+/// What's done with an imported llms.txt when its website already has one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportExisting {
+    /// It's skipped: the website keeps its llms.txt
+    #[default]
+    Skip,
+    /// It's added when it was created after the website's latest llms.txt, which becomes part of its history
+    Newer,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    /// `tar` (the default) or `ndjson`, as GET /api/export sends them
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// `skip` (the default) or `newer`
+    #[serde(default)]
+    pub existing: ImportExisting,
+}
+
+// POST /api/import - Load llms.txt files and their metadata from an export
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    tag = "llms.txt",
+    params(ImportQuery),
+    request_body(content = Vec<u8>, description = "An export of GET /api/export, in the format", content_type = ["application/gzip", "application/x-ndjson"]),
+    responses(
+        (status = 200, description = "How many llms.txt files were imported, and how many were skipped", body = ImportResponse),
+        (status = 400, description = "The body isn't an export in the format, or one of its records is invalid: nothing was imported", body = ImportError),
+        (status = 413, description = "The body is larger than 256 MiB"),
+        (status = 500, description = "The server failed: nothing was imported", body = ImportError),
+    )
+)]
+pub async fn post_import(
+    State(pool): State<DbPool>,
+    Query(query): Query<ImportQuery>,
+    body: Bytes,
+) -> Result<impl IntoResponse, ImportError> {
+    let format = query.format;
+    let records = tokio::task::spawn_blocking(move || match format {
+        ExportFormat::Tar => read_archive(&body),
+        ExportFormat::Ndjson => read_ndjson(&body),
+    })
+    .await
+    .map_err(|e| ImportError::Unknown(e.to_string()))?
+    .map_err(ImportError::InvalidRequest)?;
+
+    // All or nothing: an import that fails midway can be sent again as it is
+    let mut conn = pool.get().await?;
+    let response = conn
+        .transaction(|conn| import_records(conn, records, query.existing).scope_boxed())
+        .await?;
+
+    tracing::info!(
+        "Imported {} llms.txt file(s): skipped {} of websites that have one, and {} already imported",
+        response.imported,
+        response.skipped_existing,
+        response.skipped_duplicate
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// The records of an NDJSON export, with their llms.txt.
+fn read_ndjson(body: &[u8]) -> Result<Vec<ExportRecord>, String> {
+    let mut records = Vec::new();
+    for (index, line) in body.split(|&byte| byte == b'\n').enumerate() {
+        if line.iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let record = parse_record(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if record.llms_txt.is_none() {
+            return Err(format!("line {}: no llms_txt", index + 1));
+        }
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// The records of a tar.gz export's `index.ndjson`, with their llms.txt read from the archive.
+fn read_archive(body: &[u8]) -> Result<Vec<ExportRecord>, String> {
+    let mut archive = tar::Archive::new(GzDecoder::new(body).take(IMPORT_MAX_DECOMPRESSED_BYTES));
+    let mut files = HashMap::new();
+    let mut index = None;
+    for entry in archive.entries().map_err(|e| format!("not a tar.gz archive: {}", e))? {
+        let mut entry = entry.map_err(|e| format!("not a tar.gz archive: {}", e))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()
+            .map_err(|e| format!("invalid path in the archive: {}", e))?
+            .to_string_lossy()
+            .into_owned();
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if path == INDEX_PATH {
+            index = Some(content);
+        } else {
+            files.insert(path, content);
+        }
+    }
+    let index = index.ok_or_else(|| format!("no {} in the archive", INDEX_PATH))?;
+
+    let mut records = Vec::new();
+    for (number, line) in index.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut record = parse_record(line.as_bytes()).map_err(|e| format!("{}:{}: {}", INDEX_PATH, number + 1, e))?;
+        let path = record
+            .path
+            .take()
+            .ok_or_else(|| format!("{}:{}: no path", INDEX_PATH, number + 1))?;
+        let content = files
+            .remove(&path)
+            .ok_or_else(|| format!("{}:{}: no {} in the archive", INDEX_PATH, number + 1, path))?;
+        record.llms_txt = Some(content);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn parse_record(line: &[u8]) -> Result<ExportRecord, String> {
+    let record: ExportRecord = serde_json::from_slice(line).map_err(|e| format!("invalid record: {}", e))?;
+    match Url::parse(&record.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(record),
+        _ => Err(format!("invalid URL '{}'", record.url)),
+    }
+}
+
+/// Imports the records in order, each as a finished job and its llms.txt.
+///
+/// A record whose job is already in the database (e.g. an export of this same index) is skipped, as is, following
+/// `existing`, one whose website already has an llms.txt: including one imported by an earlier record.
+async fn import_records(
+    conn: &mut AsyncPgConnection,
+    records: Vec<ExportRecord>,
+    existing: ImportExisting,
+) -> Result<ImportResponse, ImportError> {
+    let mut response = ImportResponse::default();
+    let mut to_index = Vec::new();
+    for record in records {
+        if job_exists(conn, record.job_id).await? {
+            response.skipped_duplicate += 1;
+            continue;
+        }
+        let latest: Option<DateTime<Utc>> = llms_txt::table
+            .filter(llms_txt::url_normalized.eq(normalize_url(&record.url)))
+            .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+            .select(max(llms_txt::created_at))
+            .get_result(conn)
+            .await?;
+        let import = match (existing, latest) {
+            (_, None) => true,
+            (ImportExisting::Skip, Some(_)) => false,
+            (ImportExisting::Newer, Some(latest)) => record.created_at > latest,
+        };
+        if !import {
+            response.skipped_existing += 1;
+            continue;
+        }
+
+        let (job, llms_txt) = imported_rows(record);
+        diesel::insert_into(job_state::table).values(&job).execute(conn).await?;
+        diesel::insert_into(llms_txt::table)
+            .values(&llms_txt)
+            .execute(conn)
+            .await?;
+        response.imported += 1;
+        to_index.push(llms_txt);
+        if to_index.len() >= INDEX_BATCH_SIZE {
+            index_llms_txt(conn, &to_index).await?;
+            to_index.clear();
+        }
+    }
+    index_llms_txt(conn, &to_index).await?;
+    if response.imported > 0 {
+        notify_llms_txt_changed(conn, None).await?;
+    }
+    Ok(response)
+}
+
+/// Whether the job is in the database: as a job, an llms.txt, or an archived one.
+async fn job_exists(conn: &mut AsyncPgConnection, job_id: Uuid) -> Result<bool, diesel::result::Error> {
+    diesel::select(
+        exists(job_state::table.filter(job_state::job_id.eq(job_id)))
+            .or(exists(llms_txt::table.filter(llms_txt::job_id.eq(job_id))))
+            .or(exists(
+                llms_txt_archive::table.filter(llms_txt_archive::job_id.eq(job_id)),
+            )),
+    )
+    .get_result(conn)
+    .await
+}
+
+/// The finished job of an imported llms.txt, and the llms.txt. There's no HTML in an export: the llms.txt is stored
+/// without its page, so when it was generated, the first scheduled check of the website finds the page changed.
+fn imported_rows(record: ExportRecord) -> (JobState, LlmsTxt) {
+    let job = JobState {
+        job_id: record.job_id,
+        url: record.url.clone(),
+        status: JobStatus::Success,
+        kind: JobKind::New,
+        llms_txt: None,
+        created_at: record.created_at,
+        trace_context: None,
+        expires_at: None,
+        failure_kind: None,
+        model: record.model.clone(),
+        detail: record.detail.unwrap_or_default().to_string(),
+        section: None,
+        section_url: None,
+        crawl_max_depth: None,
+        crawl_max_pages: None,
+        attempts: 1,
+    };
+    let llms_txt = LlmsTxt {
+        created_at: record.created_at,
+        source: record.source,
+        title: record.title,
+        description: record.description,
+        canonical_url: record.canonical_url,
+        favicon_url: record.favicon_url,
+        prompt_version: record.prompt_version,
+        model: record.model,
+        detail: record.detail.map(|detail| detail.to_string()),
+        ..LlmsTxt::from_result(
+            record.job_id,
+            record.url,
+            LlmsTxtResult::Ok {
+                llms_txt: record.llms_txt.unwrap_or_default(),
+            },
+            Vec::new(),
+            String::new(),
+        )
+    };
+    (job, llms_txt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ndjson() {
+        let body = concat!(
+            r##"{"url":"https://example.com","job_id":"6f1c1b5e-5a57-4f7e-9a55-2f0d3b1a9c01","created_at":"2026-01-01T00:00:00Z","source":"Generated","llms_txt":"# Example"}"##,
+            "\n\n",
+            r##"{"url":"https://docs.example.com/guide/","job_id":"6f1c1b5e-5a57-4f7e-9a55-2f0d3b1a9c02","created_at":"2026-01-02T00:00:00Z","source":"Origin","title":"Guide","llms_txt":"# Guide"}"##,
+            "\n",
+        );
+        let records = read_ndjson(body.as_bytes()).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].llms_txt.as_deref(), Some("# Example"));
+        assert_eq!(records[1].title.as_deref(), Some("Guide"));
+
+        let no_llms_txt = r##"{"url":"https://example.com","job_id":"6f1c1b5e-5a57-4f7e-9a55-2f0d3b1a9c01","created_at":"2026-01-01T00:00:00Z","source":"Generated"}"##;
+        assert_eq!(read_ndjson(no_llms_txt.as_bytes()).unwrap_err(), "line 1: no llms_txt");
+        let invalid_url = r##"{"url":"ftp://example.com","job_id":"6f1c1b5e-5a57-4f7e-9a55-2f0d3b1a9c01","created_at":"2026-01-01T00:00:00Z","source":"Generated","llms_txt":"# Example"}"##;
+        assert_eq!(
+            read_ndjson(invalid_url.as_bytes()).unwrap_err(),
+            "line 1: invalid URL 'ftp://example.com'"
+        );
+        assert!(
+            read_ndjson(b"not json")
+                .unwrap_err()
+                .starts_with("line 1: invalid record")
+        );
+    }
+
+    fn archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast()));
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            archive.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        archive.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_read_archive() {
+        let index = r##"{"url":"https://example.com","job_id":"6f1c1b5e-5a57-4f7e-9a55-2f0d3b1a9c01","created_at":"2026-01-01T00:00:00Z","source":"Generated","path":"example.com/llms.txt"}"##;
+        let records = read_archive(&archive(&[("example.com/llms.txt", "# Example"), (INDEX_PATH, index)])).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].url, "https://example.com");
+        assert_eq!(records[0].llms_txt.as_deref(), Some("# Example"));
+        assert_eq!(records[0].path, None);
+
+        assert_eq!(
+            read_archive(&archive(&[(INDEX_PATH, index)])).unwrap_err(),
+            "index.ndjson:1: no example.com/llms.txt in the archive"
+        );
+        assert_eq!(
+            read_archive(&archive(&[("example.com/llms.txt", "# Example")])).unwrap_err(),
+            "no index.ndjson in the archive"
+        );
+        assert!(read_archive(b"not gzip").is_err());
+    }
+}
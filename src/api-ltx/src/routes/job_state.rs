@@ -3,26 +3,37 @@ use axum::{
     http::StatusCode,
     response::IntoResponse,
 };
+use chrono::Utc;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::time::Duration;
 use uuid::Uuid;
 
 use core_ltx::db::DbPool;
-use data_model_ltx::models::JobStatus;
+use core_ltx::{FailureKind, QueueThresholds, current_trace_context, estimate_wait, get_queue_thresholds};
+use data_model_ltx::models::{BatchStatusError, BatchStatusPayload, BatchStatusResponse, JobKind, JobStatus};
 use data_model_ltx::models::{
-    JobDetailsResponse, JobIdPayload, JobState, JobStatusResponse, ResultStatus, StatusError,
+    CancelJobError, JobDetailsResponse, JobIdPayload, JobIdResponse, JobLogs, JobLogsResponse, JobState,
+    JobStatusResponse, JobSummary, JobsListResponse, JobsPage, ListError, PurgeStaleJobsError, PurgeStaleJobsPayload,
+    PurgeStaleJobsResponse, QueueLoad, ResultData, ResultStatus, RetryJobError, StaleJobAction, StatusError,
 };
-use data_model_ltx::schema::{job_state, llms_txt};
+use data_model_ltx::schema::{job_logs, job_state, llms_txt, llms_txt_archive};
+use data_model_ltx::urls::normalize_url;
+
+use crate::routes::llms_txt::insert_queued_job;
 
 /// Gets all currently running jobs for a given URL.
 ///
-/// Returns all JobIds (UUID v4) of all in-progress jobs that match the `url`.
+/// Returns all JobIds (UUID v4) of all in-progress jobs that match the `url`, however it's spelled.
 /// An in-progress job is one whose status is either Queued or Running.
 ///
 /// An error is returned if there are no matching rows or if there's an internal DB error.
 pub async fn in_progress_jobs(conn: &mut AsyncPgConnection, url: &str) -> Result<Vec<Uuid>, diesel::result::Error> {
     job_state::table
-        .filter(job_state::url.eq(url))
+        .filter(job_state::url_normalized.eq(normalize_url(url)))
         // only select currently running jobs
         .filter(job_state::status.eq_any(&[JobStatus::Queued, JobStatus::Queued, JobStatus::Running]))
         .select(job_state::job_id)
@@ -30,7 +41,70 @@ pub async fn in_progress_jobs(conn: &mut AsyncPgConnection, url: &str) -> Result
         .await
 }
 
+/// When the queue counts as backed up: read from the environment once, on first use.
+pub static QUEUE_THRESHOLDS: LazyLock<QueueThresholds> = LazyLock::new(get_queue_thresholds);
+
+/// How long a job has been running for before POST /api/admin/jobs/purge_stale counts it as stale, by default.
+const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// How many jobs finished in this window tells how fast the queue is going down.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Where a new job would be in a queue of `queued` jobs, and when it would start, if that's past the thresholds.
+fn queue_load(
+    thresholds: &QueueThresholds,
+    queued: u64,
+    finished_in_window: u64,
+    window: Duration,
+) -> Option<QueueLoad> {
+    let position = queued + 1;
+    let wait = estimate_wait(position, finished_in_window, window);
+    if !thresholds.exceeded(position, wait) {
+        return None;
+    }
+    Some(QueueLoad {
+        queue_position: position,
+        estimated_start_at: wait
+            .and_then(|wait| chrono::Duration::from_std(wait).ok())
+            .map(|wait| Utc::now() + wait),
+    })
+}
+
+/// How backed up the queue is for a new job, or None while it's within `QUEUE_THRESHOLDS`.
+///
+/// The wait is estimated from the jobs that finished (successfully or not) within the last hour.
+pub async fn queue_backpressure(conn: &mut AsyncPgConnection) -> Result<Option<QueueLoad>, diesel::result::Error> {
+    let queued: i64 = job_state::table
+        .filter(job_state::status.eq(JobStatus::Queued))
+        .count()
+        .get_result(conn)
+        .await?;
+    let since = Utc::now() - chrono::Duration::from_std(THROUGHPUT_WINDOW).expect("window fits a chrono::Duration");
+    let finished: i64 = llms_txt::table
+        .filter(llms_txt::created_at.ge(since))
+        .count()
+        .get_result(conn)
+        .await?;
+    Ok(queue_load(
+        &QUEUE_THRESHOLDS,
+        queued.max(0) as u64,
+        finished.max(0) as u64,
+        THROUGHPUT_WINDOW,
+    ))
+}
+
 // GET /api/status - Get the status of a job
+#[utoipa::path(
+    get,
+    path = "/api/status",
+    tag = "jobs",
+    request_body = JobIdPayload,
+    responses(
+        (status = 200, description = "The job's status", body = JobStatusResponse),
+        (status = 404, description = "There's no job with the job_id", body = StatusError),
+        (status = 500, description = "The server failed", body = StatusError),
+    )
+)]
 pub async fn get_status(
     State(pool): State<DbPool>,
     Json(payload): Json<JobIdPayload>,
@@ -47,13 +121,89 @@ pub async fn get_status(
     Ok((
         StatusCode::OK,
         Json(JobStatusResponse {
-            status: job.status,
-            kind: job.kind,
+            status: job.status.into(),
+            kind: job.kind.into(),
         }),
     ))
 }
 
+fn check_batch_status(payload: &BatchStatusPayload) -> Result<(), String> {
+    if payload.job_ids.is_empty() {
+        return Err("no job_ids".to_string());
+    }
+    if payload.job_ids.len() > BatchStatusPayload::MAX_JOB_IDS {
+        return Err(format!(
+            "{} job_ids: at most {} can be polled at once",
+            payload.job_ids.len(),
+            BatchStatusPayload::MAX_JOB_IDS
+        ));
+    }
+    Ok(())
+}
+
+// POST /api/status/batch - Get the statuses of many jobs at once
+#[utoipa::path(
+    post,
+    path = "/api/status/batch",
+    tag = "jobs",
+    request_body = BatchStatusPayload,
+    responses(
+        (status = 200, description = "The status of each known job, and the job_ids of no job", body = BatchStatusResponse),
+        (status = 400, description = "There are no job_ids, or too many", body = BatchStatusError),
+        (status = 500, description = "The server failed", body = BatchStatusError),
+    )
+)]
+pub async fn post_status_batch(
+    State(pool): State<DbPool>,
+    Json(payload): Json<BatchStatusPayload>,
+) -> Result<impl IntoResponse, BatchStatusError> {
+    check_batch_status(&payload).map_err(BatchStatusError::InvalidRequest)?;
+    let mut job_ids = payload.job_ids;
+    job_ids.sort();
+    job_ids.dedup();
+    let mut conn = pool.get().await?;
+
+    let statuses: BTreeMap<Uuid, JobStatusResponse> = job_state::table
+        .filter(job_state::job_id.eq_any(&job_ids))
+        .select((job_state::job_id, job_state::status, job_state::kind))
+        .load::<(Uuid, JobStatus, JobKind)>(&mut conn)
+        .await?
+        .into_iter()
+        .map(|(job_id, status, kind)| {
+            (
+                job_id,
+                JobStatusResponse {
+                    status: status.into(),
+                    kind: kind.into(),
+                },
+            )
+        })
+        .collect();
+    let unknown: Vec<Uuid> = job_ids
+        .into_iter()
+        .filter(|job_id| !statuses.contains_key(job_id))
+        .collect();
+
+    tracing::trace!(
+        "Success: retrieved the statuses of {} job(s), {} unknown",
+        statuses.len(),
+        unknown.len()
+    );
+    Ok((StatusCode::OK, Json(BatchStatusResponse { statuses, unknown })))
+}
+
 // GET /api/job - Get full job details by job_id
+#[utoipa::path(
+    get,
+    path = "/api/job",
+    tag = "jobs",
+    params(JobIdPayload),
+    responses(
+        (status = 200, description = "The job, and its llms.txt or why it failed", body = JobDetailsResponse),
+        (status = 404, description = "There's no job with the job_id", body = StatusError),
+        (status = 500, description = "The server failed", body = StatusError),
+    )
+)]
 pub async fn get_job(
     State(pool): State<DbPool>,
     Query(payload): Query<JobIdPayload>,
@@ -66,33 +216,279 @@ pub async fn get_job(
         .first::<JobState>(&mut conn)
         .await?;
 
-    // If the job failed, fetch the error message from llms_txt table
+    // If the job failed, fetch the error message from llms_txt table, or its archive once it's superseded
     let error_message = if job.status == JobStatus::Failure {
-        llms_txt::table
+        let result_data = match llms_txt::table
             .filter(llms_txt::job_id.eq(&payload.job_id))
             .filter(llms_txt::result_status.eq(ResultStatus::Error))
             .select(llms_txt::result_data)
-            .first::<String>(&mut conn)
+            .first::<ResultData>(&mut conn)
             .await
-            .ok()
+        {
+            Ok(result_data) => Some(result_data),
+            Err(_) => llms_txt_archive::table
+                .filter(llms_txt_archive::job_id.eq(&payload.job_id))
+                .filter(llms_txt_archive::result_status.eq(ResultStatus::Error))
+                .select(llms_txt_archive::result_data)
+                .first::<ResultData>(&mut conn)
+                .await
+                .ok(),
+        };
+        result_data.map(ResultData::into_text)
     } else {
         None
     };
 
+    let failure_kind = job.failure_kind.as_deref().and_then(FailureKind::from_code);
+    let detail = job.detail();
     let response = JobDetailsResponse {
         job_id: job.job_id,
         url: job.url,
-        status: job.status,
-        kind: job.kind,
+        status: job.status.into(),
+        kind: job.kind.into(),
+        detail,
+        section: job.section,
         llms_txt: job.llms_txt,
         error_message,
+        failure_kind,
+        failure_message: failure_kind.map(|kind| kind.description()),
+        attempts: job.attempts.max(1) as u32,
     };
 
     tracing::trace!("Success: retrieved details for job ({})", job.job_id);
     Ok((StatusCode::OK, Json(response)))
 }
 
+// GET /api/job/logs - Get what the worker logged while working on a job
+#[utoipa::path(
+    get,
+    path = "/api/job/logs",
+    tag = "jobs",
+    params(JobIdPayload),
+    responses(
+        (status = 200, description = "What the worker logged", body = JobLogsResponse),
+        (status = 404, description = "There's no finished job with the job_id", body = StatusError),
+        (status = 500, description = "The server failed", body = StatusError),
+    )
+)]
+pub async fn get_job_logs(
+    State(pool): State<DbPool>,
+    Query(payload): Query<JobIdPayload>,
+) -> Result<impl IntoResponse, StatusError> {
+    let mut conn = pool.get().await?;
+
+    // only finished jobs have logs: a job that's queued or still running is reported as unknown
+    let logs = job_logs::table
+        .find(payload.job_id)
+        .select(JobLogs::as_select())
+        .first::<JobLogs>(&mut conn)
+        .await?;
+
+    tracing::trace!(
+        "Success: retrieved {} bytes of logs for job ({})",
+        logs.log.len(),
+        logs.job_id
+    );
+    Ok((StatusCode::OK, Json(JobLogsResponse::from(logs))))
+}
+
+// POST /api/job/cancel - Cancel a queued or running job
+#[utoipa::path(
+    post,
+    path = "/api/job/cancel",
+    tag = "jobs",
+    request_body = JobIdPayload,
+    responses(
+        (status = 200, description = "The job was cancelled", body = JobStatusResponse),
+        (status = 404, description = "There's no job with the job_id", body = CancelJobError),
+        (status = 409, description = "The job already finished", body = CancelJobError),
+        (status = 500, description = "The server failed", body = CancelJobError),
+    )
+)]
+pub async fn post_cancel_job(
+    State(pool): State<DbPool>,
+    Json(payload): Json<JobIdPayload>,
+) -> Result<impl IntoResponse, CancelJobError> {
+    let mut conn = pool.get().await?;
+
+    // a queued job is never claimed once it's cancelled, a running one is abandoned by its worker on its next poll
+    let cancelled = diesel::update(
+        job_state::table
+            .find(payload.job_id)
+            .filter(job_state::status.eq_any(&[JobStatus::Queued, JobStatus::Running])),
+    )
+    .set(job_state::status.eq(JobStatus::Cancelled))
+    .returning(JobState::as_returning())
+    .get_result::<JobState>(&mut conn)
+    .await
+    .optional()?;
+
+    let Some(job) = cancelled else {
+        let status = job_state::table
+            .find(payload.job_id)
+            .select(job_state::status)
+            .first::<JobStatus>(&mut conn)
+            .await?;
+        return Err(CancelJobError::AlreadyFinished(status));
+    };
+
+    tracing::info!("Cancelled job {} ({:?} - '{}')", job.job_id, job.kind, job.url);
+    Ok((
+        StatusCode::OK,
+        Json(JobStatusResponse {
+            status: job.status.into(),
+            kind: job.kind.into(),
+        }),
+    ))
+}
+
+// POST /api/job/retry - Queue a failed job again, as its next attempt
+#[utoipa::path(
+    post,
+    path = "/api/job/retry",
+    tag = "jobs",
+    request_body = JobIdPayload,
+    responses(
+        (status = 201, description = "The job that retries the failed one: the same URL, kind and options", body = JobIdResponse),
+        (status = 404, description = "There's no job with the job_id", body = RetryJobError),
+        (status = 409, description = "The job didn't fail, or the URL already has a queued or running job", body = RetryJobError),
+        (status = 403, description = "The domain policy doesn't allow the URL anymore", body = RetryJobError),
+        (status = 500, description = "The server failed", body = RetryJobError),
+    )
+)]
+pub async fn post_retry_job(
+    State(pool): State<DbPool>,
+    Json(payload): Json<JobIdPayload>,
+) -> Result<impl IntoResponse, RetryJobError> {
+    let mut conn = pool.get().await?;
+
+    let failed = job_state::table
+        .find(payload.job_id)
+        .select(JobState::as_select())
+        .first::<JobState>(&mut conn)
+        .await?;
+    if failed.status != JobStatus::Failure {
+        return Err(RetryJobError::NotFailed(failed.status));
+    }
+
+    // a job of its own: the failed one keeps its result and logs
+    let retry = JobState {
+        trace_context: current_trace_context(),
+        ..failed.retry(Uuid::new_v4())
+    };
+    let (url, attempts) = (retry.url.clone(), retry.attempts);
+    let response = insert_queued_job(&mut conn, retry, false).await?;
+
+    tracing::info!(
+        "Retrying job {} as {} (attempt {} - '{}')",
+        payload.job_id,
+        response.job_id,
+        attempts,
+        url
+    );
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// POST /api/admin/jobs/purge_stale - Requeue or fail the jobs that have been running for too long
+#[utoipa::path(
+    post,
+    path = "/api/admin/jobs/purge_stale",
+    tag = "admin",
+    request_body = PurgeStaleJobsPayload,
+    responses(
+        (status = 200, description = "The jobs that were requeued or failed", body = PurgeStaleJobsResponse),
+        (status = 400, description = "The threshold is 0, or longer than there's been time", body = PurgeStaleJobsError),
+        (status = 500, description = "The server failed", body = PurgeStaleJobsError),
+    )
+)]
+pub async fn post_purge_stale_jobs(
+    State(pool): State<DbPool>,
+    Json(payload): Json<PurgeStaleJobsPayload>,
+) -> Result<impl IntoResponse, PurgeStaleJobsError> {
+    let running_for = match payload.running_for_s {
+        Some(0) => {
+            return Err(PurgeStaleJobsError::InvalidRequest(
+                "running_for_s must be at least 1".to_string(),
+            ));
+        }
+        Some(seconds) => Duration::from_secs(seconds),
+        None => DEFAULT_STALE_AFTER,
+    };
+    let started_before = chrono::Duration::from_std(running_for)
+        .ok()
+        .and_then(|running_for| Utc::now().checked_sub_signed(running_for))
+        .ok_or_else(|| {
+            PurgeStaleJobsError::InvalidRequest(format!(
+                "running_for_s reaches back past the earliest time there is: {}",
+                running_for.as_secs()
+            ))
+        })?;
+    let mut conn = pool.get().await?;
+
+    // jobs that were running before their start was recorded count from their creation
+    let stale = job_state::table
+        .filter(job_state::status.eq(JobStatus::Running))
+        .filter(
+            job_state::started_at.lt(started_before).or(job_state::started_at
+                .is_null()
+                .and(job_state::created_at.lt(started_before))),
+        )
+        .select(job_state::job_id)
+        .load::<Uuid>(&mut conn)
+        .await?;
+    if stale.is_empty() {
+        return Ok((StatusCode::OK, Json(PurgeStaleJobsResponse::default())));
+    }
+
+    // still running: the jobs that finished in the meantime are left alone
+    let purged = diesel::update(
+        job_state::table
+            .filter(job_state::job_id.eq_any(&stale))
+            .filter(job_state::status.eq(JobStatus::Running)),
+    );
+    let mut job_ids = match payload.action {
+        StaleJobAction::Requeue => {
+            purged
+                .set((
+                    job_state::status.eq(JobStatus::Queued),
+                    job_state::started_at.eq(None::<chrono::DateTime<Utc>>),
+                ))
+                .returning(job_state::job_id)
+                .get_results::<Uuid>(&mut conn)
+                .await?
+        }
+        StaleJobAction::Fail => {
+            purged
+                .set((
+                    job_state::status.eq(JobStatus::Failure),
+                    job_state::failure_kind.eq(FailureKind::Internal.code()),
+                ))
+                .returning(job_state::job_id)
+                .get_results::<Uuid>(&mut conn)
+                .await?
+        }
+    };
+    job_ids.sort();
+
+    tracing::info!(
+        "Purged {} job(s) running for more than {:?} ({:?})",
+        job_ids.len(),
+        running_for,
+        payload.action
+    );
+    Ok((StatusCode::OK, Json(PurgeStaleJobsResponse { job_ids })))
+}
+
 // GET /api/jobs/in_progress - List all in-progress jobs
+#[utoipa::path(
+    get,
+    path = "/api/jobs/in_progress",
+    tag = "jobs",
+    responses(
+        (status = 200, description = "Every queued or running job", body = Vec<JobSummary>),
+        (status = 500, description = "The server failed", body = StatusError),
+    )
+)]
 pub async fn get_in_progress_jobs(State(pool): State<DbPool>) -> Result<impl IntoResponse, StatusError> {
     let span = tracing::debug_span!("/api/jobs/in_progress");
     let _span = span.enter();
@@ -106,5 +502,109 @@ pub async fn get_in_progress_jobs(State(pool): State<DbPool>) -> Result<impl Int
         .await?;
 
     tracing::trace!("Success: retrieved all {} in-progress jobs", jobs.len());
+    let jobs: Vec<JobSummary> = jobs.into_iter().map(JobSummary::from).collect();
     Ok((StatusCode::OK, Json(jobs)))
 }
+
+fn check_jobs_page(page: &JobsPage) -> Result<(), String> {
+    if page.page == 0 {
+        return Err("page starts at 1".to_string());
+    }
+    if !(1..=JobsPage::MAX_PER_PAGE).contains(&page.per_page) {
+        return Err(format!("per_page must be between 1 and {}", JobsPage::MAX_PER_PAGE));
+    }
+    if page.url.as_deref().is_some_and(|url| url.trim().is_empty()) {
+        return Err("url is empty".to_string());
+    }
+    Ok(())
+}
+
+/// The jobs GET /api/jobs lists: those of the page's status and URL.
+fn listed_jobs(page: &JobsPage) -> job_state::BoxedQuery<'static, Pg> {
+    let mut jobs = job_state::table.into_boxed();
+    if let Some(status) = page.status.status() {
+        jobs = jobs.filter(job_state::status.eq(JobStatus::from(status)));
+    }
+    if let Some(url) = &page.url {
+        jobs = jobs.filter(job_state::url_normalized.eq(normalize_url(url.trim().to_string())));
+    }
+    jobs
+}
+
+// GET /api/jobs - Every job, of any status, newest first, page by page
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    tag = "jobs",
+    params(JobsPage),
+    responses(
+        (status = 200, description = "The page's jobs of the status and URL, newest first", body = JobsListResponse),
+        (status = 400, description = "The page or the number of jobs per page is out of range, or the URL is empty", body = ListError),
+        (status = 500, description = "The server failed", body = ListError),
+    )
+)]
+pub async fn get_jobs(
+    State(pool): State<DbPool>,
+    Query(page): Query<JobsPage>,
+) -> Result<impl IntoResponse, ListError> {
+    check_jobs_page(&page).map_err(ListError::InvalidRequest)?;
+    let mut conn = pool.get().await?;
+
+    let total = listed_jobs(&page).count().get_result::<i64>(&mut conn).await?;
+    let jobs = listed_jobs(&page)
+        .order((job_state::created_at.desc(), job_state::job_id.asc()))
+        .offset(page.offset() as i64)
+        .limit(i64::from(page.per_page))
+        .select(JobState::as_select())
+        .load::<JobState>(&mut conn)
+        .await?;
+
+    tracing::trace!(
+        "Success: retrieved {} of {} {} jobs (page {})",
+        jobs.len(),
+        total,
+        page.status.as_str(),
+        page.page
+    );
+    Ok((
+        StatusCode::OK,
+        Json(JobsListResponse {
+            items: jobs.into_iter().map(JobSummary::from).collect(),
+            total: total as u64,
+            page: page.page,
+            per_page: page.per_page,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_load() {
+        let thresholds = QueueThresholds {
+            depth: 100,
+            wait: Duration::from_secs(600),
+        };
+        let hour = Duration::from_secs(3600);
+
+        // short queue, going down fast
+        assert_eq!(queue_load(&thresholds, 5, 600, hour), None);
+
+        // long queue: 99 jobs ahead, at 60 an hour
+        let load = queue_load(&thresholds, 99, 60, hour).unwrap();
+        assert_eq!(load.queue_position, 100);
+        let wait = load.estimated_start_at.unwrap() - Utc::now();
+        assert!((wait.num_minutes() - 99).abs() <= 1);
+
+        // short queue, but nothing finished lately to estimate from
+        assert_eq!(queue_load(&thresholds, 5, 0, hour), None);
+        let load = queue_load(&thresholds, 150, 0, hour).unwrap();
+        assert_eq!(load.queue_position, 151);
+        assert_eq!(load.estimated_start_at, None);
+
+        // short queue, but slow: 20 jobs ahead at 60 an hour is past the 10 minute threshold
+        assert_eq!(queue_load(&thresholds, 20, 60, hour).unwrap().queue_position, 21);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        Extension, State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::IntoResponse,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use core_ltx::{TimeUnit, get_poll_interval};
+use data_model_ltx::models::{JobState, JobStatus, JobSummary, LiveEvent, ResultStatus};
+use data_model_ltx::schema::{job_state, llms_txt};
+
+/// Environment variable with how often (in milliseconds) the database is checked for changes to push.
+pub const LIVE_POLL_INTERVAL_ENV_VAR: &str = "LIVE_POLL_INTERVAL_MS";
+
+/// Default interval between checks for changes: 1 second.
+pub const DEFAULT_LIVE_POLL_INTERVAL_MS: u64 = 1000;
+
+/// Events buffered for each client. A client that falls further behind misses the oldest ones, and is told so.
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// How far before the newest result seen results are looked up again: a worker sets `created_at` before its
+/// transaction commits, so a row can show up with an older timestamp than one seen already.
+const RESULT_OVERLAP: TimeDelta = TimeDelta::seconds(30);
+
+/// The changes pushed to the clients of GET /api/ws.
+///
+/// The database is polled by one task for all of them, started by the first client: a job whose status changed is
+/// found by comparing the queued and running jobs with the previous poll's, and a stored result by its `created_at`.
+/// While no client is connected, nothing is queried.
+#[derive(Debug, Clone)]
+pub struct LiveUpdates {
+    sender: broadcast::Sender<LiveEvent>,
+    started: Arc<AtomicBool>,
+    poll_interval: Duration,
+}
+
+impl LiveUpdates {
+    pub fn new(poll_interval: Duration) -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        LiveUpdates {
+            sender,
+            started: Arc::new(AtomicBool::new(false)),
+            poll_interval,
+        }
+    }
+
+    /// Polls every `LIVE_POLL_INTERVAL_MS` (1 second by default). Panics on an invalid value.
+    pub fn from_env() -> Self {
+        LiveUpdates::new(get_poll_interval(
+            TimeUnit::Milliseconds,
+            LIVE_POLL_INTERVAL_ENV_VAR,
+            DEFAULT_LIVE_POLL_INTERVAL_MS,
+        ))
+    }
+
+    /// The events from now on. The first subscriber starts the polling.
+    pub fn subscribe(&self, pool: &DbPool) -> broadcast::Receiver<LiveEvent> {
+        let receiver = self.sender.subscribe();
+        if !self.started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(poll_changes(pool.clone(), self.sender.clone(), self.poll_interval));
+        }
+        receiver
+    }
+}
+
+/// GET /api/ws - WebSocket of live updates: a JSON `LiveEvent` for every job queued or changing status, and every
+/// generation result stored
+#[utoipa::path(
+    get,
+    path = "/api/ws",
+    tag = "jobs",
+    responses(
+        (status = 101, description = "Switches to a WebSocket: each text message is a JSON LiveEvent", body = LiveEvent),
+    )
+)]
+pub async fn get_ws(
+    ws: WebSocketUpgrade,
+    State(pool): State<DbPool>,
+    Extension(live): Extension<LiveUpdates>,
+) -> impl IntoResponse {
+    let receiver = live.subscribe(&pool);
+    ws.on_upgrade(move |socket| forward_events(socket, receiver))
+}
+
+/// Sends the events to the client until it disconnects. Messages from the client are ignored.
+async fn forward_events(mut socket: WebSocket, mut receiver: broadcast::Receiver<LiveEvent>) {
+    loop {
+        tokio::select! {
+            event = receiver.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::debug!("WebSocket client missed {} events", missed);
+                        LiveEvent::Lagged { missed }
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::error!("Failed to serialize live event: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // pings are answered by axum
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Publishes the changes found every `poll_interval`, while anyone is subscribed.
+async fn poll_changes(pool: DbPool, sender: broadcast::Sender<LiveEvent>, poll_interval: Duration) {
+    let mut watcher: Option<ChangeWatcher> = None;
+    let mut ticker = tokio::time::interval(poll_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if sender.receiver_count() == 0 {
+            // start over from the current state when someone subscribes again
+            watcher = None;
+            continue;
+        }
+        let mut conn = match pool.get().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("Live updates: no database connection: {}", e);
+                continue;
+            }
+        };
+        let polled = match watcher.as_mut() {
+            None => ChangeWatcher::start(&mut conn).await.map(|started| {
+                watcher = Some(started);
+                Vec::new()
+            }),
+            Some(watcher) => watcher.changes(&mut conn).await,
+        };
+        match polled {
+            Ok(events) => {
+                for event in events {
+                    // no receivers left: the next tick notices
+                    let _ = sender.send(event);
+                }
+            }
+            Err(e) => tracing::warn!("Live updates: failed to check for changes: {}", e),
+        }
+    }
+}
+
+/// What the last poll saw, to tell what changed since.
+struct ChangeWatcher {
+    /// Queued and running jobs, with their status.
+    jobs: HashMap<Uuid, JobStatus>,
+    /// `created_at` of the newest result seen.
+    newest_result: DateTime<Utc>,
+    /// Results seen within `RESULT_OVERLAP` of the newest, which are looked up again.
+    recent_results: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl ChangeWatcher {
+    /// Starts from the current state: nothing that's already there is reported.
+    async fn start(conn: &mut AsyncPgConnection) -> Result<Self, diesel::result::Error> {
+        let jobs = in_progress_jobs(conn)
+            .await?
+            .into_iter()
+            .map(|job| (job.job_id, job.status))
+            .collect();
+        let mut watcher = ChangeWatcher {
+            jobs,
+            newest_result: Utc::now(),
+            recent_results: HashMap::new(),
+        };
+        for (job_id, _, _, created_at) in watcher.results_since_overlap(conn).await? {
+            watcher.see_result(job_id, created_at);
+        }
+        Ok(watcher)
+    }
+
+    /// What changed since the last poll: jobs that were queued or changed status, then results that were stored.
+    async fn changes(&mut self, conn: &mut AsyncPgConnection) -> Result<Vec<LiveEvent>, diesel::result::Error> {
+        let mut events = Vec::new();
+
+        let current = in_progress_jobs(conn).await?;
+        let mut jobs = HashMap::with_capacity(current.len());
+        for job in current {
+            jobs.insert(job.job_id, job.status);
+            if self.jobs.get(&job.job_id) != Some(&job.status) {
+                events.push(LiveEvent::Job(JobSummary::from(job)));
+            }
+        }
+        // no longer queued or running: finished (deleted ones aren't reported)
+        let finished: Vec<Uuid> = self.jobs.keys().filter(|id| !jobs.contains_key(id)).copied().collect();
+        if !finished.is_empty() {
+            let finished = job_state::table
+                .filter(job_state::job_id.eq_any(&finished))
+                .select(JobState::as_select())
+                .load::<JobState>(conn)
+                .await?;
+            events.extend(finished.into_iter().map(|job| LiveEvent::Job(JobSummary::from(job))));
+        }
+        self.jobs = jobs;
+
+        for (job_id, url, result_status, created_at) in self.results_since_overlap(conn).await? {
+            if self.see_result(job_id, created_at) {
+                events.push(LiveEvent::LlmsTxt {
+                    url,
+                    job_id,
+                    result_status: result_status.into(),
+                    created_at,
+                });
+            }
+        }
+        Ok(events)
+    }
+
+    async fn results_since_overlap(
+        &self,
+        conn: &mut AsyncPgConnection,
+    ) -> Result<Vec<(Uuid, String, ResultStatus, DateTime<Utc>)>, diesel::result::Error> {
+        llms_txt::table
+            .filter(llms_txt::created_at.gt(self.newest_result - RESULT_OVERLAP))
+            .order(llms_txt::created_at.asc())
+            .select((
+                llms_txt::job_id,
+                llms_txt::url,
+                llms_txt::result_status,
+                llms_txt::created_at,
+            ))
+            .load(conn)
+            .await
+    }
+
+    /// Records the result. False if it was seen already.
+    fn see_result(&mut self, job_id: Uuid, created_at: DateTime<Utc>) -> bool {
+        let new = self.recent_results.insert(job_id, created_at).is_none();
+        self.newest_result = self.newest_result.max(created_at);
+        let oldest = self.newest_result - RESULT_OVERLAP;
+        self.recent_results.retain(|_, seen| *seen > oldest);
+        new
+    }
+}
+
+async fn in_progress_jobs(conn: &mut AsyncPgConnection) -> Result<Vec<JobState>, diesel::result::Error> {
+    job_state::table
+        .filter(job_state::status.eq_any(&[JobStatus::Queued, JobStatus::Running]))
+        .select(JobState::as_select())
+        .load::<JobState>(conn)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_see_result() {
+        let now = Utc::now();
+        let mut watcher = ChangeWatcher {
+            jobs: HashMap::new(),
+            newest_result: now,
+            recent_results: HashMap::new(),
+        };
+        let (first, late) = (Uuid::new_v4(), Uuid::new_v4());
+        assert!(watcher.see_result(first, now + TimeDelta::seconds(5)));
+        assert!(!watcher.see_result(first, now + TimeDelta::seconds(5)));
+        // committed after a newer one, but still within the overlap
+        assert!(watcher.see_result(late, now + TimeDelta::seconds(1)));
+        assert_eq!(watcher.newest_result, now + TimeDelta::seconds(5));
+
+        // a minute later, both are forgotten
+        watcher.see_result(Uuid::new_v4(), now + TimeDelta::seconds(65));
+        assert!(!watcher.recent_results.contains_key(&first));
+        assert!(!watcher.recent_results.contains_key(&late));
+    }
+}
@@ -1,34 +1,54 @@
 use axum::{
+    Extension,
     extract::{Json, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
+use chrono::{DateTime, Utc};
+use diesel::dsl::sql;
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::sql_types::{Bool, Text};
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use utoipa::IntoParams;
+use uuid::Uuid;
 
 use core_ltx::db::DbPool;
+use core_ltx::llms::find_model;
+use core_ltx::section::section_names;
+use core_ltx::{canonical_url, current_trace_context, is_valid_markdown, is_valid_url, validate_is_llm_txt};
+use data_model_ltx::changes::notify_llms_txt_changed;
 use data_model_ltx::models::{
-    AppError, GetLlmTxtError, JobIdResponse, JobKindData, JobState, JobStatus, LlmTxtResponse, LlmsTxt,
-    LlmsTxtListItem, LlmsTxtListResponse, PostLlmTxtError, PutLlmTxtError, ResultStatus, UpdateLlmTxtError, UrlPayload,
+    BatchJobRequestPayload, BatchJobResponse, BatchJobResult, BatchLlmTxtError, Crawl, DeleteLlmTxtError,
+    DeleteLlmTxtResponse, GetLlmTxtError, JobIdResponse, JobKindData, JobRequestPayload, JobState, JobStatus,
+    ListError, ListPage, ListSort, LlmTxtResponse, LlmsTxt, LlmsTxtHistoryResponse, LlmsTxtListItem,
+    LlmsTxtListResponse, LlmsTxtPreviewRow, LlmsTxtVersion, PostLlmTxtError, PutLlmTxtError, QueueLoad, ResultData,
+    ResultStatus, RetryJobError, UpdateLlmTxtError, UrlPayload,
 };
-use data_model_ltx::schema::{job_state, llms_txt};
+use data_model_ltx::schema::{crawl_schedule, job_state, llms_txt, llms_txt_archive};
+use data_model_ltx::urls::normalize_url;
 
-use crate::routes::job_state::in_progress_jobs;
+use crate::mcp::escape_like;
+use crate::routes::domain_policy::domain_violation;
+use crate::routes::job_state::{in_progress_jobs, queue_backpressure};
+use crate::routes::read_cache::{CachedLlmsTxt, ReadCache};
 
 /// Gets the most recent llm.txt entry for the website, if available.
 ///
 /// Only returns an Ok result if:
 ///   - There's at least one row with a result of OK
-///   - There's at least one row that has the url
+///   - There's at least one row that has the url, however it's spelled (see `data_model_ltx::urls`)
 ///
 /// If there are multiple, the most recent one (using `created_at`) is returned.
 ///
 /// An Error is returned if there are either no matching rows or if there's an internal DB error.
 pub async fn fetch_llms_txt(conn: &mut AsyncPgConnection, url: &str) -> Result<LlmsTxt, diesel::result::Error> {
     llms_txt::table
-        .filter(llms_txt::url.eq(url))
+        .filter(llms_txt::url_normalized.eq(normalize_url(url)))
         .filter(llms_txt::result_status.eq(ResultStatus::Ok))
         .order(llms_txt::created_at.desc())
         .select(LlmsTxt::as_select())
@@ -36,149 +56,773 @@ pub async fn fetch_llms_txt(conn: &mut AsyncPgConnection, url: &str) -> Result<L
         .await
 }
 
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LlmTxtFormatQuery {
+    /// `json`, or `raw` for the llms.txt file itself. Overrides the `Accept` header.
+    pub format: Option<String>,
+}
+
 /// GET /api/llm_txt - Retrieve llms.txt content for a URL
+///
+/// JSON by default. The llms.txt file itself, as `text/plain`, when asked for with `?format=raw` or an `Accept`
+/// header with `text/plain` or `text/markdown`. Errors are JSON either way.
+///
+/// The `ETag` is the job that generated the llms.txt: `304 Not Modified` when the `If-None-Match` header has it.
+#[utoipa::path(
+    get,
+    path = "/api/llm_txt",
+    tag = "llms.txt",
+    params(UrlPayload, LlmTxtFormatQuery),
+    responses(
+        (status = 200, description = "The URL's latest llms.txt, in JSON or as is", body = LlmTxtResponse, content_type = ["application/json", "text/plain"]),
+        (status = 304, description = "The latest llms.txt is the one of the If-None-Match ETag"),
+        (status = 404, description = "No llms.txt was generated for the URL yet", body = GetLlmTxtError),
+        (status = 500, description = "The latest generation failed, or the server failed", body = GetLlmTxtError),
+    )
+)]
 pub async fn get_llm_txt(
+    State(pool): State<DbPool>,
+    Extension(cache): Extension<ReadCache>,
+    Query(payload): Query<UrlPayload>,
+    Query(query): Query<LlmTxtFormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, GetLlmTxtError> {
+    let generation = cache.generation(&pool);
+    let cached = match generation {
+        Some(_) => cache.llms_txt(&payload.url).await,
+        None => None,
+    };
+    let llms_txt = match cached {
+        Some(llms_txt) => llms_txt,
+        None => {
+            let mut conn = pool.get().await?;
+            // like `fetch_llms_txt`, with the key it's cached by
+            let (llms_txt_record, key) = llms_txt::table
+                .filter(llms_txt::url_normalized.eq(normalize_url(&payload.url)))
+                .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+                .order(llms_txt::created_at.desc())
+                .select((LlmsTxt::as_select(), llms_txt::url_normalized))
+                .first::<(LlmsTxt, String)>(&mut conn)
+                .await?;
+            if llms_txt_record.result_status == ResultStatus::Error {
+                tracing::trace!("Error: failed generation record for '{}'", payload.url);
+                return Err(GetLlmTxtError::GenerationFailure(
+                    llms_txt_record.result_data.into_text(),
+                ));
+            }
+            let llms_txt = Arc::new(CachedLlmsTxt {
+                job_id: llms_txt_record.job_id,
+                content: llms_txt_record.result_data.into_text(),
+            });
+            if let Some(generation) = generation {
+                cache
+                    .insert_llms_txt(generation, payload.url.clone(), key, llms_txt.clone())
+                    .await;
+            }
+            llms_txt
+        }
+    };
+
+    // the same URL answers both ways: caches have to tell them apart, by `Vary`
+    let etag = llms_txt.etag();
+    if etag_matches(&headers, &etag) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag), (header::VARY, "accept".to_string())],
+        )
+            .into_response());
+    }
+    if wants_raw(query.format.as_deref(), &headers) {
+        Ok((
+            StatusCode::OK,
+            [
+                (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+                (header::ETAG, etag),
+                (header::VARY, "accept".to_string()),
+            ],
+            llms_txt.content.clone(),
+        )
+            .into_response())
+    } else {
+        Ok((
+            StatusCode::OK,
+            [(header::ETAG, etag), (header::VARY, "accept".to_string())],
+            Json(LlmTxtResponse {
+                content: llms_txt.content.clone(),
+            }),
+        )
+            .into_response())
+    }
+}
+
+/// Whether the client's `If-None-Match` header has the `ETag`: it already has this llms.txt.
+fn etag_matches(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn wants_raw(format: Option<&str>, headers: &HeaderMap) -> bool {
+    match format.map(|f| f.trim().to_ascii_lowercase()) {
+        Some(format) => format == "raw",
+        None => headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("text/plain") || accept.contains("text/markdown")),
+    }
+}
+
+/// A generation of GET /api/llm_txt/history: its job, when, how it went, and how it was generated.
+type VersionRow = (
+    Uuid,
+    DateTime<Utc>,
+    ResultStatus,
+    ResultData,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+/// GET /api/llm_txt/history - Every generation of a URL's llms.txt, successful or not, newest first
+///
+/// Only what each generation was and how it went: its content is in GET /api/job, by `job_id`.
+#[utoipa::path(
+    get,
+    path = "/api/llm_txt/history",
+    tag = "llms.txt",
+    params(UrlPayload),
+    responses(
+        (status = 200, description = "Every generation of the URL, newest first", body = LlmsTxtHistoryResponse),
+        (status = 404, description = "The URL has no generation", body = GetLlmTxtError),
+        (status = 500, description = "The server failed", body = GetLlmTxtError),
+    )
+)]
+pub async fn get_llm_txt_history(
     State(pool): State<DbPool>,
     Query(payload): Query<UrlPayload>,
 ) -> Result<impl IntoResponse, GetLlmTxtError> {
     let mut conn = pool.get().await?;
 
-    match fetch_llms_txt(&mut conn, &payload.url).await {
-        Ok(llms_txt_record) => match llms_txt_record.result_status {
-            ResultStatus::Ok => Ok((
+    // Not LlmsTxt::as_select(): every version's compressed HTML isn't needed
+    let mut rows = llms_txt::table
+        .filter(llms_txt::url_normalized.eq(normalize_url(&payload.url)))
+        .order(llms_txt::created_at.desc())
+        .select((
+            llms_txt::job_id,
+            llms_txt::created_at,
+            llms_txt::result_status,
+            llms_txt::result_data,
+            llms_txt::prompt_version,
+            llms_txt::model,
+            llms_txt::detail,
+        ))
+        .load::<VersionRow>(&mut conn)
+        .await?;
+    // and the superseded ones that were archived (see `data_model_ltx::archive`)
+    let archived = llms_txt_archive::table
+        .filter(llms_txt_archive::url_normalized.eq(normalize_url(&payload.url)))
+        .order(llms_txt_archive::created_at.desc())
+        .select((
+            llms_txt_archive::job_id,
+            llms_txt_archive::created_at,
+            llms_txt_archive::result_status,
+            llms_txt_archive::result_data,
+            llms_txt_archive::prompt_version,
+            llms_txt_archive::model,
+            llms_txt_archive::detail,
+        ))
+        .load::<VersionRow>(&mut conn)
+        .await?;
+    rows.extend(archived);
+    rows.sort_by(|a, b| b.1.cmp(&a.1));
+    if rows.is_empty() {
+        return Err(GetLlmTxtError::NotGenerated);
+    }
+
+    let versions: Vec<LlmsTxtVersion> = rows
+        .into_iter()
+        .map(
+            |(job_id, created_at, result_status, result_data, prompt_version, model, detail)| LlmsTxtVersion {
+                job_id,
+                created_at,
+                result_status: result_status.into(),
+                error: (result_status == ResultStatus::Error).then(|| result_data.into_text()),
+                prompt_version,
+                model,
+                detail: detail.and_then(|detail| detail.parse().ok()),
+            },
+        )
+        .collect();
+    tracing::trace!("Success: {} generations of '{}'", versions.len(), payload.url);
+    Ok((
+        StatusCode::OK,
+        Json(LlmsTxtHistoryResponse {
+            url: payload.url,
+            versions,
+        }),
+    ))
+}
+
+/// DELETE /api/llm_txt - Remove a website: every llms.txt and job of the URL, and its crawl schedule
+///
+/// Not while a job for it is queued or running, since the job would store its result afterwards.
+#[utoipa::path(
+    delete,
+    path = "/api/llm_txt",
+    tag = "llms.txt",
+    params(UrlPayload),
+    responses(
+        (status = 200, description = "What was removed", body = DeleteLlmTxtResponse),
+        (status = 404, description = "There's no llms.txt, nor any job, for the URL", body = DeleteLlmTxtError),
+        (status = 409, description = "A job for the URL is queued or running", body = DeleteLlmTxtError),
+        (status = 500, description = "The server failed", body = DeleteLlmTxtError),
+    )
+)]
+pub async fn delete_llm_txt(
+    State(pool): State<DbPool>,
+    Query(payload): Query<UrlPayload>,
+) -> Result<impl IntoResponse, DeleteLlmTxtError> {
+    let mut conn = pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            let in_progress = in_progress_jobs(conn, &payload.url).await?;
+            if !in_progress.is_empty() {
+                return Err(DeleteLlmTxtError::JobsInProgress(in_progress));
+            }
+
+            let deleted_llms_txt =
+                diesel::delete(llms_txt::table.filter(llms_txt::url_normalized.eq(normalize_url(&payload.url))))
+                    .execute(conn)
+                    .await?;
+            let deleted_archived = diesel::delete(
+                llms_txt_archive::table.filter(llms_txt_archive::url_normalized.eq(normalize_url(&payload.url))),
+            )
+            .execute(conn)
+            .await?;
+            let deleted_llms_txt = deleted_llms_txt + deleted_archived;
+            // their logs and token usage go with them (ON DELETE CASCADE)
+            let deleted_jobs =
+                diesel::delete(job_state::table.filter(job_state::url_normalized.eq(normalize_url(&payload.url))))
+                    .execute(conn)
+                    .await?;
+            let deleted_crawl_schedule = diesel::delete(
+                crawl_schedule::table.filter(normalize_url(crawl_schedule::url).eq(normalize_url(&payload.url))),
+            )
+            .execute(conn)
+            .await?;
+            if deleted_llms_txt == 0 && deleted_jobs == 0 && deleted_crawl_schedule == 0 {
+                return Err(DeleteLlmTxtError::NotGenerated);
+            }
+            notify_llms_txt_changed(conn, Some(&payload.url)).await?;
+
+            tracing::info!(
+                "Deleted '{}': {} llms.txt record(s) and {} job(s)",
+                payload.url,
+                deleted_llms_txt,
+                deleted_jobs
+            );
+            Ok((
                 StatusCode::OK,
-                Json(LlmTxtResponse {
-                    content: llms_txt_record.result_data,
+                Json(DeleteLlmTxtResponse {
+                    url: payload.url.clone(),
+                    deleted_llms_txt: deleted_llms_txt as u64,
+                    deleted_jobs: deleted_jobs as u64,
+                    deleted_crawl_schedule: deleted_crawl_schedule > 0,
                 }),
+            ))
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Why a job couldn't be created.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum NewJobError {
+    /// There's already a queued or running job for the URL.
+    #[error("jobs are already in progress: {0:?}")]
+    JobsInProgress(Vec<Uuid>),
+    /// The queue is backed up, and the job was low priority.
+    #[error("the queue is backed up: a new job would be number {}", .0.queue_position)]
+    QueueBusy(QueueLoad),
+    /// The URL's host isn't allowed by the domain policy, or is on a private network.
+    #[error("the domain isn't allowed: {0}")]
+    DomainNotAllowed(String),
+    #[error("Database error: {0}")]
+    Db(#[from] diesel::result::Error),
+}
+
+impl From<NewJobError> for PostLlmTxtError {
+    fn from(e: NewJobError) -> Self {
+        match e {
+            NewJobError::JobsInProgress(job_ids) => PostLlmTxtError::JobsInProgress(job_ids),
+            NewJobError::QueueBusy(load) => PostLlmTxtError::QueueBusy(load),
+            NewJobError::DomainNotAllowed(reason) => PostLlmTxtError::DomainNotAllowed(reason),
+            NewJobError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<NewJobError> for PutLlmTxtError {
+    fn from(e: NewJobError) -> Self {
+        match e {
+            NewJobError::JobsInProgress(job_ids) => PutLlmTxtError::JobsInProgress(job_ids),
+            NewJobError::QueueBusy(load) => PutLlmTxtError::QueueBusy(load),
+            NewJobError::DomainNotAllowed(reason) => PutLlmTxtError::DomainNotAllowed(reason),
+            NewJobError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<NewJobError> for RetryJobError {
+    fn from(e: NewJobError) -> Self {
+        match e {
+            NewJobError::JobsInProgress(job_ids) => RetryJobError::JobsInProgress(job_ids),
+            // retries aren't low priority: they're always queued
+            NewJobError::QueueBusy(load) => RetryJobError::Unknown(format!(
+                "the queue is backed up: the job would be number {}",
+                load.queue_position
             )),
-            ResultStatus::Error => {
-                tracing::trace!("Error: failed generation record for '{}'", payload.url);
-                Err(GetLlmTxtError::GenerationFailure(llms_txt_record.result_data))
-            }
-        },
-        Err(e) => Err(e.into()),
+            NewJobError::DomainNotAllowed(reason) => RetryJobError::DomainNotAllowed(reason),
+            NewJobError::Db(e) => e.into(),
+        }
+    }
+}
+
+impl From<NewJobError> for UpdateLlmTxtError {
+    fn from(e: NewJobError) -> Self {
+        match e {
+            NewJobError::JobsInProgress(job_ids) => UpdateLlmTxtError::JobsInProgress(job_ids),
+            NewJobError::QueueBusy(load) => UpdateLlmTxtError::QueueBusy(load),
+            NewJobError::DomainNotAllowed(reason) => UpdateLlmTxtError::DomainNotAllowed(reason),
+            NewJobError::Db(e) => e.into(),
+        }
     }
 }
 
-/// Create a request to generate a new llms.txt
-async fn new_llms_txt_generate_job(
+/// Inserts a queued job, unless the URL already has a queued or running one.
+///
+/// Checking `in_progress_jobs` first isn't enough: two concurrent requests can both see none and both insert.
+/// The partial unique index `job_state_one_in_progress_per_url` (of the normalized URL, so two spellings of a URL
+/// can't both have one either) makes the second insert a no-op instead,
+/// and then the job that won is reported.
+///
+/// When the queue is backed up (see `queue_backpressure`), the job's queue position and estimated start are reported,
+/// and a `low_priority` job isn't inserted at all.
+///
+/// Every job goes through here, so this is where the domain policy is enforced (see `domain_violation`).
+pub(crate) async fn insert_queued_job(
     conn: &mut AsyncPgConnection,
-    url: &str,
-) -> Result<JobIdResponse, diesel::result::Error> {
-    let job_id = uuid::Uuid::new_v4();
-    let new_job = JobState::from_kind_data(job_id, url.to_string(), JobStatus::Queued, JobKindData::New);
+    new_job: JobState,
+    low_priority: bool,
+) -> Result<JobIdResponse, NewJobError> {
+    if let Some(reason) = domain_violation(conn, &new_job.url).await? {
+        tracing::trace!("Error: not creating a job for '{}': {}", new_job.url, reason);
+        return Err(NewJobError::DomainNotAllowed(reason));
+    }
+
+    let backpressure = queue_backpressure(conn).await?;
+    if low_priority && let Some(load) = backpressure {
+        tracing::trace!(
+            "Error: queue is backed up, turning away low-priority job for '{}' (it would be number {})",
+            new_job.url,
+            load.queue_position
+        );
+        return Err(NewJobError::QueueBusy(load));
+    }
 
-    diesel::insert_into(job_state::table)
+    let inserted = diesel::insert_into(job_state::table)
         .values(&new_job)
+        .on_conflict_do_nothing()
         .execute(conn)
         .await?;
+    if inserted == 0 {
+        let existing_jobs = in_progress_jobs(conn, &new_job.url).await?;
+        tracing::trace!(
+            "Error: '{}' already has an in-progress job: {:?}",
+            new_job.url,
+            existing_jobs
+        );
+        return Err(NewJobError::JobsInProgress(existing_jobs));
+    }
+    Ok(JobIdResponse {
+        job_id: new_job.job_id,
+        queue_position: backpressure.as_ref().map(|load| load.queue_position),
+        estimated_start_at: backpressure.and_then(|load| load.estimated_start_at),
+    })
+}
+
+/// Unless it's one of the supported models, the requested model as an error.
+fn check_model(model: Option<&str>) -> Result<(), String> {
+    match model {
+        Some(model) if find_model(model).is_none() => {
+            tracing::trace!("Error: unknown model '{}' requested", model);
+            Err(model.to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Most links a crawl job follows away from its URL: deeper crawls are cut short.
+pub(crate) const MAX_CRAWL_DEPTH: u32 = 5;
+/// Most pages a crawl job downloads: larger crawls are cut short.
+pub(crate) const MAX_CRAWL_PAGES: u32 = 500;
+
+/// The requested crawl, within the limits this server crawls to.
+fn bounded_crawl(crawl: Option<Crawl>) -> Option<Crawl> {
+    crawl.map(|crawl| Crawl {
+        max_depth: crawl.max_depth.min(MAX_CRAWL_DEPTH),
+        max_pages: crawl.max_pages.clamp(1, MAX_CRAWL_PAGES),
+    })
+}
+
+/// Create a request to generate a new llms.txt for the payload's URL, at the requested detail level, optionally with
+/// a deadline for starting it, a model to use, and a crawl of the website. A `low_priority` request isn't queued when
+/// the queue is backed up.
+pub(crate) async fn new_llms_txt_generate_job(
+    conn: &mut AsyncPgConnection,
+    payload: &JobRequestPayload,
+) -> Result<JobIdResponse, NewJobError> {
+    let job_id = uuid::Uuid::new_v4();
+    let new_job = JobState {
+        trace_context: current_trace_context(),
+        expires_at: payload.expires_at,
+        model: payload.model.clone(),
+        detail: payload.detail.unwrap_or_default().to_string(),
+        ..JobState::from_kind_data(job_id, payload.url.clone(), JobStatus::Queued, JobKindData::New)
+    }
+    .with_crawl(bounded_crawl(payload.crawl));
 
-    Ok(JobIdResponse { job_id })
+    insert_queued_job(conn, new_job, payload.low_priority).await
+}
+
+/// Regenerate only one H2 section of an llms.txt, from the page at `url`.
+pub(crate) struct SectionUpdate {
+    pub(crate) name: String,
+    pub(crate) url: String,
+}
+
+/// The section of the existing llms.txt the request asks to regenerate on its own, if any.
+///
+/// It has to be one of the llms.txt's H2 sections (the names are matched case-insensitively), and the page to
+/// regenerate it from has to be on the same website. Otherwise, what's wrong with the request as an error.
+fn check_section(payload: &JobRequestPayload, existing: &LlmsTxt) -> Result<Option<SectionUpdate>, String> {
+    let Some(requested) = payload.section.as_deref() else {
+        return match payload.section_url {
+            Some(_) => Err("section_url can only be given with a section".to_string()),
+            None => Ok(None),
+        };
+    };
+    if payload.crawl.is_some() {
+        return Err("a section is regenerated from a single page: it can't be combined with a crawl".to_string());
+    }
+
+    let llms_txt = is_valid_markdown(&existing.result_data.text())
+        .and_then(validate_is_llm_txt)
+        .map_err(|e| format!("the llms.txt of {} can't be split into sections: {}", existing.url, e))?;
+    let name = section_names(&llms_txt)
+        .into_iter()
+        .find(|name| name.eq_ignore_ascii_case(requested.trim()))
+        .ok_or_else(|| format!("the llms.txt of {} has no '{}' section", existing.url, requested))?;
+
+    let url = match payload.section_url.as_deref() {
+        None => existing.url.clone(),
+        Some(section_url) => {
+            let section_host = is_valid_url(section_url)
+                .map_err(|e| format!("section_url: {}", e))?
+                .host_str()
+                .map(str::to_string);
+            let host = is_valid_url(&existing.url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string));
+            if section_host != host {
+                return Err(format!(
+                    "section_url {} isn't on the website of {}",
+                    section_url, existing.url
+                ));
+            }
+            section_url.to_string()
+        }
+    };
+    Ok(Some(SectionUpdate { name, url }))
+}
+
+/// The request with its URL spelled the one way it's indexed under (see `core_ltx::canonical_url`), so that spellings
+/// of a URL don't make duplicate entries. As an error, why the URL isn't valid.
+fn with_canonical_url(payload: JobRequestPayload) -> Result<JobRequestPayload, String> {
+    match canonical_url(&payload.url) {
+        Ok(url) => Ok(JobRequestPayload {
+            url: url.to_string(),
+            ..payload
+        }),
+        Err(e) => {
+            tracing::trace!("Error: invalid URL '{}': {}", payload.url, e);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Sections can only be regenerated in an existing llms.txt: as an error, when a request for a new one asks for one.
+fn reject_section(payload: &JobRequestPayload) -> Result<(), String> {
+    match (&payload.section, &payload.section_url) {
+        (None, None) => Ok(()),
+        _ => Err(format!(
+            "{} has no llms.txt yet: only the sections of an existing one can be regenerated",
+            payload.url
+        )),
+    }
 }
 
 /// POST /api/llm_txt - Create a new job to generate llms.txt
+#[utoipa::path(
+    post,
+    path = "/api/llm_txt",
+    tag = "llms.txt",
+    request_body = JobRequestPayload,
+    responses(
+        (status = 201, description = "The job was queued", body = JobIdResponse),
+        (status = 400, description = "The URL, the model, or the section is invalid", body = PostLlmTxtError),
+        (status = 403, description = "The domain policy doesn't allow indexing the URL", body = PostLlmTxtError),
+        (status = 409, description = "The URL has an llms.txt already, or a job for it is queued or running", body = PostLlmTxtError),
+        (status = 429, description = "Too many requests from the client: retry after Retry-After seconds"),
+        (status = 503, description = "The queue is backed up and the job was low priority", body = PostLlmTxtError),
+        (status = 500, description = "The server failed", body = PostLlmTxtError),
+    )
+)]
 pub async fn post_llm_txt(
     State(pool): State<DbPool>,
-    Json(payload): Json<UrlPayload>,
+    Json(payload): Json<JobRequestPayload>,
 ) -> Result<impl IntoResponse, PostLlmTxtError> {
+    let payload = with_canonical_url(payload).map_err(PostLlmTxtError::InvalidUrl)?;
+    check_model(payload.model.as_deref()).map_err(PostLlmTxtError::UnknownModel)?;
+    reject_section(&payload).map_err(PostLlmTxtError::InvalidSection)?;
     let mut conn = pool.get().await?;
     conn.transaction(|conn| {
         async move {
-            match fetch_llms_txt(conn, &payload.url).await {
-                Ok(prior) => {
-                  match prior.result_status {
-                      ResultStatus::Ok => {
-                        tracing::trace!(
-                          "Error: '{}' cannot POST llms_txt because it already exists (job ID: {})",
-                          payload.url,
-                          prior.job_id,
-                        );
-                        Err(PostLlmTxtError::AlreadyGenerated)
-                      },
-                      ResultStatus::Error => {
-                        tracing::trace!("Success: '{}' had a failed POST before (job ID: {}). Re-creating.",
-                          payload.url,
-                          prior.job_id,
-                        );
-                        let job_id_response = new_llms_txt_generate_job(conn, &payload.url).await?;
-                        Ok((StatusCode::CREATED, Json(job_id_response)))
-                      }
-                  }
-                },
-                Err(e) => match e {
-                    diesel::result::Error::NotFound => match in_progress_jobs(conn, &payload.url).await {
-                        Ok(existing_jobs) => {
-                            if existing_jobs.is_empty() {
-                                tracing::trace!("Success: '{}' creating for the first time.", payload.url);
-                                let job_id_response = new_llms_txt_generate_job(conn, &payload.url).await?;
-                                Ok((StatusCode::CREATED, Json(job_id_response)))
-                            } else {
-                                tracing::trace!("Error: '{}' already has existing in-progress jobs: {:?}", payload.url, existing_jobs,);
-                                Err(PostLlmTxtError::JobsInProgress(existing_jobs))
-                            }
-                        }
-
-                        Err(e_jobs) => match e_jobs {
-                            diesel::result::Error::NotFound => {
-                                tracing::trace!("Success: '{}' creating for the first time.", payload.url);
-                                let job_id_response = new_llms_txt_generate_job(conn, &payload.url).await?;
-                                Ok((StatusCode::CREATED, Json(job_id_response)))
-                            }
-                            _ => {
-                              tracing::trace!("Error: unknown DB error encountered checking in-progress jobs for '{}': {}", payload.url,e_jobs);
-                              Err(e_jobs.into())
-                            },
-                        },
-                    },
-                    _ => {
-                      tracing::trace!("Error: unknown DB error encountered while checking for prior llms_txt records for '{}': {}", payload.url, e);
-                      Err(e.into())
-                    },
-                },
-            }
+            let job_id_response = create_new_job(conn, &payload).await?;
+            Ok((StatusCode::CREATED, Json(job_id_response)))
         }
         .scope_boxed()
     })
     .await
 }
 
-/// Create a request to update an existing llms.txt
-async fn update_llms_txt_generation(
+/// Creates the job of POST /api/llm_txt: for a URL without an llms.txt, or whose last generation failed, and that
+/// doesn't have a job queued or running already.
+async fn create_new_job(
     conn: &mut AsyncPgConnection,
-    url: &str,
-    llms_txt: &str,
-) -> Result<JobIdResponse, diesel::result::Error> {
-    let job_id = uuid::Uuid::new_v4();
-    let new_job = JobState::from_kind_data(
-        job_id,
-        url.to_string(),
-        JobStatus::Queued,
-        JobKindData::Update {
-            llms_txt: llms_txt.to_string(),
+    payload: &JobRequestPayload,
+) -> Result<JobIdResponse, PostLlmTxtError> {
+    match fetch_llms_txt(conn, &payload.url).await {
+        Ok(prior) => match prior.result_status {
+            ResultStatus::Ok => {
+                tracing::trace!(
+                    "Error: '{}' cannot POST llms_txt because it already exists (job ID: {})",
+                    payload.url,
+                    prior.job_id,
+                );
+                Err(PostLlmTxtError::AlreadyGenerated)
+            }
+            ResultStatus::Error => {
+                tracing::trace!(
+                    "Success: '{}' had a failed POST before (job ID: {}). Re-creating.",
+                    payload.url,
+                    prior.job_id,
+                );
+                Ok(new_llms_txt_generate_job(conn, payload).await?)
+            }
         },
-    );
+        Err(e) => match e {
+            diesel::result::Error::NotFound => match in_progress_jobs(conn, &payload.url).await {
+                Ok(existing_jobs) => {
+                    if existing_jobs.is_empty() {
+                        tracing::trace!("Success: '{}' creating for the first time.", payload.url);
+                        Ok(new_llms_txt_generate_job(conn, payload).await?)
+                    } else {
+                        tracing::trace!(
+                            "Error: '{}' already has existing in-progress jobs: {:?}",
+                            payload.url,
+                            existing_jobs,
+                        );
+                        Err(PostLlmTxtError::JobsInProgress(existing_jobs))
+                    }
+                }
 
-    diesel::insert_into(job_state::table)
-        .values(&new_job)
-        .execute(conn)
+                Err(e_jobs) => match e_jobs {
+                    diesel::result::Error::NotFound => {
+                        tracing::trace!("Success: '{}' creating for the first time.", payload.url);
+                        Ok(new_llms_txt_generate_job(conn, payload).await?)
+                    }
+                    _ => {
+                        tracing::trace!(
+                            "Error: unknown DB error encountered checking in-progress jobs for '{}': {}",
+                            payload.url,
+                            e_jobs
+                        );
+                        Err(e_jobs.into())
+                    }
+                },
+            },
+            _ => {
+                tracing::trace!(
+                    "Error: unknown DB error encountered while checking for prior llms_txt records for '{}': {}",
+                    payload.url,
+                    e
+                );
+                Err(e.into())
+            }
+        },
+    }
+}
+
+/// What's wrong with the batch, if anything.
+fn check_batch(payload: &BatchJobRequestPayload) -> Result<(), String> {
+    if payload.urls.is_empty() {
+        return Err("no URLs".to_string());
+    }
+    if payload.urls.len() > BatchJobRequestPayload::MAX_URLS {
+        return Err(format!(
+            "{} URLs: at most {} can be submitted at once",
+            payload.urls.len(),
+            BatchJobRequestPayload::MAX_URLS
+        ));
+    }
+    Ok(())
+}
+
+/// POST /api/llm_txt/batch - Create the jobs of POST /api/llm_txt for many URLs at once, in a single transaction
+///
+/// A URL that can't get a job (e.g. it has an llms.txt already) doesn't keep the others from getting theirs: its error
+/// is in its result. Only an invalid request, or a database failure, rejects the whole batch.
+#[utoipa::path(
+    post,
+    path = "/api/llm_txt/batch",
+    tag = "llms.txt",
+    request_body = BatchJobRequestPayload,
+    responses(
+        (status = 200, description = "The job of each URL, or why it has none", body = BatchJobResponse),
+        (status = 400, description = "There are no URLs, too many, or the model is invalid", body = BatchLlmTxtError),
+        (status = 429, description = "Too many requests from the client: retry after Retry-After seconds"),
+        (status = 500, description = "The server failed: no job was created", body = BatchLlmTxtError),
+    )
+)]
+pub async fn post_llm_txt_batch(
+    State(pool): State<DbPool>,
+    Json(payload): Json<BatchJobRequestPayload>,
+) -> Result<impl IntoResponse, BatchLlmTxtError> {
+    check_batch(&payload).map_err(BatchLlmTxtError::InvalidRequest)?;
+    check_model(payload.model.as_deref()).map_err(BatchLlmTxtError::UnknownModel)?;
+    let urls: BTreeSet<&str> = payload.urls.iter().map(String::as_str).collect();
+    let payload = &payload;
+    let mut conn = pool.get().await?;
+    let results = conn
+        .transaction(|conn| {
+            async move {
+                let mut results = BTreeMap::new();
+                for url in urls {
+                    let created = match with_canonical_url(payload.job(url)) {
+                        Ok(job) => create_new_job(conn, &job).await,
+                        Err(e) => Err(PostLlmTxtError::InvalidUrl(e)),
+                    };
+                    let result = match created {
+                        Ok(job_id_response) => BatchJobResult::Created(job_id_response),
+                        // the transaction is rolled back: none of the jobs are created
+                        Err(PostLlmTxtError::Unknown(e)) => return Err(BatchLlmTxtError::Unknown(e)),
+                        Err(e) => BatchJobResult::Failed(e),
+                    };
+                    results.insert(url.to_string(), result);
+                }
+                Ok::<_, BatchLlmTxtError>(results)
+            }
+            .scope_boxed()
+        })
         .await?;
 
-    Ok(JobIdResponse { job_id })
+    let created = results
+        .values()
+        .filter(|result| matches!(result, BatchJobResult::Created(_)))
+        .count();
+    tracing::trace!(
+        "Success: created {} job(s) for a batch of {} URL(s)",
+        created,
+        results.len()
+    );
+    Ok((StatusCode::OK, Json(BatchJobResponse { results })))
+}
+
+/// Create a request to update an existing llms.txt, with the payload's deadline, model, and crawl (if any).
+/// It keeps the detail level the existing llms.txt was generated at, unless the payload asks for another one.
+/// With a `section`, only that H2 section is regenerated (see `check_section`).
+/// A `low_priority` request isn't queued when the queue is backed up.
+pub(crate) async fn update_llms_txt_generation(
+    conn: &mut AsyncPgConnection,
+    existing: &LlmsTxt,
+    payload: &JobRequestPayload,
+    section: Option<SectionUpdate>,
+) -> Result<JobIdResponse, NewJobError> {
+    let job_id = uuid::Uuid::new_v4();
+    let (section, section_url) = match section {
+        Some(SectionUpdate { name, url }) => (Some(name), Some(url)),
+        None => (None, None),
+    };
+    let new_job = JobState {
+        trace_context: current_trace_context(),
+        expires_at: payload.expires_at,
+        model: payload.model.clone(),
+        detail: payload.detail.or(existing.detail()).unwrap_or_default().to_string(),
+        section,
+        section_url,
+        ..JobState::from_kind_data(
+            job_id,
+            existing.url.clone(),
+            JobStatus::Queued,
+            JobKindData::Update {
+                llms_txt: existing.result_data.text().into_owned(),
+            },
+        )
+    }
+    .with_crawl(bounded_crawl(payload.crawl));
+
+    insert_queued_job(conn, new_job, payload.low_priority).await
 }
 
 /// POST /api/update - Create an update job for existing llms.txt
+#[utoipa::path(
+    post,
+    path = "/api/update",
+    tag = "llms.txt",
+    request_body = JobRequestPayload,
+    responses(
+        (status = 201, description = "The job was queued", body = JobIdResponse),
+        (status = 400, description = "The URL, the model, or the section is invalid", body = UpdateLlmTxtError),
+        (status = 403, description = "The domain policy doesn't allow indexing the URL", body = UpdateLlmTxtError),
+        (status = 404, description = "No llms.txt was generated for the URL yet", body = UpdateLlmTxtError),
+        (status = 409, description = "A job for the URL is queued or running", body = UpdateLlmTxtError),
+        (status = 429, description = "Too many requests from the client: retry after Retry-After seconds"),
+        (status = 503, description = "The queue is backed up and the job was low priority", body = UpdateLlmTxtError),
+        (status = 500, description = "The server failed", body = UpdateLlmTxtError),
+    )
+)]
 pub async fn post_update(
     State(pool): State<DbPool>,
-    Json(payload): Json<UrlPayload>,
+    Json(payload): Json<JobRequestPayload>,
 ) -> Result<impl IntoResponse, UpdateLlmTxtError> {
+    let payload = with_canonical_url(payload).map_err(UpdateLlmTxtError::InvalidUrl)?;
+    check_model(payload.model.as_deref()).map_err(UpdateLlmTxtError::UnknownModel)?;
     let mut conn = pool.get().await?;
     conn.transaction(|conn| {
         async move {
             match fetch_llms_txt(conn, &payload.url).await {
                 Ok(llms_txt) => {
+                    let section = check_section(&payload, &llms_txt).map_err(UpdateLlmTxtError::InvalidSection)?;
                     tracing::trace!("Success: started update check for '{}'", payload.url);
                     // Create an update job using the existing llms.txt result_data
-                    let job_id_response = update_llms_txt_generation(conn, &payload.url, &llms_txt.result_data).await?;
+                    let job_id_response = update_llms_txt_generation(conn, &llms_txt, &payload, section).await?;
                     Ok((StatusCode::CREATED, Json(job_id_response)))
                 }
 
@@ -194,24 +838,43 @@ pub async fn post_update(
 }
 
 /// PUT /api/llm_txt - Create a new job: either a 1st time or an update
+#[utoipa::path(
+    put,
+    path = "/api/llm_txt",
+    tag = "llms.txt",
+    request_body = JobRequestPayload,
+    responses(
+        (status = 201, description = "The job was queued", body = JobIdResponse),
+        (status = 400, description = "The URL, the model, or the section is invalid", body = PutLlmTxtError),
+        (status = 403, description = "The domain policy doesn't allow indexing the URL", body = PutLlmTxtError),
+        (status = 409, description = "A job for the URL is queued or running", body = PutLlmTxtError),
+        (status = 429, description = "Too many requests from the client: retry after Retry-After seconds"),
+        (status = 503, description = "The queue is backed up and the job was low priority", body = PutLlmTxtError),
+        (status = 500, description = "The server failed", body = PutLlmTxtError),
+    )
+)]
 pub async fn put_llm_txt(
     State(pool): State<DbPool>,
-    Json(payload): Json<UrlPayload>,
+    Json(payload): Json<JobRequestPayload>,
 ) -> Result<impl IntoResponse, PutLlmTxtError> {
+    let payload = with_canonical_url(payload).map_err(PutLlmTxtError::InvalidUrl)?;
+    check_model(payload.model.as_deref()).map_err(PutLlmTxtError::UnknownModel)?;
     let mut conn = pool.get().await?;
     conn.transaction(|conn| {
         async move {
             match fetch_llms_txt(conn, &payload.url).await {
                 Ok(llms_txt) => {
+                    let section = check_section(&payload, &llms_txt).map_err(PutLlmTxtError::InvalidSection)?;
                     tracing::trace!("Success: re-generating llms.txt for '{}'", payload.url);
-                    let job_id_response = update_llms_txt_generation(conn, &payload.url, &llms_txt.result_data).await?;
+                    let job_id_response = update_llms_txt_generation(conn, &llms_txt, &payload, section).await?;
                     Ok((StatusCode::CREATED, Json(job_id_response)))
                 }
 
                 Err(e) => match e {
                     diesel::result::Error::NotFound => {
+                        reject_section(&payload).map_err(PutLlmTxtError::InvalidSection)?;
                         tracing::trace!("Success: 1st-time llms.txt generation for '{}'", payload.url);
-                        let job_id_response = new_llms_txt_generate_job(conn, &payload.url).await?;
+                        let job_id_response = new_llms_txt_generate_job(conn, &payload).await?;
                         Ok((StatusCode::CREATED, Json(job_id_response)))
                     }
                     _ => {
@@ -226,33 +889,177 @@ pub async fn put_llm_txt(
     .await
 }
 
-// GET /api/list - List all successfully fetched llms.txt files
-pub async fn get_list(State(pool): State<DbPool>) -> Result<impl IntoResponse, AppError> {
+/// Computes the preview of the rows that don't have one: only failures, and the llms.txt files stored compressed before
+/// previews existed, have to read their result.
+async fn with_previews(
+    conn: &mut AsyncPgConnection,
+    rows: Vec<LlmsTxtPreviewRow>,
+) -> Result<Vec<LlmsTxtPreviewRow>, diesel::result::Error> {
+    let missing: Vec<Uuid> = rows
+        .iter()
+        .filter(|row| row.preview.is_none())
+        .map(|row| row.job_id)
+        .collect();
+    if missing.is_empty() {
+        return Ok(rows);
+    }
+    let llms_txts: HashMap<Uuid, ResultData> = llms_txt::table
+        .filter(llms_txt::job_id.eq_any(&missing))
+        .select((llms_txt::job_id, llms_txt::result_data))
+        .load::<(Uuid, ResultData)>(conn)
+        .await?
+        .into_iter()
+        .collect();
+    Ok(rows
+        .into_iter()
+        .map(|row| match (&row.preview, llms_txts.get(&row.job_id)) {
+            (None, Some(llms_txt)) => row.with_preview(&llms_txt.text()),
+            _ => row,
+        })
+        .collect())
+}
+
+/// What's wrong with the requested page, if anything.
+fn check_list_page(page: &ListPage) -> Result<(), String> {
+    if page.page == 0 {
+        return Err("page starts at 1".to_string());
+    }
+    if !(1..=ListPage::MAX_PER_PAGE).contains(&page.per_page) {
+        return Err(format!("per_page must be between 1 and {}", ListPage::MAX_PER_PAGE));
+    }
+    if page
+        .domain
+        .as_deref()
+        .is_some_and(|domain| list_domain(domain).is_empty())
+    {
+        return Err("domain is empty".to_string());
+    }
+    if let (Some(after), Some(before)) = (page.created_after, page.created_before)
+        && after >= before
+    {
+        return Err("created_after must be before created_before".to_string());
+    }
+    Ok(())
+}
+
+/// The domain as hosts are compared to it: lowercase, without its trailing dot.
+fn list_domain(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// The host of a record's URL, lowercase, in SQL.
+const URL_HOST_SQL: &str = "lower(substring(llms_txt.url from '^[^:]+://(?:[^/@]*@)?([^/:?#]+)'))";
+
+/// The records GET /api/list lists: each website's latest one of the page's status, in its domain and time range,
+/// in its order. Everything is done by the database, so that only the page's records are loaded.
+fn listed_records(page: &ListPage) -> llms_txt::BoxedQuery<'static, Pg> {
+    let status = page.status.map(ResultStatus::from).unwrap_or(ResultStatus::Ok);
+    let latest = llms_txt::table
+        .filter(llms_txt::result_status.eq(status))
+        .distinct_on(llms_txt::url_normalized)
+        .order((llms_txt::url_normalized.asc(), llms_txt::created_at.desc()))
+        .select(llms_txt::job_id);
+    let mut records = llms_txt::table.filter(llms_txt::job_id.eq_any(latest)).into_boxed();
+
+    if let Some(domain) = page.domain.as_deref().map(list_domain) {
+        // the domain's pages, and its subdomains' pages
+        records = records.filter(
+            sql::<Bool>("(")
+                .sql(URL_HOST_SQL)
+                .sql(" = ")
+                .bind::<Text, _>(domain.clone())
+                .sql(" OR ")
+                .sql(URL_HOST_SQL)
+                .sql(" LIKE ")
+                .bind::<Text, _>(format!("%.{}", escape_like(&domain)))
+                .sql(")"),
+        );
+    }
+    if let Some(after) = page.created_after {
+        records = records.filter(llms_txt::created_at.gt(after));
+    }
+    if let Some(before) = page.created_before {
+        records = records.filter(llms_txt::created_at.lt(before));
+    }
+    records
+}
+
+/// The records in the page's order.
+fn sorted(records: llms_txt::BoxedQuery<'static, Pg>, sort: ListSort) -> llms_txt::BoxedQuery<'static, Pg> {
+    match sort {
+        ListSort::UrlAsc => records.order(llms_txt::url.asc()),
+        ListSort::UrlDesc => records.order(llms_txt::url.desc()),
+        ListSort::CreatedAtAsc => records.order((llms_txt::created_at.asc(), llms_txt::url.asc())),
+        ListSort::CreatedAtDesc => records.order((llms_txt::created_at.desc(), llms_txt::url.asc())),
+    }
+}
+
+// GET /api/list - List a page of the latest llms.txt files, filtered and sorted, by their previews
+#[utoipa::path(
+    get,
+    path = "/api/list",
+    tag = "llms.txt",
+    params(ListPage),
+    responses(
+        (status = 200, description = "One page of websites, in URL order unless sorted otherwise, with the preview of their llms.txt (all of it with full=true)", body = LlmsTxtListResponse),
+        (status = 400, description = "The page or the number of websites per page is out of range, the domain is empty, or the time range is", body = ListError),
+        (status = 500, description = "The server failed", body = ListError),
+    )
+)]
+pub async fn get_list(
+    State(pool): State<DbPool>,
+    Extension(cache): Extension<ReadCache>,
+    Query(page): Query<ListPage>,
+) -> Result<Response, ListError> {
+    check_list_page(&page).map_err(ListError::InvalidRequest)?;
+    let generation = cache.generation(&pool);
+    if generation.is_some()
+        && let Some(list) = cache.list(&page).await
+    {
+        return Ok((StatusCode::OK, Json(&*list)).into_response());
+    }
     let mut conn = pool.get().await?;
 
-    // Load all Ok records ordered by url and created_at DESC
-    let all_records = llms_txt::table
-        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
-        .order((llms_txt::url.asc(), llms_txt::created_at.desc()))
-        .select(LlmsTxt::as_select())
-        .load::<LlmsTxt>(&mut conn)
-        .await?;
+    let total = listed_records(&page).count().get_result::<i64>(&mut conn).await?;
 
-    // Deduplicate by URL, keeping only the most recent
-    let url_map = {
-        let mut url_map: HashMap<String, String> = HashMap::new();
-        for record in all_records {
-            url_map.entry(record.url).or_insert(record.result_data);
-        }
-        url_map
+    let latest = sorted(listed_records(&page), page.sort)
+        .offset(page.offset() as i64)
+        .limit(i64::from(page.per_page));
+    let items: Vec<LlmsTxtListItem> = if page.full {
+        latest
+            .select(LlmsTxt::as_select())
+            .load::<LlmsTxt>(&mut conn)
+            .await?
+            .into_iter()
+            .map(LlmsTxtListItem::from)
+            .collect()
+    } else {
+        let rows = latest
+            .select(LlmsTxtPreviewRow::as_select())
+            .load::<LlmsTxtPreviewRow>(&mut conn)
+            .await?;
+        with_previews(&mut conn, rows)
+            .await?
+            .into_iter()
+            .map(LlmsTxtListItem::from)
+            .collect()
     };
 
-    // Convert to list response
-    let items: Vec<LlmsTxtListItem> = url_map
-        .into_iter()
-        .map(|(url, llm_txt)| LlmsTxtListItem { url, llm_txt })
-        .collect();
-
-    tracing::trace!("Success: retrieved {} all llms.txt results", items.len());
-    Ok((StatusCode::OK, Json(LlmsTxtListResponse { items })))
+    tracing::trace!(
+        "Success: retrieved {} of {} llms.txt results (page {})",
+        items.len(),
+        total,
+        page.page
+    );
+    let list = Arc::new(LlmsTxtListResponse {
+        items,
+        total: total as u64,
+        page: page.page,
+        per_page: page.per_page,
+    });
+    let response = (StatusCode::OK, Json(&*list)).into_response();
+    if let Some(generation) = generation {
+        cache.insert_list(generation, page, list).await;
+    }
+    Ok(response)
 }
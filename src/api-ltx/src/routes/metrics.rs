@@ -0,0 +1,187 @@
+use std::sync::LazyLock;
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use diesel::dsl::count_star;
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use prometheus::{
+    Encoder, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder, register_histogram_vec,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+};
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::JobStatus;
+use data_model_ltx::schema::job_state;
+
+/// Every job status, so a status without jobs is exported as 0 rather than keeping its last count.
+const JOB_STATUSES: [JobStatus; 6] = [
+    JobStatus::Queued,
+    JobStatus::Running,
+    JobStatus::Success,
+    JobStatus::Failure,
+    JobStatus::Expired,
+    JobStatus::Cancelled,
+];
+
+/// Requests handled, by `method`, `route` (the route's pattern, e.g. `/api/llm_txt`) and `status`.
+pub static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "api_http_requests_total",
+        "HTTP requests handled by the API.",
+        &["method", "route", "status"]
+    )
+    .expect("api_http_requests_total is registered once")
+});
+
+/// How long requests took to answer, by `method` and `route`.
+pub static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "api_http_request_duration_seconds",
+        "Time the API took to answer HTTP requests.",
+        &["method", "route"]
+    )
+    .expect("api_http_request_duration_seconds is registered once")
+});
+
+/// Lookups of the read cache, by `endpoint` (`llm_txt` or `list`) and `result` (`hit` or `miss`).
+pub static READ_CACHE_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "api_read_cache_requests_total",
+        "Lookups of the API's read cache, by endpoint and result.",
+        &["endpoint", "result"]
+    )
+    .expect("api_read_cache_requests_total is registered once")
+});
+
+/// Jobs in the database, by `status`. Refreshed on every scrape.
+pub static JOBS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!("api_jobs", "Jobs in the database, by status.", &["status"])
+        .expect("api_jobs is registered once")
+});
+
+/// Connections the pool can open at most.
+pub static DB_POOL_MAX_SIZE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "api_db_pool_max_size",
+        "Most database connections the API's pool opens."
+    )
+    .expect("api_db_pool_max_size is registered once")
+});
+
+/// Connections the pool has open, in use or not.
+pub static DB_POOL_SIZE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("api_db_pool_size", "Database connections the API's pool has open.")
+        .expect("api_db_pool_size is registered once")
+});
+
+/// Open connections that aren't in use.
+pub static DB_POOL_AVAILABLE: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "api_db_pool_available",
+        "Open database connections of the API's pool that are idle."
+    )
+    .expect("api_db_pool_available is registered once")
+});
+
+/// Requests waiting for a connection.
+pub static DB_POOL_WAITING: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "api_db_pool_waiting",
+        "Requests waiting for a database connection of the API's pool."
+    )
+    .expect("api_db_pool_waiting is registered once")
+});
+
+/// Middleware counting and timing requests. It's a route layer: only requests to a route are counted, by the route's
+/// pattern, so URLs with IDs in them don't each get their own series.
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method.as_str(), route.as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    HTTP_REQUESTS
+        .with_label_values(&[method.as_str(), route.as_str(), response.status().as_str()])
+        .inc();
+    response
+}
+
+/// Counts the jobs of every status that has some.
+async fn count_jobs(conn: &mut AsyncPgConnection) -> Result<Vec<(JobStatus, i64)>, diesel::result::Error> {
+    job_state::table
+        .group_by(job_state::status)
+        .select((job_state::status, count_star()))
+        .load::<(JobStatus, i64)>(conn)
+        .await
+}
+
+/// Refreshes the gauges that are read from the database and the pool.
+async fn refresh(pool: &DbPool) {
+    let status = pool.status();
+    DB_POOL_MAX_SIZE.set(status.max_size as i64);
+    DB_POOL_SIZE.set(status.size as i64);
+    DB_POOL_AVAILABLE.set(status.available as i64);
+    DB_POOL_WAITING.set(status.waiting as i64);
+
+    let counts = match pool.get().await {
+        Ok(mut conn) => count_jobs(&mut conn).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match counts {
+        Ok(counts) => {
+            for job_status in JOB_STATUSES {
+                let count = counts
+                    .iter()
+                    .find(|(status, _)| *status == job_status)
+                    .map_or(0, |(_, count)| *count);
+                JOBS.with_label_values(&[format!("{:?}", job_status).as_str()])
+                    .set(count);
+            }
+        }
+        // keep the last known counts: the scrape still has the requests and the pool
+        Err(e) => tracing::warn!("[SKIP] Failed to count jobs for metrics: {}", e),
+    }
+}
+
+// GET /metrics - The API's metrics, in the Prometheus text format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Requests, jobs by status, and database pool metrics (and the worker's, in serve-ltx)", content_type = "text/plain"),
+        (status = 500, description = "The metrics can't be encoded", content_type = "text/plain"),
+    )
+)]
+pub async fn get_metrics(State(pool): State<DbPool>) -> impl IntoResponse {
+    refresh(&pool).await;
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    match encoder.encode(&prometheus::gather(), &mut body) {
+        Ok(()) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, encoder.format_type().to_string())],
+            body,
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain".to_string())],
+            e.to_string().into_bytes(),
+        ),
+    }
+}
@@ -1,60 +1,219 @@
 use axum::{
-    Router, middleware,
-    routing::{get, post, put},
+    Extension, Router,
+    extract::{DefaultBodyLimit, Request},
+    middleware,
+    routing::{delete, get, post, put},
 };
-use core_ltx::{AuthConfig, health_check};
+use core_ltx::{AuthConfig, db_ready_check, health_check, set_parent_from_headers};
 use std::sync::Arc;
+use tower_http::compression::CompressionLayer;
+use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Predicate};
 use tower_http::services::{ServeDir, ServeFile};
 use tower_http::trace::TraceLayer;
 
 use core_ltx::db::DbPool;
 
-use crate::auth;
+use crate::{auth, mcp};
 
+pub mod api_keys;
+pub mod campaign;
+pub mod crawl_schedule;
+pub mod directory;
+pub mod domain_policy;
+pub mod domains;
+pub mod export;
+pub mod feed;
+pub mod import;
 pub mod job_state;
+pub mod live;
 pub mod llms_txt;
 pub mod logging_middleware;
+pub mod metrics;
+pub mod openapi;
+pub mod rate_limit;
+pub mod read_cache;
+pub mod search;
+pub mod secrets;
+pub mod stats;
+pub mod webhooks;
 
 //
 // Router
 //
 
-pub fn router(auth_config: Option<AuthConfig>) -> Router<DbPool> {
+pub fn router(auth_config: Option<AuthConfig>, pool: DbPool) -> Router {
     let auth_config_arc = Arc::new(auth_config);
+    // Requests are authenticated by session cookie, or by API key: which needs the database
+    let auth_state = auth::AuthState {
+        auth_config: auth_config_arc.clone(),
+        pool: pool.clone(),
+    };
 
     // Public auth routes (no authentication required)
     let auth_routes = Router::new()
-        .route("/api/auth/login", post(auth::post_login))
         .route("/api/auth/logout", post(auth::post_logout))
         .route("/api/auth/check", get(auth::get_check))
         .with_state(auth_config_arc.clone());
 
-    // Protected API routes (authentication required when enabled)
-    let protected_routes = Router::new()
+    // The login, and the management of its second factor (authentication required), need the database too: they get
+    // the auth config as an extension instead
+    let two_factor_routes = Router::new()
+        .route("/api/auth/totp", get(auth::get_totp))
+        .route("/api/auth/totp/enroll", post(auth::post_totp_enroll))
+        .route("/api/auth/totp/confirm", post(auth::post_totp_confirm))
+        .route("/api/auth/totp/recovery_codes", post(auth::post_totp_recovery_codes))
+        .route("/api/auth/totp/disable", post(auth::post_totp_disable))
+        .route_layer(middleware::from_fn_with_state(auth_state.clone(), auth::require_auth))
+        .route("/api/auth/login", post(auth::post_login))
+        .layer(Extension(auth_config_arc.clone()));
+
+    // Shared by the routes that spend the LLM budget, per client
+    let rate_limits = Arc::new(rate_limit::RateLimits::from_env());
+
+    // Read-only API routes: public in public read-only mode, otherwise authentication required when enabled
+    let read_routes = Router::new()
         .route("/api/llm_txt", get(llms_txt::get_llm_txt))
-        .route("/api/llm_txt", post(llms_txt::post_llm_txt))
-        .route("/api/llm_txt", put(llms_txt::put_llm_txt))
-        .route("/api/update", post(llms_txt::post_update))
+        .route("/api/llm_txt/history", get(llms_txt::get_llm_txt_history))
+        .route("/api/llm_txt/related", get(search::get_related_llm_txt))
         .route("/api/list", get(llms_txt::get_list))
+        .route("/api/search", get(search::get_search))
+        // every new query is embedded by the embedding model
+        .route(
+            "/api/search/semantic",
+            get(search::get_semantic_search).layer(middleware::from_fn_with_state(
+                rate_limits.clone(),
+                rate_limit::limit_requests,
+            )),
+        )
         .route("/api/status", get(job_state::get_status))
+        .route("/api/status/batch", post(job_state::post_status_batch))
         .route("/api/job", get(job_state::get_job))
+        .route("/api/job/logs", get(job_state::get_job_logs))
+        .route("/api/jobs", get(job_state::get_jobs))
         .route("/api/jobs/in_progress", get(job_state::get_in_progress_jobs))
-        .route_layer(middleware::from_fn_with_state(
-            auth_config_arc.clone(),
-            auth::require_auth,
-        ));
+        .route("/api/stats", get(stats::get_url_stats))
+        .route("/api/stats/models", get(stats::get_model_stats))
+        .route("/api/crawl_schedule", get(crawl_schedule::get_crawl_schedule))
+        .route("/feed.xml", get(feed::get_feed))
+        .route("/.well-known/llms-directory", get(directory::get_directory))
+        .route("/llms/{domain}/llms.txt", get(directory::get_domain_llms_txt))
+        .route("/llms.txt", get(directory::get_index_llms_txt))
+        .route("/api/ws", get(live::get_ws));
+    let public_reads = auth_config_arc
+        .as_ref()
+        .as_ref()
+        .is_some_and(|config| config.public_reads);
+    let read_routes = if public_reads {
+        read_routes
+    } else {
+        read_routes.route_layer(middleware::from_fn_with_state(auth_state.clone(), auth::require_auth))
+    }
+    .layer(Extension(live::LiveUpdates::from_env()))
+    .layer(Extension(read_cache::ReadCache::from_env()));
+
+    // Routes creating jobs, which spend the LLM budget: rate limited per client.
+    // MCP is here too: its request_generation tool creates jobs.
+    let job_routes = Router::new()
+        .route("/api/llm_txt", post(llms_txt::post_llm_txt).put(llms_txt::put_llm_txt))
+        .route("/api/llm_txt/batch", post(llms_txt::post_llm_txt_batch))
+        .route("/api/update", post(llms_txt::post_update))
+        .route("/api/job/retry", post(job_state::post_retry_job))
+        .route("/api/admin/regenerate_all", post(campaign::post_regenerate_all))
+        .route("/mcp", post(mcp::post_mcp))
+        .route_layer(middleware::from_fn_with_state(rate_limits, rate_limit::limit_requests));
+
+    // Protected API routes that change data (authentication required when enabled).
+    let protected_routes = Router::new()
+        .merge(job_routes)
+        .route("/api/llm_txt", delete(llms_txt::delete_llm_txt))
+        // every llms.txt at once: heavy enough to keep it for logged in users, even in public read-only mode
+        .route("/api/export", get(export::get_export))
+        .route(
+            "/api/import",
+            post(import::post_import).layer(DefaultBodyLimit::max(import::IMPORT_MAX_BYTES)),
+        )
+        .route("/api/job/cancel", post(job_state::post_cancel_job))
+        .route("/api/admin/jobs/purge_stale", post(job_state::post_purge_stale_jobs))
+        .route("/api/admin/regenerate_all", get(campaign::get_regenerate_all))
+        .route(
+            "/api/admin/domain_policy",
+            get(domain_policy::get_domain_policy)
+                .put(domain_policy::put_domain_policy)
+                .delete(domain_policy::delete_domain_policy),
+        )
+        .route(
+            "/api/admin/domains",
+            get(domains::get_domains)
+                .put(domains::put_domain)
+                .delete(domains::delete_domain),
+        )
+        .route("/api/admin/secrets/rotate", post(secrets::post_rotate_secrets))
+        .route(
+            "/api/admin/webhooks",
+            get(webhooks::get_webhooks)
+                .post(webhooks::post_webhook)
+                .delete(webhooks::delete_webhook),
+        )
+        .route("/api/admin/webhooks/deliveries", get(webhooks::get_webhook_deliveries))
+        .route(
+            "/api/admin/api_keys",
+            get(api_keys::get_api_keys)
+                .post(api_keys::post_api_key)
+                .delete(api_keys::delete_api_key),
+        )
+        .route(
+            "/api/crawl_schedule",
+            put(crawl_schedule::put_crawl_schedule).delete(crawl_schedule::delete_crawl_schedule),
+        )
+        // Prometheus metrics: scrapers authenticate with an API key
+        .route("/metrics", get(metrics::get_metrics))
+        .route_layer(middleware::from_fn_with_state(auth_state, auth::require_auth));
 
     // Combine all routes
     Router::new()
         .route("/health", get(health_check))
+        .route("/ready", get(db_ready_check))
+        // The OpenAPI spec and its Swagger UI (no auth required)
+        .merge(openapi::swagger_ui())
         .merge(auth_routes)
+        .merge(two_factor_routes)
+        .merge(read_routes)
         .merge(protected_routes)
-        // Serve static assets from frontend pkg directory (no auth required)
-        .nest_service("/pkg", ServeDir::new("src/front-ltx/www/pkg"))
+        // Request counts and latencies, by route: static assets and the fallback aren't counted
+        .route_layer(middleware::from_fn(metrics::track_requests))
+        // Serve static assets from frontend pkg directory (no auth required). The build pre-compresses them: the
+        // `.br` or `.gz` file is sent, as is, to browsers that accept it
+        .nest_service(
+            "/pkg",
+            ServeDir::new("src/front-ltx/www/pkg")
+                .precompressed_br()
+                .precompressed_gzip(),
+        )
         // Fallback to index.html for all other routes (enables client-side routing, no auth required)
         .fallback_service(ServeFile::new("src/front-ltx/www/index.html"))
+        // Brotli or gzip, as the client accepts. Not what's compressed already (the export's tar.gz, the pre-compressed
+        // assets), nor tiny bodies, images, or event streams (see `DefaultPredicate`)
+        .layer(
+            CompressionLayer::new()
+                .br(true)
+                .gzip(true)
+                .compress_when(DefaultPredicate::new().and(NotForContentType::new("application/gzip"))),
+        )
         // Custom route access logging
         .layer(middleware::from_fn(logging_middleware::log_route_access))
-        // Tracing middleware
-        .layer(TraceLayer::new_for_http())
+        // Tracing middleware: continues the caller's trace when the request has a `traceparent` header
+        .layer(TraceLayer::new_for_http().make_span_with(request_span))
+        .with_state(pool)
+}
+
+/// Span for one HTTP request, parented to the caller's span (if any) so traces cross service boundaries.
+fn request_span(request: &Request) -> tracing::Span {
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        version = ?request.version(),
+    );
+    set_parent_from_headers(&span, request.headers());
+    span
 }
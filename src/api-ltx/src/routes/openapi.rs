@@ -0,0 +1,131 @@
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::routes::{
+    api_keys, campaign, crawl_schedule, directory, domain_policy, domains, export, feed, import, job_state, live,
+    llms_txt, metrics, search, secrets, stats, webhooks,
+};
+use crate::{auth, mcp};
+
+/// Where the OpenAPI spec is served, as JSON.
+pub const OPENAPI_JSON_PATH: &str = "/api/openapi.json";
+
+/// Where the Swagger UI of the spec is served.
+pub const SWAGGER_UI_PATH: &str = "/api/docs";
+
+/// The OpenAPI spec of every endpoint, for third parties to generate clients from. A new handler needs a
+/// `#[utoipa::path]` annotation, and to be listed in `paths` here.
+///
+/// Every endpoint needs the session cookie, or an API key, when authentication is enabled, but the ones that log in,
+/// and the read-only ones in public read-only mode.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "llm-web-index",
+        description = "Generates, stores, and keeps up to date the llms.txt files of websites."
+    ),
+    paths(
+        auth::handlers::post_login,
+        auth::handlers::post_logout,
+        auth::handlers::get_check,
+        auth::two_factor::get_totp,
+        auth::two_factor::post_totp_enroll,
+        auth::two_factor::post_totp_confirm,
+        auth::two_factor::post_totp_recovery_codes,
+        auth::two_factor::post_totp_disable,
+        llms_txt::get_llm_txt,
+        llms_txt::post_llm_txt,
+        llms_txt::post_llm_txt_batch,
+        llms_txt::put_llm_txt,
+        llms_txt::delete_llm_txt,
+        llms_txt::get_llm_txt_history,
+        llms_txt::post_update,
+        llms_txt::get_list,
+        search::get_search,
+        search::get_semantic_search,
+        search::get_related_llm_txt,
+        export::get_export,
+        import::post_import,
+        job_state::get_status,
+        job_state::post_status_batch,
+        job_state::get_job,
+        job_state::get_job_logs,
+        job_state::post_cancel_job,
+        job_state::post_retry_job,
+        job_state::post_purge_stale_jobs,
+        job_state::get_jobs,
+        job_state::get_in_progress_jobs,
+        live::get_ws,
+        stats::get_url_stats,
+        stats::get_model_stats,
+        crawl_schedule::get_crawl_schedule,
+        crawl_schedule::put_crawl_schedule,
+        crawl_schedule::delete_crawl_schedule,
+        campaign::post_regenerate_all,
+        campaign::get_regenerate_all,
+        domain_policy::get_domain_policy,
+        domain_policy::put_domain_policy,
+        domain_policy::delete_domain_policy,
+        domains::get_domains,
+        domains::put_domain,
+        domains::delete_domain,
+        secrets::post_rotate_secrets,
+        webhooks::get_webhooks,
+        webhooks::post_webhook,
+        webhooks::delete_webhook,
+        webhooks::get_webhook_deliveries,
+        api_keys::get_api_keys,
+        api_keys::post_api_key,
+        api_keys::delete_api_key,
+        metrics::get_metrics,
+        feed::get_feed,
+        directory::get_directory,
+        directory::get_domain_llms_txt,
+        directory::get_index_llms_txt,
+        mcp::post_mcp,
+    ),
+    modifiers(&SecuritySchemes),
+    security(("session" = []), ("api_key" = [])),
+    tags(
+        (name = "llms.txt", description = "Generating, updating, and reading llms.txt files"),
+        (name = "jobs", description = "The jobs that generate them"),
+        (name = "crawl schedules", description = "Recurring full-site re-crawls"),
+        (name = "discovery", description = "Feeds and directories of the indexed websites"),
+        (name = "admin", description = "Administration of the server"),
+        (name = "auth", description = "Logging in, and two-factor authentication"),
+        (name = "mcp", description = "Model Context Protocol over HTTP"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the session cookie that `POST /api/auth/login` sets as the spec's `session` security scheme, and the keys
+/// of `POST /api/admin/api_keys` as its `api_key` one.
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                auth::session::COOKIE_NAME,
+                "Set by POST /api/auth/login",
+            ))),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some("A key created by POST /api/admin/api_keys"))
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// GET /api/openapi.json, and the Swagger UI of it at /api/docs. Both are public: the spec is no secret.
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_JSON_PATH, ApiDoc::openapi())
+}
@@ -0,0 +1,319 @@
+use axum::{
+    Extension, Json,
+    extract::{ConnectInfo, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::auth::middleware::parse_bearer_token;
+use crate::auth::session::parse_session_cookie;
+use crate::routes::api_keys::hash_api_key;
+
+/// Environment variable with the requests per minute that one IP address can make to the endpoints creating jobs.
+pub const RATE_LIMIT_PER_IP_ENV_VAR: &str = "RATE_LIMIT_PER_IP";
+/// Environment variable with the requests per minute that one login session (or API key) can make to the endpoints
+/// creating jobs.
+pub const RATE_LIMIT_PER_SESSION_ENV_VAR: &str = "RATE_LIMIT_PER_SESSION";
+
+const DEFAULT_RATE_LIMIT_PER_IP: u32 = 30;
+const DEFAULT_RATE_LIMIT_PER_SESSION: u32 = 60;
+
+/// The limits are per minute: a client can use a whole minute's worth of requests at once, and then gets one more
+/// every `RATE_WINDOW / limit`.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Clients tracked at once. Past it, the clients whose allowance is full again are forgotten early, and then the ones
+/// seen least recently.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Requests a client has left, refilled continuously up to the limit (a token bucket).
+#[derive(Debug, Clone, Copy)]
+struct Allowance {
+    requests: f64,
+    updated: Instant,
+}
+
+/// Limits the requests per minute of every client, identified by a `K` (e.g. its IP address).
+///
+/// The state is in memory: it's per API server, and starts over when the server restarts.
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    per_minute: u32,
+    clients: Mutex<HashMap<K, Allowance>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.per_minute) / RATE_WINDOW.as_secs_f64()
+    }
+
+    /// The client's allowance at `now`, refilled since it was last used.
+    fn refilled(&self, allowance: Allowance, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(allowance.updated).as_secs_f64();
+        (allowance.requests + elapsed * self.refill_rate()).min(f64::from(self.per_minute))
+    }
+
+    /// Counts a request of the client at `now`, if it's allowed. Otherwise, returns how long until it is.
+    pub fn check(&self, client: K, now: Instant) -> Result<(), Duration> {
+        self.reserve(client, now)?.spend();
+        Ok(())
+    }
+
+    /// The client's allowance at `now`, if it has a request left: it's locked until it's spent (or dropped). Otherwise,
+    /// returns how long until it has one.
+    fn reserve(&self, client: K, now: Instant) -> Result<Reserved<'_, K>, Duration> {
+        let clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let requests = clients
+            .get(&client)
+            .map_or(f64::from(self.per_minute), |allowance| self.refilled(*allowance, now));
+        if requests < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - requests) / self.refill_rate()));
+        }
+        Ok(Reserved {
+            limiter: self,
+            clients,
+            client,
+            requests,
+            now,
+        })
+    }
+
+    /// Forgets clients until there's room for one more.
+    fn make_room(&self, clients: &mut HashMap<K, Allowance>, now: Instant) {
+        if clients.len() < MAX_TRACKED_CLIENTS {
+            return;
+        }
+        let full = f64::from(self.per_minute);
+        clients.retain(|_, allowance| self.refilled(*allowance, now) < full);
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            // every client is still limited: the tenth seen least recently goes, so it isn't done on every request
+            let mut updated: Vec<Instant> = clients.values().map(|allowance| allowance.updated).collect();
+            let (_, &mut cutoff, _) = updated.select_nth_unstable(MAX_TRACKED_CLIENTS / 10);
+            clients.retain(|_, allowance| allowance.updated > cutoff);
+        }
+    }
+}
+
+/// A request a client is allowed, not counted yet.
+struct Reserved<'a, K> {
+    limiter: &'a RateLimiter<K>,
+    clients: MutexGuard<'a, HashMap<K, Allowance>>,
+    client: K,
+    requests: f64,
+    now: Instant,
+}
+
+impl<K: Eq + Hash> Reserved<'_, K> {
+    /// Counts the request.
+    fn spend(mut self) {
+        if !self.clients.contains_key(&self.client) {
+            self.limiter.make_room(&mut self.clients, self.now);
+        }
+        self.clients.insert(
+            self.client,
+            Allowance {
+                requests: self.requests - 1.0,
+                updated: self.now,
+            },
+        );
+    }
+}
+
+/// Rate limits of the endpoints that create jobs, and of semantic search: every job can spend LLM tokens, and every
+/// search query embedding ones, so one client can't spend an unbounded number of them. None means unlimited.
+#[derive(Debug)]
+pub struct RateLimits {
+    /// Per client IP address, when it's known (i.e. the server was started with its clients' addresses)
+    pub per_ip: Option<RateLimiter<IpAddr>>,
+    /// Per login session, or per API key, when the request has one
+    pub per_session: Option<RateLimiter<String>>,
+}
+
+impl RateLimits {
+    /// Reads the requests per minute from `RATE_LIMIT_PER_IP` (default: 30) and `RATE_LIMIT_PER_SESSION` (default:
+    /// 60). `0` turns a limit off. Panics on invalid values.
+    pub fn from_env() -> Self {
+        Self {
+            per_ip: limit_from_env(RATE_LIMIT_PER_IP_ENV_VAR, DEFAULT_RATE_LIMIT_PER_IP).map(RateLimiter::new),
+            per_session: limit_from_env(RATE_LIMIT_PER_SESSION_ENV_VAR, DEFAULT_RATE_LIMIT_PER_SESSION)
+                .map(RateLimiter::new),
+        }
+    }
+
+    /// Counts a request, if every limit that applies allows it. Otherwise, returns how long until they do: the request
+    /// isn't counted towards any limit then.
+    pub fn check(&self, ip: Option<IpAddr>, session: Option<String>, now: Instant) -> Result<(), Duration> {
+        let per_ip = match (&self.per_ip, ip) {
+            (Some(limiter), Some(ip)) => Some(limiter.reserve(ip, now)?),
+            _ => None,
+        };
+        let per_session = match (&self.per_session, session) {
+            (Some(limiter), Some(session)) => Some(limiter.reserve(session, now)?),
+            _ => None,
+        };
+        if let Some(reserved) = per_ip {
+            reserved.spend();
+        }
+        if let Some(reserved) = per_session {
+            reserved.spend();
+        }
+        Ok(())
+    }
+}
+
+fn limit_from_env(name: &str, default: u32) -> Option<u32> {
+    let per_minute = match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse::<u32>()
+            .unwrap_or_else(|_| panic!("{} must be a number of requests per minute (0 for no limit)", name)),
+        Err(_) => default,
+    };
+    (per_minute > 0).then_some(per_minute)
+}
+
+/// Middleware answering `429 Too Many Requests`, with a `Retry-After` header, to clients past their `RateLimits`.
+pub async fn limit_requests(
+    State(limits): State<Arc<RateLimits>>,
+    connect_info: Option<Extension<ConnectInfo<SocketAddr>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let ip = connect_info.map(|Extension(ConnectInfo(addr))| addr.ip());
+    let session = request
+        .headers()
+        .get(header::COOKIE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(parse_session_cookie)
+        // keys aren't kept in memory, only their hashes
+        .or_else(|| parse_bearer_token(request.headers()).map(hash_api_key));
+
+    match limits.check(ip, session, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            // rounded up: retrying right at Retry-After is allowed
+            let retry_after = wait.as_secs_f64().ceil() as u64;
+            tracing::warn!(
+                "Rate limited {} {} from {:?}: retry in {}s",
+                request.method(),
+                request.uri().path(),
+                ip,
+                retry_after
+            );
+            let body = Json(serde_json::json!({
+                "error": "Too many requests",
+                "retry_after_s": retry_after,
+            }));
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                body,
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(60);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let start = Instant::now();
+        for _ in 0..60 {
+            assert!(limiter.check(ip, start).is_ok());
+        }
+        // one request every second after the burst
+        assert_eq!(limiter.check(ip, start), Err(Duration::from_secs(1)));
+        assert_eq!(
+            limiter.check(ip, start + Duration::from_millis(250)),
+            Err(Duration::from_millis(750))
+        );
+        assert!(limiter.check(ip, start + Duration::from_secs(1)).is_ok());
+        assert!(limiter.check(ip, start + Duration::from_secs(1)).is_err());
+    }
+
+    #[test]
+    fn test_clients_are_limited_separately() {
+        let limiter = RateLimiter::new(1);
+        let now = Instant::now();
+        assert!(limiter.check("a".to_string(), now).is_ok());
+        assert!(limiter.check("a".to_string(), now).is_err());
+        assert!(limiter.check("b".to_string(), now).is_ok());
+    }
+
+    #[test]
+    fn test_allowance_refills_up_to_limit() {
+        let limiter = RateLimiter::new(2);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let start = Instant::now();
+        assert!(limiter.check(ip, start).is_ok());
+        // an hour idle doesn't allow more than a minute's worth at once
+        let later = start + Duration::from_secs(3600);
+        assert!(limiter.check(ip, later).is_ok());
+        assert!(limiter.check(ip, later).is_ok());
+        assert!(limiter.check(ip, later).is_err());
+    }
+
+    #[test]
+    fn test_least_recent_clients_are_forgotten() {
+        let limiter = RateLimiter::new(1);
+        let start = Instant::now();
+        for client in 0..MAX_TRACKED_CLIENTS {
+            let now = start + Duration::from_millis(client as u64);
+            assert!(limiter.check(client, now).is_ok());
+        }
+        let now = start + Duration::from_secs(20);
+        assert!(limiter.check(MAX_TRACKED_CLIENTS, now).is_ok());
+        assert!(limiter.clients.lock().unwrap().len() < MAX_TRACKED_CLIENTS);
+        // the first clients start over, the latest are still limited
+        assert!(limiter.check(0, now).is_ok());
+        assert!(limiter.check(MAX_TRACKED_CLIENTS - 1, now).is_err());
+    }
+
+    #[test]
+    fn test_rejected_request_isnt_counted() {
+        let limits = RateLimits {
+            per_ip: Some(RateLimiter::new(1)),
+            per_session: Some(RateLimiter::new(1)),
+        };
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        assert!(limits.check(None, Some("session".to_string()), now).is_ok());
+        // the session's limit turns it away: the address keeps its request
+        assert!(limits.check(Some(ip), Some("session".to_string()), now).is_err());
+        assert!(limits.check(Some(ip), None, now).is_ok());
+    }
+
+    #[test]
+    fn test_every_limit_applies() {
+        let limits = RateLimits {
+            per_ip: Some(RateLimiter::new(5)),
+            per_session: Some(RateLimiter::new(1)),
+        };
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        assert!(limits.check(Some(ip), Some("session".to_string()), now).is_ok());
+        assert!(limits.check(Some(ip), Some("session".to_string()), now).is_err());
+        // without a session (or an address), only the other limit applies
+        assert!(limits.check(Some(ip), None, now).is_ok());
+        assert!(limits.check(None, Some("other".to_string()), now).is_ok());
+        assert!(limits.check(None, None, now).is_ok());
+    }
+}
@@ -0,0 +1,338 @@
+use std::pin::pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
+
+use diesel_async::RunQueryDsl;
+use diesel_async::pooled_connection::deadpool::Object;
+use futures_util::StreamExt;
+use moka::future::Cache;
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use core_ltx::{TimeUnit, get_poll_interval};
+use data_model_ltx::changes::LLMS_TXT_CHANGED_CHANNEL;
+use data_model_ltx::models::{ListPage, LlmsTxtListResponse};
+
+use crate::routes::metrics::READ_CACHE_REQUESTS;
+
+/// Environment variable with how long (in seconds) a response is cached at most. `0` turns the cache off.
+pub const READ_CACHE_TTL_ENV_VAR: &str = "READ_CACHE_TTL_S";
+
+/// Environment variable with how many websites' llms.txt, and how many pages of the list, are cached at most.
+pub const READ_CACHE_CAPACITY_ENV_VAR: &str = "READ_CACHE_CAPACITY";
+
+const DEFAULT_READ_CACHE_TTL_S: u64 = 60;
+const DEFAULT_READ_CACHE_CAPACITY: u64 = 10_000;
+
+/// Hosts the index's llms.txt is cached for at most: it links to the one it's requested from.
+const INDEX_LLMS_TXT_CAPACITY: u64 = 16;
+
+/// How long to wait before listening again, after the listening connection failed.
+const RELISTEN_DELAY: Duration = Duration::from_secs(5);
+
+/// A website's latest llms.txt, as GET /api/llm_txt answers it.
+#[derive(Debug)]
+pub struct CachedLlmsTxt {
+    /// The job that generated it: its `ETag`
+    pub job_id: Uuid,
+    /// Decompressed
+    pub content: String,
+}
+
+impl CachedLlmsTxt {
+    /// The `ETag` header of the llms.txt: every generation is a record of its own, so its job identifies it.
+    pub fn etag(&self) -> String {
+        format!("\"{}\"", self.job_id)
+    }
+}
+
+/// In-process cache of the read-heavy GET /api/llm_txt (by normalized URL) and GET /api/list (by page), and of the
+/// index's own GET /llms.txt (by the host it links to).
+///
+/// An entry is dropped when it's `READ_CACHE_TTL_S` old, or as soon as the llms.txt records change: the worker, and
+/// the API's deletes and restores, notify of their changes (see `data_model_ltx::changes`), which one task per server
+/// listens to. Nothing is cached until it is listening, nor while it isn't, so that entries can't outlive a change
+/// that went unnoticed.
+#[derive(Clone)]
+pub struct ReadCache {
+    /// None when the cache is turned off
+    caches: Option<Arc<Caches>>,
+}
+
+struct Caches {
+    /// The normalized URL of the requested ones, as the database normalizes them. That never changes, so changes of
+    /// the records don't invalidate these
+    keys: Cache<String, String>,
+    /// By normalized URL: every spelling of a URL is one entry, which a change of the website's records drops
+    llms_txt: Cache<String, Arc<CachedLlmsTxt>>,
+    lists: Cache<ListPage, Arc<LlmsTxtListResponse>>,
+    /// By the base URL of its links: every change of a website's records changes it
+    index_llms_txt: Cache<String, Arc<String>>,
+    /// Incremented on every invalidation: an entry read from the database before one isn't cached after it
+    generation: AtomicU64,
+    started: AtomicBool,
+    listening: AtomicBool,
+}
+
+impl ReadCache {
+    pub fn new(ttl: Duration, capacity: u64) -> Self {
+        if ttl.is_zero() || capacity == 0 {
+            return Self::disabled();
+        }
+        ReadCache {
+            caches: Some(Arc::new(Caches {
+                keys: Cache::builder().max_capacity(capacity).time_to_live(ttl).build(),
+                llms_txt: Cache::builder().max_capacity(capacity).time_to_live(ttl).build(),
+                lists: Cache::builder().max_capacity(capacity).time_to_live(ttl).build(),
+                index_llms_txt: Cache::builder()
+                    .max_capacity(INDEX_LLMS_TXT_CAPACITY)
+                    .time_to_live(ttl)
+                    .build(),
+                generation: AtomicU64::new(0),
+                started: AtomicBool::new(false),
+                listening: AtomicBool::new(false),
+            })),
+        }
+    }
+
+    /// Every request goes to the database.
+    pub fn disabled() -> Self {
+        ReadCache { caches: None }
+    }
+
+    /// Caches for `READ_CACHE_TTL_S` (60 seconds by default) at most `READ_CACHE_CAPACITY` entries of each endpoint
+    /// (10000 by default). Panics on invalid values.
+    pub fn from_env() -> Self {
+        let ttl = get_poll_interval(TimeUnit::Seconds, READ_CACHE_TTL_ENV_VAR, DEFAULT_READ_CACHE_TTL_S);
+        let capacity = match std::env::var(READ_CACHE_CAPACITY_ENV_VAR) {
+            Ok(value) => value
+                .trim()
+                .parse::<u64>()
+                .unwrap_or_else(|_| panic!("{} must be a number of entries", READ_CACHE_CAPACITY_ENV_VAR)),
+            Err(_) => DEFAULT_READ_CACHE_CAPACITY,
+        };
+        ReadCache::new(ttl, capacity)
+    }
+
+    /// The generation of the cache to read and fill it at, when it can be used. The first call starts listening for
+    /// changes.
+    pub fn generation(&self, pool: &DbPool) -> Option<u64> {
+        let caches = self.caches.as_ref()?;
+        if !caches.started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(listen(pool.clone(), caches.clone()));
+        }
+        caches
+            .listening
+            .load(Ordering::SeqCst)
+            .then(|| caches.generation.load(Ordering::SeqCst))
+    }
+
+    pub async fn llms_txt(&self, url: &str) -> Option<Arc<CachedLlmsTxt>> {
+        let caches = self.caches.as_ref()?;
+        let cached = match caches.keys.get(url).await {
+            Some(key) => caches.llms_txt.get(&key).await,
+            None => None,
+        };
+        count("llm_txt", cached.is_some());
+        cached
+    }
+
+    /// Caches the llms.txt read from the database at `generation`, unless it changed since. `key` is the normalized
+    /// URL of the record: changes are notified by it.
+    pub async fn insert_llms_txt(&self, generation: u64, url: String, key: String, llms_txt: Arc<CachedLlmsTxt>) {
+        if let Some(caches) = self.current(generation) {
+            caches.llms_txt.insert(key.clone(), llms_txt).await;
+            caches.keys.insert(url, key).await;
+        }
+    }
+
+    pub async fn list(&self, page: &ListPage) -> Option<Arc<LlmsTxtListResponse>> {
+        let cached = self.caches.as_ref()?.lists.get(page).await;
+        count("list", cached.is_some());
+        cached
+    }
+
+    /// Caches the page read from the database at `generation`, unless anything changed since.
+    pub async fn insert_list(&self, generation: u64, page: ListPage, list: Arc<LlmsTxtListResponse>) {
+        if let Some(caches) = self.current(generation) {
+            caches.lists.insert(page, list).await;
+        }
+    }
+
+    pub async fn index_llms_txt(&self, base_url: &str) -> Option<Arc<String>> {
+        let cached = self.caches.as_ref()?.index_llms_txt.get(base_url).await;
+        count("index_llms_txt", cached.is_some());
+        cached
+    }
+
+    /// Caches the index's llms.txt rendered from the database at `generation`, unless anything changed since.
+    pub async fn insert_index_llms_txt(&self, generation: u64, base_url: String, llms_txt: Arc<String>) {
+        if let Some(caches) = self.current(generation) {
+            caches.index_llms_txt.insert(base_url, llms_txt).await;
+        }
+    }
+
+    fn current(&self, generation: u64) -> Option<&Caches> {
+        self.caches
+            .as_deref()
+            .filter(|caches| caches.generation.load(Ordering::SeqCst) == generation)
+    }
+}
+
+impl Caches {
+    /// Drops what the change of the website's records makes stale: every page of the list, and the index's llms.txt,
+    /// has it, or could have it. `key` is the website's normalized URL.
+    async fn invalidate(&self, key: Option<&str>) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        match key {
+            Some(key) => self.llms_txt.invalidate(key).await,
+            None => self.llms_txt.invalidate_all(),
+        }
+        self.lists.invalidate_all();
+        self.index_llms_txt.invalidate_all();
+    }
+}
+
+fn count(endpoint: &str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    READ_CACHE_REQUESTS.with_label_values(&[endpoint, result]).inc();
+}
+
+/// Listens for changes for as long as the server runs, again after a failure.
+async fn listen(pool: DbPool, caches: Arc<Caches>) {
+    loop {
+        let error = listen_until_failure(&pool, &caches).await;
+        // changes can be missed until it listens again: nothing is cached meanwhile
+        caches.listening.store(false, Ordering::SeqCst);
+        caches.invalidate(None).await;
+        tracing::warn!(
+            "[SKIP] Read cache: stopped listening for changes, again in {:?}: {}",
+            RELISTEN_DELAY,
+            error
+        );
+        tokio::time::sleep(RELISTEN_DELAY).await;
+    }
+}
+
+async fn listen_until_failure(pool: &DbPool, caches: &Caches) -> String {
+    // a connection of its own, out of the pool: it's busy listening as long as it's open
+    let mut conn = match pool.get().await {
+        Ok(conn) => Object::take(conn),
+        Err(e) => return e.to_string(),
+    };
+    if let Err(e) = diesel::sql_query(format!("LISTEN {}", LLMS_TXT_CHANGED_CHANNEL))
+        .execute(&mut conn)
+        .await
+    {
+        return e.to_string();
+    }
+    // what changed before it listened
+    caches.invalidate(None).await;
+    caches.listening.store(true, Ordering::SeqCst);
+    tracing::debug!("Read cache: listening for changes");
+
+    let mut notifications = pin!(conn.notifications_stream());
+    while let Some(notification) = notifications.next().await {
+        match notification {
+            Ok(notification) if notification.payload.is_empty() => caches.invalidate(None).await,
+            Ok(notification) => caches.invalidate(Some(&notification.payload)).await,
+            Err(e) => return e.to_string(),
+        }
+    }
+    "the connection was closed".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listening_cache() -> (ReadCache, Arc<Caches>) {
+        let cache = ReadCache::new(Duration::from_secs(60), 100);
+        let caches = cache.caches.clone().unwrap();
+        caches.started.store(true, Ordering::SeqCst);
+        caches.listening.store(true, Ordering::SeqCst);
+        (cache, caches)
+    }
+
+    fn llms_txt(content: &str) -> Arc<CachedLlmsTxt> {
+        Arc::new(CachedLlmsTxt {
+            job_id: Uuid::new_v4(),
+            content: content.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_changes_invalidate() {
+        let (cache, caches) = listening_cache();
+        let generation = caches.generation.load(Ordering::SeqCst);
+        cache
+            .insert_llms_txt(
+                generation,
+                "https://a.com".to_string(),
+                "https://a.com/".to_string(),
+                llms_txt("# A"),
+            )
+            .await;
+        cache
+            .insert_llms_txt(
+                generation,
+                "https://B.com".to_string(),
+                "https://b.com/".to_string(),
+                llms_txt("# B"),
+            )
+            .await;
+        cache
+            .insert_list(generation, ListPage::default(), Arc::new(response()))
+            .await;
+        assert_eq!(cache.llms_txt("https://a.com").await.unwrap().content, "# A");
+
+        // another spelling of the URL is another key to the same entry
+        caches
+            .keys
+            .insert("https://A.com:443".to_string(), "https://a.com/".to_string())
+            .await;
+        assert_eq!(cache.llms_txt("https://A.com:443").await.unwrap().content, "# A");
+
+        caches.invalidate(Some("https://a.com/")).await;
+        assert!(cache.llms_txt("https://a.com").await.is_none());
+        assert!(cache.llms_txt("https://A.com:443").await.is_none());
+        assert!(cache.llms_txt("https://B.com").await.is_some());
+        assert!(cache.list(&ListPage::default()).await.is_none());
+
+        // read before the change: not cached after it
+        cache
+            .insert_llms_txt(
+                generation,
+                "https://a.com".to_string(),
+                "https://a.com/".to_string(),
+                llms_txt("# Old A"),
+            )
+            .await;
+        assert!(cache.llms_txt("https://a.com").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nothing_cached_when_off() {
+        let cache = ReadCache::new(Duration::ZERO, 100);
+        assert!(cache.caches.is_none());
+        cache
+            .insert_llms_txt(
+                0,
+                "https://a.com".to_string(),
+                "https://a.com/".to_string(),
+                llms_txt("# A"),
+            )
+            .await;
+        assert!(cache.llms_txt("https://a.com").await.is_none());
+    }
+
+    fn response() -> LlmsTxtListResponse {
+        LlmsTxtListResponse {
+            items: vec![],
+            total: 0,
+            page: 1,
+            per_page: 50,
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Float4, Nullable, Text};
+use diesel_async::RunQueryDsl;
+use moka::future::Cache;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use core_ltx::db::DbPool;
+use core_ltx::llms::Embedder;
+use data_model_ltx::embeddings::{Neighbor, embeddings_available, nearest_llms_txt, related_llms_txt};
+use data_model_ltx::models::{
+    RelatedLlmTxtError, RelatedLlmsTxtResponse, RelatedSite, ResultData, ResultStatus, SearchError,
+};
+use data_model_ltx::schema::llms_txt;
+use data_model_ltx::search::SEARCH_CONFIG;
+use data_model_ltx::urls::normalize_url;
+
+const DEFAULT_SEARCH_LIMIT: u32 = 20;
+const MAX_SEARCH_LIMIT: u32 = 100;
+
+const DEFAULT_RELATED_LIMIT: u32 = 5;
+const MAX_RELATED_LIMIT: u32 = 20;
+
+/// Queries longer than this are rejected: they don't find more, and they cost more to parse.
+const MAX_QUERY_CHARS: usize = 500;
+
+/// Start and end of the matched words in Postgres' headlines. The worker removes control characters from the stored
+/// llms.txt files, so they can't be in one: they're replaced with `<mark>` tags once the rest is HTML-escaped.
+const HIGHLIGHT_START: char = '\u{2}';
+const HIGHLIGHT_END: char = '\u{3}';
+
+/// Embeds the semantic search queries: None when there's no `EMBEDDING_MODEL`. It's the worker's model, so that the
+/// queries and the llms.txt files are comparable.
+static EMBEDDER: LazyLock<Option<Embedder>> = LazyLock::new(Embedder::from_env);
+
+/// Semantic search queries whose embedding is kept at most: embedding one is a paid request to the embedding model.
+const QUERY_EMBEDDINGS_CAPACITY: u64 = 1_000;
+
+/// The embeddings of recent semantic search queries, by query. The model is fixed, so they never go stale.
+static QUERY_EMBEDDINGS: LazyLock<Cache<String, Arc<Vec<f32>>>> =
+    LazyLock::new(|| Cache::new(QUERY_EMBEDDINGS_CAPACITY));
+
+/// Query parameters of GET /api/search
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// What to search for, in web search syntax: words, `"quoted phrases"`, `or`, and `-excluded` words
+    pub q: String,
+    /// Number of websites, best match first: 20 when absent, at most 100
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A website whose latest llms.txt matches the query
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResult {
+    pub url: String,
+    /// The page's `<title>`, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// How well it matches: higher is better
+    pub rank: f32,
+    /// Passages of the llms.txt with the matched words, as HTML: the words are in `<mark>` tags, and everything else
+    /// is escaped
+    pub snippet: String,
+}
+
+/// Response payload for GET /api/search
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SearchResponse {
+    /// Best match first
+    pub results: Vec<SearchResult>,
+}
+
+/// Query parameters of GET /api/llm_txt/related
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RelatedQuery {
+    pub url: String,
+    /// Number of websites, most similar first: 5 when absent, at most 20
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// A website whose latest llms.txt is about what the query is about
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SemanticSearchResult {
+    pub url: String,
+    /// The page's `<title>`, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The page's meta description, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Cosine similarity of the query's and the llms.txt's embeddings: higher is closer, at most 1
+    pub similarity: f32,
+}
+
+/// Response payload for GET /api/search/semantic
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SemanticSearchResponse {
+    /// Most similar first
+    pub results: Vec<SemanticSearchResult>,
+}
+
+/// A matching record, before its snippet.
+#[derive(QueryableByName)]
+struct Match {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    job_id: Uuid,
+    #[diesel(sql_type = Text)]
+    url: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    title: Option<String>,
+    #[diesel(sql_type = Float4)]
+    rank: f32,
+}
+
+#[derive(QueryableByName)]
+struct Headline {
+    #[diesel(sql_type = BigInt)]
+    position: i64,
+    #[diesel(sql_type = Text)]
+    headline: String,
+}
+
+/// The query, trimmed, if it's one to search for.
+fn check_search_query(query: &SearchQuery) -> Result<(&str, u32), String> {
+    let q = query.q.trim();
+    if q.is_empty() {
+        return Err("q is empty".to_string());
+    }
+    if q.chars().count() > MAX_QUERY_CHARS {
+        return Err(format!("q is longer than {} characters", MAX_QUERY_CHARS));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        return Err(format!("limit must be between 1 and {}", MAX_SEARCH_LIMIT));
+    }
+    Ok((q, limit))
+}
+
+/// The headline as HTML: escaped, with the matched words in `<mark>` tags.
+fn highlight(headline: &str) -> String {
+    let mut html = String::with_capacity(headline.len());
+    for c in headline.chars() {
+        match c {
+            HIGHLIGHT_START => html.push_str("<mark>"),
+            HIGHLIGHT_END => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+// GET /api/search - Full-text search of the latest llms.txt of every website
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "llms.txt",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "The websites whose latest llms.txt (or URL) matches, best match first, with snippets", body = SearchResponse),
+        (status = 400, description = "The query is empty or too long, or the limit is out of range", body = SearchError),
+        (status = 500, description = "The server failed", body = SearchError),
+    )
+)]
+pub async fn get_search(
+    State(pool): State<DbPool>,
+    Query(query): Query<SearchQuery>,
+) -> Result<impl IntoResponse, SearchError> {
+    let (q, limit) = check_search_query(&query).map_err(SearchError::InvalidRequest)?;
+    let mut conn = pool.get().await?;
+
+    // Matching records that are their website's latest Ok one: the GIN index finds the matches, and only those are
+    // checked for newer records
+    let matches = diesel::sql_query(
+        "SELECT l.job_id, l.url, l.title, ts_rank_cd(l.search_vector, q.query) AS rank \
+         FROM llms_txt l, websearch_to_tsquery($1::regconfig, $2) AS q(query) \
+         WHERE l.result_status = 'ok' AND l.search_vector @@ q.query \
+         AND NOT EXISTS ( \
+             SELECT 1 FROM llms_txt newer \
+             WHERE newer.url_normalized = l.url_normalized AND newer.result_status = 'ok' AND newer.created_at > l.created_at \
+         ) \
+         ORDER BY rank DESC, l.url \
+         LIMIT $3",
+    )
+    .bind::<Text, _>(SEARCH_CONFIG)
+    .bind::<Text, _>(q)
+    .bind::<BigInt, _>(i64::from(limit))
+    .load::<Match>(&mut conn)
+    .await?;
+    if matches.is_empty() {
+        return Ok((StatusCode::OK, Json(SearchResponse { results: vec![] })));
+    }
+
+    // The snippets are made from the decompressed llms.txt files
+    let job_ids: Vec<Uuid> = matches.iter().map(|m| m.job_id).collect();
+    let mut llms_txts: HashMap<Uuid, ResultData> = llms_txt::table
+        .filter(llms_txt::job_id.eq_any(&job_ids))
+        .select((llms_txt::job_id, llms_txt::result_data))
+        .load::<(Uuid, ResultData)>(&mut conn)
+        .await?
+        .into_iter()
+        .collect();
+    let documents: Vec<String> = job_ids
+        .iter()
+        .map(|job_id| llms_txts.remove(job_id).map(ResultData::into_text).unwrap_or_default())
+        .collect();
+    let options = format!(
+        "StartSel=\"{}\", StopSel=\"{}\", MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=\" … \"",
+        HIGHLIGHT_START, HIGHLIGHT_END
+    );
+    let mut headlines: HashMap<i64, String> = diesel::sql_query(
+        "SELECT d.position, ts_headline($1::regconfig, d.document, websearch_to_tsquery($1::regconfig, $2), $3) \
+         AS headline \
+         FROM unnest($4::text[]) WITH ORDINALITY AS d(document, position)",
+    )
+    .bind::<Text, _>(SEARCH_CONFIG)
+    .bind::<Text, _>(q)
+    .bind::<Text, _>(options)
+    .bind::<Array<Text>, _>(documents)
+    .load::<Headline>(&mut conn)
+    .await?
+    .into_iter()
+    .map(|headline| (headline.position, headline.headline))
+    .collect();
+
+    let results: Vec<SearchResult> = matches
+        .into_iter()
+        .enumerate()
+        .map(|(index, m)| SearchResult {
+            url: m.url,
+            title: m.title,
+            rank: m.rank,
+            // ordinality starts at 1
+            snippet: highlight(&headlines.remove(&(index as i64 + 1)).unwrap_or_default()),
+        })
+        .collect();
+
+    tracing::trace!("Success: {} websites match '{}'", results.len(), q);
+    Ok((StatusCode::OK, Json(SearchResponse { results })))
+}
+
+// GET /api/search/semantic - Websites whose latest llms.txt is about the same topic as the query
+#[utoipa::path(
+    get,
+    path = "/api/search/semantic",
+    tag = "llms.txt",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "The websites whose latest llms.txt is the most similar to the query, most similar first", body = SemanticSearchResponse),
+        (status = 400, description = "The query is empty or too long, or the limit is out of range", body = SearchError),
+        (status = 503, description = "Semantic search is off: there's no EMBEDDING_MODEL, or the database has no pgvector", body = SearchError),
+        (status = 500, description = "The server failed", body = SearchError),
+    )
+)]
+pub async fn get_semantic_search(
+    State(pool): State<DbPool>,
+    Query(query): Query<SearchQuery>,
+) -> Result<impl IntoResponse, SearchError> {
+    let (q, limit) = check_search_query(&query).map_err(SearchError::InvalidRequest)?;
+    let Some(embedder) = EMBEDDER.as_ref() else {
+        return Err(SearchError::NotEnabled("no EMBEDDING_MODEL is set".to_string()));
+    };
+    let mut conn = pool.get().await?;
+    if !embeddings_available(&mut conn).await? {
+        return Err(SearchError::NotEnabled(
+            "the database has no pgvector extension".to_string(),
+        ));
+    }
+    // the connection isn't held while the embedding model works
+    drop(conn);
+
+    // concurrent requests for the same query share one embedding request
+    let embedding = QUERY_EMBEDDINGS
+        .try_get_with(q.to_string(), async {
+            embedder
+                .embed(&[q])
+                .await
+                .map_err(|e| format!("Failed to embed the query: {}", e))?
+                .pop()
+                .map(Arc::new)
+                .ok_or_else(|| "The embedding model returned no embedding".to_string())
+        })
+        .await
+        .map_err(|e| SearchError::Unknown(e.to_string()))?;
+
+    let mut conn = pool.get().await?;
+    let neighbors = nearest_llms_txt(&mut conn, &embedder.model_name, &embedding, i64::from(limit)).await?;
+    let results: Vec<SemanticSearchResult> = neighbors
+        .into_iter()
+        .map(|neighbor| SemanticSearchResult {
+            url: neighbor.url,
+            title: neighbor.title,
+            description: neighbor.description,
+            similarity: neighbor.similarity,
+        })
+        .collect();
+
+    tracing::trace!("Success: {} websites are similar to '{}'", results.len(), q);
+    Ok((StatusCode::OK, Json(SemanticSearchResponse { results })))
+}
+
+// GET /api/llm_txt/related - Other websites whose latest llms.txt is about the same topics as the URL's
+#[utoipa::path(
+    get,
+    path = "/api/llm_txt/related",
+    tag = "llms.txt",
+    params(RelatedQuery),
+    responses(
+        (status = 200, description = "The other websites whose latest llms.txt is the most similar to the URL's, most similar first", body = RelatedLlmsTxtResponse),
+        (status = 400, description = "The limit is out of range", body = RelatedLlmTxtError),
+        (status = 404, description = "The URL has no successful llms.txt", body = RelatedLlmTxtError),
+        (status = 503, description = "Semantic search is off: there's no EMBEDDING_MODEL, or the database has no pgvector", body = RelatedLlmTxtError),
+        (status = 500, description = "The server failed", body = RelatedLlmTxtError),
+    )
+)]
+pub async fn get_related_llm_txt(
+    State(pool): State<DbPool>,
+    Query(query): Query<RelatedQuery>,
+) -> Result<impl IntoResponse, RelatedLlmTxtError> {
+    let limit = query.limit.unwrap_or(DEFAULT_RELATED_LIMIT);
+    if !(1..=MAX_RELATED_LIMIT).contains(&limit) {
+        return Err(RelatedLlmTxtError::InvalidRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_RELATED_LIMIT
+        )));
+    }
+    let Some(embedder) = EMBEDDER.as_ref() else {
+        return Err(RelatedLlmTxtError::NotEnabled("no EMBEDDING_MODEL is set".to_string()));
+    };
+    let mut conn = pool.get().await?;
+    if !embeddings_available(&mut conn).await? {
+        return Err(RelatedLlmTxtError::NotEnabled(
+            "the database has no pgvector extension".to_string(),
+        ));
+    }
+
+    // 404 when there's no llms.txt at all, rather than nothing related to it
+    llms_txt::table
+        .filter(llms_txt::url_normalized.eq(normalize_url(&query.url)))
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .select(llms_txt::job_id)
+        .first::<Uuid>(&mut conn)
+        .await?;
+
+    let neighbors = related_llms_txt(&mut conn, &embedder.model_name, &query.url, i64::from(limit)).await?;
+    let related: Vec<RelatedSite> = neighbors.into_iter().map(related_site).collect();
+
+    tracing::trace!("Success: {} websites are related to '{}'", related.len(), query.url);
+    Ok((
+        StatusCode::OK,
+        Json(RelatedLlmsTxtResponse {
+            url: query.url,
+            related,
+        }),
+    ))
+}
+
+fn related_site(neighbor: Neighbor) -> RelatedSite {
+    RelatedSite {
+        url: neighbor.url,
+        title: neighbor.title,
+        description: neighbor.description,
+        similarity: neighbor.similarity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_search_query() {
+        let query = |q: &str, limit| SearchQuery {
+            q: q.to_string(),
+            limit,
+        };
+        assert_eq!(check_search_query(&query(" rust ", None)), Ok(("rust", 20)));
+        assert_eq!(check_search_query(&query("rust", Some(100))), Ok(("rust", 100)));
+        assert!(check_search_query(&query("  ", None)).is_err());
+        assert!(check_search_query(&query("rust", Some(0))).is_err());
+        assert!(check_search_query(&query("rust", Some(101))).is_err());
+        assert!(check_search_query(&query(&"a".repeat(501), None)).is_err());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(
+            highlight("a \u{2}Rust\u{3} <crate> & \"more\""),
+            "a <mark>Rust</mark> &lt;crate&gt; &amp; &quot;more&quot;"
+        );
+    }
+}
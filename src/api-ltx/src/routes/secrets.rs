@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{DomainCredential, RotateSecretsError, RotateSecretsResponse, TotpSecret, Webhook};
+use data_model_ltx::schema::{domains, totp, webhooks};
+use data_model_ltx::secrets::Keyring;
+
+// POST /api/admin/secrets/rotate - Re-encrypt every stored secret with the current key of SECRETS_KEYS
+#[utoipa::path(
+    post,
+    path = "/api/admin/secrets/rotate",
+    tag = "admin",
+    responses(
+        (status = 200, description = "How many secrets were re-encrypted", body = RotateSecretsResponse),
+        (status = 503, description = "The server has no (valid) SECRETS_KEYS", body = RotateSecretsError),
+        (status = 500, description = "The server failed", body = RotateSecretsError),
+    )
+)]
+pub async fn post_rotate_secrets(State(pool): State<DbPool>) -> Result<impl IntoResponse, RotateSecretsError> {
+    let keyring = Keyring::from_env()?;
+    let mut conn = pool.get().await?;
+
+    let mut response = RotateSecretsResponse {
+        key_id: keyring.current().id().to_string(),
+        ..Default::default()
+    };
+
+    let stored = domains::table
+        .order(domains::domain.asc())
+        .select(DomainCredential::as_select())
+        .load::<DomainCredential>(&mut conn)
+        .await?;
+    for domain in stored {
+        match keyring.rotate(&domain.credential) {
+            Ok(Some(rotated)) => {
+                // unless it was changed since it was read: then it's encrypted with the current key already
+                let updated = diesel::update(
+                    domains::table
+                        .find(&domain.domain)
+                        .filter(domains::credential.eq(&domain.credential)),
+                )
+                .set(domains::credential.eq(rotated))
+                .execute(&mut conn)
+                .await?;
+                count_rotated(&mut response, updated);
+            }
+            Ok(None) => response.up_to_date += 1,
+            Err(error) => {
+                tracing::error!("Can't rotate the credential of '{}': {}", domain.domain, error);
+                response.failed.push(format!("domains/{}", domain.domain));
+            }
+        }
+    }
+
+    let stored = webhooks::table
+        .order(webhooks::webhook_id.asc())
+        .select(Webhook::as_select())
+        .load::<Webhook>(&mut conn)
+        .await?;
+    for webhook in stored {
+        match keyring.rotate(&webhook.secret) {
+            Ok(Some(rotated)) => {
+                let updated = diesel::update(
+                    webhooks::table
+                        .find(webhook.webhook_id)
+                        .filter(webhooks::secret.eq(&webhook.secret)),
+                )
+                .set(webhooks::secret.eq(rotated))
+                .execute(&mut conn)
+                .await?;
+                count_rotated(&mut response, updated);
+            }
+            Ok(None) => response.up_to_date += 1,
+            Err(error) => {
+                tracing::error!("Can't rotate the secret of webhook {}: {}", webhook.webhook_id, error);
+                response.failed.push(format!("webhooks/{}", webhook.webhook_id));
+            }
+        }
+    }
+
+    let stored = totp::table
+        .select(TotpSecret::as_select())
+        .first::<TotpSecret>(&mut conn)
+        .await
+        .optional()?;
+    if let Some(second_factor) = stored {
+        match keyring.rotate(&second_factor.secret) {
+            Ok(Some(rotated)) => {
+                let updated = diesel::update(totp::table.filter(totp::secret.eq(&second_factor.secret)))
+                    .set(totp::secret.eq(rotated))
+                    .execute(&mut conn)
+                    .await?;
+                count_rotated(&mut response, updated);
+            }
+            Ok(None) => response.up_to_date += 1,
+            Err(error) => {
+                tracing::error!("Can't rotate the TOTP secret of the login: {}", error);
+                response.failed.push("totp".to_string());
+            }
+        }
+    }
+
+    tracing::info!(
+        "Secrets: {} re-encrypted with key '{}', {} already were, {} failed",
+        response.rotated,
+        response.key_id,
+        response.up_to_date,
+        response.failed.len()
+    );
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Counts a secret whose re-encrypted value was written with `updated` rows: when none were, it was changed
+/// (or removed) since it was read, so it's left as the writer stored it.
+fn count_rotated(response: &mut RotateSecretsResponse, updated: usize) {
+    if updated == 0 {
+        response.up_to_date += 1;
+    } else {
+        response.rotated += 1;
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Double, Nullable, Text, Timestamptz};
+use diesel_async::RunQueryDsl;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use core_ltx::db::DbPool;
+use core_ltx::llms::{MODELS, ModelInfo};
+use data_model_ltx::models::{AppError, GetLlmTxtError, ResultData, ResultStatus, UrlPayload};
+use data_model_ltx::schema::llms_txt;
+
+/// The supported models, and which of them the stored llms.txt files were generated with.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelStats {
+    pub models: Vec<ModelUsage>,
+    /// Latest llms.txt files generated with models that aren't supported anymore, by model.
+    pub unsupported: BTreeMap<String, u64>,
+    /// Latest llms.txt files without a recorded model: imported from the website, or generated before models were
+    /// recorded.
+    pub unknown: u64,
+    /// Things to act on, e.g. llms.txt files to re-generate because their model is deprecated.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ModelUsage {
+    #[serde(flatten)]
+    pub info: ModelInfo,
+    /// Websites whose latest llms.txt was generated with this model.
+    pub llms_txt_count: u64,
+}
+
+/// Tallies the model of each website's latest llms.txt.
+pub fn model_stats(models: impl IntoIterator<Item = Option<String>>) -> ModelStats {
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    let mut unknown = 0;
+    for model in models {
+        match model {
+            Some(model) => *counts.entry(model).or_default() += 1,
+            None => unknown += 1,
+        }
+    }
+
+    let models: Vec<ModelUsage> = MODELS
+        .iter()
+        .map(|info| ModelUsage {
+            info: *info,
+            llms_txt_count: counts.remove(info.model).unwrap_or_default(),
+        })
+        .collect();
+    let mut warnings: Vec<String> = models
+        .iter()
+        .filter(|usage| usage.info.deprecated && usage.llms_txt_count > 0)
+        .map(|usage| {
+            format!(
+                "{} llms.txt file(s) were generated with the deprecated model {}: re-generate them with a supported one",
+                usage.llms_txt_count, usage.info.model
+            )
+        })
+        .collect();
+    warnings.extend(counts.iter().map(|(model, count)| {
+        format!(
+            "{} llms.txt file(s) were generated with {}, which is no longer supported: re-generate them with a supported model",
+            count, model
+        )
+    }));
+
+    ModelStats {
+        models,
+        unsupported: counts,
+        unknown,
+        warnings,
+    }
+}
+
+// GET /api/stats/models - The supported models and how many websites' llms.txt each generated
+#[utoipa::path(
+    get,
+    path = "/api/stats/models",
+    tag = "llms.txt",
+    responses(
+        (status = 200, description = "The supported models, and how many websites' llms.txt each generated", body = ModelStats),
+    )
+)]
+pub async fn get_model_stats(State(pool): State<DbPool>) -> Result<impl IntoResponse, AppError> {
+    let mut conn = pool.get().await?;
+
+    // the model of the latest successful generation of each website
+    let latest: Vec<(String, Option<String>)> = llms_txt::table
+        .filter(llms_txt::result_status.eq(ResultStatus::Ok))
+        .distinct_on(llms_txt::url_normalized)
+        .order((llms_txt::url_normalized.asc(), llms_txt::created_at.desc()))
+        .select((llms_txt::url, llms_txt::model))
+        .load(&mut conn)
+        .await?;
+
+    let stats = model_stats(latest.into_iter().map(|(_, model)| model));
+    for warning in stats.warnings.iter() {
+        tracing::warn!("{}", warning);
+    }
+
+    tracing::trace!("Success: model stats of {} websites", stats_total(&stats));
+    Ok((StatusCode::OK, Json(stats)))
+}
+
+fn stats_total(stats: &ModelStats) -> u64 {
+    stats.models.iter().map(|usage| usage.llms_txt_count).sum::<u64>()
+        + stats.unsupported.values().sum::<u64>()
+        + stats.unknown
+}
+
+/// How the generations of a website went, archived ones included.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UrlStats {
+    pub url: String,
+    /// Every generation of the website, successful or not
+    pub generations: u64,
+    pub successes: u64,
+    pub failures: u64,
+    /// When the latest successful generation finished
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// When the latest failed generation finished, and why it failed
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub last_failure_reason: Option<String>,
+    /// Average time, in seconds, from the creation of a generation's job to its result: queued time included.
+    /// Imported llms.txt files, which weren't generated here, aren't counted.
+    pub average_duration_s: Option<f64>,
+}
+
+/// The generations of a website, aggregated.
+#[derive(QueryableByName)]
+struct UrlStatsRow {
+    #[diesel(sql_type = BigInt)]
+    generations: i64,
+    #[diesel(sql_type = BigInt)]
+    successes: i64,
+    #[diesel(sql_type = BigInt)]
+    failures: i64,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    last_success_at: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Nullable<Timestamptz>)]
+    last_failure_at: Option<DateTime<Utc>>,
+    #[diesel(sql_type = Nullable<Text>)]
+    last_failure: Option<ResultData>,
+    #[diesel(sql_type = Nullable<Double>)]
+    average_duration_s: Option<f64>,
+}
+
+// GET /api/stats - How the generations of a URL went
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "llms.txt",
+    params(UrlPayload),
+    responses(
+        (status = 200, description = "The number of generations of the URL, how many failed, when the latest succeeded and failed, and how long they take", body = UrlStats),
+        (status = 404, description = "The URL has no generation", body = GetLlmTxtError),
+        (status = 500, description = "The server failed", body = GetLlmTxtError),
+    )
+)]
+pub async fn get_url_stats(
+    State(pool): State<DbPool>,
+    Query(payload): Query<UrlPayload>,
+) -> Result<impl IntoResponse, GetLlmTxtError> {
+    let mut conn = pool.get().await?;
+
+    // One pass over the website's records, in llms_txt and in its archive. A result that was stored with its job
+    // (an imported llms.txt) took no time to generate.
+    let row: UrlStatsRow = diesel::sql_query(
+        "WITH records AS ( \
+             SELECT job_id, result_data, result_status, created_at FROM llms_txt \
+             WHERE url_normalized = normalize_url($1) \
+             UNION ALL \
+             SELECT job_id, result_data, result_status, created_at FROM llms_txt_archive \
+             WHERE url_normalized = normalize_url($1) \
+         ) \
+         SELECT \
+             COUNT(*) AS generations, \
+             COUNT(*) FILTER (WHERE r.result_status = 'ok') AS successes, \
+             COUNT(*) FILTER (WHERE r.result_status = 'error') AS failures, \
+             MAX(r.created_at) FILTER (WHERE r.result_status = 'ok') AS last_success_at, \
+             MAX(r.created_at) FILTER (WHERE r.result_status = 'error') AS last_failure_at, \
+             (SELECT f.result_data FROM records f WHERE f.result_status = 'error' \
+              ORDER BY f.created_at DESC LIMIT 1) AS last_failure, \
+             CAST(AVG(EXTRACT(EPOCH FROM r.created_at - j.created_at)) FILTER (WHERE r.created_at > j.created_at) \
+                 AS float8) AS average_duration_s \
+         FROM records r LEFT JOIN job_state j ON j.job_id = r.job_id",
+    )
+    .bind::<Text, _>(&payload.url)
+    .get_result(&mut conn)
+    .await?;
+    if row.generations == 0 {
+        return Err(GetLlmTxtError::NotGenerated);
+    }
+
+    let stats = UrlStats {
+        url: payload.url,
+        generations: row.generations as u64,
+        successes: row.successes as u64,
+        failures: row.failures as u64,
+        last_success_at: row.last_success_at,
+        last_failure_at: row.last_failure_at,
+        last_failure_reason: row.last_failure.map(ResultData::into_text),
+        average_duration_s: row.average_duration_s,
+    };
+    tracing::trace!(
+        "Success: stats of {} generation(s) of '{}'",
+        stats.generations,
+        stats.url
+    );
+    Ok((StatusCode::OK, Json(stats)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_stats_warns_about_deprecated_and_unsupported_models() {
+        let stats = model_stats(vec![
+            Some("gpt-5-mini".to_string()),
+            Some("gpt-4o".to_string()),
+            Some("gpt-4o".to_string()),
+            Some("gpt-3.5-turbo".to_string()),
+            None,
+        ]);
+
+        let count = |model: &str| {
+            stats
+                .models
+                .iter()
+                .find(|usage| usage.info.model == model)
+                .unwrap()
+                .llms_txt_count
+        };
+        assert_eq!(count("gpt-5-mini"), 1);
+        assert_eq!(count("gpt-4o"), 2);
+        assert_eq!(count("gpt-5"), 0);
+        assert_eq!(stats.unsupported.get("gpt-3.5-turbo"), Some(&1));
+        assert_eq!(stats.unknown, 1);
+        assert_eq!(stats_total(&stats), 5);
+
+        assert_eq!(stats.warnings.len(), 2);
+        assert!(stats.warnings[0].starts_with("2 llms.txt file(s) were generated with the deprecated model gpt-4o"));
+        assert!(stats.warnings[1].contains("gpt-3.5-turbo, which is no longer supported"));
+    }
+}
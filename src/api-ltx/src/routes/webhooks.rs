@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Json, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use url::Url;
+
+use core_ltx::db::DbPool;
+use data_model_ltx::models::{
+    CreatedWebhookResponse, Webhook, WebhookDeliveriesPayload, WebhookDeliveriesResponse, WebhookDelivery,
+    WebhookDeliveryAttempt, WebhookDeliveryHistory, WebhookError, WebhookIdPayload, WebhookPayload, WebhookSummary,
+    WebhooksResponse,
+};
+use data_model_ltx::schema::{webhook_deliveries, webhook_delivery_attempts, webhooks};
+use data_model_ltx::secrets::Keyring;
+
+/// Deliveries shown when the request doesn't say how many.
+const DEFAULT_DELIVERIES: u32 = 50;
+/// Most deliveries shown at once.
+const MAX_DELIVERIES: u32 = 500;
+
+/// The webhook URL, if it's an http(s) URL.
+fn check_webhook_url(url: &str) -> Result<Url, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("'{}' isn't a valid URL: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("'{}' isn't an http(s) URL", url));
+    }
+    Ok(parsed)
+}
+
+// GET /api/admin/webhooks - The webhooks (never their secrets)
+#[utoipa::path(
+    get,
+    path = "/api/admin/webhooks",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every webhook", body = WebhooksResponse),
+        (status = 500, description = "The server failed", body = WebhookError),
+    )
+)]
+pub async fn get_webhooks(State(pool): State<DbPool>) -> Result<impl IntoResponse, WebhookError> {
+    let mut conn = pool.get().await?;
+    let webhooks = webhooks::table
+        .order((webhooks::created_at.asc(), webhooks::webhook_id.asc()))
+        .select(Webhook::as_select())
+        .load::<Webhook>(&mut conn)
+        .await?;
+    Ok((
+        StatusCode::OK,
+        Json(WebhooksResponse {
+            webhooks: webhooks.into_iter().map(WebhookSummary::from).collect(),
+        }),
+    ))
+}
+
+// POST /api/admin/webhooks - Send events to a URL, signed with a new secret that's returned this once
+#[utoipa::path(
+    post,
+    path = "/api/admin/webhooks",
+    tag = "admin",
+    request_body = WebhookPayload,
+    responses(
+        (status = 201, description = "The webhook, with its signing secret", body = CreatedWebhookResponse),
+        (status = 400, description = "The URL isn't an http(s) URL", body = WebhookError),
+        (status = 503, description = "The server has no (valid) SECRETS_KEYS", body = WebhookError),
+        (status = 500, description = "The server failed", body = WebhookError),
+    )
+)]
+pub async fn post_webhook(
+    State(pool): State<DbPool>,
+    Json(payload): Json<WebhookPayload>,
+) -> Result<impl IntoResponse, WebhookError> {
+    let url = check_webhook_url(&payload.url).map_err(WebhookError::InvalidRequest)?;
+    let keyring = Keyring::from_env()?;
+    let mut conn = pool.get().await?;
+
+    let secret = Webhook::generate_secret();
+    let webhook = Webhook::new(url.to_string(), &secret, &keyring, Utc::now());
+    let created = diesel::insert_into(webhooks::table)
+        .values(&webhook)
+        .returning(Webhook::as_returning())
+        .get_result::<Webhook>(&mut conn)
+        .await?;
+
+    tracing::info!("Webhooks: sending events to '{}' ({})", created.url, created.webhook_id);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhookResponse {
+            webhook: WebhookSummary::from(created),
+            secret,
+        }),
+    ))
+}
+
+// DELETE /api/admin/webhooks - Stop sending events to a webhook, forgetting its deliveries
+#[utoipa::path(
+    delete,
+    path = "/api/admin/webhooks",
+    tag = "admin",
+    params(WebhookIdPayload),
+    responses(
+        (status = 204, description = "The webhook was removed"),
+        (status = 404, description = "There's no webhook with the webhook_id", body = WebhookError),
+        (status = 500, description = "The server failed", body = WebhookError),
+    )
+)]
+pub async fn delete_webhook(
+    State(pool): State<DbPool>,
+    Query(payload): Query<WebhookIdPayload>,
+) -> Result<impl IntoResponse, WebhookError> {
+    let mut conn = pool.get().await?;
+
+    // its deliveries and their attempts are deleted with it (ON DELETE CASCADE)
+    let deleted = diesel::delete(webhooks::table.find(payload.webhook_id))
+        .execute(&mut conn)
+        .await?;
+    if deleted == 0 {
+        return Err(WebhookError::UnknownId);
+    }
+
+    tracing::info!("Webhooks: removed {}", payload.webhook_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/admin/webhooks/deliveries - The latest deliveries to a webhook, with every attempt at them
+#[utoipa::path(
+    get,
+    path = "/api/admin/webhooks/deliveries",
+    tag = "admin",
+    params(WebhookDeliveriesPayload),
+    responses(
+        (status = 200, description = "The latest deliveries, most recent first", body = WebhookDeliveriesResponse),
+        (status = 400, description = "The number of deliveries is out of range", body = WebhookError),
+        (status = 404, description = "There's no webhook with the webhook_id", body = WebhookError),
+        (status = 500, description = "The server failed", body = WebhookError),
+    )
+)]
+pub async fn get_webhook_deliveries(
+    State(pool): State<DbPool>,
+    Query(payload): Query<WebhookDeliveriesPayload>,
+) -> Result<impl IntoResponse, WebhookError> {
+    let limit = payload.limit.unwrap_or(DEFAULT_DELIVERIES);
+    if limit == 0 || limit > MAX_DELIVERIES {
+        return Err(WebhookError::InvalidRequest(format!(
+            "limit must be between 1 and {}",
+            MAX_DELIVERIES
+        )));
+    }
+    let mut conn = pool.get().await?;
+
+    let exists = webhooks::table
+        .find(payload.webhook_id)
+        .select(webhooks::webhook_id)
+        .first::<uuid::Uuid>(&mut conn)
+        .await
+        .optional()?;
+    if exists.is_none() {
+        return Err(WebhookError::UnknownId);
+    }
+
+    let deliveries = webhook_deliveries::table
+        .filter(webhook_deliveries::webhook_id.eq(payload.webhook_id))
+        .order((
+            webhook_deliveries::created_at.desc(),
+            webhook_deliveries::delivery_id.asc(),
+        ))
+        .limit(limit as i64)
+        .select(WebhookDelivery::as_select())
+        .load::<WebhookDelivery>(&mut conn)
+        .await?;
+    let ids: Vec<uuid::Uuid> = deliveries.iter().map(|delivery| delivery.delivery_id).collect();
+    let attempts = webhook_delivery_attempts::table
+        .filter(webhook_delivery_attempts::delivery_id.eq_any(&ids))
+        .order((
+            webhook_delivery_attempts::delivery_id.asc(),
+            webhook_delivery_attempts::attempt.asc(),
+        ))
+        .select(WebhookDeliveryAttempt::as_select())
+        .load::<WebhookDeliveryAttempt>(&mut conn)
+        .await?;
+
+    let deliveries = deliveries
+        .into_iter()
+        .map(|delivery| WebhookDeliveryHistory {
+            attempts: attempts
+                .iter()
+                .filter(|attempt| attempt.delivery_id == delivery.delivery_id)
+                .cloned()
+                .collect(),
+            delivery,
+        })
+        .collect();
+    Ok((StatusCode::OK, Json(WebhookDeliveriesResponse { deliveries })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_webhook_url() {
+        assert!(check_webhook_url("https://hooks.example.com/llms").is_ok());
+        assert!(check_webhook_url(" http://localhost:9000/hook ").is_ok());
+        assert!(check_webhook_url("ftp://hooks.example.com/").is_err());
+        assert!(check_webhook_url("hooks.example.com").is_err());
+        assert!(check_webhook_url("").is_err());
+    }
+}
@@ -1,36 +1,83 @@
 //! Integration tests for API route handlers
 //!
 //! Tests key endpoints:
-//! - GET /api/llm_txt - Retrieve llms.txt content
+//! - GET /ready - Readiness check
+//! - GET /api/llm_txt - Retrieve llms.txt content, as JSON or as is, with its ETag, by URL however it's spelled
 //! - POST /api/llm_txt - Create generation job
+//! - POST /api/llm_txt/batch - Create generation jobs for many URLs
 //! - POST /api/update - Create update job
 //! - PUT /api/llm_txt - Create job (new or update)
-//! - GET /api/list - List all llms.txt
+//! - GET /api/llm_txt/history - Every generation of a URL
+//! - DELETE /api/llm_txt - Remove a website
+//! - GET /api/list - List llms.txt, page by page, by their previews, filtered and sorted
+//! - GET /api/search - Full-text search of llms.txt
+//! - GET /api/search/semantic - Semantic search, when it's off
+//! - GET /api/llm_txt/related - Related websites, when semantic search is off
+//! - GET /api/export - Every llms.txt and its metadata, as a streamed tar.gz archive or NDJSON
+//! - POST /api/import - Load an export, skipping the llms.txt files already there
 //! - POST /api/status - Get job status
+//! - POST /api/status/batch - Get the statuses of many jobs at once
 //! - GET /api/job - Get job details
+//! - GET /api/job/logs - Get a job's logs
+//! - POST /api/job/cancel - Cancel a queued or running job
+//! - POST /api/job/retry - Queue a failed job again
+//! - POST /api/admin/jobs/purge_stale - Requeue or fail the jobs whose worker died
+//! - GET /api/jobs - Every job, filtered by status and URL, page by page
 //! - GET /api/jobs/in_progress - List in-progress jobs
+//! - GET /api/ws - Live updates of jobs and results
+//! - GET /metrics - Prometheus metrics
+//! - GET /.well-known/llms-directory - Directory of indexed websites
+//! - GET /llms/{domain}/llms.txt - Latest llms.txt of a domain, as a file
+//! - GET /llms.txt - The index's own llms.txt
+//! - POST /mcp - MCP tools
+//! - POST/GET /api/admin/regenerate_all - Re-generation campaigns
+//! - GET /api/stats - How the generations of a URL went
+//! - GET /api/stats/models - Supported models and their use
+//! - GET/PUT/DELETE /api/admin/domains - Credentials of domains behind a login
+//! - POST /api/admin/secrets/rotate - Re-encrypt stored secrets with the current key
+//! - GET/POST/DELETE /api/admin/webhooks - Signed webhooks, and GET /api/admin/webhooks/deliveries
+//! - GET/POST/DELETE /api/admin/api_keys - API keys, and authenticating with them
+//! - POST /api/auth/login - Throttling of failed logins
+//! - POST/PUT /api/llm_txt, POST /api/update - Rate limiting per client
+//! - GET /api/auth/totp, POST /api/auth/totp/{enroll,confirm,recovery_codes,disable} - Two-factor login
+//! - GET /api/openapi.json, GET /api/docs - OpenAPI spec and its Swagger UI
+//! - Compression of responses (gzip, brotli)
 
 use axum::{
     body::Body,
     http::{Request, StatusCode, header},
 };
-use core_ltx::normalize_html;
+use core_ltx::{FailureKind, SiteMetadata, llms::prompt_version, normalize_html};
 use data_model_ltx::{
-    models::{JobIdPayload, JobIdResponse, JobKind, JobStatus, LlmTxtResponse, LlmsTxtListResponse, UrlPayload},
-    test_helpers::{TestDbGuard, clean_test_db, create_completed_test_job, create_test_job, test_db_pool},
+    models::{
+        ApiKeysResponse, BatchJobRequestPayload, BatchJobResponse, BatchJobResult, BatchStatusPayload,
+        BatchStatusResponse, Crawl, CrawlSchedule, CreatedApiKeyResponse, CreatedWebhookResponse, DeleteLlmTxtResponse,
+        Detail, DomainPolicyResponse, DomainSummary, DomainsResponse, ExportRecord, ImportResponse, JobDetailsResponse,
+        JobIdPayload, JobIdResponse, JobKind, JobLogsResponse, JobStatus, JobsListResponse, LiveEvent, LlmTxtResponse,
+        LlmsTxtHistoryResponse, LlmsTxtListResponse, LlmsTxtSource, PostLlmTxtError, PurgeStaleJobsResponse,
+        RegenerateAllPayload, RegenerationCampaign, RotateSecretsResponse, UrlPayload, WebhookDeliveriesResponse,
+        WebhooksResponse,
+    },
+    test_helpers::{
+        TestDbGuard, clean_test_db, clear_preview, create_completed_test_job, create_failed_test_job,
+        create_failed_webhook_delivery, create_test_job, get_job_by_id, get_jobs_with_status, set_detail,
+        set_failure_kind, set_job_log, set_job_started_at, set_model, set_prompt_version, set_site_metadata,
+        test_db_pool, update_job_status,
+    },
 };
 use http_body_util::BodyExt;
 use tokio::sync::Mutex;
 use tower::ServiceExt;
 
 use api_ltx::routes::router;
+use api_ltx::routes::search::SearchResponse;
 
 // =============================================================================
 
 /// Helper to create a router with test database (does NOT clean DB)
 async fn test_router() -> axum::Router {
     let pool = test_db_pool().await;
-    router(None).with_state(pool)
+    router(None, pool)
 }
 
 /// Helper to parse JSON response body
@@ -94,59 +141,107 @@ async fn test_get_llm_txt_success() {
 }
 
 #[tokio::test]
-async fn test_get_llm_txt_not_found() {
+async fn test_get_llm_txt_raw() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
+    let url = "https://example.com";
+    let content = "# Test\n\n> Description\n\n- [Link](/)";
+    create_completed_test_job(
+        &pool,
+        url,
+        content,
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
     let app = test_router().await;
+    let uri = format!("/api/llm_txt?url={}", urlencoding::encode(url));
+    let raw = |uri: String, accept: &str| {
+        Request::builder()
+            .uri(uri)
+            .header(header::ACCEPT, accept)
+            .body(Body::empty())
+            .unwrap()
+    };
 
-    let request = Request::builder()
-        .uri("/api/llm_txt?url=https://nonexistent.com")
-        .body(Body::empty())
+    for request in [
+        raw(uri.clone(), "text/plain"),
+        raw(uri.clone(), "text/markdown, */*"),
+        raw(format!("{}&format=raw", uri), "application/json"),
+    ] {
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+        assert_eq!(response.headers()[header::VARY], "accept");
+        let bytes = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(bytes, content.as_bytes());
+    }
+
+    // the format parameter wins over the Accept header
+    let response = app
+        .clone()
+        .oneshot(raw(format!("{}&format=json", uri), "text/plain"))
+        .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmTxtResponse = response_json(response.into_body()).await;
+    assert_eq!(body.content, content);
 
-    let response = app.oneshot(request).await.unwrap();
-    assert!(response.status().is_client_error() || response.status().is_server_error());
+    // errors stay JSON
+    let response = app
+        .oneshot(raw(
+            format!(
+                "/api/llm_txt?url={}&format=raw",
+                urlencoding::encode("https://unknown.com")
+            ),
+            "text/plain",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_generated");
 }
 
-//
-// POST /api/llm_txt tests
-//
-
 #[tokio::test]
-async fn test_post_llm_txt_creates_job() {
+async fn test_get_llm_txt_compressed() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let app = test_router().await;
-
-    let payload = UrlPayload {
-        url: "https://unique-test-url.com".to_string(),
-    };
+    let url = "https://example.com";
+    let content = format!(
+        "# Test\n\n> Description\n\n## Docs\n\n{}",
+        "- [Page](https://example.com/page): A page\n".repeat(100)
+    );
+    let (_, record) = create_completed_test_job(
+        &pool,
+        url,
+        &content,
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    assert!(record.result_data.is_compressed());
 
+    let app = test_router().await;
     let request = Request::builder()
-        .method("POST")
-        .uri("/api/llm_txt")
-        .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .uri(format!("/api/llm_txt?url={}", urlencoding::encode(url)))
+        .body(Body::empty())
         .unwrap();
-
     let response = app.oneshot(request).await.unwrap();
-    // TODO: Known bug - in_progress_jobs returns Ok([]) instead of Err(NotFound), causing 409 even for new URLs
-    assert_eq!(response.status(), StatusCode::CREATED);
-
-    let body: JobIdResponse = response_json(response.into_body()).await;
-    assert!(!body.job_id.is_nil());
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmTxtResponse = response_json(response.into_body()).await;
+    assert_eq!(body.content, content);
 }
 
 #[tokio::test]
-async fn test_post_llm_txt_fails_if_already_generated() {
+async fn test_get_llm_txt_etag() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
@@ -154,74 +249,198 @@ async fn test_post_llm_txt_fails_if_already_generated() {
     clean_test_db(&pool).await;
 
     let url = "https://example.com";
-    create_completed_test_job(
-        &pool,
-        url,
-        "# Existing",
-        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let (job, _) = create_completed_test_job(&pool, url, "# Test\n\n> Description", &html).await;
+
+    let get = |if_none_match: Option<&str>| {
+        let request = Request::builder().uri(format!("/api/llm_txt?url={}", urlencoding::encode(url)));
+        match if_none_match {
+            Some(etag) => request.header(header::IF_NONE_MATCH, etag),
+            None => request,
+        }
+        .body(Body::empty())
+        .unwrap()
+    };
 
-    let app = test_router().await;
+    let response = test_router().await.oneshot(get(None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    assert_eq!(etag, format!("\"{}\"", job.job_id));
+
+    // the client's copy is the latest
+    let response = test_router().await.oneshot(get(Some(&etag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(bytes.is_empty());
+
+    // a new generation has an ETag of its own
+    let (newer, _) = create_completed_test_job(&pool, url, "# Test\n\n> Newer description", &html).await;
+    let response = test_router().await.oneshot(get(Some(&etag))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::ETAG],
+        format!("\"{}\"", newer.job_id).as_str()
+    );
+    let body: LlmTxtResponse = response_json(response.into_body()).await;
+    assert_eq!(body.content, "# Test\n\n> Newer description");
+}
 
-    let payload = UrlPayload { url: url.to_string() };
+#[tokio::test]
+async fn test_get_llm_txt_url_spellings() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
 
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://example.com/docs?b=2&a=1", "# Docs", &html).await;
+    create_test_job(&pool, "https://other.com", JobKind::New, JobStatus::Queued).await;
+
+    for spelling in [
+        "https://example.com/docs?b=2&a=1",
+        "https://Example.COM:443/docs?a=1&b=2",
+        "HTTPS://example.com/docs?a=1&b=2&",
+    ] {
+        let request = Request::builder()
+            .uri(format!("/api/llm_txt?url={}", urlencoding::encode(spelling)))
+            .body(Body::empty())
+            .unwrap();
+        let response = test_router().await.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{} isn't found", spelling);
+        let body: LlmTxtResponse = response_json(response.into_body()).await;
+        assert_eq!(body.content, "# Docs");
+    }
+
+    // not the same URL
+    for spelling in [
+        "https://example.com/Docs?a=1&b=2",
+        "https://example.com:8443/docs?a=1&b=2",
+    ] {
+        let request = Request::builder()
+            .uri(format!("/api/llm_txt?url={}", urlencoding::encode(spelling)))
+            .body(Body::empty())
+            .unwrap();
+        let response = test_router().await.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND, "{} is found", spelling);
+    }
+
+    // one job in progress per URL, however it's spelled
     let request = Request::builder()
         .method("POST")
         .uri("/api/llm_txt")
         .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .body(Body::from(
+            serde_json::to_vec(&UrlPayload {
+                url: "https://OTHER.com:443/".to_string(),
+            })
+            .unwrap(),
+        ))
         .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-    assert!(response.status().is_client_error());
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "jobs_in_progress");
 }
 
-//
-// POST /api/update tests
-//
-
 #[tokio::test]
-async fn test_post_update_creates_job() {
+async fn test_responses_are_compressed() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let url = "https://example.com";
-    create_completed_test_job(
-        &pool,
-        url,
-        "# Existing",
-        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let content = format!(
+        "# Docs\n\n> The docs\n\n{}",
+        "- [Page](https://example.com/page): A page\n".repeat(50)
+    );
+    create_completed_test_job(&pool, "https://example.com", &content, &html).await;
+
+    let get = |uri: &str, accept_encoding: Option<&str>| {
+        let mut request = Request::builder().uri(uri);
+        if let Some(accept_encoding) = accept_encoding {
+            request = request.header(header::ACCEPT_ENCODING, accept_encoding);
+        }
+        request.body(Body::empty()).unwrap()
+    };
+    let uri = format!("/api/llm_txt?url={}", urlencoding::encode("https://example.com"));
 
-    let app = test_router().await;
+    // gzip when it's accepted
+    let response = test_router().await.oneshot(get(&uri, Some("gzip"))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let mut json = String::new();
+    std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(&bytes[..]), &mut json).unwrap();
+    let body: LlmTxtResponse = serde_json::from_str(&json).unwrap();
+    assert_eq!(body.content, content);
 
-    let payload = UrlPayload { url: url.to_string() };
+    // the one the client prefers
+    let response = test_router()
+        .await
+        .oneshot(get(&uri, Some("gzip;q=0.5, br")))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[header::CONTENT_ENCODING], "br");
+
+    // as is otherwise
+    let response = test_router().await.oneshot(get(&uri, None)).await.unwrap();
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    let body: LlmTxtResponse = response_json(response.into_body()).await;
+    assert_eq!(body.content, content);
+
+    // the export is a tar.gz already
+    let response = test_router()
+        .await
+        .oneshot(get("/api/export", Some("gzip, br")))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+}
+
+#[tokio::test]
+async fn test_get_llm_txt_not_found() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let app = test_router().await;
 
     let request = Request::builder()
-        .method("POST")
-        .uri("/api/update")
-        .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .uri("/api/llm_txt?url=https://nonexistent.com")
+        .body(Body::empty())
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(response.status().is_client_error() || response.status().is_server_error());
+}
 
-    let body: JobIdResponse = response_json(response.into_body()).await;
-    assert!(!body.job_id.is_nil());
+//
+// GET /ready tests
+//
+
+#[tokio::test]
+async fn test_ready_with_database() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let request = Request::builder().uri("/ready").body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
 }
 
 //
-// PUT /api/llm_txt tests
+// POST /api/llm_txt tests
 //
 
 #[tokio::test]
-async fn test_put_llm_txt_creates_new_job() {
+async fn test_post_llm_txt_creates_job() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
@@ -231,17 +450,18 @@ async fn test_put_llm_txt_creates_new_job() {
     let app = test_router().await;
 
     let payload = UrlPayload {
-        url: "https://newsite.com".to_string(),
+        url: "https://unique-test-url.com".to_string(),
     };
 
     let request = Request::builder()
-        .method("PUT")
+        .method("POST")
         .uri("/api/llm_txt")
         .header(header::CONTENT_TYPE, "application/json")
         .body(Body::from(serde_json::to_vec(&payload).unwrap()))
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
+    // TODO: Known bug - in_progress_jobs returns Ok([]) instead of Err(NotFound), causing 409 even for new URLs
     assert_eq!(response.status(), StatusCode::CREATED);
 
     let body: JobIdResponse = response_json(response.into_body()).await;
@@ -249,203 +469,3478 @@ async fn test_put_llm_txt_creates_new_job() {
 }
 
 #[tokio::test]
-async fn test_put_llm_txt_creates_update_job_when_exists() {
+async fn test_post_llm_txt_with_deadline() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let url = "https://example.com";
-    create_completed_test_job(
-        &pool,
-        url,
-        "# Existing",
-        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
-
     let app = test_router().await;
 
-    let payload = UrlPayload { url: url.to_string() };
-
     let request = Request::builder()
-        .method("PUT")
+        .method("POST")
         .uri("/api/llm_txt")
         .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .body(Body::from(
+            r#"{"url":"https://demo.example.com","expires_at":"2030-01-02T03:04:05Z"}"#,
+        ))
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
     assert_eq!(response.status(), StatusCode::CREATED);
-}
 
-//
-// GET /api/list tests
-//
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(
+        job.expires_at.map(|deadline| deadline.to_rfc3339()),
+        Some("2030-01-02T03:04:05+00:00".to_string())
+    );
+}
 
 #[tokio::test]
-async fn test_get_list_empty() {
+async fn test_post_llm_txt_with_model() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let app = test_router().await;
-
-    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    let post = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
 
-    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
-    assert_eq!(body.items.len(), 0);
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://demo.example.com","model":"gpt-9000"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "unknown_model");
+    assert_eq!(body["details"], "gpt-9000");
+    assert!(get_jobs_with_status(&pool, JobStatus::Queued).await.is_empty());
+
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://demo.example.com","model":"gpt-5-nano"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.model.as_deref(), Some("gpt-5-nano"));
 }
 
 #[tokio::test]
-async fn test_get_list_returns_results() {
+async fn test_post_llm_txt_with_detail() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    create_completed_test_job(
-        &pool,
-        "https://site1.com",
-        "# Site 1",
-        &normalize_html("<html>1</html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
-    create_completed_test_job(
-        &pool,
-        "https://site2.com",
-        "# Site 2",
-        &normalize_html("<html>2</html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
-    create_completed_test_job(
-        &pool,
-        "https://site3.com",
-        "# Site 3",
-        &normalize_html("<html>3</html>").expect("Failed to parse & clean HTML"),
-    )
-    .await;
-
-    let app = test_router().await;
+    let post = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
 
-    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://demo.example.com","detail":"exhaustive"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
 
-    let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://brief.example.com","detail":"brief"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.detail(), Detail::Brief);
 
-    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
-    assert_eq!(body.items.len(), 3);
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://standard.example.com"}"#))
+        .await
+        .unwrap();
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.detail(), Detail::Standard);
 }
 
-//
-// POST /api/status tests
-//
-
 #[tokio::test]
-async fn test_get_status_success() {
+async fn test_update_keeps_detail() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Queued).await;
-
-    let app = test_router().await;
+    let url = "https://example.com";
+    let (job, _) = create_completed_test_job(
+        &pool,
+        url,
+        "# Existing",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    set_detail(&pool, job.job_id, Some("comprehensive")).await;
+
+    let update = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/update")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
 
-    let payload = JobIdPayload { job_id: job.job_id };
+    // the list reports the level it was generated at
+    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.items[0].detail, Some(Detail::Comprehensive));
 
-    // Note: GET with JSON body is unusual but that's how the API is designed
-    let request = Request::builder()
-        .method("GET")
-        .uri("/api/status")
-        .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+    let response = test_router()
+        .await
+        .oneshot(update(format!(r#"{{"url":"{}"}}"#, url)))
+        .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.detail(), Detail::Comprehensive);
 
-    let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    let response = test_router()
+        .await
+        .oneshot(update(format!(r#"{{"url":"{}","detail":"brief"}}"#, url)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.detail(), Detail::Brief);
 }
 
-//
-// GET /api/job tests
-//
-
 #[tokio::test]
-async fn test_get_job_success() {
+async fn test_put_llm_txt_with_crawl() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Queued).await;
-
+    let request = Request::builder()
+        .method("PUT")
+        .uri("/api/llm_txt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            r#"{"url":"https://example.com","crawl":{"max_depth":1,"max_pages":100000}}"#,
+        ))
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    // cut down to the most pages a crawl downloads
+    assert_eq!(
+        job.crawl(),
+        Some(Crawl {
+            max_depth: 1,
+            max_pages: 500
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_domain_policy() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let request = |method: &str, uri: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let submit = |body: &'static str| request("POST", "/api/llm_txt", body);
+
+    // never on a private network
+    let response = test_router()
+        .await
+        .oneshot(submit(r#"{"url":"http://169.254.169.254/latest/meta-data/"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "domain_not_allowed");
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/admin/domain_policy",
+            r#"{"pattern":"https://example.com/","rule":"block"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/admin/domain_policy",
+            r#"{"pattern":"*.Blocked.example","rule":"block"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = test_router()
+        .await
+        .oneshot(submit(r#"{"url":"https://docs.blocked.example/"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // with an allowed pattern, only what matches one is allowed
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/admin/domain_policy",
+            r#"{"pattern":"example.com","rule":"allow"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = test_router()
+        .await
+        .oneshot(submit(r#"{"url":"https://example.org"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = test_router()
+        .await
+        .oneshot(submit(r#"{"url":"https://example.com"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 1);
+
+    let response = test_router()
+        .await
+        .oneshot(request("GET", "/api/admin/domain_policy", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: DomainPolicyResponse = response_json(response.into_body()).await;
+    let patterns: Vec<(&str, &str)> = body
+        .entries
+        .iter()
+        .map(|entry| (entry.pattern.as_str(), entry.rule.as_str()))
+        .collect();
+    assert_eq!(patterns, vec![("*.blocked.example", "block"), ("example.com", "allow")]);
+    assert!(!body.private_hosts_allowed);
+
+    let delete_uri = "/api/admin/domain_policy?pattern=example.com";
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = test_router()
+        .await
+        .oneshot(submit(r#"{"url":"https://example.org"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_domain_credentials() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let request = |method: &str, uri: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let put_basic = || {
+        request(
+            "PUT",
+            "/api/admin/domains",
+            r#"{"domain":"Docs.Internal.example","credential":{"type":"basic","username":"indexer","password":"hunter2"}}"#,
+        )
+    };
+
+    // credentials are only stored encrypted
+    unsafe {
+        std::env::remove_var("SECRETS_KEYS");
+        std::env::remove_var("SECRETS_KEYS_FILE");
+    }
+    let response = test_router().await.oneshot(put_basic()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "key_not_configured");
+
+    unsafe {
+        std::env::set_var("SECRETS_KEYS", "k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=");
+    }
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/admin/domains",
+            r#"{"domain":"https://docs.internal.example/","credential":{"type":"bearer","token":"t0k3n"}}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = test_router().await.oneshot(put_basic()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let created: DomainSummary = response_json(response.into_body()).await;
+    assert_eq!(created.domain, "docs.internal.example");
+    assert_eq!(created.credential_kind, "basic");
+
+    // changing the credential keeps the domain's creation time
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/admin/domains",
+            r#"{"domain":"docs.internal.example","credential":{"type":"cookie","cookie":"session=abc"}}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: DomainSummary = response_json(response.into_body()).await;
+    assert_eq!(updated.credential_kind, "cookie");
+    assert_eq!(
+        updated.created_at.timestamp_micros(),
+        created.created_at.timestamp_micros()
+    );
+
+    // never shows the credential
+    let response = test_router()
+        .await
+        .oneshot(request("GET", "/api/admin/domains", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(!String::from_utf8_lossy(&bytes).contains("session=abc"));
+    let body: DomainsResponse = serde_json::from_slice(&bytes).unwrap();
+    let domains: Vec<(&str, &str)> = body
+        .domains
+        .iter()
+        .map(|domain| (domain.domain.as_str(), domain.credential_kind.as_str()))
+        .collect();
+    assert_eq!(domains, vec![("docs.internal.example", "cookie")]);
+
+    // rotating to a new key: the old one is kept to decrypt until then
+    unsafe {
+        std::env::set_var(
+            "SECRETS_KEYS",
+            "k2:ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=,k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=",
+        );
+    }
+    let rotate = || request("POST", "/api/admin/secrets/rotate", "");
+    let response = test_router().await.oneshot(rotate()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: RotateSecretsResponse = response_json(response.into_body()).await;
+    assert_eq!((body.key_id.as_str(), body.rotated, body.up_to_date), ("k2", 1, 0));
+    assert!(body.failed.is_empty());
+
+    // then the old key can go
+    unsafe {
+        std::env::set_var("SECRETS_KEYS", "k2:ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=");
+    }
+    let response = test_router().await.oneshot(rotate()).await.unwrap();
+    let body: RotateSecretsResponse = response_json(response.into_body()).await;
+    assert_eq!((body.rotated, body.up_to_date), (0, 1));
+
+    // without the key a secret was encrypted with, it can't be rotated
+    unsafe {
+        std::env::set_var("SECRETS_KEYS", "k3:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=");
+    }
+    let response = test_router().await.oneshot(rotate()).await.unwrap();
+    let body: RotateSecretsResponse = response_json(response.into_body()).await;
+    assert_eq!(body.failed, vec!["domains/docs.internal.example".to_string()]);
+
+    let delete_uri = "/api/admin/domains?domain=docs.internal.example";
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_crawl_schedule() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let request = |method: &str, uri: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let get_uri = "/api/crawl_schedule?url=https%3A%2F%2Fexample.com";
+
+    let response = test_router().await.oneshot(request("GET", get_uri, "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // too often
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/crawl_schedule",
+            r#"{"url":"https://example.com","interval_s":60}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "invalid_request");
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/crawl_schedule",
+            r#"{"url":"https://example.com","interval_s":86400,"crawl":{"max_pages":20}}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: CrawlSchedule = response_json(response.into_body()).await;
+    assert_eq!(created.crawl().max_pages, 20);
+    assert!(created.is_due(chrono::Utc::now()), "a new schedule crawls right away");
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "PUT",
+            "/api/crawl_schedule",
+            r#"{"url":"https://example.com","interval_s":604800}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = test_router().await.oneshot(request("GET", get_uri, "")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let schedule: CrawlSchedule = response_json(response.into_body()).await;
+    assert_eq!(schedule.interval_s, 604_800);
+    assert_eq!(schedule.crawl(), Crawl::default());
+    assert_eq!(
+        schedule.created_at.timestamp_micros(),
+        created.created_at.timestamp_micros()
+    );
+
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", get_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", get_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_section() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://example.com";
+    create_completed_test_job(
+        &pool,
+        url,
+        "# Example\n\n> An example website.\n\n## Docs\n\n- [Guide](https://example.com/docs/guide)\n",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
+    let request = |method: &str, uri: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // not one of its sections
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/update",
+            r#"{"url":"https://example.com","section":"Pricing"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "invalid_section");
+
+    // not the same website
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/update",
+            r#"{"url":"https://example.com","section":"Docs","section_url":"https://other.example.org/docs"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // a new llms.txt has no sections yet
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/llm_txt",
+            r#"{"url":"https://new.example.com","section":"Docs"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(get_jobs_with_status(&pool, JobStatus::Queued).await.is_empty());
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/update",
+            r#"{"url":"https://example.com","section":"docs","section_url":"https://example.com/docs"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.kind, JobKind::Update);
+    assert_eq!(job.section.as_deref(), Some("Docs"));
+    assert_eq!(job.section_url.as_deref(), Some("https://example.com/docs"));
+}
+
+#[tokio::test]
+async fn test_post_llm_txt_backpressure() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let post = |body: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // a short queue isn't reported
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://first.example.com"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert!(body.get("queue_position").is_none());
+
+    // back up the queue past the default threshold of 100 jobs, with nothing finished lately
+    for i in 0..100 {
+        create_test_job(
+            &pool,
+            &format!("https://site{}.example.com", i),
+            JobKind::New,
+            JobStatus::Queued,
+        )
+        .await;
+    }
+
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://demo.example.com","low_priority":true}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "queue_busy");
+    assert_eq!(body["details"]["queue_position"], 102);
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 101);
+
+    let response = test_router()
+        .await
+        .oneshot(post(r#"{"url":"https://demo.example.com"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    assert_eq!(body.queue_position, Some(102));
+    // nothing finished lately to estimate the wait from
+    assert_eq!(body.estimated_start_at, None);
+}
+
+#[tokio::test]
+async fn test_post_llm_txt_fails_if_already_generated() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://example.com";
+    create_completed_test_job(
+        &pool,
+        url,
+        "# Existing",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
     let app = test_router().await;
 
+    let payload = UrlPayload { url: url.to_string() };
+
     let request = Request::builder()
-        .uri(format!("/api/job?job_id={}", job.job_id))
-        .body(Body::empty())
+        .method("POST")
+        .uri("/api/llm_txt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
+    assert!(response.status().is_client_error());
+}
+
+#[tokio::test]
+async fn test_post_and_put_llm_txt_canonical_url() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let request = |method: &str, url: &str| {
+        Request::builder()
+            .method(method)
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&UrlPayload { url: url.to_string() }).unwrap(),
+            ))
+            .unwrap()
+    };
+    let app = test_router().await;
+
+    let response = app
+        .clone()
+        .oneshot(request("POST", " https://Example.com./docs?#install"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    let job = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(job.url, "https://example.com/docs");
+
+    // another spelling of the same page is the same website
+    for method in ["POST", "PUT"] {
+        let response = app
+            .clone()
+            .oneshot(request(method, "HTTPS://EXAMPLE.COM:443/docs#usage"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT, "{}", method);
+        let body: serde_json::Value = response_json(response.into_body()).await;
+        assert_eq!(body["details"], serde_json::json!([job.job_id]));
+    }
+
+    for method in ["POST", "PUT"] {
+        for url in ["ftp://example.com/docs", "example.com", "mailto:someone@example.com"] {
+            let response = app.clone().oneshot(request(method, url)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{} {}", method, url);
+            let body: serde_json::Value = response_json(response.into_body()).await;
+            assert_eq!(body["error"], "invalid_url");
+        }
+    }
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_post_llm_txt_batch() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let generated = "https://generated.example.com";
+    create_completed_test_job(
+        &pool,
+        generated,
+        "# Existing",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    let running = "https://running.example.com";
+    let running_job = create_test_job(&pool, running, JobKind::New, JobStatus::Running).await;
+
+    let batch = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/llm_txt/batch")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+    let app = test_router().await;
+
+    let response = app
+        .clone()
+        .oneshot(batch(serde_json::json!({
+            "urls": ["https://one.example.com", generated, running, "https://two.example.com", "https://one.example.com"],
+            "detail": "brief",
+        })))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::OK);
+    let body: BatchJobResponse = response_json(response.into_body()).await;
+    // the repeated URL got a single job
+    assert_eq!(body.results.len(), 4);
+    for url in ["https://one.example.com", "https://two.example.com"] {
+        let Some(BatchJobResult::Created(created)) = body.results.get(url) else {
+            panic!("no job for {}: {:?}", url, body.results.get(url));
+        };
+        let job = get_job_by_id(&pool, created.job_id).await.expect("job wasn't created");
+        // in canonical form
+        assert_eq!(job.url, format!("{}/", url));
+        assert_eq!(job.detail, "brief");
+    }
+    assert!(matches!(
+        body.results[generated],
+        BatchJobResult::Failed(PostLlmTxtError::AlreadyGenerated)
+    ));
+    let BatchJobResult::Failed(PostLlmTxtError::JobsInProgress(job_ids)) = &body.results[running] else {
+        panic!("{} has a running job: {:?}", running, body.results[running]);
+    };
+    assert_eq!(job_ids, &vec![running_job.job_id]);
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 2);
+
+    // the whole batch is rejected: no URLs, too many, or an unknown model
+    let too_many: Vec<String> = (0..=BatchJobRequestPayload::MAX_URLS)
+        .map(|i| format!("https://site{}.example.com", i))
+        .collect();
+    for body in [
+        serde_json::json!({"urls": []}),
+        serde_json::json!({"urls": too_many}),
+        serde_json::json!({"urls": ["https://three.example.com"], "model": "no-such-model"}),
+    ] {
+        let response = app.clone().oneshot(batch(body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 2);
 }
 
 //
-// GET /api/jobs/in_progress tests
+// POST /api/update tests
 //
 
 #[tokio::test]
-async fn test_get_in_progress_jobs_empty() {
+async fn test_post_update_creates_job() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
+    let url = "https://example.com";
+    create_completed_test_job(
+        &pool,
+        url,
+        "# Existing",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
     let app = test_router().await;
 
+    let payload = UrlPayload { url: url.to_string() };
+
     let request = Request::builder()
-        .uri("/api/jobs/in_progress")
-        .body(Body::empty())
+        .method("POST")
+        .uri("/api/update")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::CREATED);
 
-    let body: Vec<data_model_ltx::models::JobState> = response_json(response.into_body()).await;
-    assert_eq!(body.len(), 0);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    assert!(!body.job_id.is_nil());
 }
 
+//
+// PUT /api/llm_txt tests
+//
+
 #[tokio::test]
-async fn test_get_in_progress_jobs_returns_queued() {
+async fn test_put_llm_txt_creates_new_job() {
     let _db = TestDbGuard::acquire().await;
     let _guard = TEST_MUTEX.lock().await;
 
     let pool = test_db_pool().await;
     clean_test_db(&pool).await;
 
-    create_test_job(&pool, "https://site1.com", JobKind::New, JobStatus::Queued).await;
-    create_test_job(&pool, "https://site2.com", JobKind::New, JobStatus::Queued).await;
-
     let app = test_router().await;
 
+    let payload = UrlPayload {
+        url: "https://newsite.com".to_string(),
+    };
+
     let request = Request::builder()
-        .uri("/api/jobs/in_progress")
-        .body(Body::empty())
+        .method("PUT")
+        .uri("/api/llm_txt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
         .unwrap();
 
     let response = app.oneshot(request).await.unwrap();
-    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.status(), StatusCode::CREATED);
 
-    let body: Vec<data_model_ltx::models::JobState> = response_json(response.into_body()).await;
-    assert_eq!(body.len(), 2);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    assert!(!body.job_id.is_nil());
+}
+
+#[tokio::test]
+async fn test_put_llm_txt_creates_update_job_when_exists() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://example.com";
+    create_completed_test_job(
+        &pool,
+        url,
+        "# Existing",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
+    let app = test_router().await;
+
+    let payload = UrlPayload { url: url.to_string() };
+
+    let request = Request::builder()
+        .method("PUT")
+        .uri("/api/llm_txt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_put_llm_txt_conflicts_with_in_progress_job() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://example.com";
+    let queued = create_test_job(&pool, url, JobKind::New, JobStatus::Queued).await;
+
+    let payload = UrlPayload { url: url.to_string() };
+    let request = Request::builder()
+        .method("PUT")
+        .uri("/api/llm_txt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "jobs_in_progress");
+    assert_eq!(body["details"], serde_json::json!([queued.job_id]));
+}
+
+#[tokio::test]
+async fn test_concurrent_puts_create_one_job() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let put = |url: &str| {
+        Request::builder()
+            .method("PUT")
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::to_vec(&UrlPayload { url: url.to_string() }).unwrap(),
+            ))
+            .unwrap()
+    };
+    let url = "https://race.example.com";
+    let (first, second) = tokio::join!(
+        test_router().await.oneshot(put(url)),
+        test_router().await.oneshot(put(url)),
+    );
+    let mut statuses = vec![first.unwrap().status(), second.unwrap().status()];
+    statuses.sort();
+    assert_eq!(statuses, vec![StatusCode::CREATED, StatusCode::CONFLICT]);
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Queued).await.len(), 1);
+}
+
+//
+// /api/admin/regenerate_all tests
+//
+
+#[tokio::test]
+async fn test_regenerate_all_campaign() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    // no summary blockquote: scores 80
+    let poor = "# Example\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n";
+    let clean = "# Example\n\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n";
+    create_completed_test_job(&pool, "https://docs.example.com", poor, &html).await;
+    create_completed_test_job(&pool, "https://www.example.com", poor, &html).await;
+    create_completed_test_job(&pool, "https://example.com", clean, &html).await;
+    create_completed_test_job(&pool, "https://other.org", poor, &html).await;
+    // already being re-generated
+    let queued = create_test_job(&pool, "https://docs.example.com", JobKind::Update, JobStatus::Queued).await;
+
+    let post = |payload: &RegenerateAllPayload| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/admin/regenerate_all")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(payload).unwrap()))
+            .unwrap()
+    };
+
+    let invalid = RegenerateAllPayload {
+        quality_below: Some(101),
+        ..Default::default()
+    };
+    let response = test_router().await.oneshot(post(&invalid)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let payload = RegenerateAllPayload {
+        domain: Some("example.com".to_string()),
+        quality_below: Some(90),
+        batch_size: Some(1),
+        batch_interval_s: Some(0),
+        ..Default::default()
+    };
+    let response = test_router().await.oneshot(post(&payload)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let started: RegenerationCampaign = response_json(response.into_body()).await;
+    assert_eq!(started.total, 2);
+
+    let mut campaign = started.clone();
+    for _ in 0..100 {
+        let request = Request::builder()
+            .uri(format!("/api/admin/regenerate_all?campaign_id={}", started.campaign_id))
+            .body(Body::empty())
+            .unwrap();
+        let response = test_router().await.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        campaign = response_json(response.into_body()).await;
+        if campaign.finished_at.is_some() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert!(campaign.finished_at.is_some(), "Campaign didn't finish");
+    assert_eq!((campaign.enqueued, campaign.skipped, campaign.failed), (1, 1, 0));
+
+    let queued_jobs = get_jobs_with_status(&pool, JobStatus::Queued).await;
+    let mut queued_urls: Vec<&str> = queued_jobs.iter().map(|job| job.url.as_str()).collect();
+    queued_urls.sort();
+    assert_eq!(queued_urls, vec!["https://docs.example.com", "https://www.example.com"]);
+    assert!(queued_jobs.iter().any(|job| job.job_id == queued.job_id));
+
+    let request = Request::builder()
+        .uri(format!(
+            "/api/admin/regenerate_all?campaign_id={}",
+            uuid::Uuid::new_v4()
+        ))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_regenerate_all_outdated_prompt() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://current.example.com", "# Current", &html).await;
+    let (old, _) = create_completed_test_job(&pool, "https://old.example.com", "# Old", &html).await;
+    set_prompt_version(&pool, old.job_id, Some("0123456789ab")).await;
+
+    let payload = RegenerateAllPayload {
+        outdated_prompt: true,
+        batch_interval_s: Some(0),
+        ..Default::default()
+    };
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/admin/regenerate_all")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let campaign: RegenerationCampaign = response_json(response.into_body()).await;
+    assert_eq!(campaign.total, 1);
+    assert!(campaign.outdated_prompt);
+}
+
+//
+// DELETE /api/llm_txt tests
+//
+
+#[tokio::test]
+async fn test_delete_llm_txt() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://stale.example.com";
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let (first, _) = create_completed_test_job(&pool, url, "# Stale", &html).await;
+    set_job_log(&pool, first.job_id, "INFO generated").await;
+    create_completed_test_job(&pool, url, "# Stale, updated", &html).await;
+    create_test_job(&pool, url, JobKind::Update, JobStatus::Failure).await;
+    let (other, _) = create_completed_test_job(&pool, "https://other.example.com", "# Other", &html).await;
+
+    let delete = |url: &str| {
+        Request::builder()
+            .method("DELETE")
+            .uri(format!("/api/llm_txt?url={}", url))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // not while a job would write its result afterwards
+    let running = create_test_job(&pool, url, JobKind::Update, JobStatus::Running).await;
+    let response = test_router().await.oneshot(delete(url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "jobs_in_progress");
+    assert_eq!(body["details"][0], running.job_id.to_string());
+    update_job_status(&pool, running.job_id, JobStatus::Failure).await;
+
+    // its crawl schedule goes too, however its URL is spelled
+    let schedule = Request::builder()
+        .method("PUT")
+        .uri("/api/crawl_schedule")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"url":"https://STALE.example.com/","interval_s":86400}"#))
+        .unwrap();
+    let response = test_router().await.oneshot(schedule).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = test_router().await.oneshot(delete(url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: DeleteLlmTxtResponse = response_json(response.into_body()).await;
+    assert_eq!((body.deleted_llms_txt, body.deleted_jobs), (2, 4));
+    assert!(body.deleted_crawl_schedule);
+
+    assert!(get_job_by_id(&pool, first.job_id).await.is_none());
+    let request = Request::builder()
+        .uri(format!("/api/llm_txt?url={}", url))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    // other websites stay
+    assert!(get_job_by_id(&pool, other.job_id).await.is_some());
+
+    let response = test_router().await.oneshot(delete(url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_generated");
+}
+
+#[tokio::test]
+async fn test_get_llm_txt_history() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://history.example.com";
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let (first, _) = create_completed_test_job(&pool, url, "# First", &html).await;
+    set_model(&pool, first.job_id, Some("gpt-5-mini")).await;
+    set_detail(&pool, first.job_id, Some("brief")).await;
+    let (failed, _) = create_failed_test_job(&pool, url, "LLM timed out", Some(html.clone())).await;
+    let (latest, _) = create_completed_test_job(&pool, url, "# Latest", &html).await;
+    create_completed_test_job(&pool, "https://other.example.com", "# Other", &html).await;
+
+    let history = |url: &str| {
+        Request::builder()
+            .uri(format!("/api/llm_txt/history?url={}", url))
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = test_router().await.oneshot(history(url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmsTxtHistoryResponse = response_json(response.into_body()).await;
+    assert_eq!(body.url, url);
+    let job_ids: Vec<_> = body.versions.iter().map(|version| version.job_id).collect();
+    assert_eq!(job_ids, vec![latest.job_id, failed.job_id, first.job_id]);
+    assert!(body.versions[0].error.is_none());
+    assert_eq!(body.versions[1].error.as_deref(), Some("LLM timed out"));
+    assert_eq!(body.versions[2].model.as_deref(), Some("gpt-5-mini"));
+    assert_eq!(body.versions[2].detail, Some(Detail::Brief));
+
+    let raw: serde_json::Value = serde_json::to_value(&body).unwrap();
+    assert_eq!(raw["versions"][0]["result_status"], "Ok");
+    assert_eq!(raw["versions"][1]["result_status"], "Error");
+
+    let response = test_router()
+        .await
+        .oneshot(history("https://never.example.com"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_generated");
+}
+
+//
+// GET /api/list tests
+//
+
+#[tokio::test]
+async fn test_get_list_empty() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let app = test_router().await;
+
+    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.items.len(), 0);
+}
+
+#[tokio::test]
+async fn test_get_list_returns_results() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    create_completed_test_job(
+        &pool,
+        "https://site1.com",
+        "# Site 1",
+        &normalize_html("<html>1</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    create_completed_test_job(
+        &pool,
+        "https://site2.com",
+        "# Site 2",
+        &normalize_html("<html>2</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    create_completed_test_job(
+        &pool,
+        "https://site3.com",
+        "# Site 3",
+        &normalize_html("<html>3</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
+    let app = test_router().await;
+
+    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.items.len(), 3);
+    assert_eq!((body.total, body.page, body.per_page), (3, 1, 50));
+}
+
+#[tokio::test]
+async fn test_get_list_pages() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    for (url, content) in [
+        ("https://site3.com", "# Site 3"),
+        ("https://site1.com", "# Site 1"),
+        ("https://site2.com", "# Site 2"),
+        ("https://site2.com", "# Site 2, updated"),
+    ] {
+        let html = normalize_html(&format!("<html>{}</html>", content)).expect("Failed to parse & clean HTML");
+        create_completed_test_job(&pool, url, content, &html).await;
+    }
+
+    let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    // in URL order, one website per page, each with its most recent llms.txt
+    let response = test_router()
+        .await
+        .oneshot(list("/api/list?page=2&per_page=1"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    assert_eq!((body.total, body.page, body.per_page), (3, 2, 1));
+    assert_eq!(body.page_count(), 3);
+    assert!(body.has_next_page());
+    let items: Vec<(&str, &str)> = body
+        .items
+        .iter()
+        .map(|item| (item.url.as_str(), item.preview.as_str()))
+        .collect();
+    assert_eq!(items, vec![("https://site2.com", "# Site 2, updated")]);
+
+    let response = test_router()
+        .await
+        .oneshot(list("/api/list?page=2&per_page=2"))
+        .await
+        .unwrap();
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    let urls: Vec<&str> = body.items.iter().map(|item| item.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://site3.com"]);
+    assert!(!body.has_next_page());
+
+    // past the last page: no websites, but still the total
+    let response = test_router().await.oneshot(list("/api/list?page=9")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    assert!(body.items.is_empty());
+    assert_eq!(body.total, 3);
+
+    for uri in ["/api/list?page=0", "/api/list?per_page=0", "/api/list?per_page=501"] {
+        let response = test_router().await.oneshot(list(uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        let body: serde_json::Value = response_json(response.into_body()).await;
+        assert_eq!(body["error"], "invalid_request");
+    }
+}
+
+#[tokio::test]
+async fn test_get_list_previews() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let lines: Vec<String> = (1..=30)
+        .map(|i| format!("- [Page {i}](https://example.com/{i}): A page"))
+        .collect();
+    let long = format!("# Long\n{}", lines.join("\n"));
+    create_completed_test_job(&pool, "https://long.com", &long, &html).await;
+    create_completed_test_job(&pool, "https://short.com", "# Short\n\n> Short", &html).await;
+    // stored compressed before previews existed: the preview is computed when it's listed
+    let compressed = format!("# Old\n{}", lines.join("\n").repeat(10));
+    let (old, record) = create_completed_test_job(&pool, "https://old.com", &compressed, &html).await;
+    assert!(record.result_data.is_compressed());
+    clear_preview(&pool, old.job_id).await;
+
+    let list = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(list("/api/list")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    let items = body["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert!(items.iter().all(|item| item.get("llm_txt").is_none()));
+
+    let (long_item, old_item, short_item) = (&items[0], &items[1], &items[2]);
+    assert_eq!(long_item["url"], "https://long.com");
+    assert_eq!(long_item["preview"].as_str().unwrap().lines().count(), 20);
+    assert!(long.starts_with(long_item["preview"].as_str().unwrap()));
+    assert_eq!(long_item["truncated"], true);
+    assert_eq!(old_item["url"], "https://old.com");
+    assert_eq!(old_item["preview"].as_str().unwrap().lines().count(), 20);
+    assert_eq!(old_item["truncated"], true);
+    assert_eq!(short_item["preview"], "# Short\n\n> Short");
+    assert_eq!(short_item["truncated"], false);
+
+    // the whole llms.txt, when it's asked for
+    let response = test_router().await.oneshot(list("/api/list?full=true")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+    let contents: Vec<Option<&str>> = body.items.iter().map(|item| item.llm_txt.as_deref()).collect();
+    assert_eq!(
+        contents,
+        vec![
+            Some(long.as_str()),
+            Some(compressed.as_str()),
+            Some("# Short\n\n> Short")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_get_list_filters() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://example.com/blog", "# Blog", &html).await;
+    create_completed_test_job(&pool, "https://other.com", "# Other", &html).await;
+    let since = chrono::Utc::now();
+    create_completed_test_job(&pool, "https://docs.example.com", "# Docs", &html).await;
+    // not a subdomain of example.com
+    create_completed_test_job(&pool, "https://example.com.evil.io", "# Evil", &html).await;
+    create_failed_test_job(
+        &pool,
+        "https://broken.example.com",
+        "LLM call failed",
+        Some(html.clone()),
+    )
+    .await;
+
+    let urls = |uri: String| async move {
+        let request = Request::builder().uri(&uri).body(Body::empty()).unwrap();
+        let response = test_router().await.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", uri);
+        let body: LlmsTxtListResponse = response_json(response.into_body()).await;
+        assert_eq!(body.total as usize, body.items.len(), "{}", uri);
+        body.items
+            .into_iter()
+            .map(|item| (item.url, item.preview))
+            .collect::<Vec<(String, String)>>()
+    };
+    let only_urls = |items: Vec<(String, String)>| items.into_iter().map(|(url, _)| url).collect::<Vec<_>>();
+
+    assert_eq!(
+        only_urls(urls("/api/list?domain=Example.com.".to_string()).await),
+        vec!["https://docs.example.com", "https://example.com/blog"]
+    );
+    let since = urlencoding::encode(&since.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)).into_owned();
+    assert_eq!(
+        only_urls(urls(format!("/api/list?created_after={}&sort=url_desc", since)).await),
+        vec!["https://example.com.evil.io", "https://docs.example.com"]
+    );
+    assert_eq!(
+        only_urls(urls(format!("/api/list?created_before={}", since)).await),
+        vec!["https://example.com/blog", "https://other.com"]
+    );
+    assert_eq!(
+        only_urls(urls("/api/list?sort=created_at_desc".to_string()).await),
+        vec![
+            "https://example.com.evil.io",
+            "https://docs.example.com",
+            "https://other.com",
+            "https://example.com/blog"
+        ]
+    );
+    // failed generations, with why they failed
+    assert_eq!(
+        urls("/api/list?status=error&domain=example.com".to_string()).await,
+        vec![("https://broken.example.com".to_string(), "LLM call failed".to_string())]
+    );
+
+    for uri in [
+        "/api/list?domain=%20".to_string(),
+        format!("/api/list?created_after={0}&created_before={0}", since),
+        "/api/list?sort=newest".to_string(),
+        "/api/list?status=running".to_string(),
+    ] {
+        let request = Request::builder().uri(&uri).body(Body::empty()).unwrap();
+        let response = test_router().await.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn test_search() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(
+        &pool,
+        "https://tokio.rs",
+        "# Tokio\n\n> An asynchronous runtime for Rust\n\n## Docs\n\n- [Tutorial](https://tokio.rs/tutorial): Spawning tasks & timers",
+        &html,
+    )
+    .await;
+    create_completed_test_job(
+        &pool,
+        "https://django.com",
+        "# Django\n\n> A Python web framework",
+        &html,
+    )
+    .await;
+    // only the latest llms.txt of a website is searched
+    create_completed_test_job(&pool, "https://old.com", "# Old\n\n> Used to be about runtimes", &html).await;
+    create_completed_test_job(&pool, "https://old.com", "# Old\n\n> Now about gardening", &html).await;
+    // stored compressed: it's indexed from the decompressed text
+    let long = format!(
+        "# Embassy\n\n> An async runtime for embedded Rust\n\n## Docs\n\n{}",
+        "- [Page](https://embassy.dev/page): A page\n".repeat(100)
+    );
+    let (_, record) = create_completed_test_job(&pool, "https://embassy.dev", &long, &html).await;
+    assert!(record.result_data.is_compressed());
+    create_failed_test_job(&pool, "https://failed.com", "runtime error", Some(html.clone())).await;
+
+    let search = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = test_router()
+        .await
+        .oneshot(search("/api/search?q=runtimes"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: SearchResponse = response_json(response.into_body()).await;
+    let mut urls: Vec<&str> = body.results.iter().map(|result| result.url.as_str()).collect();
+    urls.sort();
+    assert_eq!(urls, vec!["https://embassy.dev", "https://tokio.rs"]);
+    let tokio = body
+        .results
+        .iter()
+        .find(|result| result.url == "https://tokio.rs")
+        .unwrap();
+    assert!(tokio.rank > 0.0);
+    assert!(tokio.snippet.contains("<mark>runtime</mark>"), "{}", tokio.snippet);
+
+    // snippets are escaped HTML
+    let response = test_router()
+        .await
+        .oneshot(search("/api/search?q=tasks&limit=1"))
+        .await
+        .unwrap();
+    let body: SearchResponse = response_json(response.into_body()).await;
+    assert_eq!(body.results.len(), 1);
+    assert!(
+        body.results[0].snippet.contains("<mark>tasks</mark> &amp; timers"),
+        "{}",
+        body.results[0].snippet
+    );
+
+    let response = test_router()
+        .await
+        .oneshot(search("/api/search?q=%22python%20web%22%20-rust"))
+        .await
+        .unwrap();
+    let body: SearchResponse = response_json(response.into_body()).await;
+    let urls: Vec<&str> = body.results.iter().map(|result| result.url.as_str()).collect();
+    assert_eq!(urls, vec!["https://django.com"]);
+
+    for uri in [
+        "/api/search?q=%20",
+        "/api/search?q=rust&limit=0",
+        "/api/search?q=rust&limit=101",
+    ] {
+        let response = test_router().await.oneshot(search(uri)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", uri);
+        let body: serde_json::Value = response_json(response.into_body()).await;
+        assert_eq!(body["error"], "invalid_request");
+    }
+}
+
+#[tokio::test]
+async fn test_semantic_search_not_enabled() {
+    // the embedding model would have to be called: only semantic search being off is tested
+    if std::env::var(core_ltx::llms::embeddings::EMBEDDING_MODEL_ENV_VAR).is_ok() {
+        return;
+    }
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let search = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let response = test_router()
+        .await
+        .oneshot(search("/api/search/semantic?q=async%20runtimes"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_enabled");
+
+    // the query is checked first
+    let response = test_router()
+        .await
+        .oneshot(search("/api/search/semantic?q=%20"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_related_llm_txt_not_enabled() {
+    // like semantic search, only it being off is tested
+    if std::env::var(core_ltx::llms::embeddings::EMBEDDING_MODEL_ENV_VAR).is_ok() {
+        return;
+    }
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://tokio.rs", "# Tokio", &html).await;
+
+    let related = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+    let uri = format!("/api/llm_txt/related?url={}", urlencoding::encode("https://tokio.rs"));
+    let response = test_router().await.oneshot(related(uri.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_enabled");
+
+    // the limit is checked first
+    let response = test_router()
+        .await
+        .oneshot(related(format!("{}&limit=21", uri)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_list_includes_site_metadata() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let (job, _) = create_completed_test_job(
+        &pool,
+        "https://docs.example.com",
+        "# Docs",
+        &normalize_html("<html>docs</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    set_site_metadata(
+        &pool,
+        job.job_id,
+        SiteMetadata {
+            title: Some("Example Docs".to_string()),
+            description: Some("Guides and API reference.".to_string()),
+            canonical_url: None,
+            favicon_url: Some("https://docs.example.com/favicon.ico".to_string()),
+        },
+    )
+    .await;
+
+    let app = test_router().await;
+
+    let request = Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    let item = &body["items"][0];
+    assert_eq!(item["title"], "Example Docs");
+    assert_eq!(item["description"], "Guides and API reference.");
+    assert_eq!(item["favicon_url"], "https://docs.example.com/favicon.ico");
+    assert!(item.get("canonical_url").is_none());
+    assert_eq!(item["prompt_version"], prompt_version());
+}
+
+//
+// GET /api/export tests
+//
+
+#[tokio::test]
+async fn test_export() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://example.com", "# Example, old", &html).await;
+    let (latest, _) = create_completed_test_job(&pool, "https://example.com", "# Example", &html).await;
+    let long = format!(
+        "# Docs\n\n{}",
+        "- [Page](https://docs.example.com/page): A page\n".repeat(100)
+    );
+    let (_, record) = create_completed_test_job(&pool, "https://docs.example.com/guide/", &long, &html).await;
+    assert!(record.result_data.is_compressed());
+    // the same path as https://example.com
+    create_completed_test_job(&pool, "http://example.com", "# Example over HTTP", &html).await;
+    create_failed_test_job(&pool, "https://broken.com", "LLM call failed", Some(html.clone())).await;
+
+    let request = Request::builder().uri("/api/export").body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/gzip");
+    assert!(
+        response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment; filename=\"llms-txt-export-")
+    );
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(&bytes[..]));
+    let mut files: Vec<(String, String)> = archive
+        .entries()
+        .unwrap()
+        .map(|entry| {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut content = String::new();
+            std::io::Read::read_to_string(&mut entry, &mut content).unwrap();
+            (path, content)
+        })
+        .collect();
+    files.sort();
+    let (_, index) = files.remove(files.iter().position(|(path, _)| path == "index.ndjson").unwrap());
+    assert_eq!(
+        files,
+        vec![
+            ("docs.example.com/guide/llms.txt".to_string(), long.clone()),
+            ("example.com/llms-2.txt".to_string(), "# Example".to_string()),
+            ("example.com/llms.txt".to_string(), "# Example over HTTP".to_string()),
+        ]
+    );
+
+    // the metadata of every llms.txt, with its path
+    let index: Vec<ExportRecord> = index.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let paths: Vec<(&str, Option<&str>)> = index
+        .iter()
+        .map(|record| (record.url.as_str(), record.path.as_deref()))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            ("http://example.com", Some("example.com/llms.txt")),
+            (
+                "https://docs.example.com/guide/",
+                Some("docs.example.com/guide/llms.txt")
+            ),
+            ("https://example.com", Some("example.com/llms-2.txt")),
+        ]
+    );
+    assert!(index.iter().all(|record| record.llms_txt.is_none()));
+
+    // or as NDJSON: the metadata and the llms.txt of every website, a line each
+    let request = Request::builder()
+        .uri("/api/export?format=ndjson")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let records: Vec<ExportRecord> = std::str::from_utf8(&bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let contents: Vec<(&str, Option<&str>)> = records
+        .iter()
+        .map(|record| (record.url.as_str(), record.llms_txt.as_deref()))
+        .collect();
+    assert_eq!(
+        contents,
+        vec![
+            ("http://example.com", Some("# Example over HTTP")),
+            ("https://docs.example.com/guide/", Some(long.as_str())),
+            ("https://example.com", Some("# Example")),
+        ]
+    );
+    assert_eq!(records[2].job_id, latest.job_id);
+    assert_eq!(records[2].source, LlmsTxtSource::Generated);
+    assert!(records.iter().all(|record| record.path.is_none()));
+
+    let request = Request::builder()
+        .uri("/api/export?format=zip")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+async fn export(format: &str) -> axum::body::Bytes {
+    let request = Request::builder()
+        .uri(format!("/api/export?format={}", format))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.into_body().collect().await.unwrap().to_bytes()
+}
+
+async fn import(query: &str, body: Vec<u8>) -> axum::response::Response {
+    let request = Request::builder()
+        .method("POST")
+        .uri(format!("/api/import?{}", query))
+        .body(Body::from(body))
+        .unwrap();
+    test_router().await.oneshot(request).await.unwrap()
+}
+
+async fn latest_llms_txt(url: &str) -> String {
+    let request = Request::builder()
+        .uri(format!("/api/llm_txt?url={}", urlencoding::encode(url)))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response_json::<LlmTxtResponse>(response.into_body()).await.content
+}
+
+#[tokio::test]
+async fn test_import() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://example.com", "# Example", &html).await;
+    create_completed_test_job(&pool, "https://docs.example.com/guide/", "# Guide", &html).await;
+    create_completed_test_job(&pool, "http://example.com", "# Example over HTTP", &html).await;
+    let archive = export("tar").await.to_vec();
+    let ndjson = export("ndjson").await.to_vec();
+
+    // importing an export of the same index imports nothing
+    let response = import("", archive.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ImportResponse = response_json(response.into_body()).await;
+    assert_eq!(
+        body,
+        ImportResponse {
+            imported: 0,
+            skipped_existing: 0,
+            skipped_duplicate: 3,
+        }
+    );
+
+    // into an empty index, everything is imported: each llms.txt with a finished job
+    clean_test_db(&pool).await;
+    let response = import("format=tar", archive).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ImportResponse = response_json(response.into_body()).await;
+    assert_eq!(body.imported, 3);
+    assert_eq!(latest_llms_txt("https://docs.example.com/guide/").await, "# Guide");
+    assert_eq!(latest_llms_txt("http://example.com").await, "# Example over HTTP");
+    assert_eq!(get_jobs_with_status(&pool, JobStatus::Success).await.len(), 3);
+
+    // a website that already has an llms.txt keeps it...
+    clean_test_db(&pool).await;
+    create_completed_test_job(&pool, "https://Example.com/", "# Local", &html).await;
+    let response = import("format=ndjson", ndjson.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: ImportResponse = response_json(response.into_body()).await;
+    assert_eq!(
+        body,
+        ImportResponse {
+            imported: 2,
+            skipped_existing: 1,
+            skipped_duplicate: 0,
+        }
+    );
+    assert_eq!(latest_llms_txt("https://example.com").await, "# Local");
+
+    // ...unless the imported one is newer, and newer ones are asked for
+    let newer: Vec<u8> = std::str::from_utf8(&ndjson)
+        .unwrap()
+        .lines()
+        .flat_map(|line| {
+            let mut record: ExportRecord = serde_json::from_str(line).unwrap();
+            if record.url == "https://example.com" {
+                record.created_at = chrono::Utc::now() + chrono::Duration::hours(1);
+            }
+            let mut line = serde_json::to_vec(&record).unwrap();
+            line.push(b'\n');
+            line
+        })
+        .collect();
+    let response = import("format=ndjson&existing=skip", newer.clone()).await;
+    let body: ImportResponse = response_json(response.into_body()).await;
+    assert_eq!(body.imported, 0);
+    let response = import("format=ndjson&existing=newer", newer).await;
+    let body: ImportResponse = response_json(response.into_body()).await;
+    assert_eq!(
+        body,
+        ImportResponse {
+            imported: 1,
+            skipped_existing: 0,
+            skipped_duplicate: 2,
+        }
+    );
+    assert_eq!(latest_llms_txt("https://example.com").await, "# Example");
+
+    // an invalid record: nothing is imported
+    clean_test_db(&pool).await;
+    let mut invalid = ndjson;
+    invalid.extend_from_slice(b"not json\n");
+    let response = import("format=ndjson", invalid).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "invalid_request");
+    assert!(get_jobs_with_status(&pool, JobStatus::Success).await.is_empty());
+
+    let response = import("format=tar", b"not an archive".to_vec()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+//
+// GET /api/stats tests
+//
+
+#[tokio::test]
+async fn test_get_url_stats() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    create_completed_test_job(&pool, "https://example.com", "# Example, old", &html).await;
+    create_failed_test_job(&pool, "https://example.com", "LLM call failed", Some(html.clone())).await;
+    let (_, latest) = create_completed_test_job(&pool, "https://Example.com/", "# Example", &html).await;
+    create_failed_test_job(&pool, "https://example.com", "LLM timed out", Some(html.clone())).await;
+    create_completed_test_job(&pool, "https://other.com", "# Other", &html).await;
+    // the superseded ones are counted once they're archived too
+    let mut conn = pool.get().await.unwrap();
+    let archived = data_model_ltx::archive::archive_superseded(&mut conn, chrono::Utc::now(), 100)
+        .await
+        .unwrap();
+    assert_eq!(archived, 2);
+    drop(conn);
+
+    let request = Request::builder()
+        .uri(format!("/api/stats?url={}", urlencoding::encode("https://example.com")))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["url"], "https://example.com");
+    assert_eq!(body["generations"], 4);
+    assert_eq!(body["successes"], 2);
+    assert_eq!(body["failures"], 2);
+    let last_success_at: chrono::DateTime<chrono::Utc> =
+        serde_json::from_value(body["last_success_at"].clone()).unwrap();
+    assert!((last_success_at - latest.created_at).num_milliseconds().abs() < 1);
+    assert_eq!(body["last_failure_reason"], "LLM timed out");
+    assert!(body["last_failure_at"].is_string());
+    assert!(body["average_duration_s"].as_f64().unwrap() >= 0.0);
+
+    let request = Request::builder()
+        .uri(format!("/api/stats?url={}", urlencoding::encode("https://unknown.com")))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+//
+// GET /api/stats/models tests
+//
+
+#[tokio::test]
+async fn test_get_model_stats() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let (old, _) = create_completed_test_job(&pool, "https://old.example.com", "# Old", &html).await;
+    set_model(&pool, old.job_id, Some("gpt-4o")).await;
+    let (new, _) = create_completed_test_job(&pool, "https://new.example.com", "# New", &html).await;
+    set_model(&pool, new.job_id, Some("gpt-5-mini")).await;
+    create_completed_test_job(&pool, "https://unknown.example.com", "# Unknown", &html).await;
+
+    let request = Request::builder().uri("/api/stats/models").body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    let model = |name: &str| {
+        body["models"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|model| model["model"] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(model("gpt-4o")["llms_txt_count"], 1);
+    assert_eq!(model("gpt-4o")["deprecated"], true);
+    assert_eq!(model("gpt-5-mini")["llms_txt_count"], 1);
+    assert_eq!(model("gpt-5-mini")["context_window"], 400_000);
+    assert_eq!(body["unknown"], 1);
+    assert_eq!(body["warnings"].as_array().unwrap().len(), 1);
+    assert!(
+        body["warnings"][0]
+            .as_str()
+            .unwrap()
+            .contains("deprecated model gpt-4o")
+    );
+}
+
+//
+// POST /api/status tests
+//
+
+#[tokio::test]
+async fn test_get_status_success() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Queued).await;
+
+    let app = test_router().await;
+
+    let payload = JobIdPayload { job_id: job.job_id };
+
+    // Note: GET with JSON body is unusual but that's how the API is designed
+    let request = Request::builder()
+        .method("GET")
+        .uri("/api/status")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_post_status_batch() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let queued = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Queued).await;
+    let failed = create_test_job(&pool, "https://other.com", JobKind::Update, JobStatus::Failure).await;
+    let missing = uuid::Uuid::new_v4();
+
+    let app = test_router().await;
+
+    let payload = BatchStatusPayload {
+        job_ids: vec![queued.job_id, missing, failed.job_id, queued.job_id],
+    };
+    let request = Request::builder()
+        .method("POST")
+        .uri("/api/status/batch")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: BatchStatusResponse = response_json(response.into_body()).await;
+    assert_eq!(body.statuses.len(), 2);
+    let status = |job_id: uuid::Uuid| {
+        let status = &body.statuses[&job_id];
+        (JobStatus::from(status.status), JobKind::from(status.kind))
+    };
+    assert_eq!(status(queued.job_id), (JobStatus::Queued, JobKind::New));
+    assert_eq!(status(failed.job_id), (JobStatus::Failure, JobKind::Update));
+    assert_eq!(body.unknown, vec![missing]);
+
+    // No job_ids, or too many of them
+    for job_ids in [vec![], vec![missing; BatchStatusPayload::MAX_JOB_IDS + 1]] {
+        let request = Request::builder()
+            .method("POST")
+            .uri("/api/status/batch")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&BatchStatusPayload { job_ids }).unwrap()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+//
+// GET /api/job tests
+//
+
+#[tokio::test]
+async fn test_get_job_success() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Queued).await;
+
+    let app = test_router().await;
+
+    let request = Request::builder()
+        .uri(format!("/api/job?job_id={}", job.job_id))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+//
+// POST /api/job/cancel tests
+//
+
+#[tokio::test]
+async fn test_cancel_job() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let queued = create_test_job(&pool, "https://queued.com", JobKind::New, JobStatus::Queued).await;
+    let running = create_test_job(&pool, "https://running.com", JobKind::Update, JobStatus::Running).await;
+    let done = create_test_job(&pool, "https://done.com", JobKind::New, JobStatus::Success).await;
+
+    let cancel = |job_id: uuid::Uuid| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/job/cancel")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&JobIdPayload { job_id }).unwrap()))
+            .unwrap()
+    };
+
+    for job in [&queued, &running] {
+        let response = test_router().await.oneshot(cancel(job.job_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response_json(response.into_body()).await;
+        assert_eq!(body["status"], "Cancelled");
+        let cancelled = get_job_by_id(&pool, job.job_id).await.unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert!(cancelled.status.is_completed());
+    }
+
+    // finished jobs, including cancelled ones, can't be cancelled
+    for job in [&done, &queued] {
+        let response = test_router().await.oneshot(cancel(job.job_id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = response_json(response.into_body()).await;
+        assert_eq!(body["error"], "already_finished");
+    }
+    assert_eq!(
+        get_job_by_id(&pool, done.job_id).await.unwrap().status,
+        JobStatus::Success
+    );
+
+    let response = test_router().await.oneshot(cancel(uuid::Uuid::new_v4())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+//
+// POST /api/job/retry tests
+//
+
+#[tokio::test]
+async fn test_retry_job() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let failed = create_test_job(&pool, "https://failed.com", JobKind::Update, JobStatus::Failure).await;
+    let done = create_test_job(&pool, "https://done.com", JobKind::New, JobStatus::Success).await;
+
+    let retry = |job_id: uuid::Uuid| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/job/retry")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::to_vec(&JobIdPayload { job_id }).unwrap()))
+            .unwrap()
+    };
+
+    let response = test_router().await.oneshot(retry(failed.job_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JobIdResponse = response_json(response.into_body()).await;
+    assert_ne!(body.job_id, failed.job_id);
+    let retried = get_job_by_id(&pool, body.job_id).await.unwrap();
+    assert_eq!(retried.status, JobStatus::Queued);
+    assert_eq!(retried.url, "https://failed.com");
+    assert_eq!(retried.kind, JobKind::Update);
+    assert_eq!(retried.attempts, 2);
+    assert_eq!(
+        get_job_by_id(&pool, failed.job_id).await.unwrap().status,
+        JobStatus::Failure
+    );
+
+    // the retry is in progress
+    let response = test_router().await.oneshot(retry(failed.job_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "jobs_in_progress");
+
+    let response = test_router().await.oneshot(retry(done.job_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "not_failed");
+
+    let response = test_router().await.oneshot(retry(uuid::Uuid::new_v4())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+//
+// POST /api/admin/jobs/purge_stale tests
+//
+
+#[tokio::test]
+async fn test_purge_stale_jobs() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let now = chrono::Utc::now();
+    let hours_ago = |hours| now - chrono::Duration::hours(hours);
+    let stale = create_test_job(&pool, "https://stale.com", JobKind::New, JobStatus::Running).await;
+    set_job_started_at(&pool, stale.job_id, hours_ago(3), Some(hours_ago(2))).await;
+    // claimed before claims were recorded: it counts from its creation
+    let legacy = create_test_job(&pool, "https://legacy.com", JobKind::Update, JobStatus::Running).await;
+    set_job_started_at(&pool, legacy.job_id, hours_ago(2), None).await;
+    let fresh = create_test_job(&pool, "https://fresh.com", JobKind::New, JobStatus::Running).await;
+    set_job_started_at(
+        &pool,
+        fresh.job_id,
+        hours_ago(3),
+        Some(now - chrono::Duration::minutes(10)),
+    )
+    .await;
+    let queued = create_test_job(&pool, "https://queued.com", JobKind::New, JobStatus::Queued).await;
+    set_job_started_at(&pool, queued.job_id, hours_ago(5), None).await;
+
+    let purge = |body: serde_json::Value| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/admin/jobs/purge_stale")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    // requeued after an hour, by default
+    let response = test_router().await.oneshot(purge(serde_json::json!({}))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: PurgeStaleJobsResponse = response_json(response.into_body()).await;
+    let mut expected = vec![stale.job_id, legacy.job_id];
+    expected.sort();
+    assert_eq!(body.job_ids, expected);
+    for job_id in [stale.job_id, legacy.job_id, queued.job_id] {
+        assert_eq!(get_job_by_id(&pool, job_id).await.unwrap().status, JobStatus::Queued);
+    }
+    assert_eq!(
+        get_job_by_id(&pool, fresh.job_id).await.unwrap().status,
+        JobStatus::Running
+    );
+
+    let response = test_router()
+        .await
+        .oneshot(purge(serde_json::json!({"running_for_s": 60, "action": "fail"})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: PurgeStaleJobsResponse = response_json(response.into_body()).await;
+    assert_eq!(body.job_ids, vec![fresh.job_id]);
+    let failed = get_job_by_id(&pool, fresh.job_id).await.unwrap();
+    assert_eq!(failed.status, JobStatus::Failure);
+    assert_eq!(failed.failure_kind.as_deref(), Some("internal"));
+
+    let response = test_router()
+        .await
+        .oneshot(purge(serde_json::json!({"running_for_s": 0})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "invalid_request");
+
+    let response = test_router()
+        .await
+        .oneshot(purge(serde_json::json!({"running_for_s": u64::MAX})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["error"], "invalid_request");
+}
+
+#[tokio::test]
+async fn test_get_job_failure_kind() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let (job, _) = create_failed_test_job(
+        &pool,
+        "https://example.com",
+        "HTTP 406 error for: https://example.com",
+        None,
+    )
+    .await;
+    set_failure_kind(&pool, job.job_id, FailureKind::HttpStatus(406)).await;
+
+    let app = test_router().await;
+
+    let request = Request::builder()
+        .uri(format!("/api/job?job_id={}", job.job_id))
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: JobDetailsResponse = response_json(response.into_body()).await;
+    assert_eq!(JobStatus::from(body.status), JobStatus::Failure);
+    assert_eq!(body.failure_kind, Some(FailureKind::HttpStatus(406)));
+    assert_eq!(body.failure_message.as_deref(), Some("The site returned HTTP 406."));
+}
+
+#[tokio::test]
+async fn test_get_job_logs() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Success).await;
+    let app = test_router().await;
+
+    // no logs until the worker stores them
+    let request = Request::builder()
+        .uri(format!("/api/job/logs?job_id={}", job.job_id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    set_job_log(&pool, job.job_id, "INFO worker_ltx::work: Received job\n").await;
+    let request = Request::builder()
+        .uri(format!("/api/job/logs?job_id={}", job.job_id))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: JobLogsResponse = response_json(response.into_body()).await;
+    assert_eq!(body.job_id, job.job_id);
+    assert_eq!(body.log, "INFO worker_ltx::work: Received job\n");
+    assert!(!body.truncated);
+}
+
+//
+// GET /api/jobs/in_progress tests
+//
+
+#[tokio::test]
+async fn test_get_in_progress_jobs_empty() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let app = test_router().await;
+
+    let request = Request::builder()
+        .uri("/api/jobs/in_progress")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: Vec<data_model_ltx::models::JobSummary> = response_json(response.into_body()).await;
+    assert_eq!(body.len(), 0);
+}
+
+#[tokio::test]
+async fn test_get_in_progress_jobs_returns_queued() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    create_test_job(&pool, "https://site1.com", JobKind::New, JobStatus::Queued).await;
+    create_test_job(&pool, "https://site2.com", JobKind::New, JobStatus::Queued).await;
+
+    let app = test_router().await;
+
+    let request = Request::builder()
+        .uri("/api/jobs/in_progress")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body: Vec<data_model_ltx::models::JobSummary> = response_json(response.into_body()).await;
+    assert_eq!(body.len(), 2);
+}
+
+#[tokio::test]
+async fn test_get_jobs() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let failed = create_test_job(&pool, "https://site1.com", JobKind::New, JobStatus::Failure).await;
+    let succeeded = create_test_job(&pool, "https://site1.com", JobKind::New, JobStatus::Success).await;
+    let other = create_test_job(&pool, "https://site2.com", JobKind::New, JobStatus::Success).await;
+    let queued = create_test_job(&pool, "https://site3.com", JobKind::New, JobStatus::Queued).await;
+
+    let list = |query: &str| {
+        Request::builder()
+            .uri(format!("/api/jobs{}", query))
+            .body(Body::empty())
+            .unwrap()
+    };
+    let job_ids = |body: &JobsListResponse| body.items.iter().map(|job| job.job_id).collect::<Vec<_>>();
+
+    // newest first
+    let response = test_router().await.oneshot(list("")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JobsListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.total, 4);
+    assert_eq!(
+        job_ids(&body),
+        vec![queued.job_id, other.job_id, succeeded.job_id, failed.job_id]
+    );
+
+    let response = test_router().await.oneshot(list("?status=failure")).await.unwrap();
+    let body: JobsListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.total, 1);
+    assert_eq!(job_ids(&body), vec![failed.job_id]);
+
+    let response = test_router()
+        .await
+        .oneshot(list("?status=success&url=https%3A%2F%2FSITE1.com%3A443%2F"))
+        .await
+        .unwrap();
+    let body: JobsListResponse = response_json(response.into_body()).await;
+    assert_eq!(job_ids(&body), vec![succeeded.job_id]);
+
+    let response = test_router().await.oneshot(list("?page=2&per_page=3")).await.unwrap();
+    let body: JobsListResponse = response_json(response.into_body()).await;
+    assert_eq!(body.total, 4);
+    assert_eq!(body.page_count(), 2);
+    assert_eq!(job_ids(&body), vec![failed.job_id]);
+
+    for query in ["?page=0", "?per_page=501", "?status=unknown", "?url=%20"] {
+        let response = test_router().await.oneshot(list(query)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{} is accepted", query);
+    }
+}
+
+//
+// GET /metrics tests
+//
+
+#[tokio::test]
+async fn test_get_metrics() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    create_test_job(&pool, "https://site1.com", JobKind::New, JobStatus::Queued).await;
+    create_test_job(&pool, "https://site2.com", JobKind::New, JobStatus::Queued).await;
+    create_test_job(&pool, "https://site3.com", JobKind::New, JobStatus::Failure).await;
+
+    let app = test_router().await;
+    let request = Request::builder()
+        .uri("/api/jobs/in_progress")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = Request::builder().uri("/metrics").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let metrics = String::from_utf8(bytes.to_vec()).unwrap();
+
+    // requests are labeled by route, not by URL
+    assert!(metrics.contains(r#"api_http_requests_total{method="GET",route="/api/jobs/in_progress",status="200"}"#));
+    assert!(metrics.contains("api_http_request_duration_seconds_bucket"));
+    // statuses without jobs are exported too
+    assert!(metrics.contains(r#"api_jobs{status="Queued"} 2"#));
+    assert!(metrics.contains(r#"api_jobs{status="Failure"} 1"#));
+    assert!(metrics.contains(r#"api_jobs{status="Cancelled"} 0"#));
+    assert!(metrics.contains("api_db_pool_max_size"));
+}
+
+//
+// GET /.well-known/llms-directory tests
+//
+
+#[tokio::test]
+async fn test_get_directory_json_and_markdown() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    create_completed_test_job(
+        &pool,
+        "https://site1.com",
+        "# Site 1\n\n> The first site.",
+        &normalize_html("<html>1</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    create_completed_test_job(
+        &pool,
+        "https://site2.com",
+        "# Site 2",
+        &normalize_html("<html>2</html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
+    let app = test_router().await;
+
+    let request = Request::builder()
+        .uri("/.well-known/llms-directory")
+        .header(header::HOST, "index.example.org")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    let sites = body["sites"].as_array().unwrap();
+    assert_eq!(sites.len(), 2);
+    assert_eq!(sites[0]["url"], "https://site1.com");
+    assert_eq!(sites[0]["title"], "Site 1");
+    assert_eq!(sites[0]["summary"], "The first site.");
+    assert_eq!(
+        sites[0]["llms_txt"],
+        "https://index.example.org/api/llm_txt?url=https%3A%2F%2Fsite1.com"
+    );
+    assert!(sites[1]["summary"].is_null());
+
+    let request = Request::builder()
+        .uri("/.well-known/llms-directory")
+        .header(header::ACCEPT, "text/markdown")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .starts_with("text/markdown")
+    );
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let markdown = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(markdown.contains("- [Site 2](/api/llm_txt?url=https%3A%2F%2Fsite2.com): https://site2.com"));
+}
+
+#[tokio::test]
+async fn test_get_domain_llms_txt() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    for (url, content) in [
+        ("https://docs.example.com/guide", "# Guide"),
+        ("https://docs.example.com/", "# Docs"),
+        ("https://docs.example.com.evil.io/", "# Evil"),
+        ("https://example.com/", "# Example"),
+    ] {
+        create_completed_test_job(
+            &pool,
+            url,
+            content,
+            &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+        )
+        .await;
+    }
+
+    let app = test_router().await;
+    let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    // the latest llms.txt of any page of the host, and only of that host
+    let response = app
+        .clone()
+        .oneshot(get("/llms/Docs.Example.com/llms.txt"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    assert!(response.headers().contains_key(header::LAST_MODIFIED));
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(bytes, "# Docs".as_bytes());
+
+    let response = app.clone().oneshot(get("/llms/example.com/llms.txt")).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(bytes, "# Example".as_bytes());
+
+    let response = app.oneshot(get("/llms/unknown.com/llms.txt")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_index_llms_txt() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    for (url, content) in [
+        ("https://docs.example.com/guide", "# Guide\n\n> How to start."),
+        ("https://site1.com", "# Site 1"),
+    ] {
+        create_completed_test_job(
+            &pool,
+            url,
+            content,
+            &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+        )
+        .await;
+    }
+
+    let app = test_router().await;
+    let get = || {
+        Request::builder()
+            .uri("/llms.txt")
+            .header(header::HOST, "index.example.org")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(get()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; charset=utf-8");
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let llms_txt = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(llms_txt.starts_with("# llm-web-index\n\n> "));
+    assert!(llms_txt.contains(
+        "## docs.example.com\n\n- [Guide](https://index.example.org/api/llm_txt?url=\
+         https%3A%2F%2Fdocs.example.com%2Fguide&format=raw): How to start.\n"
+    ));
+    assert!(llms_txt.contains("## site1.com\n\n- [Site 1]("));
+
+    // a new website is in it once it's generated
+    create_completed_test_job(
+        &pool,
+        "https://site2.com",
+        "# Site 2",
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+    let response = app.oneshot(get()).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let llms_txt = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(llms_txt.contains("## site2.com\n\n- [Site 2]("));
+}
+
+//
+// POST /mcp tests
+//
+
+/// Helper to send one JSON-RPC message to /mcp
+async fn post_mcp(app: axum::Router, message: serde_json::Value) -> (StatusCode, Option<serde_json::Value>) {
+    let request = Request::builder()
+        .method("POST")
+        .uri("/mcp")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::to_vec(&message).unwrap()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, serde_json::from_slice(&bytes).ok())
+}
+
+#[tokio::test]
+async fn test_mcp_tools_list_and_notification() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let (status, body) = post_mcp(
+        test_router().await,
+        serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let body = body.unwrap();
+    assert_eq!(body["id"], 1);
+    assert_eq!(body["result"]["tools"].as_array().unwrap().len(), 3);
+
+    let (status, _) = post_mcp(
+        test_router().await,
+        serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn test_mcp_get_search_and_request_generation() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let url = "https://example.com";
+    let content = "# Example\n\n> Widgets for everyone";
+    create_completed_test_job(
+        &pool,
+        url,
+        content,
+        &normalize_html("<html></html>").expect("Failed to parse & clean HTML"),
+    )
+    .await;
+
+    let call = |name: &str, arguments: serde_json::Value| {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": name,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        })
+    };
+
+    let (_, body) = post_mcp(
+        test_router().await,
+        call("get_llms_txt", serde_json::json!({ "url": url })),
+    )
+    .await;
+    let result = &body.unwrap()["result"];
+    assert_eq!(result["isError"], false);
+    assert_eq!(result["content"][0]["text"], content);
+
+    let (_, body) = post_mcp(
+        test_router().await,
+        call("search", serde_json::json!({ "query": "WIDGETS" })),
+    )
+    .await;
+    let text = body.unwrap()["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert!(text.contains(url), "{}", text);
+
+    let (_, body) = post_mcp(
+        test_router().await,
+        call(
+            "request_generation",
+            serde_json::json!({ "url": "https://new-site.com" }),
+        ),
+    )
+    .await;
+    let result = &body.unwrap()["result"];
+    assert_eq!(result["isError"], false);
+    assert!(result["content"][0]["text"].as_str().unwrap().starts_with("Generating"));
+
+    let (_, body) = post_mcp(test_router().await, call("request_generation", serde_json::json!({}))).await;
+    assert_eq!(body.unwrap()["result"]["isError"], true);
+}
+
+//
+// Public read-only mode tests
+//
+
+fn auth_config(public_reads: bool) -> core_ltx::AuthConfig {
+    core_ltx::AuthConfig {
+        password_hash: "unused".to_string(),
+        session_secret: "test-secret".to_string(),
+        session_duration_seconds: 3600,
+        password: None,
+        public_reads,
+    }
+}
+
+#[tokio::test]
+async fn test_public_read_only_mode() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let list = || Request::builder().uri("/api/list").body(Body::empty()).unwrap();
+    let create = || {
+        Request::builder()
+            .method("POST")
+            .uri("/api/llm_txt")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"url":"https://example.com"}"#))
+            .unwrap()
+    };
+
+    // reads are public, writes still need a login
+    let app = router(Some(auth_config(true)), pool.clone());
+    assert_eq!(app.clone().oneshot(list()).await.unwrap().status(), StatusCode::OK);
+    assert_eq!(app.oneshot(create()).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+    // without public reads, everything needs a login
+    let app = router(Some(auth_config(false)), pool);
+    assert_eq!(
+        app.clone().oneshot(list()).await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(app.oneshot(create()).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_openapi_spec() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    // public, even when every other endpoint needs a login
+    let app = router(Some(auth_config(false)), pool);
+
+    let request = Request::builder().uri("/api/openapi.json").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let spec: serde_json::Value = response_json(response.into_body()).await;
+
+    let paths = spec["paths"].as_object().expect("no paths in the spec");
+    for (path, method) in [
+        ("/api/llm_txt", "get"),
+        ("/api/llm_txt", "post"),
+        ("/api/llm_txt", "put"),
+        ("/api/llm_txt", "delete"),
+        ("/api/update", "post"),
+        ("/api/status", "get"),
+        ("/api/job/cancel", "post"),
+        ("/api/job/retry", "post"),
+        ("/api/jobs", "get"),
+        ("/api/admin/webhooks/deliveries", "get"),
+        ("/api/auth/login", "post"),
+        ("/.well-known/llms-directory", "get"),
+        ("/llms.txt", "get"),
+    ] {
+        assert!(paths[path][method].is_object(), "{} {} isn't in the spec", method, path);
+    }
+    assert_eq!(
+        paths["/api/llm_txt"]["get"]["parameters"][0]["name"], "url",
+        "UrlPayload is a query parameter"
+    );
+    let schemas = spec["components"]["schemas"].as_object().unwrap();
+    for schema in [
+        "UrlPayload",
+        "JobIdResponse",
+        "JobRequestPayload",
+        "PostLlmTxtError",
+        "LiveEvent",
+    ] {
+        assert!(schemas.contains_key(schema), "no {} schema in the spec", schema);
+    }
+    assert_eq!(spec["components"]["securitySchemes"]["session"]["in"], "cookie");
+    assert_eq!(spec["components"]["securitySchemes"]["api_key"]["scheme"], "bearer");
+
+    let request = Request::builder().uri("/api/docs/").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(String::from_utf8_lossy(&bytes).contains("swagger-ui"));
+}
+
+#[tokio::test]
+async fn test_live_updates() {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    // a plain GET isn't a WebSocket
+    let request = Request::builder().uri("/api/ws").body(Body::empty()).unwrap();
+    let response = test_router().await.oneshot(request).await.unwrap();
+    assert!(response.status().is_client_error());
+    assert_ne!(response.status(), StatusCode::NOT_FOUND);
+
+    unsafe { std::env::set_var("LIVE_POLL_INTERVAL_MS", "50") };
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let app = router(None, pool.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/ws", address))
+        .await
+        .unwrap();
+    // let the first poll see the (empty) current state
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+
+    let mut next_event = async || loop {
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .expect("no event within 5 seconds")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str::<LiveEvent>(&text).unwrap();
+        }
+    };
+
+    let url = "https://live.example.com";
+    let job = create_test_job(&pool, url, JobKind::New, JobStatus::Queued).await;
+    match next_event().await {
+        LiveEvent::Job(summary) => assert_eq!((summary.job_id, summary.status), (job.job_id, JobStatus::Queued)),
+        event => panic!("unexpected event: {:?}", event),
+    }
+    update_job_status(&pool, job.job_id, JobStatus::Running).await;
+    match next_event().await {
+        LiveEvent::Job(summary) => assert_eq!((summary.job_id, summary.status), (job.job_id, JobStatus::Running)),
+        event => panic!("unexpected event: {:?}", event),
+    }
+    update_job_status(&pool, job.job_id, JobStatus::Failure).await;
+    match next_event().await {
+        LiveEvent::Job(summary) => assert_eq!((summary.job_id, summary.status), (job.job_id, JobStatus::Failure)),
+        event => panic!("unexpected event: {:?}", event),
+    }
+
+    // a result stored for a job that was never seen in progress
+    let html = normalize_html("<html></html>").expect("Failed to parse & clean HTML");
+    let (completed, _) = create_completed_test_job(&pool, url, "# Live", &html).await;
+    match next_event().await {
+        LiveEvent::LlmsTxt {
+            job_id,
+            url: result_url,
+            ..
+        } => {
+            assert_eq!((job_id, result_url.as_str()), (completed.job_id, url))
+        }
+        event => panic!("unexpected event: {:?}", event),
+    }
+}
+
+#[tokio::test]
+async fn test_webhooks() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+
+    let request = |method: &str, uri: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    unsafe {
+        std::env::set_var("SECRETS_KEYS", "k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=");
+    }
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/admin/webhooks",
+            r#"{"url":"ftp://hooks.example.com/"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = test_router()
+        .await
+        .oneshot(request(
+            "POST",
+            "/api/admin/webhooks",
+            r#"{"url":"https://hooks.example.com/llms"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: CreatedWebhookResponse = response_json(response.into_body()).await;
+    assert_eq!(created.webhook.url, "https://hooks.example.com/llms");
+    assert!(created.secret.starts_with("whsec_"));
+
+    // the secret is only shown when the webhook is created
+    let response = test_router()
+        .await
+        .oneshot(request("GET", "/api/admin/webhooks", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(!String::from_utf8_lossy(&body).contains(&created.secret));
+    let listed: WebhooksResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(listed.webhooks, vec![created.webhook.clone()]);
+
+    let webhook_id = created.webhook.webhook_id;
+    let delivery = create_failed_webhook_delivery(&pool, webhook_id, 503).await;
+    let deliveries_uri = format!("/api/admin/webhooks/deliveries?webhook_id={}", webhook_id);
+    let response = test_router()
+        .await
+        .oneshot(request("GET", &deliveries_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let history: WebhookDeliveriesResponse = response_json(response.into_body()).await;
+    assert_eq!(history.deliveries.len(), 1);
+    assert_eq!(history.deliveries[0].delivery, delivery);
+    assert_eq!(history.deliveries[0].attempts.len(), 1);
+    assert_eq!(history.deliveries[0].attempts[0].status_code, Some(503));
+
+    let response = test_router()
+        .await
+        .oneshot(request("GET", &format!("{}&limit=0", deliveries_uri), ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // the webhook's secret is re-encrypted along with the rest
+    let response = test_router()
+        .await
+        .oneshot(request("POST", "/api/admin/secrets/rotate", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let rotated: RotateSecretsResponse = response_json(response.into_body()).await;
+    assert_eq!(rotated.up_to_date, 1);
+    assert!(rotated.failed.is_empty());
+
+    let delete_uri = format!("/api/admin/webhooks?webhook_id={}", webhook_id);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", &delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", &delete_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = test_router()
+        .await
+        .oneshot(request("GET", &deliveries_uri, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_login_throttling() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    let config = core_ltx::AuthConfig {
+        password_hash: bcrypt::hash("correct horse", 4).unwrap(),
+        ..auth_config(false)
+    };
+    let app = router(Some(config), pool);
+    let login = |ip: [u8; 4], password: &'static str| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((ip, 40000))))
+            .body(Body::from(format!(r#"{{"password":"{}"}}"#, password)))
+            .unwrap()
+    };
+
+    let response = app.clone().oneshot(login([192, 0, 2, 10], "wrong")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // the client has to wait before trying again, even with the right password
+    let response = app
+        .clone()
+        .oneshot(login([192, 0, 2, 10], "correct horse"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(header::RETRY_AFTER));
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["retry_after_s"], 1);
+
+    // other clients only wait for the shorter overall delay
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    let response = app
+        .clone()
+        .oneshot(login([192, 0, 2, 11], "correct horse"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().contains_key(header::SET_COOKIE));
+
+    // the client's wait passed while the other one logged in (a login takes at least a second)
+    let response = app.oneshot(login([192, 0, 2, 10], "correct horse")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_job_creation_rate_limit() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    // SAFETY: tests in this file run one at a time (TEST_MUTEX); the limit is read when the router is built
+    unsafe { std::env::set_var("RATE_LIMIT_PER_IP", "2") };
+    let app = router(None, pool);
+    unsafe { std::env::remove_var("RATE_LIMIT_PER_IP") };
+    let create = |ip: [u8; 4], method: &'static str, uri: &'static str, url: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((ip, 40000))))
+            .body(Body::from(format!(r#"{{"url":"{}"}}"#, url)))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(create(
+            [192, 0, 2, 20],
+            "POST",
+            "/api/llm_txt",
+            "https://one.example.com",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = app
+        .clone()
+        .oneshot(create(
+            [192, 0, 2, 20],
+            "PUT",
+            "/api/llm_txt",
+            "https://two.example.com",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // the endpoints creating jobs share the client's allowance
+    let response = app
+        .clone()
+        .oneshot(create(
+            [192, 0, 2, 20],
+            "POST",
+            "/api/update",
+            "https://one.example.com",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["retry_after_s"], 30);
+
+    // other clients have their own, and reads aren't limited
+    let response = app
+        .clone()
+        .oneshot(create(
+            [192, 0, 2, 21],
+            "POST",
+            "/api/llm_txt",
+            "https://three.example.com",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/api/jobs/in_progress")
+                .extension(axum::extract::ConnectInfo(std::net::SocketAddr::from((
+                    [192, 0, 2, 20],
+                    40000,
+                ))))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_two_factor_login() {
+    use api_ltx::auth::totp::{code_at_step, decode_secret, step_at};
+    use api_ltx::auth::two_factor::{confirmed_second_factor, verify_second_factor};
+
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    unsafe {
+        std::env::set_var("SECRETS_KEYS", "k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=");
+    }
+    let config = core_ltx::AuthConfig {
+        password_hash: bcrypt::hash("correct horse", 4).unwrap(),
+        ..auth_config(false)
+    };
+    let app = router(Some(config), pool.clone());
+    let login = |body: String| {
+        Request::builder()
+            .method("POST")
+            .uri("/api/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let request = |method: &str, uri: &str, cookie: &str, body: String| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::COOKIE, cookie)
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(login(r#"{"password":"correct horse"}"#.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let cookie = response.headers()[header::SET_COOKIE]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+
+    // managing the second factor needs a session
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/auth/totp", "", String::new()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(request("POST", "/api/auth/totp/enroll", &cookie, String::new()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let enrollment: serde_json::Value = response_json(response.into_body()).await;
+    assert!(
+        enrollment["otpauth_uri"]
+            .as_str()
+            .unwrap()
+            .starts_with("otpauth://totp/")
+    );
+    let secret = decode_secret(enrollment["secret"].as_str().unwrap()).unwrap();
+    let now_step = step_at(chrono::Utc::now().timestamp());
+    let code = |step: i64| format!(r#"{{"code":"{}"}}"#, code_at_step(&secret, step));
+
+    let response = app
+        .clone()
+        .oneshot(request("POST", "/api/auth/totp/confirm", &cookie, code(now_step + 5)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .clone()
+        .oneshot(request("POST", "/api/auth/totp/confirm", &cookie, code(now_step)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    let recovery_codes: Vec<String> = serde_json::from_value(body["recovery_codes"].clone()).unwrap();
+    assert_eq!(recovery_codes.len(), 10);
+
+    // the password alone isn't enough anymore
+    let response = app
+        .clone()
+        .oneshot(login(r#"{"password":"correct horse"}"#.to_string()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert!(!response.headers().contains_key(header::SET_COOKIE));
+    let body: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(body["second_factor_required"], true);
+
+    // the code that confirmed the enrollment was used: the next one logs in
+    let response = app
+        .clone()
+        .oneshot(login(format!(
+            r#"{{"password":"correct horse","code":"{}"}}"#,
+            code_at_step(&secret, now_step + 1)
+        )))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(login(format!(
+            r#"{{"password":"correct horse","code":"{}"}}"#,
+            recovery_codes[0]
+        )))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // codes don't work twice (checked directly: failed logins would throttle the other tests' logins)
+    let second_factor = confirmed_second_factor(&pool).await.unwrap().unwrap();
+    let now = chrono::Utc::now();
+    for used in [code_at_step(&secret, now_step + 1), recovery_codes[0].clone()] {
+        assert_eq!(
+            verify_second_factor(&pool, &second_factor, &used, now).await.unwrap(),
+            None
+        );
+    }
+
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/auth/totp", &cookie, String::new()))
+        .await
+        .unwrap();
+    let status: serde_json::Value = response_json(response.into_body()).await;
+    assert_eq!(status["enabled"], true);
+    assert_eq!(status["recovery_codes_left"], 9);
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/auth/totp/disable",
+            &cookie,
+            format!(r#"{{"code":"{}"}}"#, recovery_codes[1]),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(confirmed_second_factor(&pool).await.unwrap().is_none());
+}
+
+//
+// /api/admin/api_keys tests
+//
+
+#[tokio::test]
+async fn test_api_keys() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    let app = router(Some(auth_config(false)), pool.clone());
+    let request = |method: &str, uri: &str, key: &str, body: &'static str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::AUTHORIZATION, format!("Bearer {}", key))
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // creating a key needs to be authenticated already
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/api/admin/api_keys",
+            "ltx_unknown",
+            r#"{"name":"cron"}"#,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = test_router()
+        .await
+        .oneshot(request("POST", "/api/admin/api_keys", "", r#"{"name":" "}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = test_router()
+        .await
+        .oneshot(request("POST", "/api/admin/api_keys", "", r#"{"name":"cron"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: CreatedApiKeyResponse = response_json(response.into_body()).await;
+    assert_eq!(created.api_key.name, "cron");
+    assert!(created.key.starts_with(&created.api_key.prefix));
+
+    // the key authenticates requests, and only the key
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/admin/api_keys", &created.key, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(
+        !String::from_utf8_lossy(&bytes).contains(&created.key),
+        "The key is never listed"
+    );
+    let listed: ApiKeysResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(listed.api_keys.len(), 1);
+    assert!(listed.api_keys[0].last_used_at.is_some());
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/list", &format!("{}0", created.key), ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // a revoked key doesn't authenticate anymore
+    let revoke = format!("/api/admin/api_keys?key_id={}", created.api_key.key_id);
+    let response = app
+        .clone()
+        .oneshot(request("DELETE", &revoke, &created.key, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/api/list", &created.key, ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = test_router()
+        .await
+        .oneshot(request("DELETE", &revoke, "", ""))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = test_router()
+        .await
+        .oneshot(request("GET", "/api/admin/api_keys", "", ""))
+        .await
+        .unwrap();
+    let listed: ApiKeysResponse = response_json(response.into_body()).await;
+    assert!(listed.api_keys[0].revoked_at.is_some());
 }
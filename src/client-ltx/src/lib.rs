@@ -0,0 +1,250 @@
+//! Typed client for the API server.
+//!
+//! The same `ApiClient` methods work in the browser (wasm32, over `fetch`) and natively (over `reqwest`), so the
+//! frontend, the CLI's `remote` commands, and cron share one client. The requests and responses are `dto-ltx`'s,
+//! the same types the server uses.
+
+#[cfg(not(target_arch = "wasm32"))]
+mod native;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+use serde::de::DeserializeOwned;
+
+pub use dto_ltx::{
+    BatchStatusPayload, BatchStatusResponse, Crawl, FailureKind, JobDetailsResponse, JobIdPayload, JobIdResponse,
+    JobKind, JobLogsResponse, JobRequestPayload, JobStatus, JobStatusFilter, JobStatusResponse, JobSummary,
+    JobsListResponse, JobsPage, ListPage, ListSort, LiveEvent, LlmTxtResponse, LlmsTxtHistoryResponse, LlmsTxtListItem,
+    LlmsTxtListResponse, LlmsTxtVersion, RelatedLlmsTxtResponse, RelatedSite, ResultStatus, Uuid,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use native::ApiClient;
+#[cfg(target_arch = "wasm32")]
+pub use wasm::ApiClient;
+
+/// Why a request to the API server failed.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The request couldn't be sent, or no response came back.
+    #[error("Request to the API server failed: {0}")]
+    Transport(String),
+    /// The server answered with a non-success status. `message` is the response's body.
+    #[error("API server returned HTTP {status} for {url}: {message}")]
+    Api { url: String, status: u16, message: String },
+    /// The response's body isn't what the endpoint returns.
+    #[error("Unexpected response from the API server: {0}")]
+    Decode(String),
+    /// Logging in failed, or there's no password to log in with.
+    #[error("Authentication with the API server failed: {0}")]
+    Auth(String),
+}
+
+impl ClientError {
+    /// The HTTP status, when the server answered with a non-success one.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ClientError::Api { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+/// HTTP methods the API uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Get,
+    Post,
+    Put,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Method {
+    fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+        }
+    }
+}
+
+/// `base_url` and `path`, e.g. `https://host:3000` and `/api/list`. An empty `base_url` is the page's own origin.
+fn endpoint(base_url: &str, path: &str) -> String {
+    format!("{}{}", base_url.trim_end_matches('/'), path)
+}
+
+impl ApiClient {
+    /// The most recent llms.txt of the website.
+    pub async fn get_llm_txt(&self, url: &str) -> Result<String, ClientError> {
+        let response: LlmTxtResponse = self.get("/api/llm_txt", &[("url", url)]).await?;
+        Ok(response.content)
+    }
+
+    /// Every generation of the website's llms.txt, newest first. Fails with HTTP 404 if there's none.
+    pub async fn llm_txt_history(&self, url: &str) -> Result<LlmsTxtHistoryResponse, ClientError> {
+        self.get("/api/llm_txt/history", &[("url", url)]).await
+    }
+
+    /// The websites whose latest llms.txt is the most similar to the website's, most similar first. Fails with HTTP 404
+    /// if it has none, and HTTP 503 if the server's semantic search is off.
+    pub async fn related(&self, url: &str) -> Result<RelatedLlmsTxtResponse, ClientError> {
+        self.get("/api/llm_txt/related", &[("url", url)]).await
+    }
+
+    /// Creates a job generating the website's first llms.txt. Fails with HTTP 409 if it already has one.
+    pub async fn post_llm_txt(&self, request: &JobRequestPayload) -> Result<JobIdResponse, ClientError> {
+        self.send_json(Method::Post, "/api/llm_txt", request).await
+    }
+
+    /// Creates a job generating the website's llms.txt, or updating it if it already has one.
+    pub async fn put_llm_txt(&self, request: &JobRequestPayload) -> Result<JobIdResponse, ClientError> {
+        self.send_json(Method::Put, "/api/llm_txt", request).await
+    }
+
+    /// Creates a job updating the website's llms.txt. Fails with HTTP 404 if it doesn't have one yet.
+    pub async fn post_update(&self, request: &JobRequestPayload) -> Result<JobIdResponse, ClientError> {
+        self.send_json(Method::Post, "/api/update", request).await
+    }
+
+    /// The most recent llms.txt of every website, fetched page by page. `full` fetches the whole llms.txt of every
+    /// website, not only its preview.
+    pub async fn list(&self, full: bool) -> Result<Vec<LlmsTxtListItem>, ClientError> {
+        let mut items = Vec::new();
+        let mut page = ListPage {
+            full,
+            ..ListPage::new(1)
+        };
+        loop {
+            let response = self.list_page(page.clone()).await?;
+            let more = response.has_next_page() && !response.items.is_empty();
+            items.extend(response.items);
+            if !more {
+                return Ok(items);
+            }
+            page.page += 1;
+        }
+    }
+
+    /// One page of the most recent llms.txt of every website (of the page's domain, status and time range), in the
+    /// page's order.
+    pub async fn list_page(&self, page: ListPage) -> Result<LlmsTxtListResponse, ClientError> {
+        let (number, per_page) = (page.page.to_string(), page.per_page.to_string());
+        let created_after = page.created_after.map(|time| time.to_rfc3339());
+        let created_before = page.created_before.map(|time| time.to_rfc3339());
+        let mut query = vec![("page", number.as_str()), ("per_page", per_page.as_str())];
+        if page.full {
+            query.push(("full", "true"));
+        }
+        if let Some(domain) = &page.domain {
+            query.push(("domain", domain.as_str()));
+        }
+        if let Some(status) = page.status {
+            query.push(("status", status.as_str()));
+        }
+        if let Some(created_after) = &created_after {
+            query.push(("created_after", created_after.as_str()));
+        }
+        if let Some(created_before) = &created_before {
+            query.push(("created_before", created_before.as_str()));
+        }
+        if page.sort != ListSort::default() {
+            query.push(("sort", page.sort.as_str()));
+        }
+        self.get("/api/list", &query).await
+    }
+
+    /// The status of a job, and its result once it's finished.
+    pub async fn job(&self, job_id: Uuid) -> Result<JobDetailsResponse, ClientError> {
+        self.get("/api/job", &[("job_id", &job_id.to_string())]).await
+    }
+
+    /// The statuses of many jobs in one request per `BatchStatusPayload::MAX_JOB_IDS` of them. The job_ids that no
+    /// job has are in `unknown`.
+    pub async fn statuses(&self, job_ids: &[Uuid]) -> Result<BatchStatusResponse, ClientError> {
+        let mut response = BatchStatusResponse::default();
+        for chunk in job_ids.chunks(BatchStatusPayload::MAX_JOB_IDS) {
+            let payload = BatchStatusPayload {
+                job_ids: chunk.to_vec(),
+            };
+            let page: BatchStatusResponse = self.send_json(Method::Post, "/api/status/batch", &payload).await?;
+            response.statuses.extend(page.statuses);
+            response.unknown.extend(page.unknown);
+        }
+        Ok(response)
+    }
+
+    /// Every queued or running job.
+    pub async fn in_progress_jobs(&self) -> Result<Vec<JobSummary>, ClientError> {
+        self.get("/api/jobs/in_progress", &[]).await
+    }
+
+    /// One page of the jobs (of the page's status and URL), newest first.
+    pub async fn jobs_page(&self, page: JobsPage) -> Result<JobsListResponse, ClientError> {
+        let (number, per_page) = (page.page.to_string(), page.per_page.to_string());
+        let mut query = vec![("page", number.as_str()), ("per_page", per_page.as_str())];
+        if page.status != JobStatusFilter::default() {
+            query.push(("status", page.status.as_str()));
+        }
+        if let Some(url) = &page.url {
+            query.push(("url", url.as_str()));
+        }
+        self.get("/api/jobs", &query).await
+    }
+
+    /// Cancels a queued or running job. Fails with HTTP 409 if it already finished.
+    pub async fn cancel_job(&self, job_id: Uuid) -> Result<JobStatusResponse, ClientError> {
+        self.send_json(Method::Post, "/api/job/cancel", &JobIdPayload { job_id })
+            .await
+    }
+
+    /// What the worker logged while working on a finished job.
+    pub async fn job_logs(&self, job_id: Uuid) -> Result<JobLogsResponse, ClientError> {
+        self.get("/api/job/logs", &[("job_id", &job_id.to_string())]).await
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, ClientError> {
+        self.request(Method::Get, path, query, None).await
+    }
+
+    async fn send_json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &impl serde::Serialize,
+    ) -> Result<T, ClientError> {
+        let body = serde_json::to_string(body).expect("request bodies serialize to JSON");
+        self.request(method, path, &[], Some(body)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint() {
+        assert_eq!(endpoint("https://host:3000", "/api/list"), "https://host:3000/api/list");
+        assert_eq!(
+            endpoint("https://host:3000/", "/api/list"),
+            "https://host:3000/api/list"
+        );
+        // behind a reverse proxy, under a path
+        assert_eq!(
+            endpoint("https://host/prefix/", "/api/job"),
+            "https://host/prefix/api/job"
+        );
+        // in the browser: the page's origin
+        assert_eq!(endpoint("", "/api/job"), "/api/job");
+    }
+
+    #[test]
+    fn test_client_error_status() {
+        let error = ClientError::Api {
+            url: "https://host/api/llm_txt".to_string(),
+            status: 409,
+            message: r#"{"error":"jobs_in_progress"}"#.to_string(),
+        };
+        assert_eq!(error.status(), Some(409));
+        assert_eq!(ClientError::Transport("timed out".to_string()).status(), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::Mutex;
+
+use reqwest::header::{CONTENT_TYPE, COOKIE, HeaderMap, SET_COOKIE};
+use reqwest::{Client, Response, StatusCode};
+use serde::de::DeserializeOwned;
+
+use crate::{ClientError, Method, endpoint};
+
+/// Client for the API server, over `reqwest`.
+///
+/// Authenticates with an API key (sent as an `Authorization: Bearer` header), or with a password: it logs in on the
+/// first `401 Unauthorized`, keeps the session cookie, and retries the request once.
+pub struct ApiClient {
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+    password: Option<String>,
+    cookie: Mutex<Option<String>>,
+    header_hook: Option<fn(&mut HeaderMap)>,
+}
+
+impl ApiClient {
+    /// Client for the API server at `base_url`, e.g. `https://localhost:3000`, without authentication.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        ApiClient {
+            client: Client::new(),
+            base_url: base_url.into(),
+            api_key: None,
+            password: None,
+            cookie: Mutex::new(None),
+            header_hook: None,
+        }
+    }
+
+    /// Sends requests with this `reqwest` client, e.g. one with a timeout or accepting self-signed certificates.
+    pub fn with_http_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Authenticates every request with this API key.
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Logs in with this password when the server asks for authentication.
+    pub fn with_password(mut self, password: Option<String>) -> Self {
+        self.password = password;
+        self
+    }
+
+    /// Adds headers to every request, e.g. to continue the caller's trace.
+    pub fn with_header_hook(mut self, hook: fn(&mut HeaderMap)) -> Self {
+        self.header_hook = Some(hook);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Logs in with the password, keeping the session cookie for the following requests.
+    pub async fn login(&self) -> Result<(), ClientError> {
+        let password = self
+            .password
+            .as_ref()
+            .ok_or_else(|| ClientError::Auth("No password configured for authentication".to_string()))?;
+
+        let response = self
+            .client
+            .post(endpoint(&self.base_url, "/api/auth/login"))
+            .json(&serde_json::json!({ "password": password }))
+            .send()
+            .await
+            .map_err(transport_error)?;
+        if !response.status().is_success() {
+            return Err(ClientError::Auth(format!("login returned HTTP {}", response.status())));
+        }
+
+        // the cookie's value is before the first semicolon (;), followed by its attributes
+        let cookie = response
+            .headers()
+            .get(SET_COOKIE)
+            .ok_or_else(|| ClientError::Auth("No cookie in response".to_string()))?
+            .to_str()
+            .map_err(|_| ClientError::Auth("Invalid cookie header".to_string()))?
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        *self.cookie.lock().expect("cookie lock poisoned") = Some(cookie);
+        Ok(())
+    }
+
+    pub(crate) async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<T, ClientError> {
+        let mut response = self.send(method, path, query, body.clone()).await?;
+        if response.status() == StatusCode::UNAUTHORIZED && self.password.is_some() {
+            self.login().await?;
+            response = self.send(method, path, query, body).await?;
+        }
+
+        let status = response.status();
+        if !status.is_success() {
+            let url = response.url().to_string();
+            let message = response.text().await.unwrap_or_default();
+            return Err(ClientError::Api {
+                url,
+                status: status.as_u16(),
+                message,
+            });
+        }
+        response
+            .json::<T>()
+            .await
+            .map_err(|e| ClientError::Decode(e.to_string()))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<Response, ClientError> {
+        let url = endpoint(&self.base_url, path);
+        let mut request = match method {
+            Method::Get => self.client.get(url),
+            Method::Post => self.client.post(url),
+            Method::Put => self.client.put(url),
+        };
+        if !query.is_empty() {
+            request = request.query(query);
+        }
+        if let Some(body) = body {
+            request = request.header(CONTENT_TYPE, "application/json").body(body);
+        }
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        if let Some(cookie) = self.cookie.lock().expect("cookie lock poisoned").as_ref() {
+            request = request.header(COOKIE, cookie);
+        }
+        if let Some(hook) = self.header_hook {
+            let mut headers = HeaderMap::new();
+            hook(&mut headers);
+            request = request.headers(headers);
+        }
+        request.send().await.map_err(transport_error)
+    }
+}
+
+fn transport_error(e: reqwest::Error) -> ClientError {
+    ClientError::Transport(e.to_string())
+}
@@ -0,0 +1,81 @@
+use serde::de::DeserializeOwned;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, RequestMode, Response};
+
+use crate::{ClientError, Method, endpoint};
+
+/// Client for the API server, over the browser's `fetch`.
+///
+/// Authentication is the browser's: the session cookie set by logging in is sent with every request.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    base_url: String,
+}
+
+impl ApiClient {
+    /// Client for the API server at `base_url`. An empty one is the page's own origin.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        ApiClient {
+            base_url: base_url.into(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub(crate) async fn request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, &str)],
+        body: Option<String>,
+    ) -> Result<T, ClientError> {
+        let mut url = endpoint(&self.base_url, path);
+        for (i, (name, value)) in query.iter().enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.push_str(&format!(
+                "{}={}",
+                js_sys::encode_uri_component(name),
+                js_sys::encode_uri_component(value)
+            ));
+        }
+
+        let opts = RequestInit::new();
+        opts.set_method(method.as_str());
+        opts.set_mode(RequestMode::Cors);
+        if let Some(body) = body {
+            opts.set_body(&JsValue::from_str(&body));
+        }
+        let request = Request::new_with_str_and_init(&url, &opts).map_err(transport_error)?;
+        request
+            .headers()
+            .set("Content-Type", "application/json")
+            .map_err(transport_error)?;
+
+        let window = web_sys::window().ok_or_else(|| ClientError::Transport("no global window exists".to_string()))?;
+        let response: Response = JsFuture::from(window.fetch_with_request(&request))
+            .await
+            .and_then(|response| response.dyn_into())
+            .map_err(transport_error)?;
+        let text = JsFuture::from(response.text().map_err(transport_error)?)
+            .await
+            .map_err(transport_error)?
+            .as_string()
+            .unwrap_or_default();
+
+        if !response.ok() {
+            return Err(ClientError::Api {
+                url,
+                status: response.status(),
+                message: text,
+            });
+        }
+        serde_json::from_str(&text).map_err(|e| ClientError::Decode(e.to_string()))
+    }
+}
+
+fn transport_error(e: JsValue) -> ClientError {
+    ClientError::Transport(e.as_string().unwrap_or_else(|| format!("{:?}", e)))
+}
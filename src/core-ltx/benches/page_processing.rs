@@ -0,0 +1,97 @@
+//! Benchmarks of the work done on every downloaded page and every LLM response: normalizing the HTML, its checksum,
+//! compressing it for storage, and validating the llms.txt.
+//!
+//! The inputs are the `docs_site` golden case, as is ("small") and scaled up by repeating its content ("medium" and
+//! "huge"), so changes like another compression codec or a streaming parser can be compared on the same pages.
+//!
+//! ```bash
+//! cargo bench -p core-ltx --bench page_processing
+//! # only some of them, e.g. the huge pages
+//! cargo bench -p core-ltx --bench page_processing -- huge
+//! ```
+
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+use core_ltx::{compress_string, compute_html_checksum, is_valid_markdown, normalize_html, validate_is_llm_txt};
+
+const PAGE: &str = include_str!("../golden/docs_site/page.html");
+const LLMS_TXT: &str = include_str!("../golden/docs_site/responses/1.md");
+
+/// Times the page's body is repeated: about 8 KiB, 500 KiB and 8 MiB of HTML, within the default `HTML_MAX_BYTES`.
+const PAGE_SCALES: [(&str, usize); 3] = [("small", 1), ("medium", 64), ("huge", 1000)];
+
+/// Times the llms.txt's sections are repeated: about 1.5 KiB, 100 KiB and 2 MiB of markdown, within the default
+/// `MARKDOWN_MAX_BYTES`.
+const LLMS_TXT_SCALES: [(&str, usize); 3] = [("small", 1), ("medium", 64), ("huge", 1400)];
+
+/// The page with its body's content repeated `times`.
+fn scaled_page(times: usize) -> String {
+    let start = PAGE.find("<body>").expect("The page has a body") + "<body>".len();
+    let end = PAGE.rfind("</body>").expect("The page has a body");
+    format!("{}{}{}", &PAGE[..start], PAGE[start..end].repeat(times), &PAGE[end..])
+}
+
+/// The llms.txt with its sections (everything from the first H2) repeated `times`.
+fn scaled_llms_txt(times: usize) -> String {
+    let sections = LLMS_TXT.find("\n## ").expect("The llms.txt has sections");
+    format!("{}{}", &LLMS_TXT[..sections], LLMS_TXT[sections..].repeat(times))
+}
+
+fn bench_html(c: &mut Criterion) {
+    let mut group = c.benchmark_group("html");
+    group.sample_size(10);
+    for (scale, times) in PAGE_SCALES {
+        let page = scaled_page(times);
+        let normalized = normalize_html(&page).expect("The page normalizes");
+        group.throughput(Throughput::Bytes(page.len() as u64));
+
+        group.bench_with_input(BenchmarkId::new("normalize_html", scale), &page, |b, page| {
+            b.iter(|| normalize_html(black_box(page)).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("compute_html_checksum", scale),
+            &normalized,
+            |b, normalized| b.iter(|| compute_html_checksum(black_box(normalized)).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("compress_string", scale),
+            &normalized,
+            |b, normalized| b.iter(|| compress_string(black_box(normalized.as_str())).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+fn bench_llms_txt(c: &mut Criterion) {
+    let mut group = c.benchmark_group("llms_txt");
+    group.sample_size(10);
+    for (scale, times) in LLMS_TXT_SCALES {
+        let llms_txt = scaled_llms_txt(times);
+        let markdown = is_valid_markdown(&llms_txt).expect("The llms.txt is markdown");
+        group.throughput(Throughput::Bytes(llms_txt.len() as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("is_valid_markdown", scale),
+            &llms_txt,
+            |b, llms_txt| b.iter(|| is_valid_markdown(black_box(llms_txt)).unwrap()),
+        );
+        // validation takes the parsed document: each iteration gets its own copy
+        group.bench_with_input(
+            BenchmarkId::new("validate_is_llm_txt", scale),
+            &markdown,
+            |b, markdown| {
+                b.iter_batched(
+                    || markdown.clone(),
+                    |markdown| validate_is_llm_txt(markdown).unwrap(),
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_html, bench_llms_txt);
+criterion_main!(benches);
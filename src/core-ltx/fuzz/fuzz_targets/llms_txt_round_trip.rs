@@ -0,0 +1,12 @@
+//! Valid llms.txt files must render to text that parses and validates back to the same llms.txt.
+
+#![no_main]
+
+use core_ltx::round_trip::check_round_trip;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|content: &str| {
+    if let Err(asymmetry) = check_round_trip(content) {
+        panic!("{}", asymmetry);
+    }
+});
@@ -0,0 +1,10 @@
+//! Parsing and validating any text returns, whether or not it's an llms.txt: the validator never panics.
+
+#![no_main]
+
+use core_ltx::{is_valid_markdown, validate_is_llm_txt};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|content: &str| {
+    let _ = is_valid_markdown(content).and_then(validate_is_llm_txt);
+});
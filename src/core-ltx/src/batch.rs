@@ -0,0 +1,87 @@
+use url::Url;
+
+/// Parses a list of website URLs: one URL per line.
+/// Surrounding whitespace is trimmed. Blank lines and lines starting with `#` are skipped.
+pub fn parse_url_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Derives a filesystem-safe llms.txt file name from a website URL.
+///
+/// The host, port, path segments, and query are joined with `_` and any character that
+/// isn't alphanumeric, `.`, or `-` is replaced with `_`.
+/// E.g. `https://docs.example.com/guide/intro` -> `docs.example.com_guide_intro.llms.txt`
+pub fn output_file_name(url: &Url) -> String {
+    let mut parts: Vec<String> = vec![url.host_str().unwrap_or("unknown-host").to_string()];
+    if let Some(port) = url.port() {
+        parts.push(port.to_string());
+    }
+    if let Some(segments) = url.path_segments() {
+        parts.extend(segments.filter(|s| !s.is_empty()).map(str::to_string));
+    }
+    if let Some(query) = url.query()
+        && !query.is_empty()
+    {
+        parts.push(query.to_string());
+    }
+
+    let name: String = parts
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!("{}.llms.txt", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url_list_skips_blanks_and_comments() {
+        let content = "https://example.com\n\n# a comment\n   https://docs.example.com/guide  \n";
+        assert_eq!(
+            parse_url_list(content),
+            vec![
+                "https://example.com".to_string(),
+                "https://docs.example.com/guide".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_url_list_empty() {
+        assert!(parse_url_list("").is_empty());
+        assert!(parse_url_list("\n# only comments\n").is_empty());
+    }
+
+    #[test]
+    fn test_output_file_name_root() {
+        let url = Url::parse("https://example.com").unwrap();
+        assert_eq!(output_file_name(&url), "example.com.llms.txt");
+
+        let url = Url::parse("https://example.com/").unwrap();
+        assert_eq!(output_file_name(&url), "example.com.llms.txt");
+    }
+
+    #[test]
+    fn test_output_file_name_with_path_port_and_query() {
+        let url = Url::parse("https://docs.example.com/guide/intro/").unwrap();
+        assert_eq!(output_file_name(&url), "docs.example.com_guide_intro.llms.txt");
+
+        let url = Url::parse("http://localhost:8080/a?b=c&d").unwrap();
+        assert_eq!(output_file_name(&url), "localhost_8080_a_b_c_d.llms.txt");
+    }
+}
@@ -8,13 +8,27 @@ pub struct AuthConfig {
     /// Plain text password for programmatic authentication (e.g., cron service)
     /// Only populated when AUTH_PASSWORD is set
     pub password: Option<String>,
+    /// When true, read-only (GET) endpoints are served without authentication.
+    /// Only endpoints that change data require a login.
+    pub public_reads: bool,
 }
 
 /// Check if authentication is enabled
 /// True if the env var ENABLE_AUTH is present and is one of "1", "true", "yes", or "y".
 /// False otherwise.
 pub fn is_auth_enabled() -> bool {
-    env::var("ENABLE_AUTH")
+    is_env_flag_set("ENABLE_AUTH")
+}
+
+/// Check if public read-only mode is enabled
+/// True if the env var PUBLIC_READ_ONLY is present and is one of "1", "true", "yes", or "y".
+/// Only has an effect when authentication is enabled.
+pub fn is_public_read_only() -> bool {
+    is_env_flag_set("PUBLIC_READ_ONLY")
+}
+
+pub(crate) fn is_env_flag_set(name: &str) -> bool {
+    env::var(name)
         .map(|v| {
             let v = v.trim().to_lowercase();
             v == "1" || v == "true" || v == "yes" || v == "y"
@@ -52,6 +66,7 @@ pub fn get_auth_config() -> Option<AuthConfig> {
         session_secret,
         session_duration_seconds,
         password,
+        public_reads: is_public_read_only(),
     })
 }
 
@@ -96,6 +111,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_public_read_only() {
+        let _guard = TEST_MUTEX.lock().unwrap();
+        unsafe {
+            env::remove_var("PUBLIC_READ_ONLY");
+        }
+        assert!(!is_public_read_only());
+        unsafe {
+            env::set_var("PUBLIC_READ_ONLY", "yes");
+        }
+        assert!(is_public_read_only());
+        unsafe {
+            env::remove_var("PUBLIC_READ_ONLY");
+        }
+    }
+
     #[test]
     fn test_is_auth_enabled_false() {
         let _guard = TEST_MUTEX.lock().unwrap();
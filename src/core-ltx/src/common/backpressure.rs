@@ -0,0 +1,84 @@
+use std::time::Duration;
+
+use crate::common::poll_interval::{TimeUnit, get_poll_interval};
+
+/// Environment variable with the number of queued jobs at which the queue counts as backed up.
+pub const QUEUE_DEPTH_THRESHOLD_ENV_VAR: &str = "QUEUE_DEPTH_THRESHOLD";
+/// Environment variable with the estimated wait (in seconds) at which the queue counts as backed up.
+pub const QUEUE_WAIT_THRESHOLD_ENV_VAR: &str = "QUEUE_WAIT_THRESHOLD_S";
+
+/// Default number of queued jobs at which the queue counts as backed up.
+pub const DEFAULT_QUEUE_DEPTH_THRESHOLD: u64 = 100;
+/// Default estimated wait at which the queue counts as backed up: 10 minutes.
+pub const DEFAULT_QUEUE_WAIT_THRESHOLD_S: u64 = 600;
+
+/// When the job queue counts as backed up: new jobs are then told where they are in the queue and when they're
+/// expected to start, and low-priority ones are turned away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueThresholds {
+    /// Queued jobs, counting the new one.
+    pub depth: u64,
+    /// Estimated wait before the new job starts.
+    pub wait: Duration,
+}
+
+impl QueueThresholds {
+    /// Whether a job at this position in the queue, with this estimated wait, is past either threshold.
+    /// An unknown wait (nothing finished lately to estimate from) only counts through the position.
+    pub fn exceeded(&self, position: u64, wait: Option<Duration>) -> bool {
+        position >= self.depth || wait.is_some_and(|wait| wait >= self.wait)
+    }
+}
+
+/// Reads the thresholds from `QUEUE_DEPTH_THRESHOLD` and `QUEUE_WAIT_THRESHOLD_S`, with defaults for unset ones.
+/// Panics on invalid values.
+pub fn get_queue_thresholds() -> QueueThresholds {
+    let depth = match std::env::var(QUEUE_DEPTH_THRESHOLD_ENV_VAR) {
+        Ok(value) => match value.trim().parse::<u64>() {
+            Ok(depth) if depth > 0 => depth,
+            _ => panic!("{} must be a positive number of jobs", QUEUE_DEPTH_THRESHOLD_ENV_VAR),
+        },
+        Err(_) => DEFAULT_QUEUE_DEPTH_THRESHOLD,
+    };
+    let wait = get_poll_interval(
+        TimeUnit::Seconds,
+        QUEUE_WAIT_THRESHOLD_ENV_VAR,
+        DEFAULT_QUEUE_WAIT_THRESHOLD_S,
+    );
+    QueueThresholds { depth, wait }
+}
+
+/// Estimated wait before the job at `position` (1 is next) starts, from how many jobs finished in the last `window`.
+/// None when nothing finished in the window, as there's no throughput to estimate from.
+pub fn estimate_wait(position: u64, finished_in_window: u64, window: Duration) -> Option<Duration> {
+    if finished_in_window == 0 {
+        return None;
+    }
+    let ahead = position.saturating_sub(1);
+    Some(window.mul_f64(ahead as f64 / finished_in_window as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_wait() {
+        let hour = Duration::from_secs(3600);
+        assert_eq!(estimate_wait(1, 60, hour), Some(Duration::ZERO));
+        assert_eq!(estimate_wait(31, 60, hour), Some(Duration::from_secs(1800)));
+        assert_eq!(estimate_wait(10, 0, hour), None);
+    }
+
+    #[test]
+    fn test_thresholds_exceeded() {
+        let thresholds = QueueThresholds {
+            depth: 100,
+            wait: Duration::from_secs(600),
+        };
+        assert!(!thresholds.exceeded(99, Some(Duration::from_secs(599))));
+        assert!(thresholds.exceeded(100, None));
+        assert!(thresholds.exceeded(2, Some(Duration::from_secs(600))));
+        assert!(!thresholds.exceeded(2, None));
+    }
+}
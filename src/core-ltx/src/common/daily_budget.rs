@@ -0,0 +1,26 @@
+/// Environment variable with the maximum number of LLM tokens the workers may use per day (UTC).
+pub const DAILY_TOKEN_BUDGET_ENV_VAR: &str = "DAILY_TOKEN_BUDGET";
+
+/// Same as daily_token_budget but panics on error.
+pub fn get_daily_token_budget() -> Option<u64> {
+    daily_token_budget().unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// The daily LLM token budget, or None if there's no limit (the environment variable is unset or empty).
+/// Errors if the value is not a positive integer.
+pub fn daily_token_budget() -> Result<Option<u64>, String> {
+    let Ok(value) = std::env::var(DAILY_TOKEN_BUDGET_ENV_VAR) else {
+        return Ok(None);
+    };
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    match value.parse::<u64>() {
+        Ok(budget) if budget > 0 => Ok(Some(budget)),
+        _ => Err(format!(
+            "{} must be a positive number of tokens",
+            DAILY_TOKEN_BUDGET_ENV_VAR
+        )),
+    }
+}
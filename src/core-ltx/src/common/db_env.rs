@@ -1,5 +1,6 @@
 use std::env::VarError;
 
+use crate::common::auth_config::is_env_flag_set;
 use crate::common::db::{DbPool, establish_connection_pool};
 
 /// Uses the env var DATABASE_URL to establish a database connection pool using diesel.
@@ -13,6 +14,12 @@ pub async fn get_db_pool() -> DbPool {
     }
 }
 
+/// True if the env var RUN_MIGRATIONS is one of "1", "true", "yes", or "y".
+/// Binaries that use the database then apply pending migrations when they start.
+pub fn is_run_migrations_enabled() -> bool {
+    is_env_flag_set("RUN_MIGRATIONS")
+}
+
 /// Retrieves the value for the env var DATABASE_URL.
 pub fn get_database_url() -> Result<String, VarError> {
     std::env::var("DATABASE_URL")
@@ -0,0 +1,45 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use sentry::{Hub, SentryFutureExt};
+
+/// When set, panics and error-level log events are reported to this Sentry DSN.
+pub const SENTRY_DSN_ENV_VAR: &str = "SENTRY_DSN";
+
+/// Starts reporting panics to Sentry if `SENTRY_DSN` is set. The returned guard flushes pending reports when dropped.
+///
+/// Error-level log events are reported by the `sentry::integrations::tracing` layer, which `setup_telemetry`
+/// installs whenever this returns a guard. `SENTRY_ENVIRONMENT` is honored as usual.
+pub fn init_error_reporting(service_name: &str) -> Option<sentry::ClientInitGuard> {
+    let dsn = std::env::var(SENTRY_DSN_ENV_VAR)
+        .ok()
+        .filter(|dsn| !dsn.trim().is_empty())?;
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: Some(format!("{}@{}", service_name, env!("CARGO_PKG_VERSION")).into()),
+            ..Default::default()
+        },
+    ));
+    if !guard.is_enabled() {
+        eprintln!(
+            "{} is set, but is not a valid Sentry DSN: not reporting errors",
+            SENTRY_DSN_ENV_VAR
+        );
+        return None;
+    }
+    sentry::configure_scope(|scope| scope.set_tag("service", service_name));
+    Some(guard)
+}
+
+/// Runs `future` with the `tags` attached to every error it reports, e.g. the job ID and URL being worked on.
+/// Has no effect when error reporting isn't enabled.
+pub fn with_error_context<F: Future>(tags: &[(&str, &str)], future: F) -> impl Future<Output = F::Output> + use<F> {
+    let hub = Arc::new(Hub::new_from_top(Hub::current()));
+    hub.configure_scope(|scope| {
+        for (key, value) in tags {
+            scope.set_tag(key, value);
+        }
+    });
+    future.bind_hub(hub)
+}
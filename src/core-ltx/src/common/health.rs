@@ -1,4 +1,12 @@
-use axum::{Router, http::StatusCode};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::{Router, extract::State, http::StatusCode};
+
+use crate::db::DbPool;
+
+/// How long `/ready` waits for a database connection before reporting the database as unavailable.
+const DB_READY_TIMEOUT: Duration = Duration::from_secs(2);
 
 pub async fn health_check() -> (StatusCode, &'static str) {
     (StatusCode::OK, "healthy")
@@ -7,3 +15,85 @@ pub async fn health_check() -> (StatusCode, &'static str) {
 pub fn health_router() -> Router {
     Router::new().route("/health", axum::routing::get(health_check))
 }
+
+/// GET /ready - 200 if a database connection can be acquired, 503 otherwise.
+/// The pool checks connections before handing them out, so this also catches a database that stopped answering.
+pub async fn db_ready_check(State(pool): State<DbPool>) -> (StatusCode, String) {
+    match tokio::time::timeout(DB_READY_TIMEOUT, pool.get()).await {
+        Ok(Ok(_conn)) => (StatusCode::OK, "ready".to_string()),
+        Ok(Err(e)) => {
+            tracing::warn!("Not ready: database unavailable: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("unready: database unavailable: {}", e),
+            )
+        }
+        Err(_) => {
+            tracing::warn!("Not ready: no database connection after {:?}", DB_READY_TIMEOUT);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "unready: database connection timed out".to_string(),
+            )
+        }
+    }
+}
+
+/// Outcome of the dependency checks a service runs when it starts. Shared between the service and its health server.
+#[derive(Debug, Clone)]
+pub struct Readiness(Arc<RwLock<Result<(), String>>>);
+
+impl Readiness {
+    /// Unhealthy until `set_ready`: the checks haven't run yet.
+    pub fn starting() -> Self {
+        Self(Arc::new(RwLock::new(Err("starting".to_string()))))
+    }
+
+    pub fn set_ready(&self) {
+        *self.0.write().expect("readiness lock poisoned") = Ok(());
+    }
+
+    /// Reports the service unhealthy, with the reason shown by its health endpoints.
+    pub fn set_failed(&self, reason: String) {
+        *self.0.write().expect("readiness lock poisoned") = Err(reason);
+    }
+
+    pub fn status(&self) -> Result<(), String> {
+        self.0.read().expect("readiness lock poisoned").clone()
+    }
+}
+
+async fn readiness_check(State(readiness): State<Readiness>) -> (StatusCode, String) {
+    match readiness.status() {
+        Ok(()) => (StatusCode::OK, "healthy".to_string()),
+        Err(reason) => (StatusCode::SERVICE_UNAVAILABLE, format!("unhealthy: {}", reason)),
+    }
+}
+
+/// `/health` and `/ready`, both answering 503 with the reason until `readiness` is set ready.
+pub fn readiness_router(readiness: Readiness) -> Router {
+    Router::new()
+        .route("/health", axum::routing::get(readiness_check))
+        .route("/ready", axum::routing::get(readiness_check))
+        .with_state(readiness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_readiness_check() {
+        let readiness = Readiness::starting();
+        let (status, body) = readiness_check(State(readiness.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "unhealthy: starting");
+
+        readiness.set_ready();
+        assert_eq!(readiness_check(State(readiness.clone())).await.0, StatusCode::OK);
+
+        readiness.set_failed("LLM provider check failed: invalid API key".to_string());
+        let (status, body) = readiness_check(State(readiness)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "unhealthy: LLM provider check failed: invalid API key");
+    }
+}
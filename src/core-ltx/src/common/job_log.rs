@@ -0,0 +1,195 @@
+//! Per-job logs: the events logged while working on a job, kept so that they can be stored with it.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{SecondsFormat, Utc};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Events are captured inside spans with this name and a `job_id` field, e.g. `info_span!("job", job_id = %id)`.
+pub const JOB_SPAN_NAME: &str = "job";
+
+/// Most bytes of log kept per job. Later events are dropped, and the log is marked as truncated.
+pub const MAX_JOB_LOG_BYTES: usize = 64 * 1024;
+
+/// The events of one job, one per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobLog {
+    pub text: String,
+    /// Events were dropped because the log reached `MAX_JOB_LOG_BYTES`.
+    pub truncated: bool,
+}
+
+impl JobLog {
+    fn push_line(&mut self, line: &str) {
+        if self.truncated {
+            return;
+        }
+        if self.text.len() + line.len() + 1 > MAX_JOB_LOG_BYTES {
+            self.truncated = true;
+            return;
+        }
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+}
+
+/// Logs of the jobs in progress, by job ID.
+static JOB_LOGS: LazyLock<Mutex<HashMap<String, JobLog>>> = LazyLock::new(Mutex::default);
+
+/// Removes and returns the log of a job. Call it when the job is done, while still in its span.
+pub fn take_job_log(job_id: &str) -> Option<JobLog> {
+    JOB_LOGS.lock().expect("job log lock poisoned").remove(job_id)
+}
+
+/// The job ID of a job span, kept in the span's extensions.
+struct JobId(String);
+
+/// Tracing layer that copies every event logged inside a job span to that job's log (see `take_job_log`).
+/// Only events that the subscriber's filter (`RUST_LOG`) lets through are captured.
+pub struct JobLogLayer;
+
+impl<S> Layer<S> for JobLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != JOB_SPAN_NAME {
+            return;
+        }
+        let mut visitor = JobIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(job_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(JobId(job_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(scope) = ctx.event_scope(event) else {
+            return;
+        };
+        for span in scope {
+            if let Some(JobId(job_id)) = span.extensions().get::<JobId>() {
+                let line = format_event(event);
+                JOB_LOGS
+                    .lock()
+                    .expect("job log lock poisoned")
+                    .entry(job_id.clone())
+                    .or_default()
+                    .push_line(&line);
+                return;
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        // a log that wasn't taken (e.g. events logged after the job was stored) would otherwise be kept forever
+        if let Some(span) = ctx.span(&id)
+            && let Some(JobId(job_id)) = span.extensions().get::<JobId>()
+        {
+            take_job_log(job_id);
+        }
+    }
+}
+
+struct JobIdVisitor(Option<String>);
+
+impl Visit for JobIdVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "job_id" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "job_id" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+/// `<timestamp> <LEVEL> <target>: <message> <field>=<value> ...`
+fn format_event(event: &Event<'_>) -> String {
+    let metadata = event.metadata();
+    let mut visitor = EventVisitor::default();
+    event.record(&mut visitor);
+    let mut line = format!(
+        "{} {} {}: {}",
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        metadata.level(),
+        metadata.target(),
+        visitor.message
+    );
+    line.push_str(&visitor.fields);
+    line
+}
+
+#[derive(Default)]
+struct EventVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::*;
+
+    #[test]
+    fn test_captures_events_of_job_spans() {
+        let subscriber = tracing_subscriber::registry().with(JobLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside of any job");
+            let span = tracing::info_span!("job", job_id = %"job-1", url = "https://example.com");
+            span.in_scope(|| {
+                tracing::info!(attempt = 2, "Downloading");
+                tracing::warn_span!("download").in_scope(|| tracing::warn!("Slow response"));
+            });
+            tracing::info_span!("other", job_id = "job-2").in_scope(|| tracing::info!("not a job span"));
+
+            let log = span.in_scope(|| take_job_log("job-1")).unwrap();
+            let lines: Vec<&str> = log.text.lines().collect();
+            assert_eq!(lines.len(), 2);
+            assert!(lines[0].ends_with(" INFO core_ltx::common::job_log::tests: Downloading attempt=2"));
+            assert!(lines[1].ends_with(" WARN core_ltx::common::job_log::tests: Slow response"));
+            assert!(!log.truncated);
+            assert_eq!(take_job_log("job-2"), None);
+        });
+    }
+
+    #[test]
+    fn test_log_is_capped_and_dropped_when_the_job_span_closes() {
+        let subscriber = tracing_subscriber::registry().with(JobLogLayer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("job", job_id = %"job-3");
+            span.in_scope(|| {
+                for _ in 0..(MAX_JOB_LOG_BYTES / 100 + 10) {
+                    tracing::info!("{}", "x".repeat(100));
+                }
+            });
+            let log = span.in_scope(|| take_job_log("job-3")).unwrap();
+            assert!(log.truncated);
+            assert!(log.text.len() <= MAX_JOB_LOG_BYTES);
+
+            span.in_scope(|| tracing::info!("after the log was taken"));
+            drop(span);
+            assert_eq!(take_job_log("job-3"), None);
+        });
+    }
+}
@@ -1,11 +1,17 @@
 pub mod auth_config;
+pub mod backpressure;
 pub mod compression;
+pub mod daily_budget;
 pub mod db;
 pub mod db_env;
 pub mod env_check;
+pub mod error_reporting;
 pub mod health;
 pub mod hostname;
+pub mod job_log;
 pub mod logging;
 pub mod max_concurrency;
 pub mod poll_interval;
+pub mod reload;
+pub mod telemetry;
 pub mod tls_config;
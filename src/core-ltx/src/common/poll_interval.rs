@@ -24,3 +24,56 @@ pub fn poll_interval(units: TimeUnit, env_var_name: &str, default: u64) -> Resul
     };
     Ok(interval)
 }
+
+/// Parses a human-friendly duration: a whole number followed by a unit of `ms`, `s`, `m`, `h`, or `d`.
+/// A number without a unit is in seconds. E.g. `500ms`, `30s`, `10m`, `2h`, `45`.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split_at = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration '{}': must start with a whole number", value))?;
+
+    let seconds_per_unit = match unit.trim() {
+        "ms" => return Ok(Duration::from_millis(number)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        other => {
+            return Err(format!(
+                "Invalid duration unit '{}' in '{}': expected one of ms, s, m, h, d",
+                other, value
+            ));
+        }
+    };
+    number
+        .checked_mul(seconds_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Invalid duration '{}': too long", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+        assert_eq!(parse_duration("10m"), Ok(Duration::from_secs(600)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(86400)));
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("10y").is_err());
+        assert!(parse_duration("-5s").is_err());
+        assert!(parse_duration(&format!("{}d", u64::MAX / 1000)).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use tokio::sync::watch;
+
+/// Loads configuration with `load`, and loads it again every time the process receives SIGHUP.
+///
+/// Before reloading, the `.env` file (if any) is re-read and overrides the environment, so editing it
+/// and sending `kill -HUP` is enough to change settings. If reloading fails, the error is logged and the
+/// previous configuration stays in place. Must be called from within a tokio runtime.
+///
+/// On non-Unix platforms there's no SIGHUP: the configuration is only loaded once.
+pub fn reload_on_sighup<T, F>(name: &'static str, load: F) -> Result<watch::Receiver<T>, String>
+where
+    T: Send + Sync + 'static,
+    F: Fn() -> Result<T, String> + Send + 'static,
+{
+    let (sender, receiver) = watch::channel(load()?);
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut hangups = match signal(SignalKind::hangup()) {
+            Ok(hangups) => hangups,
+            Err(e) => {
+                tracing::error!(
+                    "Cannot listen for SIGHUP, {} configuration will not be reloaded: {}",
+                    name,
+                    e
+                );
+                return;
+            }
+        };
+        while hangups.recv().await.is_some() {
+            dotenvy::dotenv_override().ok();
+            match load() {
+                Ok(config) => {
+                    tracing::info!("Received SIGHUP: reloaded {} configuration", name);
+                    if sender.send(config).is_err() {
+                        // nothing is using the configuration anymore
+                        return;
+                    }
+                }
+                Err(e) => tracing::error!("Received SIGHUP: keeping the current {} configuration: {}", name, e),
+            }
+        }
+    });
+    #[cfg(not(unix))]
+    {
+        let _ = (name, sender);
+    }
+    Ok(receiver)
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use axum::http::{HeaderMap, HeaderValue};
+use opentelemetry::{global, trace::TracerProvider as _};
+use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, trace::SdkTracerProvider};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::common::error_reporting::init_error_reporting;
+use crate::common::job_log::JobLogLayer;
+
+/// When set, spans are exported over OTLP (HTTP) to this collector, e.g. `http://localhost:4318`.
+pub const OTLP_ENDPOINT_ENV_VAR: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// W3C trace context header carrying the trace ID and parent span ID.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Flushes and shuts down the OTLP exporter and Sentry client when dropped. Keep it alive for the whole program.
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+    _sentry: Option<sentry::ClientInitGuard>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take()
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+        }
+    }
+}
+
+/// Like `setup_logging`, but also exports spans over OTLP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+/// and reports panics and error-level events to Sentry when `SENTRY_DSN` is set.
+///
+/// Events logged inside a job span are also kept in that job's log (see `JobLogLayer`).
+///
+/// Spans are tagged with `service_name` so a job can be followed across the API, worker, and cron
+/// services in Jaeger or Tempo. Without either environment variable, this only sets up logging.
+pub fn setup_telemetry(service_name: &str, default_log_settings: &str) -> TelemetryGuard {
+    let sentry = init_error_reporting(service_name);
+
+    let provider = match std::env::var(OTLP_ENDPOINT_ENV_VAR) {
+        Ok(endpoint) if !endpoint.trim().is_empty() => match build_tracer_provider(service_name) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                eprintln!("Failed to set up OpenTelemetry exporter to {}: {}", endpoint, e);
+                None
+            }
+        },
+        _ => None,
+    };
+
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name.to_string())));
+
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| default_log_settings.into()))
+        .with(tracing_subscriber::fmt::layer())
+        .with(JobLogLayer)
+        .with(otel_layer)
+        .with(sentry.as_ref().map(|_| sentry::integrations::tracing::layer()))
+        .init();
+
+    if provider.is_some() {
+        tracing::info!("Exporting OpenTelemetry spans for '{}'", service_name);
+    }
+    if sentry.is_some() {
+        tracing::info!("Reporting errors to Sentry for '{}'", service_name);
+    }
+    TelemetryGuard {
+        provider,
+        _sentry: sentry,
+    }
+}
+
+fn build_tracer_provider(service_name: &str) -> Result<SdkTracerProvider, opentelemetry_otlp::ExporterBuildError> {
+    // the exporter reads the endpoint (and any headers) from the standard OTEL_EXPORTER_OTLP_* variables
+    let exporter = opentelemetry_otlp::SpanExporter::builder().with_http().build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+        .build();
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
+
+/// The current span's W3C `traceparent`, for carrying the trace across a process boundary (e.g. a job row).
+/// None when spans aren't being exported.
+pub fn current_trace_context() -> Option<String> {
+    let context = tracing::Span::current().context();
+    let mut carrier: HashMap<String, String> = HashMap::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut carrier));
+    carrier.remove(TRACEPARENT_HEADER)
+}
+
+/// Makes `span` a child of the remote span described by the W3C `traceparent`.
+pub fn set_parent_from_trace_context(span: &tracing::Span, traceparent: &str) {
+    let carrier = HashMap::from([(TRACEPARENT_HEADER.to_string(), traceparent.to_string())]);
+    let context = global::get_text_map_propagator(|propagator| propagator.extract(&carrier));
+    span.set_parent(context);
+}
+
+/// Makes `span` a child of the remote span in the request's `traceparent` header, if it has one.
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+    if let Some(traceparent) = headers.get(TRACEPARENT_HEADER).and_then(|v| v.to_str().ok()) {
+        set_parent_from_trace_context(span, traceparent);
+    }
+}
+
+/// Adds the current span's `traceparent` header to an outgoing request, so the receiver continues the trace.
+pub fn inject_trace_headers(headers: &mut HeaderMap) {
+    if let Some(traceparent) = current_trace_context()
+        && let Ok(value) = HeaderValue::from_str(&traceparent)
+    {
+        headers.insert(TRACEPARENT_HEADER, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_context_round_trip() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber =
+            tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+            let span = tracing::info_span!("job");
+            set_parent_from_trace_context(&span, parent);
+            let _entered = span.enter();
+
+            // same trace, new span
+            let traceparent = current_trace_context().unwrap();
+            assert!(traceparent.starts_with("00-4bf92f3577b34da6a3ce929d0e0e4736-"));
+            assert!(!traceparent.contains("00f067aa0ba902b7"));
+
+            let mut headers = HeaderMap::new();
+            inject_trace_headers(&mut headers);
+            assert_eq!(headers.get(TRACEPARENT_HEADER).unwrap(), traceparent.as_str());
+        });
+    }
+}
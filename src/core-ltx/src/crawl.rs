@@ -0,0 +1,325 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use url::Url;
+
+use crate::{Error, download, normalize_html};
+
+/// File extensions of links that never point to an HTML page, so they're never downloaded.
+const NON_HTML_EXTENSIONS: [&str; 16] = [
+    ".png", ".jpg", ".jpeg", ".gif", ".svg", ".webp", ".ico", ".pdf", ".zip", ".gz", ".tar", ".mp4", ".mp3", ".css",
+    ".js", ".woff2",
+];
+
+/// Limits on how much of a website a crawl visits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrawlOptions {
+    /// How many links away from the starting page to follow. 0 means only the starting page.
+    pub max_depth: usize,
+    /// Maximum number of pages to download.
+    pub max_pages: usize,
+}
+
+/// A single page downloaded during a crawl.
+#[derive(Debug, Clone)]
+pub struct CrawledPage {
+    pub url: Url,
+    /// Number of links followed from the starting page to reach this page.
+    pub depth: usize,
+    pub html: String,
+}
+
+/// Crawls a website breadth-first from `start`, following only links to the same host.
+///
+/// Failing to download the starting page is an error. Failures on any other page are logged and skipped.
+pub async fn crawl(start: &Url, options: CrawlOptions) -> Result<Vec<CrawledPage>, Error> {
+    crawl_with_progress(start, options, |_| ()).await
+}
+
+/// Same as `crawl`, but calls `on_page` with each page as soon as it's downloaded.
+pub async fn crawl_with_progress<F>(
+    start: &Url,
+    options: CrawlOptions,
+    mut on_page: F,
+) -> Result<Vec<CrawledPage>, Error>
+where
+    F: FnMut(&CrawledPage),
+{
+    let start = without_fragment(start);
+
+    let mut pages: Vec<CrawledPage> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::from([start.to_string()]);
+    let mut queue: VecDeque<(Url, usize)> = VecDeque::from([(start.clone(), 0)]);
+
+    while let Some((url, depth)) = queue.pop_front() {
+        if pages.len() >= options.max_pages {
+            break;
+        }
+
+        let html = match download(&url).await {
+            Ok(html) => html,
+            Err(e) if url == start => return Err(e),
+            Err(e) => {
+                tracing::warn!("Skipping page that failed to download ({}): {}", url, e);
+                continue;
+            }
+        };
+        tracing::debug!("Crawled '{}' (depth: {}, {} bytes)", url, depth, html.len());
+
+        if depth < options.max_depth {
+            for link in extract_links(&html, &url) {
+                if link.host_str() == start.host_str() && seen.insert(link.to_string()) {
+                    queue.push_back((link, depth + 1));
+                }
+            }
+        }
+
+        let page = CrawledPage { url, depth, html };
+        on_page(&page);
+        pages.push(page);
+    }
+
+    Ok(pages)
+}
+
+/// Extracts the targets of all `<a href>` links in the HTML that point to web pages.
+/// Relative links are resolved against `base`. Fragments are removed and non-HTTP(S) links are dropped.
+pub fn extract_links(html: &str, base: &Url) -> Vec<Url> {
+    let dom = match parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+    {
+        Ok(dom) => dom,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut hrefs = Vec::new();
+    collect_hrefs(&dom.document, &mut hrefs);
+
+    hrefs
+        .iter()
+        .filter_map(|href| base.join(href.trim()).ok())
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .filter(|url| {
+            let path = url.path().to_lowercase();
+            !NON_HTML_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+        })
+        .map(|url| without_fragment(&url))
+        .collect()
+}
+
+/// Recursively collects every `href` value of `<a>` elements under the node.
+fn collect_hrefs(handle: &Handle, hrefs: &mut Vec<String>) {
+    if let NodeData::Element { name, attrs, .. } = &handle.data
+        && &*name.local == "a"
+    {
+        hrefs.extend(
+            attrs
+                .borrow()
+                .iter()
+                .filter(|attr| &*attr.name.local == "href")
+                .map(|attr| attr.value.to_string()),
+        );
+    }
+    for child in handle.children.borrow().iter() {
+        collect_hrefs(child, hrefs);
+    }
+}
+
+fn without_fragment(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url
+}
+
+/// Reads a built static site from disk as if it had been crawled, mapping each HTML file to its URL under `base_url`.
+///
+/// `index.html` files map to their directory's URL, e.g. `docs/index.html` -> `https://example.com/docs/`.
+/// Other files keep their name, e.g. `about.html` -> `https://example.com/about.html`.
+/// Pages are ordered shallowest first, then by URL, and at most `max_pages` are read.
+pub fn pages_from_dir(dir: &Path, base_url: &Url, max_pages: usize) -> Result<Vec<CrawledPage>, Error> {
+    let mut files: Vec<(PathBuf, Url, usize)> = html_files(dir)?
+        .into_iter()
+        .filter_map(|file| {
+            let relative = file.strip_prefix(dir).ok()?.to_path_buf();
+            let (url, depth) = url_for_file(base_url, &relative)?;
+            Some((file, url, depth))
+        })
+        .collect();
+    files.sort_by(|(_, a_url, a_depth), (_, b_url, b_depth)| (a_depth, a_url.as_str()).cmp(&(b_depth, b_url.as_str())));
+
+    files
+        .into_iter()
+        .take(max_pages)
+        .map(|(file, url, depth)| {
+            let html = std::fs::read_to_string(&file)?;
+            Ok(CrawledPage { url, depth, html })
+        })
+        .collect()
+}
+
+/// Recursively finds every `.html` and `.htm` file under the directory.
+fn html_files(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+            {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Maps a file's path, relative to the site's root directory, to its URL and depth.
+fn url_for_file(base_url: &Url, relative: &Path) -> Option<(Url, usize)> {
+    let mut segments: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_str().map(str::to_string))
+        .collect::<Option<_>>()?;
+    let is_index = segments
+        .last()
+        .is_some_and(|name| name == "index.html" || name == "index.htm");
+    if is_index {
+        segments.pop();
+    }
+    let depth = segments.len().saturating_sub(if is_index { 0 } else { 1 });
+
+    let mut path = segments.join("/");
+    if is_index && !path.is_empty() {
+        path.push('/');
+    }
+    let mut base = base_url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    Some((base.join(&path).ok()?, depth))
+}
+
+/// Combines crawled pages into a single website document for llms.txt generation.
+/// Each page's normalized HTML (or raw HTML, if it cannot be normalized) is wrapped in a `<page url="...">` tag.
+pub fn combine_pages(pages: &[CrawledPage]) -> String {
+    pages
+        .iter()
+        .map(|page| {
+            let html = normalize_html(&page.html)
+                .map(|clean| clean.as_str().to_string())
+                .unwrap_or_else(|_| page.html.clone());
+            format!("<page url=\"{}\">\n{}\n</page>", page.url, html)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links_resolves_and_filters() {
+        let base = Url::parse("https://docs.example.com/guide/").unwrap();
+        let html = r#"
+            <html><body>
+              <a href="intro">Intro</a>
+              <a href="/api#section">API</a>
+              <a href="https://other.com/page">Other</a>
+              <a href="mailto:someone@example.com">Mail</a>
+              <a href="/logo.png">Logo</a>
+              <a>No href</a>
+            </body></html>
+        "#;
+
+        let links: Vec<String> = extract_links(html, &base).iter().map(Url::to_string).collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://docs.example.com/guide/intro",
+                "https://docs.example.com/api",
+                "https://other.com/page",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_links_no_links() {
+        let base = Url::parse("https://example.com").unwrap();
+        assert!(extract_links("<html><body><p>nothing</p></body></html>", &base).is_empty());
+    }
+
+    #[test]
+    fn test_url_for_file() {
+        let base = Url::parse("https://example.com").unwrap();
+        let url = |p: &str| url_for_file(&base, Path::new(p)).map(|(u, d)| (u.to_string(), d));
+
+        assert_eq!(url("index.html"), Some(("https://example.com/".to_string(), 0)));
+        assert_eq!(
+            url("about.html"),
+            Some(("https://example.com/about.html".to_string(), 0))
+        );
+        assert_eq!(
+            url("docs/index.html"),
+            Some(("https://example.com/docs/".to_string(), 1))
+        );
+        assert_eq!(
+            url("docs/guide/intro.html"),
+            Some(("https://example.com/docs/guide/intro.html".to_string(), 2))
+        );
+
+        let base = Url::parse("https://example.com/project").unwrap();
+        assert_eq!(
+            url_for_file(&base, Path::new("api/index.html")).map(|(u, _)| u.to_string()),
+            Some("https://example.com/project/api/".to_string())
+        );
+    }
+
+    #[test]
+    fn test_pages_from_dir() {
+        let dir = std::env::temp_dir().join(format!("core-ltx-pages-from-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("index.html"), "<h1>Home</h1>").unwrap();
+        std::fs::write(dir.join("docs/index.html"), "<h1>Docs</h1>").unwrap();
+        std::fs::write(dir.join("docs/style.css"), "body {}").unwrap();
+
+        let base = Url::parse("https://example.com").unwrap();
+        let pages = pages_from_dir(&dir, &base, 10).unwrap();
+        let urls: Vec<&str> = pages.iter().map(|p| p.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.com/", "https://example.com/docs/"]);
+        assert_eq!(pages[1].html, "<h1>Docs</h1>");
+
+        assert_eq!(pages_from_dir(&dir, &base, 1).unwrap().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_combine_pages() {
+        let pages = vec![
+            CrawledPage {
+                url: Url::parse("https://example.com/").unwrap(),
+                depth: 0,
+                html: "<html><body><h1>Home</h1></body></html>".to_string(),
+            },
+            CrawledPage {
+                url: Url::parse("https://example.com/about").unwrap(),
+                depth: 1,
+                html: "<html><body><h1>About</h1></body></html>".to_string(),
+            },
+        ];
+
+        let combined = combine_pages(&pages);
+        assert!(combined.starts_with("<page url=\"https://example.com/\">"));
+        assert!(combined.contains("<h1>Home</h1>"));
+        assert!(combined.contains("<page url=\"https://example.com/about\">"));
+        assert!(combined.contains("<h1>About</h1>"));
+        assert_eq!(combined.matches("</page>").count(), 2);
+    }
+}
@@ -0,0 +1,368 @@
+use serde::Serialize;
+
+/// The structure of an llms.txt file: the parts that carry meaning, without any markdown formatting.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Outline {
+    /// The H1 header: the name of the website.
+    pub title: Option<String>,
+    /// The blockquote summarizing the website. Multi-line blockquotes are joined with a single space.
+    pub summary: Option<String>,
+    /// Free-form text between the summary and the first H2 section.
+    pub details: Option<String>,
+    /// The H2 file list sections, in document order.
+    pub sections: Vec<Section>,
+}
+
+/// An H2 section of an llms.txt file and its list of links.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section {
+    pub name: String,
+    pub links: Vec<Link>,
+}
+
+/// A `- [title](url): description` list item in an llms.txt section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Link {
+    pub title: String,
+    pub url: String,
+    pub description: Option<String>,
+}
+
+/// Extracts the outline of an llms.txt file.
+///
+/// This is deliberately lenient: it never fails, so that even invalid llms.txt files can be compared.
+/// Lines that don't fit the llms.txt structure are ignored.
+pub fn parse_outline(content: &str) -> Outline {
+    let mut outline = Outline::default();
+    let mut summary_lines: Vec<&str> = Vec::new();
+    let mut details_lines: Vec<&str> = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix("## ") {
+            outline.sections.push(Section {
+                name: name.trim().to_string(),
+                links: Vec::new(),
+            });
+        } else if let Some(title) = line.strip_prefix("# ") {
+            if outline.title.is_none() {
+                outline.title = Some(title.trim().to_string());
+            }
+        } else if let Some(section) = outline.sections.last_mut() {
+            if let Some(link) = parse_link(line) {
+                section.links.push(link);
+            }
+        } else if let Some(quote) = line.strip_prefix('>') {
+            if details_lines.is_empty() {
+                summary_lines.push(quote.trim());
+            } else {
+                details_lines.push(line);
+            }
+        } else if !line.is_empty() {
+            details_lines.push(line);
+        }
+    }
+
+    outline.summary = join_non_empty(&summary_lines, " ");
+    outline.details = join_non_empty(&details_lines, "\n");
+    outline
+}
+
+fn join_non_empty(lines: &[&str], separator: &str) -> Option<String> {
+    let joined = lines.join(separator).trim().to_string();
+    if joined.is_empty() { None } else { Some(joined) }
+}
+
+/// Parses a `- [title](url)` or `- [title](url): description` list item.
+pub(crate) fn parse_link(line: &str) -> Option<Link> {
+    let item = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))?.trim();
+    let rest = item.strip_prefix('[')?;
+    let (title, rest) = rest.split_once("](")?;
+    let (url, rest) = rest.split_once(')')?;
+    let description = rest
+        .trim()
+        .strip_prefix(':')
+        .map(str::trim)
+        .filter(|d| !d.is_empty())
+        .map(str::to_string);
+    Some(Link {
+        title: title.trim().to_string(),
+        url: url.trim().to_string(),
+        description,
+    })
+}
+
+/// A single semantic difference between two llms.txt files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    TitleChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    SummaryChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    DetailsChanged {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    SectionAdded {
+        section: String,
+    },
+    SectionRemoved {
+        section: String,
+    },
+    LinkAdded {
+        section: String,
+        link: Link,
+    },
+    LinkRemoved {
+        section: String,
+        link: Link,
+    },
+    /// A link with the same URL in the same section has a different title or description.
+    LinkChanged {
+        section: String,
+        old: Link,
+        new: Link,
+    },
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        fn show(value: &Option<String>) -> String {
+            match value {
+                Some(v) => format!("\"{}\"", v),
+                None => "(none)".to_string(),
+            }
+        }
+
+        match self {
+            Change::TitleChanged { old, new } => write!(f, "~ title: {} -> {}", show(old), show(new)),
+            Change::SummaryChanged { old, new } => write!(f, "~ summary: {} -> {}", show(old), show(new)),
+            Change::DetailsChanged { old, new } => write!(f, "~ details: {} -> {}", show(old), show(new)),
+            Change::SectionAdded { section } => write!(f, "+ section \"{}\"", section),
+            Change::SectionRemoved { section } => write!(f, "- section \"{}\"", section),
+            Change::LinkAdded { section, link } => write!(f, "+ [{}] {} ({})", section, link.url, link.title),
+            Change::LinkRemoved { section, link } => write!(f, "- [{}] {} ({})", section, link.url, link.title),
+            Change::LinkChanged { section, old, new } => {
+                write!(f, "~ [{}] {}:", section, new.url)?;
+                if old.title != new.title {
+                    write!(f, " title \"{}\" -> \"{}\"", old.title, new.title)?;
+                }
+                if old.description != new.description {
+                    write!(
+                        f,
+                        " description {} -> {}",
+                        show(&old.description),
+                        show(&new.description)
+                    )?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// All semantic differences between two llms.txt files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LlmsTxtDiff {
+    pub changes: Vec<Change>,
+}
+
+impl LlmsTxtDiff {
+    /// True if both llms.txt files have the same meaning.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// One line per change: `+` for additions, `-` for removals, `~` for modifications.
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "No differences.".to_string();
+        }
+        self.changes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// A markdown report, suitable for a pull request comment.
+    pub fn to_markdown(&self) -> String {
+        if self.is_empty() {
+            return "## llms.txt diff\n\nNo differences.".to_string();
+        }
+        let items = self
+            .changes
+            .iter()
+            .map(|c| format!("- `{}`", c.to_string().replace('`', "'")))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("## llms.txt diff\n\n{} change(s):\n\n{}", self.changes.len(), items)
+    }
+}
+
+/// Compares two llms.txt files by their outline (title, summary, details, sections, and links).
+///
+/// Formatting-only differences (whitespace, list markers, section order) are not reported.
+/// Links are matched by URL within a section.
+pub fn diff_llms_txt(old: &str, new: &str) -> LlmsTxtDiff {
+    diff_outlines(&parse_outline(old), &parse_outline(new))
+}
+
+/// Compares two llms.txt outlines. See `diff_llms_txt`.
+pub fn diff_outlines(old: &Outline, new: &Outline) -> LlmsTxtDiff {
+    let mut changes = Vec::new();
+
+    if old.title != new.title {
+        changes.push(Change::TitleChanged {
+            old: old.title.clone(),
+            new: new.title.clone(),
+        });
+    }
+    if old.summary != new.summary {
+        changes.push(Change::SummaryChanged {
+            old: old.summary.clone(),
+            new: new.summary.clone(),
+        });
+    }
+    if old.details != new.details {
+        changes.push(Change::DetailsChanged {
+            old: old.details.clone(),
+            new: new.details.clone(),
+        });
+    }
+
+    let find = |sections: &[Section], name: &str| sections.iter().find(|s| s.name == name).cloned();
+
+    for old_section in old.sections.iter() {
+        if find(&new.sections, &old_section.name).is_none() {
+            changes.push(Change::SectionRemoved {
+                section: old_section.name.clone(),
+            });
+        }
+    }
+
+    for new_section in new.sections.iter() {
+        let section = new_section.name.clone();
+        let Some(old_section) = find(&old.sections, &section) else {
+            changes.push(Change::SectionAdded {
+                section: section.clone(),
+            });
+            changes.extend(new_section.links.iter().map(|link| Change::LinkAdded {
+                section: section.clone(),
+                link: link.clone(),
+            }));
+            continue;
+        };
+
+        for old_link in old_section.links.iter() {
+            match new_section.links.iter().find(|l| l.url == old_link.url) {
+                None => changes.push(Change::LinkRemoved {
+                    section: section.clone(),
+                    link: old_link.clone(),
+                }),
+                Some(new_link) if new_link != old_link => changes.push(Change::LinkChanged {
+                    section: section.clone(),
+                    old: old_link.clone(),
+                    new: new_link.clone(),
+                }),
+                Some(_) => (),
+            }
+        }
+        for new_link in new_section.links.iter() {
+            if !old_section.links.iter().any(|l| l.url == new_link.url) {
+                changes.push(Change::LinkAdded {
+                    section: section.clone(),
+                    link: new_link.clone(),
+                });
+            }
+        }
+    }
+
+    LlmsTxtDiff { changes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "# Example\n\n> An example website.\n\nSome details.\n\n## Docs\n\n- [Intro](https://example.com/intro): Getting started\n- [API](https://example.com/api)\n\n## Examples\n\n- [Demo](https://example.com/demo)\n";
+
+    #[test]
+    fn test_parse_outline() {
+        let outline = parse_outline(OLD);
+        assert_eq!(outline.title.as_deref(), Some("Example"));
+        assert_eq!(outline.summary.as_deref(), Some("An example website."));
+        assert_eq!(outline.details.as_deref(), Some("Some details."));
+        assert_eq!(outline.sections.len(), 2);
+        assert_eq!(outline.sections[0].name, "Docs");
+        assert_eq!(
+            outline.sections[0].links[0],
+            Link {
+                title: "Intro".to_string(),
+                url: "https://example.com/intro".to_string(),
+                description: Some("Getting started".to_string()),
+            }
+        );
+        assert_eq!(outline.sections[0].links[1].description, None);
+        assert_eq!(outline.sections[1].links.len(), 1);
+    }
+
+    #[test]
+    fn test_diff_identical_ignores_formatting() {
+        let reformatted = OLD.replace("\n\n", "\n").replace("- [", "* [");
+        assert!(diff_llms_txt(OLD, OLD).is_empty());
+        assert!(diff_llms_txt(OLD, &reformatted).is_empty());
+    }
+
+    #[test]
+    fn test_diff_detects_changes() {
+        let new = "# Example Site\n\n> An example website.\n\nSome details.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n- [Guide](https://example.com/guide)\n\n## Optional\n\n- [Blog](https://example.com/blog)\n";
+
+        let diff = diff_llms_txt(OLD, new);
+        assert_eq!(
+            diff.changes,
+            vec![
+                Change::TitleChanged {
+                    old: Some("Example".to_string()),
+                    new: Some("Example Site".to_string()),
+                },
+                Change::SectionRemoved {
+                    section: "Examples".to_string()
+                },
+                Change::LinkChanged {
+                    section: "Docs".to_string(),
+                    old: parse_link("- [Intro](https://example.com/intro): Getting started").unwrap(),
+                    new: parse_link("- [Intro](https://example.com/intro): Start here").unwrap(),
+                },
+                Change::LinkRemoved {
+                    section: "Docs".to_string(),
+                    link: parse_link("- [API](https://example.com/api)").unwrap(),
+                },
+                Change::LinkAdded {
+                    section: "Docs".to_string(),
+                    link: parse_link("- [Guide](https://example.com/guide)").unwrap(),
+                },
+                Change::SectionAdded {
+                    section: "Optional".to_string()
+                },
+                Change::LinkAdded {
+                    section: "Optional".to_string(),
+                    link: parse_link("- [Blog](https://example.com/blog)").unwrap(),
+                },
+            ]
+        );
+        assert!(diff.to_text().contains("~ title: \"Example\" -> \"Example Site\""));
+        assert!(diff.to_markdown().starts_with("## llms.txt diff\n\n7 change(s):"));
+    }
+
+    #[test]
+    fn test_diff_json() {
+        let diff = diff_llms_txt("# A\n> s\n", "# B\n> s\n");
+        let json = serde_json::to_string(&diff).unwrap();
+        assert_eq!(json, r#"{"changes":[{"change":"title_changed","old":"A","new":"B"}]}"#);
+    }
+}
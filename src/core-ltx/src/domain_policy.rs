@@ -0,0 +1,259 @@
+use std::net::IpAddr;
+use std::sync::{LazyLock, RwLock};
+
+use url::{Host, Url};
+
+/// Environment variable that lets jobs target hosts on private networks (e.g. `localhost` in development).
+pub const ALLOW_PRIVATE_HOSTS_ENV_VAR: &str = "ALLOW_PRIVATE_HOSTS";
+
+/// Whether `ALLOW_PRIVATE_HOSTS` is `true`. Off by default: a public-facing server must not fetch internal hosts.
+pub fn private_hosts_allowed() -> bool {
+    std::env::var(ALLOW_PRIVATE_HOSTS_ENV_VAR).is_ok_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Which hosts can be indexed: none that match a `block` pattern, and, when there's at least one `allow` pattern,
+/// only the ones that match one of those.
+///
+/// Patterns are globs matched case-insensitively against the whole host, where `*` is any run of characters
+/// (dots included): `*.example.com` matches the subdomains of `example.com`, but not `example.com` itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DomainPolicy {
+    pub allow: Vec<String>,
+    pub block: Vec<String>,
+}
+
+impl DomainPolicy {
+    /// Why the host can't be indexed, if it can't.
+    pub fn check_host(&self, host: &str) -> Result<(), String> {
+        if let Some(pattern) = self.block.iter().find(|pattern| glob_matches(pattern, host)) {
+            return Err(format!("{} is blocked (by '{}')", host, pattern));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|pattern| glob_matches(pattern, host)) {
+            return Err(format!("{} isn't on the list of allowed domains", host));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the glob `pattern` matches all of `host`, ignoring case. `*` matches any run of characters.
+pub fn glob_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().trim_end_matches('.').to_ascii_lowercase();
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = host.strip_prefix(first) else {
+        return false;
+    };
+    let middle: Vec<&str> = parts.collect();
+    let Some((last, middle)) = middle.split_last() else {
+        // no `*`: the pattern is the host
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Whether the address is on a network the public can't reach: loopback, private, link-local, shared (CGNAT),
+/// unspecified, or IPv6 unique-local. IPv4-mapped IPv6 addresses are checked as IPv4.
+pub fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_private_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                ip.is_loopback() || ip.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80
+            }
+        },
+    }
+}
+
+/// Guards against server-side request forgery: why the URL can't be fetched, if its host is an address on a private
+/// network or a name that resolves to one.
+///
+/// Names that don't resolve pass: downloading them fails anyway.
+pub async fn check_public_host(url: &Url) -> Result<(), String> {
+    let host = match url.host() {
+        Some(host) => host,
+        None => return Err(format!("{} has no host", url)),
+    };
+    let ip = match &host {
+        Host::Ipv4(ip) => Some(IpAddr::V4(*ip)),
+        Host::Ipv6(ip) => Some(IpAddr::V6(*ip)),
+        Host::Domain(domain) => {
+            let port = url.port_or_known_default().unwrap_or(443);
+            match tokio::net::lookup_host((*domain, port)).await {
+                Ok(mut addresses) => addresses.map(|address| address.ip()).find(|ip| is_private_ip(*ip)),
+                Err(e) => {
+                    tracing::debug!("Couldn't resolve '{}': {}", domain, e);
+                    None
+                }
+            }
+        }
+    };
+    match ip {
+        Some(ip) if is_private_ip(ip) => Err(format!("{} is on a private network ({})", host, ip)),
+        _ => Ok(()),
+    }
+}
+
+/// What downloads are checked against: the domain policy, and unless `private_hosts_allowed`, that the host isn't on
+/// a private network.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadGuard {
+    pub policy: DomainPolicy,
+    /// `ALLOW_PRIVATE_HOSTS`
+    pub private_hosts_allowed: bool,
+}
+
+impl DownloadGuard {
+    /// Why the URL can't be fetched, if it can't.
+    pub async fn check(&self, url: &Url) -> Result<(), String> {
+        if let Some(host) = url.host_str() {
+            self.policy
+                .check_host(host.trim_start_matches('[').trim_end_matches(']'))?;
+        }
+        if !self.private_hosts_allowed {
+            check_public_host(url).await?;
+        }
+        Ok(())
+    }
+}
+
+/// The guard of the downloads, once the process set one.
+static DOWNLOAD_GUARD: LazyLock<RwLock<Option<DownloadGuard>>> = LazyLock::new(Default::default);
+
+/// Has every download check its URL, and the URL of each redirect it follows, with the guard. Until one is set (e.g.
+/// in the CLI, or in tests), downloads aren't checked.
+pub fn set_download_guard(guard: DownloadGuard) {
+    *DOWNLOAD_GUARD.write().unwrap_or_else(|e| e.into_inner()) = Some(guard);
+}
+
+/// The guard of the downloads, if one was set.
+pub fn download_guard() -> Option<DownloadGuard> {
+    DOWNLOAD_GUARD.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("example.com", "Example.com"));
+        assert!(glob_matches("example.com.", "example.com"));
+        assert!(!glob_matches("example.com", "docs.example.com"));
+        assert!(glob_matches("*.example.com", "docs.example.com"));
+        assert!(glob_matches("*.example.com", "a.b.example.com"));
+        assert!(!glob_matches("*.example.com", "example.com"));
+        assert!(!glob_matches("*.example.com", "example.com.evil.org"));
+        assert!(glob_matches("docs.*", "docs.example.com"));
+        assert!(glob_matches("*internal*", "app.internal.corp"));
+        assert!(glob_matches("*", "anything.org"));
+    }
+
+    #[test]
+    fn test_check_host() {
+        let policy = DomainPolicy {
+            allow: vec![],
+            block: vec!["*.internal".to_string(), "metadata.google.internal".to_string()],
+        };
+        assert!(policy.check_host("example.com").is_ok());
+        assert!(policy.check_host("db.internal").is_err());
+
+        let policy = DomainPolicy {
+            allow: vec!["example.com".to_string(), "*.example.com".to_string()],
+            block: vec!["admin.example.com".to_string()],
+        };
+        assert!(policy.check_host("example.com").is_ok());
+        assert!(policy.check_host("docs.example.com").is_ok());
+        assert!(policy.check_host("admin.example.com").is_err());
+        assert!(policy.check_host("example.org").is_err());
+    }
+
+    #[test]
+    fn test_is_private_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:10.0.0.1",
+        ] {
+            assert!(is_private_ip(ip.parse().unwrap()), "{} should be private", ip);
+        }
+        for ip in ["93.184.216.34", "8.8.8.8", "2606:4700::1111", "100.128.0.1"] {
+            assert!(!is_private_ip(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_public_host() {
+        assert!(
+            check_public_host(&Url::parse("http://127.0.0.1:8080/").unwrap())
+                .await
+                .is_err()
+        );
+        assert!(check_public_host(&Url::parse("http://[::1]/").unwrap()).await.is_err());
+        assert!(
+            check_public_host(&Url::parse("http://localhost/").unwrap())
+                .await
+                .is_err()
+        );
+        assert!(
+            check_public_host(&Url::parse("https://93.184.216.34/").unwrap())
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_download_guard_check() {
+        let guard = DownloadGuard {
+            policy: DomainPolicy {
+                allow: vec![],
+                block: vec!["*.internal".to_string()],
+            },
+            private_hosts_allowed: false,
+        };
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "https://db.internal/",
+        ] {
+            assert!(guard.check(&Url::parse(url).unwrap()).await.is_err(), "{}", url);
+        }
+        assert!(
+            guard
+                .check(&Url::parse("https://93.184.216.34/").unwrap())
+                .await
+                .is_ok()
+        );
+
+        let guard = DownloadGuard {
+            private_hosts_allowed: true,
+            ..guard
+        };
+        assert!(guard.check(&Url::parse("http://10.0.0.1/").unwrap()).await.is_ok());
+        assert!(guard.check(&Url::parse("https://db.internal/").unwrap()).await.is_err());
+    }
+}
@@ -4,6 +4,9 @@ pub enum Error {
     /// Cannot download website because user supplied an invalid URL.
     InvalidUrl(url::ParseError),
 
+    /// The URL isn't a website's: only `http` and `https` URLs are indexed (see `web_html::canonical_url`).
+    NotWebUrl(String),
+
     /// Website download failed.
     DownloadError(reqwest::Error),
 
@@ -19,18 +22,37 @@ pub enum Error {
     /// Redirect Location header contains invalid characters.
     RedirectInvalidLocation { url: url::Url },
 
+    /// The download guard (see `domain_policy::set_download_guard`) doesn't let the URL, or a URL it redirected to, be
+    /// fetched, e.g. it's on a private network.
+    HostNotAllowed { url: url::Url, reason: String },
+
     /// HTTP request returned a non-success status code.
     HttpError { url: url::Url, status_code: u16 },
 
+    /// The response is not a web page (e.g. a PDF or an image).
+    NotHtml { url: url::Url, content_type: String },
+
+    /// A request to the api-ltx server failed, or it responded with a non-success status code.
+    ApiError(client_ltx::ClientError),
+
     /// HTML is invalid, even after attempting to fix using HTML5 rules.
     InvalidUtf8(std::string::FromUtf8Error),
 
+    /// The page is too big or too deeply nested to parse (see `parse_limits::ParseLimits`).
+    HtmlTooComplex(String),
+
     /// File is not valid markdown.
     InvalidMarkdown(nom::Err<nom::error::Error<String>>),
 
+    /// The markdown is too big or too deeply nested to parse (see `parse_limits::ParseLimits`).
+    MarkdownTooComplex(String),
+
     /// Markdown file does not adhere to the llms.txt format.
     InvalidLlmsTxtFormat(String),
 
+    /// The llms.txt has no H2 section with this name.
+    UnknownSection(String),
+
     /// Internal error: prompt substitution failed.
     PromptCreationFailure(subst::Error),
 
@@ -39,12 +61,19 @@ pub enum Error {
 
     /// Error during IO operations
     IoError(std::io::Error),
+
+    /// Sending a notification (email or Slack message) failed.
+    NotificationError(String),
+
+    /// CPU-heavy work (parsing, compressing) panicked or was cancelled on its blocking thread.
+    BlockingTaskError(tokio::task::JoinError),
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Error::InvalidUrl(url) => write!(f, "Not a valid URL: {}", url),
+            Error::NotWebUrl(url) => write!(f, "Not an http(s) URL: {}", url),
             Error::DownloadError(err) => write!(f, "Download error: {}", err),
             Error::TooManyRedirects {
                 original_url,
@@ -62,15 +91,25 @@ impl std::fmt::Display for Error {
             Error::RedirectInvalidLocation { url } => {
                 write!(f, "Redirect Location header contains invalid characters for: {}", url)
             }
+            Error::HostNotAllowed { url, reason } => write!(f, "Not allowed to download {}: {}", url, reason),
             Error::HttpError { url, status_code } => {
                 write!(f, "HTTP {} error for: {}", status_code, url)
             }
+            Error::NotHtml { url, content_type } => {
+                write!(f, "Not an HTML page ({}): {}", content_type, url)
+            }
+            Error::ApiError(err) => write!(f, "{}", err),
             Error::InvalidUtf8(err) => write!(f, "Tried to convert non-UTF8 bytes into a string: {}", err),
+            Error::HtmlTooComplex(why) => write!(f, "HTML too complex to parse: {}", why),
             Error::InvalidMarkdown(err) => write!(f, "Not valid Markdown: {}", err),
+            Error::MarkdownTooComplex(why) => write!(f, "Markdown too complex to parse: {}", why),
             Error::InvalidLlmsTxtFormat(msg) => write!(f, "Not valid llms.txt Format: {}", msg),
+            Error::UnknownSection(name) => write!(f, "The llms.txt has no '{}' section", name),
             Error::PromptCreationFailure(err) => write!(f, "Failed to create prompt: {}", err),
             Error::ChatGptError(err) => write!(f, "Error calling ChatGPT: {}", err),
             Error::IoError(err) => write!(f, "Error during IO operations: {}", err),
+            Error::NotificationError(msg) => write!(f, "Failed to send notification: {}", msg),
+            Error::BlockingTaskError(err) => write!(f, "Blocking task failed: {}", err),
         }
     }
 }
@@ -84,6 +123,13 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+/// Requests to a remote api-ltx server fail with client errors.
+impl From<client_ltx::ClientError> for Error {
+    fn from(err: client_ltx::ClientError) -> Self {
+        Error::ApiError(err)
+    }
+}
+
 /// URL parsing errors occur during the URL validation process.
 impl From<url::ParseError> for Error {
     fn from(err: url::ParseError) -> Self {
@@ -115,3 +161,9 @@ impl From<std::io::Error> for Error {
         Error::IoError(err)
     }
 }
+
+impl From<tokio::task::JoinError> for Error {
+    fn from(err: tokio::task::JoinError) -> Self {
+        Error::BlockingTaskError(err)
+    }
+}
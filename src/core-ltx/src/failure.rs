@@ -0,0 +1,115 @@
+use std::error::Error as _;
+
+use crate::Error;
+use crate::web_html::{StatusPolicy, status_policy};
+
+/// Why a job failed, in terms a user can act on. It's part of the API's responses, so it's defined with the
+/// other wire-format types.
+pub use dto_ltx::FailureKind;
+
+/// False when retrying later won't help, e.g. the page doesn't exist (404) or the publisher opted out.
+/// HTTP statuses are classified by `web_html::status_policy`.
+pub fn is_retryable(kind: &FailureKind) -> bool {
+    match kind {
+        FailureKind::HttpStatus(status) => status_policy(*status) == StatusPolicy::Retryable,
+        FailureKind::InvalidUrl
+        | FailureKind::BadRedirect
+        | FailureKind::NotHtml
+        | FailureKind::PageTooComplex
+        | FailureKind::RobotsDisallowed => false,
+        FailureKind::DnsFailure
+        | FailureKind::ConnectionFailed
+        | FailureKind::Timeout
+        | FailureKind::LlmRateLimited
+        | FailureKind::LlmError
+        | FailureKind::InvalidOutput
+        | FailureKind::Internal => true,
+    }
+}
+
+impl From<&Error> for FailureKind {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::InvalidUrl(_) | Error::NotWebUrl(_) | Error::HostNotAllowed { .. } => Self::InvalidUrl,
+            Error::DownloadError(err) => from_reqwest(err),
+            Error::TooManyRedirects { .. }
+            | Error::RedirectMissingLocation { .. }
+            | Error::RedirectInvalidLocation { .. } => Self::BadRedirect,
+            Error::HttpError { status_code, .. } => Self::HttpStatus(*status_code),
+            Error::NotHtml { .. } | Error::InvalidUtf8(_) => Self::NotHtml,
+            Error::HtmlTooComplex(_) => Self::PageTooComplex,
+            Error::InvalidMarkdown(_) | Error::MarkdownTooComplex(_) | Error::InvalidLlmsTxtFormat(_) => {
+                Self::InvalidOutput
+            }
+            Error::ChatGptError(err) => from_openai(err),
+            Error::ApiError(_)
+            | Error::UnknownSection(_)
+            | Error::PromptCreationFailure(_)
+            | Error::IoError(_)
+            | Error::NotificationError(_)
+            | Error::BlockingTaskError(_) => Self::Internal,
+        }
+    }
+}
+
+fn from_reqwest(err: &reqwest::Error) -> FailureKind {
+    if err.is_timeout() {
+        FailureKind::Timeout
+    } else if let Some(status) = err.status() {
+        FailureKind::HttpStatus(status.as_u16())
+    } else if err.is_connect() {
+        // the resolver's error is somewhere in the chain: its message is the only way to recognize it
+        let mut source = err.source();
+        while let Some(cause) = source {
+            let message = cause.to_string().to_lowercase();
+            if message.contains("dns") || message.contains("lookup") || message.contains("resolve") {
+                return FailureKind::DnsFailure;
+            }
+            source = cause.source();
+        }
+        FailureKind::ConnectionFailed
+    } else if err.is_decode() || err.is_body() {
+        FailureKind::NotHtml
+    } else {
+        FailureKind::ConnectionFailed
+    }
+}
+
+fn from_openai(err: &async_openai::error::OpenAIError) -> FailureKind {
+    match err {
+        async_openai::error::OpenAIError::ApiError(api_error) => {
+            let is_rate_limit = [api_error.code.as_deref(), api_error.r#type.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|code| code.contains("rate_limit") || code == "insufficient_quota");
+            if is_rate_limit {
+                FailureKind::LlmRateLimited
+            } else {
+                FailureKind::LlmError
+            }
+        }
+        _ => FailureKind::LlmError,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_core_error() {
+        let url = url::Url::parse("https://example.com").unwrap();
+        let kind = FailureKind::from(&Error::HttpError { url, status_code: 406 });
+        assert_eq!(kind, FailureKind::HttpStatus(406));
+        assert_eq!(kind.description(), "The site returned HTTP 406.");
+        assert_eq!(
+            FailureKind::from(&Error::InvalidLlmsTxtFormat("no H1".to_string())),
+            FailureKind::InvalidOutput
+        );
+        assert!(!is_retryable(&FailureKind::HttpStatus(406)));
+        assert!(is_retryable(&FailureKind::HttpStatus(503)));
+        assert!(is_retryable(&FailureKind::Timeout));
+        assert!(!is_retryable(&FailureKind::RobotsDisallowed));
+        assert!(!is_retryable(&FailureKind::PageTooComplex));
+    }
+}
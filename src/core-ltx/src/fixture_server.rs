@@ -0,0 +1,156 @@
+//! A local website for tests of the download layer
+//!
+//! Wraps a `wiremock` server with the responses real websites send that the download policy has to handle: redirects,
+//! permanent and retryable error statuses, slow responses, gzip and charset variations, and robots directives. Tests
+//! of `core-ltx`, `worker-ltx` and `cron-ltx` serve their pages from it instead of hitting real sites.
+
+use std::io::Write;
+use std::time::Duration;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use url::Url;
+use wiremock::matchers::{self, header_regex, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Mounted responses are matched by priority first (lower is first), and by mounting order then. Responses that only
+/// apply for a while, or only to some requests, have this priority, so they're matched before the usual page.
+const OVERRIDE_PRIORITY: u8 = 1;
+
+/// A local website. Paths without a response are `404 Not Found`.
+pub struct FixtureServer {
+    server: MockServer,
+}
+
+impl FixtureServer {
+    /// Starts the website on a free local port. It stops when dropped.
+    pub async fn start() -> Self {
+        Self {
+            server: MockServer::start().await,
+        }
+    }
+
+    /// The website's home page, e.g. `http://127.0.0.1:1234/`.
+    pub fn base_url(&self) -> Url {
+        self.url("/")
+    }
+
+    /// The URL of a path of the website, e.g. `/docs`.
+    pub fn url(&self, path: &str) -> Url {
+        Url::parse(&self.server.uri())
+            .and_then(|base| base.join(path))
+            .expect("The fixture server's URLs are valid")
+    }
+
+    /// The underlying server, to mount responses the helpers below don't cover.
+    pub fn mock_server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// Forgets every response and every received request.
+    pub async fn reset(&self) {
+        self.server.reset().await;
+    }
+
+    /// Serves the HTML page at `path`.
+    pub async fn page(&self, path: &str, html: &str) {
+        self.respond(path, ResponseTemplate::new(200).set_body_raw(html, "text/html"))
+            .await;
+    }
+
+    /// Serves `body` as is at `path`, with the `content_type`, e.g. `text/html; charset=ISO-8859-1` or `text/plain`.
+    pub async fn body(&self, path: &str, body: impl Into<Vec<u8>>, content_type: &str) {
+        self.respond(path, ResponseTemplate::new(200).set_body_raw(body, content_type))
+            .await;
+    }
+
+    /// Serves the HTML page at `path`, with extra response headers, e.g. `X-Robots-Tag: noindex`.
+    pub async fn page_with_headers(&self, path: &str, html: &str, headers: &[(&str, &str)]) {
+        let response = headers.iter().fold(
+            ResponseTemplate::new(200).set_body_raw(html, "text/html"),
+            |response, (name, value)| response.insert_header(*name, *value),
+        );
+        self.respond(path, response).await;
+    }
+
+    /// Serves the HTML page at `path` only after `delay`, like an overloaded server.
+    pub async fn slow_page(&self, path: &str, html: &str, delay: Duration) {
+        self.respond(
+            path,
+            ResponseTemplate::new(200)
+                .set_body_raw(html, "text/html")
+                .set_delay(delay),
+        )
+        .await;
+    }
+
+    /// Serves the HTML page at `path`, gzip-compressed (`Content-Encoding: gzip`) to the clients that accept it, and
+    /// as is to the others, like most web servers.
+    pub async fn gzip_page(&self, path: &str, html: &str) {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(html.as_bytes()).expect("Failed to gzip the page");
+        let compressed = encoder.finish().expect("Failed to gzip the page");
+        Mock::given(method("GET"))
+            .and(matchers::path(path))
+            .and(header_regex("accept-encoding", "gzip"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw(compressed, "text/html")
+                    .insert_header("content-encoding", "gzip"),
+            )
+            .with_priority(OVERRIDE_PRIORITY)
+            .mount(&self.server)
+            .await;
+        self.page(path, html).await;
+    }
+
+    /// Redirects `path` to `location` (absolute, or relative to `path`) with the redirect `status`, e.g. 301.
+    pub async fn redirect(&self, path: &str, status: u16, location: &str) {
+        self.respond(path, ResponseTemplate::new(status).insert_header("location", location))
+            .await;
+    }
+
+    /// Answers `path` with an empty response of the `status`, e.g. `406 Not Acceptable` or `500 Internal Server
+    /// Error`.
+    pub async fn status(&self, path: &str, status: u16) {
+        self.respond(path, ResponseTemplate::new(status)).await;
+    }
+
+    /// Answers `path` with the `status` for the first `times` requests, then serves the HTML page, like a server
+    /// that's rate limiting (429) or briefly unavailable (503). The error responses have a `Retry-After: 0` header,
+    /// so the download retries at once.
+    pub async fn status_then_page(&self, path: &str, status: u16, times: u64, html: &str) {
+        Mock::given(method("GET"))
+            .and(matchers::path(path))
+            .respond_with(ResponseTemplate::new(status).insert_header("retry-after", "0"))
+            .up_to_n_times(times)
+            .with_priority(OVERRIDE_PRIORITY)
+            .mount(&self.server)
+            .await;
+        self.page(path, html).await;
+    }
+
+    /// Serves the website's `/robots.txt`.
+    pub async fn robots_txt(&self, robots_txt: &str) {
+        self.body("/robots.txt", robots_txt, "text/plain").await;
+    }
+
+    /// Number of requests the website received for `path`.
+    pub async fn requests_to(&self, path: &str) -> usize {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .iter()
+            .filter(|request| request.url.path() == path)
+            .count()
+    }
+
+    async fn respond(&self, path: &str, response: ResponseTemplate) {
+        Mock::given(method("GET"))
+            .and(matchers::path(path))
+            .respond_with(response)
+            .mount(&self.server)
+            .await;
+    }
+}
@@ -0,0 +1,253 @@
+//! Golden-file regression suite for llms.txt generation: real-world pages, the LLM responses recorded for them, and
+//! what generating an llms.txt from them must give. The corpus is in `core-ltx/golden`, one directory per case (see
+//! its README), and is run by `test_golden_corpus`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::llms::mock::MockLlmProvider;
+use crate::llms::{Detail, generate_llms_txt};
+use crate::{Error, FailureKind, LlmsTxt, normalize_html};
+
+/// Environment variable that, set to `1`, re-records every case's snapshots instead of comparing against them.
+pub const GOLDEN_UPDATE_ENV_VAR: &str = "GOLDEN_UPDATE";
+
+/// The corpus committed with the crate.
+pub fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("golden")
+}
+
+/// What a case expects generating its llms.txt to do: its `case.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaseSpec {
+    /// Where the page comes from, and what the case is there to catch.
+    pub description: String,
+    #[serde(default)]
+    pub detail: Detail,
+    pub expect: Expected,
+    /// Prompts sent to the LLM: 1 when its first response is accepted, 2 when it's asked to fix it.
+    pub llm_calls: usize,
+}
+
+/// Whether an llms.txt is generated, or why not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expected {
+    Valid,
+    Failure(FailureKind),
+}
+
+/// One directory of the corpus.
+#[derive(Debug, Clone)]
+pub struct GoldenCase {
+    /// The directory's name.
+    pub name: String,
+    pub dir: PathBuf,
+    pub spec: CaseSpec,
+    /// `page.html`: the page, as downloaded.
+    pub html: String,
+    /// `responses/1.md`, `responses/2.md`, ...: the LLM's responses to the case's prompts, in order.
+    pub responses: Vec<String>,
+}
+
+impl GoldenCase {
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| format!("not a case directory: {}", dir.display()))?;
+        let spec = serde_json::from_str(&read(&dir.join("case.json"))?)
+            .map_err(|e| format!("{}: invalid case.json: {}", name, e))?;
+        let html = read(&dir.join("page.html"))?;
+
+        let mut responses = Vec::new();
+        loop {
+            let path = dir.join("responses").join(format!("{}.md", responses.len() + 1));
+            if !path.exists() {
+                break;
+            }
+            responses.push(read(&path)?);
+        }
+
+        Ok(GoldenCase {
+            name,
+            dir: dir.to_path_buf(),
+            spec,
+            html,
+            responses,
+        })
+    }
+
+    fn snapshot_dir(&self) -> PathBuf {
+        self.dir.join("snapshots")
+    }
+}
+
+/// Every case in the directory, by name.
+pub fn load_corpus(dir: &Path) -> Result<Vec<GoldenCase>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("{}: {}", dir.display(), e))?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    dirs.iter().map(|dir| GoldenCase::load(dir)).collect()
+}
+
+/// What generating a case's llms.txt did.
+#[derive(Debug)]
+pub struct GoldenRun {
+    /// Every prompt sent to the LLM, in order.
+    pub prompts: Vec<String>,
+    pub result: Result<LlmsTxt, Error>,
+}
+
+/// Generates the case's llms.txt as the worker would, with the recorded responses standing in for the LLM.
+pub async fn run_case(case: &GoldenCase) -> GoldenRun {
+    let provider = MockLlmProvider::with_sequence(case.responses.clone());
+    let result = match normalize_html(&case.html) {
+        Ok(html) => generate_llms_txt(&provider, html.as_str(), case.spec.detail).await,
+        Err(e) => Err(e),
+    };
+    GoldenRun {
+        prompts: provider.received_prompts(),
+        result,
+    }
+}
+
+/// Compares the run with what the case expects, and with its snapshots: `snapshots/prompt_<n>.txt` for each prompt,
+/// and `snapshots/llms.txt` (the generated llms.txt) or `snapshots/error.txt`. Missing snapshots are recorded, and
+/// with `update` all of them are. Returns what didn't match.
+pub fn check_case(case: &GoldenCase, run: &GoldenRun, update: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    match (case.spec.expect, &run.result) {
+        (Expected::Valid, Ok(_)) => {}
+        (Expected::Failure(expected), Err(e)) if FailureKind::from(e) == expected => {}
+        (expected, Ok(_)) => problems.push(format!("expected {:?}, but an llms.txt was generated", expected)),
+        (expected, Err(e)) => problems.push(format!(
+            "expected {:?}, but failed with {:?}: {}",
+            expected,
+            FailureKind::from(e),
+            e
+        )),
+    }
+    if run.prompts.len() != case.spec.llm_calls {
+        problems.push(format!(
+            "expected {} prompt(s) to the LLM, but sent {}",
+            case.spec.llm_calls,
+            run.prompts.len()
+        ));
+    }
+    if case.responses.len() > run.prompts.len() {
+        problems.push(format!(
+            "{} recorded response(s), but only {} used",
+            case.responses.len(),
+            run.prompts.len()
+        ));
+    }
+
+    let snapshot_dir = case.snapshot_dir();
+    if update
+        && snapshot_dir.exists()
+        && let Err(e) = std::fs::remove_dir_all(&snapshot_dir)
+    {
+        problems.push(format!("{}: {}", snapshot_dir.display(), e));
+        return problems;
+    }
+    let mut snapshots: Vec<(String, String)> = run
+        .prompts
+        .iter()
+        .enumerate()
+        .map(|(i, prompt)| (format!("prompt_{}.txt", i + 1), prompt.clone()))
+        .collect();
+    snapshots.push(match &run.result {
+        Ok(llms_txt) => ("llms.txt".to_string(), llms_txt.md_content()),
+        Err(e) => ("error.txt".to_string(), e.to_string()),
+    });
+    for (file, actual) in &snapshots {
+        if let Some(problem) = check_snapshot(&snapshot_dir.join(file), actual) {
+            problems.push(problem);
+        }
+    }
+    // e.g. a prompt_2.txt from when the LLM was asked to fix its response, which it isn't anymore
+    if let Ok(entries) = std::fs::read_dir(&snapshot_dir) {
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().into_owned();
+            if !snapshots.iter().any(|(name, _)| *name == file) {
+                problems.push(format!("snapshots/{} is no longer produced", file));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Records the snapshot if it's missing. Otherwise, describes the first line where it differs, if it does.
+fn check_snapshot(path: &Path, actual: &str) -> Option<String> {
+    let Ok(expected) = std::fs::read_to_string(path) else {
+        let recorded = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, actual));
+        return match recorded {
+            Ok(()) => {
+                eprintln!("recorded {}", path.display());
+                None
+            }
+            Err(e) => Some(format!("failed to record {}: {}", path.display(), e)),
+        };
+    };
+    if expected == actual {
+        return None;
+    }
+
+    let file = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let (expected_lines, actual_lines): (Vec<&str>, Vec<&str>) = (expected.lines().collect(), actual.lines().collect());
+    let line = (0..expected_lines.len().max(actual_lines.len()))
+        .find(|&i| expected_lines.get(i) != actual_lines.get(i))
+        .unwrap_or(expected_lines.len());
+    Some(format!(
+        "snapshots/{} differs from line {}\n  expected: {}\n  actual:   {}",
+        file,
+        line + 1,
+        expected_lines.get(line).copied().unwrap_or("<end of file>"),
+        actual_lines.get(line).copied().unwrap_or("<end of file>"),
+    ))
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_golden_corpus() {
+        let update = std::env::var(GOLDEN_UPDATE_ENV_VAR).is_ok_and(|value| value == "1");
+        let cases = load_corpus(&corpus_dir()).unwrap();
+        assert!(!cases.is_empty(), "no cases in {}", corpus_dir().display());
+
+        let mut failures = Vec::new();
+        for case in &cases {
+            let run = run_case(case).await;
+            for problem in check_case(case, &run, update) {
+                failures.push(format!("{}: {}", case.name, problem));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{} problem(s) in the golden corpus:\n{}\n\nIf the prompts or llms.txt files changed on purpose, \
+             re-record the snapshots with {}=1 and review the diff.",
+            failures.len(),
+            failures.join("\n"),
+            GOLDEN_UPDATE_ENV_VAR
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use reqwest::Client;
+use reqwest::redirect::Policy;
+
+use crate::parse_limits::limit_from_env;
+
+/// Environment variable with how many idle connections to a host are kept open, to be reused.
+pub const HTTP_POOL_MAX_IDLE_PER_HOST_ENV_VAR: &str = "HTTP_POOL_MAX_IDLE_PER_HOST";
+/// Environment variable with how long (in seconds) an idle connection is kept open.
+pub const HTTP_POOL_IDLE_TIMEOUT_S_ENV_VAR: &str = "HTTP_POOL_IDLE_TIMEOUT_S";
+/// Environment variable with how long (in seconds) connecting to a host can take.
+pub const HTTP_CONNECT_TIMEOUT_S_ENV_VAR: &str = "HTTP_CONNECT_TIMEOUT_S";
+/// Environment variable with how long (in seconds) a request can take, from sending it to reading the whole body.
+pub const HTTP_TIMEOUT_S_ENV_VAR: &str = "HTTP_TIMEOUT_S";
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Builds the client every download shares, with the settings of the environment (see `HttpClientSettings::from_env`),
+/// and returns them. Services call it at startup, so that invalid settings stop them there rather than failing their
+/// downloads. Only the first call builds the client.
+pub fn init_http_client() -> Result<HttpClientSettings, String> {
+    let settings = HttpClientSettings::from_env()?;
+    let client = settings
+        .client()
+        .map_err(|e| format!("Failed to build the HTTP client: {}", e))?;
+    let _ = HTTP_CLIENT.set(client);
+    Ok(settings)
+}
+
+/// The client every download uses. Sharing it lets downloads from the same host reuse its connections (kept alive,
+/// or multiplexed over HTTP/2 when the server negotiates it) and TLS sessions, instead of connecting anew each time.
+///
+/// It doesn't follow redirects: `download_page` follows them itself, checking each hop with the download guard.
+///
+/// Without `init_http_client` (e.g. in tests), it's built on first use: with the default settings if the
+/// environment's are invalid, which is logged.
+pub fn http_client() -> &'static Client {
+    HTTP_CLIENT.get_or_init(|| {
+        let settings = HttpClientSettings::from_env().unwrap_or_else(|e| {
+            tracing::error!("{}: using the default HTTP client settings", e);
+            HttpClientSettings::default()
+        });
+        settings
+            .client()
+            .unwrap_or_else(|e| panic!("Failed to build the HTTP client: {}", e))
+    })
+}
+
+/// How the shared HTTP client pools its connections, and how long it waits for servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpClientSettings {
+    /// Idle connections to a host past this many are closed.
+    pub pool_max_idle_per_host: usize,
+    /// Idle connections are closed after this long.
+    pub pool_idle_timeout: Duration,
+    /// Connecting to a host (including the TLS handshake) fails after this long.
+    pub connect_timeout: Duration,
+    /// A request fails after this long, however much of the response was read.
+    pub timeout: Duration,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        HttpClientSettings {
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Duration::from_secs(90),
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl HttpClientSettings {
+    /// Reads the settings from `HTTP_POOL_MAX_IDLE_PER_HOST`, `HTTP_POOL_IDLE_TIMEOUT_S`, `HTTP_CONNECT_TIMEOUT_S`
+    /// and `HTTP_TIMEOUT_S`, with defaults for unset ones. Fails on values that aren't positive numbers.
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = HttpClientSettings::default();
+        let seconds = |name: &str, default: Duration| -> Result<Duration, String> {
+            limit_from_env(name, default.as_secs() as usize).map(|s| Duration::from_secs(s as u64))
+        };
+        Ok(HttpClientSettings {
+            pool_max_idle_per_host: limit_from_env(
+                HTTP_POOL_MAX_IDLE_PER_HOST_ENV_VAR,
+                defaults.pool_max_idle_per_host,
+            )?,
+            pool_idle_timeout: seconds(HTTP_POOL_IDLE_TIMEOUT_S_ENV_VAR, defaults.pool_idle_timeout)?,
+            connect_timeout: seconds(HTTP_CONNECT_TIMEOUT_S_ENV_VAR, defaults.connect_timeout)?,
+            timeout: seconds(HTTP_TIMEOUT_S_ENV_VAR, defaults.timeout)?,
+        })
+    }
+
+    /// A client with these settings, that doesn't follow redirects.
+    pub fn client(&self) -> Result<Client, reqwest::Error> {
+        Client::builder()
+            .redirect(Policy::none())
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.timeout)
+            .tcp_keepalive(Duration::from_secs(60))
+            .http2_adaptive_window(true)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_env() {
+        assert_eq!(HttpClientSettings::from_env(), Ok(HttpClientSettings::default()));
+        assert!(HttpClientSettings::default().client().is_ok());
+    }
+}
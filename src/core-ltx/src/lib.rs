@@ -1,22 +1,66 @@
+pub mod batch;
 pub mod common;
+pub mod crawl;
+pub mod credentials;
+pub mod diff;
+pub mod domain_policy;
 pub mod errors;
+pub mod failure;
+// A local website for tests of downloads: redirects, error statuses, slow responses, encodings, ...
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod fixture_server;
 pub mod functional;
+// The golden-file regression suite of generation, run against the corpus in `golden/`
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod golden;
+pub mod http_client;
+pub mod lint;
 pub mod llms;
 pub mod md_llm_txt;
+pub mod metadata;
+pub mod notify;
+pub mod origin;
+pub mod parse_limits;
+pub mod remote;
+pub mod repair;
+pub mod robots;
+// The parse/render property of the property tests and fuzz targets
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod round_trip;
+pub mod section;
 pub mod web_html;
 
-pub use md_llm_txt::{LlmsTxt, Markdown, is_valid_markdown, validate_is_llm_txt};
-pub use web_html::{clean_html, compute_html_checksum, download, is_valid_url, normalize_html, parse_html};
+pub use failure::FailureKind;
+pub use http_client::{HttpClientSettings, http_client, init_http_client};
+pub use md_llm_txt::{LlmsTxt, Markdown, is_valid_markdown, is_valid_markdown_with_limits, validate_is_llm_txt};
+pub use metadata::{SiteMetadata, extract_site_metadata};
+pub use origin::{fetch_origin_llms_txt, origin_llms_txt_url};
+pub use parse_limits::{PARSE_LIMITS, ParseLimits};
+pub use robots::{Disallowed, find_disallowing_directive};
+pub use web_html::{
+    Page, RawResponse, canonical_url, clean_html, compute_html_checksum, download, download_page,
+    download_page_guarded, download_page_with_limits, is_valid_url, normalize_html, normalize_html_with_limits,
+    parse_html, parse_html_with_limits,
+};
 
-pub use common::auth_config::{AuthConfig, get_auth_config, is_auth_enabled};
+pub use common::auth_config::{AuthConfig, get_auth_config, is_auth_enabled, is_public_read_only};
+pub use common::backpressure::{QueueThresholds, estimate_wait, get_queue_thresholds};
 pub use common::compression::{compress_string, decompress_to_string};
+pub use common::daily_budget::{daily_token_budget, get_daily_token_budget};
 pub use common::db;
-pub use common::db_env::get_db_pool;
-pub use common::health::{health_check, health_router};
+pub use common::db_env::{get_database_url, get_db_pool, is_run_migrations_enabled};
+pub use common::error_reporting::with_error_context;
+pub use common::health::{Readiness, db_ready_check, health_check, health_router, readiness_router};
 pub use common::hostname::{HostPortError, get_api_base_url};
+pub use common::job_log::{JobLog, JobLogLayer, take_job_log};
 pub use common::logging::setup_logging;
 pub use common::max_concurrency::get_max_concurrency;
-pub use common::poll_interval::{TimeUnit, get_poll_interval};
+pub use common::poll_interval::{TimeUnit, get_poll_interval, parse_duration};
+pub use common::reload::reload_on_sighup;
+pub use common::telemetry::{
+    TelemetryGuard, current_trace_context, inject_trace_headers, set_parent_from_headers,
+    set_parent_from_trace_context, setup_telemetry,
+};
 pub use common::tls_config::get_tls_config;
 
 pub use errors::Error;
@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::diff::parse_link;
+
+/// How serious a lint diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The file is a valid llms.txt, but could be better.
+    Warning,
+    /// The file does not adhere to the llms.txt format.
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single problem found in an llms.txt file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column number, counted in characters.
+    pub column: usize,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(severity: Severity, line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Renders the diagnostic like rustc does, pointing at the offending line of `content`.
+    pub fn render(&self, path: &Path, content: &str) -> String {
+        let source_line = content.lines().nth(self.line.saturating_sub(1)).unwrap_or("");
+        let gutter = " ".repeat(self.line.to_string().len());
+        format!(
+            "{}: {}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}^",
+            self.severity,
+            self.message,
+            gutter,
+            path.display(),
+            self.line,
+            self.column,
+            gutter,
+            self.line,
+            source_line,
+            gutter,
+            " ".repeat(self.column.saturating_sub(1)),
+        )
+    }
+}
+
+/// Checks an llms.txt file line-by-line and reports every problem found, rather than stopping at the first.
+///
+/// Errors are violations of the llms.txt format: a missing or misplaced H1, a missing summary blockquote,
+/// or section list items that aren't `[title](url)` links. Warnings are for things that are allowed but
+/// unhelpful: empty sections, duplicate links, links without descriptions, and headers deeper than H2.
+pub fn lint_llms_txt(content: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let mut h1_line: Option<usize> = None;
+    let mut has_summary = false;
+    let mut first_block_seen = false;
+    // (line number, number of links) of the current H2 section
+    let mut section: Option<(usize, usize)> = None;
+    let mut seen_urls: HashMap<String, usize> = HashMap::new();
+
+    fn close_section(section: Option<(usize, usize)>, diagnostics: &mut Vec<Diagnostic>) {
+        if let Some((line, 0)) = section {
+            diagnostics.push(Diagnostic::new(Severity::Warning, line, 1, "section has no links"));
+        }
+    }
+
+    for (index, raw_line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let indent = raw_line.len() - raw_line.trim_start().len();
+        let column = raw_line[..indent].chars().count() + 1;
+        let line = raw_line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(title) = line.strip_prefix("# ") {
+            if let Some(first) = h1_line {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    column,
+                    format!("second H1 header; the H1 was already declared on line {}", first),
+                ));
+            } else {
+                if first_block_seen {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        line_number,
+                        column,
+                        "H1 header must be the first line of the file",
+                    ));
+                }
+                if title.trim().is_empty() {
+                    diagnostics.push(Diagnostic::new(
+                        Severity::Error,
+                        line_number,
+                        column,
+                        "H1 header is empty",
+                    ));
+                }
+                h1_line = Some(line_number);
+            }
+        } else if line.starts_with("## ") {
+            close_section(section.take(), &mut diagnostics);
+            if !has_summary {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    column,
+                    "missing summary blockquote (`> ...`) before the first section",
+                ));
+                // only report the missing summary once
+                has_summary = true;
+            }
+            section = Some((line_number, 0));
+        } else if line.starts_with("###") {
+            diagnostics.push(Diagnostic::new(
+                Severity::Warning,
+                line_number,
+                column,
+                "headers deeper than H2 are not part of the llms.txt format",
+            ));
+        } else if line.starts_with('>') {
+            if section.is_none() && h1_line.is_some() {
+                has_summary = true;
+            }
+        } else if let Some((_, links)) = section.as_mut() {
+            if !(line.starts_with("- ") || line.starts_with("* ")) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    column,
+                    "sections may only contain list items of links",
+                ));
+                continue;
+            }
+            let Some(link) = parse_link(line) else {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    column,
+                    "list item is not a `[title](url)` link",
+                ));
+                continue;
+            };
+            *links += 1;
+
+            let trimmed = raw_line.trim_start();
+            let url_column = column
+                + trimmed
+                    .find("](")
+                    .map(|i| trimmed[..i].chars().count() + 2)
+                    .unwrap_or(0);
+            if link.title.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    column + 3,
+                    "link has an empty title",
+                ));
+            }
+            if link.url.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Error,
+                    line_number,
+                    url_column,
+                    "link has an empty URL",
+                ));
+            } else if let Some(first) = seen_urls.get(&link.url) {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    line_number,
+                    url_column,
+                    format!("duplicate link; '{}' is already listed on line {}", link.url, first),
+                ));
+            } else {
+                seen_urls.insert(link.url.clone(), line_number);
+            }
+            if link.description.is_none() {
+                diagnostics.push(Diagnostic::new(
+                    Severity::Warning,
+                    line_number,
+                    column,
+                    "link has no description; add one after a `:`",
+                ));
+            }
+        }
+        first_block_seen = true;
+    }
+    close_section(section, &mut diagnostics);
+
+    if h1_line.is_none() {
+        diagnostics.push(Diagnostic::new(
+            Severity::Error,
+            1,
+            1,
+            "missing H1 header with the website's name",
+        ));
+    }
+    if !has_summary {
+        let line = h1_line.map(|l| l + 1).unwrap_or(1);
+        diagnostics.push(Diagnostic::new(
+            Severity::Error,
+            line,
+            1,
+            "missing summary blockquote (`> ...`) after the H1 header",
+        ));
+    }
+
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+/// Points a quality score loses per diagnostic of each severity.
+const ERROR_PENALTY: u32 = 20;
+const WARNING_PENALTY: u32 = 5;
+
+/// Scores an llms.txt from 0 to 100 by its lint diagnostics: 100 for a clean file,
+/// minus 20 per error and 5 per warning.
+pub fn quality_score(content: &str) -> u8 {
+    let penalty: u32 = lint_llms_txt(content)
+        .iter()
+        .map(|d| match d.severity {
+            Severity::Error => ERROR_PENALTY,
+            Severity::Warning => WARNING_PENALTY,
+        })
+        .sum();
+    100u32.saturating_sub(penalty) as u8
+}
+
+/// Finds the llms.txt files to lint under `path`.
+///
+/// A file is always linted. A directory is searched recursively for files named `llms.txt`, `llms-full.txt`,
+/// or ending in `.llms.txt`. Results are sorted so output is stable.
+pub fn find_llms_txt_files(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            } else if is_llms_txt_file_name(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn is_llms_txt_file_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name == "llms.txt" || name == "llms-full.txt" || name.ends_with(".llms.txt"))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(diagnostics: &[Diagnostic]) -> Vec<(Severity, usize, &str)> {
+        diagnostics
+            .iter()
+            .map(|d| (d.severity, d.line, d.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lint_clean_file() {
+        let content =
+            "# Example\n\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n";
+        assert_eq!(lint_llms_txt(content), vec![]);
+    }
+
+    #[test]
+    fn test_quality_score() {
+        let clean =
+            "# Example\n\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n";
+        assert_eq!(quality_score(clean), 100);
+        // a link without a description
+        let warned = "# Example\n\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro)\n";
+        assert_eq!(quality_score(warned), 95);
+        // missing H1 and summary
+        assert_eq!(
+            quality_score("## Docs\n\n- [Intro](https://example.com/intro): Start here\n"),
+            60
+        );
+        assert_eq!(quality_score(&format!("## Docs\n{}", "not a list\n".repeat(10))), 0);
+    }
+
+    #[test]
+    fn test_lint_reports_all_errors() {
+        let content = "Some text\n# Example\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\nnot a list\n- just text\n# Again\n";
+        let diagnostics = lint_llms_txt(content);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Error, 2, "H1 header must be the first line of the file"),
+                (
+                    Severity::Error,
+                    4,
+                    "missing summary blockquote (`> ...`) before the first section"
+                ),
+                (Severity::Error, 7, "sections may only contain list items of links"),
+                (Severity::Error, 8, "list item is not a `[title](url)` link"),
+                (
+                    Severity::Error,
+                    9,
+                    "second H1 header; the H1 was already declared on line 2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_warnings() {
+        let content =
+            "# Example\n> Summary\n## Empty\n## Docs\n- [A](https://a.com)\n- [B](https://a.com): dup\n### Deep\n";
+        let diagnostics = lint_llms_txt(content);
+        assert_eq!(
+            messages(&diagnostics),
+            vec![
+                (Severity::Warning, 3, "section has no links"),
+                (Severity::Warning, 5, "link has no description; add one after a `:`"),
+                (
+                    Severity::Warning,
+                    6,
+                    "duplicate link; 'https://a.com' is already listed on line 5"
+                ),
+                (
+                    Severity::Warning,
+                    7,
+                    "headers deeper than H2 are not part of the llms.txt format"
+                ),
+            ]
+        );
+        assert_eq!(diagnostics[2].column, 7);
+    }
+
+    #[test]
+    fn test_lint_empty_file() {
+        assert_eq!(
+            messages(&lint_llms_txt("")),
+            vec![
+                (Severity::Error, 1, "missing H1 header with the website's name"),
+                (
+                    Severity::Error,
+                    1,
+                    "missing summary blockquote (`> ...`) after the H1 header"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let diagnostic = Diagnostic::new(Severity::Error, 2, 3, "bad");
+        assert_eq!(
+            diagnostic.render(Path::new("llms.txt"), "# T\n- x\n"),
+            "error: bad\n --> llms.txt:2:3\n  |\n2 | - x\n  |   ^"
+        );
+    }
+
+    #[test]
+    fn test_is_llms_txt_file_name() {
+        assert!(is_llms_txt_file_name(Path::new("docs/llms.txt")));
+        assert!(is_llms_txt_file_name(Path::new("llms-full.txt")));
+        assert!(is_llms_txt_file_name(Path::new("out/example.com.llms.txt")));
+        assert!(!is_llms_txt_file_name(Path::new("notes.txt")));
+    }
+}
@@ -4,13 +4,22 @@ use async_openai::{
     types::{ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessage, CreateChatCompletionRequestArgs},
 };
 use async_trait::async_trait;
+use std::sync::Arc;
 
-use crate::{Error, llms::LlmProvider};
+use crate::{
+    Error,
+    llms::{LlmProvider, SYSTEM_PROMPT, TokenUsage, UsageCounter},
+};
+
+/// Model used when none is specified.
+pub const DEFAULT_MODEL: &str = "gpt-5-mini";
 
 #[derive(Debug, Clone)]
 pub struct ChatGpt {
     pub client: Client<OpenAIConfig>,
     pub model_name: String,
+    /// Tokens used by this provider (and all of its clones).
+    pub usage: Arc<UsageCounter>,
 }
 
 impl ChatGpt {
@@ -18,6 +27,7 @@ impl ChatGpt {
         Self {
             client: Client::new(),
             model_name: model_name.to_string(),
+            usage: Arc::default(),
         }
     }
 }
@@ -26,7 +36,8 @@ impl Default for ChatGpt {
     fn default() -> Self {
         Self {
             client: Client::new(),
-            model_name: "gpt-5-mini".to_string(),
+            model_name: DEFAULT_MODEL.to_string(),
+            usage: Arc::default(),
         }
     }
 }
@@ -39,12 +50,16 @@ impl LlmProvider for ChatGpt {
             .model(&self.model_name)
             .messages([
                 // Can also use ChatCompletionRequest<Role>MessageArgs for builder pattern
-                ChatCompletionRequestSystemMessage::from("You are a helpful assistant. You produce summaries of websites formatted in Markdown according to the llms.txt specification.").into(),
+                ChatCompletionRequestSystemMessage::from(SYSTEM_PROMPT).into(),
                 ChatCompletionRequestUserMessage::from(prompt).into(),
             ])
             .build()?;
 
         let response = self.client.chat().create(request).await?;
+        if let Some(usage) = &response.usage {
+            self.usage
+                .record(usage.prompt_tokens as u64, usage.completion_tokens as u64);
+        }
 
         let llm_text_response = response
             .choices
@@ -55,4 +70,14 @@ impl LlmProvider for ChatGpt {
 
         Ok(llm_text_response)
     }
+
+    /// Looks up the model, which needs a valid API key that has access to it.
+    async fn check_credentials(&self) -> Result<(), Error> {
+        self.client.models().retrieve(&self.model_name).await?;
+        Ok(())
+    }
+
+    fn token_usage(&self) -> TokenUsage {
+        self.usage.total()
+    }
 }
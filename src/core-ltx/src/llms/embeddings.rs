@@ -0,0 +1,78 @@
+//! Embeddings of texts, for semantic search of the llms.txt files.
+
+use async_openai::{Client, config::OpenAIConfig, types::CreateEmbeddingRequestArgs};
+
+use crate::Error;
+
+/// Embedding model to embed the llms.txt files (and the search queries) with, e.g. `text-embedding-3-small`.
+/// Semantic search is off when it's not set.
+pub const EMBEDDING_MODEL_ENV_VAR: &str = "EMBEDDING_MODEL";
+
+/// Length of every embedding: the stored ones and the queries' have to be comparable.
+pub const EMBEDDING_DIMENSIONS: u32 = 1536;
+
+/// Characters of a text that are embedded: the embedding models read at most 8191 tokens, so only the start of a long
+/// llms.txt is. It's the part that says what the website is about.
+pub const MAX_EMBEDDED_CHARS: usize = 24_000;
+
+/// Embeds texts with an OpenAI embedding model.
+#[derive(Debug, Clone)]
+pub struct Embedder {
+    pub client: Client<OpenAIConfig>,
+    pub model_name: String,
+}
+
+impl Embedder {
+    pub fn new(model_name: &str) -> Self {
+        Self {
+            client: Client::new(),
+            model_name: model_name.to_string(),
+        }
+    }
+
+    /// The embedder of `EMBEDDING_MODEL`, or None when it's not set.
+    pub fn from_env() -> Option<Self> {
+        std::env::var(EMBEDDING_MODEL_ENV_VAR)
+            .ok()
+            .map(|model| model.trim().to_string())
+            .filter(|model| !model.is_empty())
+            .map(|model| Self::new(&model))
+    }
+
+    /// The embedding of each text, in the same order, in one request.
+    pub async fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>, Error> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+        let input: Vec<String> = texts.iter().map(|text| embedded_text(text).to_string()).collect();
+        let request = CreateEmbeddingRequestArgs::default()
+            .model(&self.model_name)
+            .input(input)
+            .dimensions(EMBEDDING_DIMENSIONS)
+            .build()?;
+        let mut data = self.client.embeddings().create(request).await?.data;
+        // they're numbered by their text, not necessarily in order
+        data.sort_by_key(|embedding| embedding.index);
+        Ok(data.into_iter().map(|embedding| embedding.embedding).collect())
+    }
+}
+
+/// The part of the text that's embedded: at most `MAX_EMBEDDED_CHARS` characters.
+pub fn embedded_text(text: &str) -> &str {
+    match text.char_indices().nth(MAX_EMBEDDED_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded_text() {
+        assert_eq!(embedded_text("# Example"), "# Example");
+        let long = "é".repeat(MAX_EMBEDDED_CHARS + 10);
+        assert_eq!(embedded_text(&long).chars().count(), MAX_EMBEDDED_CHARS);
+    }
+}
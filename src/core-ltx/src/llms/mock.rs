@@ -5,7 +5,8 @@
 //! without making real API calls.
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
 
 use crate::{Error, llms::LlmProvider};
 
@@ -14,7 +15,10 @@ use crate::{Error, llms::LlmProvider};
 /// Can be configured to:
 /// - Return specific responses based on prompt content
 /// - Return a default response for any prompt
+/// - Replay a recorded conversation: one response per prompt, in order
 /// - Simulate API failures
+///
+/// Every prompt it's sent is kept, see `received_prompts`.
 pub struct MockLlmProvider {
     /// Map of prompt substrings to responses
     /// If the prompt contains the key, return the corresponding response
@@ -23,6 +27,10 @@ pub struct MockLlmProvider {
     default_response: Option<String>,
     /// If true, always return an error
     should_fail: bool,
+    /// Responses returned in order, before any other configured response
+    sequence: Mutex<VecDeque<String>>,
+    /// Every prompt received so far
+    prompts: Mutex<Vec<String>>,
 }
 
 impl MockLlmProvider {
@@ -32,6 +40,8 @@ impl MockLlmProvider {
             responses: HashMap::new(),
             default_response: None,
             should_fail: false,
+            sequence: Mutex::new(VecDeque::new()),
+            prompts: Mutex::new(Vec::new()),
         }
     }
 
@@ -56,18 +66,25 @@ impl MockLlmProvider {
     /// Create a mock with a default response for any prompt
     pub fn with_default(response: &str) -> Self {
         Self {
-            responses: HashMap::new(),
             default_response: Some(response.to_string()),
-            should_fail: false,
+            ..Self::new()
         }
     }
 
     /// Create a mock that always fails with an error
     pub fn with_failure() -> Self {
         Self {
-            responses: HashMap::new(),
-            default_response: None,
             should_fail: true,
+            ..Self::new()
+        }
+    }
+
+    /// Create a mock that replays recorded responses: the first prompt gets the first response, and so on.
+    /// Once they're used up, it fails.
+    pub fn with_sequence<S: Into<String>>(responses: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            sequence: Mutex::new(responses.into_iter().map(Into::into).collect()),
+            ..Self::new()
         }
     }
 
@@ -100,6 +117,11 @@ impl MockLlmProvider {
     pub fn set_should_fail(&mut self, should_fail: bool) {
         self.should_fail = should_fail;
     }
+
+    /// Every prompt this provider was sent, in order
+    pub fn received_prompts(&self) -> Vec<String> {
+        self.prompts.lock().expect("prompts lock poisoned").clone()
+    }
 }
 
 impl Default for MockLlmProvider {
@@ -111,6 +133,11 @@ impl Default for MockLlmProvider {
 #[async_trait]
 impl LlmProvider for MockLlmProvider {
     async fn complete_prompt(&self, prompt: &str) -> Result<String, Error> {
+        self.prompts
+            .lock()
+            .expect("prompts lock poisoned")
+            .push(prompt.to_string());
+
         if self.should_fail {
             // Use InvalidLlmsTxtFormat as a generic error for mock failures
             return Err(Error::InvalidLlmsTxtFormat(
@@ -118,6 +145,10 @@ impl LlmProvider for MockLlmProvider {
             ));
         }
 
+        if let Some(response) = self.sequence.lock().expect("sequence lock poisoned").pop_front() {
+            return Ok(response);
+        }
+
         // Try to find a matching response based on prompt content
         for (key, response) in &self.responses {
             if prompt.contains(key) {
@@ -135,6 +166,15 @@ impl LlmProvider for MockLlmProvider {
             "Mock LLM provider has no response configured for this prompt".to_string(),
         ))
     }
+
+    async fn check_credentials(&self) -> Result<(), Error> {
+        if self.should_fail {
+            return Err(Error::InvalidLlmsTxtFormat(
+                "Mock LLM provider configured to fail".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 //
@@ -330,6 +370,15 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_mock_with_sequence() {
+        let provider = MockLlmProvider::with_sequence(["first", "second"]);
+        assert_eq!(provider.complete_prompt("generate").await.unwrap(), "first");
+        assert_eq!(provider.complete_prompt("retry").await.unwrap(), "second");
+        assert!(provider.complete_prompt("again").await.is_err());
+        assert_eq!(provider.received_prompts(), vec!["generate", "retry", "again"]);
+    }
+
     #[tokio::test]
     async fn test_mock_with_failure() {
         let provider = MockLlmProvider::with_failure();
@@ -337,6 +386,12 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_mock_check_credentials() {
+        assert!(MockLlmProvider::new().check_credentials().await.is_ok());
+        assert!(MockLlmProvider::with_failure().check_credentials().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_mock_with_valid_llms_txt() {
         let provider = MockLlmProvider::with_valid_llms_txt();
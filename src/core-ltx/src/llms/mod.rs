@@ -1,43 +1,69 @@
 pub mod chatgpt;
 pub mod claude;
+pub mod embeddings;
 pub mod prompts;
+pub mod registry;
+pub mod usage;
 
-// Make mock module available for tests in this crate and dependent crates
+// Make mock and replay modules available for tests in this crate and dependent crates
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod mock;
+#[cfg(any(test, feature = "test-helpers"))]
+pub mod replay;
 
 use async_trait::async_trait;
 pub use prompts::{
-    prompt_generate_llms_txt, prompt_retry_generate_llms_txt, prompt_retry_update_llms_txt, prompt_update_llms_txt,
+    SYSTEM_PROMPT, prompt_fix_llms_txt, prompt_generate_llms_txt, prompt_regenerate_section,
+    prompt_retry_generate_llms_txt, prompt_retry_regenerate_section, prompt_retry_update_llms_txt,
+    prompt_update_llms_txt, prompt_version,
 };
 
 pub use chatgpt::ChatGpt;
+pub use dto_ltx::Detail;
+pub use embeddings::Embedder;
+pub use registry::{MODELS, ModelInfo, find_model};
+pub use usage::{TokenUsage, UsageCounter, estimate_tokens, track_usage};
 
+use crate::section::{section_content, splice_section};
 use crate::{Error, LlmsTxt, download, is_valid_markdown, is_valid_url, validate_is_llm_txt};
 
 /// Interface to a hosted LLM that lets us complete a prompt and await a response.
 #[async_trait]
 pub trait LlmProvider: Send + Sync {
     async fn complete_prompt(&self, prompt: &str) -> Result<String, Error>;
+
+    /// Cheap request that fails if the provider can't be used, e.g. because of a bad API key or an unknown model.
+    /// Costs no tokens. Providers without credentials always pass.
+    async fn check_credentials(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Total tokens used by all prompts completed by this provider so far.
+    /// Providers that don't track usage report zero.
+    fn token_usage(&self) -> TokenUsage {
+        TokenUsage::default()
+    }
 }
 
 /// Downloads a website's HTML and generates an llms.txt file for it using an LLM.
-pub async fn generate_llms_txt_url(provider: &dyn LlmProvider, website_url: &str) -> Result<LlmsTxt, Error> {
+pub async fn generate_llms_txt_url(
+    provider: &dyn LlmProvider,
+    website_url: &str,
+    detail: Detail,
+) -> Result<LlmsTxt, Error> {
     let url = is_valid_url(website_url)?;
     let html = download(&url).await?;
-    generate_llms_txt(provider, &html).await
+    generate_llms_txt(provider, &html, detail).await
 }
 
 /// Generates an llms.txt file from a website's HTML using an LLM provider with specific prompting.
-pub async fn generate_llms_txt(provider: &dyn LlmProvider, html: &str) -> Result<LlmsTxt, Error> {
-    let prompt = prompt_generate_llms_txt(html)?;
+/// The detail level picks how much of the website it goes into.
+pub async fn generate_llms_txt(provider: &dyn LlmProvider, html: &str, detail: Detail) -> Result<LlmsTxt, Error> {
+    let prompt = prompt_generate_llms_txt(html, detail)?;
     let llm_response = provider.complete_prompt(&prompt).await?;
 
-    match is_valid_markdown(&llm_response) {
-        Ok(markdown) => match validate_is_llm_txt(markdown) {
-            Ok(llms_txt) => Ok(llms_txt),
-            Err(e) => retry_generate(provider, html, &llm_response, &e).await,
-        },
+    match parse_blocking(&llm_response, parse_llms_txt).await? {
+        Ok(llms_txt) => Ok(llms_txt),
         Err(e) => retry_generate(provider, html, &llm_response, &e).await,
     }
 }
@@ -47,10 +73,11 @@ pub async fn update_llms_txt_url(
     provider: &dyn LlmProvider,
     existing_llms_txt: &str,
     website_url: &str,
+    detail: Detail,
 ) -> Result<LlmsTxt, Error> {
     let url = is_valid_url(website_url)?;
     let html = download(&url).await?;
-    update_llms_txt(provider, existing_llms_txt, &html).await
+    update_llms_txt(provider, existing_llms_txt, &html, detail).await
 }
 
 /// Updates an old llms.txt file with the website's new content.
@@ -58,21 +85,52 @@ pub async fn update_llms_txt(
     provider: &dyn LlmProvider,
     existing_llms_txt: &str,
     html: &str,
+    detail: Detail,
 ) -> Result<LlmsTxt, Error> {
-    validate_is_llm_txt(is_valid_markdown(existing_llms_txt)?)?;
+    parse_blocking(existing_llms_txt, parse_llms_txt).await??;
 
-    let prompt = prompt_update_llms_txt(existing_llms_txt, html)?;
+    let prompt = prompt_update_llms_txt(existing_llms_txt, html, detail)?;
     let llm_response = provider.complete_prompt(&prompt).await?;
 
-    match is_valid_markdown(&llm_response) {
-        Ok(markdown) => match validate_is_llm_txt(markdown) {
-            Ok(llms_txt) => Ok(llms_txt),
-            Err(e) => retry_update(provider, existing_llms_txt, html, &llm_response, &e).await,
-        },
+    match parse_blocking(&llm_response, parse_llms_txt).await? {
+        Ok(llms_txt) => Ok(llms_txt),
         Err(e) => retry_update(provider, existing_llms_txt, html, &llm_response, &e).await,
     }
 }
 
+/// Regenerates only one H2 section of an llms.txt file, from the HTML of the page that section is about, and splices
+/// it back in: the rest of the file is kept as-is.
+pub async fn regenerate_section(
+    provider: &dyn LlmProvider,
+    existing_llms_txt: &str,
+    section: &str,
+    html: &str,
+    detail: Detail,
+) -> Result<LlmsTxt, Error> {
+    let existing = parse_blocking(existing_llms_txt, parse_llms_txt).await??;
+    let current_section = section_content(&existing, section)?;
+
+    let prompt = prompt_regenerate_section(existing_llms_txt, section, &current_section, html, detail)?;
+    let llm_response = provider.complete_prompt(&prompt).await?;
+
+    let parsed = parse_blocking(&llm_response, is_valid_markdown).await?;
+    match parsed.and_then(|markdown| splice_section(&existing, section, markdown)) {
+        Ok(llms_txt) => Ok(llms_txt),
+        Err(e) => {
+            let prompt = prompt_retry_regenerate_section(section, html, &llm_response, &e.to_string())?;
+            let new_llm_response = provider.complete_prompt(&prompt).await?;
+            parse_blocking(&new_llm_response, is_valid_markdown)
+                .await?
+                .and_then(|markdown| splice_section(&existing, section, markdown))
+        }
+    }
+}
+
+/// Asks the LLM to repair an invalid llms.txt file, given the reason it's invalid. Makes a single attempt.
+pub async fn fix_llms_txt(provider: &dyn LlmProvider, llms_txt: &str, error: &Error) -> Result<LlmsTxt, Error> {
+    retry(provider, &prompt_fix_llms_txt(llms_txt, &error.to_string())?).await
+}
+
 async fn retry_generate(
     provider: &dyn LlmProvider,
     html: &str,
@@ -102,5 +160,19 @@ async fn retry_update(
 
 async fn retry(provider: &dyn LlmProvider, prompt: &str) -> Result<LlmsTxt, Error> {
     let new_llm_response = provider.complete_prompt(prompt).await?;
-    is_valid_markdown(&new_llm_response).and_then(validate_is_llm_txt)
+    parse_blocking(&new_llm_response, parse_llms_txt).await?
+}
+
+fn parse_llms_txt(text: &str) -> Result<LlmsTxt, Error> {
+    is_valid_markdown(text).and_then(validate_is_llm_txt)
+}
+
+/// Parses the text on a blocking thread: a long llms.txt takes a while to parse, which would hold up the other jobs'
+/// I/O if it were done on the runtime. Fails when the thread does, otherwise returns what `parse` returned.
+async fn parse_blocking<T: Send + 'static>(
+    text: &str,
+    parse: fn(&str) -> Result<T, Error>,
+) -> Result<Result<T, Error>, Error> {
+    let text = text.to_string();
+    Ok(tokio::task::spawn_blocking(move || parse(&text)).await?)
 }
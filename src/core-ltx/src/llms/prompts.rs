@@ -1,9 +1,14 @@
 use std::collections::HashMap;
+use std::sync::LazyLock;
 
 use crate::Error;
+use dto_ltx::Detail;
 use indoc::indoc;
 use subst::substitute;
 
+/// The system message sent along with every prompt.
+pub const SYSTEM_PROMPT: &str = "You are a helpful assistant. You produce summaries of websites formatted in Markdown according to the llms.txt specification.";
+
 const GENERATE_LLMS_TXT: &str = indoc! { "
   You need to generate an llms.txt file for a website. This file summarizes and describes the main content of the website. It includes a description of the website's structured elements and all outbound links.
 
@@ -63,13 +68,32 @@ const GENERATE_LLMS_TXT: &str = indoc! { "
   ${WEBSITE}
   </website>
 
+  ${DETAIL}
+
   Output only valid markdown exactly in the described llms.txt format. Do not output any other text!
 "};
 
-pub fn prompt_generate_llms_txt(website: &str) -> Result<String, Error> {
+// How much of the website the llms.txt goes into, for each `Detail`.
+
+const BRIEF_DETAIL: &str = "Keep the llms.txt brief: at most 30 lines. Write the H1 and the blockquote summary, then link only to the handful of most important pages, in one or two sections.";
+
+const STANDARD_DETAIL: &str = "Cover the main sections of the website: link to their most important pages, with a short note for each. Secondary pages go in the \"Optional\" section.";
+
+const COMPREHENSIVE_DETAIL: &str = "Be comprehensive: enumerate every subpage the website links to, grouped into sections by topic, with a note for each describing what it contains. Do not leave any page out.";
+
+fn detail_instructions(detail: Detail) -> &'static str {
+    match detail {
+        Detail::Brief => BRIEF_DETAIL,
+        Detail::Standard => STANDARD_DETAIL,
+        Detail::Comprehensive => COMPREHENSIVE_DETAIL,
+    }
+}
+
+pub fn prompt_generate_llms_txt(website: &str, detail: Detail) -> Result<String, Error> {
     let res = substitute(GENERATE_LLMS_TXT, &{
         let mut v = HashMap::new();
         v.insert("WEBSITE".to_string(), website.to_string());
+        v.insert("DETAIL".to_string(), detail_instructions(detail).to_string());
         v
     })?;
     Ok(res)
@@ -171,14 +195,17 @@ const UPDATE_LLMS_TXT: &str = indoc! {"
   ${WEBSITE}
   </website>
 
+  ${DETAIL}
+
   Output only valid markdown exactly in the described llms.txt format. Do not output any other text!
 "};
 
-pub fn prompt_update_llms_txt(llms_txt: &str, website: &str) -> Result<String, Error> {
+pub fn prompt_update_llms_txt(llms_txt: &str, website: &str, detail: Detail) -> Result<String, Error> {
     let res = substitute(UPDATE_LLMS_TXT, &{
         let mut v = HashMap::new();
         v.insert("LLMS_TXT".to_string(), llms_txt.to_string());
         v.insert("WEBSITE".to_string(), website.to_string());
+        v.insert("DETAIL".to_string(), detail_instructions(detail).to_string());
         v
     })?;
     Ok(res)
@@ -227,6 +254,139 @@ pub fn prompt_retry_update_llms_txt(
     Ok(res)
 }
 
+const REGENERATE_SECTION: &str = indoc! { "
+  You need to regenerate one section of an existing llms.txt file, because the part of the website it describes has changed. The rest of the file stays as it is.
+
+  Here is the whole llms.txt file, for context:
+  <llms_txt>
+  ${LLMS_TXT}
+  </llms_txt>
+
+  This is its \"${SECTION}\" section, which you need to regenerate:
+  <section>
+  ${SECTION_CONTENT}
+  </section>
+
+  This is the HTML content of the page the section is about:
+  <website>
+  ${WEBSITE}
+  </website>
+
+  ${DETAIL}
+
+  Output only the regenerated section: the \"## ${SECTION}\" H2 header, followed by a markdown list of `[name](url)` links, each optionally followed by `:` and notes. Do not output the rest of the llms.txt file, or any other text!
+"};
+
+pub fn prompt_regenerate_section(
+    llms_txt: &str,
+    section: &str,
+    section_content: &str,
+    website: &str,
+    detail: Detail,
+) -> Result<String, Error> {
+    let res = substitute(REGENERATE_SECTION, &{
+        let mut v = HashMap::new();
+        v.insert("LLMS_TXT".to_string(), llms_txt.to_string());
+        v.insert("SECTION".to_string(), section.to_string());
+        v.insert("SECTION_CONTENT".to_string(), section_content.to_string());
+        v.insert("WEBSITE".to_string(), website.to_string());
+        v.insert("DETAIL".to_string(), detail_instructions(detail).to_string());
+        v
+    })?;
+    Ok(res)
+}
+
+const RETRY_REGENERATE_SECTION: &str = indoc! { "
+  You failed to regenerate a valid section of an llms.txt file!
+
+  From the page:
+  <website>
+  ${WEBSITE}
+  </website>
+
+  You generated:
+  <output>
+  ${SECTION_CONTENT}
+  </output>
+
+  But it can't replace the \"${SECTION}\" section of the llms.txt file because:
+  <error>
+  ${ERROR}
+  </error>
+
+  Please fix the error and output only the \"## ${SECTION}\" section: its H2 header, followed by a markdown list of links. Do not output any other text!
+"};
+
+pub fn prompt_retry_regenerate_section(
+    section: &str,
+    website: &str,
+    section_content: &str,
+    error: &str,
+) -> Result<String, Error> {
+    let res = substitute(RETRY_REGENERATE_SECTION, &{
+        let mut v = HashMap::new();
+        v.insert("SECTION".to_string(), section.to_string());
+        v.insert("WEBSITE".to_string(), website.to_string());
+        v.insert("SECTION_CONTENT".to_string(), section_content.to_string());
+        v.insert("ERROR".to_string(), error.to_string());
+        v
+    })?;
+    Ok(res)
+}
+
+const FIX_LLMS_TXT: &str = indoc! { "
+  This llms.txt file is almost, but not quite, valid:
+  <llms_txt>
+  ${LLMS_TXT}
+  </llms_txt>
+
+  It is not a valid markdown llms.txt file because:
+  <error>
+  ${ERROR}
+  </error>
+
+  A valid llms.txt file has, in order: an H1 with the name of the site, a blockquote summarizing the site, optional paragraphs of details, and optional H2 sections that each contain a markdown list of `[name](url)` links, each optionally followed by `:` and notes.
+
+  Fix the error while changing as little of the content as possible. Output only valid markdown exactly in the llms.txt format. Do not output any other text!
+"};
+
+pub fn prompt_fix_llms_txt(llms_txt: &str, error: &str) -> Result<String, Error> {
+    let res = substitute(FIX_LLMS_TXT, &{
+        let mut v = HashMap::new();
+        v.insert("LLMS_TXT".to_string(), llms_txt.to_string());
+        v.insert("ERROR".to_string(), error.to_string());
+        v
+    })?;
+    Ok(res)
+}
+
+/// Hex digits of the MD5 digest kept in the prompt version.
+const PROMPT_VERSION_LEN: usize = 12;
+
+static PROMPT_VERSION: LazyLock<String> = LazyLock::new(|| {
+    let templates = [
+        SYSTEM_PROMPT,
+        GENERATE_LLMS_TXT,
+        RETRY_GENERATE_LLMS_TXT,
+        UPDATE_LLMS_TXT,
+        RETRY_UPDATE_LLMS_TXT,
+        REGENERATE_SECTION,
+        RETRY_REGENERATE_SECTION,
+        FIX_LLMS_TXT,
+        BRIEF_DETAIL,
+        STANDARD_DETAIL,
+        COMPREHENSIVE_DETAIL,
+    ];
+    let digest = md5::compute(templates.join("\0"));
+    format!("{:x}", digest)[..PROMPT_VERSION_LEN].to_string()
+});
+
+/// Identifies the prompts: a hash of the system prompt and every template (detail instructions included), which changes whenever any of them is
+/// edited. Stored with each generated llms.txt, so that the ones made with older prompts can be found.
+pub fn prompt_version() -> &'static str {
+    &PROMPT_VERSION
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,7 +397,7 @@ mod tests {
 
     #[test]
     fn test_prompt_generate() {
-        let p = prompt_generate_llms_txt(WEBSITE).unwrap();
+        let p = prompt_generate_llms_txt(WEBSITE, Detail::Standard).unwrap();
         assert_eq!(
             p,
             indoc! { "
@@ -299,6 +459,8 @@ mod tests {
       <html><head></head><body><h1>Hello, World!</h1></body></html>
       </website>
 
+      Cover the main sections of the website: link to their most important pages, with a short note for each. Secondary pages go in the \"Optional\" section.
+
       Output only valid markdown exactly in the described llms.txt format. Do not output any other text!
     "}
         )
@@ -335,7 +497,7 @@ mod tests {
 
     #[test]
     fn test_prompt_update() {
-        let p = prompt_update_llms_txt(LLMS_TXT, WEBSITE).unwrap();
+        let p = prompt_update_llms_txt(LLMS_TXT, WEBSITE, Detail::Standard).unwrap();
         assert_eq!(
             p,
             indoc! {"
@@ -403,6 +565,8 @@ mod tests {
       <html><head></head><body><h1>Hello, World!</h1></body></html>
       </website>
 
+      Cover the main sections of the website: link to their most important pages, with a short note for each. Secondary pages go in the \"Optional\" section.
+
       Output only valid markdown exactly in the described llms.txt format. Do not output any other text!
     "}
         );
@@ -442,4 +606,52 @@ mod tests {
     "}
         );
     }
+
+    #[test]
+    fn test_prompt_detail() {
+        for detail in Detail::ALL {
+            let generate = prompt_generate_llms_txt(WEBSITE, detail).unwrap();
+            let update = prompt_update_llms_txt(LLMS_TXT, WEBSITE, detail).unwrap();
+            for other in Detail::ALL {
+                let instructions = detail_instructions(other);
+                assert_eq!(generate.contains(instructions), other == detail);
+                assert_eq!(update.contains(instructions), other == detail);
+            }
+        }
+        assert!(
+            prompt_generate_llms_txt(WEBSITE, Detail::Brief)
+                .unwrap()
+                .contains("at most 30 lines")
+        );
+    }
+
+    #[test]
+    fn test_prompt_regenerate_section() {
+        let section = "## Docs\n\n- [Guide](https://example.com/guide)\n";
+        let p = prompt_regenerate_section(LLMS_TXT, "Docs", section, WEBSITE, Detail::Brief).unwrap();
+        assert!(p.contains("<llms_txt>\n# Example\n"));
+        assert!(p.contains("This is its \"Docs\" section"));
+        assert!(p.contains("<section>\n## Docs\n\n- [Guide](https://example.com/guide)\n\n</section>"));
+        assert!(p.contains(detail_instructions(Detail::Brief)));
+        assert!(p.contains("the \"## Docs\" H2 header"));
+
+        let p = prompt_retry_regenerate_section("Docs", WEBSITE, section, ERROR).unwrap();
+        assert!(p.contains("can't replace the \"Docs\" section"));
+        assert!(p.contains("<error>\nQUALITY: Not a good summary of website content.\n</error>"));
+    }
+
+    #[test]
+    fn test_prompt_fix() {
+        let p = prompt_fix_llms_txt(LLMS_TXT, ERROR).unwrap();
+        assert!(p.starts_with("This llms.txt file is almost, but not quite, valid:\n<llms_txt>\n# Example\n"));
+        assert!(p.contains("<error>\nQUALITY: Not a good summary of website content.\n</error>"));
+    }
+
+    #[test]
+    fn test_prompt_version() {
+        let version = prompt_version();
+        assert_eq!(version.len(), PROMPT_VERSION_LEN);
+        assert!(version.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(version, prompt_version());
+    }
 }
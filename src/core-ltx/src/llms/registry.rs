@@ -0,0 +1,118 @@
+//! The provider/model combinations that llms.txt files can be generated with.
+
+use serde::Serialize;
+
+/// What's known about a supported model.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ModelInfo {
+    /// Who hosts the model, e.g. `openai`.
+    pub provider: &'static str,
+    /// Name of the model in the provider's API.
+    pub model: &'static str,
+    /// Maximum number of tokens of a prompt and its completion.
+    pub context_window: u32,
+    /// USD per million prompt tokens.
+    pub input_cost_per_mtok: f64,
+    /// USD per million completion tokens.
+    pub output_cost_per_mtok: f64,
+    /// Still usable, but superseded: llms.txt files generated with it should be re-generated.
+    pub deprecated: bool,
+}
+
+impl ModelInfo {
+    /// USD cost of a completion.
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.input_cost_per_mtok + completion_tokens as f64 * self.output_cost_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Every supported model.
+pub const MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-5",
+        context_window: 400_000,
+        input_cost_per_mtok: 1.25,
+        output_cost_per_mtok: 10.0,
+        deprecated: false,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-5-mini",
+        context_window: 400_000,
+        input_cost_per_mtok: 0.25,
+        output_cost_per_mtok: 2.0,
+        deprecated: false,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-5-nano",
+        context_window: 400_000,
+        input_cost_per_mtok: 0.05,
+        output_cost_per_mtok: 0.4,
+        deprecated: false,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-4.1",
+        context_window: 1_047_576,
+        input_cost_per_mtok: 2.0,
+        output_cost_per_mtok: 8.0,
+        deprecated: false,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-4.1-mini",
+        context_window: 1_047_576,
+        input_cost_per_mtok: 0.4,
+        output_cost_per_mtok: 1.6,
+        deprecated: false,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-4o",
+        context_window: 128_000,
+        input_cost_per_mtok: 2.5,
+        output_cost_per_mtok: 10.0,
+        deprecated: true,
+    },
+    ModelInfo {
+        provider: "openai",
+        model: "gpt-4o-mini",
+        context_window: 128_000,
+        input_cost_per_mtok: 0.15,
+        output_cost_per_mtok: 0.6,
+        deprecated: true,
+    },
+];
+
+/// The supported model with this name, if there is one.
+pub fn find_model(model: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|info| info.model == model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llms::chatgpt::DEFAULT_MODEL;
+
+    #[test]
+    fn test_default_model_is_supported() {
+        let info = find_model(DEFAULT_MODEL).expect("the default model must be in the registry");
+        assert!(!info.deprecated);
+    }
+
+    #[test]
+    fn test_find_model() {
+        assert_eq!(find_model("gpt-4o").map(|info| info.deprecated), Some(true));
+        assert_eq!(find_model("gpt-9000"), None);
+    }
+
+    #[test]
+    fn test_cost() {
+        let info = find_model("gpt-5-mini").unwrap();
+        assert!((info.cost(1_000_000, 500_000) - 1.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,168 @@
+//! Replay LLM provider for testing
+//!
+//! Serves canned responses keyed by the hash of the prompt they answer, so a test exercising the full generation
+//! pipeline gets the same responses on every run without network access or an API key. Unlike `MockLlmProvider`,
+//! a prompt only gets a response if it is exactly the one recorded: a change to the prompts or the HTML cleaning
+//! makes the replay miss, instead of silently answering a different question.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::{Error, llms::LlmProvider};
+
+/// File extension of the responses in a replay directory: `<prompt hash>.md`
+pub const RESPONSE_EXTENSION: &str = "md";
+
+/// The key a prompt's response is stored under: the hex MD5 digest of the prompt.
+pub fn prompt_hash(prompt: &str) -> String {
+    format!("{:x}", md5::compute(prompt.as_bytes()))
+}
+
+/// Replays recorded LLM responses, keyed by `prompt_hash`.
+///
+/// Prompts without a recorded response fail, and their hashes are kept, see `misses`, so the missing responses can be
+/// recorded as `<hash>.md` files.
+pub struct ReplayLlmProvider {
+    /// Map of prompt hashes to responses
+    responses: HashMap<String, String>,
+    /// Hashes of the prompts that had no recorded response, in order
+    misses: Mutex<Vec<String>>,
+}
+
+impl ReplayLlmProvider {
+    /// Create a new replay provider without any recorded response
+    pub fn new() -> Self {
+        Self {
+            responses: HashMap::new(),
+            misses: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Create a replay provider answering each prompt with its response
+    pub fn with_responses<P: AsRef<str>, R: Into<String>>(responses: impl IntoIterator<Item = (P, R)>) -> Self {
+        let mut provider = Self::new();
+        for (prompt, response) in responses {
+            provider.insert(prompt.as_ref(), response);
+        }
+        provider
+    }
+
+    /// Load the responses recorded in `dir`: every `<prompt hash>.md` file in it is the response to that prompt.
+    /// Other files are ignored.
+    pub fn from_dir(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut provider = Self::new();
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(RESPONSE_EXTENSION) {
+                continue;
+            }
+            if let Some(hash) = path.file_stem().and_then(|s| s.to_str()) {
+                let response = std::fs::read_to_string(&path)?;
+                provider.responses.insert(hash.to_string(), response);
+            }
+        }
+        Ok(provider)
+    }
+
+    /// Record the response to a prompt
+    pub fn insert(&mut self, prompt: &str, response: impl Into<String>) {
+        self.responses.insert(prompt_hash(prompt), response.into());
+    }
+
+    /// Number of recorded responses
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// True if there's no recorded response
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Hashes of the prompts this provider had no response for, in order
+    pub fn misses(&self) -> Vec<String> {
+        self.misses.lock().expect("misses lock poisoned").clone()
+    }
+}
+
+impl Default for ReplayLlmProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl LlmProvider for ReplayLlmProvider {
+    async fn complete_prompt(&self, prompt: &str) -> Result<String, Error> {
+        let hash = prompt_hash(prompt);
+        match self.responses.get(&hash) {
+            Some(response) => Ok(response.clone()),
+            None => {
+                self.misses.lock().expect("misses lock poisoned").push(hash.clone());
+                // Use InvalidLlmsTxtFormat as a generic error, like the mock provider's failures
+                Err(Error::InvalidLlmsTxtFormat(format!(
+                    "Replay LLM provider has no recorded response for prompt {hash}: record it as {hash}.{RESPONSE_EXTENSION}"
+                )))
+            }
+        }
+    }
+
+    async fn check_credentials(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_hash_is_hex_md5() {
+        let hash = prompt_hash("hello");
+        assert_eq!(hash, "5d41402abc4b2a76b9719d911017c592");
+        assert_eq!(prompt_hash("hello"), hash);
+        assert_ne!(prompt_hash("hello "), hash);
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_response() {
+        let provider = ReplayLlmProvider::with_responses([("generate llms.txt", "# Title")]);
+        assert_eq!(provider.complete_prompt("generate llms.txt").await.unwrap(), "# Title");
+        assert!(provider.misses().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replay_needs_the_exact_prompt() {
+        let provider = ReplayLlmProvider::with_responses([("generate llms.txt", "# Title")]);
+
+        let result = provider.complete_prompt("generate llms.txt, please").await;
+        let error = result.unwrap_err().to_string();
+        let hash = prompt_hash("generate llms.txt, please");
+        assert!(error.contains(&hash), "{error}");
+        assert_eq!(provider.misses(), vec![hash]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_from_dir() {
+        let dir = std::env::temp_dir().join(format!("replay-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{}.md", prompt_hash("first"))), "one").unwrap();
+        std::fs::write(dir.join(format!("{}.md", prompt_hash("second"))), "two").unwrap();
+        std::fs::write(dir.join("README.txt"), "not a response").unwrap();
+
+        let provider = ReplayLlmProvider::from_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(provider.len(), 2);
+        assert_eq!(provider.complete_prompt("first").await.unwrap(), "one");
+        assert_eq!(provider.complete_prompt("second").await.unwrap(), "two");
+        assert!(provider.complete_prompt("third").await.is_err());
+    }
+
+    #[test]
+    fn test_replay_from_missing_dir() {
+        assert!(ReplayLlmProvider::from_dir("/nonexistent/replay/dir").is_err());
+    }
+}
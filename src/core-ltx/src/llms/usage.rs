@@ -0,0 +1,139 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+/// Number of tokens consumed by LLM calls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl std::ops::Add for TokenUsage {
+    type Output = TokenUsage;
+
+    fn add(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens + other.prompt_tokens,
+            completion_tokens: self.completion_tokens + other.completion_tokens,
+            total_tokens: self.total_tokens + other.total_tokens,
+        }
+    }
+}
+
+/// Roughly estimates how many tokens the text is, at about 4 characters per token.
+/// Real counts depend on the model's tokenizer: only use this for ballpark figures.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Thread-safe running total of token usage. Providers keep one to report `LlmProvider::token_usage`.
+#[derive(Debug, Default)]
+pub struct UsageCounter {
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+}
+
+tokio::task_local! {
+    static TASK_USAGE: Arc<UsageCounter>;
+}
+
+impl UsageCounter {
+    /// Adds the tokens used by one LLM call to the running total,
+    /// and to the total of the enclosing `track_usage` call, if any.
+    pub fn record(&self, prompt_tokens: u64, completion_tokens: u64) {
+        self.add(prompt_tokens, completion_tokens);
+        let _ = TASK_USAGE.try_with(|task_usage| task_usage.add(prompt_tokens, completion_tokens));
+    }
+
+    fn add(&self, prompt_tokens: u64, completion_tokens: u64) {
+        self.prompt_tokens.fetch_add(prompt_tokens, Ordering::Relaxed);
+        self.completion_tokens.fetch_add(completion_tokens, Ordering::Relaxed);
+    }
+
+    /// The total usage recorded so far.
+    pub fn total(&self) -> TokenUsage {
+        let prompt_tokens = self.prompt_tokens.load(Ordering::Relaxed);
+        let completion_tokens = self.completion_tokens.load(Ordering::Relaxed);
+        TokenUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+}
+
+/// Runs `future` and returns the tokens used by the LLM calls it made, e.g. the cost of a single job.
+///
+/// Unlike `LlmProvider::token_usage`, this isn't affected by other tasks sharing the provider.
+/// Only calls recorded in a `UsageCounter` are counted.
+pub async fn track_usage<F: Future>(future: F) -> (F::Output, TokenUsage) {
+    let counter = Arc::new(UsageCounter::default());
+    let output = TASK_USAGE.scope(counter.clone(), future).await;
+    (output, counter.total())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_counter_accumulates() {
+        let counter = UsageCounter::default();
+        assert_eq!(counter.total(), TokenUsage::default());
+
+        counter.record(100, 20);
+        counter.record(50, 5);
+        assert_eq!(
+            counter.total(),
+            TokenUsage {
+                prompt_tokens: 150,
+                completion_tokens: 25,
+                total_tokens: 175,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_usage_only_counts_its_own_calls() {
+        let shared = UsageCounter::default();
+        shared.record(1000, 1000);
+
+        let ((), usage) = track_usage(async {
+            shared.record(10, 2);
+            shared.record(5, 1);
+        })
+        .await;
+
+        assert_eq!(usage.total_tokens, 18);
+        assert_eq!(shared.total().total_tokens, 2018);
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abc"), 1);
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("abcdefghi"), 3);
+    }
+
+    #[test]
+    fn test_token_usage_add() {
+        let a = TokenUsage {
+            prompt_tokens: 1,
+            completion_tokens: 2,
+            total_tokens: 3,
+        };
+        assert_eq!(
+            a + a,
+            TokenUsage {
+                prompt_tokens: 2,
+                completion_tokens: 4,
+                total_tokens: 6,
+            }
+        );
+    }
+}
@@ -1,14 +1,54 @@
-use std::{fmt::Debug, path::PathBuf};
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-use clap::{Args, Parser, Subcommand, ValueEnum};
-use core_ltx::{is_valid_markdown, llms::LlmProvider, validate_is_llm_txt};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use client_ltx::{ApiClient, JobRequestPayload, Uuid};
+use core_ltx::{
+    batch::{output_file_name, parse_url_list},
+    crawl::{CrawlOptions, combine_pages, crawl_with_progress, pages_from_dir},
+    diff::diff_llms_txt,
+    is_valid_markdown,
+    lint::{Diagnostic, Severity, find_llms_txt_files, lint_llms_txt},
+    llms::{
+        Detail, LlmProvider, TokenUsage, estimate_tokens, prompt_generate_llms_txt, prompt_regenerate_section,
+        prompt_update_llms_txt,
+    },
+    normalize_html, parse_duration,
+    remote::{API_KEY_ENV_VAR, remote_client},
+    repair::{repair_llms_txt, unified_diff},
+    section::section_content,
+    validate_is_llm_txt,
+    web_html::compute_html_checksum,
+};
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// Commands are listed in these groups (via `display_order`). clap has no native sections for subcommands.
+const COMMAND_GROUPS: &str = "\
+Command groups:
+  Generate:          generate, update, batch, crawl, generate-dir, watch
+  Check & repair:    validate, markdown, lint, diff, fix
+  Hosted service:    remote
+  Shell integration: completions";
 
 #[derive(Parser)]
 #[command(name = "core-llmstxt")]
 #[command(about = "The Core llms.txt Toolkit", long_about = None)]
+#[command(after_help = COMMAND_GROUPS)]
 struct CoreCli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Output format. `json` prints a structured report to stdout for the markdown, validate, generate,
+    /// update, diff, and lint commands. `markdown` only applies to diff; other commands treat it as `text`.
+    #[arg(long, global = true, default_value = "text")]
+    format: OutputFormat,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -30,22 +70,34 @@ impl LlmProviders {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "lowercase")]
+enum OutputFormat {
+    Text,
+    Json,
+    Markdown,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Parse and validate a file as markdown
+    #[command(display_order = 11)]
     Markdown {
-        /// The file to parse and validate.
+        /// The file to parse and validate. Use `-` to read from stdin.
         #[arg(short, long)]
         file: PathBuf,
     },
 
     /// Validate that a file is a valid llms.txt.
+    #[command(display_order = 10)]
     Validate {
+        /// The file to validate. Use `-` to read from stdin.
         #[arg(short, long)]
         file: PathBuf,
     },
 
     /// Generate a new llms.txt from a website
+    #[command(display_order = 1)]
     Generate {
         /// The website to generate an llms.txt file for.
         #[command(flatten)]
@@ -59,18 +111,27 @@ enum Commands {
         #[arg(short, long)]
         model: Option<String>,
 
-        /// Output file path for the generated llms.txt
+        /// How detailed the llms.txt is: brief (about 30 lines), standard, or comprehensive (every subpage).
+        #[arg(long, default_value = "standard")]
+        detail: Detail,
+
+        /// Output file path for the generated llms.txt. Use `-` to write to stdout.
         #[arg(short, long, value_parser = validate_output_file)]
         output: PathBuf,
+
+        /// Print the assembled prompt and its estimated token count instead of calling the LLM provider.
+        #[arg(long, visible_alias = "show-prompt")]
+        dry_run: bool,
     },
 
     /// Update an existing llms.txt
+    #[command(display_order = 2)]
     Update {
         /// The website to generate an updated llms.txt file for.
         #[command(flatten)]
         website: Website,
 
-        /// The prior existing llms.txt file.
+        /// The prior existing llms.txt file. Use `-` to read from stdin.
         #[arg(short, long, value_parser = validate_input_file)]
         llms_txt: PathBuf,
 
@@ -82,9 +143,245 @@ enum Commands {
         #[arg(short, long)]
         model: Option<String>,
 
-        /// Output file path for the updated llms.txt
+        /// How detailed the updated llms.txt is: brief (about 30 lines), standard, or comprehensive (every subpage).
+        #[arg(long, default_value = "standard")]
+        detail: Detail,
+
+        /// Regenerate only this H2 section of the llms.txt from the website (e.g. a docs page), keeping the rest as-is.
+        #[arg(long)]
+        section: Option<String>,
+
+        /// Output file path for the updated llms.txt. Use `-` to write to stdout.
         #[arg(short, long, value_parser = validate_output_file)]
         output: PathBuf,
+
+        /// Print the assembled prompt and its estimated token count instead of calling the LLM provider.
+        #[arg(long, visible_alias = "show-prompt")]
+        dry_run: bool,
+    },
+
+    /// Generate llms.txt files for many websites concurrently.
+    #[command(display_order = 3)]
+    Batch {
+        /// File listing one website URL per line. Blank lines and `#` comments are ignored.
+        #[arg(short, long, value_parser = validate_input_file)]
+        input: PathBuf,
+
+        /// Directory to write each generated llms.txt into. Created if it does not exist.
+        #[arg(short, long)]
+        output_dir: PathBuf,
+
+        /// Maximum number of websites processed at the same time.
+        #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+
+        /// The LLM provider to use for generation
+        #[arg(short, long)]
+        provider: LlmProviders,
+
+        /// The model to use for generation. Otherwise uses default for the provider.
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Don't show progress bars (e.g. in CI). Per-website results and the summary are still printed.
+        #[arg(short, long)]
+        quiet: bool,
+    },
+
+    /// Crawl a website and generate a single site-level llms.txt from all pages found.
+    #[command(display_order = 4)]
+    Crawl {
+        /// The website URL to start crawling from. Only links to the same host are followed.
+        #[arg(short, long)]
+        url: String,
+
+        /// How many links away from the starting page to follow.
+        #[arg(long, default_value_t = 2)]
+        max_depth: usize,
+
+        /// Maximum number of pages to download.
+        #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+        max_pages: u32,
+
+        /// The LLM provider to use for generation
+        #[arg(short, long)]
+        provider: LlmProviders,
+
+        /// The model to use for generation. Otherwise uses default for the provider.
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Output file path for the generated llms.txt
+        #[arg(short, long, value_parser = validate_output_file)]
+        output: PathBuf,
+
+        /// Don't show progress bars (e.g. in CI).
+        #[arg(short, long)]
+        quiet: bool,
+    },
+
+    /// Generate a single site-level llms.txt from a built static site on disk (e.g. `./public`).
+    #[command(display_order = 5)]
+    GenerateDir {
+        /// Root directory of the built site. Every `.html` file under it is a page.
+        #[arg(short, long)]
+        dir: PathBuf,
+
+        /// The URL the site is published at. Files map to URLs under it, e.g. `docs/index.html` -> `<base-url>/docs/`.
+        #[arg(short, long)]
+        base_url: String,
+
+        /// Maximum number of pages to read, shallowest first.
+        #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u32).range(1..))]
+        max_pages: u32,
+
+        /// The LLM provider to use for generation
+        #[arg(short, long)]
+        provider: LlmProviders,
+
+        /// The model to use for generation. Otherwise uses default for the provider.
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Output file path for the generated llms.txt. Use `-` to write to stdout.
+        #[arg(short, long, value_parser = validate_output_file)]
+        output: PathBuf,
+    },
+
+    /// Compare two llms.txt files by title, summary, sections, and links.
+    /// Exits with a non-zero status when they differ. Use `--format` to choose text, json, or markdown output.
+    #[command(display_order = 13)]
+    Diff {
+        /// The original llms.txt file.
+        #[arg(value_parser = validate_input_file)]
+        old: PathBuf,
+
+        /// The llms.txt file to compare against the original.
+        #[arg(value_parser = validate_input_file)]
+        new: PathBuf,
+    },
+
+    /// Periodically re-check a website (or a local HTML file) and regenerate the llms.txt only when its content changes.
+    #[command(display_order = 6)]
+    Watch {
+        /// The website, or local HTML build output, to watch.
+        #[command(flatten)]
+        website: Website,
+
+        /// The LLM provider to use for generation
+        #[arg(short, long)]
+        provider: LlmProviders,
+
+        /// The model to use for generation. Otherwise uses default for the provider.
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Output file path for the llms.txt. If the file already exists, it's updated instead of regenerated.
+        #[arg(short, long, value_parser = validate_output_file)]
+        output: PathBuf,
+
+        /// How long to wait between checks, e.g. `30s`, `10m`, or `1h`.
+        #[arg(short, long, default_value = "10m", value_parser = parse_duration)]
+        interval: Duration,
+    },
+
+    /// Repair an almost-valid llms.txt and print a unified diff of what changed.
+    /// Deterministic fixes are applied first. If the file is still invalid and a provider is given,
+    /// the LLM gets a single attempt to repair it.
+    #[command(display_order = 14)]
+    Fix {
+        /// The llms.txt file to repair. Use `-` to read from stdin.
+        #[arg(value_parser = validate_input_file)]
+        file: PathBuf,
+
+        /// Where to write the repaired llms.txt. Defaults to overwriting the input file (stdout if it's `-`).
+        #[arg(short, long, value_parser = validate_output_file)]
+        output: Option<PathBuf>,
+
+        /// The LLM provider to use if deterministic fixes aren't enough. Otherwise, the LLM is never called.
+        #[arg(short, long)]
+        provider: Option<LlmProviders>,
+
+        /// The model to use for repair. Otherwise uses default for the provider.
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+
+    /// Talk to a running api-ltx server: submit jobs, check their status, and fetch results.
+    #[command(display_order = 20)]
+    Remote {
+        /// Base URL of the api-ltx server, e.g. `https://localhost:3000`.
+        #[arg(short, long)]
+        server: String,
+
+        /// API key to authenticate with. Otherwise read from the LLMSTXT_API_KEY environment variable.
+        #[arg(long)]
+        api_key: Option<String>,
+
+        /// Accept invalid (e.g. self-signed) TLS certificates. Only use for local development!
+        #[arg(long)]
+        insecure: bool,
+
+        #[command(subcommand)]
+        action: RemoteAction,
+    },
+
+    /// Lint an llms.txt file, or every llms.txt file under a directory, reporting all problems found.
+    /// Exits with status 0 when clean, 1 when there are only warnings, and 2 when there are errors.
+    #[command(display_order = 12)]
+    Lint {
+        /// A file, or a directory to search recursively for `llms.txt`, `llms-full.txt`, and `*.llms.txt` files.
+        path: PathBuf,
+    },
+
+    /// Print a shell completion script, e.g. `core-llmstxt completions bash > /etc/bash_completion.d/core-llmstxt`.
+    #[command(display_order = 30)]
+    Completions {
+        /// The shell to generate completions for.
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand)]
+enum RemoteAction {
+    /// Submit a job to generate an llms.txt for a website. Prints the job ID.
+    Submit {
+        /// The website URL.
+        #[arg(short, long)]
+        url: String,
+
+        /// How detailed the llms.txt is: brief, standard, or comprehensive. The server's default when absent.
+        #[arg(long)]
+        detail: Option<Detail>,
+    },
+
+    /// Get the status of a job.
+    Status {
+        /// The job ID returned by `submit`.
+        #[arg(short, long)]
+        job_id: Uuid,
+
+        /// Keep polling until the job has finished.
+        #[arg(short, long)]
+        wait: bool,
+    },
+
+    /// Get the most recent llms.txt for a website.
+    Get {
+        /// The website URL.
+        #[arg(short, long)]
+        url: String,
+
+        /// Output file path for the llms.txt. Use `-` (the default) to write to stdout.
+        #[arg(short, long, default_value = STDIO, value_parser = validate_output_file)]
+        output: PathBuf,
+    },
+
+    /// List every website with an llms.txt.
+    List {
+        /// With `--format json`, include every website's whole llms.txt, not only its preview.
+        #[arg(long)]
+        full: bool,
     },
 }
 
@@ -94,14 +391,46 @@ struct Website {
     /// The website URL to download and generate an llms.txt for.
     #[arg(short, long, group = "website")]
     url: Option<String>,
-    /// The local filepath of HTML of a pre-downloaded webpage to generate an llms.txt for.
+    /// The local filepath of HTML of a pre-downloaded webpage to generate an llms.txt for. Use `-` to read from stdin.
     #[arg(short, long, group = "website")]
     file: Option<PathBuf>,
 }
 
+/// The file name that means stdin when reading or stdout when writing.
+const STDIO: &str = "-";
+
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == STDIO
+}
+
+/// Reads the whole file, or all of stdin if the path is `-`.
+fn read_input(path: &Path) -> std::io::Result<String> {
+    if is_stdio(path) {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Writes the content to the file, or to stdout if the path is `-`.
+fn write_output(path: &Path, content: &str) -> std::io::Result<()> {
+    if is_stdio(path) {
+        use std::io::Write;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        stdout.flush()
+    } else {
+        std::fs::write(path, content)
+    }
+}
+
 fn validate_input_file(s: &str) -> Result<PathBuf, String> {
     let path = PathBuf::from(s);
 
+    if is_stdio(&path) {
+        return Ok(path);
+    }
+
     if !path.exists() {
         return Err(format!("Input path does not exist: {}", path.display()));
     }
@@ -142,6 +471,54 @@ fn validate_output_file(s: &str) -> Result<PathBuf, String> {
     Ok(path)
 }
 
+/// Structured result of a command, printed as JSON with `--format json`.
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    command: &'static str,
+    /// Either "ok" or "error".
+    status: &'static str,
+    /// Why the command failed. Empty on success.
+    diagnostics: Vec<String>,
+    /// The file written by the command, if any.
+    output: Option<&'a Path>,
+    /// Tokens used by the LLM provider, for commands that call one.
+    token_usage: Option<TokenUsage>,
+}
+
+impl<'a> Report<'a> {
+    fn new<T, E: std::fmt::Display>(command: &'static str, result: &Result<T, E>) -> Self {
+        let (status, diagnostics) = match result {
+            Ok(_) => ("ok", Vec::new()),
+            Err(e) => ("error", vec![e.to_string()]),
+        };
+        Self {
+            command,
+            status,
+            diagnostics,
+            output: None,
+            token_usage: None,
+        }
+    }
+
+    fn print(&self) -> Result<(), MainError> {
+        print_json(self)
+    }
+}
+
+/// Prints the value to stdout as pretty JSON.
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), MainError> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| MainError(format!("Cannot serialize as JSON: {e}")))?;
+    println!("{json}");
+    Ok(())
+}
+
+/// Lint diagnostics for one file, printed as JSON with `--format json`.
+#[derive(Debug, Serialize)]
+struct LintedFile<'a> {
+    file: &'a Path,
+    diagnostics: Vec<Diagnostic>,
+}
+
 struct MainError(String);
 
 impl Debug for MainError {
@@ -164,6 +541,12 @@ impl From<core_ltx::Error> for MainError {
     }
 }
 
+impl From<client_ltx::ClientError> for MainError {
+    fn from(e: client_ltx::ClientError) -> Self {
+        Self(e.to_string())
+    }
+}
+
 impl From<std::io::Error> for MainError {
     fn from(e: std::io::Error) -> Self {
         Self(e.to_string())
@@ -175,24 +558,44 @@ async fn main() -> Result<(), MainError> {
     let cli = CoreCli::parse();
 
     match &cli.command {
-        Commands::Markdown { file } => match std::fs::read_to_string(file) {
-            Ok(content) => match is_valid_markdown(&content) {
-                Ok(_doc) => println!("Valid markdown file: {file:?}"),
-                Err(e) => println!("[ERROR] Invalid markdown file ({file:?}):\n{e:?}"),
-            },
+        Commands::Completions { shell } => {
+            clap_complete::generate(*shell, &mut CoreCli::command(), "core-llmstxt", &mut std::io::stdout());
+        }
+
+        Commands::Markdown { file } => match read_input(file) {
+            Ok(content) => {
+                let result = is_valid_markdown(&content);
+                if cli.format == OutputFormat::Json {
+                    Report::new("markdown", &result).print()?;
+                } else {
+                    match result {
+                        Ok(_doc) => println!("Valid markdown file: {file:?}"),
+                        Err(e) => println!("[ERROR] Invalid markdown file ({file:?}):\n{e:?}"),
+                    }
+                }
+            }
             Err(e) => return Err(MainError(format!("Cannot read file ({file:?}) due to: {e:?}"))),
         },
 
-        Commands::Validate { file } => match std::fs::read_to_string(file) {
-            Ok(content) => match is_valid_markdown(&content) {
-                Ok(doc) => match validate_is_llm_txt(doc) {
-                    Ok(_llms_txt) => println!("Valid llms.txt file: {file:?}"),
-                    Err(e) => println!("[ERROR] Invalid llms.txt file ({file:?}): {e:?}"),
-                },
-                Err(e) => {
-                    println!("[ERROR] Invalid llms.txt file because it's an invalid markdown file ({file:?}):\n{e:?}")
+        Commands::Validate { file } => match read_input(file) {
+            Ok(content) => {
+                if cli.format == OutputFormat::Json {
+                    let result = is_valid_markdown(&content).and_then(validate_is_llm_txt);
+                    Report::new("validate", &result).print()?;
+                } else {
+                    match is_valid_markdown(&content) {
+                        Ok(doc) => match validate_is_llm_txt(doc) {
+                            Ok(_llms_txt) => println!("Valid llms.txt file: {file:?}"),
+                            Err(e) => println!("[ERROR] Invalid llms.txt file ({file:?}): {e:?}"),
+                        },
+                        Err(e) => {
+                            println!(
+                                "[ERROR] Invalid llms.txt file because it's an invalid markdown file ({file:?}):\n{e:?}"
+                            )
+                        }
+                    }
                 }
-            },
+            }
             Err(e) => {
                 return Err(MainError(format!("Cannot read file ({file:?}) due to: {e:?}")));
             }
@@ -202,13 +605,23 @@ async fn main() -> Result<(), MainError> {
             website,
             provider,
             model,
+            detail,
             output,
+            dry_run,
         } => {
-            let html = website_content(website).await?;
+            if *dry_run {
+                let html = website_content(website).await?;
+                return show_prompt(cli.format, "generate", &prompt_generate_llms_txt(&html, *detail)?);
+            }
             let llm_provider = provider.provider(model);
-            let llms_txt = core_ltx::llms::generate_llms_txt(&*llm_provider, &html).await?;
-            let as_markdown = llms_txt.md_content();
-            std::fs::write(output, &as_markdown)?;
+            let result = async {
+                let html = website_content(website).await?;
+                let llms_txt = core_ltx::llms::generate_llms_txt(&*llm_provider, &html, *detail).await?;
+                write_output(output, &llms_txt.md_content())?;
+                Ok::<(), MainError>(())
+            }
+            .await;
+            report_llm_command(cli.format, "generate", result, output, &*llm_provider)?;
         }
 
         Commands::Update {
@@ -216,22 +629,496 @@ async fn main() -> Result<(), MainError> {
             llms_txt,
             provider,
             model,
+            detail,
+            section,
             output,
+            dry_run,
         } => {
-            let html = website_content(website).await?;
-            let llms_txt_content = std::fs::read_to_string(llms_txt)?;
+            if is_stdio(llms_txt) && website.file.as_deref().is_some_and(is_stdio) {
+                return Err(MainError(
+                    "Only one of --llms-txt and --file can be read from stdin ('-')".to_string(),
+                ));
+            }
+            if *dry_run {
+                let html = website_content(website).await?;
+                let llms_txt_content = read_input(llms_txt)?;
+                let prompt = match section {
+                    Some(section) => {
+                        let existing = validate_is_llm_txt(is_valid_markdown(&llms_txt_content)?)?;
+                        let current_section = section_content(&existing, section)?;
+                        prompt_regenerate_section(&llms_txt_content, section, &current_section, &html, *detail)?
+                    }
+                    None => prompt_update_llms_txt(&llms_txt_content, &html, *detail)?,
+                };
+                return show_prompt(cli.format, "update", &prompt);
+            }
             let llm_provider = provider.provider(model);
-            let updated_llms_txt = core_ltx::llms::update_llms_txt(&*llm_provider, &llms_txt_content, &html).await?;
-            let as_markdown = updated_llms_txt.md_content();
-            std::fs::write(output, &as_markdown)?;
+            let result = async {
+                let html = website_content(website).await?;
+                let llms_txt_content = read_input(llms_txt)?;
+                let updated_llms_txt = match section {
+                    Some(section) => {
+                        core_ltx::llms::regenerate_section(&*llm_provider, &llms_txt_content, section, &html, *detail)
+                            .await?
+                    }
+                    None => core_ltx::llms::update_llms_txt(&*llm_provider, &llms_txt_content, &html, *detail).await?,
+                };
+                write_output(output, &updated_llms_txt.md_content())?;
+                Ok::<(), MainError>(())
+            }
+            .await;
+            report_llm_command(cli.format, "update", result, output, &*llm_provider)?;
+        }
+
+        Commands::Batch {
+            input,
+            output_dir,
+            concurrency,
+            provider,
+            model,
+            quiet,
+        } => {
+            let urls = parse_url_list(&read_input(input)?);
+            std::fs::create_dir_all(output_dir)?;
+            let llm_provider: Arc<dyn LlmProvider> = Arc::from(provider.provider(model));
+            let semaphore = Arc::new(Semaphore::new(*concurrency as usize));
+
+            let progress = if *quiet {
+                MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+            } else {
+                MultiProgress::new()
+            };
+            let overall = progress.add(ProgressBar::new(urls.len() as u64).with_style(overall_progress_style()));
+            // progress bars swallow plain println! output while they're drawn
+            let print_line = |line: String| {
+                if *quiet {
+                    println!("{line}");
+                } else {
+                    let _ = progress.println(line);
+                }
+            };
+
+            let mut tasks = JoinSet::new();
+            for url in urls.iter().cloned() {
+                let llm_provider = llm_provider.clone();
+                let semaphore = semaphore.clone();
+                let output_dir = output_dir.clone();
+                let progress = progress.clone();
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    let spinner = progress.add(new_spinner(false));
+                    let result = batch_generate_one(llm_provider.as_ref(), &url, &output_dir, &spinner).await;
+                    spinner.finish_and_clear();
+                    progress.remove(&spinner);
+                    (url, result)
+                });
+            }
+
+            let mut failed = 0;
+            while let Some(joined) = tasks.join_next().await {
+                let (url, result) = joined.map_err(|e| MainError(format!("Batch task failed to complete: {e}")))?;
+                match result {
+                    Ok(path) => print_line(format!("[OK] {url} -> {}", path.display())),
+                    Err(e) => {
+                        failed += 1;
+                        print_line(format!("[ERROR] {url}: {e}"));
+                    }
+                }
+                overall.inc(1);
+                overall.set_message(format!("{} tokens used", llm_provider.token_usage().total_tokens));
+            }
+            overall.finish_and_clear();
+
+            println!(
+                "Batch complete: {} succeeded, {} failed ({} total)",
+                urls.len() - failed,
+                failed,
+                urls.len()
+            );
+            if failed > 0 {
+                return Err(MainError(format!("{failed} of {} websites failed", urls.len())));
+            }
+        }
+
+        Commands::Crawl {
+            url,
+            max_depth,
+            max_pages,
+            provider,
+            model,
+            output,
+            quiet,
+        } => {
+            let start = core_ltx::is_valid_url(url)?;
+            let options = CrawlOptions {
+                max_depth: *max_depth,
+                max_pages: *max_pages as usize,
+            };
+            let bar = if *quiet {
+                ProgressBar::hidden()
+            } else {
+                ProgressBar::new(*max_pages as u64).with_style(overall_progress_style())
+            };
+            let pages = crawl_with_progress(&start, options, |page| {
+                bar.inc(1);
+                bar.set_message(page.url.to_string());
+            })
+            .await?;
+            bar.finish_and_clear();
+            println!("Crawled {} page(s) from {}", pages.len(), start);
+
+            let llm_provider = provider.provider(model);
+            let spinner = new_spinner(*quiet);
+            spinner.set_message("Generating llms.txt");
+            let llms_txt =
+                core_ltx::llms::generate_llms_txt(&*llm_provider, &combine_pages(&pages), Detail::Standard).await;
+            spinner.finish_and_clear();
+            std::fs::write(output, llms_txt?.md_content())?;
+            println!(
+                "[OK] Wrote {} ({} tokens used)",
+                output.display(),
+                llm_provider.token_usage().total_tokens
+            );
+        }
+
+        Commands::GenerateDir {
+            dir,
+            base_url,
+            max_pages,
+            provider,
+            model,
+            output,
+        } => {
+            if !dir.is_dir() {
+                return Err(MainError(format!("Not a directory: {}", dir.display())));
+            }
+            let base_url = core_ltx::is_valid_url(base_url)?;
+            let pages = pages_from_dir(dir, &base_url, *max_pages as usize)?;
+            if pages.is_empty() {
+                return Err(MainError(format!("No HTML files found under {}", dir.display())));
+            }
+            eprintln!("Read {} page(s) from {}", pages.len(), dir.display());
+
+            let llm_provider = provider.provider(model);
+            let llms_txt =
+                core_ltx::llms::generate_llms_txt(&*llm_provider, &combine_pages(&pages), Detail::Standard).await?;
+            write_output(output, &llms_txt.md_content())?;
+        }
+
+        Commands::Diff { old, new } => {
+            let diff = diff_llms_txt(&read_input(old)?, &read_input(new)?);
+            match cli.format {
+                OutputFormat::Text => println!("{}", diff.to_text()),
+                OutputFormat::Markdown => println!("{}", diff.to_markdown()),
+                OutputFormat::Json => print_json(&diff)?,
+            }
+            if !diff.is_empty() {
+                return Err(MainError(format!(
+                    "{} difference(s) between {} and {}",
+                    diff.changes.len(),
+                    old.display(),
+                    new.display()
+                )));
+            }
+        }
+
+        Commands::Watch {
+            website,
+            provider,
+            model,
+            output,
+            interval,
+        } => {
+            let llm_provider = provider.provider(model);
+            let mut last_checksum: Option<String> = None;
+            loop {
+                match watch_check(website, &*llm_provider, output, last_checksum.as_deref()).await {
+                    Ok(Some(checksum)) => {
+                        println!(
+                            "[OK] Content changed (checksum: {checksum}): wrote {}",
+                            output.display()
+                        );
+                        last_checksum = Some(checksum);
+                    }
+                    Ok(None) => println!("No content changes, next check in {interval:?}"),
+                    Err(e) => println!("[ERROR] {e}"),
+                }
+                tokio::time::sleep(*interval).await;
+            }
+        }
+
+        Commands::Fix {
+            file,
+            output,
+            provider,
+            model,
+        } => {
+            let output = output.as_ref().unwrap_or(file);
+            // when the repaired file goes to stdout, keep the report out of it
+            let report = |line: &str| {
+                if is_stdio(output) {
+                    eprintln!("{line}");
+                } else {
+                    println!("{line}");
+                }
+            };
+
+            let original = read_input(file)?;
+            let repaired = repair_llms_txt(&original);
+            for fix in repaired.fixes.iter() {
+                report(&format!("[FIXED] {fix}"));
+            }
+
+            let (content, still_invalid) = match is_valid_markdown(&repaired.content).and_then(validate_is_llm_txt) {
+                Ok(_) => (repaired.content, None),
+                Err(e) => match provider {
+                    Some(provider) => {
+                        report(&format!(
+                            "Still invalid after automatic fixes ({e}), asking the LLM to repair it"
+                        ));
+                        let llm_provider = provider.provider(model);
+                        let fixed = core_ltx::llms::fix_llms_txt(&*llm_provider, &repaired.content, &e).await?;
+                        (fixed.md_content(), None)
+                    }
+                    None => (repaired.content, Some(e)),
+                },
+            };
+
+            let name = file.display().to_string();
+            let diff = unified_diff(&original, &content, &format!("a/{name}"), &format!("b/{name}"));
+            if diff.is_empty() {
+                report("No changes needed.");
+            } else {
+                report(diff.trim_end());
+            }
+            if !diff.is_empty() || is_stdio(output) {
+                write_output(output, &content)?;
+            }
+
+            if let Some(e) = still_invalid {
+                return Err(MainError(format!(
+                    "Still not a valid llms.txt after automatic fixes: {e}. Use --provider to let an LLM repair it."
+                )));
+            }
+        }
+
+        Commands::Remote {
+            server,
+            api_key,
+            insecure,
+            action,
+        } => {
+            let server = core_ltx::is_valid_url(server)?;
+            let api_key = api_key.clone().or_else(|| std::env::var(API_KEY_ENV_VAR).ok());
+            let client = remote_client(server.as_str(), api_key, *insecure)
+                .map_err(|e| MainError(format!("Failed to build HTTP client: {e}")))?;
+            remote(&client, action, cli.format).await?;
+        }
+
+        Commands::Lint { path } => {
+            let files = find_llms_txt_files(path)?;
+            let mut results: Vec<LintedFile> = Vec::with_capacity(files.len());
+            for file in files.iter() {
+                let content = std::fs::read_to_string(file)?;
+                let diagnostics = lint_llms_txt(&content);
+                if cli.format != OutputFormat::Json {
+                    for diagnostic in diagnostics.iter() {
+                        println!("{}\n", diagnostic.render(file, &content));
+                    }
+                }
+                results.push(LintedFile { file, diagnostics });
+            }
+
+            let count = |severity: Severity| {
+                results
+                    .iter()
+                    .flat_map(|r| r.diagnostics.iter())
+                    .filter(|d| d.severity == severity)
+                    .count()
+            };
+            let (errors, warnings) = (count(Severity::Error), count(Severity::Warning));
+
+            if cli.format == OutputFormat::Json {
+                print_json(&results)?;
+            } else {
+                println!(
+                    "Linted {} file(s): {} error(s), {} warning(s)",
+                    results.len(),
+                    errors,
+                    warnings
+                );
+            }
+
+            if errors > 0 {
+                std::process::exit(2);
+            } else if warnings > 0 {
+                std::process::exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the prompt that would be sent to the LLM provider, along with its size.
+fn show_prompt(format: OutputFormat, command: &'static str, prompt: &str) -> Result<(), MainError> {
+    let characters = prompt.chars().count();
+    let estimated_tokens = estimate_tokens(prompt);
+    if format == OutputFormat::Json {
+        print_json(&serde_json::json!({
+            "command": command,
+            "prompt": prompt,
+            "characters": characters,
+            "estimated_tokens": estimated_tokens,
+        }))
+    } else {
+        println!("{prompt}");
+        println!("[DRY RUN] Prompt is {characters} characters, ~{estimated_tokens} tokens (estimated)");
+        Ok(())
+    }
+}
+
+/// Prints the outcome of a command that calls an LLM provider: a JSON report with `--format json`,
+/// otherwise nothing extra. The command's error, if any, is passed through.
+fn report_llm_command(
+    format: OutputFormat,
+    command: &'static str,
+    result: Result<(), MainError>,
+    output: &Path,
+    provider: &dyn LlmProvider,
+) -> Result<(), MainError> {
+    if format == OutputFormat::Json {
+        let mut report = Report::new(command, &result);
+        if result.is_ok() {
+            report.output = Some(output);
+        }
+        report.token_usage = Some(provider.token_usage());
+        report.print()?;
+    }
+    result
+}
+
+/// Downloads one website, generates its llms.txt, and writes it into the output directory.
+/// Returns the path of the written file.
+/// Progress is reported by updating the spinner's message.
+async fn batch_generate_one(
+    provider: &dyn LlmProvider,
+    url: &str,
+    output_dir: &Path,
+    spinner: &ProgressBar,
+) -> Result<PathBuf, core_ltx::Error> {
+    let validated_url = core_ltx::is_valid_url(url)?;
+    spinner.set_message(format!("Downloading {url}"));
+    let html = core_ltx::download(&validated_url).await?;
+    spinner.set_message(format!("Generating {url}"));
+    let llms_txt = core_ltx::llms::generate_llms_txt(provider, &html, Detail::Standard).await?;
+    let path = output_dir.join(output_file_name(&validated_url));
+    std::fs::write(&path, llms_txt.md_content())?;
+    Ok(path)
+}
+
+/// Progress bar for an operation over many items: elapsed time, count, ETA, and a status message.
+fn overall_progress_style() -> ProgressStyle {
+    ProgressStyle::with_template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} (ETA {eta}) {msg}")
+        .expect("progress bar template is valid")
+}
+
+/// A spinner showing the state of a single in-flight item. Hidden when `quiet`.
+fn new_spinner(quiet: bool) -> ProgressBar {
+    if quiet {
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner()
+        .with_style(ProgressStyle::with_template("  {spinner} {msg}").expect("spinner template is valid"));
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    spinner
+}
+
+/// Runs a `remote` subcommand against the api-ltx server.
+async fn remote(client: &ApiClient, action: &RemoteAction, format: OutputFormat) -> Result<(), MainError> {
+    let json = format == OutputFormat::Json;
+
+    match action {
+        RemoteAction::Submit { url, detail } => {
+            let request = JobRequestPayload {
+                detail: *detail,
+                ..JobRequestPayload::new(url.as_str())
+            };
+            let job = client.post_llm_txt(&request).await?;
+            if json {
+                print_json(&job)?;
+            } else {
+                println!("[OK] Submitted job {} for {}", job.job_id, url);
+            }
+        }
+
+        RemoteAction::Status { job_id, wait } => {
+            let job = loop {
+                let job = client.job(*job_id).await?;
+                if !*wait || job.status.is_completed() {
+                    break job;
+                }
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            };
+            if json {
+                print_json(&job)?;
+            } else {
+                println!("{} {:?} ({:?}) {}", job.job_id, job.status, job.kind, job.url);
+                if let Some(error_message) = &job.error_message {
+                    println!("[ERROR] {error_message}");
+                }
+            }
+        }
+
+        RemoteAction::Get { url, output } => {
+            let content = client.get_llm_txt(url).await?;
+            if json {
+                print_json(&serde_json::json!({ "url": url, "content": content }))?;
+            } else {
+                write_output(output, &content)?;
+            }
+        }
+
+        RemoteAction::List { full } => {
+            let items = client.list(*full).await?;
+            if json {
+                print_json(&items)?;
+            } else {
+                for item in items.iter() {
+                    println!("{}", item.url);
+                }
+            }
         }
     }
     Ok(())
 }
 
+/// Regenerates the llms.txt if the website's normalized content no longer matches `last_checksum`.
+/// Returns the new checksum if the llms.txt was written, or `None` if the content is unchanged.
+async fn watch_check(
+    website: &Website,
+    provider: &dyn LlmProvider,
+    output: &Path,
+    last_checksum: Option<&str>,
+) -> Result<Option<String>, MainError> {
+    let html = website_content(website).await?;
+    let checksum = compute_html_checksum(&normalize_html(&html)?)?;
+    if last_checksum == Some(checksum.as_str()) {
+        return Ok(None);
+    }
+
+    let llms_txt = if !is_stdio(output) && output.is_file() {
+        let existing = std::fs::read_to_string(output)?;
+        core_ltx::llms::update_llms_txt(provider, &existing, &html, Detail::Standard).await?
+    } else {
+        core_ltx::llms::generate_llms_txt(provider, &html, Detail::Standard).await?
+    };
+    write_output(output, &llms_txt.md_content())?;
+    Ok(Some(checksum))
+}
+
 async fn website_content(website: &Website) -> Result<String, MainError> {
     if let Some(file) = &website.file {
-        let content = std::fs::read_to_string(file)?;
+        let content = read_input(file)?;
         Ok(content)
     } else if let Some(url) = &website.url {
         let validated_url = core_ltx::is_valid_url(url.as_str())?;
@@ -241,3 +1128,13 @@ async fn website_content(website: &Website) -> Result<String, MainError> {
         unreachable!("Clap should enforce that exactly one option is provided")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        CoreCli::command().debug_assert();
+    }
+}
@@ -3,12 +3,35 @@ use markdown_ppp::parser::{MarkdownParserState, parse_markdown};
 use markdown_ppp::printer::{config::Config, render_markdown};
 
 use crate::Error;
+use crate::parse_limits::{PARSE_LIMITS, ParseLimits, markdown_nesting_depth};
 
 /// A markdown document, represented as an abstract syntax tree (AST) of markdown blocks.
 pub type Markdown = ast::Document;
 
 /// Parses the text as markdown, returning a Markdown AST. Otherwise, produces an error explaining why the text isn't valid markdown.
+///
+/// Text past the `PARSE_LIMITS` (too big or too deeply nested) isn't parsed at all.
 pub fn is_valid_markdown(content: &str) -> Result<Markdown, Error> {
+    is_valid_markdown_with_limits(content, &PARSE_LIMITS)
+}
+
+/// `is_valid_markdown`, with these limits.
+pub fn is_valid_markdown_with_limits(content: &str, limits: &ParseLimits) -> Result<Markdown, Error> {
+    if content.len() > limits.markdown_max_bytes {
+        return Err(Error::MarkdownTooComplex(format!(
+            "{} bytes, more than the limit of {}",
+            content.len(),
+            limits.markdown_max_bytes
+        )));
+    }
+    let depth = markdown_nesting_depth(content);
+    if depth > limits.markdown_max_depth {
+        return Err(Error::MarkdownTooComplex(format!(
+            "nested {} deep, more than the limit of {}",
+            depth, limits.markdown_max_depth
+        )));
+    }
+
     match parse_markdown(MarkdownParserState::default(), content) {
         Err(error) => Err(Error::InvalidMarkdown(error.to_owned())),
         Ok(document) => Ok(document),
@@ -547,4 +570,115 @@ mod tests {
             .is_err()
         );
     }
+
+    #[test]
+    fn markdown_limits() {
+        let limits = ParseLimits {
+            markdown_max_bytes: 64,
+            markdown_max_depth: 8,
+            ..ParseLimits::default()
+        };
+        assert!(is_valid_markdown_with_limits("# Title\n> Summary\n", &limits).is_ok());
+        assert!(matches!(
+            is_valid_markdown_with_limits(&"a".repeat(65), &limits),
+            Err(Error::MarkdownTooComplex(_))
+        ));
+        assert!(matches!(
+            is_valid_markdown_with_limits(&">".repeat(9), &limits),
+            Err(Error::MarkdownTooComplex(_))
+        ));
+        assert!(matches!(
+            is_valid_markdown_with_limits(&format!("{}x{}", "[".repeat(9), "]".repeat(9)), &limits),
+            Err(Error::MarkdownTooComplex(_))
+        ));
+
+        // deep enough to overflow the stack of the recursive parser
+        assert!(matches!(
+            is_valid_markdown(&"> ".repeat(100_000)),
+            Err(Error::MarkdownTooComplex(_))
+        ));
+        assert!(matches!(
+            is_valid_markdown(&"[".repeat(100_000)),
+            Err(Error::MarkdownTooComplex(_))
+        ));
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        /// Whatever the markdown, parsing and validating it return instead of panicking.
+        #[test]
+        fn fuzz_markdown(
+            content in r"(#{1,7} |> ?|[-*+] |[0-9]{1,3}[.)] |```|\[|\]|\(|\)|\*{1,3}|_{1,2}|`|<|&[a-z]{0,6};?|https?://[a-z.]{1,12}|\n|  |[a-zA-Z0-9 :.,]{0,12}){0,64}"
+        ) {
+            if let Ok(markdown) = is_valid_markdown(&content) {
+                let _ = validate_is_llm_txt(markdown).map(|llms_txt| llms_txt.md_content());
+            }
+        }
+
+        #[test]
+        fn fuzz_arbitrary_text(content in proptest::prelude::any::<String>()) {
+            let _ = is_valid_markdown(&content);
+        }
+
+        /// Generated llms.txt files are valid, and render to text that parses back to the same llms.txt.
+        #[test]
+        fn generated_llms_txt_round_trips((content, urls) in llms_txt_strategy()) {
+            let llms_txt = validate_is_llm_txt(is_valid_markdown(&content).unwrap());
+            proptest::prop_assert!(llms_txt.is_ok(), "{:?}\n---\n{}", llms_txt.err(), content);
+            let rendered = llms_txt.unwrap().md_content();
+            for url in urls {
+                proptest::prop_assert!(rendered.contains(&url), "{} is missing\n---\n{}", url, rendered);
+            }
+            let round_trip = crate::round_trip::check_round_trip(&content);
+            proptest::prop_assert!(round_trip.is_ok(), "{}", round_trip.unwrap_err());
+        }
+    }
+
+    /// A valid llms.txt (a title, a summary, optional details, and sections of links), with its links' URLs.
+    fn llms_txt_strategy() -> impl proptest::strategy::Strategy<Value = (String, Vec<String>)> {
+        use proptest::collection::vec;
+        use proptest::prelude::*;
+
+        let text = "[A-Za-z][A-Za-z0-9 ]{0,24}[A-Za-z0-9]";
+        let link = (
+            text,
+            "https://[a-z]{1,10}\\.com(/[a-z0-9-]{1,8}){0,3}",
+            proptest::option::of(text),
+        );
+        let section = (text, vec(link, 1..6));
+        let details = vec("[A-Za-z][A-Za-z0-9 ,]{0,60}[A-Za-z0-9.]", 0..3);
+        (text, text, details, vec(section, 0..4)).prop_map(|(title, summary, details, sections)| {
+            let mut blocks = vec![format!("# {}", title), format!("> {}", summary)];
+            blocks.extend(details);
+            let mut urls = Vec::new();
+            for (name, links) in sections {
+                let items: Vec<String> = links
+                    .into_iter()
+                    .map(|(name, url, description)| {
+                        let item = match description {
+                            Some(description) => format!("- [{}]({}): {}", name, url, description),
+                            None => format!("- [{}]({})", name, url),
+                        };
+                        urls.push(url);
+                        item
+                    })
+                    .collect();
+                blocks.push(format!("## {}\n\n{}", name, items.join("\n")));
+            }
+            (blocks.join("\n\n") + "\n", urls)
+        })
+    }
+
+    #[test]
+    fn round_trip_of_examples() {
+        for content in [
+            "# Example\n\n> The example website.\n\n## Docs\n\n- [Guide](https://example.com/guide): How to start\n",
+            "# Example\n\n> Summary\n\nSome details.\n\n- a list\n- of details\n",
+            // not an llms.txt: nothing to check
+            "no title",
+        ] {
+            assert_eq!(crate::round_trip::check_round_trip(content), Ok(()), "{}", content);
+        }
+    }
 }
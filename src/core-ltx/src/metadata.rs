@@ -0,0 +1,185 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+use url::Url;
+
+/// Longest title or description kept. Some pages stuff their meta description with keywords.
+const MAX_TEXT_CHARS: usize = 300;
+
+/// What a page says about itself in its `<head>`, for showing a website as more than its URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SiteMetadata {
+    /// `<title>`, or `og:title` if there's none.
+    pub title: Option<String>,
+    /// `<meta name="description">`, or `og:description` if there's none.
+    pub description: Option<String>,
+    /// `<link rel="canonical">`, as an absolute URL.
+    pub canonical_url: Option<String>,
+    /// `<link rel="icon">` (or `shortcut icon`, `apple-touch-icon`) as an absolute URL.
+    /// Falls back to `/favicon.ico`, where browsers look when the page doesn't declare one.
+    pub favicon_url: Option<String>,
+}
+
+/// Extracts the page's title, description, canonical URL and favicon. Relative URLs are resolved against `page_url`.
+/// Whitespace in the title and description is collapsed, and they're cut to `MAX_TEXT_CHARS`.
+pub fn extract_site_metadata(html: &str, page_url: &Url) -> SiteMetadata {
+    let dom = match parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+    {
+        Ok(dom) => dom,
+        Err(_) => return SiteMetadata::default(),
+    };
+    let mut found = Found::default();
+    collect(&dom.document, &mut found);
+
+    let resolve = |href: String| page_url.join(href.trim()).ok().map(|url| url.to_string());
+    SiteMetadata {
+        title: found
+            .title
+            .and_then(clean_text)
+            .or_else(|| found.og_title.and_then(clean_text)),
+        description: found
+            .description
+            .and_then(clean_text)
+            .or_else(|| found.og_description.and_then(clean_text)),
+        canonical_url: found.canonical.and_then(resolve),
+        favicon_url: found
+            .icon
+            .and_then(resolve)
+            .or_else(|| page_url.join("/favicon.ico").ok().map(|url| url.to_string())),
+    }
+}
+
+/// First occurrence of everything that's looked for.
+#[derive(Default)]
+struct Found {
+    title: Option<String>,
+    og_title: Option<String>,
+    description: Option<String>,
+    og_description: Option<String>,
+    canonical: Option<String>,
+    icon: Option<String>,
+}
+
+fn collect(handle: &Handle, found: &mut Found) {
+    if let NodeData::Element { name, attrs, .. } = &handle.data {
+        let attrs = attrs.borrow();
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|a| &*a.name.local == key)
+                .map(|a| a.value.to_string())
+        };
+        match &*name.local {
+            "title" if found.title.is_none() => found.title = Some(text_content(handle)),
+            "meta" => {
+                let name = attr("name").or_else(|| attr("property")).unwrap_or_default();
+                let slot = match name.trim().to_ascii_lowercase().as_str() {
+                    "description" => Some(&mut found.description),
+                    "og:title" => Some(&mut found.og_title),
+                    "og:description" => Some(&mut found.og_description),
+                    _ => None,
+                };
+                if let Some(slot) = slot
+                    && slot.is_none()
+                {
+                    *slot = attr("content");
+                }
+            }
+            "link" => {
+                let rel = attr("rel").unwrap_or_default().to_ascii_lowercase();
+                let rels: Vec<&str> = rel.split_ascii_whitespace().collect();
+                let slot = if rels.contains(&"canonical") {
+                    Some(&mut found.canonical)
+                } else if rels.contains(&"icon") || rels.contains(&"apple-touch-icon") {
+                    Some(&mut found.icon)
+                } else {
+                    None
+                };
+                if let Some(slot) = slot
+                    && slot.is_none()
+                {
+                    *slot = attr("href").filter(|href| !href.trim().is_empty());
+                }
+            }
+            _ => {}
+        }
+    }
+    for child in handle.children.borrow().iter() {
+        collect(child, found);
+    }
+}
+
+fn text_content(handle: &Handle) -> String {
+    let mut text = String::new();
+    if let NodeData::Text { contents } = &handle.data {
+        text.push_str(&contents.borrow());
+    }
+    for child in handle.children.borrow().iter() {
+        text.push_str(&text_content(child));
+    }
+    text
+}
+
+/// None for text that's only whitespace.
+fn clean_text(text: String) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_site_metadata() {
+        let html = r#"<html><head>
+            <title>
+                Example   Docs
+            </title>
+            <meta name="Description" content="Guides and API reference.">
+            <meta property="og:title" content="Not used">
+            <link rel="canonical" href="/docs/">
+            <link rel="shortcut icon" href="static/icon.png">
+        </head><body><title>ignored</title></body></html>"#;
+        let page_url = Url::parse("https://example.com/docs/index.html?ref=x").unwrap();
+        assert_eq!(
+            extract_site_metadata(html, &page_url),
+            SiteMetadata {
+                title: Some("Example Docs".to_string()),
+                description: Some("Guides and API reference.".to_string()),
+                canonical_url: Some("https://example.com/docs/".to_string()),
+                favicon_url: Some("https://example.com/docs/static/icon.png".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_fallbacks() {
+        let html = r#"<html><head>
+            <meta property="og:title" content="Open Graph title">
+            <meta property="og:description" content="  ">
+        </head><body></body></html>"#;
+        let metadata = extract_site_metadata(html, &Url::parse("https://example.com/a/b").unwrap());
+        assert_eq!(metadata.title.as_deref(), Some("Open Graph title"));
+        assert_eq!(metadata.description, None);
+        assert_eq!(metadata.canonical_url, None);
+        assert_eq!(metadata.favicon_url.as_deref(), Some("https://example.com/favicon.ico"));
+    }
+
+    #[test]
+    fn test_long_text_is_cut() {
+        let html = format!("<title>{}</title>", "x".repeat(400));
+        let title = extract_site_metadata(&html, &Url::parse("https://example.com").unwrap())
+            .title
+            .unwrap();
+        assert_eq!(title.chars().count(), MAX_TEXT_CHARS + 1);
+        assert!(title.ends_with('…'));
+    }
+}
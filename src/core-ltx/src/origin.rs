@@ -0,0 +1,47 @@
+use url::Url;
+
+use crate::{Error, LlmsTxt, download, is_valid_markdown, validate_is_llm_txt};
+
+/// Where a website publishes its own llms.txt: `/llms.txt` at the root of the website's origin.
+/// None for URLs that aren't on an http(s) website.
+pub fn origin_llms_txt_url(url: &Url) -> Option<Url> {
+    match url.scheme() {
+        "http" | "https" if url.host().is_some() => url.join("/llms.txt").ok(),
+        _ => None,
+    }
+}
+
+/// Downloads the llms.txt the website publishes itself, if it has a valid one.
+///
+/// An error means there's nothing usable to import: the file doesn't exist (e.g. 404),
+/// couldn't be downloaded, or isn't a valid llms.txt (e.g. a catch-all HTML page).
+pub async fn fetch_origin_llms_txt(url: &Url) -> Result<LlmsTxt, Error> {
+    let llms_txt_url = origin_llms_txt_url(url).ok_or_else(|| {
+        Error::InvalidLlmsTxtFormat(format!("{} isn't on a website that can publish an llms.txt", url))
+    })?;
+    let content = download(&llms_txt_url).await?;
+    is_valid_markdown(&content).and_then(validate_is_llm_txt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_llms_txt_url() {
+        let url = Url::parse("https://docs.example.com:8443/guide/intro.html?x=1#top").unwrap();
+        assert_eq!(
+            origin_llms_txt_url(&url).unwrap().as_str(),
+            "https://docs.example.com:8443/llms.txt"
+        );
+        let url = Url::parse("http://example.com").unwrap();
+        assert_eq!(
+            origin_llms_txt_url(&url).unwrap().as_str(),
+            "http://example.com/llms.txt"
+        );
+        assert_eq!(
+            origin_llms_txt_url(&Url::parse("file:///tmp/index.html").unwrap()),
+            None
+        );
+    }
+}
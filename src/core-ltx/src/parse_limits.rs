@@ -0,0 +1,189 @@
+use std::sync::LazyLock;
+
+/// Environment variable with the largest HTML page (in bytes) that's parsed.
+pub const HTML_MAX_BYTES_ENV_VAR: &str = "HTML_MAX_BYTES";
+/// Environment variable with the deepest nesting of HTML elements that's parsed.
+pub const HTML_MAX_DEPTH_ENV_VAR: &str = "HTML_MAX_DEPTH";
+/// Environment variable with the longest HTML attribute value (in bytes) that's kept.
+pub const HTML_MAX_ATTRIBUTE_BYTES_ENV_VAR: &str = "HTML_MAX_ATTRIBUTE_BYTES";
+/// Environment variable with the largest markdown document (in bytes) that's parsed.
+pub const MARKDOWN_MAX_BYTES_ENV_VAR: &str = "MARKDOWN_MAX_BYTES";
+/// Environment variable with the deepest nesting of markdown blocks and links that's parsed.
+pub const MARKDOWN_MAX_DEPTH_ENV_VAR: &str = "MARKDOWN_MAX_DEPTH";
+
+/// The limits `normalize_html` and `is_valid_markdown` enforce: read from the environment the first time they're
+/// needed, with defaults for unset variables.
+pub static PARSE_LIMITS: LazyLock<ParseLimits> =
+    LazyLock::new(|| ParseLimits::from_env().unwrap_or_else(|e| panic!("{}", e)));
+
+/// How big and how deeply nested the HTML and markdown that's parsed can be, so a single malicious or broken page
+/// (or LLM response) can't exhaust the worker's memory or stack, or keep it busy for minutes.
+///
+/// HTML5 has no user-defined entities, so entity expansion ("billion laughs") can't blow up a page: the size limit
+/// bounds the parsed document too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Larger pages are rejected before parsing.
+    pub html_max_bytes: usize,
+    /// Pages with elements nested deeper than this are rejected.
+    pub html_max_depth: usize,
+    /// Longer attribute values (e.g. inline `data:` images) are dropped: they say nothing about the website.
+    pub html_max_attribute_bytes: usize,
+    /// Larger markdown documents are rejected before parsing.
+    pub markdown_max_bytes: usize,
+    /// Markdown with blockquotes, lists, or link brackets nested deeper than this is rejected before parsing.
+    pub markdown_max_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            html_max_bytes: 10 * 1024 * 1024,
+            html_max_depth: 512,
+            html_max_attribute_bytes: 64 * 1024,
+            markdown_max_bytes: 4 * 1024 * 1024,
+            markdown_max_depth: 64,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Reads the limits from `HTML_MAX_BYTES`, `HTML_MAX_DEPTH`, `HTML_MAX_ATTRIBUTE_BYTES`, `MARKDOWN_MAX_BYTES`
+    /// and `MARKDOWN_MAX_DEPTH`, with defaults for unset ones. Fails on values that aren't positive numbers.
+    pub fn from_env() -> Result<Self, String> {
+        let defaults = ParseLimits::default();
+        Ok(ParseLimits {
+            html_max_bytes: limit_from_env(HTML_MAX_BYTES_ENV_VAR, defaults.html_max_bytes)?,
+            html_max_depth: limit_from_env(HTML_MAX_DEPTH_ENV_VAR, defaults.html_max_depth)?,
+            html_max_attribute_bytes: limit_from_env(
+                HTML_MAX_ATTRIBUTE_BYTES_ENV_VAR,
+                defaults.html_max_attribute_bytes,
+            )?,
+            markdown_max_bytes: limit_from_env(MARKDOWN_MAX_BYTES_ENV_VAR, defaults.markdown_max_bytes)?,
+            markdown_max_depth: limit_from_env(MARKDOWN_MAX_DEPTH_ENV_VAR, defaults.markdown_max_depth)?,
+        })
+    }
+}
+
+pub(crate) fn limit_from_env(name: &str, default: usize) -> Result<usize, String> {
+    match std::env::var(name) {
+        Ok(value) if !value.trim().is_empty() => match value.trim().parse::<usize>() {
+            Ok(limit) if limit > 0 => Ok(limit),
+            _ => Err(format!("{} must be a positive number, not '{}'", name, value)),
+        },
+        _ => Ok(default),
+    }
+}
+
+/// How deeply the markdown nests: blockquotes and list items within each other, and link brackets within each
+/// other. Fenced code blocks don't count.
+///
+/// It's an estimate made without parsing (which is what it protects), and errs on the high side: list items count
+/// their indentation (two columns per level) as well as their markers.
+pub fn markdown_nesting_depth(content: &str) -> usize {
+    let mut max_depth = 0;
+    let mut fence: Option<&str> = None;
+    let mut brackets: usize = 0;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = fence {
+            if trimmed.starts_with(marker) {
+                fence = None;
+            }
+            continue;
+        }
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) {
+            fence = Some(marker);
+            continue;
+        }
+        if trimmed.is_empty() {
+            // brackets don't continue past the end of a paragraph
+            brackets = 0;
+            continue;
+        }
+
+        max_depth = max_depth.max(container_depth(line));
+        for c in line.chars() {
+            match c {
+                '[' => {
+                    brackets += 1;
+                    max_depth = max_depth.max(brackets);
+                }
+                ']' => brackets = brackets.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+    max_depth
+}
+
+/// Blockquote and list markers at the start of the line (e.g. 3 for `> - > text`), plus a level per two columns
+/// of indentation before them.
+fn container_depth(line: &str) -> usize {
+    let mut rest = line.trim_start_matches([' ', '\t']);
+    let indentation: usize = line[..line.len() - rest.len()]
+        .chars()
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum();
+    let mut markers = 0;
+    loop {
+        rest = rest.trim_start_matches([' ', '\t']);
+        if let Some(after) = rest.strip_prefix('>') {
+            markers += 1;
+            rest = after;
+        } else if let Some(after) = list_marker(rest) {
+            markers += 1;
+            rest = after;
+        } else {
+            break;
+        }
+    }
+    if markers == 0 { 0 } else { markers + indentation / 2 }
+}
+
+/// What follows the list item marker (`-`, `*`, `+`, `1.` or `1)`) at the start of the text, if there's one.
+fn list_marker(text: &str) -> Option<&str> {
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let after = match text.chars().next()? {
+        '-' | '*' | '+' => &text[1..],
+        _ if (1..=9).contains(&digits) => text[digits..].strip_prefix(['.', ')'])?,
+        _ => return None,
+    };
+    (after.is_empty() || after.starts_with([' ', '\t'])).then_some(after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_nesting_depth() {
+        assert_eq!(markdown_nesting_depth(""), 0);
+        assert_eq!(markdown_nesting_depth("# Title\n\n> Summary\n"), 1);
+        assert_eq!(
+            markdown_nesting_depth("- [Docs](https://example.com/docs): The docs\n"),
+            1
+        );
+        assert_eq!(markdown_nesting_depth("> - > text"), 3);
+        assert_eq!(markdown_nesting_depth("- a\n  - b\n    - c\n"), 3);
+        assert_eq!(markdown_nesting_depth("1. first\n2) second\n3.not a list\n"), 1);
+        assert_eq!(markdown_nesting_depth("-not a list\n*emphasis*\n"), 0);
+        assert_eq!(markdown_nesting_depth(&">".repeat(1000)), 1000);
+        assert_eq!(markdown_nesting_depth(&"[".repeat(1000)), 1000);
+        // paragraphs and code blocks don't nest
+        assert_eq!(markdown_nesting_depth("[[a\n\n[b]"), 2);
+        assert_eq!(markdown_nesting_depth("```\n>>>>>>>>\n```\n"), 0);
+    }
+
+    #[test]
+    fn test_limit_from_env() {
+        assert_eq!(limit_from_env("PARSE_LIMITS_TEST_UNSET", 42), Ok(42));
+        unsafe { std::env::set_var("PARSE_LIMITS_TEST_INVALID", "0") };
+        assert!(limit_from_env("PARSE_LIMITS_TEST_INVALID", 42).is_err());
+        unsafe { std::env::set_var("PARSE_LIMITS_TEST_INVALID", "lots") };
+        assert!(limit_from_env("PARSE_LIMITS_TEST_INVALID", 42).is_err());
+        unsafe { std::env::set_var("PARSE_LIMITS_TEST_INVALID", " 7 ") };
+        assert_eq!(limit_from_env("PARSE_LIMITS_TEST_INVALID", 42), Ok(7));
+    }
+}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use client_ltx::ApiClient;
+
+use crate::Error;
+
+/// Environment variable holding the API key used to authenticate with a remote api-ltx server.
+pub const API_KEY_ENV_VAR: &str = "LLMSTXT_API_KEY";
+
+/// Client for a running api-ltx server at `server`.
+///
+/// When an API key is set, every request carries it as an `Authorization: Bearer` header.
+/// `accept_invalid_certs` accepts self-signed TLS certificates: only use it for local development!
+pub fn remote_client(server: &str, api_key: Option<String>, accept_invalid_certs: bool) -> Result<ApiClient, Error> {
+    let http_client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .timeout(Duration::from_secs(30))
+        .build()?;
+    Ok(ApiClient::new(server)
+        .with_http_client(http_client)
+        .with_api_key(api_key))
+}
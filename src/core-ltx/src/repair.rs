@@ -0,0 +1,322 @@
+/// The result of the deterministic repair pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repaired {
+    /// The repaired llms.txt content.
+    pub content: String,
+    /// A human-readable description of every fix applied, in the order applied.
+    pub fixes: Vec<String>,
+}
+
+/// Applies deterministic fixes for the most common ways an llms.txt is almost, but not quite, valid.
+///
+/// In order:
+///   - removes a markdown code fence wrapping the whole file
+///   - removes text before the H1 header
+///   - promotes the first header to an H1 if there is no H1
+///   - demotes any additional H1 headers to H2
+///   - turns the first paragraph after the H1 into the summary blockquote if there is none
+///   - normalizes `*` and `+` list markers to `-`
+///   - collapses runs of blank lines and ends the file with a single newline
+///
+/// This never calls an LLM and never fails: content that can't be repaired is returned as-is.
+pub fn repair_llms_txt(content: &str) -> Repaired {
+    let mut fixes = Vec::new();
+    let mut lines: Vec<String> = content.lines().map(|l| l.trim_end().to_string()).collect();
+
+    strip_code_fence(&mut lines, &mut fixes);
+    strip_before_h1(&mut lines, &mut fixes);
+    ensure_h1(&mut lines, &mut fixes);
+    demote_extra_h1s(&mut lines, &mut fixes);
+    ensure_summary(&mut lines, &mut fixes);
+    normalize_list_markers(&mut lines, &mut fixes);
+
+    let mut content = String::new();
+    let mut previous_blank = true;
+    let mut collapsed = false;
+    for line in lines.iter() {
+        let blank = line.trim().is_empty();
+        if blank && previous_blank {
+            collapsed = true;
+            continue;
+        }
+        content.push_str(line);
+        content.push('\n');
+        previous_blank = blank;
+    }
+    while content.ends_with("\n\n") {
+        content.pop();
+        collapsed = true;
+    }
+    if collapsed {
+        fixes.push("Removed extra blank lines".to_string());
+    }
+
+    Repaired { content, fixes }
+}
+
+fn is_h1(line: &str) -> bool {
+    line.trim_start().starts_with("# ")
+}
+
+fn first_non_blank(lines: &[String]) -> Option<usize> {
+    lines.iter().position(|l| !l.trim().is_empty())
+}
+
+fn strip_code_fence(lines: &mut Vec<String>, fixes: &mut Vec<String>) {
+    let first_h1 = lines.iter().position(|l| is_h1(l)).unwrap_or(lines.len());
+    let Some(open) = lines[..first_h1].iter().position(|l| l.trim_start().starts_with("```")) else {
+        return;
+    };
+    let Some(close) = lines.iter().rposition(|l| !l.trim().is_empty()) else {
+        return;
+    };
+    if open < close && lines[close].trim() == "```" {
+        lines.remove(close);
+        lines.remove(open);
+        fixes.push("Removed the code fence wrapping the file".to_string());
+    }
+}
+
+fn strip_before_h1(lines: &mut Vec<String>, fixes: &mut Vec<String>) {
+    if let Some(h1) = lines.iter().position(|l| is_h1(l))
+        && lines[..h1].iter().any(|l| !l.trim().is_empty())
+    {
+        lines.drain(..h1);
+        fixes.push("Removed text before the H1 header".to_string());
+    }
+}
+
+fn ensure_h1(lines: &mut [String], fixes: &mut Vec<String>) {
+    if lines.iter().any(|l| is_h1(l)) {
+        return;
+    }
+    let Some(first) = first_non_blank(lines) else {
+        return;
+    };
+    let line = lines[first].trim_start();
+    if line.starts_with('#') {
+        let title = line.trim_start_matches('#').trim().to_string();
+        if !title.is_empty() {
+            lines[first] = format!("# {}", title);
+            fixes.push(format!("Promoted the first header to H1: '{}'", title));
+        }
+    }
+}
+
+fn demote_extra_h1s(lines: &mut [String], fixes: &mut Vec<String>) {
+    let mut seen_h1 = false;
+    for line in lines.iter_mut() {
+        if is_h1(line) {
+            if seen_h1 {
+                *line = format!("#{}", line.trim_start());
+                fixes.push(format!("Demoted extra H1 header to H2: '{}'", line));
+            }
+            seen_h1 = true;
+        }
+    }
+}
+
+fn ensure_summary(lines: &mut [String], fixes: &mut Vec<String>) {
+    let Some(h1) = lines.iter().position(|l| is_h1(l)) else {
+        return;
+    };
+    let Some(next) = lines[h1 + 1..]
+        .iter()
+        .position(|l| !l.trim().is_empty())
+        .map(|i| i + h1 + 1)
+    else {
+        return;
+    };
+    let line = lines[next].trim_start();
+    let is_paragraph = !(line.starts_with('>')
+        || line.starts_with('#')
+        || line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with("+ "));
+    if is_paragraph {
+        let mut i = next;
+        while i < lines.len() && !lines[i].trim().is_empty() {
+            lines[i] = format!("> {}", lines[i].trim_start());
+            i += 1;
+        }
+        fixes.push("Turned the first paragraph into the summary blockquote".to_string());
+    }
+}
+
+fn normalize_list_markers(lines: &mut [String], fixes: &mut Vec<String>) {
+    let mut count = 0;
+    for line in lines.iter_mut() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("* ").or_else(|| trimmed.strip_prefix("+ ")) {
+            let indent = &line[..line.len() - trimmed.len()];
+            *line = format!("{}- {}", indent, rest);
+            count += 1;
+        }
+    }
+    if count > 0 {
+        fixes.push(format!("Normalized {} list marker(s) to '-'", count));
+    }
+}
+
+/// Line-level edit between two texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit<'a> {
+    Keep(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Computes the line edits turning `old` into `new` using the longest common subsequence.
+fn line_edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            edits.push(Edit::Keep(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            edits.push(Edit::Delete(old[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(new[j]));
+            j += 1;
+        }
+    }
+    edits.extend(old[i..].iter().map(|l| Edit::Delete(l)));
+    edits.extend(new[j..].iter().map(|l| Edit::Insert(l)));
+    edits
+}
+
+/// Produces a unified diff (as `diff -u` would) of the lines changed between `old` and `new`,
+/// with 3 lines of context around each change. Returns an empty string if they're the same.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let edits = line_edits(&old_lines, &new_lines);
+
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Keep(_)))
+        .map(|(i, _)| i)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // group changes that are close enough to share context into hunks of edit indices [start, end)
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in changed.iter() {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        // line numbers (1-based) of the first line of the hunk in each file
+        let old_start = 1 + edits[..start].iter().filter(|e| !matches!(e, Edit::Insert(_))).count();
+        let new_start = 1 + edits[..start].iter().filter(|e| !matches!(e, Edit::Delete(_))).count();
+        let hunk = &edits[start..end];
+        let old_count = hunk.iter().filter(|e| !matches!(e, Edit::Insert(_))).count();
+        let new_count = hunk.iter().filter(|e| !matches!(e, Edit::Delete(_))).count();
+
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { old_start - 1 } else { old_start },
+            old_count,
+            if new_count == 0 { new_start - 1 } else { new_start },
+            new_count
+        ));
+        for edit in hunk {
+            match edit {
+                Edit::Keep(l) => out.push_str(&format!(" {}\n", l)),
+                Edit::Delete(l) => out.push_str(&format!("-{}\n", l)),
+                Edit::Insert(l) => out.push_str(&format!("+{}\n", l)),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{is_valid_markdown, validate_is_llm_txt};
+
+    const VALID: &str =
+        "# Example\n\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n";
+
+    #[test]
+    fn test_repair_valid_is_unchanged() {
+        let repaired = repair_llms_txt(VALID);
+        assert_eq!(repaired.content, VALID);
+        assert!(repaired.fixes.is_empty());
+    }
+
+    #[test]
+    fn test_repair_common_llm_mistakes() {
+        let broken = "Here is your llms.txt:\n\n```markdown\n# Example\nAn example website.\n\n\n\n## Docs\n\n* [Intro](https://example.com/intro): Start here\n\n# Extra\n\n+ [API](https://example.com/api)\n```\n";
+        let repaired = repair_llms_txt(broken);
+        assert_eq!(
+            repaired.content,
+            "# Example\n> An example website.\n\n## Docs\n\n- [Intro](https://example.com/intro): Start here\n\n## Extra\n\n- [API](https://example.com/api)\n"
+        );
+        assert_eq!(repaired.fixes.len(), 6);
+        assert!(
+            is_valid_markdown(&repaired.content)
+                .and_then(validate_is_llm_txt)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_repair_promotes_first_header() {
+        let repaired = repair_llms_txt("## Example\n\n> Summary\n");
+        assert_eq!(repaired.content, "# Example\n\n> Summary\n");
+        assert_eq!(
+            repaired.fixes,
+            vec!["Promoted the first header to H1: 'Example'".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n";
+        assert_eq!(
+            unified_diff(old, new, "a/llms.txt", "b/llms.txt"),
+            "--- a/llms.txt\n+++ b/llms.txt\n@@ -2,9 +2,10 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n 9\n 10\n+11\n"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old = "a\n1\n2\n3\n4\n5\n6\n7\n8\nb\n";
+        let new = "A\n1\n2\n3\n4\n5\n6\n7\n8\nB\n";
+        assert_eq!(
+            unified_diff(old, new, "old", "new"),
+            "--- old\n+++ new\n@@ -1,4 +1,4 @@\n-a\n+A\n 1\n 2\n 3\n@@ -7,4 +7,4 @@\n 6\n 7\n 8\n-b\n+B\n"
+        );
+    }
+}
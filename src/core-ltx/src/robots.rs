@@ -0,0 +1,175 @@
+use html5ever::{parse_document, tendril::TendrilSink};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+
+/// Directives with which publishers ask not to be indexed, or not to have their content used by AI.
+/// `none` is shorthand for `noindex, nofollow`.
+const DISALLOWING_DIRECTIVES: [&str; 4] = ["noindex", "none", "noai", "noimageai"];
+
+/// X-Robots-Tag directives that take a value after a colon, e.g. `unavailable_after: 25 Jun 2030`.
+/// Any other `name:` prefix names the crawler the following directives are for.
+const DIRECTIVES_WITH_VALUES: [&str; 4] = [
+    "unavailable_after",
+    "max-snippet",
+    "max-image-preview",
+    "max-video-preview",
+];
+
+/// Where the publisher put the directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectiveSource {
+    MetaTag,
+    Header,
+}
+
+/// A directive that disallows generating an llms.txt for the page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disallowed {
+    pub directive: String,
+    pub source: DirectiveSource,
+}
+
+impl std::fmt::Display for Disallowed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            DirectiveSource::MetaTag => write!(f, "'{}' in <meta name=\"robots\">", self.directive),
+            DirectiveSource::Header => write!(f, "'{}' in X-Robots-Tag header", self.directive),
+        }
+    }
+}
+
+/// Finds the first directive in the page's `<meta name="robots">` tags or `X-Robots-Tag` headers that disallows
+/// indexing it: `noindex`, `none`, `noai` or `noimageai`. Directives addressed to a specific crawler
+/// (`<meta name="googlebot">`, `X-Robots-Tag: googlebot: noindex`) are for that crawler only, and are ignored.
+pub fn find_disallowing_directive(html: &str, x_robots_tags: &[String]) -> Option<Disallowed> {
+    let from_headers = x_robots_tags
+        .iter()
+        .flat_map(|value| header_directives(value))
+        .find(|directive| is_disallowing(directive))
+        .map(|directive| Disallowed {
+            directive,
+            source: DirectiveSource::Header,
+        });
+    from_headers.or_else(|| {
+        meta_robots_contents(html)
+            .iter()
+            .flat_map(|content| content.split(',').map(|d| d.trim().to_ascii_lowercase()))
+            .find(|directive| is_disallowing(directive))
+            .map(|directive| Disallowed {
+                directive,
+                source: DirectiveSource::MetaTag,
+            })
+    })
+}
+
+fn is_disallowing(directive: &str) -> bool {
+    DISALLOWING_DIRECTIVES.contains(&directive)
+}
+
+/// The directives of one X-Robots-Tag header value that apply to all crawlers.
+fn header_directives(value: &str) -> Vec<String> {
+    let mut directives = Vec::new();
+    let mut for_all_crawlers = true;
+    for token in value.split(',') {
+        let mut directive = token.trim().to_ascii_lowercase();
+        if let Some((prefix, rest)) = directive.split_once(':')
+            && !DIRECTIVES_WITH_VALUES.contains(&prefix.trim())
+        {
+            for_all_crawlers = false;
+            directive = rest.trim().to_string();
+        }
+        if for_all_crawlers && !directive.is_empty() {
+            directives.push(directive);
+        }
+    }
+    directives
+}
+
+/// The `content` of every `<meta name="robots">` tag.
+fn meta_robots_contents(html: &str) -> Vec<String> {
+    let dom = match parse_document(RcDom::default(), Default::default())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+    {
+        Ok(dom) => dom,
+        Err(_) => return Vec::new(),
+    };
+    let mut contents = Vec::new();
+    collect_meta_robots(&dom.document, &mut contents);
+    contents
+}
+
+fn collect_meta_robots(handle: &Handle, contents: &mut Vec<String>) {
+    if let NodeData::Element { name, attrs, .. } = &handle.data
+        && &*name.local == "meta"
+    {
+        let attrs = attrs.borrow();
+        let attr = |key: &str| {
+            attrs
+                .iter()
+                .find(|a| &*a.name.local == key)
+                .map(|a| a.value.to_string())
+        };
+        if attr("name").is_some_and(|name| name.trim().eq_ignore_ascii_case("robots"))
+            && let Some(content) = attr("content")
+        {
+            contents.push(content);
+        }
+    }
+    for child in handle.children.borrow().iter() {
+        collect_meta_robots(child, contents);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(meta: &str) -> String {
+        format!("<html><head>{}<title>T</title></head><body>Hi</body></html>", meta)
+    }
+
+    #[test]
+    fn test_meta_tags() {
+        let disallowed = find_disallowing_directive(&page(r#"<meta name="Robots" content="NoIndex, follow">"#), &[]);
+        assert_eq!(
+            disallowed,
+            Some(Disallowed {
+                directive: "noindex".to_string(),
+                source: DirectiveSource::MetaTag
+            })
+        );
+        let disallowed = find_disallowing_directive(&page(r#"<meta name="robots" content="noai, noimageai">"#), &[]);
+        assert_eq!(disallowed.unwrap().directive, "noai");
+
+        assert_eq!(
+            find_disallowing_directive(&page(r#"<meta name="robots" content="index, follow">"#), &[]),
+            None
+        );
+        assert_eq!(
+            find_disallowing_directive(&page(r#"<meta name="googlebot" content="noindex">"#), &[]),
+            None
+        );
+        assert_eq!(find_disallowing_directive(&page(""), &[]), None);
+    }
+
+    #[test]
+    fn test_headers() {
+        let headers = vec!["max-snippet: 20, none".to_string()];
+        let disallowed = find_disallowing_directive(&page(""), &headers).unwrap();
+        assert_eq!(disallowed.directive, "none");
+        assert_eq!(disallowed.to_string(), "'none' in X-Robots-Tag header");
+
+        // for one crawler only
+        let headers = vec![
+            "googlebot: noindex, nofollow".to_string(),
+            "unavailable_after: 2030-01-01".to_string(),
+        ];
+        assert_eq!(find_disallowing_directive(&page(""), &headers), None);
+
+        let headers = vec!["googlebot: noindex".to_string(), "noimageai".to_string()];
+        assert_eq!(
+            find_disallowing_directive(&page(""), &headers).unwrap().directive,
+            "noimageai"
+        );
+    }
+}
@@ -0,0 +1,31 @@
+//! The parse/render property every llms.txt must have, shared by the property tests and the fuzz targets
+//! (`core-ltx/fuzz`).
+
+use crate::{is_valid_markdown, validate_is_llm_txt};
+
+/// Checks that a valid llms.txt survives being rendered: `md_content` must parse back into a valid llms.txt, and
+/// rendering that one again must give the same text. Input that isn't a valid llms.txt passes, as long as parsing
+/// and validating it didn't panic.
+///
+/// A failure is an asymmetry between the parser, the validator, and the renderer: an llms.txt that's stored and
+/// served differently from how it was validated.
+pub fn check_round_trip(content: &str) -> Result<(), String> {
+    let Ok(llms_txt) = is_valid_markdown(content).and_then(validate_is_llm_txt) else {
+        return Ok(());
+    };
+    let rendered = llms_txt.md_content();
+
+    let reparsed = is_valid_markdown(&rendered)
+        .map_err(|e| format!("rendered llms.txt isn't markdown: {}\n---\n{}", e, rendered))?;
+    let revalidated = validate_is_llm_txt(reparsed)
+        .map_err(|e| format!("rendered llms.txt isn't valid: {}\n---\n{}", e, rendered))?;
+
+    let rerendered = revalidated.md_content();
+    if rerendered != rendered {
+        return Err(format!(
+            "rendering isn't stable\n--- first render\n{}\n--- second render\n{}",
+            rendered, rerendered
+        ));
+    }
+    Ok(())
+}
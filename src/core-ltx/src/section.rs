@@ -0,0 +1,193 @@
+use std::ops::Range;
+
+use markdown_ppp::ast::{self, Block};
+use markdown_ppp::printer::{config::Config, render_markdown};
+
+use crate::{Error, LlmsTxt, Markdown, validate_is_llm_txt};
+
+/// Names of the H2 sections of an llms.txt, in document order.
+pub fn section_names(llms_txt: &LlmsTxt) -> Vec<String> {
+    llms_txt.map(|doc| doc.blocks.iter().filter_map(h2_name).collect())
+}
+
+/// The markdown of one H2 section of an llms.txt: its header, and everything up to the next header.
+/// Section names are matched case-insensitively.
+pub fn section_content(llms_txt: &LlmsTxt, name: &str) -> Result<String, Error> {
+    llms_txt.map(|doc| {
+        let range = find_section(doc, name)?;
+        let mut section = doc.clone();
+        section.blocks = doc.blocks[range].to_vec();
+        Ok(render_markdown(&section, Config::default()))
+    })
+}
+
+/// Replaces one H2 section of an llms.txt with a new version of it. The rest of the document is kept as-is.
+///
+/// The new version must be a single section: a list of links, optionally preceded by its H2 header. It keeps the
+/// original header, so a renamed section still has its old name. The spliced document must be a valid llms.txt.
+pub fn splice_section(llms_txt: &LlmsTxt, name: &str, section: Markdown) -> Result<LlmsTxt, Error> {
+    let new_blocks = section_body(section)?;
+    let mut doc = llms_txt.clone().extract();
+    let range = find_section(&doc, name)?;
+    let header = doc.blocks[range.start].clone();
+    doc.blocks.splice(range, std::iter::once(header).chain(new_blocks));
+    validate_is_llm_txt(doc)
+}
+
+/// Indices of the blocks of the named section: from its header up to the next header, or the end of the document.
+fn find_section(doc: &Markdown, name: &str) -> Result<Range<usize>, Error> {
+    let name = name.trim();
+    let start = doc
+        .blocks
+        .iter()
+        .position(|block| h2_name(block).is_some_and(|h2| h2.eq_ignore_ascii_case(name)))
+        .ok_or_else(|| Error::UnknownSection(name.to_string()))?;
+    let end = doc.blocks[start + 1..]
+        .iter()
+        .position(|block| matches!(block, Block::Heading(_)))
+        .map_or(doc.blocks.len(), |offset| start + 1 + offset);
+    Ok(start..end)
+}
+
+/// The blocks of a regenerated section, without its header.
+fn section_body(section: Markdown) -> Result<Vec<Block>, Error> {
+    let mut blocks: Vec<Block> = section
+        .blocks
+        .into_iter()
+        .filter(|block| !matches!(block, Block::Empty))
+        .collect();
+    if blocks.first().is_some_and(|block| h2_name(block).is_some()) {
+        blocks.remove(0);
+    }
+    if blocks.iter().any(|block| matches!(block, Block::Heading(_))) {
+        return Err(Error::InvalidLlmsTxtFormat(
+            "A regenerated section can only have a single H2 header.".into(),
+        ));
+    }
+    if !blocks.iter().any(|block| matches!(block, Block::List(_))) {
+        return Err(Error::InvalidLlmsTxtFormat(
+            "A regenerated section needs a list of links.".into(),
+        ));
+    }
+    Ok(blocks)
+}
+
+/// The text of an H2 header (ATX `## Name` or Setext), without formatting.
+fn h2_name(block: &Block) -> Option<String> {
+    use ast::HeadingKind::*;
+    match block {
+        Block::Heading(ast::Heading {
+            kind: Atx(2) | Setext(ast::SetextHeading::Level2),
+            content,
+        }) => Some(plain_text(content).trim().to_string()),
+        _ => None,
+    }
+}
+
+fn plain_text(inlines: &[ast::Inline]) -> String {
+    use ast::Inline::*;
+    inlines
+        .iter()
+        .map(|inline| match inline {
+            Text(text) | Code(text) => text.clone(),
+            Emphasis(children) | Strong(children) | Strikethrough(children) => plain_text(children),
+            Link(link) => plain_text(&link.children),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::diff::parse_outline;
+    use crate::is_valid_markdown;
+
+    const LLMS_TXT: &str = indoc! {"
+        # Example
+
+        > An example website.
+
+        ## Docs
+
+        - [Guide](https://example.com/docs/guide): How to get started
+
+        ## Blog
+
+        - [Launch](https://example.com/blog/launch): We launched
+    "};
+
+    fn llms_txt(content: &str) -> LlmsTxt {
+        validate_is_llm_txt(is_valid_markdown(content).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_section_names() {
+        assert_eq!(section_names(&llms_txt(LLMS_TXT)), vec!["Docs", "Blog"]);
+    }
+
+    #[test]
+    fn test_section_content() {
+        let docs = section_content(&llms_txt(LLMS_TXT), "docs").unwrap();
+        assert!(docs.contains("Docs"));
+        assert!(docs.contains("https://example.com/docs/guide"));
+        assert!(!docs.contains("Blog"));
+
+        assert!(matches!(
+            section_content(&llms_txt(LLMS_TXT), "Pricing"),
+            Err(Error::UnknownSection(name)) if name == "Pricing"
+        ));
+    }
+
+    #[test]
+    fn test_splice_section() {
+        let section = is_valid_markdown(indoc! {"
+            ## Documentation
+
+            - [Guide](https://example.com/docs/guide): How to get started
+            - [API](https://example.com/docs/api): Every endpoint
+        "})
+        .unwrap();
+        let spliced = splice_section(&llms_txt(LLMS_TXT), "Docs", section).unwrap();
+
+        let outline = parse_outline(&spliced.md_content());
+        assert_eq!(outline.title.as_deref(), Some("Example"));
+        assert_eq!(outline.summary.as_deref(), Some("An example website."));
+        let names: Vec<&str> = outline.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Docs", "Blog"]);
+        assert_eq!(outline.sections[0].links.len(), 2);
+        assert_eq!(outline.sections[0].links[1].url, "https://example.com/docs/api");
+        assert_eq!(outline.sections[1].links[0].url, "https://example.com/blog/launch");
+    }
+
+    #[test]
+    fn test_splice_section_without_header() {
+        let section = is_valid_markdown("- [Post](https://example.com/blog/post): Another post\n").unwrap();
+        let spliced = splice_section(&llms_txt(LLMS_TXT), "Blog", section).unwrap();
+
+        let outline = parse_outline(&spliced.md_content());
+        assert_eq!(outline.sections[1].name, "Blog");
+        assert_eq!(outline.sections[1].links[0].url, "https://example.com/blog/post");
+        assert_eq!(outline.sections[0].links[0].url, "https://example.com/docs/guide");
+    }
+
+    #[test]
+    fn test_splice_section_rejects_more_than_one_section() {
+        let section = is_valid_markdown(indoc! {"
+            ## Docs
+
+            - [Guide](https://example.com/docs/guide)
+
+            ## Blog
+
+            - [Launch](https://example.com/blog/launch)
+        "})
+        .unwrap();
+        assert!(splice_section(&llms_txt(LLMS_TXT), "Docs", section).is_err());
+
+        let section = is_valid_markdown("Nothing changed.\n").unwrap();
+        assert!(splice_section(&llms_txt(LLMS_TXT), "Docs", section).is_err());
+    }
+}
@@ -1,4 +1,6 @@
-use reqwest::redirect::Policy;
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime};
+
 use url::Url;
 
 use html5ever::{
@@ -6,14 +8,76 @@ use html5ever::{
     serialize::{SerializeOpts, serialize},
     tendril::TendrilSink,
 };
-use markup5ever_rcdom::{RcDom, SerializableHandle};
+use markup5ever_rcdom::{Handle, NodeData, RcDom, SerializableHandle};
 use minify_html::{Cfg, minify};
 
 use crate::Error;
+use crate::credentials::credential_for;
+use crate::domain_policy::{DownloadGuard, download_guard};
+use crate::http_client::http_client;
+use crate::parse_limits::{PARSE_LIMITS, ParseLimits};
 
 /// Maximum number of redirects to follow before giving up.
 const MAX_REDIRECTS: usize = 10;
 
+/// Maximum number of times a response with a retryable status is retried before giving up.
+const MAX_RETRIES: u32 = 3;
+
+/// Delay before the first retry. It doubles with each one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between retries, even if the server's `Retry-After` asks for more.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// What the download does with a response, by its HTTP status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusPolicy {
+    /// The page's content is in the response.
+    Success,
+    /// Follow the `Location` header, up to `MAX_REDIRECTS` times. Only the redirect statuses (301, 302, 303, 307, 308):
+    /// the other 3xx (e.g. 300 Multiple Choices, 304 Not Modified) have no page to follow to.
+    Redirect,
+    /// The page can't be downloaded, and asking again won't change that: fail without retrying.
+    Permanent,
+    /// The server is overloaded or having trouble: retry with backoff, up to `MAX_RETRIES` times.
+    Retryable,
+}
+
+/// Policy for each range of statuses. The first range that contains the status applies.
+const STATUS_POLICIES: [(u16, u16, StatusPolicy); 9] = [
+    (200, 299, StatusPolicy::Success),
+    (301, 303, StatusPolicy::Redirect),
+    (307, 308, StatusPolicy::Redirect),
+    // Multiple Choices, Not Modified, Use Proxy, ...
+    (300, 399, StatusPolicy::Permanent),
+    // Request Timeout, Too Early, Too Many Requests
+    (408, 408, StatusPolicy::Retryable),
+    (425, 425, StatusPolicy::Retryable),
+    (429, 429, StatusPolicy::Retryable),
+    // 401, 403, 404, 406, 410, ...
+    (400, 499, StatusPolicy::Permanent),
+    (500, 599, StatusPolicy::Retryable),
+];
+
+/// How a response with this HTTP status is handled. Statuses outside the table (e.g. 1xx) are permanent failures.
+pub fn status_policy(status_code: u16) -> StatusPolicy {
+    STATUS_POLICIES
+        .iter()
+        .find(|(first, last, _)| (*first..=*last).contains(&status_code))
+        .map(|(_, _, policy)| *policy)
+        .unwrap_or(StatusPolicy::Permanent)
+}
+
+/// How long to wait before retry number `retry` (starting at 0): exponential backoff, or the server's
+/// `Retry-After` (in seconds) if it sent one. Never more than `MAX_RETRY_DELAY`.
+fn retry_delay(retry: u32, retry_after: Option<&str>) -> Duration {
+    let delay = retry_after
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| RETRY_BASE_DELAY * 2u32.saturating_pow(retry));
+    delay.min(MAX_RETRY_DELAY)
+}
+
 macro_rules! string_wrap {
     ($x:ident) => {
         #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -43,29 +107,170 @@ impl std::fmt::Display for Html {
     }
 }
 
+/// Content types that are downloaded as pages. Other text types (e.g. `text/plain`) are allowed too.
+const PAGE_CONTENT_TYPES: [&str; 2] = ["application/xhtml+xml", "application/xml"];
+
 /// Validates that the input string is a URL.
 pub fn is_valid_url(url: &str) -> Result<Url, Error> {
     let valid_url = Url::parse(url)?;
     Ok(valid_url)
 }
 
+/// The website's URL, spelled the one way it's indexed under, so that `https://Example.com/` and `https://example.com`
+/// aren't two websites. Errors when it isn't an `http` or `https` URL.
+///
+/// The scheme and host are lowercased (and the host's trailing dot dropped), the scheme's default port is dropped, an
+/// empty path is `/`, and the fragment and an empty query are dropped: none of them change the page. A path's
+/// trailing slash is kept, since `/docs` and `/docs/` can be different pages. The database's key of a URL
+/// (`url_normalized`) sorts the query's parameters too.
+pub fn canonical_url(url: &str) -> Result<Url, Error> {
+    let mut url = Url::parse(url.trim())?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(Error::NotWebUrl(url.to_string()));
+    }
+    let Some(host) = url.host_str().map(str::to_string) else {
+        return Err(Error::NotWebUrl(url.to_string()));
+    };
+    if host.ends_with('.') {
+        url.set_host(Some(host.trim_end_matches('.')))?;
+    }
+    url.set_fragment(None);
+    if url.query() == Some("") {
+        url.set_query(None);
+    }
+    Ok(url)
+}
+
+/// A downloaded web page.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Values of the response's `X-Robots-Tag` headers.
+    pub x_robots_tags: Vec<String>,
+    /// The response the page was read from, byte for byte. It's the only copy of the page: `text` decodes it.
+    pub response: RawResponse,
+}
+
+impl Page {
+    /// The page's content as text, decoded with the `charset` of its `Content-Type`. Pages that are valid UTF-8
+    /// already (most of them) are borrowed from the response rather than copied.
+    pub fn text(&self) -> Cow<'_, str> {
+        decode_body(&self.response.body, self.response.content_type())
+    }
+
+    /// Like `text`, but takes the response's body: pages that are valid UTF-8 become the text without being copied.
+    pub fn into_text(self) -> String {
+        let is_body_as_is = matches!(self.text(), Cow::Borrowed(text) if text.len() == self.response.body.len());
+        if is_body_as_is {
+            String::from_utf8(self.response.body).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+        } else {
+            self.text().into_owned()
+        }
+    }
+}
+
+/// An HTTP response as it was received, e.g. to archive it.
+#[derive(Debug, Clone)]
+pub struct RawResponse {
+    /// Where the response came from, after following redirects.
+    pub url: Url,
+    pub fetched_at: SystemTime,
+    /// e.g. `HTTP/1.1`
+    pub version: String,
+    pub status: u16,
+    /// In the order they were received. Values that aren't valid UTF-8 are decoded lossily.
+    pub headers: Vec<(String, String)>,
+    /// Decompressed, but otherwise as sent.
+    pub body: Vec<u8>,
+}
+
+impl RawResponse {
+    /// The value of the `Content-Type` header, if there's one.
+    pub fn content_type(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(reqwest::header::CONTENT_TYPE.as_str()))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
 /// Downloads the website's content as text, following redirects.
 ///
 /// This function explicitly handles HTTP redirects (301, 302, 303, 307, 308)
 /// up to `MAX_REDIRECTS` hops, logging each redirect for visibility.
 pub async fn download(url: &Url) -> Result<String, Error> {
-    // Build a client that does NOT auto-follow redirects so we can handle them explicitly
-    let client = reqwest::Client::builder().redirect(Policy::none()).build()?;
+    download_page(url).await.map(Page::into_text)
+}
+
+/// Like `download`, but also keeps the response headers that say how the page may be used.
+///
+/// Responses are handled according to their status's `StatusPolicy`. The URL, and each URL a redirect leads to, is
+/// checked with the download guard, if the process set one (see `domain_policy::set_download_guard`), right before
+/// it's requested. Requests to a host that has a credential (see
+/// `credentials::set_domain_credentials`) are authenticated with it. Pages larger than the `PARSE_LIMITS` couldn't
+/// be parsed anyway: they're abandoned as soon as they're past the limit, rather than downloaded whole.
+pub async fn download_page(url: &Url) -> Result<Page, Error> {
+    download_page_with_limits(url, &PARSE_LIMITS).await
+}
+
+/// `download_page`, with these limits.
+pub async fn download_page_with_limits(url: &Url, limits: &ParseLimits) -> Result<Page, Error> {
+    download_page_guarded(url, limits, download_guard().as_ref()).await
+}
+
+/// `download_page`, with these limits, checking every hop with this `guard` rather than the process's.
+pub async fn download_page_guarded(
+    url: &Url,
+    limits: &ParseLimits,
+    guard: Option<&DownloadGuard>,
+) -> Result<Page, Error> {
+    // The shared client doesn't auto-follow redirects, so we can handle them explicitly
+    let client = http_client();
 
     let mut current_url = url.clone();
     let mut redirects = 0;
+    let mut retries = 0;
 
     loop {
-        let response = client.get(current_url.as_str()).send().await?;
+        // checked on every hop, not only when the job was created: a redirect, or a name that resolves elsewhere since,
+        // mustn't lead into the server's network
+        if let Some(guard) = guard
+            && let Err(reason) = guard.check(&current_url).await
+        {
+            return Err(Error::HostNotAllowed {
+                url: current_url,
+                reason,
+            });
+        }
+        let mut request = client.get(current_url.as_str());
+        // looked up on every hop: a redirect to another host doesn't get the credential
+        if let Some(credential) = credential_for(&current_url) {
+            let (name, value) = credential.header();
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
         let status = response.status();
+        let policy = status_policy(status.as_u16());
+
+        if policy == StatusPolicy::Retryable && retries < MAX_RETRIES {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok());
+            let delay = retry_delay(retries, retry_after);
+            tracing::warn!(
+                "HTTP {} for {}, retry {}/{} in {:?}",
+                status.as_u16(),
+                current_url,
+                retries + 1,
+                MAX_RETRIES,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            retries += 1;
+            continue;
+        }
 
-        // Check if this is a redirect response
-        if status.is_redirection() {
+        if policy == StatusPolicy::Redirect {
             if redirects >= MAX_REDIRECTS {
                 return Err(Error::TooManyRedirects {
                     original_url: url.clone(),
@@ -103,8 +308,8 @@ pub async fn download(url: &Url) -> Result<String, Error> {
             continue;
         }
 
-        // Not a redirect - check for success and return content
-        if !status.is_success() {
+        // Not a redirect: permanent failures, and retryable ones that ran out of retries, fail
+        if policy != StatusPolicy::Success {
             return Err(Error::HttpError {
                 url: current_url,
                 status_code: status.as_u16(),
@@ -120,17 +325,122 @@ pub async fn download(url: &Url) -> Result<String, Error> {
             );
         }
 
-        let text_body = response.text().await?;
-        return Ok(text_body);
+        if let Some(content_type) = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            && !is_page_content_type(content_type)
+        {
+            return Err(Error::NotHtml {
+                url: current_url,
+                content_type: content_type.to_string(),
+            });
+        }
+
+        let x_robots_tags = response
+            .headers()
+            .get_all("x-robots-tag")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+            .collect();
+        let version = format!("{:?}", response.version());
+        let headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.as_str().to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let body = read_body(response, &current_url, limits.html_max_bytes).await?;
+        return Ok(Page {
+            x_robots_tags,
+            response: RawResponse {
+                url: current_url,
+                fetched_at: SystemTime::now(),
+                version,
+                status: status.as_u16(),
+                headers,
+                body,
+            },
+        });
     }
 }
 
+/// Reads the body chunk by chunk as it's received, into a buffer of at most `max_bytes`. A larger body is abandoned
+/// as soon as it's past the limit, or right away when its `Content-Length` says it will be.
+async fn read_body(mut response: reqwest::Response, url: &Url, max_bytes: usize) -> Result<Vec<u8>, Error> {
+    let too_large = || Error::HtmlTooComplex(format!("{} is larger than the limit of {} bytes", url, max_bytes));
+    // unknown when the response is compressed: it's only known to be within the limit once it's read
+    let content_length = response.content_length().unwrap_or(0);
+    if content_length > max_bytes as u64 {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::with_capacity(content_length as usize);
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Decodes a body as text using the `charset` of its `Content-Type`, or UTF-8 if there's none (as reqwest's `text` does).
+/// Invalid sequences are replaced with U+FFFD. Only copies the body when it has to be changed.
+fn decode_body<'a>(body: &'a [u8], content_type: Option<&str>) -> Cow<'a, str> {
+    let encoding = content_type
+        .and_then(|content_type| {
+            content_type.split(';').skip(1).find_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                name.trim()
+                    .eq_ignore_ascii_case("charset")
+                    .then(|| value.trim().trim_matches('"').to_string())
+            })
+        })
+        .and_then(|charset| encoding_rs::Encoding::for_label(charset.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    let (text, _, _) = encoding.decode(body);
+    text
+}
+
+/// True for a `Content-Type` whose body can be read as a page. Parameters like `charset` are ignored.
+fn is_page_content_type(content_type: &str) -> bool {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    mime.is_empty() || mime.starts_with("text/") || PAGE_CONTENT_TYPES.contains(&mime.as_str())
+}
+
 /// Parses and validates the input as HTML. Returns valid HTML 5 or an error.
 /// Attempts to fix the input string according to HTML5 parsing rules.
+///
+/// Pages past the `PARSE_LIMITS` (too big or too deeply nested) are rejected, and oversized attribute values dropped.
 pub fn parse_html(content: &str) -> Result<Html, Error> {
+    parse_html_with_limits(content, &PARSE_LIMITS)
+}
+
+/// `parse_html`, with these limits.
+pub fn parse_html_with_limits(content: &str, limits: &ParseLimits) -> Result<Html, Error> {
+    if content.len() > limits.html_max_bytes {
+        return Err(Error::HtmlTooComplex(format!(
+            "{} bytes, more than the limit of {}",
+            content.len(),
+            limits.html_max_bytes
+        )));
+    }
+
     let dom: RcDom = parse_document(RcDom::default(), Default::default())
         .from_utf8()
         .read_from(&mut content.as_bytes())?;
+    enforce_dom_limits(&dom.document, limits)?;
 
     let document: SerializableHandle = dom.document.clone().into();
 
@@ -144,6 +454,45 @@ pub fn parse_html(content: &str) -> Result<Html, Error> {
     Ok(Html(html))
 }
 
+/// Rejects the document if its elements nest deeper than the limit, and drops attribute values over the limit.
+///
+/// Walks the tree with an explicit stack: recursing would overflow on the very documents it's checking.
+fn enforce_dom_limits(document: &Handle, limits: &ParseLimits) -> Result<(), Error> {
+    let mut dropped_attributes = 0;
+    let mut stack = vec![(document.clone(), 0)];
+    while let Some((node, depth)) = stack.pop() {
+        if depth > limits.html_max_depth {
+            return Err(Error::HtmlTooComplex(format!(
+                "elements nested more than {} deep",
+                limits.html_max_depth
+            )));
+        }
+        if let NodeData::Element {
+            attrs,
+            template_contents,
+            ..
+        } = &node.data
+        {
+            let mut attrs = attrs.borrow_mut();
+            let before = attrs.len();
+            attrs.retain(|attr| attr.value.len() <= limits.html_max_attribute_bytes);
+            dropped_attributes += before - attrs.len();
+            if let Some(contents) = template_contents.borrow().as_ref() {
+                stack.push((contents.clone(), depth + 1));
+            }
+        }
+        stack.extend(node.children.borrow().iter().map(|child| (child.clone(), depth + 1)));
+    }
+    if dropped_attributes > 0 {
+        tracing::debug!(
+            "Dropped {} attribute values over {} bytes",
+            dropped_attributes,
+            limits.html_max_attribute_bytes
+        );
+    }
+    Ok(())
+}
+
 /// Configuration used by `clean_html`.
 const CLEAN_HTML_CFG: Cfg = Cfg {
     // Preserve document structure
@@ -187,9 +536,14 @@ pub fn clean_html(html: &Html) -> Result<CleanHtml, std::string::FromUtf8Error>
     String::from_utf8(minified).map(CleanHtml)
 }
 
-/// Normalizes HTML by parsing and cleaning it.
+/// Normalizes HTML by parsing and cleaning it, within the `PARSE_LIMITS`.
 pub fn normalize_html(html: &str) -> Result<CleanHtml, Error> {
-    let parsed = parse_html(html)?;
+    normalize_html_with_limits(html, &PARSE_LIMITS)
+}
+
+/// `normalize_html`, with these limits.
+pub fn normalize_html_with_limits(html: &str, limits: &ParseLimits) -> Result<CleanHtml, Error> {
+    let parsed = parse_html_with_limits(html, limits)?;
     let cleaned = clean_html(&parsed)?;
     Ok(cleaned)
 }
@@ -203,6 +557,8 @@ pub fn compute_html_checksum(normalized_html: &CleanHtml) -> Result<String, Erro
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain_policy::DomainPolicy;
+    use crate::fixture_server::FixtureServer;
 
     const HTML_EXAMPLES: [&str; 2] = [
         "<html><body><h1>Hello, World!</h1></body></html>", // valid
@@ -218,6 +574,32 @@ mod tests {
         assert!(is_valid_url(url).is_err());
     }
 
+    #[test]
+    fn test_canonical_url() {
+        for (url, canonical) in [
+            ("https://Example.com/", "https://example.com/"),
+            ("https://example.com", "https://example.com/"),
+            ("  HTTPS://EXAMPLE.com:443  ", "https://example.com/"),
+            ("http://example.com:8080/Docs/?", "http://example.com:8080/Docs/"),
+            ("https://example.com./docs#install", "https://example.com/docs"),
+            (
+                "https://example.com/docs?b=2&a=1#top",
+                "https://example.com/docs?b=2&a=1",
+            ),
+        ] {
+            assert_eq!(canonical_url(url).unwrap().as_str(), canonical, "{}", url);
+        }
+
+        assert!(matches!(canonical_url("example.com"), Err(Error::InvalidUrl(_))));
+        for url in [
+            "ftp://example.com/file",
+            "file:///etc/passwd",
+            "mailto:someone@example.com",
+        ] {
+            assert!(matches!(canonical_url(url), Err(Error::NotWebUrl(_))), "{}", url);
+        }
+    }
+
     #[tokio::test]
     async fn test_download() {
         let url = Url::parse("https://example.com").unwrap();
@@ -225,6 +607,242 @@ mod tests {
         assert!(!content.is_empty());
     }
 
+    const PAGE: &str = "<html><body><h1>Hello, World!</h1></body></html>";
+
+    #[tokio::test]
+    async fn test_download_follows_redirects() {
+        let site = FixtureServer::start().await;
+        site.redirect("/old", 301, "/moved").await;
+        site.redirect("/moved", 302, site.url("/new").as_str()).await;
+        site.page("/new", PAGE).await;
+
+        let page = download_page(&site.url("/old")).await.unwrap();
+        assert_eq!(page.text(), PAGE);
+        assert_eq!(page.response.url, site.url("/new"));
+    }
+
+    #[tokio::test]
+    async fn test_download_checks_every_hop() {
+        let site = FixtureServer::start().await;
+        site.redirect("/metadata", 302, "http://169.254.169.254/latest/meta-data/")
+            .await;
+        site.redirect("/internal", 301, "http://10.0.0.1/").await;
+        site.page("/", PAGE).await;
+
+        // the fixture is on localhost: only the hosts the policy allows can be fetched instead
+        let guard = DownloadGuard {
+            policy: DomainPolicy {
+                allow: vec![site.base_url().host_str().unwrap().to_string()],
+                block: vec![],
+            },
+            private_hosts_allowed: true,
+        };
+        for path in ["/metadata", "/internal"] {
+            let result = download_page_guarded(&site.url(path), &PARSE_LIMITS, Some(&guard)).await;
+            assert!(
+                matches!(&result, Err(Error::HostNotAllowed { url, .. }) if url.host_str() != site.base_url().host_str()),
+                "{}: {:?}",
+                path,
+                result
+            );
+        }
+        assert!(
+            download_page_guarded(&site.base_url(), &PARSE_LIMITS, Some(&guard))
+                .await
+                .is_ok()
+        );
+
+        // and the first request is checked too
+        let guard = DownloadGuard::default();
+        let result = download_page_guarded(&site.base_url(), &PARSE_LIMITS, Some(&guard)).await;
+        assert!(matches!(result, Err(Error::HostNotAllowed { .. })), "{:?}", result);
+        assert_eq!(site.requests_to("/").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_redirect_loop() {
+        let site = FixtureServer::start().await;
+        site.redirect("/loop", 307, "/loop").await;
+
+        let result = download(&site.url("/loop")).await;
+        assert!(
+            matches!(result, Err(Error::TooManyRedirects { redirect_count, .. }) if redirect_count == MAX_REDIRECTS),
+            "{:?}",
+            result
+        );
+        assert_eq!(site.requests_to("/loop").await, MAX_REDIRECTS + 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_3xx_without_location_isnt_a_redirect() {
+        let site = FixtureServer::start().await;
+        site.status("/", 304).await;
+
+        let result = download(&site.base_url()).await;
+        assert!(
+            matches!(result, Err(Error::HttpError { status_code: 304, .. })),
+            "{:?}",
+            result
+        );
+        assert_eq!(site.requests_to("/").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_permanent_failure_isnt_retried() {
+        let site = FixtureServer::start().await;
+        site.status("/", 406).await;
+
+        let result = download(&site.base_url()).await;
+        assert!(
+            matches!(result, Err(Error::HttpError { status_code: 406, .. })),
+            "{:?}",
+            result
+        );
+        assert_eq!(site.requests_to("/").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_retries_rate_limited() {
+        let site = FixtureServer::start().await;
+        site.status_then_page("/", 429, 2, PAGE).await;
+
+        assert_eq!(download(&site.base_url()).await.unwrap(), PAGE);
+        assert_eq!(site.requests_to("/").await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_download_gives_up_after_retries() {
+        let site = FixtureServer::start().await;
+        site.status_then_page("/", 500, u64::from(MAX_RETRIES) + 1, PAGE).await;
+
+        let result = download(&site.base_url()).await;
+        assert!(
+            matches!(result, Err(Error::HttpError { status_code: 500, .. })),
+            "{:?}",
+            result
+        );
+        assert_eq!(site.requests_to("/").await, MAX_RETRIES as usize + 1);
+    }
+
+    #[tokio::test]
+    async fn test_download_waits_for_slow_page() {
+        let site = FixtureServer::start().await;
+        let delay = Duration::from_millis(300);
+        site.slow_page("/", PAGE, delay).await;
+
+        let start = std::time::Instant::now();
+        assert_eq!(download(&site.base_url()).await.unwrap(), PAGE);
+        assert!(start.elapsed() >= delay);
+    }
+
+    #[tokio::test]
+    async fn test_download_encodings() {
+        let site = FixtureServer::start().await;
+        site.body("/latin1", &b"<p>caf\xe9</p>"[..], "text/html; charset=ISO-8859-1")
+            .await;
+        site.gzip_page("/gzip", PAGE).await;
+
+        assert_eq!(download(&site.url("/latin1")).await.unwrap(), "<p>café</p>");
+        assert_eq!(download(&site.url("/gzip")).await.unwrap(), PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_download_content_types() {
+        let site = FixtureServer::start().await;
+        site.robots_txt("User-agent: *\nDisallow: /private\n").await;
+        site.body("/report.pdf", &b"%PDF-1.7"[..], "application/pdf").await;
+
+        let robots_txt = download(&site.url("/robots.txt")).await.unwrap();
+        assert!(robots_txt.contains("Disallow: /private"));
+        let result = download(&site.url("/report.pdf")).await;
+        assert!(matches!(result, Err(Error::NotHtml { .. })), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn test_download_stops_past_limit() {
+        let site = FixtureServer::start().await;
+        let large = format!("<html><body>{}</body></html>", "<p>text</p>".repeat(1000));
+        site.page("/large", &large).await;
+        site.gzip_page("/large.gz", &large).await;
+        let limits = ParseLimits {
+            html_max_bytes: 1024,
+            ..ParseLimits::default()
+        };
+
+        // told by its Content-Length, and found out while reading it when it's compressed
+        for path in ["/large", "/large.gz"] {
+            let result = download_page_with_limits(&site.url(path), &limits).await;
+            assert!(
+                matches!(result, Err(Error::HtmlTooComplex(_))),
+                "{}: {:?}",
+                path,
+                result
+            );
+        }
+        site.page("/small", PAGE).await;
+        let page = download_page_with_limits(&site.url("/small"), &limits).await.unwrap();
+        assert_eq!(page.into_text(), PAGE);
+    }
+
+    #[tokio::test]
+    async fn test_download_keeps_x_robots_tags() {
+        let site = FixtureServer::start().await;
+        site.page_with_headers("/", PAGE, &[("x-robots-tag", "noindex")]).await;
+
+        let page = download_page(&site.base_url()).await.unwrap();
+        assert_eq!(page.x_robots_tags, vec!["noindex".to_string()]);
+    }
+
+    #[test]
+    fn test_status_policy() {
+        assert_eq!(status_policy(200), StatusPolicy::Success);
+        for status in [301, 302, 303, 307, 308] {
+            assert_eq!(status_policy(status), StatusPolicy::Redirect, "{}", status);
+        }
+        for status in [300, 304, 305, 306, 399, 401, 403, 404, 406, 410] {
+            assert_eq!(status_policy(status), StatusPolicy::Permanent, "{}", status);
+        }
+        for status in [408, 429, 500, 502, 503] {
+            assert_eq!(status_policy(status), StatusPolicy::Retryable, "{}", status);
+        }
+        assert_eq!(status_policy(101), StatusPolicy::Permanent);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0, None), Duration::from_secs(1));
+        assert_eq!(retry_delay(2, None), Duration::from_secs(4));
+        assert_eq!(retry_delay(0, Some("7")), Duration::from_secs(7));
+        assert_eq!(retry_delay(0, Some("3600")), MAX_RETRY_DELAY);
+        // HTTP dates aren't supported: back off as usual
+        assert_eq!(
+            retry_delay(1, Some("Wed, 21 Oct 2015 07:28:00 GMT")),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn test_is_page_content_type() {
+        assert!(is_page_content_type("text/html; charset=UTF-8"));
+        assert!(is_page_content_type("Application/XHTML+XML"));
+        assert!(is_page_content_type("text/plain"));
+        assert!(is_page_content_type(""));
+        assert!(!is_page_content_type("application/pdf"));
+        assert!(!is_page_content_type("image/png"));
+    }
+
+    #[test]
+    fn test_decode_body() {
+        assert_eq!(decode_body("café".as_bytes(), Some("text/html")), "café");
+        assert_eq!(
+            decode_body(b"caf\xe9", Some("text/html; Charset=\"ISO-8859-1\"")),
+            "café"
+        );
+        assert_eq!(decode_body(b"caf\xe9", None), "caf\u{FFFD}");
+        // UTF-8 isn't copied
+        assert!(matches!(decode_body("café".as_bytes(), None), Cow::Borrowed("café")));
+    }
+
     #[test]
     fn test_parse_html() {
         let expected = Html("<html><head></head><body><h1>Hello, World!</h1></body></html>".into());
@@ -275,4 +893,80 @@ mod tests {
         assert!(cleaned.as_str().contains("</p>"));
         assert!(cleaned.as_str().contains("</div>"));
     }
+
+    #[test]
+    fn test_limits_reject_large_and_deep_pages() {
+        let limits = ParseLimits {
+            html_max_bytes: 1024,
+            html_max_depth: 32,
+            ..ParseLimits::default()
+        };
+        let large = format!("<p>{}</p>", "x".repeat(1024));
+        assert!(matches!(
+            normalize_html_with_limits(&large, &limits),
+            Err(Error::HtmlTooComplex(_))
+        ));
+        // html, body, and the divs
+        let nested = |n: usize| format!("{}{}", "<div>".repeat(n), "</div>".repeat(n));
+        assert!(normalize_html_with_limits(&nested(20), &limits).is_ok());
+        assert!(matches!(
+            normalize_html_with_limits(&nested(40), &limits),
+            Err(Error::HtmlTooComplex(_))
+        ));
+        // unclosed, so the parser nests them all
+        assert!(matches!(
+            normalize_html_with_limits(&"<span>".repeat(40), &limits),
+            Err(Error::HtmlTooComplex(_))
+        ));
+    }
+
+    #[test]
+    fn test_default_limits_survive_pathological_pages() {
+        // deep enough to overflow the stack of a recursive walk
+        let nested = "<div>".repeat(200_000);
+        assert!(matches!(normalize_html(&nested), Err(Error::HtmlTooComplex(_))));
+
+        let image = format!(
+            "<p>Hello<img alt=\"logo\" src=\"data:image/png;base64,{}\"></p>",
+            "A".repeat(1024 * 1024)
+        );
+        let normalized = normalize_html(&image).unwrap();
+        assert!(normalized.as_str().len() < 1024);
+        assert!(normalized.as_str().contains("alt=logo") || normalized.as_str().contains("alt=\"logo\""));
+
+        // HTML has no user-defined entities: the DTD's aren't expanded
+        let laughs = indoc::indoc! {r#"
+            <!DOCTYPE lolz [
+              <!ENTITY lol "lol">
+              <!ENTITY lol2 "&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;&lol;">
+              <!ENTITY lol3 "&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;&lol2;">
+            ]>
+            <html><body><p>&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;&lol3;</p></body></html>
+        "#};
+        let normalized = normalize_html(laughs).unwrap();
+        // expanded, the paragraph alone would be 3000 bytes
+        assert!(normalized.as_str().len() < 2 * laughs.len());
+    }
+
+    proptest::proptest! {
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(256))]
+
+        /// Whatever the tags, attributes, entities, and text, normalizing returns instead of panicking.
+        #[test]
+        fn fuzz_normalize_html(
+            html in r#"(<[a-z]{1,6}( [a-z-]{1,8}(="[^"]{0,16}")?){0,3}/?>|</[a-z]{1,6}>|&[a-z#0-9]{0,8};?|<!--|-->|<!DOCTYPE[^>]{0,16}>|[^<&]{0,12}){0,64}"#
+        ) {
+            let limits = ParseLimits {
+                html_max_depth: 16,
+                html_max_attribute_bytes: 8,
+                ..ParseLimits::default()
+            };
+            let _ = normalize_html_with_limits(&html, &limits);
+        }
+
+        #[test]
+        fn fuzz_normalize_arbitrary_text(html in proptest::prelude::any::<String>()) {
+            let _ = normalize_html(&html);
+        }
+    }
 }
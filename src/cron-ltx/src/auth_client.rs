@@ -1,158 +1,27 @@
-use reqwest::{Client, Response, StatusCode};
-use serde::Serialize;
-use std::sync::{Arc, Mutex};
-use tracing::{debug, warn};
-
-use crate::errors::Error;
-
-#[derive(Debug, Serialize)]
-struct LoginRequest {
-    password: String,
-}
-
-/// HTTP client with automatic authentication support
-pub struct AuthenticatedClient {
-    client: Client,
-    api_base_url: String,
-    password: Option<String>,
-    cookie: Arc<Mutex<Option<String>>>,
-}
-
-impl AuthenticatedClient {
-    /// Create a new authenticated client
-    pub fn new(client: Client, api_base_url: String, password: Option<String>) -> Self {
-        Self {
-            client,
-            api_base_url,
-            password,
-            cookie: Arc::new(Mutex::new(None)),
-        }
+use client_ltx::ApiClient;
+use core_ltx::inject_trace_headers;
+use reqwest::Client;
+use std::time::Duration;
+
+/// Builds the HTTP client used to talk to the API server.
+/// When `accept_invalid_certs` is true, self-signed TLS certificates are accepted (development only!).
+pub fn build_reqwest_client(accept_invalid_certs: bool) -> Result<Client, reqwest::Error> {
+    if accept_invalid_certs {
+        tracing::warn!("Accepting invalid TLS certificates (development mode)");
+        Client::builder()
+            .danger_accept_invalid_certs(true)
+            .timeout(Duration::from_secs(30))
+            .build()
+    } else {
+        Client::builder().timeout(Duration::from_secs(30)).build()
     }
+}
 
-    /// Authenticate with the API server and store the session cookie
-    pub async fn authenticate(&self) -> Result<(), Error> {
-        let password = self
-            .password
-            .as_ref()
-            .ok_or_else(|| Error::AuthError("No password configured for authentication".to_string()))?;
-
-        let login_url = format!("{}/api/auth/login", self.api_base_url);
-        let login_request = LoginRequest {
-            password: password.clone(),
-        };
-
-        debug!("Authenticating with API server");
-
-        let response = self
-            .client
-            .post(&login_url)
-            .json(&login_request)
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            return Err(Error::AuthError("Authentication failed".to_string()));
-        }
-
-        // Extract Set-Cookie header
-        if let Some(set_cookie) = response.headers().get("set-cookie") {
-            let cookie_value = set_cookie
-                .to_str()
-                .map_err(|_| Error::AuthError("Invalid cookie header".to_string()))?;
-
-            // The cookie value is before the first semicolon (;)
-            let cookie = cookie_value
-                .split(';')
-                .next()
-                .ok_or_else(|| Error::AuthError("Invalid cookie format".to_string()))?
-                .to_string();
-
-            let mut cookie_guard = self
-                .cookie
-                .lock()
-                .map_err(|_| Error::AuthError("Failed to lock cookie mutex".to_string()))?;
-            *cookie_guard = Some(cookie);
-
-            debug!("Authentication successful, cookie stored");
-        } else {
-            return Err(Error::AuthError("No cookie in response".to_string()));
-        }
-
-        Ok(())
-    }
-
-    /// Make a POST request with automatic authentication
-    pub async fn post<T: Serialize>(&self, path: &str, json_body: &T) -> Result<Response, Error> {
-        let url = format!("{}{}", self.api_base_url, path);
-
-        let mut request = self.client.post(&url).json(json_body);
-
-        if let Ok(cookie_guard) = self.cookie.lock()
-            && let Some(cookie) = cookie_guard.as_ref()
-        {
-            request = request.header("Cookie", cookie);
-        }
-
-        let response = request.send().await.map_err(Error::HttpError)?;
-
-        // If 401 and password is configured, try to re-authenticate
-        if response.status() == StatusCode::UNAUTHORIZED && self.password.is_some() {
-            warn!("Received 401, attempting to re-authenticate");
-
-            self.authenticate().await?;
-
-            let mut retry_request = self.client.post(&url).json(json_body);
-
-            if let Ok(cookie_guard) = self.cookie.lock()
-                && let Some(cookie) = cookie_guard.as_ref()
-            {
-                retry_request = retry_request.header("Cookie", cookie);
-            }
-
-            let retry_response = retry_request.send().await.map_err(Error::HttpError)?;
-
-            return Ok(retry_response);
-        }
-
-        Ok(response)
-    }
-
-    /// Make a GET request with automatic authentication
-    pub async fn get(&self, path: &str) -> Result<Response, Error> {
-        let url = format!("{}{}", self.api_base_url, path);
-
-        // Try request with current cookie
-        let mut request = self.client.get(&url);
-
-        if let Ok(cookie_guard) = self.cookie.lock()
-            && let Some(cookie) = cookie_guard.as_ref()
-        {
-            request = request.header("Cookie", cookie);
-        }
-
-        let response = request.send().await.map_err(Error::HttpError)?;
-
-        // If 401 and password is configured, try to re-authenticate
-        if response.status() == StatusCode::UNAUTHORIZED && self.password.is_some() {
-            warn!("Received 401, attempting to re-authenticate");
-
-            self.authenticate().await?;
-
-            // Retry request with new cookie
-            let mut retry_request = self.client.get(&url);
-
-            if let Ok(cookie_guard) = self.cookie.lock()
-                && let Some(cookie) = cookie_guard.as_ref()
-            {
-                retry_request = retry_request.header("Cookie", cookie);
-            }
-
-            let retry_response = retry_request.send().await.map_err(Error::HttpError)?;
-
-            return Ok(retry_response);
-        }
-
-        Ok(response)
-    }
+/// API client for cron: logs in with `password` (when auth is enabled) and re-authenticates on a 401.
+/// Every request continues the current trace, so the API's spans for it join the cron's trace.
+pub fn api_client(client: Client, api_base_url: &str, password: Option<String>) -> ApiClient {
+    ApiClient::new(api_base_url)
+        .with_http_client(client)
+        .with_password(password)
+        .with_header_hook(inject_trace_headers)
 }
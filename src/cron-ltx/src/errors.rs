@@ -7,7 +7,7 @@ pub enum Error {
     HttpError(reqwest::Error),
     CoreError(core_ltx::Error),
     JobInProgress,
-    AuthError(String),
+    ApiError(client_ltx::ClientError),
 }
 
 impl std::fmt::Display for Error {
@@ -20,7 +20,7 @@ impl std::fmt::Display for Error {
             Self::HttpError(e) => write!(f, "HTTP error: {}", e),
             Self::CoreError(e) => write!(f, "Core error: {}", e),
             Self::JobInProgress => write!(f, "Job already in progress"),
-            Self::AuthError(s) => write!(f, "Authentication error: {}", s),
+            Self::ApiError(e) => write!(f, "API error: {}", e),
         }
     }
 }
@@ -54,6 +54,12 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+impl From<client_ltx::ClientError> for Error {
+    fn from(error: client_ltx::ClientError) -> Self {
+        Self::ApiError(error)
+    }
+}
+
 impl From<core_ltx::Error> for Error {
     fn from(error: core_ltx::Error) -> Self {
         Self::CoreError(error)
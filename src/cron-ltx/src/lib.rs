@@ -2,11 +2,11 @@ pub mod auth_client;
 pub mod errors;
 pub mod process;
 
-pub use auth_client::AuthenticatedClient;
+pub use auth_client::{api_client, build_reqwest_client};
 pub use errors::Error;
-pub use process::poll_and_process;
+pub use process::{cron_poll_interval, poll_and_process, updater_loop};
 
-use data_model_ltx::models::{JobKind, ResultStatus};
+use data_model_ltx::models::{JobKind, LlmsTxtSource, ResultData, ResultStatus};
 use diesel::prelude::*;
 
 /// Joined result of llms_txt and job_state
@@ -14,11 +14,14 @@ use diesel::prelude::*;
 pub struct LlmsTxtWithKind {
     pub job_id: uuid::Uuid,
     pub url: String,
-    pub result_data: String,
+    pub result_data: ResultData,
     pub result_status: ResultStatus,
     pub created_at: chrono::DateTime<chrono::Utc>,
     /// Brotli-compressed normalized HTML content (stored as raw bytes)
     pub html_compress: Vec<u8>,
     pub html_checksum: String,
     pub kind: JobKind,
+    /// `core_ltx::FailureKind` code of a failed job.
+    pub failure_kind: Option<String>,
+    pub source: LlmsTxtSource,
 }
@@ -1,13 +1,13 @@
 use std::sync::Arc;
-use std::time::Duration;
 use std::{env, path::PathBuf};
 
 use core_ltx::common::env_check::check_non_empty_env_vars;
-use core_ltx::db::DbPool;
+use core_ltx::notify::Notifications;
 use core_ltx::{
-    TimeUnit, get_api_base_url, get_auth_config, get_db_pool, get_poll_interval, is_auth_enabled, setup_logging,
+    get_api_base_url, get_auth_config, get_db_pool, init_http_client, is_auth_enabled, reload_on_sighup,
+    setup_telemetry,
 };
-use cron_ltx::AuthenticatedClient;
+use cron_ltx::{api_client, build_reqwest_client, cron_poll_interval, updater_loop};
 
 #[tokio::main]
 async fn main() {
@@ -36,12 +36,16 @@ async fn main() {
         }
     }
 
-    setup_logging("cron_ltx=debug");
+    let _telemetry = setup_telemetry("cron-ltx", "cron_ltx=debug");
 
     let pool = get_db_pool().await;
 
-    let poll_interval = get_poll_interval(TimeUnit::Seconds, "CRON_POLL_INTERVAL_S", 300);
-    tracing::info!("Using a {:?} interval for updating.", poll_interval);
+    // The poll interval is re-read on SIGHUP.
+    let poll_interval = reload_on_sighup("cron", cron_poll_interval).unwrap_or_else(|e| panic!("{}", e));
+    tracing::info!("Using a {:?} interval for updating.", *poll_interval.borrow());
+    // Read now, so invalid settings fail at startup rather than on the first download
+    let http_client_settings = init_http_client().unwrap_or_else(|e| panic!("{}", e));
+    tracing::info!("Download HTTP client: {:?}", http_client_settings);
 
     // Load auth configuration
     let auth_config = get_auth_config();
@@ -61,50 +65,21 @@ async fn main() {
         })
         .unwrap_or(false);
 
-    let reqwest_client = if accept_invalid_certs {
-        tracing::warn!("Accepting invalid TLS certificates (development mode)");
-        reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .timeout(Duration::from_secs(30))
-            .build()
-    } else {
-        reqwest::Client::builder().timeout(Duration::from_secs(30)).build()
-    }
-    .expect("Failed to build HTTP client");
+    let reqwest_client = build_reqwest_client(accept_invalid_certs).expect("Failed to build HTTP client");
 
     let api_base_url = format!("https://{}", get_api_base_url());
     tracing::info!("API server URL: {}", api_base_url);
 
-    let http_client = Arc::new(AuthenticatedClient::new(reqwest_client, api_base_url.clone(), password));
+    let http_client = Arc::new(api_client(reqwest_client, &api_base_url, password));
 
     // Authenticate immediately if password is configured
-    if http_client.authenticate().await.is_ok() {
+    if http_client.login().await.is_ok() {
         tracing::info!("Initial authentication successful");
     } else {
         tracing::error!("Auth enabled but initial authentication failed!");
     }
 
-    updater_loop(pool, http_client, api_base_url, poll_interval).await;
-}
-
-async fn updater_loop(
-    pool: DbPool,
-    http_client: Arc<AuthenticatedClient>,
-    api_base_url: String,
-    poll_interval: Duration,
-) {
-    tracing::info!("Starting llms.txt update loop.");
-    loop {
-        match cron_ltx::poll_and_process(&pool, &http_client, &api_base_url).await {
-            Ok(num_spawned) => {
-                tracing::info!("Spawned {} tasks for processing", num_spawned);
-            }
-            Err(e) => {
-                tracing::error!("Error during poll cycle: {}", e);
-            }
-        }
+    let notifications = Arc::new(Notifications::from_env());
 
-        tracing::info!("Sleeping for {:?} until next poll", poll_interval);
-        tokio::time::sleep(poll_interval).await;
-    }
+    updater_loop(pool, http_client, api_base_url, poll_interval, notifications).await;
 }
@@ -1,32 +1,168 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-
-use core_ltx::{db, normalize_html, web_html::compute_html_checksum};
+use std::time::Duration;
+
+use chrono::Utc;
+use client_ltx::{ApiClient, Crawl, JobRequestPayload};
+use core_ltx::{
+    FailureKind, TimeUnit,
+    common::poll_interval::poll_interval,
+    db,
+    domain_policy::set_download_guard,
+    fetch_origin_llms_txt, normalize_html,
+    notify::{Notification, Notifications, Outage},
+    web_html::compute_html_checksum,
+    with_error_context,
+};
 use data_model_ltx::{
-    models::{JobKind, ResultStatus},
-    schema::{job_state, llms_txt},
+    domain_policy::load_download_guard,
+    models::{CrawlSchedule, JobKind, LlmsTxtSource, ResultStatus},
+    schema::{crawl_schedule, job_state, llms_txt},
 };
 use diesel::prelude::*;
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
-use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::Instrument;
 
-use crate::AuthenticatedClient;
 use crate::LlmsTxtWithKind;
 use crate::errors::Error;
 
+/// Reads the time between update cycles from `CRON_POLL_INTERVAL_S` (default: 5 minutes).
+pub fn cron_poll_interval() -> Result<Duration, String> {
+    poll_interval(TimeUnit::Seconds, "CRON_POLL_INTERVAL_S", 300)
+        .map_err(|_| "CRON_POLL_INTERVAL_S must be a valid number".to_string())
+}
+
+/// Runs `poll_and_process` forever, sleeping `poll_interval` between each cycle.
+/// A new `poll_interval` takes effect after the current sleep.
+/// Failing cycles (i.e. the database is unreachable) are sent to `notifications` when they start and stop.
+pub async fn updater_loop(
+    pool: db::DbPool,
+    http_client: Arc<ApiClient>,
+    api_base_url: String,
+    poll_interval: watch::Receiver<Duration>,
+    notifications: Arc<Notifications>,
+) {
+    tracing::info!("Starting llms.txt update loop.");
+    let outage = Outage::default();
+    loop {
+        match poll_and_process(&pool, &http_client, &api_base_url).await {
+            Ok(num_spawned) => {
+                tracing::info!("Spawned {} tasks for processing", num_spawned);
+                if outage.recovered() {
+                    notifications
+                        .send(Notification::new(
+                            "Cron updater recovered",
+                            "The cron updater is checking websites for updates again.",
+                        ))
+                        .await;
+                }
+            }
+            Err(e) => {
+                tracing::error!("Error during poll cycle: {}", e);
+                if outage.failed() {
+                    notifications
+                        .send(Notification::new(
+                            "Cron updater cannot reach the database",
+                            e.to_string(),
+                        ))
+                        .await;
+                }
+            }
+        }
+
+        let interval = *poll_interval.borrow();
+        tracing::info!("Sleeping for {:?} until next poll", interval);
+        tokio::time::sleep(interval).await;
+    }
+}
+
 /// Gets the most recent llms.txt for each url and spawns a task to determine if the llms.txt should be updated/regenerated.
+/// URLs with a crawl schedule are re-crawled when it's due instead (see `handle_due_crawls`).
 pub async fn poll_and_process(
     pool: &db::DbPool,
-    http_client: &std::sync::Arc<AuthenticatedClient>,
+    http_client: &std::sync::Arc<ApiClient>,
     api_base_url: &str,
 ) -> Result<usize, Error> {
-    let url_records = most_recent_completed(pool).await?;
+    // the downloads below are checked with the current domain policy, redirects included
+    let mut conn = pool.get().await?;
+    set_download_guard(load_download_guard(&mut conn).await?);
+    drop(conn);
+    let mut url_records = most_recent_completed(pool).await?;
+    let schedules = crawl_schedules(pool).await?;
+    // the URL's page alone doesn't show whether the pages deeper in a crawled website changed
+    let scheduled: HashSet<&str> = schedules.iter().map(|schedule| schedule.url.as_str()).collect();
+    url_records.retain(|url, _| !scheduled.contains(url.as_str()));
     let num_urls = url_records.len();
     tracing::info!("Found {} unique URLs to process.", num_urls);
 
     handle_record_updates(http_client, api_base_url, url_records).await;
+    let num_crawls = handle_due_crawls(pool, http_client, schedules).await;
+
+    Ok(num_urls + num_crawls)
+}
+
+/// Every crawl schedule.
+async fn crawl_schedules(pool: &db::DbPool) -> Result<Vec<CrawlSchedule>, Error> {
+    let mut conn = pool.get().await?;
+    crawl_schedule::table
+        .select(CrawlSchedule::as_select())
+        .load::<CrawlSchedule>(&mut conn)
+        .await
+        .map_err(Error::from)
+}
 
-    Ok(num_urls)
+/// Spawns a task to enqueue a crawl job for each schedule that's due, and returns how many there are.
+/// A schedule's next run moves on once its crawl job is created, or when the URL already has a job in progress.
+/// Otherwise, it's tried again in the next cycle.
+async fn handle_due_crawls(pool: &db::DbPool, http_client: &Arc<ApiClient>, schedules: Vec<CrawlSchedule>) -> usize {
+    let now = Utc::now();
+    let due: Vec<CrawlSchedule> = schedules.into_iter().filter(|schedule| schedule.is_due(now)).collect();
+    let num_due = due.len();
+    tracing::info!("Found {} scheduled crawls to run.", num_due);
+
+    for schedule in due {
+        let span = tracing::info_span!("scheduled_crawl", url = %schedule.url);
+        let error_url = schedule.url.clone();
+        tokio::spawn(with_error_context(&[("url", error_url.as_str())], {
+            let pool = pool.clone();
+            let http_client = http_client.clone();
+            async move {
+                match send_crawl_request(&http_client, &schedule.url, schedule.crawl()).await {
+                    Ok(job_id) => tracing::info!("Confirmed: Job ID {} for crawl of '{}'", job_id, schedule.url),
+                    Err(Error::ApiError(e)) if e.status() == Some(409) => {
+                        tracing::info!("Not crawling '{}': it already has a job in progress", schedule.url)
+                    }
+                    Err(e) => {
+                        tracing::error!("Error enqueuing crawl of {}: {}", schedule.url, e);
+                        return;
+                    }
+                }
+                if let Err(e) = record_crawl_run(&pool, &schedule, now).await {
+                    tracing::error!("Error recording crawl of {}: {}", schedule.url, e);
+                }
+            }
+            .instrument(span)
+        }));
+    }
+    num_due
+}
+
+/// Moves the schedule's next run an interval past `run_at`.
+async fn record_crawl_run(
+    pool: &db::DbPool,
+    schedule: &CrawlSchedule,
+    run_at: chrono::DateTime<Utc>,
+) -> Result<(), Error> {
+    let mut conn = pool.get().await?;
+    diesel::update(crawl_schedule::table.find(&schedule.url))
+        .set((
+            crawl_schedule::last_run_at.eq(run_at),
+            crawl_schedule::next_run_at.eq(schedule.next_run_after(run_at)),
+        ))
+        .execute(&mut conn)
+        .await?;
+    Ok(())
 }
 
 /// Gets only the most recent llms.txt record for each URL in the DB.
@@ -50,6 +186,8 @@ async fn fetch_all_completed_jobs(conn: &mut AsyncPgConnection) -> Result<Vec<Ll
             llms_txt::html_compress,
             llms_txt::html_checksum,
             job_state::kind,
+            job_state::failure_kind,
+            llms_txt::source,
         ))
         .order(llms_txt::created_at.desc())
         .load::<LlmsTxtWithKind>(conn)
@@ -70,35 +208,75 @@ fn deduplicate_by_url(records: Vec<LlmsTxtWithKind>) -> HashMap<String, LlmsTxtW
 
 /// Handles all llms.txt records by either attempting to regenerate (for a failed row) or update (for a success) the llms.txt.
 async fn handle_record_updates(
-    http_client: &std::sync::Arc<AuthenticatedClient>,
+    http_client: &std::sync::Arc<ApiClient>,
     api_base_url: &str,
     url_records: HashMap<String, LlmsTxtWithKind>,
 ) {
     for (url, record) in url_records {
-        tokio::spawn({
+        let span = tracing::info_span!("update_check", url = %url, status = ?record.result_status);
+        let error_url = url.clone();
+        tokio::spawn(with_error_context(&[("url", error_url.as_str())], {
             let http_client = http_client.clone();
             let api_base_url = api_base_url.to_string();
             async move {
                 match record.result_status {
+                    ResultStatus::Ok if record.source == LlmsTxtSource::Origin => {
+                        if let Err(e) =
+                            handle_origin(&http_client, &api_base_url, &url, &record.result_data.text()).await
+                        {
+                            log_update_error("handling imported llms.txt", &url, &e);
+                        }
+                    }
                     ResultStatus::Ok => {
                         if let Err(e) = handle_success(&http_client, &api_base_url, &url, &record.html_checksum).await {
-                            tracing::error!("Error handling success for {}: {}", url, e);
+                            log_update_error("handling success", &url, &e);
                         }
                     }
+                    ResultStatus::Error if !is_retryable(record.failure_kind.as_deref()) => {
+                        tracing::info!(
+                            "Not retrying '{}': its last failure ({}) is permanent",
+                            url,
+                            record.failure_kind.as_deref().unwrap_or_default()
+                        );
+                    }
                     ResultStatus::Error => {
                         if let Err(e) = handle_failure(&http_client, &api_base_url, &url, record.kind).await {
-                            tracing::error!("Error handling failure for {}: {}", url, e);
+                            log_update_error("handling failure", &url, &e);
                         }
                     }
                 }
             }
-        });
+            .instrument(span)
+        }));
+    }
+}
+
+/// Logs why checking the URL for an update failed. The API rate limiting the job's request (HTTP 429) isn't an error:
+/// the website still differs from its llms.txt, so the next cycle asks again.
+fn log_update_error(what: &str, url: &str, error: &Error) {
+    match error {
+        Error::ApiError(e) if e.status() == Some(429) => {
+            tracing::warn!(
+                "Rate limited by the API while {} for '{}': asking again next cycle",
+                what,
+                url
+            )
+        }
+        e => tracing::error!("Error {} for {}: {}", what, url, e),
     }
 }
 
+/// Whether a failed generation is worth retrying. Failures from before failure kinds were recorded,
+/// or of a kind this version doesn't know, are retried.
+fn is_retryable(failure_kind: Option<&str>) -> bool {
+    failure_kind
+        .and_then(FailureKind::from_code)
+        .is_none_or(|kind| core_ltx::failure::is_retryable(&kind))
+}
+
 /// Sends llms.txt update request to API server if the website's HTML has changed.
 async fn handle_success(
-    client: &Arc<AuthenticatedClient>,
+    client: &Arc<ApiClient>,
     api_base_url: &str,
     url: &str,
     stored_checksum: &str,
@@ -134,13 +312,38 @@ async fn handle_success(
     Ok(())
 }
 
-/// Sends request to API server to regenerate llms.txt since it failed to generate it last time.
-async fn handle_failure(
-    client: &Arc<AuthenticatedClient>,
+/// Re-fetches the llms.txt a website publishes itself, and sends an update request if it changed.
+/// If the website no longer publishes a valid one, the update job generates one instead.
+async fn handle_origin(
+    client: &Arc<ApiClient>,
     api_base_url: &str,
     url: &str,
-    kind: JobKind,
+    stored_llms_txt: &str,
 ) -> Result<(), Error> {
+    tracing::debug!("Handling imported llms.txt for URL: '{}'", url);
+
+    let parsed_url = core_ltx::is_valid_url(url)?;
+    match fetch_origin_llms_txt(&parsed_url).await {
+        // stored as the worker rendered it when importing
+        Ok(llms_txt) if llms_txt.md_content() == stored_llms_txt => {
+            tracing::info!("Published llms.txt unchanged for '{}', skipping update.", url);
+            return Ok(());
+        }
+        Ok(_) => tracing::info!("Published llms.txt changed for '{}', sending update request.", url),
+        Err(e) => tracing::info!(
+            "'{}' no longer publishes a valid llms.txt ({}), sending update request.",
+            url,
+            e
+        ),
+    }
+    let job_id = send_update_request(client, api_base_url, url).await?;
+    tracing::info!("Confirmed: Job ID {} for update on '{}'", job_id, url);
+
+    Ok(())
+}
+
+/// Sends request to API server to regenerate llms.txt since it failed to generate it last time.
+async fn handle_failure(client: &Arc<ApiClient>, api_base_url: &str, url: &str, kind: JobKind) -> Result<(), Error> {
     tracing::debug!("Handling failure for URL: '{}' ({:?})", url, kind);
 
     let job_id = match kind {
@@ -158,49 +361,36 @@ async fn handle_failure(
     Ok(())
 }
 
-#[derive(Serialize)]
-struct UrlPayload {
-    url: String,
-}
-
-#[derive(Deserialize)]
-struct JobIdResponse {
-    job_id: uuid::Uuid,
-}
-
 /// Sends POST /api/llm_txt request to generate new llms.txt
-async fn send_generate_request(
-    client: &Arc<AuthenticatedClient>,
-    _api_base_url: &str,
-    url: &str,
-) -> Result<uuid::Uuid, Error> {
+async fn send_generate_request(client: &Arc<ApiClient>, _api_base_url: &str, url: &str) -> Result<uuid::Uuid, Error> {
     tracing::debug!("API request: POST /api/llm_txt");
-    let payload = UrlPayload { url: url.to_string() };
-    let response = client.post("/api/llm_txt", &payload).await?;
+    let job_response = client.post_llm_txt(&JobRequestPayload::new(url)).await?;
     tracing::debug!("received response from API server");
 
-    // if response.status() == reqwest::StatusCode::CONFLICT {
-    //     tracing::info!("Job already in progress for '{}'", url);
-    //     return Err(Error::JobInProgress);
-    // }
-
-    let job_response: JobIdResponse = response.error_for_status()?.json().await?;
     tracing::info!("Created generate job {} for '{}'", job_response.job_id, url);
     Ok(job_response.job_id)
 }
 
+/// Sends PUT /api/llm_txt request to crawl the website, and generate (or update) its llms.txt from every page found
+async fn send_crawl_request(client: &Arc<ApiClient>, url: &str, crawl: Crawl) -> Result<uuid::Uuid, Error> {
+    tracing::debug!("API request: PUT /api/llm_txt");
+    let request = JobRequestPayload {
+        crawl: Some(crawl),
+        ..JobRequestPayload::new(url)
+    };
+    let job_response = client.put_llm_txt(&request).await?;
+    tracing::debug!("received response from API server");
+
+    tracing::info!("Created crawl job {} for '{}'", job_response.job_id, url);
+    Ok(job_response.job_id)
+}
+
 /// Sends POST /api/update request to update existing llms.txt
-async fn send_update_request(
-    client: &Arc<AuthenticatedClient>,
-    _api_base_url: &str,
-    url: &str,
-) -> Result<uuid::Uuid, Error> {
+async fn send_update_request(client: &Arc<ApiClient>, _api_base_url: &str, url: &str) -> Result<uuid::Uuid, Error> {
     tracing::debug!("API request: POST /api/update");
-    let payload = UrlPayload { url: url.to_string() };
-    let response = client.post("/api/update", &payload).await?;
+    let job_response = client.post_update(&JobRequestPayload::new(url)).await?;
     tracing::debug!("received response from API server");
 
-    let job_response: JobIdResponse = response.error_for_status()?.json().await?;
     tracing::info!("Created update job {} for '{}'", job_response.job_id, url);
     Ok(job_response.job_id)
 }
@@ -209,6 +399,9 @@ async fn send_update_request(
 mod tests {
     use super::*;
     use chrono::Utc;
+    use core_ltx::fixture_server::FixtureServer;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
 
     fn create_test_record(
         url: &str,
@@ -224,12 +417,14 @@ mod tests {
         LlmsTxtWithKind {
             job_id: uuid::Uuid::new_v4(),
             url: url.to_string(),
-            result_data: "test data".to_string(),
+            result_data: "test data".into(),
             result_status,
             created_at,
             html_compress,
             html_checksum,
             kind,
+            failure_kind: None,
+            source: LlmsTxtSource::Generated,
         }
     }
 
@@ -319,4 +514,81 @@ mod tests {
         assert_eq!(test_record.created_at, now);
         assert_eq!(test_record.kind, JobKind::Update);
     }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(is_retryable(None));
+        assert!(is_retryable(Some("http_status:503")));
+        assert!(is_retryable(Some("llm_rate_limited")));
+        assert!(is_retryable(Some("not_a_known_kind")));
+        assert!(!is_retryable(Some("http_status:404")));
+        assert!(!is_retryable(Some("robots_disallowed")));
+    }
+
+    /// The API of `api_client`: POST /api/update creates a job.
+    async fn serve_api() -> FixtureServer {
+        let api = FixtureServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/update"))
+            .respond_with(ResponseTemplate::new(201).set_body_raw(
+                format!(r#"{{"job_id":"{}"}}"#, uuid::Uuid::new_v4()),
+                "application/json",
+            ))
+            .mount(api.mock_server())
+            .await;
+        api
+    }
+
+    fn api_client(api: &FixtureServer) -> Arc<ApiClient> {
+        Arc::new(ApiClient::new(api.base_url().as_str().trim_end_matches('/')))
+    }
+
+    fn checksum(html: &str) -> String {
+        compute_html_checksum(&normalize_html(html).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_handle_success_skips_unchanged_page() {
+        let html = "<html><body><h1>Docs</h1></body></html>";
+        // the same page, behind a redirect and compressed
+        let site = FixtureServer::start().await;
+        site.redirect("/", 301, "/home").await;
+        site.gzip_page("/home", html).await;
+        let api = serve_api().await;
+
+        let url = site.base_url();
+        handle_success(&api_client(&api), "", url.as_str(), &checksum(html))
+            .await
+            .unwrap();
+        assert_eq!(site.requests_to("/home").await, 1);
+        assert_eq!(api.requests_to("/api/update").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_handle_success_updates_changed_page() {
+        let site = FixtureServer::start().await;
+        site.status_then_page("/", 429, 1, "<html><body><h1>Docs v2</h1></body></html>")
+            .await;
+        let api = serve_api().await;
+
+        let url = site.base_url();
+        let stored = checksum("<html><body><h1>Docs</h1></body></html>");
+        handle_success(&api_client(&api), "", url.as_str(), &stored)
+            .await
+            .unwrap();
+        assert_eq!(site.requests_to("/").await, 2, "The 429 is retried");
+        assert_eq!(api.requests_to("/api/update").await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_handle_success_download_failure() {
+        let site = FixtureServer::start().await;
+        site.status("/", 410).await;
+        let api = serve_api().await;
+
+        let url = site.base_url();
+        let result = handle_success(&api_client(&api), "", url.as_str(), "checksum").await;
+        assert!(result.is_err());
+        assert_eq!(api.requests_to("/api/update").await, 0);
+    }
 }
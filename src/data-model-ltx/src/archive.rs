@@ -0,0 +1,55 @@
+//! Archive tier of the stored llms.txt files.
+//!
+//! Every generation of a website is a record of `llms_txt`, but everything except its history only reads the latest
+//! ones. A record is superseded once its website has a newer successful one: those that are old enough are moved to
+//! `llms_txt_archive`, so that `llms_txt` only grows with the number of websites. The latest successful record of a
+//! website, and the failures after it, are never moved.
+//!
+//! Nothing reads an archived record as the website's llms.txt, so moving them isn't a change of the records (see
+//! `crate::changes`).
+
+use chrono::{DateTime, Utc};
+use diesel::sql_types::{BigInt, Timestamptz};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+/// Columns moved from `llms_txt` to `llms_txt_archive`: every one, except the preview and the search vector.
+const ARCHIVED_COLUMNS: &str = "job_id, url, url_normalized, result_data, result_status, created_at, html_compress, \
+     html_checksum, source, title, description, canonical_url, favicon_url, prompt_version, model, detail";
+
+/// Moves at most `limit` superseded records created before `older_than` to `llms_txt_archive`, oldest first.
+/// Returns the number of records moved.
+///
+/// Records that another transaction has locked (e.g. another worker archiving them) are skipped, so that any number
+/// of workers can archive at once.
+pub async fn archive_superseded(
+    conn: &mut AsyncPgConnection,
+    older_than: DateTime<Utc>,
+    limit: i64,
+) -> Result<usize, diesel::result::Error> {
+    // In one statement: a record is either still in llms_txt, or already in the archive. One that's in both (e.g.
+    // restored from a backup after it was archived) is the same generation: the archived copy is kept
+    diesel::sql_query(format!(
+        "WITH moved AS ( \
+             DELETE FROM llms_txt WHERE job_id IN ( \
+                 SELECT l.job_id FROM llms_txt l \
+                 WHERE l.created_at < $1 \
+                 AND EXISTS ( \
+                     SELECT 1 FROM llms_txt newer \
+                     WHERE newer.url_normalized = l.url_normalized AND newer.result_status = 'ok' \
+                     AND newer.created_at > l.created_at \
+                 ) \
+                 ORDER BY l.created_at \
+                 LIMIT $2 \
+                 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING {columns} \
+         ) \
+         INSERT INTO llms_txt_archive ({columns}) SELECT {columns} FROM moved \
+         ON CONFLICT (job_id) DO NOTHING",
+        columns = ARCHIVED_COLUMNS
+    ))
+    .bind::<Timestamptz, _>(older_than)
+    .bind::<BigInt, _>(limit)
+    .execute(conn)
+    .await
+}
@@ -0,0 +1,32 @@
+//! Notifications of changes to the stored llms.txt files, for the API servers' read caches.
+//!
+//! A change is a Postgres `NOTIFY` on `LLMS_TXT_CHANGED_CHANNEL`, with the website's normalized URL (see
+//! `crate::urls`) as payload: every spelling of the URL changed. Sent in the transaction that makes the change, it's
+//! only delivered once that commits, and not at all if it rolls back.
+
+use diesel::sql_types::Text;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+/// Channel of the notifications. An empty payload means any website may have changed.
+pub const LLMS_TXT_CHANGED_CHANNEL: &str = "llms_txt_changed";
+
+/// Longest payload: Postgres' limit is 8000 bytes. Longer normalized URLs are sent as an empty payload.
+const MAX_PAYLOAD_BYTES: usize = 7999;
+
+/// Notifies that the llms.txt records of the website changed: of every website when `url` is None.
+pub async fn notify_llms_txt_changed(
+    conn: &mut AsyncPgConnection,
+    url: Option<&str>,
+) -> Result<(), diesel::result::Error> {
+    // normalizing can make the URL longer (e.g. `https://a.com` is `https://a.com/`): the key is measured
+    diesel::sql_query(format!(
+        "SELECT pg_notify($1, CASE WHEN octet_length(k.url) <= {} THEN k.url ELSE '' END) \
+         FROM normalize_url($2) AS k(url)",
+        MAX_PAYLOAD_BYTES
+    ))
+    .bind::<Text, _>(LLMS_TXT_CHANGED_CHANNEL)
+    .bind::<Text, _>(url.unwrap_or_default())
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,27 @@
+//! The server's domain policy, as stored in the `domain_policy` table: what the API checks new jobs with, and what
+//! the downloads of the worker and the cron updater are guarded with.
+
+use core_ltx::domain_policy::{DownloadGuard, private_hosts_allowed};
+use diesel::prelude::*;
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+
+use crate::models::{DomainPolicyEntry, to_domain_policy};
+use crate::schema::domain_policy;
+
+/// Every pattern of the domain policy, in pattern order.
+pub async fn policy_entries(conn: &mut AsyncPgConnection) -> Result<Vec<DomainPolicyEntry>, diesel::result::Error> {
+    domain_policy::table
+        .order(domain_policy::pattern.asc())
+        .select(DomainPolicyEntry::as_select())
+        .load(conn)
+        .await
+}
+
+/// The guard of the downloads: the stored domain policy, and whether `ALLOW_PRIVATE_HOSTS`.
+pub async fn load_download_guard(conn: &mut AsyncPgConnection) -> Result<DownloadGuard, diesel::result::Error> {
+    let entries = policy_entries(conn).await?;
+    Ok(DownloadGuard {
+        policy: to_domain_policy(&entries),
+        private_hosts_allowed: private_hosts_allowed(),
+    })
+}
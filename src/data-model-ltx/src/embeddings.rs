@@ -0,0 +1,187 @@
+//! Embeddings of the stored llms.txt files, for semantic search.
+//!
+//! `llms_txt_embedding` has the embedding of a record's llms.txt, made with the model it names. It only exists where
+//! Postgres has the pgvector extension (see its migration), and Diesel has no type for its vectors: it's not in
+//! `crate::schema`, and it's queried in SQL, with the vectors as pgvector's text (`[0.1,0.2,...]`). A record's
+//! embedding is deleted with it, e.g. when it's archived.
+
+use chrono::{DateTime, Utc};
+use diesel::QueryableByName;
+use diesel::sql_types::{BigInt, Bool, Float4, Nullable, Text, Timestamptz};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::models::ResultData;
+
+/// A website's latest llms.txt that has no embedding of the model yet.
+#[derive(Debug, QueryableByName)]
+pub struct Unembedded {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub job_id: Uuid,
+    #[diesel(sql_type = Text)]
+    pub url: String,
+    #[diesel(sql_type = Text)]
+    pub result_data: ResultData,
+}
+
+/// A website whose latest llms.txt is close to an embedding.
+#[derive(Debug, QueryableByName)]
+pub struct Neighbor {
+    #[diesel(sql_type = Text)]
+    pub url: String,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub title: Option<String>,
+    #[diesel(sql_type = Nullable<Text>)]
+    pub description: Option<String>,
+    #[diesel(sql_type = Timestamptz)]
+    pub created_at: DateTime<Utc>,
+    /// Cosine similarity: 1 is the same direction
+    #[diesel(sql_type = Float4)]
+    pub similarity: f32,
+}
+
+#[derive(QueryableByName)]
+struct Exists {
+    #[diesel(sql_type = Bool)]
+    exists: bool,
+}
+
+/// Whether the embeddings can be stored: whether the database has pgvector, and so `llms_txt_embedding`.
+pub async fn embeddings_available(conn: &mut AsyncPgConnection) -> Result<bool, diesel::result::Error> {
+    let row: Exists = diesel::sql_query("SELECT to_regclass('llms_txt_embedding') IS NOT NULL AS exists")
+        .get_result(conn)
+        .await?;
+    Ok(row.exists)
+}
+
+/// The embedding as pgvector's text.
+pub fn vector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+/// At most `limit` of the websites' latest successful llms.txt files without an embedding of `model`, newest first.
+pub async fn unembedded_llms_txt(
+    conn: &mut AsyncPgConnection,
+    model: &str,
+    limit: i64,
+) -> Result<Vec<Unembedded>, diesel::result::Error> {
+    diesel::sql_query(
+        "SELECT l.job_id, l.url, l.result_data FROM llms_txt l \
+         WHERE l.result_status = 'ok' \
+         AND NOT EXISTS (SELECT 1 FROM llms_txt_embedding e WHERE e.job_id = l.job_id AND e.model = $1) \
+         AND NOT EXISTS ( \
+             SELECT 1 FROM llms_txt newer \
+             WHERE newer.url_normalized = l.url_normalized AND newer.result_status = 'ok' AND newer.created_at > l.created_at \
+         ) \
+         ORDER BY l.created_at DESC \
+         LIMIT $2",
+    )
+    .bind::<Text, _>(model)
+    .bind::<BigInt, _>(limit)
+    .load(conn)
+    .await
+}
+
+/// Stores the embedding of a record's llms.txt, replacing one of another model. Nothing is stored when the record
+/// was deleted in the meantime.
+pub async fn store_embedding(
+    conn: &mut AsyncPgConnection,
+    job_id: Uuid,
+    model: &str,
+    embedding: &[f32],
+) -> Result<(), diesel::result::Error> {
+    diesel::sql_query(
+        "INSERT INTO llms_txt_embedding (job_id, model, embedding) \
+         SELECT l.job_id, $2, $3::vector FROM llms_txt l WHERE l.job_id = $1 \
+         ON CONFLICT (job_id) DO UPDATE SET model = EXCLUDED.model, embedding = EXCLUDED.embedding, created_at = NOW()",
+    )
+    .bind::<diesel::sql_types::Uuid, _>(job_id)
+    .bind::<Text, _>(model)
+    .bind::<Text, _>(vector_literal(embedding))
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// The `limit` websites whose latest llms.txt's embedding of `model` is the most similar to `embedding`, most similar
+/// first.
+pub async fn nearest_llms_txt(
+    conn: &mut AsyncPgConnection,
+    model: &str,
+    embedding: &[f32],
+    limit: i64,
+) -> Result<Vec<Neighbor>, diesel::result::Error> {
+    // The HNSW index finds the nearest embeddings: the superseded records' are left out after, so more are asked for
+    diesel::sql_query(
+        "SELECT n.url, n.title, n.description, n.created_at, n.similarity FROM ( \
+             SELECT l.url, l.title, l.description, l.created_at, l.url_normalized, \
+                 (1 - (e.embedding <=> $2::vector))::real AS similarity \
+             FROM llms_txt_embedding e JOIN llms_txt l ON l.job_id = e.job_id \
+             WHERE e.model = $1 \
+             ORDER BY e.embedding <=> $2::vector \
+             LIMIT $3 * 4 \
+         ) n \
+         WHERE NOT EXISTS ( \
+             SELECT 1 FROM llms_txt newer \
+             WHERE newer.url_normalized = n.url_normalized AND newer.result_status = 'ok' AND newer.created_at > n.created_at \
+         ) \
+         ORDER BY n.similarity DESC, n.url \
+         LIMIT $3",
+    )
+    .bind::<Text, _>(model)
+    .bind::<Text, _>(vector_literal(embedding))
+    .bind::<BigInt, _>(limit)
+    .load(conn)
+    .await
+}
+
+/// The `limit` other websites whose latest llms.txt's embedding of `model` is the most similar to the one of `url`'s
+/// latest embedded llms.txt, most similar first. None are when it has no embedding of the model yet.
+pub async fn related_llms_txt(
+    conn: &mut AsyncPgConnection,
+    model: &str,
+    url: &str,
+    limit: i64,
+) -> Result<Vec<Neighbor>, diesel::result::Error> {
+    // Like `nearest_llms_txt`, with the URL's embedding as the query: the index is only used for a constant one
+    diesel::sql_query(
+        "WITH target AS ( \
+             SELECT l.url_normalized, e.embedding FROM llms_txt l \
+             JOIN llms_txt_embedding e ON e.job_id = l.job_id AND e.model = $1 \
+             WHERE l.url_normalized = normalize_url($2) AND l.result_status = 'ok' \
+             ORDER BY l.created_at DESC \
+             LIMIT 1 \
+         ) \
+         SELECT n.url, n.title, n.description, n.created_at, n.similarity FROM ( \
+             SELECT l.url, l.title, l.description, l.created_at, l.url_normalized, \
+                 (1 - (e.embedding <=> (SELECT embedding FROM target)))::real AS similarity \
+             FROM llms_txt_embedding e JOIN llms_txt l ON l.job_id = e.job_id \
+             WHERE e.model = $1 AND l.url_normalized <> (SELECT url_normalized FROM target) \
+             ORDER BY e.embedding <=> (SELECT embedding FROM target) \
+             LIMIT $3 * 4 \
+         ) n \
+         WHERE NOT EXISTS ( \
+             SELECT 1 FROM llms_txt newer \
+             WHERE newer.url_normalized = n.url_normalized AND newer.result_status = 'ok' AND newer.created_at > n.created_at \
+         ) \
+         ORDER BY n.similarity DESC, n.url \
+         LIMIT $3",
+    )
+    .bind::<Text, _>(model)
+    .bind::<Text, _>(url)
+    .bind::<BigInt, _>(limit)
+    .load(conn)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector_literal() {
+        assert_eq!(vector_literal(&[0.5, -1.0, 0.25]), "[0.5,-1,0.25]");
+        assert_eq!(vector_literal(&[]), "[]");
+    }
+}
@@ -1,5 +1,13 @@
+pub mod archive;
+pub mod changes;
+pub mod domain_policy;
+pub mod embeddings;
+pub mod migrations;
 pub mod models;
 pub mod schema;
+pub mod search;
+pub mod secrets;
+pub mod urls;
 
 // Make test_helpers available for tests in this crate and dependent crates
 #[cfg(any(test, feature = "test-helpers"))]
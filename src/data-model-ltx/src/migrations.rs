@@ -0,0 +1,103 @@
+//! Database migrations, compiled into the binaries so that they can bring the schema up to date when they start.
+
+use diesel::sql_types::BigInt;
+use diesel::{Connection, ConnectionError, PgConnection, RunQueryDsl};
+use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
+
+use core_ltx::{get_database_url, is_run_migrations_enabled};
+
+/// The migrations of `api-ltx/migrations`.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../api-ltx/migrations");
+
+/// Postgres advisory lock held while migrating, so that an API server and workers starting at the same time
+/// don't apply the same migration twice. The others wait for it, then find nothing left to do.
+const MIGRATION_LOCK_KEY: i64 = 0x6c74_785f_6d69_6772;
+
+#[derive(Debug)]
+pub enum MigrationError {
+    /// Couldn't connect to the database.
+    Connection(ConnectionError),
+    /// Couldn't take or release the advisory lock.
+    Lock(diesel::result::Error),
+    /// A migration failed. Diesel ran it in a transaction, so it's not partially applied.
+    Migration(String),
+}
+
+impl std::fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connection(e) => write!(f, "Failed to connect to the database: {}", e),
+            Self::Lock(e) => write!(f, "Failed to lock the database for migrating: {}", e),
+            Self::Migration(e) => write!(f, "Failed to run migrations: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+/// Applies the migrations that the database doesn't have yet. Returns their versions, oldest first.
+pub async fn run_pending_migrations(database_url: &str) -> Result<Vec<String>, MigrationError> {
+    let database_url = database_url.to_string();
+    // diesel's migration harness is synchronous
+    tokio::task::spawn_blocking(move || {
+        let mut conn = PgConnection::establish(&database_url).map_err(MigrationError::Connection)?;
+        diesel::sql_query("SELECT pg_advisory_lock($1)")
+            .bind::<BigInt, _>(MIGRATION_LOCK_KEY)
+            .execute(&mut conn)
+            .map_err(MigrationError::Lock)?;
+        let applied = conn
+            .run_pending_migrations(MIGRATIONS)
+            .map(|versions| versions.iter().map(|version| version.to_string()).collect())
+            .map_err(|e| MigrationError::Migration(e.to_string()));
+        diesel::sql_query("SELECT pg_advisory_unlock($1)")
+            .bind::<BigInt, _>(MIGRATION_LOCK_KEY)
+            .execute(&mut conn)
+            .map_err(MigrationError::Lock)?;
+        applied
+    })
+    .await
+    .map_err(|e| MigrationError::Migration(e.to_string()))?
+}
+
+/// When RUN_MIGRATIONS is set, applies pending migrations to the DATABASE_URL database.
+/// WARNING: Panics if they can't be applied! Nothing works against an outdated schema.
+pub async fn run_migrations_if_enabled() {
+    if !is_run_migrations_enabled() {
+        tracing::info!("Migrations: not run at startup (set RUN_MIGRATIONS=true to run them)");
+        return;
+    }
+    let database_url = get_database_url().expect("DATABASE_URL must be set in .env file or present as an env var");
+    match run_pending_migrations(&database_url).await {
+        Ok(applied) if applied.is_empty() => tracing::info!("Migrations: schema is up to date"),
+        Ok(applied) => tracing::info!("Migrations: applied {}", applied.join(", ")),
+        Err(e) => panic!("{}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{TestDbGuard, test_database_url};
+
+    #[tokio::test]
+    async fn test_concurrent_runners_apply_each_migration_once() {
+        let _db = TestDbGuard::acquire().await;
+        let database_url = test_database_url();
+
+        let (first, second) = tokio::join!(
+            run_pending_migrations(&database_url),
+            run_pending_migrations(&database_url)
+        );
+        let mut applied = first.unwrap();
+        applied.extend(second.unwrap());
+        let count = applied.len();
+        applied.sort();
+        applied.dedup();
+        assert_eq!(applied.len(), count, "A migration was applied twice");
+
+        assert_eq!(
+            run_pending_migrations(&database_url).await.unwrap(),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -1,19 +1,29 @@
 use axum::Json;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
+use base64::{Engine as _, engine::general_purpose};
 use chrono::{DateTime, Utc};
 use diesel::deserialize::{self, FromSql, FromSqlRow};
 use diesel::expression::AsExpression;
 use diesel::pg::{Pg, PgValue};
 use diesel::prelude::*;
 use diesel::serialize::{self, IsNull, Output, ToSql};
-use diesel::sql_types::SqlType;
-use serde::{Deserialize, Serialize};
+use diesel::sql_types::{SqlType, Text};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::json;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::io::Write;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use core_ltx::credentials::Credential;
 use core_ltx::db::PoolError;
+use core_ltx::domain_policy::DomainPolicy;
+use core_ltx::llms::{TokenUsage, prompt_version};
+use core_ltx::{SiteMetadata, compress_string, decompress_to_string};
+
+use crate::secrets::{Keyring, SecretsError};
 
 // SQL type definitions for custom enums
 // Note: These types use snake_case to match PostgreSQL type names
@@ -32,9 +42,19 @@ pub struct Job_kind;
 #[diesel(postgres_type(name = "result_status"))]
 pub struct Result_status;
 
+#[allow(non_camel_case_types)]
+#[derive(SqlType, diesel::query_builder::QueryId, Debug, Clone, Copy)]
+#[diesel(postgres_type(name = "llms_txt_source"))]
+pub struct Llms_txt_source;
+
+/// Postgres' full-text search document: only ever computed in queries (see `crate::search`).
+#[derive(SqlType, diesel::query_builder::QueryId, Debug, Clone, Copy)]
+#[diesel(postgres_type(name = "tsvector"))]
+pub struct Tsvector;
+
 // JobStatus enum
 /// Status of a job in the system
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow, ToSchema)]
 #[diesel(sql_type = Job_status)]
 pub enum JobStatus {
     /// A newly created job
@@ -45,14 +65,18 @@ pub enum JobStatus {
     Success,
     /// Worker failed
     Failure,
+    /// Still queued when its deadline (`expires_at`) passed, so it was never worked on
+    Expired,
+    /// Cancelled through the API before it finished: its worker abandons it, and no result is stored
+    Cancelled,
 }
 
 impl JobStatus {
-    // True if job's status is Success or Failure. False means it's Queued or Running.
+    // True if job's status is Success, Failure, Expired, or Cancelled. False means it's Queued or Running.
     pub fn is_completed(&self) -> bool {
         match self {
             Self::Queued | Self::Running => false,
-            Self::Success | Self::Failure => true,
+            Self::Success | Self::Failure | Self::Expired | Self::Cancelled => true,
         }
     }
 }
@@ -64,6 +88,8 @@ impl ToSql<Job_status, Pg> for JobStatus {
             JobStatus::Running => "running",
             JobStatus::Success => "success",
             JobStatus::Failure => "failure",
+            JobStatus::Expired => "expired",
+            JobStatus::Cancelled => "cancelled",
         };
         out.write_all(s.as_bytes())?;
         Ok(IsNull::No)
@@ -77,6 +103,8 @@ impl FromSql<Job_status, Pg> for JobStatus {
             b"running" => Ok(JobStatus::Running),
             b"success" => Ok(JobStatus::Success),
             b"failure" => Ok(JobStatus::Failure),
+            b"expired" => Ok(JobStatus::Expired),
+            b"cancelled" => Ok(JobStatus::Cancelled),
             _ => Err("Unrecognized enum variant".into()),
         }
     }
@@ -146,6 +174,39 @@ impl FromSql<Result_status, Pg> for ResultStatus {
     }
 }
 
+// LlmsTxtSource enum
+/// Where an llms.txt came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, AsExpression, FromSqlRow, ToSchema)]
+#[diesel(sql_type = Llms_txt_source)]
+pub enum LlmsTxtSource {
+    /// Generated (or updated) by the LLM from the website's HTML
+    #[default]
+    Generated,
+    /// Imported from the llms.txt the website publishes at its root (`/llms.txt`)
+    Origin,
+}
+
+impl ToSql<Llms_txt_source, Pg> for LlmsTxtSource {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let s = match self {
+            LlmsTxtSource::Generated => "generated",
+            LlmsTxtSource::Origin => "origin",
+        };
+        out.write_all(s.as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Llms_txt_source, Pg> for LlmsTxtSource {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        match bytes.as_bytes() {
+            b"generated" => Ok(LlmsTxtSource::Generated),
+            b"origin" => Ok(LlmsTxtSource::Origin),
+            _ => Err("Unrecognized enum variant".into()),
+        }
+    }
+}
+
 // job_state table model (database representation)
 #[derive(Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::job_state)]
@@ -157,6 +218,38 @@ pub struct JobState {
     pub kind: JobKind,
     pub llms_txt: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// W3C traceparent of the request that created the job, so the worker continues the same trace.
+    pub trace_context: Option<String>,
+    /// If the job is still queued after this, it's marked Expired instead of being worked on.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Why a failed job failed, as a `core_ltx::FailureKind` code. None unless the job failed.
+    pub failure_kind: Option<String>,
+    /// LLM to generate with, one of `core_ltx::llms::MODELS`. None for the worker's configured model.
+    pub model: Option<String>,
+    /// How detailed the llms.txt should be, as a `dto_ltx::Detail` name (e.g. `brief`).
+    /// Missing in backups made before jobs had a detail level.
+    #[serde(default = "default_detail")]
+    pub detail: String,
+    /// For an update job: regenerate only this H2 section of the llms.txt. None to update the whole file.
+    pub section: Option<String>,
+    /// Page to regenerate `section` from. None for the job's URL.
+    pub section_url: Option<String>,
+    /// For a crawl job: how many links away from the URL to follow. None unless the job crawls the website.
+    pub crawl_max_depth: Option<i32>,
+    /// For a crawl job: maximum number of pages to download.
+    pub crawl_max_pages: Option<i32>,
+    /// Which attempt at the generation the job is: 1, then one more for each retry of a failed job.
+    /// Missing in backups made before jobs were retried.
+    #[serde(default = "first_attempt")]
+    pub attempts: i32,
+}
+
+fn default_detail() -> String {
+    Detail::default().to_string()
+}
+
+fn first_attempt() -> i32 {
+    1
 }
 
 // JobKindData - ergonomic Rust enum for the job kind
@@ -181,6 +274,46 @@ impl JobState {
         }
     }
 
+    /// How detailed the llms.txt should be. Standard if the stored name isn't one we know.
+    pub fn detail(&self) -> Detail {
+        self.detail.parse().unwrap_or_default()
+    }
+
+    /// How far the job crawls the website. None if it only downloads its URL's page.
+    pub fn crawl(&self) -> Option<Crawl> {
+        match (self.crawl_max_depth, self.crawl_max_pages) {
+            (Some(max_depth), Some(max_pages)) => Some(Crawl {
+                max_depth: max_depth.max(0) as u32,
+                max_pages: max_pages.max(0) as u32,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Makes the job crawl the website within the limits, or only download its URL's page with None.
+    pub fn with_crawl(self, crawl: Option<Crawl>) -> Self {
+        JobState {
+            crawl_max_depth: crawl.map(|crawl| crawl.max_depth as i32),
+            crawl_max_pages: crawl.map(|crawl| crawl.max_pages as i32),
+            ..self
+        }
+    }
+
+    /// A queued job that tries this one again, as its next attempt: the same URL, kind (with the llms.txt to update)
+    /// and options, without the deadline, which was for the first attempt.
+    pub fn retry(self, job_id: Uuid) -> Self {
+        JobState {
+            job_id,
+            status: JobStatus::Queued,
+            created_at: Utc::now(),
+            trace_context: None,
+            expires_at: None,
+            failure_kind: None,
+            attempts: self.attempts + 1,
+            ..self
+        }
+    }
+
     /// Create database representation from ergonomic JobKindData enum
     pub fn from_kind_data(job_id: Uuid, url: String, status: JobStatus, kind_data: JobKindData) -> Self {
         let created_at = Utc::now();
@@ -192,6 +325,16 @@ impl JobState {
                 kind: JobKind::New,
                 llms_txt: None,
                 created_at,
+                trace_context: None,
+                expires_at: None,
+                failure_kind: None,
+                model: None,
+                detail: default_detail(),
+                section: None,
+                section_url: None,
+                crawl_max_depth: None,
+                crawl_max_pages: None,
+                attempts: first_attempt(),
             },
             JobKindData::Update { llms_txt } => JobState {
                 job_id,
@@ -200,11 +343,201 @@ impl JobState {
                 kind: JobKind::Update,
                 llms_txt: Some(llms_txt),
                 created_at,
+                trace_context: None,
+                expires_at: None,
+                failure_kind: None,
+                model: None,
+                detail: default_detail(),
+                section: None,
+                section_url: None,
+                crawl_max_depth: None,
+                crawl_max_pages: None,
+                attempts: first_attempt(),
             },
         }
     }
 }
 
+// job_logs table model (database representation)
+/// What the worker logged while working on a job.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::job_logs)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct JobLogs {
+    pub job_id: Uuid,
+    /// One event per line: `<timestamp> <LEVEL> <target>: <message> <fields>`.
+    pub log: String,
+    /// Later events were dropped because the log got too big.
+    pub truncated: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+// llm_usage table model (database representation)
+/// Tokens used by the LLM calls of a single job.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize)]
+#[diesel(table_name = crate::schema::llm_usage)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LlmUsage {
+    pub job_id: Uuid,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LlmUsage {
+    /// Records the usage of the job as of now.
+    pub fn new(job_id: Uuid, usage: TokenUsage) -> Self {
+        LlmUsage {
+            job_id,
+            prompt_tokens: usage.prompt_tokens as i64,
+            completion_tokens: usage.completion_tokens as i64,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Text at least this long is stored compressed in `result_data`. Shorter text doesn't shrink enough to be worth it.
+pub const RESULT_DATA_COMPRESS_MIN_BYTES: usize = 2 * 1024;
+
+/// Starts `result_data` that's stored compressed, followed by the codec (Brotli). No stored text can start with it:
+/// the worker removes control characters from what it stores, and `ResultData::new` compresses anything else that
+/// would.
+const BROTLI_MARKER: &str = "\u{1}br:";
+
+// ResultData - llms_txt.result_data
+/// `llms_txt.result_data`: the llms.txt, or why its generation failed, as it's stored.
+///
+/// Text of `RESULT_DATA_COMPRESS_MIN_BYTES` or more is stored Brotli-compressed, like `html_compress`: after the
+/// codec marker, and base64-encoded since the column is text. Shorter text, and every row stored before compression,
+/// is stored as is. It's only decompressed when it's read with `text`, so queries that just move it around don't pay
+/// for it. In JSON, it's always the text.
+#[derive(Debug, Clone, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct ResultData(String);
+
+impl ResultData {
+    /// Compresses the text if it's long enough, and if that makes it smaller.
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        // text that looks compressed is always compressed, so that it reads back as it was
+        let looks_compressed = text.starts_with(BROTLI_MARKER);
+        if text.len() < RESULT_DATA_COMPRESS_MIN_BYTES && !looks_compressed {
+            return ResultData(text);
+        }
+        match compress_string(&text) {
+            Ok(compressed) => {
+                let stored = format!("{}{}", BROTLI_MARKER, general_purpose::STANDARD.encode(compressed));
+                if stored.len() < text.len() || looks_compressed {
+                    ResultData(stored)
+                } else {
+                    ResultData(text)
+                }
+            }
+            Err(e) => {
+                tracing::warn!("[SKIP] Storing result_data uncompressed: {}", e);
+                ResultData(text)
+            }
+        }
+    }
+
+    /// `LIKE` pattern of the stored text that's compressed: queries can't look into it, so they have to select it
+    /// and check its `text`.
+    pub fn compressed_like_pattern() -> String {
+        format!("{}%", BROTLI_MARKER)
+    }
+
+    /// True when it's stored compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.0.starts_with(BROTLI_MARKER)
+    }
+
+    /// The text, decompressed when it's stored compressed. If it can't be decompressed (it was corrupted), it's the
+    /// stored text, and the error is logged.
+    pub fn text(&self) -> Cow<'_, str> {
+        let Some(encoded) = self.0.strip_prefix(BROTLI_MARKER) else {
+            return Cow::Borrowed(&self.0);
+        };
+        let decompressed = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| e.to_string())
+            .and_then(|compressed| decompress_to_string(&compressed).map_err(|e| e.to_string()));
+        match decompressed {
+            Ok(text) => Cow::Owned(text),
+            Err(e) => {
+                tracing::error!("Failed to decompress result_data, using it as stored: {}", e);
+                Cow::Borrowed(&self.0)
+            }
+        }
+    }
+
+    /// Like `text`, without copying text that's stored as is.
+    pub fn into_text(self) -> String {
+        if self.is_compressed() {
+            self.text().into_owned()
+        } else {
+            self.0
+        }
+    }
+}
+
+impl From<String> for ResultData {
+    fn from(text: String) -> Self {
+        ResultData::new(text)
+    }
+}
+
+impl From<&str> for ResultData {
+    fn from(text: &str) -> Self {
+        ResultData::new(text)
+    }
+}
+
+impl ToSql<Text, Pg> for ResultData {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <String as ToSql<Text, Pg>>::to_sql(&self.0, out)
+    }
+}
+
+impl FromSql<Text, Pg> for ResultData {
+    fn from_sql(bytes: PgValue) -> deserialize::Result<Self> {
+        <String as FromSql<Text, Pg>>::from_sql(bytes).map(ResultData)
+    }
+}
+
+impl Serialize for ResultData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text())
+    }
+}
+
+impl<'de> Deserialize<'de> for ResultData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(ResultData::new)
+    }
+}
+
+/// Lines of an llms.txt in its preview.
+pub const PREVIEW_MAX_LINES: usize = 20;
+
+/// Characters of an llms.txt in its preview, at most: a few very long lines don't make a long preview.
+pub const PREVIEW_MAX_CHARS: usize = 2000;
+
+/// The preview of an llms.txt that listings show: its first `PREVIEW_MAX_LINES` lines, up to `PREVIEW_MAX_CHARS`,
+/// without trailing newlines. True when there's more to it than the preview.
+///
+/// The `llms_txt_preview` migration computes the same for the rows it fills in: keep them in sync.
+pub fn llms_txt_preview(llms_txt: &str) -> (String, bool) {
+    let llms_txt = llms_txt.trim_end_matches('\n');
+    let lines = llms_txt
+        .split('\n')
+        .take(PREVIEW_MAX_LINES)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let preview: String = lines.chars().take(PREVIEW_MAX_CHARS).collect();
+    let truncated = preview.len() < llms_txt.len();
+    (preview, truncated)
+}
+
 // llms_txt table model (database representation)
 #[derive(Debug, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize)]
 #[diesel(table_name = crate::schema::llms_txt)]
@@ -212,12 +545,34 @@ impl JobState {
 pub struct LlmsTxt {
     pub job_id: Uuid,
     pub url: String,
-    pub result_data: String,
+    /// The llms.txt, or why its generation failed (see `ResultData`)
+    pub result_data: ResultData,
     pub result_status: ResultStatus,
     pub created_at: DateTime<Utc>,
     /// Brotli-compressed normalized HTML content (stored as raw bytes)
     pub html_compress: Vec<u8>,
     pub html_checksum: String,
+    /// Whether the LLM generated it, or it was imported from the website
+    pub source: LlmsTxtSource,
+    /// The page's `<title>`
+    pub title: Option<String>,
+    /// The page's meta description
+    pub description: Option<String>,
+    /// The page's canonical URL (`<link rel="canonical">`)
+    pub canonical_url: Option<String>,
+    /// The website's icon
+    pub favicon_url: Option<String>,
+    /// `core_ltx::llms::prompt_version` of the prompts it was generated with. None when it wasn't generated.
+    pub prompt_version: Option<String>,
+    /// LLM that generated it. None when it wasn't generated, or when that wasn't recorded.
+    pub model: Option<String>,
+    /// `dto_ltx::Detail` name of the level it was generated at. None when it wasn't generated, or wasn't recorded.
+    pub detail: Option<String>,
+    /// The start of the llms.txt that listings show (see `llms_txt_preview`). None for failures, and for the rows
+    /// stored compressed before previews existed: their preview is computed when they're listed.
+    pub preview: Option<String>,
+    /// Whether the llms.txt is longer than its preview.
+    pub preview_truncated: bool,
 }
 
 impl PartialEq for LlmsTxt {
@@ -230,6 +585,438 @@ impl PartialEq for LlmsTxt {
     }
 }
 
+// llms_txt_archive table model: a superseded llms_txt row (see `crate::archive`)
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::llms_txt_archive)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ArchivedLlmsTxt {
+    pub job_id: Uuid,
+    pub url: String,
+    pub result_data: ResultData,
+    pub result_status: ResultStatus,
+    pub created_at: DateTime<Utc>,
+    pub html_compress: Vec<u8>,
+    pub html_checksum: String,
+    pub source: LlmsTxtSource,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub favicon_url: Option<String>,
+    pub prompt_version: Option<String>,
+    pub model: Option<String>,
+    pub detail: Option<String>,
+    /// When it was moved out of `llms_txt`
+    pub archived_at: DateTime<Utc>,
+}
+
+impl From<ArchivedLlmsTxt> for LlmsTxt {
+    /// The row as it was in `llms_txt`, without its preview: it isn't listed anymore.
+    fn from(row: ArchivedLlmsTxt) -> Self {
+        LlmsTxt {
+            job_id: row.job_id,
+            url: row.url,
+            result_data: row.result_data,
+            result_status: row.result_status,
+            created_at: row.created_at,
+            html_compress: row.html_compress,
+            html_checksum: row.html_checksum,
+            source: row.source,
+            title: row.title,
+            description: row.description,
+            canonical_url: row.canonical_url,
+            favicon_url: row.favicon_url,
+            prompt_version: row.prompt_version,
+            model: row.model,
+            detail: row.detail,
+            preview: None,
+            preview_truncated: false,
+        }
+    }
+}
+
+/// The columns of an `llms_txt` row that listings show: everything but the llms.txt itself and the HTML.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::llms_txt)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct LlmsTxtPreviewRow {
+    pub job_id: Uuid,
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub canonical_url: Option<String>,
+    pub favicon_url: Option<String>,
+    pub prompt_version: Option<String>,
+    pub model: Option<String>,
+    pub detail: Option<String>,
+    /// None for the rows stored compressed before previews existed (see `LlmsTxt::preview`)
+    pub preview: Option<String>,
+    pub preview_truncated: bool,
+}
+
+impl LlmsTxtPreviewRow {
+    /// Sets the preview of the llms.txt, for the rows that don't have one.
+    pub fn with_preview(self, llms_txt: &str) -> Self {
+        let (preview, preview_truncated) = llms_txt_preview(llms_txt);
+        LlmsTxtPreviewRow {
+            preview: Some(preview),
+            preview_truncated,
+            ..self
+        }
+    }
+}
+
+// regeneration_campaign table model (database representation)
+/// A bulk re-generation: the filters that picked the URLs, and how far enqueuing their Update jobs has got.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::regeneration_campaign)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RegenerationCampaign {
+    pub campaign_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    /// When the last batch was enqueued. None while the campaign is running.
+    pub finished_at: Option<DateTime<Utc>>,
+    pub domain: Option<String>,
+    pub older_than: Option<DateTime<Utc>>,
+    pub provider: Option<LlmsTxtSource>,
+    pub quality_below: Option<i32>,
+    pub outdated_prompt: bool,
+    /// Number of URLs that matched the filters
+    pub total: i32,
+    /// Update jobs created so far
+    pub enqueued: i32,
+    /// URLs passed over because they already had a queued or running job
+    pub skipped: i32,
+    /// URLs whose job couldn't be created
+    pub failed: i32,
+}
+
+// crawl_schedule table model (database representation)
+/// A recurring full-site re-crawl of a URL. Cron enqueues a crawl job for it once `next_run_at` has passed, instead of
+/// checking the URL's page for changes: pages deeper in the website can change while the URL's page doesn't.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::crawl_schedule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CrawlSchedule {
+    pub url: String,
+    /// Seconds between crawls
+    pub interval_s: i64,
+    pub max_depth: i32,
+    pub max_pages: i32,
+    /// When cron enqueues the next crawl job
+    pub next_run_at: DateTime<Utc>,
+    /// When cron last enqueued a crawl job. None until it has.
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl CrawlSchedule {
+    /// The limits of every crawl.
+    pub fn crawl(&self) -> Crawl {
+        Crawl {
+            max_depth: self.max_depth.max(0) as u32,
+            max_pages: self.max_pages.max(0) as u32,
+        }
+    }
+
+    /// Whether its next crawl job should be enqueued by `now`.
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.next_run_at <= now
+    }
+
+    /// When the crawl after the one enqueued at `run_at` is due.
+    pub fn next_run_after(&self, run_at: DateTime<Utc>) -> DateTime<Utc> {
+        run_at + chrono::Duration::seconds(self.interval_s)
+    }
+}
+
+/// Whether a domain policy pattern allows or blocks the hosts it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DomainRule {
+    /// Once there's one allow pattern, only the hosts that match an allow pattern can be indexed
+    Allow,
+    /// Hosts that match a block pattern can't be indexed
+    Block,
+}
+
+impl DomainRule {
+    /// Name stored in the database, e.g. `allow`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Block => "block",
+        }
+    }
+}
+
+// domain_policy table model (database representation)
+/// One pattern of the server's domain policy (see `core_ltx::domain_policy::DomainPolicy`).
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::domain_policy)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DomainPolicyEntry {
+    /// Glob of hosts, e.g. `*.example.com`
+    pub pattern: String,
+    /// A `DomainRule` name: `allow` or `block`
+    pub rule: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The domain policy the entries make up.
+pub fn to_domain_policy(entries: &[DomainPolicyEntry]) -> DomainPolicy {
+    let patterns = |rule: DomainRule| {
+        entries
+            .iter()
+            .filter(|entry| entry.rule == rule.as_str())
+            .map(|entry| entry.pattern.clone())
+            .collect()
+    };
+    DomainPolicy {
+        allow: patterns(DomainRule::Allow),
+        block: patterns(DomainRule::Block),
+    }
+}
+
+// domains table model (database representation)
+/// The credential the worker authenticates to a domain with. Not serialized: the credential is a secret, even
+/// encrypted. The API shows a `DomainSummary` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::domains)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct DomainCredential {
+    /// Host the credential is sent to, e.g. `docs.internal.example.com`
+    pub domain: String,
+    /// `Credential::kind`: kept in the clear so listing the domains needs no key
+    pub credential_kind: String,
+    /// The JSON of the `Credential`, encrypted by the `Keyring`
+    pub credential: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DomainCredential {
+    /// The domain's credential, encrypted with the keyring's current key.
+    pub fn new(domain: String, credential: &Credential, keyring: &Keyring, now: DateTime<Utc>) -> Self {
+        let json = serde_json::to_string(credential).expect("a Credential always serializes");
+        Self {
+            domain,
+            credential_kind: credential.kind().to_string(),
+            credential: keyring.encrypt(&json),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// The credential, decrypted with the key of the keyring it was encrypted with.
+    pub fn decrypt(&self, keyring: &Keyring) -> Result<Credential, SecretsError> {
+        let json = keyring.decrypt(&self.credential)?;
+        serde_json::from_str(&json).map_err(|_| SecretsError::Malformed)
+    }
+}
+
+// webhooks table model (database representation)
+/// An endpoint that's notified of events, e.g. finished jobs. Not serialized: its signing secret is in it. The API
+/// shows a `WebhookSummary` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::webhooks)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct Webhook {
+    pub webhook_id: Uuid,
+    /// Where events are POSTed to
+    pub url: String,
+    /// The key payloads are signed with, encrypted by the `Keyring`
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    /// A webhook for the URL, with its signing secret encrypted with the keyring's current key.
+    pub fn new(url: String, secret: &str, keyring: &Keyring, now: DateTime<Utc>) -> Self {
+        Self {
+            webhook_id: Uuid::new_v4(),
+            url,
+            secret: keyring.encrypt(secret),
+            created_at: now,
+        }
+    }
+
+    /// A new random signing secret: `whsec_` then 32 random bytes in hex.
+    pub fn generate_secret() -> String {
+        let bytes: [u8; 32] = rand::random();
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("whsec_{}", hex)
+    }
+
+    /// The signing secret, decrypted with the key of the keyring it was encrypted with.
+    pub fn decrypt_secret(&self, keyring: &Keyring) -> Result<String, SecretsError> {
+        keyring.decrypt(&self.secret)
+    }
+}
+
+// totp table model (database representation)
+/// The login's second factor: the TOTP secret shared with an authenticator app. There's at most one, since there's one
+/// password. Not serialized: the secret is in it.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::totp)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TotpSecret {
+    /// Always true: the table has a single row
+    pub id: bool,
+    /// The base32 secret, encrypted by the `Keyring`
+    pub secret: String,
+    /// When a code confirmed the enrollment. Logins only need a code once it's confirmed.
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// Time step of the last accepted code: codes of this step or earlier are rejected
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TotpSecret {
+    /// A pending enrollment of the base32 secret, encrypted with the keyring's current key.
+    pub fn new(secret: &str, keyring: &Keyring, now: DateTime<Utc>) -> Self {
+        Self {
+            id: true,
+            secret: keyring.encrypt(secret),
+            confirmed_at: None,
+            last_used_step: None,
+            created_at: now,
+        }
+    }
+
+    /// The base32 secret, decrypted with the key of the keyring it was encrypted with.
+    pub fn decrypt_secret(&self, keyring: &Keyring) -> Result<String, SecretsError> {
+        keyring.decrypt(&self.secret)
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
+
+// totp_recovery_codes table model (database representation)
+/// A one-time code that logs in without the authenticator app, by its SHA-256.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::totp_recovery_codes)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TotpRecoveryCode {
+    /// Hex SHA-256 of the code
+    pub code_hash: String,
+    /// When it logged in. It can't be used again.
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+// api_keys table model (database representation)
+/// A key authenticating requests as an `Authorization: Bearer` header, by its SHA-256. Not serialized: the summary in
+/// listings leaves the hash out.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::api_keys)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiKey {
+    pub key_id: Uuid,
+    /// What it's for, e.g. "cron on build server"
+    pub name: String,
+    /// Hex SHA-256 of the key
+    pub key_hash: String,
+    /// The start of the key, to tell it apart from the others
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+    /// When it last authenticated a request
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// When it was revoked. It doesn't authenticate anymore.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Length of the `prefix` kept of a key: `ltx_` and 8 hex digits.
+    pub const PREFIX_LEN: usize = 12;
+
+    /// A new random key: `ltx_` then 32 random bytes in hex.
+    pub fn generate_key() -> String {
+        let bytes: [u8; 32] = rand::random();
+        let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("ltx_{}", hex)
+    }
+}
+
+/// Where an event is in being delivered to a webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// Not delivered yet: it's (re)tried once its `next_attempt_at` passes
+    Pending,
+    /// The webhook answered with a 2xx
+    Delivered,
+    /// Every attempt failed: it's not retried anymore
+    Failed,
+}
+
+impl DeliveryStatus {
+    /// Name stored in the database, e.g. `pending`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+// webhook_deliveries table model (database representation)
+/// One event to deliver to one webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::webhook_deliveries)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDelivery {
+    pub delivery_id: Uuid,
+    pub webhook_id: Uuid,
+    /// Name of the event, e.g. `job.finished`
+    pub event: String,
+    /// The JSON body that's POSTed
+    pub payload: String,
+    /// A `DeliveryStatus` name: `pending`, `delivered`, or `failed`
+    pub status: String,
+    /// Number of attempts made so far
+    pub attempts: i32,
+    /// When it's (re)tried, while it's pending
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    /// A delivery of the event to the webhook, to attempt right away.
+    pub fn new(webhook_id: Uuid, event: &str, payload: String, now: DateTime<Utc>) -> Self {
+        Self {
+            delivery_id: Uuid::new_v4(),
+            webhook_id,
+            event: event.to_string(),
+            payload,
+            status: DeliveryStatus::Pending.as_str().to_string(),
+            attempts: 0,
+            next_attempt_at: now,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+}
+
+// webhook_delivery_attempts table model (database representation)
+/// One attempt to deliver an event: what the webhook answered, or why it couldn't be reached.
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Selectable, Insertable, Serialize, Deserialize, ToSchema)]
+#[diesel(table_name = crate::schema::webhook_delivery_attempts)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookDeliveryAttempt {
+    pub delivery_id: Uuid,
+    /// 1 for the first attempt
+    pub attempt: i32,
+    /// HTTP status the webhook answered with. None when there was no response.
+    pub status_code: Option<i32>,
+    /// Why the attempt failed: the connection error, or the start of the response body
+    pub error: Option<String>,
+    pub attempted_at: DateTime<Utc>,
+}
+
 // LlmsTxtResult - ergonomic Rust enum for the result
 /// Result of fetching an llms.txt file
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -246,15 +1033,16 @@ impl LlmsTxt {
     pub fn to_result(&self) -> LlmsTxtResult {
         match self.result_status {
             ResultStatus::Ok => LlmsTxtResult::Ok {
-                llms_txt: self.result_data.clone(),
+                llms_txt: self.result_data.text().into_owned(),
             },
             ResultStatus::Error => LlmsTxtResult::Error {
-                failure_reason: self.result_data.clone(),
+                failure_reason: self.result_data.text().into_owned(),
             },
         }
     }
 
-    /// Create database representation from ergonomic Result enum.
+    /// Create database representation from ergonomic Result enum, for an llms.txt the LLM generated with the current
+    /// prompts (see `with_source` for an imported one), without site metadata (see `with_metadata`).
     /// `html_compress` is Brotli-compressed normalized HTML bytes.
     /// `html_checksum` is the MD5 checksum of the normalized (pre-compression) HTML.
     pub fn from_result(
@@ -267,32 +1055,99 @@ impl LlmsTxt {
         let created_at = Utc::now();
 
         match result {
-            LlmsTxtResult::Ok { llms_txt } => LlmsTxt {
-                job_id,
-                url,
-                result_data: llms_txt,
-                result_status: ResultStatus::Ok,
-                created_at,
-                html_compress,
-                html_checksum,
-            },
+            LlmsTxtResult::Ok { llms_txt } => {
+                let (preview, preview_truncated) = llms_txt_preview(&llms_txt);
+                LlmsTxt {
+                    job_id,
+                    url,
+                    result_data: ResultData::new(llms_txt),
+                    result_status: ResultStatus::Ok,
+                    created_at,
+                    html_compress,
+                    html_checksum,
+                    source: LlmsTxtSource::Generated,
+                    title: None,
+                    description: None,
+                    canonical_url: None,
+                    favicon_url: None,
+                    prompt_version: Some(prompt_version().to_string()),
+                    model: None,
+                    detail: None,
+                    preview: Some(preview),
+                    preview_truncated,
+                }
+            }
             LlmsTxtResult::Error { failure_reason } => LlmsTxt {
                 job_id,
                 url,
-                result_data: failure_reason,
+                result_data: ResultData::new(failure_reason),
                 result_status: ResultStatus::Error,
                 created_at,
                 html_compress,
                 html_checksum,
+                source: LlmsTxtSource::Generated,
+                title: None,
+                description: None,
+                canonical_url: None,
+                favicon_url: None,
+                prompt_version: Some(prompt_version().to_string()),
+                model: None,
+                detail: None,
+                preview: None,
+                preview_truncated: false,
+            },
+        }
+    }
+
+    /// Sets where it came from. An imported llms.txt wasn't made with a prompt, so it has no prompt version, model
+    /// or detail level.
+    pub fn with_source(self, source: LlmsTxtSource) -> Self {
+        match source {
+            LlmsTxtSource::Generated => LlmsTxt { source, ..self },
+            LlmsTxtSource::Origin => LlmsTxt {
+                source,
+                prompt_version: None,
+                model: None,
+                detail: None,
+                ..self
             },
         }
     }
+
+    /// Records the LLM that generated it.
+    pub fn with_model(self, model: Option<String>) -> Self {
+        LlmsTxt { model, ..self }
+    }
+
+    /// Records the detail level it was generated at.
+    pub fn with_detail(self, detail: Detail) -> Self {
+        LlmsTxt {
+            detail: Some(detail.to_string()),
+            ..self
+        }
+    }
+
+    /// The detail level it was generated at, if that's known.
+    pub fn detail(&self) -> Option<Detail> {
+        self.detail.as_deref().and_then(|detail| detail.parse().ok())
+    }
+
+    /// Adds what the page says about itself.
+    pub fn with_metadata(self, metadata: SiteMetadata) -> Self {
+        LlmsTxt {
+            title: metadata.title,
+            description: metadata.description,
+            canonical_url: metadata.canonical_url,
+            favicon_url: metadata.favicon_url,
+            ..self
+        }
+    }
 }
 
 // API Error Types
 
 /// Error for GET /api/llm_txt endpoint
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "error", content = "details")]
 pub enum GetLlmTxtError {
     /// llms.txt has not been generated for this URL yet
@@ -307,7 +1162,7 @@ pub enum GetLlmTxtError {
 }
 
 /// Error for POST /api/llm_txt endpoint
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "error", content = "details")]
 pub enum PostLlmTxtError {
     /// llms.txt has already been generated for this URL
@@ -316,102 +1171,883 @@ pub enum PostLlmTxtError {
     /// llms.txt jobs are in progress for this URL
     #[serde(rename = "jobs_in_progress")]
     JobsInProgress(Vec<Uuid>),
+    /// The URL isn't a valid http(s) URL
+    #[serde(rename = "invalid_url")]
+    InvalidUrl(String),
+    /// The requested model isn't one of the supported models
+    #[serde(rename = "unknown_model")]
+    UnknownModel(String),
+    /// The section to regenerate isn't in the llms.txt, or can't be regenerated from the requested page
+    #[serde(rename = "invalid_section")]
+    InvalidSection(String),
+    /// The server's domain policy doesn't allow indexing the URL's host, or it's on a private network
+    #[serde(rename = "domain_not_allowed")]
+    DomainNotAllowed(String),
+    /// The queue is backed up and the request was low priority: try again later
+    #[serde(rename = "queue_busy")]
+    QueueBusy(QueueLoad),
     /// Unknown error occurred
     #[serde(rename = "unknown")]
     Unknown(String),
 }
 
 /// Error for PUT /api/llm_txt endpoint
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "error", content = "details")]
 pub enum PutLlmTxtError {
+    /// A job for this URL is already queued or running
+    #[serde(rename = "jobs_in_progress")]
+    JobsInProgress(Vec<Uuid>),
+    /// The URL isn't a valid http(s) URL
+    #[serde(rename = "invalid_url")]
+    InvalidUrl(String),
+    /// The requested model isn't one of the supported models
+    #[serde(rename = "unknown_model")]
+    UnknownModel(String),
+    /// The section to regenerate isn't in the llms.txt, or can't be regenerated from the requested page
+    #[serde(rename = "invalid_section")]
+    InvalidSection(String),
+    /// The server's domain policy doesn't allow indexing the URL's host, or it's on a private network
+    #[serde(rename = "domain_not_allowed")]
+    DomainNotAllowed(String),
+    /// The queue is backed up and the request was low priority: try again later
+    #[serde(rename = "queue_busy")]
+    QueueBusy(QueueLoad),
     /// Unknown error occurred
     #[serde(rename = "unknown")]
     Unknown(String),
 }
 
-/// Error for GET /api/status endpoint
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Error for DELETE /api/llm_txt endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "error", content = "details")]
-pub enum StatusError {
-    /// The provided job_id is not a valid UUID
-    #[serde(rename = "invalid_id")]
-    InvalidId,
-    /// The job_id was not found in the database
-    #[serde(rename = "unknown_id")]
-    UnknownId,
+pub enum DeleteLlmTxtError {
+    /// There's no llms.txt, nor any job, for this URL
+    #[serde(rename = "not_generated")]
+    NotGenerated,
+    /// A job for this URL is queued or running: it would write its result after the deletion
+    #[serde(rename = "jobs_in_progress")]
+    JobsInProgress(Vec<Uuid>),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for GET /api/status endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum StatusError {
+    /// The provided job_id is not a valid UUID
+    #[serde(rename = "invalid_id")]
+    InvalidId,
+    /// The job_id was not found in the database
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/status/batch endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum BatchStatusError {
+    /// There are no job_ids, or too many of them
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/import endpoint: nothing is imported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum ImportError {
+    /// The body isn't an export in the format, or one of its records is invalid
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/admin/jobs/purge_stale endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum PurgeStaleJobsError {
+    /// The threshold is 0, or longer than there's been time
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/job/cancel endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum CancelJobError {
+    /// The job_id was not found in the database
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// The job already finished (or was cancelled already): there's nothing to cancel
+    #[serde(rename = "already_finished")]
+    AlreadyFinished(JobStatus),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/job/retry endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum RetryJobError {
+    /// The job_id was not found in the database
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// Only failed jobs are retried: the job has this status
+    #[serde(rename = "not_failed")]
+    NotFailed(JobStatus),
+    /// A job for the URL is already queued or running
+    #[serde(rename = "jobs_in_progress")]
+    JobsInProgress(Vec<Uuid>),
+    /// The server's domain policy doesn't allow indexing the URL's host anymore
+    #[serde(rename = "domain_not_allowed")]
+    DomainNotAllowed(String),
     /// Unknown error occurred
     #[serde(rename = "unknown")]
     Unknown(String),
 }
 
 /// Error for POST /api/update endpoint
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(tag = "error", content = "details")]
 pub enum UpdateLlmTxtError {
     /// llms.txt has not been generated for this URL yet
     #[serde(rename = "not_generated")]
     NotGenerated,
+    /// A job for this URL is already queued or running
+    #[serde(rename = "jobs_in_progress")]
+    JobsInProgress(Vec<Uuid>),
+    /// The URL isn't a valid http(s) URL
+    #[serde(rename = "invalid_url")]
+    InvalidUrl(String),
+    /// The requested model isn't one of the supported models
+    #[serde(rename = "unknown_model")]
+    UnknownModel(String),
+    /// The section to regenerate isn't in the llms.txt, or can't be regenerated from the requested page
+    #[serde(rename = "invalid_section")]
+    InvalidSection(String),
+    /// The server's domain policy doesn't allow indexing the URL's host, or it's on a private network
+    #[serde(rename = "domain_not_allowed")]
+    DomainNotAllowed(String),
+    /// The queue is backed up and the request was low priority: try again later
+    #[serde(rename = "queue_busy")]
+    QueueBusy(QueueLoad),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/list and /api/jobs endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum ListError {
+    /// The page or the number of items per page is out of range, or a filter is invalid
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/search endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum SearchError {
+    /// The query is empty, or the number of results is out of range
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Semantic search is off: there's no `EMBEDDING_MODEL`, or the database has no pgvector
+    #[serde(rename = "not_enabled")]
+    NotEnabled(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for GET /api/llm_txt/related endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum RelatedLlmTxtError {
+    /// The number of results is out of range
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// llms.txt has not been generated for this URL yet
+    #[serde(rename = "not_generated")]
+    NotGenerated,
+    /// Semantic search is off: there's no `EMBEDDING_MODEL`, or the database has no pgvector
+    #[serde(rename = "not_enabled")]
+    NotEnabled(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/admin/regenerate_all endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum CampaignError {
+    /// A filter or throttling setting is out of range
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// There's no campaign with the campaign_id
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/admin/domain_policy endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum DomainPolicyError {
+    /// The pattern isn't a glob of hosts
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// The domain policy has no such pattern
+    #[serde(rename = "unknown_pattern")]
+    UnknownPattern,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/admin/domains endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum DomainError {
+    /// The domain isn't a host, or the credential is empty
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Credentials can't be stored: the server has no (valid) `SECRETS_KEYS`
+    #[serde(rename = "key_not_configured")]
+    KeyNotConfigured(String),
+    /// The domain has no credential
+    #[serde(rename = "unknown_domain")]
+    UnknownDomain,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for POST /api/admin/secrets/rotate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum RotateSecretsError {
+    /// The server has no (valid) `SECRETS_KEYS`
+    #[serde(rename = "key_not_configured")]
+    KeyNotConfigured(String),
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/admin/webhooks endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum WebhookError {
+    /// The webhook URL isn't a valid http(s) URL, or the number of deliveries asked for is out of range
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// Signing secrets can't be stored: the server has no (valid) `SECRETS_KEYS`
+    #[serde(rename = "key_not_configured")]
+    KeyNotConfigured(String),
+    /// There's no webhook with the webhook_id
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/admin/api_keys endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum ApiKeyError {
+    /// The key has no name
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// There's no (unrevoked) key with the key_id
+    #[serde(rename = "unknown_id")]
+    UnknownId,
+    /// Unknown error occurred
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
+/// Error for the /api/crawl_schedule endpoints
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum CrawlScheduleError {
+    /// The URL isn't valid, or the interval or the crawl limits are out of range
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// The URL has no crawl schedule
+    #[serde(rename = "unknown_url")]
+    UnknownUrl,
     /// Unknown error occurred
     #[serde(rename = "unknown")]
     Unknown(String),
 }
 
+/// Error for POST /api/llm_txt/batch, when the whole batch is rejected. Each URL's own error is in its result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "error", content = "details")]
+pub enum BatchLlmTxtError {
+    /// There are no URLs, or too many of them
+    #[serde(rename = "invalid_request")]
+    InvalidRequest(String),
+    /// The requested model isn't one of the supported models
+    #[serde(rename = "unknown_model")]
+    UnknownModel(String),
+    /// Unknown error occurred: no job was created
+    #[serde(rename = "unknown")]
+    Unknown(String),
+}
+
 // API Payload Types
+// The ones shared with the frontend and the CLI are in dto-ltx: the conversions from the database models are here.
+
+pub use dto_ltx::{
+    BatchStatusPayload, BatchStatusResponse, Crawl, Detail, JobDetailsResponse, JobIdPayload, JobIdResponse,
+    JobLogsResponse, JobRequestPayload, JobStatusFilter, JobStatusResponse, JobSummary, JobsListResponse, JobsPage,
+    ListPage, ListSort, LiveEvent, LlmTxtResponse, LlmsTxtHistoryResponse, LlmsTxtListItem, LlmsTxtListResponse,
+    LlmsTxtVersion, QueueLoad, RelatedLlmsTxtResponse, RelatedSite, UrlPayload,
+};
+
+/// A website's latest llms.txt, with its metadata, as GET /api/export exports it: a line of the NDJSON export (with
+/// the llms.txt), or of the `index.ndjson` of the tar.gz export (with where the llms.txt is in the archive)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExportRecord {
+    pub url: String,
+    /// The job that generated it
+    pub job_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub source: LlmsTxtSource,
+    /// The website's title, description, canonical URL and icon, when they were found on the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon_url: Option<String>,
+    /// Version of the prompts it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// LLM it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Detail level it was generated at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+    /// The llms.txt. Only in the NDJSON export
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llms_txt: Option<String>,
+    /// Where the llms.txt is in the archive. Only in the tar.gz export's `index.ndjson`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// Response payload for POST /api/import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ImportResponse {
+    /// Number of llms.txt files imported, each with a finished job
+    pub imported: u64,
+    /// Number of llms.txt files skipped because their website already has one
+    pub skipped_existing: u64,
+    /// Number of llms.txt files skipped because their job is already in the database, e.g. imported before
+    pub skipped_duplicate: u64,
+}
 
-/// Input payload for endpoints that accept a URL
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UrlPayload {
+/// Response payload for DELETE /api/llm_txt: what was removed for the URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DeleteLlmTxtResponse {
     pub url: String,
+    /// Number of llms.txt records (successful or failed generations) removed
+    pub deleted_llms_txt: u64,
+    /// Number of jobs removed, with their logs and token usage
+    pub deleted_jobs: u64,
+    /// Whether the website's crawl schedule was removed too
+    pub deleted_crawl_schedule: bool,
 }
 
-/// Input payload for /api/status endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JobIdPayload {
-    pub job_id: Uuid,
+/// Input payload for POST /api/llm_txt/batch: the URLs to generate a new llms.txt for, and the options of every job
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct BatchJobRequestPayload {
+    /// At most `BatchJobRequestPayload::MAX_URLS`. Repeated URLs get a single job.
+    pub urls: Vec<String>,
+    /// Deadline for starting the jobs: the ones still queued then are marked Expired instead of being worked on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// LLM to generate with, one of `core_ltx::llms::MODELS`. The worker's configured model when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Rather not queue the URLs at all than wait behind a backed-up queue: they fail with `queue_busy` when it is.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub low_priority: bool,
+    /// How detailed the llms.txt files should be: `standard` when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+    /// Crawl each website from its URL, and generate its llms.txt from every page found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crawl: Option<Crawl>,
 }
 
-/// Response payload containing a job ID
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JobIdResponse {
-    pub job_id: Uuid,
+impl BatchJobRequestPayload {
+    pub const MAX_URLS: usize = 100;
+
+    /// The request for the job of one of the URLs.
+    pub fn job(&self, url: &str) -> JobRequestPayload {
+        JobRequestPayload {
+            url: url.to_string(),
+            expires_at: self.expires_at,
+            model: self.model.clone(),
+            low_priority: self.low_priority,
+            detail: self.detail,
+            crawl: self.crawl,
+            ..Default::default()
+        }
+    }
 }
 
-/// Response payload for GET /api/llm_txt endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LlmTxtResponse {
-    pub content: String,
+/// What POST /api/llm_txt/batch did for one URL: the job created for it, or why there's none, as POST /api/llm_txt
+/// would have answered
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum BatchJobResult {
+    Created(JobIdResponse),
+    Failed(PostLlmTxtError),
 }
 
-/// Response payload for GET /api/status endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JobStatusResponse {
-    pub status: JobStatus,
-    pub kind: JobKind,
+/// Response payload for POST /api/llm_txt/batch: the result of every URL, by URL
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BatchJobResponse {
+    pub results: BTreeMap<String, BatchJobResult>,
+}
+
+/// Input payload for POST /api/admin/regenerate_all. Every filter is optional: without any, all URLs match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct RegenerateAllPayload {
+    /// Only URLs on this host or its subdomains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// Only llms.txt files stored before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub older_than: Option<DateTime<Utc>>,
+    /// Only llms.txt files from this source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<LlmsTxtSource>,
+    /// Only llms.txt files whose quality score (0-100, see `core_ltx::lint::quality_score`) is below this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_below: Option<u8>,
+    /// Only generated llms.txt files whose prompt version isn't the current one
+    #[serde(default)]
+    pub outdated_prompt: bool,
+    /// Jobs enqueued per batch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<u32>,
+    /// Seconds to wait between batches, so the workers aren't flooded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_interval_s: Option<u64>,
+}
+
+/// Input payload for GET /api/admin/regenerate_all
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CampaignIdPayload {
+    pub campaign_id: Uuid,
+}
+
+/// What POST /api/admin/jobs/purge_stale does with the stale jobs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StaleJobAction {
+    /// Queue them again, for another worker to claim
+    #[default]
+    Requeue,
+    /// Mark them failed, with an `internal` failure kind: they can be retried with POST /api/job/retry
+    Fail,
 }
 
-/// Individual item in the list response
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LlmsTxtListItem {
+/// Input payload for POST /api/admin/jobs/purge_stale
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct PurgeStaleJobsPayload {
+    /// Seconds a job has been running for before it counts as stale: one hour when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub running_for_s: Option<u64>,
+    #[serde(default)]
+    pub action: StaleJobAction,
+}
+
+/// Response payload for POST /api/admin/jobs/purge_stale
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PurgeStaleJobsResponse {
+    /// The jobs that were requeued or failed, in job_id order
+    pub job_ids: Vec<Uuid>,
+}
+
+/// Input payload for PUT /api/admin/domain_policy
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainPolicyPayload {
+    /// Glob of hosts, e.g. `*.example.com`
+    pub pattern: String,
+    pub rule: DomainRule,
+}
+
+/// Input payload for DELETE /api/admin/domain_policy
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PatternPayload {
+    pub pattern: String,
+}
+
+/// Response payload for GET /api/admin/domain_policy
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainPolicyResponse {
+    /// Every pattern, in pattern order
+    pub entries: Vec<DomainPolicyEntry>,
+    /// Whether jobs can target hosts on private networks (`ALLOW_PRIVATE_HOSTS`)
+    pub private_hosts_allowed: bool,
+}
+
+/// Input payload for PUT /api/admin/domains
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainCredentialPayload {
+    /// Host to authenticate to, e.g. `docs.internal.example.com`
+    pub domain: String,
+    #[schema(value_type = Object)]
+    pub credential: Credential,
+}
+
+/// Input payload for DELETE /api/admin/domains
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DomainPayload {
+    pub domain: String,
+}
+
+/// A domain that has a credential, without the credential
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DomainSummary {
+    pub domain: String,
+    /// `basic`, `bearer`, or `cookie`
+    pub credential_kind: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<DomainCredential> for DomainSummary {
+    fn from(domain: DomainCredential) -> Self {
+        Self {
+            domain: domain.domain,
+            credential_kind: domain.credential_kind,
+            created_at: domain.created_at,
+            updated_at: domain.updated_at,
+        }
+    }
+}
+
+/// Response payload for GET /api/admin/domains
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DomainsResponse {
+    /// Every domain that has a credential, in domain order
+    pub domains: Vec<DomainSummary>,
+}
+
+/// Response payload for POST /api/admin/secrets/rotate
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RotateSecretsResponse {
+    /// Id of the key every secret is now encrypted with (but the `failed` ones)
+    pub key_id: String,
+    /// Number of secrets re-encrypted with the current key
+    pub rotated: u64,
+    /// Number of secrets that were already encrypted with it
+    pub up_to_date: u64,
+    /// The secrets that can't be decrypted by any key of `SECRETS_KEYS`, e.g. `domains/docs.example.com`,
+    /// `webhooks/<webhook_id>`, or `totp`
+    pub failed: Vec<String>,
+}
+
+/// Input payload for POST /api/admin/webhooks
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookPayload {
+    /// Where events are POSTed to
     pub url: String,
-    pub llm_txt: String,
 }
 
-/// Response payload for GET /api/list endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LlmsTxtListResponse {
-    pub items: Vec<LlmsTxtListItem>,
+/// Input payload for DELETE /api/admin/webhooks
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookIdPayload {
+    pub webhook_id: Uuid,
 }
 
-/// Response payload for GET /api/job endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JobDetailsResponse {
-    pub job_id: Uuid,
+/// A webhook, without its signing secret
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSummary {
+    pub webhook_id: Uuid,
     pub url: String,
-    pub status: JobStatus,
-    pub kind: JobKind,
-    pub llms_txt: Option<String>,
-    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<Webhook> for WebhookSummary {
+    fn from(webhook: Webhook) -> Self {
+        Self {
+            webhook_id: webhook.webhook_id,
+            url: webhook.url,
+            created_at: webhook.created_at,
+        }
+    }
+}
+
+/// Response payload for POST /api/admin/webhooks. It's the only time the signing secret is shown.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedWebhookResponse {
+    #[serde(flatten)]
+    pub webhook: WebhookSummary,
+    /// Key of the HMAC-SHA256 signature in the `X-Llms-Signature` header of every delivery
+    pub secret: String,
+}
+
+/// Response payload for GET /api/admin/webhooks
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhooksResponse {
+    /// Every webhook, oldest first
+    pub webhooks: Vec<WebhookSummary>,
+}
+
+/// Input payload for POST /api/admin/api_keys
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyPayload {
+    /// What the key is for, e.g. "cron on build server"
+    pub name: String,
+}
+
+/// Input payload for DELETE /api/admin/api_keys
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ApiKeyIdPayload {
+    pub key_id: Uuid,
+}
+
+/// An API key, without the key itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeySummary {
+    pub key_id: Uuid,
+    pub name: String,
+    /// The start of the key, e.g. `ltx_3f9a0c1d`
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApiKey> for ApiKeySummary {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            key_id: api_key.key_id,
+            name: api_key.name,
+            prefix: api_key.prefix,
+            created_at: api_key.created_at,
+            last_used_at: api_key.last_used_at,
+            revoked_at: api_key.revoked_at,
+        }
+    }
+}
+
+/// Response payload for POST /api/admin/api_keys. It's the only time the key is shown.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeySummary,
+    /// Sent as an `Authorization: Bearer` header
+    pub key: String,
+}
+
+/// Response payload for GET /api/admin/api_keys
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeysResponse {
+    /// Every key, revoked ones too, newest first
+    pub api_keys: Vec<ApiKeySummary>,
+}
+
+/// Input payload for GET /api/admin/webhooks/deliveries
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookDeliveriesPayload {
+    pub webhook_id: Uuid,
+    /// Number of deliveries, most recent first: 50 when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+/// A delivery and every attempt made at it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryHistory {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    /// In attempt order
+    pub attempts: Vec<WebhookDeliveryAttempt>,
+}
+
+/// Response payload for GET /api/admin/webhooks/deliveries
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveriesResponse {
+    /// Most recent first
+    pub deliveries: Vec<WebhookDeliveryHistory>,
+}
+
+/// Input payload for PUT /api/crawl_schedule
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CrawlSchedulePayload {
+    pub url: String,
+    /// Seconds between crawls
+    pub interval_s: u64,
+    /// Limits of every crawl: `Crawl`'s defaults when absent
+    #[serde(default)]
+    pub crawl: Crawl,
+}
+
+impl From<JobStatus> for dto_ltx::JobStatus {
+    fn from(status: JobStatus) -> Self {
+        match status {
+            JobStatus::Queued => dto_ltx::JobStatus::Queued,
+            JobStatus::Running => dto_ltx::JobStatus::Running,
+            JobStatus::Success => dto_ltx::JobStatus::Success,
+            JobStatus::Failure => dto_ltx::JobStatus::Failure,
+            JobStatus::Expired => dto_ltx::JobStatus::Expired,
+            JobStatus::Cancelled => dto_ltx::JobStatus::Cancelled,
+        }
+    }
+}
+
+impl From<dto_ltx::JobStatus> for JobStatus {
+    fn from(status: dto_ltx::JobStatus) -> Self {
+        match status {
+            dto_ltx::JobStatus::Queued => JobStatus::Queued,
+            dto_ltx::JobStatus::Running => JobStatus::Running,
+            dto_ltx::JobStatus::Success => JobStatus::Success,
+            dto_ltx::JobStatus::Failure => JobStatus::Failure,
+            dto_ltx::JobStatus::Expired => JobStatus::Expired,
+            dto_ltx::JobStatus::Cancelled => JobStatus::Cancelled,
+        }
+    }
+}
+
+impl From<ResultStatus> for dto_ltx::ResultStatus {
+    fn from(status: ResultStatus) -> Self {
+        match status {
+            ResultStatus::Ok => dto_ltx::ResultStatus::Ok,
+            ResultStatus::Error => dto_ltx::ResultStatus::Error,
+        }
+    }
+}
+
+impl From<dto_ltx::ResultStatus> for ResultStatus {
+    fn from(status: dto_ltx::ResultStatus) -> Self {
+        match status {
+            dto_ltx::ResultStatus::Ok => ResultStatus::Ok,
+            dto_ltx::ResultStatus::Error => ResultStatus::Error,
+        }
+    }
+}
+
+impl From<JobKind> for dto_ltx::JobKind {
+    fn from(kind: JobKind) -> Self {
+        match kind {
+            JobKind::New => dto_ltx::JobKind::New,
+            JobKind::Update => dto_ltx::JobKind::Update,
+        }
+    }
+}
+
+impl From<dto_ltx::JobKind> for JobKind {
+    fn from(kind: dto_ltx::JobKind) -> Self {
+        match kind {
+            dto_ltx::JobKind::New => JobKind::New,
+            dto_ltx::JobKind::Update => JobKind::Update,
+        }
+    }
+}
+
+impl From<JobState> for JobSummary {
+    fn from(job: JobState) -> Self {
+        JobSummary {
+            job_id: job.job_id,
+            url: job.url,
+            status: job.status.into(),
+            kind: job.kind.into(),
+            created_at: job.created_at,
+            expires_at: job.expires_at,
+            model: job.model,
+        }
+    }
+}
+
+impl From<JobLogs> for JobLogsResponse {
+    fn from(logs: JobLogs) -> Self {
+        JobLogsResponse {
+            job_id: logs.job_id,
+            log: logs.log,
+            truncated: logs.truncated,
+            created_at: logs.created_at,
+        }
+    }
+}
+
+impl From<LlmsTxtPreviewRow> for LlmsTxtListItem {
+    fn from(row: LlmsTxtPreviewRow) -> Self {
+        LlmsTxtListItem {
+            detail: row.detail.as_deref().and_then(|detail| detail.parse().ok()),
+            url: row.url,
+            preview: row.preview.unwrap_or_default(),
+            truncated: row.preview_truncated,
+            llm_txt: None,
+            title: row.title,
+            description: row.description,
+            canonical_url: row.canonical_url,
+            favicon_url: row.favicon_url,
+            prompt_version: row.prompt_version,
+            model: row.model,
+        }
+    }
+}
+
+impl From<LlmsTxt> for LlmsTxtListItem {
+    fn from(record: LlmsTxt) -> Self {
+        let (preview, truncated) = match record.preview {
+            Some(preview) => (preview, record.preview_truncated),
+            None => llms_txt_preview(&record.result_data.text()),
+        };
+        LlmsTxtListItem {
+            url: record.url,
+            preview,
+            truncated,
+            llm_txt: Some(record.result_data.into_text()),
+            title: record.title,
+            description: record.description,
+            canonical_url: record.canonical_url,
+            favicon_url: record.favicon_url,
+            prompt_version: record.prompt_version,
+            model: record.model,
+            detail: record.detail(),
+        }
+    }
 }
 
 pub struct AppError(anyhow::Error);
@@ -484,6 +2120,11 @@ impl IntoResponse for PostLlmTxtError {
     fn into_response(self) -> axum::response::Response {
         let status = match self {
             PostLlmTxtError::AlreadyGenerated | PostLlmTxtError::JobsInProgress(_) => StatusCode::CONFLICT,
+            PostLlmTxtError::InvalidUrl(_) | PostLlmTxtError::UnknownModel(_) | PostLlmTxtError::InvalidSection(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            PostLlmTxtError::DomainNotAllowed(_) => StatusCode::FORBIDDEN,
+            PostLlmTxtError::QueueBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
             PostLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         (status, Json(self)).into_response()
@@ -497,7 +2138,15 @@ from_error!(diesel::result::Error, PostLlmTxtError);
 
 impl IntoResponse for PutLlmTxtError {
     fn into_response(self) -> axum::response::Response {
-        let status = StatusCode::INTERNAL_SERVER_ERROR;
+        let status = match self {
+            PutLlmTxtError::JobsInProgress(_) => StatusCode::CONFLICT,
+            PutLlmTxtError::InvalidUrl(_) | PutLlmTxtError::UnknownModel(_) | PutLlmTxtError::InvalidSection(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            PutLlmTxtError::DomainNotAllowed(_) => StatusCode::FORBIDDEN,
+            PutLlmTxtError::QueueBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
+            PutLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
         (status, Json(self)).into_response()
     }
 }
@@ -511,6 +2160,12 @@ impl IntoResponse for UpdateLlmTxtError {
     fn into_response(self) -> axum::response::Response {
         let status = match self {
             UpdateLlmTxtError::NotGenerated => StatusCode::NOT_FOUND,
+            UpdateLlmTxtError::JobsInProgress(_) => StatusCode::CONFLICT,
+            UpdateLlmTxtError::InvalidUrl(_)
+            | UpdateLlmTxtError::UnknownModel(_)
+            | UpdateLlmTxtError::InvalidSection(_) => StatusCode::BAD_REQUEST,
+            UpdateLlmTxtError::DomainNotAllowed(_) => StatusCode::FORBIDDEN,
+            UpdateLlmTxtError::QueueBusy(_) => StatusCode::SERVICE_UNAVAILABLE,
             UpdateLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
         };
         (status, Json(self)).into_response()
@@ -520,6 +2175,72 @@ impl IntoResponse for UpdateLlmTxtError {
 from_error!(PoolError, UpdateLlmTxtError);
 from_diesel_not_found_error!(UpdateLlmTxtError);
 
+// CancelJobError
+
+impl IntoResponse for CancelJobError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            CancelJobError::UnknownId => StatusCode::NOT_FOUND,
+            CancelJobError::AlreadyFinished(_) => StatusCode::CONFLICT,
+            CancelJobError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, CancelJobError);
+
+impl From<diesel::result::Error> for CancelJobError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => CancelJobError::UnknownId,
+            _ => CancelJobError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// RetryJobError
+
+impl IntoResponse for RetryJobError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            RetryJobError::UnknownId => StatusCode::NOT_FOUND,
+            RetryJobError::NotFailed(_) => StatusCode::CONFLICT,
+            RetryJobError::JobsInProgress(_) => StatusCode::CONFLICT,
+            RetryJobError::DomainNotAllowed(_) => StatusCode::FORBIDDEN,
+            RetryJobError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, RetryJobError);
+
+impl From<diesel::result::Error> for RetryJobError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => RetryJobError::UnknownId,
+            _ => RetryJobError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// DeleteLlmTxtError
+
+impl IntoResponse for DeleteLlmTxtError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            DeleteLlmTxtError::NotGenerated => StatusCode::NOT_FOUND,
+            DeleteLlmTxtError::JobsInProgress(_) => StatusCode::CONFLICT,
+            DeleteLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, DeleteLlmTxtError);
+from_diesel_not_found_error!(DeleteLlmTxtError);
+
 // StatusError
 
 impl IntoResponse for StatusError {
@@ -544,12 +2265,308 @@ impl From<diesel::result::Error> for StatusError {
     }
 }
 
+// BatchStatusError
+
+impl IntoResponse for BatchStatusError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            BatchStatusError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            BatchStatusError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, BatchStatusError);
+from_error!(diesel::result::Error, BatchStatusError);
+
+// ImportError
+
+impl IntoResponse for ImportError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            ImportError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ImportError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, ImportError);
+from_error!(diesel::result::Error, ImportError);
+
+// PurgeStaleJobsError
+
+impl IntoResponse for PurgeStaleJobsError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            PurgeStaleJobsError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            PurgeStaleJobsError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, PurgeStaleJobsError);
+from_error!(diesel::result::Error, PurgeStaleJobsError);
+
+// CampaignError
+
+impl IntoResponse for CampaignError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            CampaignError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            CampaignError::UnknownId => StatusCode::NOT_FOUND,
+            CampaignError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, CampaignError);
+
+impl From<diesel::result::Error> for CampaignError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => CampaignError::UnknownId,
+            _ => CampaignError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// ListError
+
+impl IntoResponse for ListError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            ListError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ListError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, ListError);
+from_error!(diesel::result::Error, ListError);
+
+// SearchError
+
+impl IntoResponse for SearchError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            SearchError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            SearchError::NotEnabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            SearchError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, SearchError);
+from_error!(diesel::result::Error, SearchError);
+
+// RelatedLlmTxtError
+
+impl IntoResponse for RelatedLlmTxtError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            RelatedLlmTxtError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            RelatedLlmTxtError::NotGenerated => StatusCode::NOT_FOUND,
+            RelatedLlmTxtError::NotEnabled(_) => StatusCode::SERVICE_UNAVAILABLE,
+            RelatedLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, RelatedLlmTxtError);
+from_diesel_not_found_error!(RelatedLlmTxtError);
+
+// DomainPolicyError
+
+impl IntoResponse for DomainPolicyError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            DomainPolicyError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            DomainPolicyError::UnknownPattern => StatusCode::NOT_FOUND,
+            DomainPolicyError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, DomainPolicyError);
+from_error!(diesel::result::Error, DomainPolicyError);
+
+// DomainError
+
+impl IntoResponse for DomainError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            DomainError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            DomainError::KeyNotConfigured(_) => StatusCode::SERVICE_UNAVAILABLE,
+            DomainError::UnknownDomain => StatusCode::NOT_FOUND,
+            DomainError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, DomainError);
+from_error!(diesel::result::Error, DomainError);
+
+impl From<SecretsError> for DomainError {
+    fn from(err: SecretsError) -> Self {
+        match err {
+            SecretsError::MissingKey | SecretsError::InvalidKey(_) => DomainError::KeyNotConfigured(err.to_string()),
+            _ => DomainError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// RotateSecretsError
+
+impl IntoResponse for RotateSecretsError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            RotateSecretsError::KeyNotConfigured(_) => StatusCode::SERVICE_UNAVAILABLE,
+            RotateSecretsError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, RotateSecretsError);
+from_error!(diesel::result::Error, RotateSecretsError);
+
+impl From<SecretsError> for RotateSecretsError {
+    fn from(err: SecretsError) -> Self {
+        match err {
+            SecretsError::MissingKey | SecretsError::InvalidKey(_) => {
+                RotateSecretsError::KeyNotConfigured(err.to_string())
+            }
+            _ => RotateSecretsError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// WebhookError
+
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            WebhookError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            WebhookError::KeyNotConfigured(_) => StatusCode::SERVICE_UNAVAILABLE,
+            WebhookError::UnknownId => StatusCode::NOT_FOUND,
+            WebhookError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, WebhookError);
+from_error!(diesel::result::Error, WebhookError);
+
+impl From<SecretsError> for WebhookError {
+    fn from(err: SecretsError) -> Self {
+        match err {
+            SecretsError::MissingKey | SecretsError::InvalidKey(_) => WebhookError::KeyNotConfigured(err.to_string()),
+            _ => WebhookError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// ApiKeyError
+
+impl IntoResponse for ApiKeyError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            ApiKeyError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiKeyError::UnknownId => StatusCode::NOT_FOUND,
+            ApiKeyError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, ApiKeyError);
+from_error!(diesel::result::Error, ApiKeyError);
+
+// CrawlScheduleError
+
+impl IntoResponse for CrawlScheduleError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            CrawlScheduleError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            CrawlScheduleError::UnknownUrl => StatusCode::NOT_FOUND,
+            CrawlScheduleError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, CrawlScheduleError);
+
+impl From<diesel::result::Error> for CrawlScheduleError {
+    fn from(err: diesel::result::Error) -> Self {
+        match err {
+            diesel::result::Error::NotFound => CrawlScheduleError::UnknownUrl,
+            _ => CrawlScheduleError::Unknown(err.to_string()),
+        }
+    }
+}
+
+// BatchLlmTxtError
+
+impl IntoResponse for BatchLlmTxtError {
+    fn into_response(self) -> axum::response::Response {
+        let status = match self {
+            BatchLlmTxtError::InvalidRequest(_) | BatchLlmTxtError::UnknownModel(_) => StatusCode::BAD_REQUEST,
+            BatchLlmTxtError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+from_error!(PoolError, BatchLlmTxtError);
+from_error!(diesel::result::Error, BatchLlmTxtError);
+
 #[cfg(test)]
 mod tests {
     use core_ltx::{normalize_html, web_html::compute_html_checksum};
 
     use super::*;
 
+    #[test]
+    fn test_retry_job_state() {
+        let failed = JobState {
+            status: JobStatus::Failure,
+            failure_kind: Some("timeout".to_string()),
+            expires_at: Some(Utc::now()),
+            model: Some("gpt-5-mini".to_string()),
+            section: Some("Docs".to_string()),
+            ..JobState::from_kind_data(
+                Uuid::new_v4(),
+                "https://example.com".to_string(),
+                JobStatus::Failure,
+                JobKindData::Update {
+                    llms_txt: "# Example".to_string(),
+                },
+            )
+        };
+        let (failed_id, kind_data) = (failed.job_id, failed.to_kind_data());
+        let (model, section) = (failed.model.clone(), failed.section.clone());
+
+        let retry = failed.retry(Uuid::new_v4());
+        assert_ne!(retry.job_id, failed_id);
+        assert_eq!(retry.status, JobStatus::Queued);
+        assert_eq!(retry.to_kind_data(), kind_data);
+        assert_eq!((retry.model.clone(), retry.section.clone()), (model, section));
+        assert_eq!((retry.failure_kind.clone(), retry.expires_at), (None, None));
+        assert_eq!(retry.attempts, 2);
+        assert_eq!(retry.retry(Uuid::new_v4()).attempts, 3);
+    }
+
     #[test]
     fn test_create_job_state() {
         let job_state = JobState {
@@ -559,6 +2576,16 @@ mod tests {
             kind: JobKind::New,
             llms_txt: None,
             created_at: Utc::now(),
+            trace_context: None,
+            expires_at: None,
+            failure_kind: None,
+            model: None,
+            detail: "standard".to_string(),
+            section: None,
+            section_url: None,
+            crawl_max_depth: None,
+            crawl_max_pages: None,
+            attempts: 1,
         };
 
         assert!(!job_state.url.is_empty());
@@ -579,6 +2606,7 @@ mod tests {
         assert_eq!(db_model.kind, JobKind::New);
         assert_eq!(db_model.llms_txt, None);
         assert_eq!(db_model.to_kind_data(), new_kind);
+        assert_eq!(db_model.detail(), Detail::Standard);
 
         // Test Update variant
         let update_kind = JobKindData::Update {
@@ -590,6 +2618,79 @@ mod tests {
         assert_eq!(db_model.to_kind_data(), update_kind);
     }
 
+    #[test]
+    fn test_job_crawl() {
+        let job = JobState::from_kind_data(
+            Uuid::new_v4(),
+            "https://example.com".into(),
+            JobStatus::Queued,
+            JobKindData::New,
+        );
+        assert_eq!(job.crawl(), None);
+
+        let crawl = Crawl {
+            max_depth: 1,
+            max_pages: 20,
+        };
+        let job = job.with_crawl(Some(crawl));
+        assert_eq!((job.crawl_max_depth, job.crawl_max_pages), (Some(1), Some(20)));
+        assert_eq!(job.crawl(), Some(crawl));
+        assert_eq!(job.with_crawl(None).crawl(), None);
+    }
+
+    #[test]
+    fn test_crawl_schedule_is_due() {
+        let now = Utc::now();
+        let schedule = CrawlSchedule {
+            url: "https://example.com".to_string(),
+            interval_s: 3600,
+            max_depth: 2,
+            max_pages: 50,
+            next_run_at: now,
+            last_run_at: None,
+            created_at: now,
+        };
+        assert!(schedule.is_due(now));
+        assert!(!schedule.is_due(now - chrono::Duration::seconds(1)));
+        assert_eq!(schedule.next_run_after(now), now + chrono::Duration::hours(1));
+        assert_eq!(schedule.crawl(), Crawl::default());
+    }
+
+    #[test]
+    fn test_domain_credential() {
+        let key = Keyring::parse("k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").unwrap();
+        let credential = Credential::Basic {
+            username: "indexer".to_string(),
+            password: "hunter2".to_string(),
+        };
+        let domain = DomainCredential::new("docs.internal.example".to_string(), &credential, &key, Utc::now());
+        assert_eq!(domain.credential_kind, "basic");
+        assert!(!domain.credential.contains("hunter2"));
+        assert_eq!(domain.decrypt(&key).unwrap(), credential);
+
+        let summary = DomainSummary::from(domain);
+        assert_eq!(summary.domain, "docs.internal.example");
+        assert!(!serde_json::to_string(&summary).unwrap().contains("v1:"));
+    }
+
+    #[test]
+    fn test_webhook_secret() {
+        let key = Keyring::parse("k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").unwrap();
+        let secret = Webhook::generate_secret();
+        assert!(secret.starts_with("whsec_"));
+        assert_eq!(secret.len(), "whsec_".len() + 64);
+        assert_ne!(secret, Webhook::generate_secret());
+
+        let webhook = Webhook::new("https://hooks.example.com/llms".to_string(), &secret, &key, Utc::now());
+        assert!(!webhook.secret.contains(&secret));
+        assert_eq!(webhook.decrypt_secret(&key).unwrap(), secret);
+
+        let delivery = WebhookDelivery::new(webhook.webhook_id, "job.finished", "{}".to_string(), Utc::now());
+        assert_eq!(delivery.status, DeliveryStatus::Pending.as_str());
+        assert_eq!(delivery.attempts, 0);
+        assert_eq!(delivery.next_attempt_at, delivery.created_at);
+    }
+
     #[test]
     fn test_create_llms_txt() {
         let html = "<html><body>Test</body></html>";
@@ -600,16 +2701,26 @@ mod tests {
         let llms_txt = LlmsTxt {
             job_id: Uuid::new_v4(),
             url: "https://example.com/llms.txt".to_string(),
-            result_data: "# Example LLMs.txt content".to_string(),
+            result_data: "# Example LLMs.txt content".into(),
             result_status: ResultStatus::Ok,
             created_at: Utc::now(),
             html_compress: html_compress.clone(),
             html_checksum: html_checksum.clone(),
+            source: LlmsTxtSource::Generated,
+            title: None,
+            description: None,
+            canonical_url: None,
+            favicon_url: None,
+            prompt_version: None,
+            model: None,
+            detail: None,
+            preview: Some("# Example LLMs.txt content".to_string()),
+            preview_truncated: false,
         };
 
         assert!(!llms_txt.url.is_empty());
-        assert!(!llms_txt.result_data.is_empty());
-        assert!(llms_txt.result_data.starts_with("# Example"));
+        assert!(!llms_txt.result_data.text().is_empty());
+        assert!(llms_txt.result_data.text().starts_with("# Example"));
         assert_eq!(llms_txt.result_status, ResultStatus::Ok);
         assert!(!llms_txt.html_compress.is_empty());
         assert!(!llms_txt.html_checksum.is_empty());
@@ -637,7 +2748,7 @@ mod tests {
             html_checksum.clone(),
         );
         assert_eq!(db_model.result_status, ResultStatus::Ok);
-        assert_eq!(db_model.result_data, "content");
+        assert_eq!(db_model.result_data.text(), "content");
         assert_eq!(db_model.html_compress, html_compress);
         assert_eq!(db_model.to_result(), ok_result);
 
@@ -653,8 +2764,48 @@ mod tests {
             html_checksum.clone(),
         );
         assert_eq!(db_model.result_status, ResultStatus::Error);
-        assert_eq!(db_model.result_data, "network timeout");
+        assert_eq!(db_model.result_data.text(), "network timeout");
         assert_eq!(db_model.html_compress, html_compress);
         assert_eq!(db_model.to_result(), error_result);
     }
+
+    #[test]
+    fn test_result_data_compression() {
+        let short = ResultData::new("# Example\n\n> Short");
+        assert!(!short.is_compressed());
+        assert_eq!(short.text(), "# Example\n\n> Short");
+
+        let llms_txt = format!(
+            "# Example\n\n## Docs\n\n{}",
+            "- [Page](https://example.com/page): A page\n".repeat(200)
+        );
+        let long = ResultData::new(llms_txt.clone());
+        assert!(long.is_compressed());
+        assert!(long.0.len() < llms_txt.len() / 4);
+        assert_eq!(long.text(), llms_txt);
+        assert_eq!(serde_json::to_value(&long).unwrap(), json!(llms_txt));
+        assert_eq!(long.into_text(), llms_txt);
+
+        // text that looks compressed reads back as it was
+        let tricky = ResultData::new(format!("{}not base64", BROTLI_MARKER));
+        assert_eq!(tricky.text(), format!("{}not base64", BROTLI_MARKER));
+    }
+
+    #[test]
+    fn test_llms_txt_preview() {
+        assert_eq!(
+            llms_txt_preview("# Example\n\n> Short\n"),
+            ("# Example\n\n> Short".to_string(), false)
+        );
+
+        let lines: Vec<String> = (1..=30).map(|i| format!("- line {}", i)).collect();
+        let (preview, truncated) = llms_txt_preview(&lines.join("\n"));
+        assert_eq!(preview, lines[..PREVIEW_MAX_LINES].join("\n"));
+        assert!(truncated);
+
+        // a single long line is cut too
+        let (preview, truncated) = llms_txt_preview(&"é".repeat(PREVIEW_MAX_CHARS + 1));
+        assert_eq!(preview.chars().count(), PREVIEW_MAX_CHARS);
+        assert!(truncated);
+    }
 }
@@ -1,5 +1,56 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    api_keys (key_id) {
+        key_id -> Uuid,
+        name -> Text,
+        key_hash -> Text,
+        prefix -> Text,
+        created_at -> Timestamptz,
+        last_used_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    crawl_schedule (url) {
+        url -> Text,
+        interval_s -> Int8,
+        max_depth -> Int4,
+        max_pages -> Int4,
+        next_run_at -> Timestamptz,
+        last_run_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    domain_policy (pattern) {
+        pattern -> Text,
+        rule -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    domains (domain) {
+        domain -> Text,
+        credential_kind -> Text,
+        credential -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    job_logs (job_id) {
+        job_id -> Uuid,
+        log -> Text,
+        truncated -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use crate::models::{Job_status, Job_kind};
@@ -11,12 +62,35 @@ diesel::table! {
         kind -> Job_kind,
         llms_txt -> Nullable<Text>,
         created_at -> Timestamptz,
+        trace_context -> Nullable<Text>,
+        expires_at -> Nullable<Timestamptz>,
+        failure_kind -> Nullable<Text>,
+        model -> Nullable<Text>,
+        detail -> Text,
+        section -> Nullable<Text>,
+        section_url -> Nullable<Text>,
+        crawl_max_depth -> Nullable<Int4>,
+        crawl_max_pages -> Nullable<Int4>,
+        attempts -> Int4,
+        url_normalized -> Text,
+        started_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    llm_usage (job_id) {
+        job_id -> Uuid,
+        prompt_tokens -> Int8,
+        completion_tokens -> Int8,
+        created_at -> Timestamptz,
     }
 }
 
 diesel::table! {
     use diesel::sql_types::*;
     use crate::models::Result_status;
+    use crate::models::Llms_txt_source;
+    use crate::models::Tsvector;
 
     llms_txt (job_id) {
         job_id -> Uuid,
@@ -26,7 +100,137 @@ diesel::table! {
         created_at -> Timestamptz,
         html_compress -> Bytea,
         html_checksum -> Varchar,
+        source -> Llms_txt_source,
+        title -> Nullable<Text>,
+        description -> Nullable<Text>,
+        canonical_url -> Nullable<Text>,
+        favicon_url -> Nullable<Text>,
+        prompt_version -> Nullable<Text>,
+        model -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        preview -> Nullable<Text>,
+        preview_truncated -> Bool,
+        search_vector -> Nullable<Tsvector>,
+        url_normalized -> Text,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::models::Result_status;
+    use crate::models::Llms_txt_source;
+
+    llms_txt_archive (job_id) {
+        job_id -> Uuid,
+        url -> Text,
+        url_normalized -> Text,
+        result_data -> Text,
+        result_status -> Result_status,
+        created_at -> Timestamptz,
+        html_compress -> Bytea,
+        html_checksum -> Varchar,
+        source -> Llms_txt_source,
+        title -> Nullable<Text>,
+        description -> Nullable<Text>,
+        canonical_url -> Nullable<Text>,
+        favicon_url -> Nullable<Text>,
+        prompt_version -> Nullable<Text>,
+        model -> Nullable<Text>,
+        detail -> Nullable<Text>,
+        archived_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use crate::models::Llms_txt_source;
+
+    regeneration_campaign (campaign_id) {
+        campaign_id -> Uuid,
+        created_at -> Timestamptz,
+        finished_at -> Nullable<Timestamptz>,
+        domain -> Nullable<Text>,
+        older_than -> Nullable<Timestamptz>,
+        provider -> Nullable<Llms_txt_source>,
+        quality_below -> Nullable<Int4>,
+        outdated_prompt -> Bool,
+        total -> Int4,
+        enqueued -> Int4,
+        skipped -> Int4,
+        failed -> Int4,
+    }
+}
+
+diesel::table! {
+    totp (id) {
+        id -> Bool,
+        secret -> Text,
+        confirmed_at -> Nullable<Timestamptz>,
+        last_used_step -> Nullable<Int8>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    totp_recovery_codes (code_hash) {
+        code_hash -> Text,
+        used_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhook_deliveries (delivery_id) {
+        delivery_id -> Uuid,
+        webhook_id -> Uuid,
+        event -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Int4,
+        next_attempt_at -> Timestamptz,
+        created_at -> Timestamptz,
+        delivered_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    webhook_delivery_attempts (delivery_id, attempt) {
+        delivery_id -> Uuid,
+        attempt -> Int4,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        attempted_at -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    webhooks (webhook_id) {
+        webhook_id -> Uuid,
+        url -> Text,
+        secret -> Text,
+        created_at -> Timestamptz,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(job_state, llms_txt,);
+diesel::joinable!(job_logs -> job_state (job_id));
+diesel::joinable!(llm_usage -> job_state (job_id));
+diesel::joinable!(webhook_deliveries -> webhooks (webhook_id));
+diesel::joinable!(webhook_delivery_attempts -> webhook_deliveries (delivery_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    api_keys,
+    crawl_schedule,
+    domain_policy,
+    domains,
+    job_logs,
+    job_state,
+    llm_usage,
+    llms_txt,
+    llms_txt_archive,
+    regeneration_campaign,
+    totp,
+    totp_recovery_codes,
+    webhook_deliveries,
+    webhook_delivery_attempts,
+    webhooks,
+);
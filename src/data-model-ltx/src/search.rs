@@ -0,0 +1,42 @@
+//! Full-text search of the stored llms.txt files.
+//!
+//! `llms_txt.search_vector` is the Postgres `tsvector` of a record's URL and llms.txt. The llms.txt can be stored
+//! compressed (see `ResultData`), which Postgres can't read: the vector is computed from the decompressed text, by
+//! whoever inserts the record, with `index_llms_txt`.
+
+use diesel::sql_types::{Array, Text};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::models::{LlmsTxt, ResultStatus};
+
+/// Text search configuration of the search vectors, and of the queries matched against them.
+pub const SEARCH_CONFIG: &str = "english";
+
+/// What's indexed of a record: its URL and its llms.txt.
+pub fn search_document(record: &LlmsTxt) -> String {
+    format!("{}\n{}", record.url, record.result_data.text())
+}
+
+/// Computes the search vector of the inserted records, in one query. Failures aren't indexed.
+pub async fn index_llms_txt(conn: &mut AsyncPgConnection, records: &[LlmsTxt]) -> Result<(), diesel::result::Error> {
+    let (job_ids, documents): (Vec<Uuid>, Vec<String>) = records
+        .iter()
+        .filter(|record| record.result_status == ResultStatus::Ok)
+        .map(|record| (record.job_id, search_document(record)))
+        .unzip();
+    if job_ids.is_empty() {
+        return Ok(());
+    }
+    diesel::sql_query(
+        "UPDATE llms_txt SET search_vector = to_tsvector($1::regconfig, d.document) \
+         FROM unnest($2::uuid[], $3::text[]) AS d(job_id, document) \
+         WHERE llms_txt.job_id = d.job_id",
+    )
+    .bind::<Text, _>(SEARCH_CONFIG)
+    .bind::<Array<diesel::sql_types::Uuid>, _>(job_ids)
+    .bind::<Array<Text>, _>(documents)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,352 @@
+//! Encryption at rest of the secrets stored in the database, e.g. the credentials of domains behind a login.
+//!
+//! Secrets are encrypted with AES-256-GCM by the current key of the `Keyring`. Every encrypted value names the key
+//! that encrypted it, so keys can be rotated: put a new key first, keep the old ones after it until every secret is
+//! re-encrypted (`Keyring::rotate`), then drop them.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{Engine as _, engine::general_purpose};
+
+/// Environment variable with the keys that encrypt the secrets stored in the database: comma-separated `<id>:<key>`
+/// pairs, where the key is 32 bytes, base64-encoded (e.g. the output of `openssl rand -base64 32`).
+/// The first key encrypts; all of them decrypt.
+pub const SECRETS_KEYS_ENV_VAR: &str = "SECRETS_KEYS";
+
+/// Environment variable with the path of a file that has the keys, in the same format as `SECRETS_KEYS`: for keys
+/// that a secrets manager (or `age`, or a KMS) decrypts to a file when the service starts, rather than to the
+/// environment. Used when `SECRETS_KEYS` isn't set.
+pub const SECRETS_KEYS_FILE_ENV_VAR: &str = "SECRETS_KEYS_FILE";
+
+/// Prefix of the values encrypted before keys had ids: they're decrypted by trying every key.
+const V1_PREFIX: &str = "v1:";
+
+/// Prefix of the values encrypted by this version of the format, followed by the key's id.
+const V2_PREFIX: &str = "v2:";
+
+/// Length of an AES-GCM nonce, in bytes.
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SecretsError {
+    /// Neither `SECRETS_KEYS` nor `SECRETS_KEYS_FILE` is set
+    MissingKey,
+    /// The keys aren't `<id>:<key>` pairs of 32 base64-encoded bytes
+    InvalidKey(String),
+    /// The stored value wasn't encrypted by `Keyring::encrypt`
+    Malformed,
+    /// The stored value was encrypted with a key that's no longer in the keyring
+    UnknownKey(String),
+    /// The stored value was encrypted with another key, or was tampered with
+    Decryption,
+}
+
+impl std::fmt::Display for SecretsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingKey => write!(
+                f,
+                "neither {} nor {} is set",
+                SECRETS_KEYS_ENV_VAR, SECRETS_KEYS_FILE_ENV_VAR
+            ),
+            Self::InvalidKey(reason) => write!(f, "{} is invalid: {}", SECRETS_KEYS_ENV_VAR, reason),
+            Self::Malformed => write!(f, "not an encrypted secret"),
+            Self::UnknownKey(id) => write!(
+                f,
+                "encrypted with key '{}', which isn't in {}",
+                id, SECRETS_KEYS_ENV_VAR
+            ),
+            Self::Decryption => write!(f, "couldn't decrypt the secret: wrong key, or tampered with"),
+        }
+    }
+}
+
+impl std::error::Error for SecretsError {}
+
+/// One key that encrypts secrets at rest, with AES-256-GCM.
+#[derive(Clone)]
+pub struct SecretsKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl SecretsKey {
+    /// The key from its id and its base64 encoding.
+    pub fn from_base64(id: &str, encoded: &str) -> Result<Self, SecretsError> {
+        let valid_id = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_id {
+            return Err(SecretsError::InvalidKey(format!(
+                "key id '{}' isn't letters, digits, '-', '_' or '.'",
+                id
+            )));
+        }
+        let bytes = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| SecretsError::InvalidKey(format!("key '{}': {}", id, e)))?;
+        if bytes.len() != 32 {
+            return Err(SecretsError::InvalidKey(format!(
+                "key '{}' is {} bytes instead of 32",
+                id,
+                bytes.len()
+            )));
+        }
+        Ok(Self {
+            id: id.to_string(),
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Encrypts with a random nonce, authenticating the key's id with the ciphertext.
+    fn seal(&self, plaintext: &str) -> Vec<u8> {
+        let nonce: [u8; NONCE_LEN] = rand::random();
+        let payload = Payload {
+            msg: plaintext.as_bytes(),
+            aad: self.id.as_bytes(),
+        };
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .expect("AES-GCM encryption of an in-memory buffer can't fail");
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        sealed
+    }
+
+    /// Decrypts what `seal` encrypted, or, with no `aad`, a `v1:` value.
+    fn open(&self, sealed: &[u8], aad: &[u8]) -> Result<String, SecretsError> {
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| SecretsError::Decryption)?;
+        String::from_utf8(plaintext).map_err(|_| SecretsError::Malformed)
+    }
+}
+
+/// The keys that encrypt secrets at rest: the current one, which encrypts, and the previous ones, which are only
+/// kept to decrypt the secrets they encrypted until those are rotated to the current key.
+#[derive(Clone)]
+pub struct Keyring {
+    keys: Vec<SecretsKey>,
+}
+
+impl Keyring {
+    /// The keys in `SECRETS_KEYS`, or else in the file at `SECRETS_KEYS_FILE`.
+    pub fn from_env() -> Result<Self, SecretsError> {
+        if let Ok(keys) = std::env::var(SECRETS_KEYS_ENV_VAR)
+            && !keys.trim().is_empty()
+        {
+            return Self::parse(&keys);
+        }
+        match std::env::var(SECRETS_KEYS_FILE_ENV_VAR) {
+            Ok(path) if !path.trim().is_empty() => {
+                let keys = std::fs::read_to_string(path.trim())
+                    .map_err(|e| SecretsError::InvalidKey(format!("can't read {}: {}", path.trim(), e)))?;
+                Self::parse(&keys)
+            }
+            _ => Err(SecretsError::MissingKey),
+        }
+    }
+
+    /// Keys from comma- (or line-) separated `<id>:<base64 key>` pairs, the current one first.
+    pub fn parse(keys: &str) -> Result<Self, SecretsError> {
+        let mut keyring = Vec::new();
+        for pair in keys.split([',', '\n']).map(str::trim).filter(|pair| !pair.is_empty()) {
+            let (id, key) = pair
+                .split_once(':')
+                .ok_or_else(|| SecretsError::InvalidKey("expected <id>:<base64 key> pairs".to_string()))?;
+            let key = SecretsKey::from_base64(id.trim(), key.trim())?;
+            if keyring.iter().any(|existing: &SecretsKey| existing.id == key.id) {
+                return Err(SecretsError::InvalidKey(format!("key id '{}' is used twice", key.id)));
+            }
+            keyring.push(key);
+        }
+        Self::new(keyring)
+    }
+
+    /// A keyring whose current key is the first one.
+    pub fn new(keys: Vec<SecretsKey>) -> Result<Self, SecretsError> {
+        if keys.is_empty() {
+            return Err(SecretsError::MissingKey);
+        }
+        Ok(Self { keys })
+    }
+
+    /// The key that encrypts.
+    pub fn current(&self) -> &SecretsKey {
+        &self.keys[0]
+    }
+
+    /// Encrypts the secret with the current key: `v2:`, the key's id, `:`, then the base64 of a random nonce
+    /// followed by the ciphertext.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let key = self.current();
+        format!(
+            "{}{}:{}",
+            V2_PREFIX,
+            key.id,
+            general_purpose::STANDARD.encode(key.seal(plaintext))
+        )
+    }
+
+    /// Decrypts a secret encrypted by `encrypt`, with any key of the keyring.
+    pub fn decrypt(&self, encrypted: &str) -> Result<String, SecretsError> {
+        let decode = |encoded: &str| {
+            general_purpose::STANDARD
+                .decode(encoded)
+                .ok()
+                .filter(|sealed| sealed.len() > NONCE_LEN)
+                .ok_or(SecretsError::Malformed)
+        };
+        if let Some(rest) = encrypted.strip_prefix(V2_PREFIX) {
+            let (id, encoded) = rest.split_once(':').ok_or(SecretsError::Malformed)?;
+            let key = self
+                .keys
+                .iter()
+                .find(|key| key.id == id)
+                .ok_or_else(|| SecretsError::UnknownKey(id.to_string()))?;
+            return key.open(&decode(encoded)?, id.as_bytes());
+        }
+        if let Some(encoded) = encrypted.strip_prefix(V1_PREFIX) {
+            let sealed = decode(encoded)?;
+            return self
+                .keys
+                .iter()
+                .find_map(|key| key.open(&sealed, &[]).ok())
+                .ok_or(SecretsError::Decryption);
+        }
+        Err(SecretsError::Malformed)
+    }
+
+    /// Whether the secret should be re-encrypted (see `rotate`): it wasn't encrypted by the current key.
+    pub fn needs_rotation(&self, encrypted: &str) -> bool {
+        let current = format!("{}{}:", V2_PREFIX, self.current().id);
+        !encrypted.starts_with(&current)
+    }
+
+    /// The secret re-encrypted with the current key, if it wasn't already.
+    pub fn rotate(&self, encrypted: &str) -> Result<Option<String>, SecretsError> {
+        if !self.needs_rotation(encrypted) {
+            return Ok(None);
+        }
+        self.decrypt(encrypted).map(|plaintext| Some(self.encrypt(&plaintext)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: &str, byte: u8) -> SecretsKey {
+        SecretsKey::from_base64(id, &general_purpose::STANDARD.encode([byte; 32])).unwrap()
+    }
+
+    fn keyring(keys: &[(&str, u8)]) -> Keyring {
+        Keyring::new(keys.iter().map(|(id, byte)| key(id, *byte)).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let keyring = keyring(&[("k1", 7)]);
+        let encrypted = keyring.encrypt("hunter2");
+        assert!(encrypted.starts_with("v2:k1:"));
+        assert!(!encrypted.contains("hunter2"));
+        // random nonces: the same secret doesn't encrypt the same twice
+        assert_ne!(encrypted, keyring.encrypt("hunter2"));
+        assert_eq!(keyring.decrypt(&encrypted).unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn test_decrypt_errors() {
+        let encrypted = keyring(&[("k1", 7)]).encrypt("hunter2");
+        assert_eq!(
+            keyring(&[("k2", 7)]).decrypt(&encrypted),
+            Err(SecretsError::UnknownKey("k1".to_string()))
+        );
+        // same id, another key
+        assert_eq!(keyring(&[("k1", 8)]).decrypt(&encrypted), Err(SecretsError::Decryption));
+        assert_eq!(keyring(&[("k1", 7)]).decrypt("hunter2"), Err(SecretsError::Malformed));
+        // the key's id is authenticated: relabeling the value doesn't decrypt it with another key
+        let relabeled = encrypted.replacen("v2:k1:", "v2:k2:", 1);
+        assert_eq!(keyring(&[("k2", 7)]).decrypt(&relabeled), Err(SecretsError::Decryption));
+
+        let mut tampered = encrypted.clone().into_bytes();
+        let last = tampered.len() - 3;
+        tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+        assert!(
+            keyring(&[("k1", 7)])
+                .decrypt(&String::from_utf8(tampered).unwrap())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_decrypt_v1() {
+        // encrypted before keys had ids: no id, nor associated data
+        let old = key("old", 7);
+        let nonce = [3u8; NONCE_LEN];
+        let ciphertext = old
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), b"hunter2".as_ref())
+            .unwrap();
+        let v1 = format!(
+            "v1:{}",
+            general_purpose::STANDARD.encode([nonce.as_slice(), &ciphertext].concat())
+        );
+        let keyring = keyring(&[("new", 9), ("old", 7)]);
+        assert_eq!(keyring.decrypt(&v1).unwrap(), "hunter2");
+        assert!(keyring.needs_rotation(&v1));
+    }
+
+    #[test]
+    fn test_rotate() {
+        let encrypted = keyring(&[("k1", 7)]).encrypt("hunter2");
+        let rotated_keyring = keyring(&[("k2", 9), ("k1", 7)]);
+        assert!(rotated_keyring.needs_rotation(&encrypted));
+
+        let rotated = rotated_keyring.rotate(&encrypted).unwrap().unwrap();
+        assert!(rotated.starts_with("v2:k2:"));
+        assert!(!rotated_keyring.needs_rotation(&rotated));
+        assert_eq!(rotated_keyring.rotate(&rotated), Ok(None));
+        // the old key can be dropped
+        assert_eq!(keyring(&[("k2", 9)]).decrypt(&rotated).unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn test_parse() {
+        let k1 = general_purpose::STANDARD.encode([1u8; 32]);
+        let k2 = general_purpose::STANDARD.encode([2u8; 32]);
+        let keyring = Keyring::parse(&format!("2026-03:{}, 2025-09:{}", k2, k1)).unwrap();
+        assert_eq!(keyring.current().id(), "2026-03");
+        assert_eq!(
+            Keyring::parse(&format!("a:{}\nb:{}\n", k1, k2)).unwrap().current().id(),
+            "a"
+        );
+
+        assert_eq!(Keyring::parse("").err(), Some(SecretsError::MissingKey));
+        assert!(matches!(Keyring::parse(&k1), Err(SecretsError::InvalidKey(_))));
+        assert!(matches!(
+            Keyring::parse("a:not base64!"),
+            Err(SecretsError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            Keyring::parse(&format!("a:{}", general_purpose::STANDARD.encode([1u8; 16]))),
+            Err(SecretsError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            Keyring::parse(&format!("a:{},a:{}", k1, k2)),
+            Err(SecretsError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            Keyring::parse(&format!("a b:{}", k1)),
+            Err(SecretsError::InvalidKey(_))
+        ));
+    }
+}
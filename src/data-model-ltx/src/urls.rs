@@ -0,0 +1,14 @@
+//! Lookups of a website's records by URL, however it's spelled.
+//!
+//! `llms_txt.url_normalized` and `job_state.url_normalized` are the key of the record's URL, generated by Postgres
+//! with its `normalize_url` function: the scheme and host lowercased, the default port dropped, and the query's
+//! parameters sorted. Lookups compare them with the key of the requested URL, so that
+//! `https://Example.com:443/?b=2&a=1` finds the records of `https://example.com/?a=1&b=2`.
+
+use diesel::define_sql_function;
+use diesel::sql_types::Text;
+
+define_sql_function! {
+    /// The key of the URL, as stored in the `url_normalized` columns.
+    fn normalize_url(url: Text) -> Text;
+}
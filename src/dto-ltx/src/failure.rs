@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a job failed, in terms a user can act on. Stored with failed jobs as a short code (see `code`),
+/// and shown to users through `description`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The job's URL isn't a valid URL.
+    InvalidUrl,
+    /// The website's domain name doesn't resolve.
+    DnsFailure,
+    /// The website's server couldn't be reached, or closed the connection.
+    ConnectionFailed,
+    /// The website took too long to respond.
+    Timeout,
+    /// The website redirected too many times, or to an invalid location.
+    BadRedirect,
+    /// The website responded with a non-success HTTP status.
+    HttpStatus(u16),
+    /// The response isn't an HTML page (e.g. a PDF or an image), or isn't valid text.
+    NotHtml,
+    /// The page is too big, or its elements are nested too deeply, to be parsed.
+    PageTooComplex,
+    /// The page's robots directives (`noindex`, `noai`, ...) disallow indexing it.
+    RobotsDisallowed,
+    /// The LLM provider's rate limit or quota was exceeded.
+    LlmRateLimited,
+    /// The LLM provider returned an error.
+    LlmError,
+    /// The LLM's output isn't a valid llms.txt.
+    InvalidOutput,
+    /// Something went wrong on our side.
+    Internal,
+}
+
+impl FailureKind {
+    /// Short, stable identifier stored in the database, e.g. `timeout` or `http_status:406`.
+    pub fn code(&self) -> String {
+        match self {
+            Self::InvalidUrl => "invalid_url".to_string(),
+            Self::DnsFailure => "dns_failure".to_string(),
+            Self::ConnectionFailed => "connection_failed".to_string(),
+            Self::Timeout => "timeout".to_string(),
+            Self::BadRedirect => "bad_redirect".to_string(),
+            Self::HttpStatus(status) => format!("http_status:{}", status),
+            Self::NotHtml => "not_html".to_string(),
+            Self::PageTooComplex => "page_too_complex".to_string(),
+            Self::RobotsDisallowed => "robots_disallowed".to_string(),
+            Self::LlmRateLimited => "llm_rate_limited".to_string(),
+            Self::LlmError => "llm_error".to_string(),
+            Self::InvalidOutput => "invalid_output".to_string(),
+            Self::Internal => "internal".to_string(),
+        }
+    }
+
+    /// Parses a `code`. None for codes this version doesn't know.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Some(match code {
+            "invalid_url" => Self::InvalidUrl,
+            "dns_failure" => Self::DnsFailure,
+            "connection_failed" => Self::ConnectionFailed,
+            "timeout" => Self::Timeout,
+            "bad_redirect" => Self::BadRedirect,
+            "not_html" => Self::NotHtml,
+            "page_too_complex" => Self::PageTooComplex,
+            "robots_disallowed" => Self::RobotsDisallowed,
+            "llm_rate_limited" => Self::LlmRateLimited,
+            "llm_error" => Self::LlmError,
+            "invalid_output" => Self::InvalidOutput,
+            "internal" => Self::Internal,
+            code => Self::HttpStatus(code.strip_prefix("http_status:")?.parse().ok()?),
+        })
+    }
+
+    /// What happened, for users.
+    pub fn description(&self) -> String {
+        match self {
+            Self::InvalidUrl => "The URL isn't valid.".to_string(),
+            Self::DnsFailure => "The website's domain name could not be found.".to_string(),
+            Self::ConnectionFailed => "Could not connect to the website.".to_string(),
+            Self::Timeout => "The website took too long to respond.".to_string(),
+            Self::BadRedirect => "The website redirected too many times, or to an invalid address.".to_string(),
+            Self::HttpStatus(status) => format!("The site returned HTTP {}.", status),
+            Self::NotHtml => "The address isn't an HTML page.".to_string(),
+            Self::PageTooComplex => "The page is too large or too deeply nested to process.".to_string(),
+            Self::RobotsDisallowed => "The website asks not to be indexed (noindex or noai).".to_string(),
+            Self::LlmRateLimited => "The LLM provider's rate limit was reached. Try again later.".to_string(),
+            Self::LlmError => "The LLM provider returned an error.".to_string(),
+            Self::InvalidOutput => "The LLM didn't produce a valid llms.txt.".to_string(),
+            Self::Internal => "Internal error while processing the job.".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_round_trip() {
+        let kinds = [
+            FailureKind::InvalidUrl,
+            FailureKind::DnsFailure,
+            FailureKind::ConnectionFailed,
+            FailureKind::Timeout,
+            FailureKind::BadRedirect,
+            FailureKind::HttpStatus(406),
+            FailureKind::NotHtml,
+            FailureKind::PageTooComplex,
+            FailureKind::RobotsDisallowed,
+            FailureKind::LlmRateLimited,
+            FailureKind::LlmError,
+            FailureKind::InvalidOutput,
+            FailureKind::Internal,
+        ];
+        for kind in kinds {
+            assert_eq!(FailureKind::from_code(&kind.code()), Some(kind));
+        }
+        assert_eq!(FailureKind::from_code("http_status:abc"), None);
+        assert_eq!(FailureKind::from_code("something_new"), None);
+    }
+
+    #[test]
+    fn test_serializes_as_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&FailureKind::HttpStatus(406)).unwrap(),
+            r#"{"http_status":406}"#
+        );
+        assert_eq!(serde_json::to_string(&FailureKind::Timeout).unwrap(), r#""timeout""#);
+    }
+}
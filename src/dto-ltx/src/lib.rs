@@ -0,0 +1,17 @@
+//! Wire-format types of the API: its requests and responses, as they're serialized.
+//!
+//! The server, the frontend, and the CLI all use these, so they can't drift apart. Only serde here (and, with the
+//! `openapi` feature, their schemas for the server's OpenAPI spec): the database models (and their conversions to and
+//! from these types) live in `data-model-ltx`.
+
+pub mod failure;
+pub mod models;
+
+pub use failure::FailureKind;
+pub use models::{
+    BatchStatusPayload, BatchStatusResponse, Crawl, Detail, JobDetailsResponse, JobIdPayload, JobIdResponse, JobKind,
+    JobLogsResponse, JobRequestPayload, JobStatus, JobStatusFilter, JobStatusResponse, JobSummary, JobsListResponse,
+    JobsPage, ListPage, ListSort, LiveEvent, LlmTxtResponse, LlmsTxtHistoryResponse, LlmsTxtListItem,
+    LlmsTxtListResponse, LlmsTxtVersion, QueueLoad, RelatedLlmsTxtResponse, RelatedSite, ResultStatus, UrlPayload,
+};
+pub use uuid::Uuid;
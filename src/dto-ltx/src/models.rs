@@ -0,0 +1,833 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::FailureKind;
+
+/// Status of a job, as the API reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum JobStatus {
+    /// A newly created job
+    Queued,
+    /// Worker received job
+    Running,
+    /// New or updated llms.txt file made and added to database
+    Success,
+    /// Worker failed
+    Failure,
+    /// Still queued when its deadline (`expires_at`) passed, so it was never worked on
+    Expired,
+    /// Cancelled through the API before it finished: its worker abandons it, and no result is stored
+    Cancelled,
+}
+
+impl JobStatus {
+    // True if job's status is Success, Failure, Expired, or Cancelled. False means it's Queued or Running.
+    pub fn is_completed(&self) -> bool {
+        match self {
+            Self::Queued | Self::Running => false,
+            Self::Success | Self::Failure | Self::Expired | Self::Cancelled => true,
+        }
+    }
+}
+
+/// Type of job operation, as the API reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum JobKind {
+    /// New llms.txt fetch
+    New,
+    /// Update existing llms.txt
+    Update,
+}
+
+/// How much an llms.txt goes into: a short index of the main pages, or every subpage enumerated.
+/// Each level has its own prompt instructions and output-size target.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Detail {
+    /// About 30 lines: the summary, and links to the most important pages only
+    Brief,
+    /// The main sections, and their most important pages
+    #[default]
+    Standard,
+    /// Every subpage the website links to, grouped in sections
+    Comprehensive,
+}
+
+impl Detail {
+    pub const ALL: [Detail; 3] = [Detail::Brief, Detail::Standard, Detail::Comprehensive];
+
+    /// Name used on the wire and in the database, e.g. `brief`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Brief => "brief",
+            Self::Standard => "standard",
+            Self::Comprehensive => "comprehensive",
+        }
+    }
+}
+
+impl std::fmt::Display for Detail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Detail {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Detail::ALL
+            .into_iter()
+            .find(|detail| detail.as_str() == s)
+            .ok_or_else(|| format!("unknown detail level '{}': use brief, standard, or comprehensive", s))
+    }
+}
+
+/// How far a crawl goes. A crawl job generates its llms.txt from every page found, not only from its URL's page,
+/// so it sees changes deep inside the website.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Crawl {
+    /// How many links away from the URL to follow. 0 means only the URL's page.
+    #[serde(default = "Crawl::default_max_depth")]
+    pub max_depth: u32,
+    /// Maximum number of pages to download.
+    #[serde(default = "Crawl::default_max_pages")]
+    pub max_pages: u32,
+}
+
+impl Crawl {
+    fn default_max_depth() -> u32 {
+        2
+    }
+
+    fn default_max_pages() -> u32 {
+        50
+    }
+}
+
+impl Default for Crawl {
+    fn default() -> Self {
+        Crawl {
+            max_depth: Crawl::default_max_depth(),
+            max_pages: Crawl::default_max_pages(),
+        }
+    }
+}
+
+// API Payload Types
+
+/// Input payload for endpoints that accept a URL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct UrlPayload {
+    pub url: String,
+}
+
+/// Input payload for endpoints that create a job for a URL
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobRequestPayload {
+    pub url: String,
+    /// Deadline for starting the job: if it's still queued then, it's marked Expired instead of being worked on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// LLM to generate with, one of `core_ltx::llms::MODELS`. The worker's configured model when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Rather not be queued at all than wait behind a backed-up queue: rejected with `queue_busy` when it is.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub low_priority: bool,
+    /// How detailed the llms.txt should be. New ones are `standard` when absent, updates keep the current level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+    /// Update only this H2 section of the existing llms.txt, leaving the rest of it as-is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Page on the same website to regenerate `section` from, e.g. the docs index. The job's URL when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section_url: Option<String>,
+    /// Crawl the website from the URL, and generate the llms.txt from every page found.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crawl: Option<Crawl>,
+}
+
+impl JobRequestPayload {
+    /// A job for the URL, with the server's defaults for everything else.
+    pub fn new(url: impl Into<String>) -> Self {
+        JobRequestPayload {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Input payload for /api/status endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct JobIdPayload {
+    pub job_id: Uuid,
+}
+
+/// Response payload containing a job ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobIdResponse {
+    pub job_id: Uuid,
+    /// Where the job is in the queue (1 is next). Only reported when the queue is backed up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub queue_position: Option<u64>,
+    /// When the job is expected to start, from the recent throughput. Only reported when the queue is backed up,
+    /// and it can be estimated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_start_at: Option<DateTime<Utc>>,
+}
+
+/// How backed up the job queue is, from the point of view of a new job.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct QueueLoad {
+    /// Where a new job would be in the queue (1 is next).
+    pub queue_position: u64,
+    /// When a new job would be expected to start, if it can be estimated.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_start_at: Option<DateTime<Utc>>,
+}
+
+/// Response payload for GET /api/llm_txt endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmTxtResponse {
+    pub content: String,
+}
+
+/// Response payload for GET /api/status endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobStatusResponse {
+    pub status: JobStatus,
+    pub kind: JobKind,
+}
+
+/// Input payload for POST /api/status/batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchStatusPayload {
+    /// At most `BatchStatusPayload::MAX_JOB_IDS`. Repeated job_ids are answered once.
+    pub job_ids: Vec<Uuid>,
+}
+
+impl BatchStatusPayload {
+    pub const MAX_JOB_IDS: usize = 500;
+}
+
+/// Response payload for POST /api/status/batch
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BatchStatusResponse {
+    /// The status of each job, by its job_id
+    pub statuses: BTreeMap<Uuid, JobStatusResponse>,
+    /// The job_ids that no job has
+    #[serde(default)]
+    pub unknown: Vec<Uuid>,
+}
+
+/// Individual item in the list response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmsTxtListItem {
+    pub url: String,
+    /// The first lines of the llms.txt
+    pub preview: String,
+    /// Whether the llms.txt is longer than its preview: GET /api/llm_txt has all of it
+    #[serde(default)]
+    pub truncated: bool,
+    /// The whole llms.txt, only when the list was asked for with `full=true`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_txt: Option<String>,
+    /// The website's title, description, canonical URL and icon, when they were found on the page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonical_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon_url: Option<String>,
+    /// Version of the prompts it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// LLM it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Detail level it was generated at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+}
+
+/// Outcome of a generation, as the API reports it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub enum ResultStatus {
+    /// An llms.txt was made
+    #[serde(alias = "ok")]
+    Ok,
+    /// The generation failed
+    #[serde(alias = "error")]
+    Error,
+}
+
+impl ResultStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResultStatus::Ok => "ok",
+            ResultStatus::Error => "error",
+        }
+    }
+}
+
+/// One generation of a website's llms.txt, without its content (see GET /api/job for that)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmsTxtVersion {
+    pub job_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub result_status: ResultStatus,
+    /// Why the generation failed. Only for failed ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Version of the prompts it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_version: Option<String>,
+    /// LLM it was generated with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Detail level it was generated at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Detail>,
+}
+
+/// Response payload for GET /api/llm_txt/history: every generation of the URL, newest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmsTxtHistoryResponse {
+    pub url: String,
+    pub versions: Vec<LlmsTxtVersion>,
+}
+
+/// A website whose latest llms.txt is about the same topics as another's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RelatedSite {
+    pub url: String,
+    /// The page's `<title>`, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// The page's meta description, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Cosine similarity of the two llms.txt files' embeddings: higher is closer, at most 1
+    pub similarity: f32,
+}
+
+/// Response payload for GET /api/llm_txt/related: the websites most similar to the URL's, most similar first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct RelatedLlmsTxtResponse {
+    pub url: String,
+    /// Empty until the URL's llms.txt is embedded, a few seconds after it's generated
+    pub related: Vec<RelatedSite>,
+}
+
+/// What GET /api/ws pushes to its clients, one JSON text message each, e.g. `{"type": "job", "job_id": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// A job was queued, or its status changed (e.g. it started running, or finished)
+    Job(JobSummary),
+    /// A generation's result was stored: a new llms.txt, or why the generation failed
+    LlmsTxt {
+        url: String,
+        job_id: Uuid,
+        result_status: ResultStatus,
+        created_at: DateTime<Utc>,
+    },
+    /// The client fell behind and this many events were dropped: it should reload what it shows
+    Lagged { missed: u64 },
+}
+
+/// Query parameters of GET /api/list: which websites, in which order, and which page of them
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ListPage {
+    /// Page number, starting at 1
+    #[serde(default = "ListPage::first_page")]
+    pub page: u32,
+    /// Websites per page, at most `ListPage::MAX_PER_PAGE`
+    #[serde(default = "ListPage::default_per_page")]
+    pub per_page: u32,
+    /// Whether every website has its whole llms.txt, and not only its preview
+    #[serde(default)]
+    pub full: bool,
+    /// Only the websites of this domain, or of its subdomains, e.g. `example.com`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// Which generation of each website is listed: its latest successful one (`ok`, the default), or its latest
+    /// failed one (`error`), whose preview is why it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<ResultStatus>,
+    /// Only the generations made after this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_after: Option<DateTime<Utc>>,
+    /// Only the generations made before this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub sort: ListSort,
+}
+
+impl ListPage {
+    pub const DEFAULT_PER_PAGE: u32 = 50;
+    pub const MAX_PER_PAGE: u32 = 500;
+
+    /// The page'th page (from 1), with the most websites per page.
+    pub fn new(page: u32) -> Self {
+        Self {
+            page,
+            per_page: Self::MAX_PER_PAGE,
+            ..Self::default()
+        }
+    }
+
+    fn first_page() -> u32 {
+        1
+    }
+
+    fn default_per_page() -> u32 {
+        Self::DEFAULT_PER_PAGE
+    }
+
+    /// Number of websites before this page's.
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+}
+
+impl Default for ListPage {
+    fn default() -> Self {
+        Self {
+            page: Self::first_page(),
+            per_page: Self::DEFAULT_PER_PAGE,
+            full: false,
+            domain: None,
+            status: None,
+            created_after: None,
+            created_before: None,
+            sort: ListSort::default(),
+        }
+    }
+}
+
+/// Order of the websites in GET /api/list
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ListSort {
+    /// By URL, A to Z
+    #[default]
+    UrlAsc,
+    /// By URL, Z to A
+    UrlDesc,
+    /// Oldest generation first
+    CreatedAtAsc,
+    /// Newest generation first
+    CreatedAtDesc,
+}
+
+impl ListSort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListSort::UrlAsc => "url_asc",
+            ListSort::UrlDesc => "url_desc",
+            ListSort::CreatedAtAsc => "created_at_asc",
+            ListSort::CreatedAtDesc => "created_at_desc",
+        }
+    }
+}
+
+/// Response payload for GET /api/list endpoint: one page of websites
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct LlmsTxtListResponse {
+    pub items: Vec<LlmsTxtListItem>,
+    /// Number of websites on all pages
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default)]
+    pub per_page: u32,
+}
+
+impl LlmsTxtListResponse {
+    /// Number of pages of `per_page` websites.
+    pub fn page_count(&self) -> u64 {
+        if self.per_page == 0 {
+            return 1;
+        }
+        self.total.div_ceil(u64::from(self.per_page)).max(1)
+    }
+
+    /// Whether there are websites after this page's.
+    pub fn has_next_page(&self) -> bool {
+        u64::from(self.page) < self.page_count()
+    }
+}
+
+/// Response payload for GET /api/job endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobDetailsResponse {
+    pub job_id: Uuid,
+    pub url: String,
+    pub status: JobStatus,
+    pub kind: JobKind,
+    /// Detail level the job generates at. Standard from servers that don't report it.
+    #[serde(default)]
+    pub detail: Detail,
+    /// The only H2 section the job regenerates, if it doesn't update the whole llms.txt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub llms_txt: Option<String>,
+    pub error_message: Option<String>,
+    /// Why the job failed, if it did and the worker could tell.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_kind: Option<FailureKind>,
+    /// What `failure_kind` means, in words users understand (e.g. "The site returned HTTP 406.").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_message: Option<String>,
+    /// Which attempt at the generation the job is: 1, then one more for each retry of a failed job. 1 from servers
+    /// that don't retry jobs.
+    #[serde(default = "JobDetailsResponse::first_attempt")]
+    pub attempts: u32,
+}
+
+impl JobDetailsResponse {
+    fn first_attempt() -> u32 {
+        1
+    }
+}
+
+/// One job in the responses of GET /api/jobs/in_progress and GET /api/jobs endpoints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobSummary {
+    pub job_id: Uuid,
+    pub url: String,
+    pub status: JobStatus,
+    pub kind: JobKind,
+    pub created_at: DateTime<Utc>,
+    /// If the job is still queued after this, it's marked Expired instead of being worked on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// LLM it's generated with. Absent for the worker's configured model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Query parameters of GET /api/jobs: which jobs, and which page of them, newest first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema, utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct JobsPage {
+    /// Page number, starting at 1
+    #[serde(default = "JobsPage::first_page")]
+    pub page: u32,
+    /// Jobs per page, at most `JobsPage::MAX_PER_PAGE`
+    #[serde(default = "JobsPage::default_per_page")]
+    pub per_page: u32,
+    /// Only the jobs with this status: every job by default
+    #[serde(default)]
+    pub status: JobStatusFilter,
+    /// Only the jobs of this URL, however it's spelled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl JobsPage {
+    pub const DEFAULT_PER_PAGE: u32 = 50;
+    pub const MAX_PER_PAGE: u32 = 500;
+
+    fn first_page() -> u32 {
+        1
+    }
+
+    fn default_per_page() -> u32 {
+        Self::DEFAULT_PER_PAGE
+    }
+
+    /// Number of jobs before this page's.
+    pub fn offset(&self) -> u64 {
+        u64::from(self.page.saturating_sub(1)) * u64::from(self.per_page)
+    }
+}
+
+impl Default for JobsPage {
+    fn default() -> Self {
+        Self {
+            page: Self::first_page(),
+            per_page: Self::DEFAULT_PER_PAGE,
+            status: JobStatusFilter::default(),
+            url: None,
+        }
+    }
+}
+
+/// Which jobs GET /api/jobs lists, by status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatusFilter {
+    /// Every job, whatever its status
+    #[default]
+    All,
+    Queued,
+    Running,
+    Success,
+    Failure,
+    Expired,
+    Cancelled,
+}
+
+impl JobStatusFilter {
+    pub const ALL: [JobStatusFilter; 7] = [
+        JobStatusFilter::All,
+        JobStatusFilter::Queued,
+        JobStatusFilter::Running,
+        JobStatusFilter::Success,
+        JobStatusFilter::Failure,
+        JobStatusFilter::Expired,
+        JobStatusFilter::Cancelled,
+    ];
+
+    /// The status of the listed jobs. None for every job.
+    pub fn status(&self) -> Option<JobStatus> {
+        match self {
+            JobStatusFilter::All => None,
+            JobStatusFilter::Queued => Some(JobStatus::Queued),
+            JobStatusFilter::Running => Some(JobStatus::Running),
+            JobStatusFilter::Success => Some(JobStatus::Success),
+            JobStatusFilter::Failure => Some(JobStatus::Failure),
+            JobStatusFilter::Expired => Some(JobStatus::Expired),
+            JobStatusFilter::Cancelled => Some(JobStatus::Cancelled),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatusFilter::All => "all",
+            JobStatusFilter::Queued => "queued",
+            JobStatusFilter::Running => "running",
+            JobStatusFilter::Success => "success",
+            JobStatusFilter::Failure => "failure",
+            JobStatusFilter::Expired => "expired",
+            JobStatusFilter::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// Response payload for GET /api/jobs endpoint: one page of jobs, newest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobsListResponse {
+    pub items: Vec<JobSummary>,
+    /// Number of jobs on all pages
+    pub total: u64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl JobsListResponse {
+    /// Number of pages of `per_page` jobs.
+    pub fn page_count(&self) -> u64 {
+        if self.per_page == 0 {
+            return 1;
+        }
+        self.total.div_ceil(u64::from(self.per_page)).max(1)
+    }
+
+    /// Whether there are jobs after this page's.
+    pub fn has_next_page(&self) -> bool {
+        u64::from(self.page) < self.page_count()
+    }
+}
+
+/// Response payload for GET /api/job/logs endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct JobLogsResponse {
+    pub job_id: Uuid,
+    /// One event per line: `<timestamp> <LEVEL> <target>: <message> <fields>`.
+    pub log: String,
+    /// Later events were dropped because the log got too big.
+    pub truncated: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_details_deserializes_api_response() {
+        let body = r##"{"job_id":"7d1c0b8e-1d4c-4b8e-9d7e-0f6a1f1f1f1f","url":"https://example.com","status":"Failure","kind":"New","llms_txt":null,"error_message":"HTTP 406","failure_kind":{"http_status":406},"failure_message":"The site returned HTTP 406."}"##;
+        let job: JobDetailsResponse = serde_json::from_str(body).unwrap();
+        assert_eq!(job.status, JobStatus::Failure);
+        assert!(job.status.is_completed());
+        assert_eq!(job.failure_kind, Some(FailureKind::HttpStatus(406)));
+        assert_eq!(job.error_message.as_deref(), Some("HTTP 406"));
+        assert_eq!(job.attempts, 1);
+    }
+
+    #[test]
+    fn test_detail_names() {
+        for detail in Detail::ALL {
+            assert_eq!(detail.as_str().parse::<Detail>(), Ok(detail));
+            assert_eq!(serde_json::to_value(detail).unwrap(), detail.as_str());
+        }
+        assert_eq!(Detail::default(), Detail::Standard);
+        assert!("exhaustive".parse::<Detail>().is_err());
+    }
+
+    #[test]
+    fn test_job_request_leaves_out_defaults() {
+        let json = serde_json::to_value(JobRequestPayload::new("https://example.com")).unwrap();
+        assert_eq!(json, serde_json::json!({"url": "https://example.com"}));
+
+        let request = JobRequestPayload {
+            model: Some("gpt-5".to_string()),
+            low_priority: true,
+            ..JobRequestPayload::new("https://example.com")
+        };
+        let json = serde_json::to_value(request).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"url": "https://example.com", "model": "gpt-5", "low_priority": true})
+        );
+    }
+    #[test]
+    fn test_crawl_limits_default() {
+        let request: JobRequestPayload =
+            serde_json::from_str(r#"{"url": "https://example.com", "crawl": {"max_pages": 10}}"#).unwrap();
+        assert_eq!(
+            request.crawl,
+            Some(Crawl {
+                max_depth: 2,
+                max_pages: 10
+            })
+        );
+        assert_eq!(serde_json::from_str::<Crawl>("{}").unwrap(), Crawl::default());
+    }
+
+    #[test]
+    fn test_list_page() {
+        let page: ListPage = serde_json::from_str(r#"{"page": 3}"#).unwrap();
+        assert_eq!(
+            page,
+            ListPage {
+                page: 3,
+                ..ListPage::default()
+            }
+        );
+        assert_eq!(page.offset(), 100);
+        assert_eq!(ListPage::default().offset(), 0);
+
+        let page: ListPage = serde_json::from_str(
+            r#"{"domain": "example.com", "status": "error", "created_after": "2026-01-01T00:00:00Z", "sort": "created_at_desc"}"#,
+        )
+        .unwrap();
+        assert_eq!(page.domain.as_deref(), Some("example.com"));
+        assert_eq!(page.status, Some(ResultStatus::Error));
+        assert_eq!(page.created_after.unwrap().to_rfc3339(), "2026-01-01T00:00:00+00:00");
+        assert_eq!(page.sort, ListSort::CreatedAtDesc);
+        assert_eq!(page.sort.as_str(), "created_at_desc");
+
+        let response = |total, page, per_page| LlmsTxtListResponse {
+            items: vec![],
+            total,
+            page,
+            per_page,
+        };
+        assert_eq!(response(0, 1, 50).page_count(), 1);
+        assert_eq!(response(100, 1, 50).page_count(), 2);
+        assert_eq!(response(101, 2, 50).page_count(), 3);
+        assert!(response(101, 2, 50).has_next_page());
+        assert!(!response(101, 3, 50).has_next_page());
+        // from a server that doesn't page: everything is on the one page
+        let old: LlmsTxtListResponse = serde_json::from_str(r#"{"items": []}"#).unwrap();
+        assert!(!old.has_next_page());
+    }
+
+    #[test]
+    fn test_jobs_page() {
+        let page: JobsPage = serde_json::from_str(r#"{"page": 2, "per_page": 10}"#).unwrap();
+        assert_eq!(page.offset(), 10);
+        assert_eq!(page.status, JobStatusFilter::All);
+        assert_eq!(page.status.status(), None);
+
+        let page: JobsPage = serde_json::from_str(r#"{"status": "failure", "url": "https://example.com"}"#).unwrap();
+        assert_eq!(page.page, 1);
+        assert_eq!(page.per_page, JobsPage::DEFAULT_PER_PAGE);
+        assert_eq!(page.status.status(), Some(JobStatus::Failure));
+        assert_eq!(page.url.as_deref(), Some("https://example.com"));
+        for filter in JobStatusFilter::ALL {
+            let parsed: JobStatusFilter = serde_json::from_value(serde_json::json!(filter.as_str())).unwrap();
+            assert_eq!(parsed, filter);
+        }
+    }
+
+    #[test]
+    fn test_live_event_wire_format() {
+        let event = LiveEvent::Job(JobSummary {
+            job_id: Uuid::nil(),
+            url: "https://example.com".to_string(),
+            status: JobStatus::Running,
+            kind: JobKind::New,
+            created_at: DateTime::UNIX_EPOCH,
+            expires_at: None,
+            model: None,
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "job");
+        assert_eq!(json["status"], "Running");
+        assert_eq!(json["url"], "https://example.com");
+
+        let lagged: LiveEvent = serde_json::from_str(r#"{"type": "lagged", "missed": 3}"#).unwrap();
+        assert!(matches!(lagged, LiveEvent::Lagged { missed: 3 }));
+        let result: LiveEvent = serde_json::from_value(serde_json::json!({
+            "type": "llms_txt",
+            "url": "https://example.com",
+            "job_id": Uuid::nil(),
+            "result_status": "Ok",
+            "created_at": "2026-03-01T12:00:00Z",
+        }))
+        .unwrap();
+        assert!(matches!(
+            result,
+            LiveEvent::LlmsTxt {
+                result_status: ResultStatus::Ok,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+//! Harness for end-to-end tests of the whole pipeline
+//!
+//! Boots the API on a local port and a worker in the same process, both on the same database pool, so a test can
+//! submit jobs through the API client and watch them go from queued to finished, like a user would. The LLM is
+//! whatever provider the test gives the worker (e.g. `core_ltx::llms::mock::MockLlmProvider`).
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use api_ltx::routes::router;
+use client_ltx::{ApiClient, ClientError, JobDetailsResponse, Uuid};
+use core_ltx::{db::DbPool, llms::LlmProvider, notify::Notifications};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use worker_ltx::{Publishers, WorkerSettings, worker_polling_loop};
+
+/// How long `wait_for` waits before giving up: generous, as the mock LLM answers at once.
+pub const WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often `wait_for` checks its condition.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Serves the API, without authentication, on a free local port. Returns its base URL, e.g. `http://127.0.0.1:1234`.
+pub async fn serve_api(pool: DbPool) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind the API server");
+    let addr = listener.local_addr().expect("The API server has a local address");
+    let app = router(None, pool);
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .await
+            .expect("The API server failed")
+    });
+    format!("http://{addr}")
+}
+
+/// Worker settings for tests: jobs are claimed as soon as they're queued.
+pub fn test_worker_settings() -> WorkerSettings {
+    WorkerSettings {
+        poll_interval: Duration::from_millis(50),
+        ..WorkerSettings::default()
+    }
+}
+
+/// Runs a worker claiming every job, with the providers made by `make_provider`. Nothing is notified nor published.
+/// It runs until the returned task is aborted, or the test's runtime shuts down.
+pub fn spawn_worker<P, F>(pool: DbPool, make_provider: F) -> JoinHandle<()>
+where
+    P: LlmProvider + 'static,
+    F: Fn(&WorkerSettings) -> P + Send + 'static,
+{
+    // the sender is dropped at once: the settings never change
+    let (_, settings) = watch::channel(test_worker_settings());
+    tokio::spawn(worker_polling_loop(
+        pool,
+        make_provider,
+        settings,
+        Arc::new(Notifications::default()),
+        Arc::new(Publishers::new(Vec::new())),
+        None,
+        None,
+    ))
+}
+
+/// Client for the API served at `base_url` (see `serve_api`).
+pub fn api_client(base_url: &str) -> Arc<ApiClient> {
+    Arc::new(ApiClient::new(base_url))
+}
+
+/// Polls `check` until it returns something, and returns that. Panics after `WAIT_TIMEOUT`, with `what` in the
+/// message.
+pub async fn wait_for<T, F, Fut>(what: &str, mut check: F) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<T>>,
+{
+    let wait = async {
+        loop {
+            if let Some(found) = check().await {
+                return found;
+            }
+            tokio::time::sleep(WAIT_POLL_INTERVAL).await;
+        }
+    };
+    tokio::time::timeout(WAIT_TIMEOUT, wait)
+        .await
+        .unwrap_or_else(|_| panic!("Timed out after {:?} waiting for {}", WAIT_TIMEOUT, what))
+}
+
+/// Waits until the job is finished (see `JobStatus::is_completed`), and returns its details.
+pub async fn wait_for_job(client: &ApiClient, job_id: Uuid) -> JobDetailsResponse {
+    wait_for(&format!("job {job_id} to finish"), || async {
+        match client.job(job_id).await {
+            Ok(job) if job.status.is_completed() => Some(job),
+            Ok(_) => None,
+            Err(error) => panic!("Failed to get job {job_id}: {error}"),
+        }
+    })
+    .await
+}
+
+/// Number of llms.txt generations stored for the URL, successful or not.
+pub async fn generations(client: &ApiClient, url: &str) -> Result<usize, ClientError> {
+    match client.llm_txt_history(url).await {
+        Ok(history) => Ok(history.versions.len()),
+        Err(error) if error.status() == Some(404) => Ok(0),
+        Err(error) => Err(error),
+    }
+}
@@ -0,0 +1,180 @@
+//! End-to-end tests of a website's lifecycle: API → worker → cron → API
+//!
+//! The website is served by a local `wiremock` server and the LLM is a `MockLlmProvider`, so these need the test
+//! database but neither network access nor an API key. Jobs are submitted and checked through the API client, like
+//! a user would, while the worker and cron updates run against the same database.
+
+use std::time::Duration;
+
+use client_ltx::{ApiClient, JobKind, JobRequestPayload, JobStatus};
+use core_ltx::llms::mock::{MockLlmProvider, minimal_llms_txt, sample_html, sample_valid_llms_txt};
+use cron_ltx::poll_and_process;
+use data_model_ltx::test_helpers::{TestDbGuard, clean_test_db, test_db_pool};
+use e2e_ltx::{api_client, generations, serve_api, spawn_worker, wait_for, wait_for_job};
+use tokio::sync::Mutex;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Only in the website's HTML once it changed: prompts with it are answered with `minimal_llms_txt()`.
+const CHANGED_MARKER: &str = "Now with a changelog";
+
+/// These tests require sequential execution.
+static TEST_MUTEX: Mutex<()> = Mutex::const_new(());
+
+/// The website is on localhost: jobs for it, and the downloads of its pages, must be allowed.
+fn allow_private_hosts() {
+    // SAFETY: set before the API, the worker and cron read it, and these tests run one at a time
+    unsafe { std::env::set_var(core_ltx::domain_policy::ALLOW_PRIVATE_HOSTS_ENV_VAR, "true") };
+}
+
+/// Answers with `sample_valid_llms_txt()`, unless the website changed (see `CHANGED_MARKER`).
+fn mock_provider() -> MockLlmProvider {
+    let mut provider = MockLlmProvider::with_response(CHANGED_MARKER, minimal_llms_txt());
+    provider.set_default(sample_valid_llms_txt());
+    provider
+}
+
+fn changed_html() -> String {
+    sample_html().replace(
+        "<li>Feature 3</li>",
+        &format!("<li>Feature 3</li><li>{CHANGED_MARKER}</li>"),
+    )
+}
+
+/// Serves `html` as the website's home page. Other paths (e.g. `/robots.txt`, `/llms.txt`) are 404s.
+async fn serve_home_page(website: &MockServer, html: &str) {
+    website.reset().await;
+    Mock::given(method("GET"))
+        .and(path("/"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(html.to_string(), "text/html"))
+        .mount(website)
+        .await;
+}
+
+async fn requests_received(website: &MockServer) -> usize {
+    website.received_requests().await.map(|r| r.len()).unwrap_or_default()
+}
+
+/// Waits for the URL to have `count` generations, and returns the newest one's job ID.
+async fn wait_for_generation(client: &ApiClient, url: &str, count: usize) -> client_ltx::Uuid {
+    wait_for(&format!("generation {count} of {url}"), || async {
+        match generations(client, url).await {
+            Ok(n) if n >= count => Some(()),
+            Ok(_) => None,
+            Err(error) => panic!("Failed to get the history of {url}: {error}"),
+        }
+    })
+    .await;
+    let history = client.llm_txt_history(url).await.unwrap();
+    history.versions[0].job_id
+}
+
+#[tokio::test]
+async fn test_lifecycle_generation_then_cron_update() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+    allow_private_hosts();
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    let website = MockServer::start().await;
+    serve_home_page(&website, sample_html()).await;
+    let api_base_url = serve_api(pool.clone()).await;
+    let client = api_client(&api_base_url);
+
+    // Queued: no worker is running yet
+    let url = format!("{}/", website.uri());
+    let created = client.post_llm_txt(&JobRequestPayload::new(&url)).await.unwrap();
+    let queued = client.job(created.job_id).await.unwrap();
+    assert_eq!(queued.status, JobStatus::Queued);
+    assert_eq!(queued.kind, JobKind::New);
+    let url = queued.url;
+
+    // Success: the worker generates it
+    let worker = spawn_worker(pool.clone(), |_| mock_provider());
+    let generated = wait_for_job(&client, created.job_id).await;
+    assert_eq!(
+        generated.status,
+        JobStatus::Success,
+        "Generation failed: {:?}",
+        generated.error_message
+    );
+    let llms_txt = client.get_llm_txt(&url).await.unwrap();
+    assert!(llms_txt.contains("# Example Website"), "{llms_txt}");
+    assert_eq!(generated.llms_txt.as_deref(), Some(llms_txt.as_str()));
+
+    // Cron: the website didn't change, so there's nothing to update
+    let before = requests_received(&website).await;
+    let spawned = poll_and_process(&pool, &client, &api_base_url).await.unwrap();
+    assert_eq!(spawned, 1);
+    wait_for("cron to download the website", || async {
+        (requests_received(&website).await > before).then_some(())
+    })
+    .await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(client.in_progress_jobs().await.unwrap().is_empty());
+    assert_eq!(generations(&client, &url).await.unwrap(), 1);
+
+    // Cron: the website changed, so it's updated
+    serve_home_page(&website, &changed_html()).await;
+    poll_and_process(&pool, &client, &api_base_url).await.unwrap();
+    let update_job_id = wait_for_generation(&client, &url, 2).await;
+    let updated = wait_for_job(&client, update_job_id).await;
+    assert_eq!(
+        updated.status,
+        JobStatus::Success,
+        "Update failed: {:?}",
+        updated.error_message
+    );
+    assert_eq!(updated.kind, JobKind::Update);
+    let llms_txt = client.get_llm_txt(&url).await.unwrap();
+    assert!(llms_txt.contains("# Site"), "The update is served: {llms_txt}");
+
+    worker.abort();
+}
+
+#[tokio::test]
+async fn test_lifecycle_failure_then_cron_retry() {
+    let _db = TestDbGuard::acquire().await;
+    let _guard = TEST_MUTEX.lock().await;
+    allow_private_hosts();
+
+    let pool = test_db_pool().await;
+    clean_test_db(&pool).await;
+    let website = MockServer::start().await;
+    serve_home_page(&website, sample_html()).await;
+    let api_base_url = serve_api(pool.clone()).await;
+    let client = api_client(&api_base_url);
+    // the LLM only answers once the website changed: until then, generating fails
+    let worker = spawn_worker(pool.clone(), |_| {
+        MockLlmProvider::with_response(CHANGED_MARKER, minimal_llms_txt())
+    });
+
+    let url = format!("{}/", website.uri());
+    let created = client.post_llm_txt(&JobRequestPayload::new(&url)).await.unwrap();
+    let failed = wait_for_job(&client, created.job_id).await;
+    assert_eq!(failed.status, JobStatus::Failure);
+    assert!(failed.failure_kind.is_some(), "The failure is classified");
+    let url = failed.url;
+    assert_eq!(
+        generations(&client, &url).await.unwrap(),
+        1,
+        "The failed generation is stored"
+    );
+
+    // Cron: the failure is retryable, so the generation is retried, and succeeds now
+    serve_home_page(&website, &changed_html()).await;
+    poll_and_process(&pool, &client, &api_base_url).await.unwrap();
+    let retry_job_id = wait_for_generation(&client, &url, 2).await;
+    let retried = wait_for_job(&client, retry_job_id).await;
+    assert_eq!(
+        retried.status,
+        JobStatus::Success,
+        "Retry failed: {:?}",
+        retried.error_message
+    );
+    assert_eq!(retried.kind, JobKind::New);
+    assert!(client.get_llm_txt(&url).await.unwrap().contains("# Site"));
+
+    worker.abort();
+}
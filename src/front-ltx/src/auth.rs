@@ -14,11 +14,33 @@ use crate::Page;
 pub struct AuthCheckResponse {
     pub auth_enabled: bool,
     pub authenticated: bool,
+    /// Browsing works without logging in: only generating llms.txt files requires it.
+    #[serde(default)]
+    pub public_reads: bool,
 }
 
 #[derive(Debug, Serialize)]
 struct LoginRequest {
     password: String,
+    /// Code of the authenticator app, or a recovery code, once two-factor authentication is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+}
+
+/// Body of the 401 of a login that needs a (right) second factor
+#[derive(Debug, Deserialize)]
+struct LoginErrorResponse {
+    #[serde(default)]
+    second_factor_required: bool,
+}
+
+/// Why a login failed
+enum LoginError {
+    /// The password was right, but a code is needed. `retried` when the code that was sent is wrong.
+    SecondFactorRequired {
+        retried: bool,
+    },
+    Failed(JsValue),
 }
 
 #[derive(Debug, Deserialize)]
@@ -54,11 +76,30 @@ pub async fn check_auth_status() -> Result<AuthCheckResponse, JsValue> {
     Ok(data)
 }
 
-/// Login with password
-async fn login(password: String) -> Result<LoginResponse, JsValue> {
+/// Login with password, and the second factor's code once it's asked for
+async fn login(password: String, code: Option<String>) -> Result<LoginResponse, LoginError> {
+    let retried = code.is_some();
+    let response = send_login(LoginRequest { password, code })
+        .await
+        .map_err(LoginError::Failed)?;
+    match response {
+        Ok(data) => Ok(data),
+        Err(text) => {
+            let second_factor_required =
+                serde_json::from_str::<LoginErrorResponse>(&text).is_ok_and(|error| error.second_factor_required);
+            if second_factor_required {
+                Err(LoginError::SecondFactorRequired { retried })
+            } else {
+                Err(LoginError::Failed(JsValue::from_str(&text)))
+            }
+        }
+    }
+}
+
+/// Sends the login. The inner error is the body of an error response.
+async fn send_login(request_body: LoginRequest) -> Result<Result<LoginResponse, String>, JsValue> {
     let window = web_sys::window().expect("no global window exists");
 
-    let request_body = LoginRequest { password };
     let body_str = serde_json::to_string(&request_body)
         .map_err(|e| JsValue::from_str(&format!("Failed to serialize login request: {}", e)))?;
 
@@ -76,13 +117,13 @@ async fn login(password: String) -> Result<LoginResponse, JsValue> {
     if !resp.ok() {
         let text = JsFuture::from(resp.text()?).await?;
         let error_text = text.as_string().unwrap_or_else(|| "Invalid credentials".to_string());
-        return Err(JsValue::from_str(&error_text));
+        return Ok(Err(error_text));
     }
 
     let json = JsFuture::from(resp.json()?).await?;
     let data: LoginResponse = serde_wasm_bindgen::from_value(json)?;
 
-    Ok(data)
+    Ok(Ok(data))
 }
 
 // ============================================================================
@@ -105,11 +146,21 @@ pub fn create_login_page(document: &Document, container: &web_sys::Element) -> R
     password_input.set_placeholder("Enter password");
     password_input.set_id("password-input");
 
+    // Only shown once the server asks for the second factor
+    let code_input = document.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+    code_input.set_type("text");
+    code_input.set_placeholder("Authenticator or recovery code");
+    code_input.set_id("code-input");
+    code_input.set_attribute("autocomplete", "one-time-code")?;
+    code_input.set_attribute("style", "display: none;")?;
+
     let login_btn = document.create_element("button")?;
     login_btn.set_text_content(Some("Login"));
     login_btn.set_class_name("menu-button");
+    login_btn.set_id("login-button");
 
     input_group.append_child(&password_input)?;
+    input_group.append_child(&code_input)?;
     input_group.append_child(&login_btn)?;
     container.append_child(&input_group)?;
 
@@ -121,90 +172,89 @@ pub fn create_login_page(document: &Document, container: &web_sys::Element) -> R
 
     // Login button click handler
     let document_clone = document.clone();
-    let password_input_clone = password_input.clone();
     let closure = Closure::wrap(Box::new(move || {
-        let document = document_clone.clone();
-        let password_input = password_input_clone.clone();
-
-        spawn_local(async move {
-            let password = password_input.value();
-
-            if password.is_empty() {
-                show_login_error(&document, "Please enter a password");
-                return;
-            }
-
-            // Disable button during login attempt
-            if let Some(btn) = document.get_element_by_id("login-button") {
-                btn.set_attribute("disabled", "true").ok();
-            }
-
-            match login(password).await {
-                Ok(_) => {
-                    console::log_1(&"Login successful".into());
-                    // Navigate to main page
-                    let window = web_sys::window().expect("no global window exists");
-                    let document = window.document().expect("should have a document on window");
-                    crate::show_page(&document, Page::Main).ok();
-                }
-                Err(e) => {
-                    console::log_1(&format!("Login failed: {:?}", e).into());
-                    show_login_error(&document, "Incorrect password");
-
-                    // Re-enable button
-                    if let Some(btn) = document.get_element_by_id("login-button") {
-                        btn.remove_attribute("disabled").ok();
-                    }
-
-                    // Clear password field
-                    password_input.set_value("");
-                }
-            }
-        });
+        spawn_local(submit_login(document_clone.clone()));
     }) as Box<dyn FnMut()>);
 
     login_btn.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
     closure.forget();
 
-    // Enter key handler
+    // Enter key handler, in either input
     let document_clone2 = document.clone();
-    let password_input_clone2 = password_input.clone();
     let closure2 = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
         if event.key() == "Enter" {
-            let document = document_clone2.clone();
-            let password_input = password_input_clone2.clone();
-
-            spawn_local(async move {
-                let password = password_input.value();
-
-                if password.is_empty() {
-                    show_login_error(&document, "Please enter a password");
-                    return;
-                }
-
-                match login(password).await {
-                    Ok(_) => {
-                        console::log_1(&"Login successful".into());
-                        let window = web_sys::window().expect("no global window exists");
-                        let document = window.document().expect("should have a document on window");
-                        crate::show_page(&document, Page::Main).ok();
-                    }
-                    Err(e) => {
-                        console::log_1(&format!("Login failed: {:?}", e).into());
-                        show_login_error(&document, "Incorrect password");
-                        password_input.set_value("");
-                    }
-                }
-            });
+            spawn_local(submit_login(document_clone2.clone()));
         }
     }) as Box<dyn FnMut(_)>);
 
     password_input.add_event_listener_with_callback("keypress", closure2.as_ref().unchecked_ref())?;
+    code_input.add_event_listener_with_callback("keypress", closure2.as_ref().unchecked_ref())?;
     closure2.forget();
 
     Ok(())
 }
 
+/// Logs in with what's in the password (and code) inputs
+async fn submit_login(document: Document) {
+    let input = |id: &str| {
+        document
+            .get_element_by_id(id)
+            .and_then(|element| element.dyn_into::<HtmlInputElement>().ok())
+    };
+    let (Some(password_input), Some(code_input)) = (input("password-input"), input("code-input")) else {
+        return;
+    };
+    let password = password_input.value();
+
+    if password.is_empty() {
+        show_login_error(&document, "Please enter a password");
+        return;
+    }
+    let code = Some(code_input.value().trim().to_string()).filter(|code| !code.is_empty());
+
+    // Disable button during login attempt
+    if let Some(btn) = document.get_element_by_id("login-button") {
+        btn.set_attribute("disabled", "true").ok();
+    }
+
+    match login(password, code).await {
+        Ok(_) => {
+            console::log_1(&"Login successful".into());
+            // Navigate to main page
+            let window = web_sys::window().expect("no global window exists");
+            let document = window.document().expect("should have a document on window");
+            crate::show_page(&document, Page::Main).ok();
+            return;
+        }
+        Err(LoginError::SecondFactorRequired { retried }) => {
+            // the password was right: keep it, and ask for the code
+            code_input.set_attribute("style", "display: block;").ok();
+            code_input.set_value("");
+            code_input.focus().ok();
+            show_login_error(
+                &document,
+                if retried {
+                    "Incorrect code"
+                } else {
+                    "Enter the code of your authenticator app, or a recovery code"
+                },
+            );
+        }
+        Err(LoginError::Failed(e)) => {
+            console::log_1(&format!("Login failed: {:?}", e).into());
+            show_login_error(&document, "Incorrect password");
+
+            // Clear password field
+            password_input.set_value("");
+        }
+    }
+
+    // Re-enable button
+    if let Some(btn) = document.get_element_by_id("login-button") {
+        btn.remove_attribute("disabled").ok();
+    }
+}
+
 fn show_login_error(document: &Document, message: &str) {
     if let Some(error_div) = document.get_element_by_id("login-error") {
         error_div.set_text_content(Some(message));
@@ -213,3 +263,292 @@ fn show_login_error(document: &Document, message: &str) {
             .ok();
     }
 }
+
+// ============================================================================
+// Two-Factor Authentication
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct TotpStatus {
+    enabled: bool,
+    pending: bool,
+    recovery_codes_left: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TotpEnrollment {
+    secret: String,
+    otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecoveryCodes {
+    recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+/// Sends a request to a two-factor endpoint, with a `code` in its body if there's one. Returns the response's JSON
+/// (null when it has none), or the server's error message.
+async fn totp_request(method: &str, path: &str, code: Option<String>) -> Result<JsValue, String> {
+    let window = web_sys::window().expect("no global window exists");
+
+    let opts = &mut RequestInit::new();
+    opts.set_method(method);
+    opts.set_mode(RequestMode::Cors);
+    if let Some(code) = code {
+        opts.set_body(&JsValue::from_str(&serde_json::json!({ "code": code }).to_string()));
+    }
+
+    let request = Request::new_with_str_and_init(path, opts).map_err(|e| format!("{:?}", e))?;
+    request
+        .headers()
+        .set("Content-Type", "application/json")
+        .map_err(|e| format!("{:?}", e))?;
+
+    let resp: Response = JsFuture::from(window.fetch_with_request(&request))
+        .await
+        .and_then(|value| value.dyn_into())
+        .map_err(|e| format!("Request failed: {:?}", e))?;
+    let text = JsFuture::from(resp.text().map_err(|e| format!("{:?}", e))?)
+        .await
+        .map_err(|e| format!("{:?}", e))?
+        .as_string()
+        .unwrap_or_default();
+
+    if !resp.ok() {
+        return Err(serde_json::from_str::<ErrorResponse>(&text)
+            .map(|error| error.error)
+            .unwrap_or(text));
+    }
+    if text.is_empty() {
+        return Ok(JsValue::NULL);
+    }
+    js_sys::JSON::parse(&text).map_err(|e| format!("Invalid response: {:?}", e))
+}
+
+fn parse<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, String> {
+    serde_wasm_bindgen::from_value(value).map_err(|e| format!("Invalid response: {}", e))
+}
+
+/// Create the page that enables, and disables, the second factor of the login
+pub fn create_two_factor_page(document: &Document, container: &web_sys::Element) -> Result<(), JsValue> {
+    container.append_child(&crate::create_back_button(document)?.into())?;
+
+    let heading = document.create_element("h1")?;
+    heading.set_text_content(Some("Two-Factor Authentication"));
+    container.append_child(&heading)?;
+
+    let section = document.create_element("div")?;
+    section.set_id("two-factor");
+    section.set_class_name("results");
+    container.append_child(&section)?;
+
+    spawn_local(render_two_factor_status());
+    Ok(())
+}
+
+/// The `two-factor` section, emptied
+fn two_factor_section() -> Option<(Document, web_sys::Element)> {
+    let window = web_sys::window()?;
+    let document = window.document()?;
+    let section = document.get_element_by_id("two-factor")?;
+    section.set_inner_html("");
+    Some((document, section))
+}
+
+fn append_text(document: &Document, parent: &web_sys::Element, tag: &str, text: &str) -> Result<(), JsValue> {
+    let element = document.create_element(tag)?;
+    element.set_text_content(Some(text));
+    parent.append_child(&element)?;
+    Ok(())
+}
+
+/// Input for a code of the authenticator app (or a recovery code), and buttons that send it
+fn append_code_form(
+    document: &Document,
+    parent: &web_sys::Element,
+    buttons: &[(&str, fn(String))],
+) -> Result<(), JsValue> {
+    let input_group = document.create_element("div")?;
+    input_group.set_class_name("input-group");
+
+    let code_input = document.create_element("input")?.dyn_into::<HtmlInputElement>()?;
+    code_input.set_type("text");
+    code_input.set_placeholder("Code");
+    code_input.set_attribute("autocomplete", "one-time-code")?;
+    input_group.append_child(&code_input)?;
+
+    for &(label, action) in buttons {
+        let button = document.create_element("button")?;
+        button.set_text_content(Some(label));
+        let code_input = code_input.clone();
+        let closure = Closure::wrap(Box::new(move || action(code_input.value().trim().to_string())) as Box<dyn Fn()>);
+        button.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+        input_group.append_child(&button)?;
+    }
+
+    parent.append_child(&input_group)?;
+    Ok(())
+}
+
+fn show_two_factor_error(message: &str) {
+    let window = web_sys::window().expect("no global window exists");
+    let document = window.document().expect("should have a document on window");
+    crate::show_error_modal(&document, message);
+}
+
+async fn render_two_factor_status() {
+    let status = match totp_request("GET", "/api/auth/totp", None)
+        .await
+        .and_then(parse::<TotpStatus>)
+    {
+        Ok(status) => status,
+        Err(e) => {
+            show_two_factor_error(&e);
+            return;
+        }
+    };
+    let Some((document, section)) = two_factor_section() else {
+        return;
+    };
+
+    if status.enabled {
+        append_text(
+            &document,
+            &section,
+            "p",
+            &format!(
+                "Enabled: logins need a code of your authenticator app. {} recovery code(s) left.",
+                status.recovery_codes_left
+            ),
+        )
+        .ok();
+        append_code_form(
+            &document,
+            &section,
+            &[
+                ("New recovery codes", |code: String| {
+                    spawn_local(async move {
+                        match totp_request("POST", "/api/auth/totp/recovery_codes", Some(code))
+                            .await
+                            .and_then(parse::<RecoveryCodes>)
+                        {
+                            Ok(codes) => render_recovery_codes(&codes.recovery_codes),
+                            Err(e) => show_two_factor_error(&e),
+                        }
+                    })
+                }),
+                ("Disable", |code: String| {
+                    spawn_local(async move {
+                        match totp_request("POST", "/api/auth/totp/disable", Some(code)).await {
+                            Ok(_) => render_two_factor_status().await,
+                            Err(e) => show_two_factor_error(&e),
+                        }
+                    })
+                }),
+            ],
+        )
+        .ok();
+        return;
+    }
+
+    append_text(
+        &document,
+        &section,
+        "p",
+        if status.pending {
+            "Not enabled yet: the enrollment wasn't confirmed with a code. Start over to get a new secret."
+        } else {
+            "Disabled: logins only need the password."
+        },
+    )
+    .ok();
+    let Ok(enable_btn) = document.create_element("button") else {
+        return;
+    };
+    enable_btn.set_text_content(Some("Enable"));
+    let closure = Closure::wrap(Box::new(|| {
+        spawn_local(async {
+            match totp_request("POST", "/api/auth/totp/enroll", None)
+                .await
+                .and_then(parse::<TotpEnrollment>)
+            {
+                Ok(enrollment) => render_enrollment(&enrollment),
+                Err(e) => show_two_factor_error(&e),
+            }
+        })
+    }) as Box<dyn Fn()>);
+    enable_btn
+        .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+        .ok();
+    closure.forget();
+    section.append_child(&enable_btn).ok();
+}
+
+fn render_enrollment(enrollment: &TotpEnrollment) {
+    let Some((document, section)) = two_factor_section() else {
+        return;
+    };
+    append_text(
+        &document,
+        &section,
+        "p",
+        "Add this account to your authenticator app, then enter the code it shows to confirm.",
+    )
+    .ok();
+
+    if let Ok(link) = document.create_element("a") {
+        link.set_attribute("href", &enrollment.otpauth_uri).ok();
+        link.set_text_content(Some("Open in authenticator app"));
+        section.append_child(&link).ok();
+    }
+    append_text(&document, &section, "p", "Or enter this secret by hand:").ok();
+    append_text(&document, &section, "pre", &enrollment.secret).ok();
+
+    append_code_form(
+        &document,
+        &section,
+        &[("Confirm", |code: String| {
+            spawn_local(async move {
+                match totp_request("POST", "/api/auth/totp/confirm", Some(code))
+                    .await
+                    .and_then(parse::<RecoveryCodes>)
+                {
+                    Ok(codes) => render_recovery_codes(&codes.recovery_codes),
+                    Err(e) => show_two_factor_error(&e),
+                }
+            })
+        })],
+    )
+    .ok();
+}
+
+fn render_recovery_codes(codes: &[String]) {
+    let Some((document, section)) = two_factor_section() else {
+        return;
+    };
+    append_text(
+        &document,
+        &section,
+        "p",
+        "Save these recovery codes somewhere safe: each one logs in once without the authenticator app. They won't be shown again.",
+    )
+    .ok();
+    append_text(&document, &section, "pre", &codes.join("\n")).ok();
+
+    let Ok(done_btn) = document.create_element("button") else {
+        return;
+    };
+    done_btn.set_text_content(Some("Done"));
+    let closure = Closure::wrap(Box::new(|| spawn_local(render_two_factor_status())) as Box<dyn Fn()>);
+    done_btn
+        .add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())
+        .ok();
+    closure.forget();
+    section.append_child(&done_btn).ok();
+}
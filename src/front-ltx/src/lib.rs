@@ -1,46 +1,19 @@
 mod auth;
+mod markdown;
 
-use pulldown_cmark::{Parser, html};
-use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use client_ltx::{
+    ApiClient, ClientError, JobDetailsResponse, JobRequestPayload, JobStatus, JobStatusFilter, JobSummary,
+    JobsListResponse, JobsPage, ListPage, LiveEvent, LlmsTxtListItem, LlmsTxtListResponse, RelatedLlmsTxtResponse,
+    Uuid,
+};
+use front_markdown_ltx::is_safe_url;
 use wasm_bindgen::JsCast;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::{JsFuture, spawn_local};
-use web_sys::{Document, HtmlElement, HtmlInputElement, Request, RequestInit, RequestMode, Response, console};
-
-// ============================================================================
-// Data Models
-// ============================================================================
-
-#[derive(Debug, Deserialize)]
-struct LlmsTxtListItem {
-    url: String,
-    llm_txt: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct LlmsTxtListResponse {
-    items: Vec<LlmsTxtListItem>,
-}
-
-#[derive(Debug, Deserialize, Serialize)]
-struct UrlPayload {
-    url: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct LlmTxtResponse {
-    content: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct JobState {
-    job_id: String,
-    url: String,
-    status: String,
-    kind: String,
-    llms_txt: Option<String>,
-    error_message: Option<String>,
-}
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{Document, HtmlElement, HtmlInputElement, HtmlSelectElement, console};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Page {
@@ -50,7 +23,9 @@ pub enum Page {
     GenerateOrUpdate,
     ListAll,
     ListInProgress,
+    ListJobs,
     InspectJob,
+    TwoFactor,
 }
 
 // ============================================================================
@@ -69,7 +44,7 @@ pub fn main() -> Result<(), JsValue> {
     spawn_local(async move {
         match auth::check_auth_status().await {
             Ok(auth_status) => {
-                if auth_status.auth_enabled && !auth_status.authenticated {
+                if auth_status.auth_enabled && !auth_status.authenticated && !auth_status.public_reads {
                     console::log_1(&"Auth required, showing login page".into());
                     show_page(&document_clone, Page::Login).ok();
                 } else {
@@ -91,7 +66,16 @@ pub fn main() -> Result<(), JsValue> {
 // Navigation
 // ============================================================================
 
+thread_local! {
+    /// The WebSocket keeping the in-progress jobs page up to date: closed when another page is shown.
+    static LIVE_SOCKET: RefCell<Option<web_sys::WebSocket>> = const { RefCell::new(None) };
+}
+
 pub fn show_page(document: &Document, page: Page) -> Result<(), JsValue> {
+    if let Some(socket) = LIVE_SOCKET.with(|live| live.borrow_mut().take()) {
+        socket.close().ok();
+    }
+
     let body = document.body().expect("document should have a body");
     body.set_inner_html("");
 
@@ -105,7 +89,9 @@ pub fn show_page(document: &Document, page: Page) -> Result<(), JsValue> {
         Page::GenerateOrUpdate => create_generate_or_update_page(document, &container)?,
         Page::ListAll => create_list_all_page(document, &container)?,
         Page::ListInProgress => create_list_in_progress_page(document, &container)?,
+        Page::ListJobs => create_list_jobs_page(document, &container)?,
         Page::InspectJob => create_inspect_job_page(document, &container)?,
+        Page::TwoFactor => auth::create_two_factor_page(document, &container)?,
     }
 
     body.append_child(&container)?;
@@ -150,32 +136,49 @@ fn create_main_page(document: &Document, container: &web_sys::Element) -> Result
         ),
         (Page::ListAll, "List all up-to-date llms.txts"),
         (Page::ListInProgress, "List all in-progress jobs"),
+        (Page::ListJobs, "Browse all jobs"),
         (Page::InspectJob, "Inspect an in-progress job"),
     ];
 
     for (page, label) in &pages {
-        let button = document.create_element("button")?;
-        button.set_text_content(Some(label));
-        button.set_class_name("menu-button");
+        container.append_child(&create_menu_button(document, *page, label)?)?;
+    }
 
-        let page_copy = *page;
-        let closure = Closure::wrap(Box::new(move || {
-            let window = web_sys::window().expect("no global window exists");
-            let document = window.document().expect("should have a document on window");
-            show_page(&document, page_copy).ok();
-        }) as Box<dyn Fn()>);
+    // only logged-in users of a server with a password can set up its second factor
+    let container = container.clone();
+    spawn_local(async move {
+        if let Ok(status) = auth::check_auth_status().await
+            && status.auth_enabled
+            && status.authenticated
+            && let Some(document) = web_sys::window().and_then(|window| window.document())
+            && let Ok(button) = create_menu_button(&document, Page::TwoFactor, "Two-factor authentication")
+        {
+            container.append_child(&button).ok();
+        }
+    });
 
-        button
-            .dyn_ref::<HtmlElement>()
-            .expect("button should be an HtmlElement")
-            .set_onclick(Some(closure.as_ref().unchecked_ref()));
+    Ok(())
+}
 
-        closure.forget();
+fn create_menu_button(document: &Document, page: Page, label: &str) -> Result<web_sys::Element, JsValue> {
+    let button = document.create_element("button")?;
+    button.set_text_content(Some(label));
+    button.set_class_name("menu-button");
 
-        container.append_child(&button)?;
-    }
+    let closure = Closure::wrap(Box::new(move || {
+        let window = web_sys::window().expect("no global window exists");
+        let document = window.document().expect("should have a document on window");
+        show_page(&document, page).ok();
+    }) as Box<dyn Fn()>);
 
-    Ok(())
+    button
+        .dyn_ref::<HtmlElement>()
+        .expect("button should be an HtmlElement")
+        .set_onclick(Some(closure.as_ref().unchecked_ref()));
+
+    closure.forget();
+
+    Ok(button)
 }
 
 // ============================================================================
@@ -233,12 +236,18 @@ fn create_get_llmstxt_page(document: &Document, container: &web_sys::Element) ->
 
         wasm_bindgen_futures::spawn_local(async move {
             match fetch_llm_txt(&url).await {
-                Ok(data) => display_text_result(&data.content),
+                Ok(content) => display_text_result(&content),
                 Err(e) => {
                     console::error_1(&format!("Could not retrieve llms.txt file due to: {:?}", e).into());
                     display_text_result(&format!("Could not retrieve llms.txt file due to: {:?}", e));
+                    return;
                 }
             }
+            // only shown when the server has semantic search
+            match fetch_related(&url).await {
+                Ok(related) => display_related(&related),
+                Err(e) => console::log_1(&format!("No related documentation: {:?}", e).into()),
+            }
         });
     }) as Box<dyn Fn()>);
 
@@ -337,18 +346,38 @@ fn create_list_all_page(document: &Document, container: &web_sys::Element) -> Re
     heading.set_text_content(Some("All Up-to-Date LLMs.txts"));
     container.append_child(&heading)?;
 
+    let pager_div = document.create_element("div")?;
+    pager_div.set_id("pager");
+    pager_div.set_class_name("pager");
+    container.append_child(&pager_div)?;
+
     let results_div = document.create_element("div")?;
     results_div.set_id("results");
     results_div.set_class_name("results");
     container.append_child(&results_div)?;
 
+    load_list_page(1);
+
+    Ok(())
+}
+
+/// Fetches a page of the list and shows it, under buttons to the previous and next pages.
+fn load_list_page(page: u32) {
     wasm_bindgen_futures::spawn_local(async move {
-        match fetch_list().await {
-            Ok(data) => {
-                if data.items.is_empty() {
+        match fetch_list_page(page).await {
+            Ok(response) => {
+                display_pager(
+                    response.page,
+                    response.page_count(),
+                    &format!("{} websites", response.total),
+                    load_list_page,
+                );
+                if !response.items.is_empty() {
+                    display_list_results(&response.items);
+                } else if response.total == 0 {
                     display_text_result("No llms.txt results exist!");
                 } else {
-                    display_list_results(&data);
+                    display_text_result("No llms.txt results on this page.");
                 }
             }
             Err(e) => {
@@ -357,8 +386,50 @@ fn create_list_all_page(document: &Document, container: &web_sys::Element) -> Re
             }
         }
     });
+}
 
-    Ok(())
+/// Shows buttons to the previous and next pages, which `load` them, around where the page is: e.g. "Page 2 of 3
+/// (120 websites)".
+fn display_pager(page: u32, page_count: u64, total: &str, load: fn(u32)) {
+    let window = web_sys::window().expect("no global window exists");
+    let document = window.document().expect("should have a document on window");
+
+    let pager_div = document.get_element_by_id("pager").expect("pager div should exist");
+    pager_div.set_inner_html("");
+    if page_count <= 1 {
+        return;
+    }
+
+    let previous = create_pager_button(&document, "← Previous", page.saturating_sub(1), page > 1, load);
+    pager_div.append_child(&previous).unwrap();
+
+    let info = document.create_element("span").unwrap();
+    info.set_text_content(Some(&format!("Page {} of {} ({})", page, page_count, total)));
+    pager_div.append_child(&info).unwrap();
+
+    let next = create_pager_button(&document, "Next →", page + 1, u64::from(page) < page_count, load);
+    pager_div.append_child(&next).unwrap();
+}
+
+fn create_pager_button(document: &Document, label: &str, page: u32, enabled: bool, load: fn(u32)) -> web_sys::Element {
+    let button = document.create_element("button").unwrap();
+    button.set_class_name("pager-button");
+    button.set_text_content(Some(label));
+    if !enabled {
+        button.set_attribute("disabled", "").unwrap();
+        return button;
+    }
+
+    let closure = Closure::wrap(Box::new(move || {
+        load(page);
+    }) as Box<dyn Fn()>);
+    button
+        .dyn_ref::<HtmlElement>()
+        .expect("button should be an HtmlElement")
+        .set_onclick(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+
+    button
 }
 
 // ============================================================================
@@ -380,11 +451,8 @@ fn create_list_in_progress_page(document: &Document, container: &web_sys::Elemen
     wasm_bindgen_futures::spawn_local(async move {
         match fetch_in_progress_jobs().await {
             Ok(jobs) => {
-                if jobs.is_empty() {
-                    display_text_result("No in-progress jobs.");
-                } else {
-                    display_jobs_results(&jobs);
-                }
+                display_in_progress_jobs(&jobs);
+                watch_in_progress_jobs(jobs);
             }
             Err(e) => {
                 console::error_1(&format!("Error: {:?}", e).into());
@@ -396,8 +464,192 @@ fn create_list_in_progress_page(document: &Document, container: &web_sys::Elemen
     Ok(())
 }
 
+fn display_in_progress_jobs(jobs: &[JobSummary]) {
+    if jobs.is_empty() {
+        display_text_result("No in-progress jobs.");
+    } else {
+        display_jobs_results(jobs);
+    }
+}
+
+/// Keeps the listed jobs up to date with the changes pushed over GET /api/ws: queued jobs are added, and finished
+/// ones removed. If the socket can't be opened, the list stays as it was loaded.
+fn watch_in_progress_jobs(jobs: Vec<JobSummary>) {
+    let socket = match live_updates_url().and_then(|url| web_sys::WebSocket::new(&url)) {
+        Ok(socket) => socket,
+        Err(e) => {
+            console::error_1(&format!("Live updates unavailable: {:?}", e).into());
+            return;
+        }
+    };
+    let jobs = Rc::new(RefCell::new(jobs));
+
+    let socket_clone = socket.clone();
+    let onmessage = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+        let Some(text) = event.data().as_string() else {
+            return;
+        };
+        match serde_json::from_str::<LiveEvent>(&text) {
+            Ok(LiveEvent::Job(job)) => {
+                let mut jobs = jobs.borrow_mut();
+                match jobs.iter().position(|listed| listed.job_id == job.job_id) {
+                    Some(index) if job.status.is_completed() => {
+                        jobs.remove(index);
+                    }
+                    Some(index) => jobs[index] = job,
+                    None if !job.status.is_completed() => jobs.push(job),
+                    None => return,
+                }
+                display_in_progress_jobs(&jobs);
+            }
+            Ok(LiveEvent::Lagged { .. }) => {
+                // some changes were missed: start over from the current jobs
+                let jobs = jobs.clone();
+                let socket = socket_clone.clone();
+                spawn_local(async move {
+                    if let Ok(current) = fetch_in_progress_jobs().await
+                        && socket.ready_state() == web_sys::WebSocket::OPEN
+                    {
+                        display_in_progress_jobs(&current);
+                        *jobs.borrow_mut() = current;
+                    }
+                });
+            }
+            Ok(LiveEvent::LlmsTxt { .. }) => {}
+            Err(e) => console::error_1(&format!("Unexpected live update: {:?}", e).into()),
+        }
+    }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+    socket.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    onmessage.forget();
+
+    LIVE_SOCKET.with(|live| *live.borrow_mut() = Some(socket));
+}
+
+/// GET /api/ws on the server this page came from.
+fn live_updates_url() -> Result<String, JsValue> {
+    let location = web_sys::window().expect("no global window exists").location();
+    let scheme = if location.protocol()? == "https:" { "wss" } else { "ws" };
+    Ok(format!("{}://{}/api/ws", scheme, location.host()?))
+}
+
+// ============================================================================
+// Page 5: Browse All Jobs
+// ============================================================================
+
+fn create_list_jobs_page(document: &Document, container: &web_sys::Element) -> Result<(), JsValue> {
+    container.append_child(&create_back_button(document)?.into())?;
+
+    let heading = document.create_element("h1")?;
+    heading.set_text_content(Some("All Jobs"));
+    container.append_child(&heading)?;
+
+    let input_container = document.create_element("div")?;
+    input_container.set_class_name("input-group");
+
+    let status_select = document.create_element("select")?;
+    status_select.set_id("status-select");
+    for filter in JobStatusFilter::ALL {
+        let option = document.create_element("option")?;
+        option.set_attribute("value", filter.as_str())?;
+        option.set_text_content(Some(filter.as_str()));
+        status_select.append_child(&option)?;
+    }
+
+    let input = document.create_element("input")?;
+    input.set_attribute("type", "text")?;
+    input.set_attribute("placeholder", "Only the jobs of this URL (optional)...")?;
+    input.set_id("url-input");
+
+    let filter_btn = document.create_element("button")?;
+    filter_btn.set_text_content(Some("Filter"));
+
+    input_container.append_child(&status_select)?;
+    input_container.append_child(&input)?;
+    input_container.append_child(&filter_btn)?;
+    container.append_child(&input_container)?;
+
+    let pager_div = document.create_element("div")?;
+    pager_div.set_id("pager");
+    pager_div.set_class_name("pager");
+    container.append_child(&pager_div)?;
+
+    let results_div = document.create_element("div")?;
+    results_div.set_id("results");
+    results_div.set_class_name("results");
+    container.append_child(&results_div)?;
+
+    let closure = Closure::wrap(Box::new(move || {
+        load_jobs_page(1);
+    }) as Box<dyn Fn()>);
+    filter_btn
+        .dyn_ref::<HtmlElement>()
+        .expect("button should be an HtmlElement")
+        .set_onclick(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+
+    load_jobs_page(1);
+
+    Ok(())
+}
+
+/// Fetches a page of the jobs of the chosen status and URL, and shows it, under buttons to the previous and next
+/// pages.
+fn load_jobs_page(page: u32) {
+    let window = web_sys::window().expect("no global window exists");
+    let document = window.document().expect("should have a document on window");
+
+    let status = document
+        .get_element_by_id("status-select")
+        .expect("status select should exist")
+        .dyn_into::<HtmlSelectElement>()
+        .expect("should be select element")
+        .value();
+    let status = JobStatusFilter::ALL
+        .into_iter()
+        .find(|filter| filter.as_str() == status)
+        .unwrap_or_default();
+    let url = document
+        .get_element_by_id("url-input")
+        .expect("input should exist")
+        .dyn_into::<HtmlInputElement>()
+        .expect("should be input element")
+        .value()
+        .trim()
+        .to_string();
+    let jobs_page = JobsPage {
+        page,
+        status,
+        url: (!url.is_empty()).then_some(url),
+        ..JobsPage::default()
+    };
+
+    wasm_bindgen_futures::spawn_local(async move {
+        match fetch_jobs_page(jobs_page).await {
+            Ok(response) => {
+                display_pager(
+                    response.page,
+                    response.page_count(),
+                    &format!("{} jobs", response.total),
+                    load_jobs_page,
+                );
+                if !response.items.is_empty() {
+                    display_jobs_results(&response.items);
+                } else if response.total == 0 {
+                    display_text_result("No jobs.");
+                } else {
+                    display_text_result("No jobs on this page.");
+                }
+            }
+            Err(e) => {
+                console::error_1(&format!("Error: {:?}", e).into());
+                display_text_result(&format!("Error: {:?}", e));
+            }
+        }
+    });
+}
+
 // ============================================================================
-// Page 5: Inspect Job by UUID
+// Page 6: Inspect Job by UUID
 // ============================================================================
 
 fn create_inspect_job_page(document: &Document, container: &web_sys::Element) -> Result<(), JsValue> {
@@ -474,106 +726,65 @@ fn create_inspect_job_page(document: &Document, container: &web_sys::Element) ->
 // API Calls
 // ============================================================================
 
-async fn fetch_llm_txt(url: &str) -> Result<LlmTxtResponse, JsValue> {
-    let encoded_url = js_sys::encode_uri_component(url);
-    let endpoint = format!("/api/llm_txt?url={}", encoded_url);
-
-    api_request(&endpoint, "GET", None).await
+/// Client for the API server this page was served from.
+fn api_client() -> ApiClient {
+    ApiClient::new("")
 }
 
-async fn put_llm_txt(url: &str) -> Result<String, JsValue> {
-    let payload = UrlPayload { url: url.to_string() };
-    let payload_json = serde_json::to_string(&payload).unwrap();
-
-    let response: serde_json::Value = api_request("/api/llm_txt", "PUT", Some(&payload_json)).await?;
-    Ok(serde_json::to_string_pretty(&response).unwrap())
+/// The server's response body for error statuses (what users should read), the error itself otherwise.
+fn to_js_error(error: ClientError) -> JsValue {
+    match error {
+        ClientError::Api { message, .. } => JsValue::from_str(&message),
+        error => JsValue::from_str(&error.to_string()),
+    }
 }
 
-async fn fetch_list() -> Result<LlmsTxtListResponse, JsValue> {
-    api_request("/api/list", "GET", None).await
+async fn fetch_llm_txt(url: &str) -> Result<String, JsValue> {
+    api_client().get_llm_txt(url).await.map_err(to_js_error)
 }
 
-async fn fetch_in_progress_jobs() -> Result<Vec<JobState>, JsValue> {
-    api_request("/api/jobs/in_progress", "GET", None).await
+async fn fetch_related(url: &str) -> Result<RelatedLlmsTxtResponse, JsValue> {
+    api_client().related(url).await.map_err(to_js_error)
 }
 
-async fn fetch_job(job_id: &str) -> Result<JobState, JsValue> {
-    let endpoint = format!("/api/job?job_id={}", job_id);
-
-    api_request(&endpoint, "GET", None).await
+async fn put_llm_txt(url: &str) -> Result<String, JsValue> {
+    let response = api_client()
+        .put_llm_txt(&JobRequestPayload::new(url))
+        .await
+        .map_err(to_js_error)?;
+    Ok(serde_json::to_string_pretty(&response).unwrap())
 }
 
-async fn api_request<T: for<'de> Deserialize<'de>>(
-    endpoint: &str,
-    method: &str,
-    body: Option<&str>,
-) -> Result<T, JsValue> {
-    let window = web_sys::window().expect("no global window exists");
-
-    let opts = &mut RequestInit::new();
-    opts.set_method(method);
-    opts.set_mode(RequestMode::Cors);
-
-    if let Some(body_str) = body {
-        opts.set_body(&JsValue::from_str(body_str));
-    }
-
-    let request = Request::new_with_str_and_init(endpoint, opts)?;
-    request.headers().set("Content-Type", "application/json")?;
+async fn fetch_list_page(page: u32) -> Result<LlmsTxtListResponse, JsValue> {
+    let page = ListPage {
+        page,
+        ..ListPage::default()
+    };
+    api_client().list_page(page).await.map_err(to_js_error)
+}
 
-    let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
-    let resp: Response = resp_value.dyn_into()?;
+async fn fetch_in_progress_jobs() -> Result<Vec<JobSummary>, JsValue> {
+    api_client().in_progress_jobs().await.map_err(to_js_error)
+}
 
-    // Check if the response status is OK (200-299)
-    if !resp.ok() {
-        let text = JsFuture::from(resp.text()?).await?;
-        let error_text = text.as_string().unwrap_or_else(|| "Unknown error".to_string());
-        return Err(JsValue::from_str(&error_text));
-    }
+async fn fetch_jobs_page(page: JobsPage) -> Result<JobsListResponse, JsValue> {
+    api_client().jobs_page(page).await.map_err(to_js_error)
+}
 
-    let json = JsFuture::from(resp.json()?).await?;
-    let data: T = serde_wasm_bindgen::from_value(json)?;
+async fn fetch_job(job_id: &str) -> Result<JobDetailsResponse, JsValue> {
+    let job_id = Uuid::parse_str(job_id.trim()).map_err(|e| JsValue::from_str(&format!("Invalid job ID: {}", e)))?;
+    api_client().job(job_id).await.map_err(to_js_error)
+}
 
-    Ok(data)
+async fn cancel_job(job_id: Uuid) -> Result<(), JsValue> {
+    api_client().cancel_job(job_id).await.map_err(to_js_error)?;
+    Ok(())
 }
 
 // ============================================================================
 // Display Helpers
 // ============================================================================
 
-/// Renders markdown content to HTML with plain text fallback.
-///
-/// This function parses the input as markdown and converts it to HTML.
-/// The pulldown-cmark library is designed to be robust and handles any markdown input
-/// gracefully, so this function should not fail under normal circumstances.
-///
-/// As a safety measure, if the rendered output is empty when the input is not,
-/// the function falls back to displaying the content as plain text in a `<pre>` element.
-///
-/// # Arguments
-/// * `content` - The markdown content to render
-///
-/// # Returns
-/// HTML string with rendered content. Either markdown-rendered HTML or plain text fallback.
-fn render_markdown_with_fallback(content: &str) -> String {
-    // Parse and render markdown
-    let parser = Parser::new(content);
-    let mut html_output = String::new();
-    html::push_html(&mut html_output, parser);
-
-    // Safety check: if rendering produced empty output from non-empty input, use fallback
-    if html_output.trim().is_empty() && !content.trim().is_empty() {
-        console::log_1(&"Markdown rendering produced empty output, falling back to plain text".into());
-        return format!(
-            r#"<pre class="result-text fallback-text">{}</pre>"#,
-            html_escape(content)
-        );
-    }
-
-    // Return successfully rendered markdown
-    format!(r#"<div class="markdown-content">{}</div>"#, html_output)
-}
-
 /// Escapes HTML special characters to prevent XSS and rendering issues.
 fn html_escape(text: &str) -> String {
     text.replace('&', "&amp;")
@@ -617,6 +828,7 @@ fn create_view_toggle(document: &Document, id_suffix: &str) -> Result<web_sys::E
             markdown.set_attribute("style", "display: none;").unwrap();
             toggle.set_text_content(Some("Show markdown"));
         } else {
+            fill_markdown_view(&markdown, &plaintext);
             plaintext.set_attribute("style", "display: none;").unwrap();
             markdown.set_attribute("style", "display: block;").unwrap();
             toggle.set_text_content(Some("Show plaintext"));
@@ -633,11 +845,38 @@ fn create_view_toggle(document: &Document, id_suffix: &str) -> Result<web_sys::E
     Ok(toggle)
 }
 
+/// Renders the markdown view from the plaintext one, the first time it's shown: the markdown renderer is a module
+/// of its own, loaded then (see `markdown`). If it can't be loaded, the content is shown as plain text, and it's
+/// tried again the next time.
+fn fill_markdown_view(view: &web_sys::Element, plaintext: &web_sys::Element) {
+    if view.has_attribute("data-rendered") {
+        return;
+    }
+    view.set_attribute("data-rendered", "").unwrap();
+    view.set_text_content(Some("Loading markdown..."));
+
+    let view = view.clone();
+    let content = plaintext.text_content().unwrap_or_default();
+    spawn_local(async move {
+        match markdown::render_markdown(&content).await {
+            Ok(html) => view.set_inner_html(&html),
+            Err(e) => {
+                console::error_1(&format!("Failed to render markdown: {:?}", e).into());
+                view.remove_attribute("data-rendered").ok();
+                view.set_inner_html(&format!(
+                    r#"<pre class="result-text fallback-text">{}</pre>"#,
+                    html_escape(&content)
+                ));
+            }
+        }
+    });
+}
+
 /// Renders content with markdown and plaintext views (without the toggle button).
 ///
 /// Creates content divs for both markdown and plaintext:
 /// - Plaintext content (visible by default)
-/// - Markdown-rendered content (hidden by default)
+/// - Markdown content (hidden by default): empty until it's first shown, see `fill_markdown_view`
 ///
 /// # Arguments
 /// * `content` - The content to render
@@ -646,13 +885,12 @@ fn create_view_toggle(document: &Document, id_suffix: &str) -> Result<web_sys::E
 /// # Returns
 /// HTML string with both content views
 fn render_content_views(content: &str, id_suffix: &str) -> String {
-    let markdown_html = render_markdown_with_fallback(content);
     let plaintext_html = format!(r#"<pre class="plaintext-content">{}</pre>"#, html_escape(content));
 
     format!(
-        r#"<div id="markdown-{}" style="display: none;">{}</div>
+        r#"<div id="markdown-{}" style="display: none;"></div>
         <div id="plaintext-{}">{}</div>"#,
-        id_suffix, markdown_html, id_suffix, plaintext_html
+        id_suffix, id_suffix, plaintext_html
     )
 }
 
@@ -676,7 +914,55 @@ fn display_text_result(text: &str) {
     results_div.append_child(&content_container).unwrap();
 }
 
-fn display_list_results(data: &LlmsTxtListResponse) {
+/// Adds a "Related documentation" panel under the llms.txt in the results: nothing when there's nothing related.
+fn display_related(related: &RelatedLlmsTxtResponse) {
+    if related.related.is_empty() {
+        return;
+    }
+    let window = web_sys::window().expect("no global window exists");
+    let document = window.document().expect("should have a document on window");
+
+    let results_div = document.get_element_by_id("results").expect("results div should exist");
+
+    let panel = document.create_element("div").unwrap();
+    panel.set_class_name("related");
+    let heading = document.create_element("h2").unwrap();
+    heading.set_text_content(Some("Related documentation"));
+    panel.append_child(&heading).unwrap();
+
+    for site in &related.related {
+        let site_div = document.create_element("div").unwrap();
+        site_div.set_class_name("list-item");
+
+        let url_heading = document.create_element("h3").unwrap();
+        let url_link = document.create_element("a").unwrap();
+        if is_safe_url(&site.url) {
+            url_link.set_attribute("href", &site.url).unwrap();
+        }
+        url_link.set_attribute("target", "_blank").unwrap();
+        url_link.set_attribute("rel", "noopener noreferrer").unwrap();
+        url_link.set_text_content(Some(site.title.as_deref().unwrap_or(&site.url)));
+        url_heading.append_child(&url_link).unwrap();
+        site_div.append_child(&url_heading).unwrap();
+
+        if site.title.is_some() {
+            let url_p = document.create_element("p").unwrap();
+            url_p.set_class_name("list-item-url");
+            url_p.set_text_content(Some(&site.url));
+            site_div.append_child(&url_p).unwrap();
+        }
+        if let Some(ref description) = site.description {
+            let description_p = document.create_element("p").unwrap();
+            description_p.set_class_name("list-item-description");
+            description_p.set_text_content(Some(description));
+            site_div.append_child(&description_p).unwrap();
+        }
+        panel.append_child(&site_div).unwrap();
+    }
+    results_div.append_child(&panel).unwrap();
+}
+
+fn display_list_results(items: &[LlmsTxtListItem]) {
     let window = web_sys::window().expect("no global window exists");
     let document = window.document().expect("should have a document on window");
 
@@ -684,27 +970,47 @@ fn display_list_results(data: &LlmsTxtListResponse) {
 
     results_div.set_inner_html("");
 
-    for (index, item) in data.items.iter().enumerate() {
+    for (index, item) in items.iter().enumerate() {
         let item_div = document.create_element("div").unwrap();
         item_div.set_class_name("list-item");
 
+        // The website's name when the page has a title, with its URL underneath
         let url_heading = document.create_element("h3").unwrap();
+        if let Some(ref favicon_url) = item.favicon_url
+            && is_safe_url(favicon_url)
+        {
+            let favicon = document.create_element("img").unwrap();
+            favicon.set_class_name("favicon");
+            favicon.set_attribute("src", favicon_url).unwrap();
+            favicon.set_attribute("alt", "").unwrap();
+            favicon.set_attribute("width", "16").unwrap();
+            favicon.set_attribute("height", "16").unwrap();
+            url_heading.append_child(&favicon).unwrap();
+        }
         let url_link = document.create_element("a").unwrap();
-        url_link.set_attribute("href", &item.url).unwrap();
+        if is_safe_url(&item.url) {
+            url_link.set_attribute("href", &item.url).unwrap();
+        }
         url_link.set_attribute("target", "_blank").unwrap();
         url_link.set_attribute("rel", "noopener noreferrer").unwrap();
-        url_link.set_text_content(Some(&item.url));
+        url_link.set_text_content(Some(item.title.as_deref().unwrap_or(&item.url)));
         url_heading.append_child(&url_link).unwrap();
         item_div.append_child(&url_heading).unwrap();
 
-        let lines: Vec<&str> = item.llm_txt.lines().collect();
-        let total_lines = lines.len();
-        let preview_lines = 20;
-
-        if total_lines > preview_lines {
-            let preview_content: String = lines.iter().take(preview_lines).copied().collect::<Vec<_>>().join("\n");
-            let full_content = item.llm_txt.clone();
+        if item.title.is_some() {
+            let url_p = document.create_element("p").unwrap();
+            url_p.set_class_name("list-item-url");
+            url_p.set_text_content(Some(&item.url));
+            item_div.append_child(&url_p).unwrap();
+        }
+        if let Some(ref description) = item.description {
+            let description_p = document.create_element("p").unwrap();
+            description_p.set_class_name("list-item-description");
+            description_p.set_text_content(Some(description));
+            item_div.append_child(&description_p).unwrap();
+        }
 
+        if item.truncated {
             // Add toggle for preview
             let toggle_preview = create_view_toggle(&document, &format!("list-preview-{}", index)).unwrap();
             item_div.append_child(&toggle_preview).unwrap();
@@ -718,17 +1024,15 @@ fn display_list_results(data: &LlmsTxtListResponse) {
             let preview_div = document.create_element("div").unwrap();
             preview_div.set_class_name("llm-txt-content");
             preview_div.set_id(&format!("preview-{}", index));
-            let preview_html = render_content_views(&preview_content, &format!("list-preview-{}", index));
+            let preview_html = render_content_views(&item.preview, &format!("list-preview-{}", index));
             preview_div.set_inner_html(&preview_html);
             item_div.append_child(&preview_div).unwrap();
 
-            // Render full content (hidden by default)
+            // Full content (hidden by default), fetched when it's first expanded
             let full_div = document.create_element("div").unwrap();
             full_div.set_class_name("llm-txt-content");
             full_div.set_id(&format!("full-{}", index));
             full_div.set_attribute("style", "display: none;").unwrap();
-            let full_html = render_content_views(&full_content, &format!("list-full-{}", index));
-            full_div.set_inner_html(&full_html);
             item_div.append_child(&full_div).unwrap();
 
             let expand_link = document.create_element("div").unwrap();
@@ -747,52 +1051,28 @@ fn display_list_results(data: &LlmsTxtListResponse) {
             let expand_closure = {
                 let document = document.clone();
                 let idx = index;
+                let url = item.url.clone();
                 Closure::wrap(Box::new(move || {
-                    let preview = document.get_element_by_id(&format!("preview-{}", idx)).unwrap();
                     let full = document.get_element_by_id(&format!("full-{}", idx)).unwrap();
-                    let expand = document.get_element_by_id(&format!("expand-{}", idx)).unwrap();
-                    let collapse = document.get_element_by_id(&format!("collapse-{}", idx)).unwrap();
-                    let toggle_preview = document
-                        .get_element_by_id(&format!("toggle-list-preview-{}", idx))
-                        .unwrap();
-                    let toggle_full = document
-                        .get_element_by_id(&format!("toggle-list-full-{}", idx))
-                        .unwrap();
-
-                    // Check current view state (markdown or plaintext) from preview
-                    let preview_markdown = document
-                        .get_element_by_id(&format!("markdown-list-preview-{}", idx))
-                        .unwrap();
-                    let is_showing_plaintext = preview_markdown
-                        .get_attribute("style")
-                        .unwrap_or_default()
-                        .contains("none");
-
-                    // Apply same view state to full content
-                    let full_markdown = document
-                        .get_element_by_id(&format!("markdown-list-full-{}", idx))
-                        .unwrap();
-                    let full_plaintext = document
-                        .get_element_by_id(&format!("plaintext-list-full-{}", idx))
-                        .unwrap();
-
-                    if is_showing_plaintext {
-                        full_markdown.set_attribute("style", "display: none;").unwrap();
-                        full_plaintext.set_attribute("style", "display: block;").unwrap();
-                        toggle_full.set_text_content(Some("Show markdown"));
-                    } else {
-                        full_markdown.set_attribute("style", "display: block;").unwrap();
-                        full_plaintext.set_attribute("style", "display: none;").unwrap();
-                        toggle_full.set_text_content(Some("Show plaintext"));
+                    if full.has_attribute("data-loaded") {
+                        show_full_list_item(&document, idx);
+                        return;
                     }
-
-                    // Show full content and hide preview
-                    preview.set_attribute("style", "display: none;").unwrap();
-                    full.set_attribute("style", "display: block;").unwrap();
-                    expand.set_attribute("style", "display: none;").unwrap();
-                    collapse.set_attribute("style", "display: block;").unwrap();
-                    toggle_preview.set_attribute("style", "display: none;").unwrap();
-                    toggle_full.set_attribute("style", "display: inline-block;").unwrap();
+                    let document = document.clone();
+                    let url = url.clone();
+                    spawn_local(async move {
+                        match fetch_llm_txt(&url).await {
+                            Ok(content) => {
+                                full.set_inner_html(&render_content_views(&content, &format!("list-full-{}", idx)));
+                                full.set_attribute("data-loaded", "").unwrap();
+                                show_full_list_item(&document, idx);
+                            }
+                            Err(e) => {
+                                console::error_1(&format!("Error: {:?}", e).into());
+                                show_error_modal(&document, &format!("Failed to load the llms.txt: {:?}", e));
+                            }
+                        }
+                    });
                 }) as Box<dyn Fn()>)
             };
 
@@ -867,7 +1147,7 @@ fn display_list_results(data: &LlmsTxtListResponse) {
             // Render short content
             let content_div = document.create_element("div").unwrap();
             content_div.set_class_name("llm-txt-content");
-            let content_html = render_content_views(&item.llm_txt, &format!("list-short-{}", index));
+            let content_html = render_content_views(&item.preview, &format!("list-short-{}", index));
             content_div.set_inner_html(&content_html);
             item_div.append_child(&content_div).unwrap();
         }
@@ -876,7 +1156,57 @@ fn display_list_results(data: &LlmsTxtListResponse) {
     }
 }
 
-fn display_jobs_results(jobs: &[JobState]) {
+/// Shows the full llms.txt of the `idx`th list item, once it's loaded, in place of its preview, in the same view
+/// (markdown or plaintext).
+fn show_full_list_item(document: &Document, idx: usize) {
+    let preview = document.get_element_by_id(&format!("preview-{}", idx)).unwrap();
+    let full = document.get_element_by_id(&format!("full-{}", idx)).unwrap();
+    let expand = document.get_element_by_id(&format!("expand-{}", idx)).unwrap();
+    let collapse = document.get_element_by_id(&format!("collapse-{}", idx)).unwrap();
+    let toggle_preview = document
+        .get_element_by_id(&format!("toggle-list-preview-{}", idx))
+        .unwrap();
+    let toggle_full = document
+        .get_element_by_id(&format!("toggle-list-full-{}", idx))
+        .unwrap();
+
+    // Check current view state (markdown or plaintext) from preview
+    let preview_markdown = document
+        .get_element_by_id(&format!("markdown-list-preview-{}", idx))
+        .unwrap();
+    let is_showing_plaintext = preview_markdown
+        .get_attribute("style")
+        .unwrap_or_default()
+        .contains("none");
+
+    // Apply same view state to full content
+    let full_markdown = document
+        .get_element_by_id(&format!("markdown-list-full-{}", idx))
+        .unwrap();
+    let full_plaintext = document
+        .get_element_by_id(&format!("plaintext-list-full-{}", idx))
+        .unwrap();
+
+    if is_showing_plaintext {
+        full_markdown.set_attribute("style", "display: none;").unwrap();
+        full_plaintext.set_attribute("style", "display: block;").unwrap();
+        toggle_full.set_text_content(Some("Show markdown"));
+    } else {
+        full_markdown.set_attribute("style", "display: block;").unwrap();
+        full_plaintext.set_attribute("style", "display: none;").unwrap();
+        toggle_full.set_text_content(Some("Show plaintext"));
+    }
+
+    // Show full content and hide preview
+    preview.set_attribute("style", "display: none;").unwrap();
+    full.set_attribute("style", "display: block;").unwrap();
+    expand.set_attribute("style", "display: none;").unwrap();
+    collapse.set_attribute("style", "display: block;").unwrap();
+    toggle_preview.set_attribute("style", "display: none;").unwrap();
+    toggle_full.set_attribute("style", "display: inline-block;").unwrap();
+}
+
+fn display_jobs_results(jobs: &[JobSummary]) {
     let window = web_sys::window().expect("no global window exists");
     let document = window.document().expect("should have a document on window");
 
@@ -889,7 +1219,7 @@ fn display_jobs_results(jobs: &[JobState]) {
         job_div.set_class_name("job-item");
 
         let job_info = format!(
-            "Job ID: {}\nURL: {}\nStatus: {}\nKind: {}",
+            "Job ID: {}\nURL: {}\nStatus: {:?}\nKind: {:?}",
             job.job_id, job.url, job.status, job.kind
         );
 
@@ -897,11 +1227,17 @@ fn display_jobs_results(jobs: &[JobState]) {
         job_pre.set_text_content(Some(&job_info));
         job_div.append_child(&job_pre).unwrap();
 
+        if !job.status.is_completed() {
+            job_div
+                .append_child(&create_cancel_button(&document, job.job_id).unwrap())
+                .unwrap();
+        }
+
         results_div.append_child(&job_div).unwrap();
     }
 }
 
-fn display_job_details(job: &JobState) {
+fn display_job_details(job: &JobDetailsResponse) {
     let window = web_sys::window().expect("no global window exists");
     let document = window.document().expect("should have a document on window");
 
@@ -913,7 +1249,7 @@ fn display_job_details(job: &JobState) {
     job_div.set_class_name("job-details");
 
     let job_info = format!(
-        "Job ID: {}\nURL: {}\nStatus: {}\nKind: {}",
+        "Job ID: {}\nURL: {}\nStatus: {:?}\nKind: {:?}",
         job.job_id, job.url, job.status, job.kind
     );
 
@@ -922,18 +1258,31 @@ fn display_job_details(job: &JobState) {
     job_pre.set_text_content(Some(&job_info));
     job_div.append_child(&job_pre).unwrap();
 
-    // Display error message if the job failed
-    if job.status == "Failure"
-        && let Some(ref error_msg) = job.error_message
-    {
-        let error_heading = document.create_element("h3").unwrap();
-        error_heading.set_text_content(Some("Error Details:"));
-        job_div.append_child(&error_heading).unwrap();
-
-        let error_pre = document.create_element("pre").unwrap();
-        error_pre.set_class_name("error-message");
-        error_pre.set_text_content(Some(error_msg));
-        job_div.append_child(&error_pre).unwrap();
+    if !job.status.is_completed() {
+        job_div
+            .append_child(&create_cancel_button(&document, job.job_id).unwrap())
+            .unwrap();
+    }
+
+    // Display why the job failed, followed by the raw error for the details
+    if job.status == JobStatus::Failure {
+        if let Some(ref failure_msg) = job.failure_message {
+            let failure_p = document.create_element("p").unwrap();
+            failure_p.set_class_name("failure-message");
+            failure_p.set_text_content(Some(failure_msg));
+            job_div.append_child(&failure_p).unwrap();
+        }
+
+        if let Some(ref error_msg) = job.error_message {
+            let error_heading = document.create_element("h3").unwrap();
+            error_heading.set_text_content(Some("Error Details:"));
+            job_div.append_child(&error_heading).unwrap();
+
+            let error_pre = document.create_element("pre").unwrap();
+            error_pre.set_class_name("error-message");
+            error_pre.set_text_content(Some(error_msg));
+            job_div.append_child(&error_pre).unwrap();
+        }
     }
 
     // Render LLMs.txt content with toggle between markdown and plaintext
@@ -957,6 +1306,39 @@ fn display_job_details(job: &JobState) {
     results_div.append_child(&job_div).unwrap();
 }
 
+/// Button cancelling the job, then showing its details (e.g. that it's now cancelled).
+fn create_cancel_button(document: &Document, job_id: Uuid) -> Result<web_sys::Element, JsValue> {
+    let button = document.create_element("button")?;
+    button.set_class_name("cancel-job-btn");
+    button.set_text_content(Some("Cancel"));
+
+    let closure = Closure::wrap(Box::new(move || {
+        wasm_bindgen_futures::spawn_local(async move {
+            let cancelled = async {
+                cancel_job(job_id).await?;
+                fetch_job(&job_id.to_string()).await
+            };
+            match cancelled.await {
+                Ok(job) => display_job_details(&job),
+                Err(e) => {
+                    console::error_1(&format!("Error: {:?}", e).into());
+                    let window = web_sys::window().expect("no global window exists");
+                    let document = window.document().expect("should have a document on window");
+                    show_error_modal(&document, &e.as_string().unwrap_or_else(|| format!("{:?}", e)));
+                }
+            }
+        });
+    }) as Box<dyn Fn()>);
+
+    button
+        .dyn_ref::<HtmlElement>()
+        .expect("button should be an HtmlElement")
+        .set_onclick(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+
+    Ok(button)
+}
+
 fn show_error_modal(document: &Document, message: &str) {
     let body = document.body().expect("document should have a body");
 
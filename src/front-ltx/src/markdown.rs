@@ -0,0 +1,36 @@
+//! Markdown views, rendered by the `front-markdown-ltx` module: it's only downloaded, and instantiated, the first
+//! time one is shown.
+
+use wasm_bindgen::JsCast;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::JsFuture;
+
+// The import is kept: every view after the first renders with the same module. A failed import is tried again.
+#[wasm_bindgen(inline_js = r#"
+let markdown = null;
+export function load_markdown_module() {
+    if (markdown === null) {
+        markdown = import('/pkg/markdown/front_markdown_ltx.js').then(async (module) => {
+            await module.default();
+            return module;
+        });
+        markdown.catch(() => { markdown = null; });
+    }
+    return markdown;
+}
+"#)]
+extern "C" {
+    fn load_markdown_module() -> js_sys::Promise;
+}
+
+/// Renders the llms.txt's markdown as safe HTML (see `front_markdown_ltx::render_markdown`), loading the renderer
+/// first if it isn't yet.
+pub async fn render_markdown(content: &str) -> Result<String, JsValue> {
+    let module = JsFuture::from(load_markdown_module()).await?;
+    let render =
+        js_sys::Reflect::get(&module, &JsValue::from_str("render_markdown"))?.dyn_into::<js_sys::Function>()?;
+    render
+        .call1(&JsValue::NULL, &JsValue::from_str(content))?
+        .as_string()
+        .ok_or_else(|| JsValue::from_str("render_markdown didn't return a string"))
+}
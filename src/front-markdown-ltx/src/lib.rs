@@ -0,0 +1,39 @@
+//! The markdown renderer of the webapp frontend.
+//!
+//! pulldown-cmark is most of the frontend's size, and only views that show an llms.txt as markdown need it, so it's
+//! built as a WASM module of its own (`www/pkg/markdown/`): `front-ltx` imports it the first time a markdown view is
+//! shown, instead of every visitor downloading it on first load.
+
+pub mod sanitize;
+
+pub use sanitize::{is_safe_url, markdown_to_safe_html};
+
+#[cfg(feature = "module")]
+use wasm_bindgen::prelude::*;
+
+/// Renders the markdown of an llms.txt as HTML that's safe to insert into the page (see `sanitize`), in a
+/// `markdown-content` div.
+///
+/// pulldown-cmark renders any input, but as a safety measure, if the rendered output is empty when the input is not,
+/// the content is shown as plain text in a `<pre>` element instead.
+#[cfg_attr(feature = "module", wasm_bindgen)]
+pub fn render_markdown(content: &str) -> String {
+    let html_output = markdown_to_safe_html(content);
+
+    if html_output.trim().is_empty() && !content.trim().is_empty() {
+        return format!(
+            r#"<pre class="result-text fallback-text">{}</pre>"#,
+            html_escape(content)
+        );
+    }
+    format!(r#"<div class="markdown-content">{}</div>"#, html_output)
+}
+
+/// Escapes HTML special characters to prevent XSS and rendering issues.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
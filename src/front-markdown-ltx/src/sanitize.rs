@@ -0,0 +1,108 @@
+//! Allow-list rendering of untrusted markdown.
+//!
+//! llms.txt files are written by an LLM from the HTML of arbitrary websites, so a page can smuggle
+//! `<script>`, `<img onerror=...>` or `javascript:` links into them. Everything that ends up in
+//! `set_inner_html` goes through here first.
+
+use pulldown_cmark::{CowStr, Event, Parser, Tag, html};
+
+/// URL schemes that links and images may use. Relative URLs are allowed too.
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Renders markdown to HTML that's safe to insert into the page.
+///
+/// Raw HTML in the markdown is shown as text instead of being interpreted, and links or images
+/// whose URL has a scheme other than `http`, `https` or `mailto` point nowhere (`#`).
+/// Everything else is generated by pulldown-cmark, which escapes text and attribute values.
+pub fn markdown_to_safe_html(content: &str) -> String {
+    let events = Parser::new(content).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: safe_dest(dest_url),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: safe_dest(dest_url),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events);
+    html_output
+}
+
+fn safe_dest(dest_url: CowStr<'_>) -> CowStr<'_> {
+    if is_safe_url(&dest_url) {
+        dest_url
+    } else {
+        CowStr::Borrowed("#")
+    }
+}
+
+/// True if the URL is relative or uses an allowed scheme.
+///
+/// Browsers ignore tabs and newlines in URLs and leading whitespace or control characters,
+/// so `java\tscript:` is a `javascript:` URL: those are removed before looking at the scheme.
+pub fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_control() && *c != ' ')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => ALLOWED_SCHEMES.contains(&&url[..i]),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_html_is_escaped() {
+        let html = markdown_to_safe_html("# Title\n\n<script>alert(1)</script>\n\nText <img src=x onerror=alert(1)>");
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("<img"));
+        assert!(html.contains("&lt;script&gt;"));
+    }
+
+    #[test]
+    fn test_unsafe_links_are_neutralized() {
+        let html = markdown_to_safe_html(
+            "- [ok](https://example.com/docs)\n- [bad](javascript:alert(1))\n- ![img](data:image/svg+xml,x)\n- <jav\tascript:alert(1)>",
+        );
+        assert!(html.contains(r#"<a href="https://example.com/docs">ok</a>"#));
+        assert!(!html.to_ascii_lowercase().contains("javascript:"));
+        assert!(!html.contains("data:"));
+    }
+
+    #[test]
+    fn test_is_safe_url() {
+        assert!(is_safe_url("https://example.com"));
+        assert!(is_safe_url("mailto:docs@example.com"));
+        assert!(is_safe_url("/docs/intro.md"));
+        assert!(is_safe_url("#section"));
+        assert!(is_safe_url("guide?x=a:b"));
+        assert!(!is_safe_url("JavaScript:alert(1)"));
+        assert!(!is_safe_url(" java\tscript:alert(1)"));
+        assert!(!is_safe_url("vbscript:msgbox"));
+        assert!(!is_safe_url("data:text/html,<script>"));
+    }
+}
@@ -0,0 +1,155 @@
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use core_ltx::notify::Notifications;
+use core_ltx::{
+    PARSE_LIMITS,
+    common::env_check::check_non_empty_env_vars,
+    get_api_base_url, get_auth_config, get_db_pool, get_tls_config, init_http_client, is_auth_enabled,
+    llms::{ChatGpt, LlmProvider},
+    reload_on_sighup, setup_telemetry,
+};
+use cron_ltx::{api_client, build_reqwest_client, cron_poll_interval, updater_loop};
+use data_model_ltx::migrations::run_migrations_if_enabled;
+use tracing::info;
+use worker_ltx::{Publishers, WarcArchive, WorkerSettings, worker_polling_loop};
+
+use api_ltx::routes;
+
+#[tokio::main]
+async fn main() {
+    // Install the default crypto provider for rustls (required for TLS)
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+
+    // Load environment variables from .env file., if it exists
+    dotenvy::dotenv().ok();
+
+    // Fail-fast check: verify required auth env vars are present if auth is enabled
+    if is_auth_enabled() {
+        check_non_empty_env_vars(&["AUTH_PASSWORD_HASH", "SESSION_SECRET"]);
+    }
+
+    let _telemetry = setup_telemetry(
+        "serve-ltx",
+        "serve_ltx=debug,api_ltx=debug,worker_ltx=debug,cron_ltx=debug,tower_http=debug",
+    );
+
+    let auth_config = get_auth_config();
+    if auth_config.is_some() {
+        info!("Authentication: ENABLED");
+    } else {
+        info!("Authentication: DISABLED");
+    }
+
+    // Load TLS configuration (REQUIRED)
+    let tls_config = get_tls_config().await;
+    info!("TLS: ENABLED");
+
+    run_migrations_if_enabled().await;
+
+    // Read now, so invalid settings fail at startup rather than in the worker's and cron's downloads
+    info!("HTML and markdown parse limits: {:?}", *PARSE_LIMITS);
+    let http_client_settings = init_http_client().unwrap_or_else(|e| panic!("{}", e));
+    info!("Download HTTP client: {:?}", http_client_settings);
+
+    // One connection pool shared by the API, worker, and cron updater.
+    let pool = get_db_pool().await;
+    let notifications = Arc::new(Notifications::from_env());
+
+    // Worker: generates & updates llms.txt for queued jobs.
+    // Its settings, and the cron poll interval, are re-read on SIGHUP.
+    {
+        let settings = reload_on_sighup("worker", WorkerSettings::from_env).unwrap_or_else(|e| panic!("{}", e));
+        info!(
+            "Worker configured with max concurrency: {}",
+            settings.borrow().max_concurrency
+        );
+        if let Some(budget) = settings.borrow().daily_token_budget {
+            info!("Daily token budget: {} tokens", budget);
+        }
+        let publishers = Arc::new(Publishers::from_env().unwrap_or_else(|e| panic!("{}", e)));
+        let archive = WarcArchive::from_env()
+            .unwrap_or_else(|e| panic!("{}", e))
+            .map(Arc::new);
+        if let Some(archive) = archive.as_ref() {
+            info!(
+                "Archiving downloaded pages as WARC files to S3 bucket '{}'",
+                archive.bucket()
+            );
+        }
+        let pool = pool.clone();
+        let notifications = notifications.clone();
+        tokio::spawn(async move {
+            // the API keeps serving, but jobs aren't claimed when the LLM provider can't be used
+            let model_name = settings.borrow().model_name.clone();
+            if let Err(e) = ChatGpt::new(&model_name).check_credentials().await {
+                tracing::error!(
+                    "LLM provider check failed for model {}: {}. Not starting the worker.",
+                    model_name,
+                    e
+                );
+                return;
+            }
+            info!("Starting worker polling loop");
+            worker_polling_loop(
+                pool,
+                |settings: &WorkerSettings| ChatGpt::new(&settings.model_name),
+                settings,
+                notifications,
+                publishers,
+                archive,
+                // the only worker: it covers every shard
+                None,
+            )
+            .await;
+        });
+    }
+
+    // Cron: periodically submits update jobs to the API.
+    {
+        let poll_interval = reload_on_sighup("cron", cron_poll_interval).unwrap_or_else(|e| panic!("{}", e));
+        info!("Using a {:?} interval for updating.", *poll_interval.borrow());
+
+        let password = auth_config.as_ref().and_then(|cfg| cfg.password.clone());
+        let accept_invalid_certs = env::var("ACCEPT_INVALID_CERTS")
+            .map(|v| {
+                let v = v.to_lowercase();
+                v == "true" || v == "1"
+            })
+            .unwrap_or(false);
+        let reqwest_client = build_reqwest_client(accept_invalid_certs).expect("Failed to build HTTP client");
+        let api_base_url = format!("https://{}", get_api_base_url());
+        let http_client = Arc::new(api_client(reqwest_client, &api_base_url, password));
+
+        let pool = pool.clone();
+        tokio::spawn(async move {
+            // The API server below isn't accepting connections yet: wait one interval before the first cycle.
+            let first_interval = *poll_interval.borrow();
+            tokio::time::sleep(first_interval).await;
+            if http_client.login().await.is_err() {
+                tracing::error!("Auth enabled but initial authentication failed!");
+            }
+            updater_loop(pool, http_client, api_base_url, poll_interval, notifications).await;
+        });
+    }
+
+    let thresholds = *routes::job_state::QUEUE_THRESHOLDS;
+    info!(
+        "Queue backpressure: from {} queued jobs or an estimated {:?} wait",
+        thresholds.depth, thresholds.wait
+    );
+    let app = routes::router(auth_config, pool);
+
+    let addr = get_api_base_url()
+        .parse::<SocketAddr>()
+        .expect("Expected a socket address!");
+
+    info!("Starting HTTPS server on https://{}", addr);
+
+    axum_server::bind_rustls(addr, tls_config)
+        // the client's address is used to throttle failed logins
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
+}
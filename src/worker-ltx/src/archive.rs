@@ -0,0 +1,226 @@
+//! Archiving of downloaded pages as WARC files, so that every llms.txt can be traced back to the page it was made
+//! from, and historical captures can be processed again (e.g. with a better prompt).
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use core_ltx::RawResponse;
+use url::Url;
+use uuid::Uuid;
+
+use crate::errors::Error;
+use crate::publish::{S3EnvVars, S3Publisher};
+
+/// Bucket to archive downloaded pages to. Archiving is enabled when this is set.
+pub const ARCHIVE_S3_BUCKET_ENV_VAR: &str = "ARCHIVE_S3_BUCKET";
+/// S3 API endpoint of the archive bucket, for S3-compatible stores. Defaults to AWS S3 in the region.
+pub const ARCHIVE_S3_ENDPOINT_ENV_VAR: &str = "ARCHIVE_S3_ENDPOINT";
+/// Region of the archive bucket. Defaults to `us-east-1`.
+pub const ARCHIVE_S3_REGION_ENV_VAR: &str = "ARCHIVE_S3_REGION";
+/// Key prefix that WARC files are stored under.
+pub const ARCHIVE_S3_PREFIX_ENV_VAR: &str = "ARCHIVE_S3_PREFIX";
+
+const WARC_CONTENT_TYPE: &str = "application/warc";
+
+/// Headers describing how the body was encoded on the wire. The archived body is decoded, so these are kept
+/// under an `X-Archive-Orig-` name, where replay tools don't act on them.
+const WIRE_ENCODING_HEADERS: &[&str] = &["transfer-encoding", "content-encoding"];
+
+/// Stores the response each job downloaded as `<prefix>/<host>/<job_id>.warc` in a bucket.
+pub struct WarcArchive {
+    bucket: S3Publisher,
+}
+
+impl WarcArchive {
+    /// None when `ARCHIVE_S3_BUCKET` isn't set. An error when it is, but the rest of the configuration is invalid.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let bucket = S3Publisher::bucket_from_env(&S3EnvVars {
+            bucket: ARCHIVE_S3_BUCKET_ENV_VAR,
+            endpoint: ARCHIVE_S3_ENDPOINT_ENV_VAR,
+            region: ARCHIVE_S3_REGION_ENV_VAR,
+            prefix: ARCHIVE_S3_PREFIX_ENV_VAR,
+        })?;
+        Ok(bucket.map(|bucket| Self { bucket }))
+    }
+
+    pub fn bucket(&self) -> &str {
+        self.bucket.bucket()
+    }
+
+    /// Key of the WARC file of a job's download.
+    pub fn key(&self, job_id: Uuid, url: &Url) -> String {
+        self.bucket.prefixed(&archive_key(job_id, url))
+    }
+
+    /// Uploads the response as a WARC file. Returns its key.
+    pub async fn store(&self, job_id: Uuid, response: &RawResponse) -> Result<String, Error> {
+        let key = self.key(job_id, &response.url);
+        let warc = to_warc(job_id, response);
+        self.bucket
+            .put_object(&key, &warc, WARC_CONTENT_TYPE)
+            .await
+            .map_err(Error::ArchiveError)?;
+        Ok(key)
+    }
+}
+
+/// `https://example.com:8443/docs/` downloaded by job `<job_id>` is archived at `example.com:8443/<job_id>.warc`.
+pub fn archive_key(job_id: Uuid, url: &Url) -> String {
+    let host = url.host_str().unwrap_or("unknown-host");
+    match url.port() {
+        Some(port) => format!("{}:{}/{}.warc", host, port, job_id),
+        None => format!("{}/{}.warc", host, job_id),
+    }
+}
+
+/// A WARC 1.1 file with a `warcinfo` record naming the job, then the `response` record.
+pub fn to_warc(job_id: Uuid, response: &RawResponse) -> Vec<u8> {
+    let date = DateTime::<Utc>::from(response.fetched_at).to_rfc3339_opts(SecondsFormat::Secs, true);
+    let warcinfo_id = format!("<urn:uuid:{}>", Uuid::new_v4());
+
+    let info = format!(
+        "software: worker-ltx/{}\r\nformat: WARC File Format 1.1\r\njob-id: {}\r\n",
+        env!("CARGO_PKG_VERSION"),
+        job_id
+    );
+    let mut warc = record(
+        &[
+            ("WARC-Type", "warcinfo"),
+            ("WARC-Record-ID", &warcinfo_id),
+            ("WARC-Date", &date),
+            ("WARC-Filename", &format!("{}.warc", job_id)),
+            ("Content-Type", "application/warc-fields"),
+        ],
+        info.as_bytes(),
+    );
+    warc.extend(record(
+        &[
+            ("WARC-Type", "response"),
+            ("WARC-Record-ID", &format!("<urn:uuid:{}>", Uuid::new_v4())),
+            ("WARC-Date", &date),
+            ("WARC-Target-URI", response.url.as_str()),
+            ("WARC-Warcinfo-ID", &warcinfo_id),
+            ("Content-Type", "application/http;msgtype=response"),
+        ],
+        &http_response(response),
+    ));
+    warc
+}
+
+fn record(headers: &[(&str, &str)], block: &[u8]) -> Vec<u8> {
+    let mut record = b"WARC/1.1\r\n".to_vec();
+    for (name, value) in headers {
+        record.extend(format!("{}: {}\r\n", name, value).as_bytes());
+    }
+    record.extend(format!("Content-Length: {}\r\n\r\n", block.len()).as_bytes());
+    record.extend(block);
+    record.extend(b"\r\n\r\n");
+    record
+}
+
+/// The response as it's sent on the wire: status line, headers, and body.
+fn http_response(response: &RawResponse) -> Vec<u8> {
+    let reason = reqwest::StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or_default();
+    let mut http = format!("{} {} {}\r\n", response.version, response.status, reason).into_bytes();
+
+    let was_decoded = response
+        .headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("content-encoding"));
+    for (name, value) in response.headers.iter() {
+        let is_wire_header = WIRE_ENCODING_HEADERS.iter().any(|h| name.eq_ignore_ascii_case(h))
+            // a decoded body doesn't have its original length
+            || (was_decoded && name.eq_ignore_ascii_case("content-length"));
+        if is_wire_header {
+            http.extend(format!("X-Archive-Orig-{}: {}\r\n", name, value).as_bytes());
+        } else {
+            http.extend(format!("{}: {}\r\n", name, value).as_bytes());
+        }
+    }
+    http.extend(b"\r\n");
+    http.extend(&response.body);
+    http
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn response(headers: &[(&str, &str)], body: &str) -> RawResponse {
+        RawResponse {
+            url: Url::parse("https://example.com/docs/").unwrap(),
+            fetched_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_767_225_600),
+            version: "HTTP/1.1".to_string(),
+            status: 200,
+            headers: headers
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_archive_key() {
+        let job_id = Uuid::nil();
+        let key = |url: &str| archive_key(job_id, &Url::parse(url).unwrap());
+        assert_eq!(
+            key("https://example.com/docs/"),
+            "example.com/00000000-0000-0000-0000-000000000000.warc"
+        );
+        assert_eq!(
+            key("http://localhost:8080/a"),
+            "localhost:8080/00000000-0000-0000-0000-000000000000.warc"
+        );
+    }
+
+    #[test]
+    fn test_to_warc() {
+        let body = "<html><title>Docs</title></html>";
+        let warc = to_warc(
+            Uuid::nil(),
+            &response(&[("content-type", "text/html"), ("content-length", "33")], body),
+        );
+        let warc = String::from_utf8(warc).unwrap();
+
+        let records: Vec<&str> = warc.split("WARC/1.1\r\n").skip(1).collect();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].contains("WARC-Type: warcinfo\r\n"));
+        assert!(records[0].contains("job-id: 00000000-0000-0000-0000-000000000000\r\n"));
+
+        let response_record = records[1];
+        assert!(response_record.contains("WARC-Type: response\r\n"));
+        assert!(response_record.contains("WARC-Target-URI: https://example.com/docs/\r\n"));
+        assert!(response_record.contains("WARC-Date: 2026-01-01T00:00:00Z\r\n"));
+        let (headers, block) = response_record.split_once("\r\n\r\n").unwrap();
+        let block = block.strip_suffix("\r\n\r\n").unwrap();
+        assert!(headers.contains(&format!("Content-Length: {}", block.len())));
+        assert_eq!(
+            block,
+            format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: 33\r\n\r\n{}",
+                body
+            )
+        );
+    }
+
+    #[test]
+    fn test_decoded_body_keeps_wire_headers_aside() {
+        let http = http_response(&response(
+            &[
+                ("content-encoding", "gzip"),
+                ("content-length", "10"),
+                ("transfer-encoding", "chunked"),
+            ],
+            "decoded",
+        ));
+        let http = String::from_utf8(http).unwrap();
+        assert!(http.contains("X-Archive-Orig-content-encoding: gzip\r\n"));
+        assert!(http.contains("X-Archive-Orig-content-length: 10\r\n"));
+        assert!(http.contains("X-Archive-Orig-transfer-encoding: chunked\r\n"));
+        assert!(http.ends_with("\r\n\r\ndecoded"));
+    }
+}
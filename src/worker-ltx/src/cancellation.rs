@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use core_ltx::db;
+use data_model_ltx::{models::JobStatus, schema};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+use crate::errors::Error;
+use crate::settings::WorkerSettings;
+
+/// The jobs this worker is running, so they can be told when they're cancelled through the API.
+///
+/// Cancelling only changes a job's status in the database: `poll_cancelled_jobs` looks up which running jobs were
+/// cancelled (see `signal_cancelled_jobs`), and their tasks abandon them.
+#[derive(Debug, Clone, Default)]
+pub struct RunningJobs {
+    jobs: Arc<Mutex<HashMap<Uuid, watch::Sender<bool>>>>,
+}
+
+impl RunningJobs {
+    /// Tracks the job until `finish`. The returned `Cancellation` resolves once the job is cancelled.
+    pub fn start(&self, job_id: Uuid) -> Cancellation {
+        let (sender, receiver) = watch::channel(false);
+        self.jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(job_id, sender);
+        Cancellation { receiver }
+    }
+
+    /// Stops tracking the job, once it's done (or abandoned).
+    pub fn finish(&self, job_id: Uuid) {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner()).remove(&job_id);
+    }
+
+    /// The jobs being run.
+    pub fn ids(&self) -> Vec<Uuid> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .copied()
+            .collect()
+    }
+
+    /// Tells the job's task that it's cancelled. False if the job isn't running here.
+    pub fn cancel(&self, job_id: Uuid) -> bool {
+        match self.jobs.lock().unwrap_or_else(|e| e.into_inner()).get(&job_id) {
+            Some(sender) => {
+                sender.send_replace(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Resolves once a running job is cancelled.
+#[derive(Debug)]
+pub struct Cancellation {
+    receiver: watch::Receiver<bool>,
+}
+
+impl Cancellation {
+    /// Waits until the job is cancelled. Never resolves if it isn't.
+    pub async fn cancelled(&mut self) {
+        if self.receiver.wait_for(|cancelled| *cancelled).await.is_err() {
+            // no longer tracked: it can't be cancelled anymore
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Whether the job was cancelled already.
+    pub fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow()
+    }
+}
+
+/// Tells the tasks of the `running` jobs that were cancelled since they were claimed to abandon them.
+/// Returns the number of jobs that were told.
+pub async fn signal_cancelled_jobs(pool: &db::DbPool, running: &RunningJobs) -> Result<usize, Error> {
+    let ids = running.ids();
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let mut conn = pool.get().await?;
+    let cancelled = schema::job_state::table
+        .filter(schema::job_state::job_id.eq_any(&ids))
+        .filter(schema::job_state::status.eq(JobStatus::Cancelled))
+        .select(schema::job_state::job_id)
+        .load::<Uuid>(&mut conn)
+        .await?;
+    Ok(cancelled.into_iter().filter(|job_id| running.cancel(*job_id)).count())
+}
+
+/// Signals the cancelled jobs every poll interval of the `settings`, until the worker stops.
+///
+/// It runs apart from claiming jobs: that waits for a free slot, i.e. for one of the very jobs that may be cancelled.
+pub async fn poll_cancelled_jobs(
+    pool: db::DbPool,
+    running: RunningJobs,
+    mut settings: watch::Receiver<WorkerSettings>,
+) {
+    let mut poll_interval = settings.borrow_and_update().poll_interval;
+    let mut ticker = tokio::time::interval(poll_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if settings.has_changed().unwrap_or(false) {
+            let updated = settings.borrow_and_update().poll_interval;
+            if updated != poll_interval {
+                poll_interval = updated;
+                ticker = tokio::time::interval_at(tokio::time::Instant::now() + poll_interval, poll_interval);
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            }
+        }
+        match signal_cancelled_jobs(&pool, &running).await {
+            Ok(0) => {}
+            Ok(cancelled) => tracing::info!("{} running job(s) were cancelled: abandoning them", cancelled),
+            Err(error) => tracing::error!("[SKIP] Failed to check for cancelled jobs: {}", error),
+        }
+    }
+}
+
+/// Whether the job was cancelled, e.g. between the last poll and finishing its work.
+pub async fn is_job_cancelled(pool: &db::DbPool, job_id: Uuid) -> Result<bool, Error> {
+    let mut conn = pool.get().await?;
+    let status = schema::job_state::table
+        .find(job_id)
+        .select(schema::job_state::status)
+        .first::<JobStatus>(&mut conn)
+        .await?;
+    Ok(status == JobStatus::Cancelled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_running_jobs_cancel() {
+        let running = RunningJobs::default();
+        let (job, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut cancellation = running.start(job);
+        let other_cancellation = running.start(other);
+        assert_eq!(running.ids().len(), 2);
+        assert!(!cancellation.is_cancelled());
+
+        assert!(running.cancel(job));
+        cancellation.cancelled().await;
+        assert!(cancellation.is_cancelled());
+        assert!(!other_cancellation.is_cancelled());
+
+        running.finish(job);
+        running.finish(other);
+        assert!(running.ids().is_empty());
+        assert!(!running.cancel(job));
+    }
+
+    #[tokio::test]
+    async fn test_finished_job_is_never_cancelled() {
+        let running = RunningJobs::default();
+        let job = Uuid::new_v4();
+        let mut cancellation = running.start(job);
+        running.finish(job);
+        let waited = tokio::time::timeout(std::time::Duration::from_millis(20), cancellation.cancelled()).await;
+        assert!(waited.is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Embeds the websites' latest llms.txt files for semantic search (see `data_model_ltx::embeddings`), in the
+//! background.
+//!
+//! Every latest llms.txt without an embedding of the model is embedded: a new generation's soon after it's stored,
+//! and the imported or restored ones, and all of them again when the model changes.
+
+use std::time::Duration;
+
+use core_ltx::db;
+use core_ltx::llms::Embedder;
+use data_model_ltx::embeddings::{store_embedding, unembedded_llms_txt};
+
+use crate::errors::Error;
+
+/// How long the worker waits before looking again, when everything is embedded.
+const EMBEDDING_INTERVAL: Duration = Duration::from_secs(10);
+/// llms.txt files embedded per request to the embedding model.
+const BATCH_SIZE: i64 = 32;
+
+/// Embeds a batch of the latest llms.txt files that have no embedding of the embedder's model.
+/// Returns the number of files embedded: 0 when they all have one.
+pub async fn embed_pending_llms_txt(pool: &db::DbPool, embedder: &Embedder) -> Result<usize, Error> {
+    let mut conn = pool.get().await?;
+    let pending = unembedded_llms_txt(&mut conn, &embedder.model_name, BATCH_SIZE).await?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    // the connection isn't held while the embedding model works
+    drop(conn);
+
+    let texts: Vec<_> = pending.iter().map(|record| record.result_data.text()).collect();
+    let texts: Vec<&str> = texts.iter().map(|text| text.as_ref()).collect();
+    let embeddings = embedder.embed(&texts).await?;
+
+    let mut conn = pool.get().await?;
+    for (record, embedding) in pending.iter().zip(embeddings.iter()) {
+        store_embedding(&mut conn, record.job_id, &embedder.model_name, embedding).await?;
+    }
+    Ok(pending.len())
+}
+
+/// Embeds the latest llms.txt files as they're generated, until the worker stops.
+/// Failures are logged: they're tried again after a while.
+pub async fn embedding_loop(pool: db::DbPool, embedder: Embedder) {
+    loop {
+        match embed_pending_llms_txt(&pool, &embedder).await {
+            Ok(0) => tokio::time::sleep(EMBEDDING_INTERVAL).await,
+            Ok(embedded) => tracing::debug!("Embedded {} llms.txt file(s)", embedded),
+            Err(error) => {
+                tracing::error!("[SKIP] Failed to embed llms.txt files: {}", error);
+                tokio::time::sleep(EMBEDDING_INTERVAL).await;
+            }
+        }
+    }
+}
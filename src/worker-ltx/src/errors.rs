@@ -1,3 +1,4 @@
+use core_ltx::FailureKind;
 use tokio::sync::AcquireError;
 
 #[derive(Debug)]
@@ -7,6 +8,13 @@ pub enum Error {
     DbPoolError(String),
     CoreError(core_ltx::Error),
     SemaphorePermitError(AcquireError),
+    PublishError(String),
+    /// The downloaded page couldn't be archived.
+    ArchiveError(String),
+    /// The page's robots directives (`noindex`, `noai`, ...) disallow generating an llms.txt for it.
+    DisallowedByPublisher(core_ltx::Disallowed),
+    /// The generated result can't be stored, e.g. because it contains null bytes.
+    InvalidResultData(String),
 }
 
 impl std::fmt::Display for Error {
@@ -19,6 +27,29 @@ impl std::fmt::Display for Error {
             Self::SemaphorePermitError(acqiure_error) => {
                 write!(f, "Failed to acquire semaphore permit: {}", acqiure_error)
             }
+            Self::PublishError(reason) => write!(f, "Failed to publish llms.txt: {}", reason),
+            Self::ArchiveError(reason) => write!(f, "Failed to archive page: {}", reason),
+            Self::DisallowedByPublisher(disallowed) => {
+                write!(f, "The publisher disallows indexing this page: {}", disallowed)
+            }
+            Self::InvalidResultData(reason) => write!(f, "Invalid result: {}", reason),
+        }
+    }
+}
+
+impl Error {
+    /// What kind of failure this is, for users.
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            Self::CoreError(core_error) => FailureKind::from(core_error),
+            Self::DisallowedByPublisher(_) => FailureKind::RobotsDisallowed,
+            Self::InvalidResultData(_) => FailureKind::InvalidOutput,
+            Self::RecordNotFound
+            | Self::DbError(_)
+            | Self::DbPoolError(_)
+            | Self::SemaphorePermitError(_)
+            | Self::PublishError(_)
+            | Self::ArchiveError(_) => FailureKind::Internal,
         }
     }
 }
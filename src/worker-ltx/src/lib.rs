@@ -1,6 +1,29 @@
+pub mod archive;
+pub mod cancellation;
+pub mod embeddings;
 pub mod errors;
+pub mod llms_txt_archive;
+pub mod metrics;
+pub mod post_process;
+pub mod publish;
+pub mod result_data;
+pub mod settings;
+pub mod shard;
+pub mod webhooks;
 pub mod work;
 
+pub use archive::WarcArchive;
+pub use cancellation::RunningJobs;
+pub use embeddings::embedding_loop;
 pub use errors::Error;
+pub use llms_txt_archive::llms_txt_archive_loop;
+pub use publish::Publishers;
+pub use settings::WorkerSettings;
+pub use shard::Shard;
+pub use webhooks::webhook_delivery_loop;
 
-pub use work::{JobResult, handle_job, handle_result, next_job_in_queue};
+pub use work::{
+    JobResult, expire_overdue_jobs, guard_downloads, handle_job, handle_job_archived, handle_result,
+    handle_result_post_processed, next_job_in_queue, next_job_in_shard, record_usage, tokens_used_today,
+    worker_polling_loop,
+};
@@ -0,0 +1,80 @@
+//! Moves superseded llms.txt records to the archive tier (see `data_model_ltx::archive`), in the background.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use core_ltx::{db, parse_duration};
+use data_model_ltx::archive::archive_superseded;
+
+use crate::errors::Error;
+
+/// How old a superseded llms.txt record is before it's archived, e.g. `30d` or `12h`. `0` turns archiving off.
+pub const LLMS_TXT_ARCHIVE_AFTER_ENV_VAR: &str = "LLMS_TXT_ARCHIVE_AFTER";
+
+const DEFAULT_ARCHIVE_AFTER: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// How often the worker looks for records to archive.
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Records moved per statement: each batch is a short transaction of its own.
+const BATCH_SIZE: i64 = 500;
+
+/// `LLMS_TXT_ARCHIVE_AFTER`, 30 days by default. None when archiving is turned off.
+/// Errors on a duration that reaches back before the earliest time there is.
+pub fn archive_after_from_env() -> Result<Option<Duration>, String> {
+    let after = match std::env::var(LLMS_TXT_ARCHIVE_AFTER_ENV_VAR) {
+        Ok(value) => parse_duration(&value).map_err(|e| format!("{}: {}", LLMS_TXT_ARCHIVE_AFTER_ENV_VAR, e))?,
+        Err(_) => DEFAULT_ARCHIVE_AFTER,
+    };
+    if created_before(after).is_none() {
+        return Err(format!("{}: {:?} is too long", LLMS_TXT_ARCHIVE_AFTER_ENV_VAR, after));
+    }
+    Ok((!after.is_zero()).then_some(after))
+}
+
+/// The time records have to be created before to be older than `after`. None when it's before the earliest time
+/// there is: no record is that old.
+fn created_before(after: Duration) -> Option<DateTime<Utc>> {
+    Utc::now().checked_sub_signed(chrono::Duration::from_std(after).ok()?)
+}
+
+/// Archives every superseded record older than `after`, a batch at a time. Returns the number of records archived.
+pub async fn archive_superseded_llms_txt(pool: &db::DbPool, after: Duration) -> Result<usize, Error> {
+    let Some(older_than) = created_before(after) else {
+        return Ok(0);
+    };
+    let mut conn = pool.get().await?;
+    let mut archived = 0;
+    loop {
+        let moved = archive_superseded(&mut conn, older_than, BATCH_SIZE).await?;
+        archived += moved;
+        if (moved as i64) < BATCH_SIZE {
+            return Ok(archived);
+        }
+    }
+}
+
+/// Archives superseded records older than `after` every hour, until the worker stops.
+/// Failures are logged: it's tried again in an hour.
+pub async fn llms_txt_archive_loop(pool: db::DbPool, after: Duration) {
+    loop {
+        match archive_superseded_llms_txt(&pool, after).await {
+            Ok(0) => {}
+            Ok(archived) => tracing::info!("Archived {} superseded llms.txt record(s)", archived),
+            Err(error) => tracing::error!("[SKIP] Failed to archive superseded llms.txt records: {}", error),
+        }
+        tokio::time::sleep(ARCHIVE_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_created_before() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let before = created_before(day).unwrap();
+        assert!(before < Utc::now() - chrono::Duration::hours(23));
+        assert!(created_before(day * 1_000_000_000).is_none());
+        assert!(created_before(Duration::MAX).is_none());
+    }
+}
@@ -1,94 +1,147 @@
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
-use core_ltx::db::DbPool;
+use clap::Parser;
+use core_ltx::llms::{ChatGpt, Embedder, LlmProvider};
+use core_ltx::notify::Notifications;
 use core_ltx::{
-    TimeUnit, get_db_pool, get_max_concurrency, get_poll_interval, health_router,
-    llms::{ChatGpt, LlmProvider},
-    setup_logging,
+    PARSE_LIMITS, Readiness, get_db_pool, init_http_client, readiness_router, reload_on_sighup, setup_telemetry,
 };
-use tokio::sync::Semaphore;
-use worker_ltx::{Error, JobResult, handle_job, handle_result, next_job_in_queue};
+use data_model_ltx::embeddings::embeddings_available;
+use data_model_ltx::migrations::run_migrations_if_enabled;
+use data_model_ltx::secrets::{Keyring, SecretsError};
+use worker_ltx::{
+    Publishers, Shard, WarcArchive, WorkerSettings, embedding_loop, llms_txt_archive::archive_after_from_env,
+    llms_txt_archive_loop, metrics::metrics_router, webhook_delivery_loop, worker_polling_loop,
+};
+
+#[derive(Parser)]
+#[command(name = "worker-ltx", version, about = "Works on queued llms.txt generation jobs")]
+struct Args {
+    /// Only claim jobs of shard k of n (e.g. `0/4`): those whose `hashtext(url_normalized) % n == k`.
+    /// Run one worker per shard to split the queue between them. Default: claim any job.
+    #[arg(long, env = "WORKER_SHARD")]
+    shard: Option<Shard>,
+}
 
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file., if it exists
     dotenvy::dotenv().ok();
+    let args = Args::parse();
 
-    setup_logging("worker_ltx=debug");
-
-    let provider: Arc<ChatGpt> = Arc::new(ChatGpt::default());
+    let _telemetry = setup_telemetry("worker-ltx", "worker_ltx=debug");
 
+    run_migrations_if_enabled().await;
     let pool = get_db_pool().await;
 
-    let poll_interval = get_poll_interval(TimeUnit::Milliseconds, "WORKER_POLL_INTERVAL_MS", 600);
-
-    let semaphore = {
-        let max_concurrency = get_max_concurrency(None);
-        tracing::info!("Worker configured with max concurrency: {}", max_concurrency);
-        Arc::new(Semaphore::new(max_concurrency))
-    };
+    // Poll interval, concurrency, token budget, and model are re-read on SIGHUP.
+    let settings = reload_on_sighup("worker", WorkerSettings::from_env).unwrap_or_else(|e| panic!("{}", e));
+    {
+        let settings = settings.borrow();
+        tracing::info!("Worker configured with max concurrency: {}", settings.max_concurrency);
+        match settings.daily_token_budget {
+            Some(budget) => tracing::info!("Daily token budget: {} tokens", budget),
+            None => tracing::info!("No daily token budget"),
+        }
+    }
+    // Read now, so invalid limits fail at startup rather than on the first job
+    tracing::info!("HTML and markdown parse limits: {:?}", *PARSE_LIMITS);
+    let http_client_settings = init_http_client().unwrap_or_else(|e| panic!("{}", e));
+    tracing::info!("Download HTTP client: {:?}", http_client_settings);
 
-    // Spawn health check HTTP server
-    tokio::spawn(async {
-        let app = health_router();
+    // Spawn health check & metrics HTTP server. It reports unhealthy until the LLM provider check passes.
+    let readiness = Readiness::starting();
+    let metrics_pool = pool.clone();
+    let health_readiness = readiness.clone();
+    tokio::spawn(async move {
+        let app = readiness_router(health_readiness).merge(metrics_router(metrics_pool));
         let listener = tokio::net::TcpListener::bind("0.0.0.0:8080")
             .await
             .expect("Failed to bind health check server to 0.0.0.0:8080");
-        tracing::info!("Health check server listening on 0.0.0.0:8080");
+        tracing::info!("Health check & metrics server listening on 0.0.0.0:8080");
         axum::serve(listener, app).await.expect("Health check server failed");
     });
 
-    tracing::info!("Starting worker polling loop");
-    worker_polling_loop(pool, provider, poll_interval, semaphore).await;
-}
+    // Fail visibly on a bad API key or model, instead of failing every job
+    let model_name = settings.borrow().model_name.clone();
+    match ChatGpt::new(&model_name).check_credentials().await {
+        Ok(()) => {
+            tracing::info!("LLM provider check passed for model {}", model_name);
+            readiness.set_ready();
+        }
+        Err(e) => {
+            tracing::error!("LLM provider check failed for model {}: {}", model_name, e);
+            readiness.set_failed(format!("LLM provider check failed for model {}: {}", model_name, e));
+            // don't claim jobs that would all fail: stay up, reporting unhealthy, until restarted with a fix
+            std::future::pending::<()>().await;
+        }
+    }
 
-/// Continuously polls the DB for new jobs and spawns tasks to work on them.
-/// Uses a semaphore to limit the maximum number of concurrent tasks.
-async fn worker_polling_loop<P>(pool: DbPool, provider: Arc<P>, poll_interval: Duration, semaphore: Arc<Semaphore>)
-where
-    P: LlmProvider + 'static,
-{
-    loop {
-        match next_job_in_queue(&pool, semaphore.clone()).await {
-            Ok((job, permit)) => {
-                #[allow(clippy::let_underscore_future)]
-                let _ = tokio::spawn({
-                    let pool = pool.clone();
-                    let provider = provider.clone();
-                    async move {
-                        tracing::info!("Received job {} ({:?}) on website '{}'", job.job_id, job.kind, job.url);
-                        let result = handle_job(provider.as_ref(), &job).await;
-                        let is_ok = matches!(result, JobResult::Success { .. });
-                        match handle_result(&pool, &job, result).await {
-                            Ok(ok) => ok,
-                            Err(error) => {
-                                tracing::error!(
-                                    "[SKIP] Failed to handle result for job {} ({:?} - '{}'). Result was ok?: {} - ERROR: {}",
-                                    job.job_id,
-                                    job.kind,
-                                    job.url,
-                                    is_ok,
-                                    error
-                                );
-                            }
-                        };
-                        // We need to:
-                        //   (1) make sure this task owns the semaphore permit
-                        //   (2) release this semaphore permit when the task ends
-                        // It just needs to be owned by the task, so a `let _permit = permit;` would work too,
-                        // but we just explicitly drop it here to move it into the task and make it clear that we release it at the end.
-                        drop(permit);
-                    }
-                });
-            }
-            Err(error) => match error {
-                Error::RecordNotFound => {}
-                _ => {
-                    tracing::error!("[SKIP] Error getting next job from DB queue: {}", error);
+    let notifications = Arc::new(Notifications::from_env());
+    let publishers = Arc::new(Publishers::from_env().unwrap_or_else(|e| panic!("{}", e)));
+    let archive = WarcArchive::from_env()
+        .unwrap_or_else(|e| panic!("{}", e))
+        .map(Arc::new);
+    if let Some(archive) = archive.as_ref() {
+        tracing::info!(
+            "Archiving downloaded pages as WARC files to S3 bucket '{}'",
+            archive.bucket()
+        );
+    }
+
+    // Webhook secrets are encrypted like the domain credentials: without a key, nothing can be signed
+    match Keyring::from_env() {
+        Ok(keyring) => {
+            let poll_interval = settings.borrow().poll_interval;
+            tokio::spawn(webhook_delivery_loop(pool.clone(), keyring, poll_interval));
+        }
+        Err(SecretsError::MissingKey) => tracing::info!("No SECRETS_KEYS: webhook events aren't delivered"),
+        Err(error) => tracing::error!("Webhook events aren't delivered: {}", error),
+    }
+
+    match archive_after_from_env().unwrap_or_else(|e| panic!("{}", e)) {
+        Some(after) => {
+            tracing::info!("Archiving superseded llms.txt records older than {:?}", after);
+            tokio::spawn(llms_txt_archive_loop(pool.clone(), after));
+        }
+        None => tracing::info!("Superseded llms.txt records aren't archived"),
+    }
+
+    // Semantic search needs both an embedding model and pgvector in the database
+    match Embedder::from_env() {
+        Some(embedder) => {
+            let available = match pool.get().await {
+                Ok(mut conn) => embeddings_available(&mut conn).await.map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            match available {
+                Ok(true) => {
+                    tracing::info!("Embedding llms.txt files with model {}", embedder.model_name);
+                    tokio::spawn(embedding_loop(pool.clone(), embedder));
                 }
-            },
+                Ok(false) => tracing::error!(
+                    "EMBEDDING_MODEL is set, but the database has no pgvector extension: llms.txt files aren't embedded"
+                ),
+                Err(error) => tracing::error!("llms.txt files aren't embedded: {}", error),
+            }
         }
-        tracing::debug!("Waiting to poll for next job");
-        tokio::time::sleep(poll_interval).await;
+        None => tracing::info!("No EMBEDDING_MODEL: llms.txt files aren't embedded"),
+    }
+
+    match args.shard {
+        Some(shard) => tracing::info!("Claiming jobs of shard {}", shard),
+        None => tracing::info!("Claiming jobs of all shards"),
     }
+
+    tracing::info!("Starting worker polling loop");
+    worker_polling_loop(
+        pool,
+        |settings: &WorkerSettings| ChatGpt::new(&settings.model_name),
+        settings,
+        notifications,
+        publishers,
+        archive,
+        args.shard,
+    )
+    .await;
 }
@@ -0,0 +1,135 @@
+use std::sync::LazyLock;
+
+use axum::{Router, extract::State, http::StatusCode, http::header, response::IntoResponse};
+use core_ltx::db;
+use data_model_ltx::{models::JobStatus, schema};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use prometheus::{Encoder, HistogramVec, IntGauge, TextEncoder, register_histogram_vec, register_int_gauge};
+
+/// Stages of a job, in the order they run.
+pub mod stage {
+    /// Checking that the job's URL is valid.
+    pub const VALIDATE: &str = "validate";
+    /// Downloading the website's HTML.
+    pub const DOWNLOAD: &str = "download";
+    /// Uploading the downloaded page to the WARC archive, when archiving is enabled.
+    pub const ARCHIVE: &str = "archive";
+    /// Normalizing, checksumming, and compressing the HTML.
+    pub const NORMALIZE: &str = "normalize";
+    /// Looking for an llms.txt the website publishes itself.
+    pub const ORIGIN: &str = "origin";
+    /// Downloading the rest of the website, for a crawl job.
+    pub const CRAWL: &str = "crawl";
+    /// Generating (or updating) llms.txt with the LLM, including validating its output.
+    pub const LLM: &str = "llm";
+    /// Storing the result and the job's final status.
+    pub const DB_WRITE: &str = "db_write";
+}
+
+/// LLM calls take tens of seconds, so the buckets go well past the defaults' 10s.
+const DURATION_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+/// How long each stage of a job took, by `stage`.
+pub static STAGE_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "worker_stage_duration_seconds",
+        "Time spent in each stage of llms.txt generation.",
+        &["stage"],
+        DURATION_BUCKETS.to_vec()
+    )
+    .expect("worker_stage_duration_seconds is registered once")
+});
+
+/// How long whole jobs took, from being claimed to their result being stored, by `outcome`.
+pub static JOB_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register_histogram_vec!(
+        "worker_job_duration_seconds",
+        "Time to complete an llms.txt generation job.",
+        &["outcome"],
+        DURATION_BUCKETS.to_vec()
+    )
+    .expect("worker_job_duration_seconds is registered once")
+});
+
+/// Jobs waiting to be claimed by any worker. Refreshed on every scrape.
+pub static QUEUE_DEPTH: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("worker_queue_depth", "Jobs queued and not yet claimed by a worker.")
+        .expect("worker_queue_depth is registered once")
+});
+
+/// Jobs this worker is running right now.
+pub static JOBS_RUNNING: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!("worker_jobs_running", "Jobs this worker is currently running.")
+        .expect("worker_jobs_running is registered once")
+});
+
+/// Times `stage` until the returned timer is dropped (or `observe_duration` is called on it).
+pub fn time_stage(stage: &str) -> prometheus::HistogramTimer {
+    STAGE_DURATION.with_label_values(&[stage]).start_timer()
+}
+
+/// Counts the jobs that are queued.
+pub async fn queue_depth(pool: &db::DbPool) -> Result<i64, crate::Error> {
+    let mut conn = pool.get().await?;
+    let queued = schema::job_state::table
+        .filter(schema::job_state::status.eq(JobStatus::Queued))
+        .count()
+        .get_result(&mut conn)
+        .await?;
+    Ok(queued)
+}
+
+/// Serves the worker's metrics in the Prometheus text format at `/metrics`.
+pub fn metrics_router(pool: db::DbPool) -> Router {
+    Router::new()
+        .route("/metrics", axum::routing::get(metrics_handler))
+        .with_state(pool)
+}
+
+async fn metrics_handler(State(pool): State<db::DbPool>) -> impl IntoResponse {
+    match queue_depth(&pool).await {
+        Ok(queued) => QUEUE_DEPTH.set(queued),
+        // keep the last known depth: the scrape still has the stage timings
+        Err(e) => tracing::warn!("[SKIP] Failed to count queued jobs for metrics: {}", e),
+    }
+    // export "0 running" before the first job, rather than nothing
+    LazyLock::force(&JOBS_RUNNING);
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    match encoder.encode(&prometheus::gather(), &mut body) {
+        Ok(()) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, encoder.format_type().to_string())],
+            body,
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain".to_string())],
+            e.to_string().into_bytes(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_timer_observes_on_drop() {
+        let histogram = STAGE_DURATION.with_label_values(&[stage::NORMALIZE]);
+        let before = histogram.get_sample_count();
+        {
+            let _timer = time_stage(stage::NORMALIZE);
+        }
+        assert_eq!(histogram.get_sample_count(), before + 1);
+
+        let mut body = Vec::new();
+        TextEncoder::new().encode(&prometheus::gather(), &mut body).unwrap();
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(r#"worker_stage_duration_seconds_bucket{stage="normalize",le="300"}"#));
+    }
+}
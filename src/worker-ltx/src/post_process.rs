@@ -0,0 +1,341 @@
+//! Post-processing of the generated llms.txt files: hooks that tidy up an llms.txt once the LLM's output is
+//! validated, before it's stored (and published).
+//!
+//! Each deployment picks its hooks, and their order, with `POST_PROCESS_HOOKS` (see `parse_hooks`): there are none by
+//! default. They're only applied to generated llms.txt files: the ones imported from a website are stored as their
+//! publisher wrote them. The hooks work on the llms.txt's text, as it's stored, so that the renderer of the validated
+//! markdown doesn't undo them.
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use core_ltx::{is_valid_markdown, validate_is_llm_txt};
+use url::Url;
+use uuid::Uuid;
+
+/// The hooks applied to every generated llms.txt, in order, separated by commas, e.g.
+/// `trim_whitespace,absolute_links,strip_tracking,max_line_length:200`.
+pub const POST_PROCESS_HOOKS_ENV_VAR: &str = "POST_PROCESS_HOOKS";
+
+/// Characters a line is cut down to by `max_line_length`, when it doesn't say.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 300;
+
+/// Query parameters that only track where a visitor came from, besides the `utm_*` ones.
+const TRACKING_PARAMS: [&str; 9] = [
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_hsenc",
+];
+
+/// A step of the post-processing, named in `POST_PROCESS_HOOKS` as documented on each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// `trim_whitespace`: removes the whitespace at the end of every line, and the blank lines at the end.
+    TrimWhitespace,
+    /// `max_line_length` or `max_line_length:<characters>`: cuts the longer lines at a word, ending them with `…`.
+    /// Links are never cut: a line whose first link is longer is kept whole.
+    MaxLineLength(usize),
+    /// `absolute_links`: resolves the relative links against the URL of the page the llms.txt was generated for.
+    AbsoluteLinks,
+    /// `strip_tracking`: removes the tracking parameters (`utm_*`, `fbclid`, `gclid`, ...) from the links.
+    StripTracking,
+}
+
+impl Hook {
+    fn apply(&self, llms_txt: &str, source: Option<&Url>) -> String {
+        match (self, source) {
+            (Hook::TrimWhitespace, _) => trim_whitespace(llms_txt),
+            (Hook::MaxLineLength(max), _) => map_lines(llms_txt, |line| shorten_line(line, *max)),
+            (Hook::AbsoluteLinks, Some(source)) => map_lines(llms_txt, |line| {
+                map_link_targets(line, |target| absolute_link(target, source))
+            }),
+            (Hook::AbsoluteLinks, None) => llms_txt.to_string(),
+            (Hook::StripTracking, _) => map_lines(llms_txt, |line| map_link_targets(line, strip_tracking)),
+        }
+    }
+}
+
+impl FromStr for Hook {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, argument) = match value.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument.trim())),
+            None => (value.trim(), None),
+        };
+        match (name.to_ascii_lowercase().as_str(), argument) {
+            ("trim_whitespace", None) => Ok(Hook::TrimWhitespace),
+            ("max_line_length", None) => Ok(Hook::MaxLineLength(DEFAULT_MAX_LINE_LENGTH)),
+            ("max_line_length", Some(length)) => match length.parse::<usize>() {
+                Ok(length) if length > 1 => Ok(Hook::MaxLineLength(length)),
+                _ => Err(format!(
+                    "max_line_length takes a number of characters above 1, not '{}'",
+                    length
+                )),
+            },
+            ("absolute_links", None) => Ok(Hook::AbsoluteLinks),
+            ("strip_tracking", None) => Ok(Hook::StripTracking),
+            _ => Err(format!(
+                "unknown post-processing hook '{}': the hooks are trim_whitespace, max_line_length[:<characters>], \
+                 absolute_links, and strip_tracking",
+                value.trim()
+            )),
+        }
+    }
+}
+
+/// The hooks of `POST_PROCESS_HOOKS`: their names, separated by commas. Errors on one that doesn't exist.
+pub fn parse_hooks(value: &str) -> Result<Vec<Hook>, String> {
+    value
+        .split(',')
+        .filter(|hook| !hook.trim().is_empty())
+        .map(Hook::from_str)
+        .collect()
+}
+
+/// The llms.txt after every hook, in order. `source` is the URL it was generated for.
+///
+/// It's validated again: when the hooks made it invalid, it's returned as it was, and that's logged. The hooks tidy up
+/// an llms.txt, they don't get to break one.
+pub fn post_process(job_id: Uuid, llms_txt: &str, hooks: &[Hook], source: &str) -> String {
+    if hooks.is_empty() {
+        return llms_txt.to_string();
+    }
+    let source = Url::parse(source).ok();
+    let processed = hooks
+        .iter()
+        .fold(llms_txt.to_string(), |text, hook| hook.apply(&text, source.as_ref()));
+    match is_valid_markdown(&processed).and_then(validate_is_llm_txt) {
+        Ok(_) => processed,
+        Err(e) => {
+            tracing::warn!(
+                "[job: {}] Post-processing made the llms.txt invalid: it's stored as generated. Error: {}",
+                job_id,
+                e
+            );
+            llms_txt.to_string()
+        }
+    }
+}
+
+/// Applies `f` to every line, keeping the line breaks as they are.
+fn map_lines(text: &str, f: impl Fn(&str) -> String) -> String {
+    text.split('\n').map(f).collect::<Vec<_>>().join("\n")
+}
+
+fn trim_whitespace(text: &str) -> String {
+    let trimmed = map_lines(text, |line| line.trim_end().to_string());
+    format!("{}\n", trimmed.trim_end())
+}
+
+/// The line, cut at the last word that fits in `max` characters with the `…`. Links are never cut.
+fn shorten_line(line: &str, max: usize) -> String {
+    let Some((cut, _)) = line.char_indices().nth(max.saturating_sub(1)) else {
+        return line.to_string();
+    };
+    if line[cut..].chars().nth(1).is_none() {
+        // exactly `max` characters
+        return line.to_string();
+    }
+    let links = links(line);
+    let mut end = match line[..cut].rfind(char::is_whitespace) {
+        Some(space) => space,
+        None => cut,
+    };
+    if let Some((link, _)) = links.iter().find(|(link, _)| link.start < end && end < link.end) {
+        end = link.start;
+    }
+    // the line's first link (e.g. a list item's) is what it's about: rather too long than without it
+    if links.first().is_some_and(|(link, _)| end < link.end) || line[..end].trim().is_empty() {
+        return line.to_string();
+    }
+    format!("{}…", line[..end].trim_end())
+}
+
+/// The markdown links of the line: the range of each `[text](target)`, and of its target.
+fn links(line: &str) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(found) = line[from..].find("](") {
+        let middle = from + found;
+        let target_start = middle + 2;
+        // URLs can have parentheses too, e.g. Wikipedia's
+        let mut depth = 0;
+        let target_end = line[target_start..].char_indices().find_map(|(i, c)| match c {
+            '(' => {
+                depth += 1;
+                None
+            }
+            ')' if depth == 0 => Some(target_start + i),
+            ')' => {
+                depth -= 1;
+                None
+            }
+            _ => None,
+        });
+        let Some(target_end) = target_end else {
+            break;
+        };
+        let start = line[from..middle].rfind('[').map_or(middle, |open| from + open);
+        links.push((start..target_end + 1, target_start..target_end));
+        from = target_end + 1;
+    }
+    links
+}
+
+/// The line, with the link targets that `f` rewrites rewritten.
+fn map_link_targets(line: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    let mut mapped = String::with_capacity(line.len());
+    let mut from = 0;
+    for (_, target) in links(line) {
+        if let Some(rewritten) = f(&line[target.clone()]) {
+            mapped.push_str(&line[from..target.start]);
+            mapped.push_str(&rewritten);
+            from = target.end;
+        }
+    }
+    mapped.push_str(&line[from..]);
+    mapped
+}
+
+/// The target resolved against the source page, unless it's absolute already (or has a title: `(url "title")`).
+fn absolute_link(target: &str, source: &Url) -> Option<String> {
+    if target.is_empty() || target.contains(char::is_whitespace) || Url::parse(target).is_ok() {
+        return None;
+    }
+    source.join(target).ok().map(String::from)
+}
+
+/// The http(s) target without its tracking parameters, if it has any.
+fn strip_tracking(target: &str) -> Option<String> {
+    let mut url = Url::parse(target).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = params.iter().filter(|(name, _)| !is_tracking_param(name)).collect();
+    if kept.len() == params.len() {
+        return None;
+    }
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    Some(url.into())
+}
+
+fn is_tracking_param(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LLMS_TXT: &str = "# Example  \n\n> Docs of Example.\t\n\n## Docs\n\n\
+        - [Guide](/docs/guide?utm_source=llm&page=2): Getting started\n\
+        - [API](https://api.example.com/ref?fbclid=abc#auth)\n\
+        - [Mail](mailto:team@example.com)\n\n\n";
+
+    fn apply(hooks: &str, llms_txt: &str) -> String {
+        post_process(
+            Uuid::nil(),
+            llms_txt,
+            &parse_hooks(hooks).unwrap(),
+            "https://example.com/start/",
+        )
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        assert!(parse_hooks("").unwrap().is_empty());
+        assert_eq!(
+            parse_hooks(" trim_whitespace, Max_Line_Length:120 ,absolute_links,strip_tracking,max_line_length,")
+                .unwrap(),
+            vec![
+                Hook::TrimWhitespace,
+                Hook::MaxLineLength(120),
+                Hook::AbsoluteLinks,
+                Hook::StripTracking,
+                Hook::MaxLineLength(DEFAULT_MAX_LINE_LENGTH),
+            ]
+        );
+        assert!(parse_hooks("trim_whitespace,uppercase").is_err());
+        assert!(parse_hooks("max_line_length:1").is_err());
+        assert!(parse_hooks("max_line_length:long").is_err());
+        assert!(parse_hooks("strip_tracking:all").is_err());
+    }
+
+    #[test]
+    fn test_trim_whitespace() {
+        assert_eq!(
+            apply("trim_whitespace", LLMS_TXT),
+            "# Example\n\n> Docs of Example.\n\n## Docs\n\n\
+             - [Guide](/docs/guide?utm_source=llm&page=2): Getting started\n\
+             - [API](https://api.example.com/ref?fbclid=abc#auth)\n\
+             - [Mail](mailto:team@example.com)\n"
+        );
+    }
+
+    #[test]
+    fn test_absolute_links_and_strip_tracking() {
+        let processed = apply("absolute_links,strip_tracking", LLMS_TXT);
+        assert!(processed.contains("- [Guide](https://example.com/docs/guide?page=2): Getting started\n"));
+        assert!(processed.contains("- [API](https://api.example.com/ref#auth)\n"));
+        assert!(processed.contains("- [Mail](mailto:team@example.com)\n"));
+        // relative to the page the llms.txt was generated for, which can be a subpage
+        let subpage = Url::parse("https://example.com/docs/start/").unwrap();
+        assert_eq!(
+            map_link_targets("[A](intro) and [B](../b)", |target| absolute_link(target, &subpage)),
+            "[A](https://example.com/docs/start/intro) and [B](https://example.com/docs/b)"
+        );
+        // a URL with parentheses, and a link with a title, which is left as is
+        let home = Url::parse("https://example.com").unwrap();
+        assert_eq!(
+            map_link_targets(
+                "[W](https://en.wikipedia.org/wiki/Rust_(language)?utm_medium=x) [T](/t \"T\")",
+                |target| strip_tracking(target).or_else(|| absolute_link(target, &home))
+            ),
+            "[W](https://en.wikipedia.org/wiki/Rust_(language)) [T](/t \"T\")"
+        );
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        assert_eq!(shorten_line("short line", 20), "short line");
+        assert_eq!(shorten_line("exactly ten", 11), "exactly ten");
+        assert_eq!(shorten_line("> a summary that goes on and on", 20), "> a summary that…");
+        let link = "- [Guide](https://example.com/docs/guide)";
+        // the description is cut, the link isn't
+        assert_eq!(
+            shorten_line(&format!("{}: Getting started with the guide", link), 60),
+            format!("{}: Getting started…", link)
+        );
+        assert_eq!(
+            shorten_line(&format!("{}: Getting started", link), 30),
+            format!("{}: Getting started", link)
+        );
+        assert_eq!(
+            shorten_line(
+                "See [A](https://a.example.com) and [B](https://b.example.com/long/path)",
+                50
+            ),
+            "See [A](https://a.example.com) and…"
+        );
+    }
+
+    #[test]
+    fn test_post_process_keeps_a_valid_llms_txt() {
+        let processed = apply("trim_whitespace,max_line_length:20", LLMS_TXT);
+        assert!(processed.starts_with("# Example\n\n> Docs of Example.\n"));
+        // a hook that breaks the llms.txt is undone: here, the H1 can't be cut without losing the title
+        assert_eq!(
+            apply("max_line_length:3", "# Example\n\n> Docs.\n"),
+            "# Example\n\n> Docs.\n"
+        );
+        assert_eq!(
+            post_process(Uuid::nil(), LLMS_TXT, &[], "https://example.com"),
+            LLMS_TXT
+        );
+    }
+}
@@ -0,0 +1,509 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use url::Url;
+use uuid::Uuid;
+
+use crate::errors::Error;
+
+mod git;
+
+pub use git::GitPublisher;
+
+/// Bucket to upload generated llms.txt files to. Publishing to S3 is enabled when this is set.
+pub const S3_BUCKET_ENV_VAR: &str = "PUBLISH_S3_BUCKET";
+/// S3 API endpoint, for S3-compatible stores (MinIO, R2, ...). Defaults to AWS S3 in the region.
+pub const S3_ENDPOINT_ENV_VAR: &str = "PUBLISH_S3_ENDPOINT";
+/// Region of the bucket. Defaults to `us-east-1`.
+pub const S3_REGION_ENV_VAR: &str = "PUBLISH_S3_REGION";
+/// Key prefix ("folder") that files are uploaded under.
+pub const S3_PREFIX_ENV_VAR: &str = "PUBLISH_S3_PREFIX";
+/// URL that the bucket is served from (e.g. by a CDN). Published files are at this URL plus their key.
+pub const PUBLIC_BASE_URL_ENV_VAR: &str = "PUBLISH_PUBLIC_BASE_URL";
+/// CDN cache purge endpoint, called with `{"files": [<public URL>]}` after each upload (e.g. Cloudflare's).
+pub const CDN_PURGE_URL_ENV_VAR: &str = "PUBLISH_CDN_PURGE_URL";
+/// Bearer token for the CDN purge endpoint.
+pub const CDN_PURGE_TOKEN_ENV_VAR: &str = "PUBLISH_CDN_PURGE_TOKEN";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// A successfully generated llms.txt.
+#[derive(Debug, Clone, Copy)]
+pub struct Publication<'a> {
+    /// Job that generated it.
+    pub job_id: Uuid,
+    /// Website it's for.
+    pub site_url: &'a Url,
+    pub llms_txt: &'a str,
+}
+
+/// A place generated llms.txt files are copied to, so they can be served from a website's own infrastructure.
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    async fn publish(&self, publication: &Publication<'_>) -> Result<(), Error>;
+}
+
+/// Publishes every successful generation to all configured publishers. With none configured, nothing is published.
+#[derive(Default)]
+pub struct Publishers {
+    publishers: Vec<Box<dyn Publisher>>,
+}
+
+impl Publishers {
+    pub fn new(publishers: Vec<Box<dyn Publisher>>) -> Self {
+        Self { publishers }
+    }
+
+    /// Configures S3 publishing when `PUBLISH_S3_BUCKET` is set and Git publishing when `PUBLISH_GIT_REPO` is set.
+    pub fn from_env() -> Result<Self, String> {
+        let mut publishers: Vec<Box<dyn Publisher>> = Vec::new();
+        if let Some(s3) = S3Publisher::from_env()? {
+            tracing::info!("Publishing llms.txt files to S3 bucket '{}'", s3.bucket);
+            publishers.push(Box::new(s3));
+        }
+        if let Some(git) = GitPublisher::from_env()? {
+            tracing::info!("Publishing llms.txt files to Git repository '{}'", git.repo_url());
+            publishers.push(Box::new(git));
+        }
+        Ok(Self::new(publishers))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.publishers.is_empty()
+    }
+
+    /// Publishes everywhere. Failures are logged, not returned: the llms.txt is already stored,
+    /// so the job succeeded even if a copy couldn't be published.
+    pub async fn publish(&self, job_id: Uuid, site_url: &str, llms_txt: &str) {
+        if self.publishers.is_empty() {
+            return;
+        }
+        let site_url = match Url::parse(site_url) {
+            Ok(url) => url,
+            Err(e) => {
+                tracing::error!("[SKIP] Not publishing llms.txt of invalid URL '{}': {}", site_url, e);
+                return;
+            }
+        };
+        let publication = Publication {
+            job_id,
+            site_url: &site_url,
+            llms_txt,
+        };
+        for publisher in self.publishers.iter() {
+            if let Err(e) = publisher.publish(&publication).await {
+                tracing::error!("[SKIP] Failed to publish llms.txt of '{}': {}", site_url, e);
+            }
+        }
+    }
+}
+
+/// Where the llms.txt of `site_url` is published, relative to the publishing root:
+/// `https://example.com/docs/` becomes `example.com/docs/llms.txt`.
+pub fn object_key(site_url: &Url) -> String {
+    let host = site_url.host_str().unwrap_or("unknown-host");
+    let host = match site_url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+    let path = site_url.path().trim_matches('/');
+    if path.is_empty() {
+        format!("{}/llms.txt", host)
+    } else {
+        format!("{}/{}/llms.txt", host, path)
+    }
+}
+
+/// Uploads llms.txt files to an S3 (or S3-compatible) bucket, then optionally purges them from a CDN.
+pub struct S3Publisher {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    prefix: String,
+    credentials: Credentials,
+    cdn: Option<CdnPurge>,
+}
+
+/// AWS credentials, from the standard `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY`, and `AWS_SESSION_TOKEN`.
+#[derive(Clone)]
+pub struct Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+}
+
+/// Names of the environment variables that configure a bucket.
+pub(crate) struct S3EnvVars {
+    pub bucket: &'static str,
+    pub endpoint: &'static str,
+    pub region: &'static str,
+    pub prefix: &'static str,
+}
+
+struct CdnPurge {
+    purge_url: String,
+    token: Option<String>,
+    public_base_url: Url,
+}
+
+impl S3Publisher {
+    pub fn new(endpoint: Url, bucket: String, region: String, prefix: String, credentials: Credentials) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            region,
+            prefix: prefix.trim_matches('/').to_string(),
+            credentials,
+            cdn: None,
+        }
+    }
+
+    /// None when `PUBLISH_S3_BUCKET` isn't set. An error when it is, but the rest of the configuration is invalid.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let Some(mut publisher) = Self::bucket_from_env(&S3EnvVars {
+            bucket: S3_BUCKET_ENV_VAR,
+            endpoint: S3_ENDPOINT_ENV_VAR,
+            region: S3_REGION_ENV_VAR,
+            prefix: S3_PREFIX_ENV_VAR,
+        })?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(purge_url) = var(CDN_PURGE_URL_ENV_VAR) {
+            let Some(public_base_url) = var(PUBLIC_BASE_URL_ENV_VAR) else {
+                return Err(format!(
+                    "{} is set: {} must be set too",
+                    CDN_PURGE_URL_ENV_VAR, PUBLIC_BASE_URL_ENV_VAR
+                ));
+            };
+            let mut public_base_url = Url::parse(&public_base_url)
+                .map_err(|e| format!("{} is not a valid URL: {}", PUBLIC_BASE_URL_ENV_VAR, e))?;
+            // so that joining keys appends to the path rather than replacing its last segment
+            if !public_base_url.path().ends_with('/') {
+                public_base_url.set_path(&format!("{}/", public_base_url.path()));
+            }
+            publisher.cdn = Some(CdnPurge {
+                purge_url,
+                token: var(CDN_PURGE_TOKEN_ENV_VAR),
+                public_base_url,
+            });
+        }
+        Ok(Some(publisher))
+    }
+
+    /// A client for the bucket named by `vars.bucket`, or None when it isn't set.
+    /// The AWS credentials come from the standard environment variables.
+    pub(crate) fn bucket_from_env(vars: &S3EnvVars) -> Result<Option<Self>, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let Some(bucket) = var(vars.bucket) else {
+            return Ok(None);
+        };
+        let region = var(vars.region).unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = var(vars.endpoint).unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = Url::parse(&endpoint).map_err(|e| format!("{} is not a valid URL: {}", vars.endpoint, e))?;
+        let (Some(access_key_id), Some(secret_access_key)) = (var("AWS_ACCESS_KEY_ID"), var("AWS_SECRET_ACCESS_KEY"))
+        else {
+            return Err(format!(
+                "{} is set: AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY must be set too",
+                vars.bucket
+            ));
+        };
+        let credentials = Credentials {
+            access_key_id,
+            secret_access_key,
+            session_token: var("AWS_SESSION_TOKEN"),
+        };
+        Ok(Some(Self::new(
+            endpoint,
+            bucket,
+            region,
+            var(vars.prefix).unwrap_or_default(),
+            credentials,
+        )))
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    /// `key` under the configured prefix.
+    pub(crate) fn prefixed(&self, key: &str) -> String {
+        if self.prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.prefix, key)
+        }
+    }
+
+    /// Key of the llms.txt of `site_url` in the bucket.
+    pub fn key(&self, site_url: &Url) -> String {
+        self.prefixed(&object_key(site_url))
+    }
+
+    /// Uploads `body` to `key`. The error says what went wrong, for the caller's own error.
+    pub(crate) async fn put_object(&self, key: &str, body: &[u8], content_type: &str) -> Result<(), String> {
+        // path-style addressing works with AWS and every S3-compatible store
+        let path = format!("/{}/{}", uri_encode(&self.bucket), uri_encode_path(key));
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+
+        let payload_hash = format!("{:x}", Sha256::digest(body));
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let authorization = sign_v4(&SigningRequest {
+            method: "PUT",
+            host: &host_header(&url),
+            path: &path,
+            payload_hash: &payload_hash,
+            amz_date: &amz_date,
+            region: &self.region,
+            credentials: &self.credentials,
+        });
+
+        let mut request = self
+            .client
+            .put(url)
+            .header("Content-Type", content_type)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("x-amz-date", &amz_date)
+            .header("Authorization", &authorization)
+            .body(body.to_vec());
+        if let Some(token) = self.credentials.session_token.as_deref() {
+            request = request.header("x-amz-security-token", token);
+        }
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let detail = response.text().await.unwrap_or_default();
+            return Err(format!("S3 upload of '{}' returned HTTP {}: {}", key, status, detail));
+        }
+        Ok(())
+    }
+
+    async fn purge_cdn(&self, cdn: &CdnPurge, key: &str) -> Result<(), Error> {
+        let public_url = cdn
+            .public_base_url
+            // "./" keeps a "host:port" key from being read as a URL scheme
+            .join(&format!("./{}", key))
+            .map_err(|e| Error::PublishError(format!("Invalid public URL for '{}': {}", key, e)))?;
+        let mut request = self
+            .client
+            .post(&cdn.purge_url)
+            .json(&serde_json::json!({ "files": [public_url.as_str()] }));
+        if let Some(token) = cdn.token.as_deref() {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.map_err(|e| Error::PublishError(e.to_string()))?;
+        if !response.status().is_success() {
+            return Err(Error::PublishError(format!(
+                "CDN purge of '{}' returned HTTP {}",
+                public_url,
+                response.status().as_u16()
+            )));
+        }
+        tracing::debug!("Purged '{}' from the CDN", public_url);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Publisher for S3Publisher {
+    async fn publish(&self, publication: &Publication<'_>) -> Result<(), Error> {
+        let key = self.key(publication.site_url);
+        self.put_object(&key, publication.llms_txt.as_bytes(), "text/plain; charset=utf-8")
+            .await
+            .map_err(Error::PublishError)?;
+        tracing::info!(
+            "Published llms.txt of '{}' to s3://{}/{}",
+            publication.site_url,
+            self.bucket,
+            key
+        );
+        if let Some(cdn) = self.cdn.as_ref() {
+            self.purge_cdn(cdn, &key).await?;
+        }
+        Ok(())
+    }
+}
+
+/// What's needed to sign an S3 request with AWS Signature Version 4.
+/// Only the `host`, `x-amz-content-sha256`, and `x-amz-date` headers are signed.
+struct SigningRequest<'a> {
+    method: &'a str,
+    host: &'a str,
+    /// Already URI-encoded.
+    path: &'a str,
+    payload_hash: &'a str,
+    /// `YYYYMMDD'T'HHMMSS'Z'`
+    amz_date: &'a str,
+    region: &'a str,
+    credentials: &'a Credentials,
+}
+
+/// The `Authorization` header of the request.
+fn sign_v4(request: &SigningRequest) -> String {
+    let date = &request.amz_date[..8];
+    let mut headers = vec![
+        ("host", request.host.to_string()),
+        ("x-amz-content-sha256", request.payload_hash.to_string()),
+        ("x-amz-date", request.amz_date.to_string()),
+    ];
+    if let Some(token) = request.credentials.session_token.as_deref() {
+        headers.push(("x-amz-security-token", token.to_string()));
+    }
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        request.method, request.path, canonical_headers, signed_headers, request.payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, request.region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{:x}",
+        request.amz_date,
+        scope,
+        Sha256::digest(canonical_request.as_bytes())
+    );
+    let key = signing_key(&request.credentials.secret_access_key, date, request.region, "s3");
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        request.credentials.access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn signing_key(secret_access_key: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac(format!("AWS4{}", secret_access_key).as_bytes(), date.as_bytes());
+    let key = hmac(&key, region.as_bytes());
+    let key = hmac(&key, service.as_bytes());
+    hmac(&key, b"aws4_request")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// The Host header reqwest sends for `url`: the port is only included when it isn't the scheme's default.
+fn host_header(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default();
+    match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// URI-encodes everything but unreserved characters, as SigV4 requires.
+fn uri_encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Like `uri_encode`, but keeps the `/` between path segments.
+fn uri_encode_path(path: &str) -> String {
+    path.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_key() {
+        let key = |url: &str| object_key(&Url::parse(url).unwrap());
+        assert_eq!(key("https://example.com"), "example.com/llms.txt");
+        assert_eq!(key("https://example.com/docs/"), "example.com/docs/llms.txt");
+        assert_eq!(key("http://localhost:8080/a/b"), "localhost:8080/a/b/llms.txt");
+    }
+
+    #[test]
+    fn test_uri_encode_path() {
+        assert_eq!(
+            uri_encode_path("llms/localhost:8080/a b/llms.txt"),
+            "llms/localhost%3A8080/a%20b/llms.txt"
+        );
+    }
+
+    #[test]
+    fn test_signing_key() {
+        // example from the AWS Signature Version 4 documentation
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+    }
+
+    #[test]
+    fn test_sign_v4_authorization() {
+        let credentials = Credentials {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+        };
+        let authorization = sign_v4(&SigningRequest {
+            method: "PUT",
+            host: "s3.us-east-1.amazonaws.com",
+            path: "/bucket/example.com/llms.txt",
+            payload_hash: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            amz_date: "20260101T000000Z",
+            region: "us-east-1",
+            credentials: &credentials,
+        });
+        assert!(authorization.starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20260101/us-east-1/s3/aws4_request, \
+             SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+        let signature = authorization.rsplit('=').next().unwrap();
+        assert_eq!(signature.len(), 64);
+    }
+
+    #[test]
+    fn test_s3_key_with_prefix() {
+        let credentials = Credentials {
+            access_key_id: "id".to_string(),
+            secret_access_key: "secret".to_string(),
+            session_token: None,
+        };
+        let publisher = S3Publisher::new(
+            Url::parse("http://localhost:9000").unwrap(),
+            "bucket".to_string(),
+            "us-east-1".to_string(),
+            "/llms/".to_string(),
+            credentials,
+        );
+        assert_eq!(
+            publisher.key(&Url::parse("https://example.com").unwrap()),
+            "llms/example.com/llms.txt"
+        );
+    }
+}
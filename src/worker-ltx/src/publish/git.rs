@@ -0,0 +1,282 @@
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+use super::{Publication, Publisher, object_key};
+use crate::errors::Error;
+
+/// HTTPS URL of the repository to commit llms.txt files to. Publishing to Git is enabled when this is set.
+pub const GIT_REPO_ENV_VAR: &str = "PUBLISH_GIT_REPO";
+/// Access token used to clone and push over HTTPS.
+pub const GIT_TOKEN_ENV_VAR: &str = "PUBLISH_GIT_TOKEN";
+/// User name sent with the token. GitHub uses `x-access-token`, GitLab `oauth2`.
+pub const GIT_USERNAME_ENV_VAR: &str = "PUBLISH_GIT_USERNAME";
+/// Branch that commits are pushed to. Created from the default branch if it doesn't exist.
+pub const GIT_BRANCH_ENV_VAR: &str = "PUBLISH_GIT_BRANCH";
+/// Directory in the repository that llms.txt files are written under.
+pub const GIT_PATH_ENV_VAR: &str = "PUBLISH_GIT_PATH";
+/// Local directory the repository is cloned into.
+pub const GIT_WORKDIR_ENV_VAR: &str = "PUBLISH_GIT_WORKDIR";
+
+/// A push can be rejected when another worker pushed first: start over from the new remote branch this many times.
+const PUSH_ATTEMPTS: usize = 3;
+
+/// Commits llms.txt files to a Git repository, one file per website (see `object_key`), e.g. for review
+/// by pull request. Runs the `git` CLI, which must be installed.
+pub struct GitPublisher {
+    repo_url: String,
+    username: String,
+    token: String,
+    branch: String,
+    path: String,
+    workdir: PathBuf,
+    /// The clone is shared: one publication at a time.
+    lock: Mutex<()>,
+}
+
+impl GitPublisher {
+    pub fn new(
+        repo_url: String,
+        username: String,
+        token: String,
+        branch: String,
+        path: String,
+        workdir: PathBuf,
+    ) -> Self {
+        Self {
+            repo_url,
+            username,
+            token,
+            branch,
+            path: path.trim_matches('/').to_string(),
+            workdir,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// None when `PUBLISH_GIT_REPO` isn't set. An error when it is, but the rest of the configuration is invalid.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+
+        let Some(repo_url) = var(GIT_REPO_ENV_VAR) else {
+            return Ok(None);
+        };
+        if !repo_url.starts_with("https://") {
+            return Err(format!("{} must be an https:// URL", GIT_REPO_ENV_VAR));
+        }
+        let Some(token) = var(GIT_TOKEN_ENV_VAR) else {
+            return Err(format!(
+                "{} is set: {} must be set too",
+                GIT_REPO_ENV_VAR, GIT_TOKEN_ENV_VAR
+            ));
+        };
+        Ok(Some(Self::new(
+            repo_url,
+            var(GIT_USERNAME_ENV_VAR).unwrap_or_else(|| "x-access-token".to_string()),
+            token,
+            var(GIT_BRANCH_ENV_VAR).unwrap_or_else(|| "llms-txt".to_string()),
+            var(GIT_PATH_ENV_VAR).unwrap_or_else(|| "llms-txt".to_string()),
+            var(GIT_WORKDIR_ENV_VAR)
+                .map(PathBuf::from)
+                .unwrap_or_else(|| std::env::temp_dir().join("llm-web-index-git-publish")),
+        )))
+    }
+
+    pub fn repo_url(&self) -> &str {
+        &self.repo_url
+    }
+
+    /// Path of the llms.txt of the publication's website, relative to the repository root.
+    fn file_path(&self, publication: &Publication<'_>) -> String {
+        if self.path.is_empty() {
+            object_key(publication.site_url)
+        } else {
+            format!("{}/{}", self.path, object_key(publication.site_url))
+        }
+    }
+
+    /// Runs git in `dir`, authenticating with the token. The token is passed in the environment,
+    /// so it's neither on the command line nor saved in the clone's config.
+    async fn git(&self, dir: &Path, args: &[&str]) -> Result<String, Error> {
+        let credentials = BASE64.encode(format!("{}:{}", self.username, self.token));
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .env("GIT_CONFIG_COUNT", "1")
+            .env("GIT_CONFIG_KEY_0", "http.extraHeader")
+            .env("GIT_CONFIG_VALUE_0", format!("Authorization: Basic {}", credentials))
+            .output()
+            .await
+            .map_err(|e| Error::PublishError(format!("Failed to run git: {}", e)))?;
+        if !output.status.success() {
+            return Err(Error::PublishError(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// Clones the repository on first use. Then checks out the latest commit of the branch, discarding local state.
+    async fn sync(&self) -> Result<(), Error> {
+        if !self.workdir.join(".git").exists() {
+            if let Some(parent) = self.workdir.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| Error::PublishError(format!("Failed to create {}: {}", parent.display(), e)))?;
+            }
+            let workdir = self.workdir.to_string_lossy();
+            let cwd = self.workdir.parent().unwrap_or(Path::new("."));
+            self.git(cwd, &["clone", "--quiet", &self.repo_url, &workdir]).await?;
+        }
+        let dir = self.workdir.as_path();
+        self.git(dir, &["fetch", "--quiet", "--prune", "origin"]).await?;
+        let remote_branch = format!("origin/{}", self.branch);
+        let start = if self
+            .git(dir, &["rev-parse", "--verify", "--quiet", &remote_branch])
+            .await
+            .is_ok()
+        {
+            remote_branch
+        } else {
+            // the branch is created from the default branch by the first push
+            "origin/HEAD".to_string()
+        };
+        self.git(dir, &["checkout", "--quiet", "--force", "-B", &self.branch, &start])
+            .await?;
+        self.git(dir, &["clean", "--quiet", "-fdx"]).await?;
+        Ok(())
+    }
+
+    /// Writes and commits the file. Returns false if it was already up to date.
+    async fn commit(&self, publication: &Publication<'_>, file_path: &str) -> Result<bool, Error> {
+        let dir = self.workdir.as_path();
+        let absolute = self.workdir.join(file_path);
+        if let Some(parent) = absolute.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| Error::PublishError(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        tokio::fs::write(&absolute, publication.llms_txt)
+            .await
+            .map_err(|e| Error::PublishError(format!("Failed to write {}: {}", absolute.display(), e)))?;
+
+        self.git(dir, &["add", "--", file_path]).await?;
+        let numstat = self
+            .git(dir, &["diff", "--cached", "--numstat", "--", file_path])
+            .await?;
+        let Some((added, removed)) = parse_numstat(&numstat) else {
+            return Ok(false);
+        };
+        let message = commit_message(publication, file_path, added, removed);
+        self.git(
+            dir,
+            &[
+                "-c",
+                "user.name=llm-web-index",
+                "-c",
+                "user.email=llm-web-index@localhost",
+                "commit",
+                "--quiet",
+                "-m",
+                &message,
+            ],
+        )
+        .await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Publisher for GitPublisher {
+    async fn publish(&self, publication: &Publication<'_>) -> Result<(), Error> {
+        let _lock = self.lock.lock().await;
+        let file_path = self.file_path(publication);
+        let refspec = format!("HEAD:refs/heads/{}", self.branch);
+
+        let mut attempt = 1;
+        loop {
+            self.sync().await?;
+            if !self.commit(publication, &file_path).await? {
+                tracing::debug!("'{}' is already up to date in '{}'", file_path, self.repo_url);
+                return Ok(());
+            }
+            match self.git(&self.workdir, &["push", "--quiet", "origin", &refspec]).await {
+                Ok(_) => {
+                    tracing::info!(
+                        "Committed llms.txt of '{}' to {} ({} on branch '{}')",
+                        publication.site_url,
+                        self.repo_url,
+                        file_path,
+                        self.branch
+                    );
+                    return Ok(());
+                }
+                Err(e) if attempt < PUSH_ATTEMPTS => {
+                    tracing::warn!("Push to '{}' failed, retrying: {}", self.repo_url, e);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Lines added and removed, from `git diff --numstat` of one file. None when there's no change.
+fn parse_numstat(numstat: &str) -> Option<(u64, u64)> {
+    let mut fields = numstat.lines().next()?.split_whitespace();
+    let added = fields.next()?.parse().ok()?;
+    let removed = fields.next()?.parse().ok()?;
+    Some((added, removed))
+}
+
+fn commit_message(publication: &Publication<'_>, file_path: &str, added: u64, removed: u64) -> String {
+    let host = publication.site_url.host_str().unwrap_or_default();
+    format!(
+        "Update llms.txt for {}\n\n{} ({} lines added, {} removed)\n\nSource: {}\nJob: {}\n",
+        host, file_path, added, removed, publication.site_url, publication.job_id
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use url::Url;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_parse_numstat() {
+        assert_eq!(parse_numstat("12\t3\tllms-txt/example.com/llms.txt\n"), Some((12, 3)));
+        assert_eq!(parse_numstat(""), None);
+    }
+
+    #[test]
+    fn test_commit_message_and_path() {
+        let site_url = Url::parse("https://example.com/docs").unwrap();
+        let publication = Publication {
+            job_id: Uuid::nil(),
+            site_url: &site_url,
+            llms_txt: "# Example",
+        };
+        let publisher = GitPublisher::new(
+            "https://github.com/example/docs.git".to_string(),
+            "x-access-token".to_string(),
+            "token".to_string(),
+            "llms-txt".to_string(),
+            "/llms-txt/".to_string(),
+            PathBuf::from("/tmp/unused"),
+        );
+        let file_path = publisher.file_path(&publication);
+        assert_eq!(file_path, "llms-txt/example.com/docs/llms.txt");
+
+        let message = commit_message(&publication, &file_path, 2, 1);
+        assert!(message.starts_with("Update llms.txt for example.com\n\n"));
+        assert!(message.contains("(2 lines added, 1 removed)"));
+        assert!(message.contains("Job: 00000000-0000-0000-0000-000000000000"));
+    }
+}
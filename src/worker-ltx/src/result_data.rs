@@ -0,0 +1,131 @@
+use std::sync::LazyLock;
+
+use prometheus::{IntCounterVec, register_int_counter_vec};
+
+use crate::errors::Error;
+
+/// Largest llms.txt stored. Real ones are a few KiB: anything near this is a runaway generation.
+pub const MAX_LLMS_TXT_BYTES: usize = 512 * 1024;
+/// Largest failure reason stored.
+pub const MAX_ERROR_BYTES: usize = 8 * 1024;
+
+/// Results cut down to size before being stored, by `kind` (`llms_txt` or `error`).
+pub static RESULTS_TRUNCATED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "worker_results_truncated_total",
+        "Results that were truncated to fit the size limit before being stored.",
+        &["kind"]
+    )
+    .expect("worker_results_truncated_total is registered once")
+});
+
+/// Text that's safe to store in `result_data`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounded {
+    pub text: String,
+    /// Bytes cut off the end to fit the limit.
+    pub truncated_bytes: usize,
+    /// Control characters that were removed.
+    pub removed_control_chars: usize,
+}
+
+/// Removes control characters other than tab & newlines, then truncates to at most `max_bytes`.
+/// With `whole_lines`, the text is cut after the last complete line that fits,
+/// so a truncated llms.txt doesn't end with half a link.
+pub fn bound_text(text: &str, max_bytes: usize, whole_lines: bool) -> Bounded {
+    let mut removed_control_chars = 0;
+    let mut cleaned: String = text
+        .chars()
+        .filter(|c| {
+            let keep = !c.is_control() || matches!(c, '\t' | '\n' | '\r');
+            if !keep {
+                removed_control_chars += 1;
+            }
+            keep
+        })
+        .collect();
+
+    let mut truncated_bytes = 0;
+    if cleaned.len() > max_bytes {
+        let mut end = max_bytes;
+        while !cleaned.is_char_boundary(end) {
+            end -= 1;
+        }
+        if whole_lines && let Some(newline) = cleaned[..end].rfind('\n') {
+            end = newline + 1;
+        }
+        truncated_bytes = cleaned.len() - end;
+        cleaned.truncate(end);
+    }
+
+    Bounded {
+        text: cleaned,
+        truncated_bytes,
+        removed_control_chars,
+    }
+}
+
+/// The llms.txt as it's stored (and published). Rejected if it contains null bytes:
+/// Postgres can't store them in text, and an llms.txt with binary content is broken anyway.
+pub fn bound_llms_txt(llms_txt: &str) -> Result<Bounded, Error> {
+    if llms_txt.contains('\0') {
+        return Err(Error::InvalidResultData(
+            "the generated llms.txt contains null bytes".to_string(),
+        ));
+    }
+    let bounded = bound_text(llms_txt, MAX_LLMS_TXT_BYTES, true);
+    if bounded.truncated_bytes > 0 {
+        RESULTS_TRUNCATED.with_label_values(&["llms_txt"]).inc();
+    }
+    Ok(bounded)
+}
+
+/// The failure reason as it's stored. Null bytes are removed with the other control characters,
+/// and a truncated reason says so at the end.
+pub fn bound_error(reason: &str) -> Bounded {
+    let mut bounded = bound_text(reason, MAX_ERROR_BYTES, false);
+    if bounded.truncated_bytes > 0 {
+        RESULTS_TRUNCATED.with_label_values(&["error"]).inc();
+        bounded
+            .text
+            .push_str(&format!(" ... [truncated {} bytes]", bounded.truncated_bytes));
+    }
+    bounded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bound_text_strips_control_chars() {
+        let bounded = bound_text("# Title\u{0}\u{1b}[31m\n\t- item\r\n\u{7f}", 100, true);
+        assert_eq!(bounded.text, "# Title[31m\n\t- item\r\n");
+        assert_eq!(bounded.removed_control_chars, 3);
+        assert_eq!(bounded.truncated_bytes, 0);
+    }
+
+    #[test]
+    fn test_bound_text_truncates() {
+        // at a line boundary
+        let bounded = bound_text("# A\n- one\n- two\n", 12, true);
+        assert_eq!(bounded.text, "# A\n- one\n");
+        assert_eq!(bounded.truncated_bytes, 6);
+
+        // never in the middle of a character
+        let bounded = bound_text("ééé", 3, false);
+        assert_eq!(bounded.text, "é");
+        assert_eq!(bounded.truncated_bytes, 4);
+    }
+
+    #[test]
+    fn test_bound_llms_txt_and_error() {
+        assert!(matches!(bound_llms_txt("# A\0"), Err(Error::InvalidResultData(_))));
+        assert_eq!(bound_llms_txt("# A\n").unwrap().text, "# A\n");
+
+        let reason = "x".repeat(MAX_ERROR_BYTES + 10);
+        let bounded = bound_error(&reason);
+        assert!(bounded.text.ends_with(" ... [truncated 10 bytes]"));
+        assert_eq!(bound_error("bad\0 byte").text, "bad byte");
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use core_ltx::{
+    TimeUnit,
+    common::{
+        max_concurrency::{self, MaxConcurrencyError},
+        poll_interval::poll_interval,
+    },
+    daily_token_budget,
+    llms::{chatgpt::DEFAULT_MODEL, find_model},
+};
+use tokio::sync::Semaphore;
+use url::Url;
+
+use crate::post_process::{Hook, POST_PROCESS_HOOKS_ENV_VAR, parse_hooks};
+
+/// Worker configuration that can be changed while the worker is running (see `reload_on_sighup`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerSettings {
+    /// How long to wait between checks for new jobs.
+    pub poll_interval: Duration,
+    /// Maximum number of jobs worked on at once.
+    pub max_concurrency: usize,
+    /// Maximum number of LLM tokens to use per day (UTC), if limited.
+    pub daily_token_budget: Option<u64>,
+    /// LLM used to generate llms.txt files.
+    pub model_name: String,
+    /// Domains whose `noindex`/`noai` directives are ignored, e.g. because the publisher agreed to be indexed.
+    pub ignore_noindex_domains: Vec<String>,
+    /// Hooks applied to every generated llms.txt before it's stored, in order (see `post_process`).
+    pub post_process_hooks: Vec<Hook>,
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(600),
+            max_concurrency: max_concurrency::DEFAULT,
+            daily_token_budget: None,
+            model_name: DEFAULT_MODEL.to_string(),
+            ignore_noindex_domains: Vec::new(),
+            post_process_hooks: Vec::new(),
+        }
+    }
+}
+
+impl WorkerSettings {
+    /// Reads the settings from `WORKER_POLL_INTERVAL_MS`, `WORKER_MAX_CONCURRENCY`, `DAILY_TOKEN_BUDGET`,
+    /// `WORKER_LLM_MODEL`, `IGNORE_NOINDEX_DOMAINS` (comma-separated), and `POST_PROCESS_HOOKS` (comma-separated).
+    pub fn from_env() -> Result<Self, String> {
+        let poll_interval = poll_interval(TimeUnit::Milliseconds, "WORKER_POLL_INTERVAL_MS", 600)
+            .map_err(|_| "WORKER_POLL_INTERVAL_MS must be a valid number".to_string())?;
+        let max_concurrency = match max_concurrency::max_concurrency() {
+            Ok(0) => return Err(MaxConcurrencyError::NonPositive.to_string()),
+            Ok(v) => v,
+            Err(MaxConcurrencyError::MissingEnvVar(_)) => max_concurrency::DEFAULT,
+            Err(e) => return Err(e.to_string()),
+        };
+        let model_name = std::env::var("WORKER_LLM_MODEL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string());
+        match find_model(&model_name) {
+            None => tracing::warn!("WORKER_LLM_MODEL '{}' is not one of the supported models", model_name),
+            Some(info) if info.deprecated => tracing::warn!(
+                "WORKER_LLM_MODEL '{}' is deprecated: llms.txt files generated with it should be re-generated",
+                model_name
+            ),
+            Some(_) => {}
+        }
+        let ignore_noindex_domains = std::env::var("IGNORE_NOINDEX_DOMAINS")
+            .map(|v| parse_domains(&v))
+            .unwrap_or_default();
+        let post_process_hooks = match std::env::var(POST_PROCESS_HOOKS_ENV_VAR) {
+            Ok(value) => parse_hooks(&value).map_err(|e| format!("{}: {}", POST_PROCESS_HOOKS_ENV_VAR, e))?,
+            Err(_) => Vec::new(),
+        };
+        Ok(Self {
+            poll_interval,
+            max_concurrency,
+            daily_token_budget: daily_token_budget()?,
+            model_name,
+            ignore_noindex_domains,
+            post_process_hooks,
+        })
+    }
+
+    /// True when the website's `noindex`/`noai` directives are to be ignored.
+    /// A domain also covers its subdomains: `example.com` covers `docs.example.com`.
+    pub fn ignores_noindex(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str().map(|h| h.to_ascii_lowercase()) else {
+            return false;
+        };
+        self.ignore_noindex_domains.iter().any(|domain| {
+            host == *domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|subdomain| subdomain.ends_with('.'))
+        })
+    }
+}
+
+fn parse_domains(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|domain| {
+            domain
+                .trim()
+                .trim_start_matches("*.")
+                .trim_end_matches('.')
+                .to_ascii_lowercase()
+        })
+        .filter(|domain| !domain.is_empty())
+        .collect()
+}
+
+/// Changes the number of jobs that can run at once from `from` to `to`.
+///
+/// Growing takes effect immediately. Shrinking never interrupts running jobs: the excess permits are
+/// taken out of circulation as those jobs finish.
+pub fn resize_semaphore(semaphore: &Arc<Semaphore>, from: usize, to: usize) {
+    if to > from {
+        semaphore.add_permits(to - from);
+    } else if to < from {
+        let semaphore = semaphore.clone();
+        let excess = (from - to) as u32;
+        tokio::spawn(async move {
+            if let Ok(permits) = semaphore.acquire_many_owned(excess).await {
+                permits.forget();
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lets the task spawned by `resize_semaphore` run.
+    async fn settle() {
+        for _ in 0..3 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resize_semaphore() {
+        let semaphore = Arc::new(Semaphore::new(4));
+        resize_semaphore(&semaphore, 4, 6);
+        assert_eq!(semaphore.available_permits(), 6);
+
+        // two jobs are running while shrinking: they keep their permits until they finish
+        let first = semaphore.clone().acquire_owned().await.unwrap();
+        let second = semaphore.clone().acquire_owned().await.unwrap();
+        resize_semaphore(&semaphore, 6, 1);
+        settle().await;
+        assert_eq!(semaphore.available_permits(), 0);
+
+        drop(first);
+        settle().await;
+        assert_eq!(semaphore.available_permits(), 0);
+
+        drop(second);
+        settle().await;
+        assert_eq!(semaphore.available_permits(), 1);
+    }
+
+    #[test]
+    fn test_ignores_noindex() {
+        let settings = WorkerSettings {
+            ignore_noindex_domains: parse_domains(" Example.com, *.docs.org ,"),
+            ..Default::default()
+        };
+        assert_eq!(settings.ignore_noindex_domains, vec!["example.com", "docs.org"]);
+        let ignores = |url: &str| settings.ignores_noindex(&Url::parse(url).unwrap());
+        assert!(ignores("https://example.com/about"));
+        assert!(ignores("https://www.EXAMPLE.com"));
+        assert!(ignores("https://api.docs.org"));
+        assert!(!ignores("https://notexample.com"));
+        assert!(!ignores("https://example.com.evil.net"));
+    }
+}
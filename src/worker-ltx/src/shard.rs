@@ -0,0 +1,89 @@
+use std::str::FromStr;
+
+/// One of `count` disjoint slices of the job queue: a worker on shard `index` only claims jobs whose
+/// `hashtext(url_normalized) % count == index`. Workers on different shards never compete for the same rows,
+/// and all jobs for a URL go to the same shard, however it's spelled (see `data_model_ltx::urls`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    index: u32,
+    count: u32,
+}
+
+impl Shard {
+    pub fn new(index: u32, count: u32) -> Result<Self, String> {
+        if count == 0 {
+            return Err("the number of shards must be at least 1".to_string());
+        }
+        if index >= count {
+            return Err(format!(
+                "shard {} doesn't exist: shards are numbered 0 to {}",
+                index,
+                count - 1
+            ));
+        }
+        Ok(Self { index, count })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// SQL condition on `job_state.url_normalized` that's true for this shard's jobs.
+    /// `hashtext` can be negative, so the remainder is shifted into `0..count`.
+    pub fn sql_filter(&self) -> String {
+        format!(
+            "(((hashtext(job_state.url_normalized) % {count}) + {count}) % {count}) = {index}",
+            count = self.count,
+            index = self.index
+        )
+    }
+}
+
+impl FromStr for Shard {
+    type Err = String;
+
+    /// Parses `k/n`, e.g. `0/4` for the first of four shards.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, count) = s
+            .split_once('/')
+            .ok_or_else(|| format!("invalid shard '{}': expected k/n, e.g. 0/4", s))?;
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|_| format!("invalid shard '{}': expected k/n, e.g. 0/4", s))
+        };
+        Self::new(parse(index)?, parse(count)?)
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_shard() {
+        let shard: Shard = " 1/4".parse().unwrap();
+        assert_eq!((shard.index(), shard.count()), (1, 4));
+        assert_eq!(shard.to_string(), "1/4");
+        assert!("4/4".parse::<Shard>().is_err());
+        assert!("0/0".parse::<Shard>().is_err());
+        assert!("1".parse::<Shard>().is_err());
+        assert!("-1/4".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn test_sql_filter() {
+        let shard = Shard::new(2, 3).unwrap();
+        assert_eq!(shard.sql_filter(), "(((hashtext(job_state.url_normalized) % 3) + 3) % 3) = 2");
+    }
+}
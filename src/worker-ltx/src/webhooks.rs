@@ -0,0 +1,362 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use core_ltx::db;
+use data_model_ltx::{
+    models::{DeliveryStatus, JobState, Webhook, WebhookDelivery, WebhookDeliveryAttempt},
+    schema,
+    secrets::Keyring,
+};
+use diesel::prelude::*;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde_json::json;
+use sha2::Sha256;
+
+use crate::errors::Error;
+use crate::publish::hex;
+
+/// Header with the signature of a delivery: `t=<unix timestamp>,v1=<hex HMAC-SHA256 of "<timestamp>.<body>">`,
+/// keyed with the webhook's secret.
+pub const SIGNATURE_HEADER: &str = "X-Llms-Signature";
+/// Header with the name of the delivered event, e.g. `job.finished`.
+pub const EVENT_HEADER: &str = "X-Llms-Event";
+/// Header with the id of the delivery: it's the same on every retry, so receivers can ignore duplicates.
+pub const DELIVERY_HEADER: &str = "X-Llms-Delivery";
+
+/// Event sent once a job succeeded or failed.
+pub const JOB_FINISHED_EVENT: &str = "job.finished";
+
+/// Attempts made at a delivery before it's marked failed.
+pub const MAX_ATTEMPTS: i32 = 8;
+/// Deliveries claimed by one worker at once.
+const BATCH_SIZE: i64 = 20;
+/// How long to wait for the webhook's response.
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// How long claimed deliveries are left alone by the other workers: longer than a whole batch can take, since they're
+/// attempted one after the other, each for up to `TIMEOUT`, then a minute to spare.
+const CLAIM_LEASE: chrono::Duration = chrono::Duration::seconds(BATCH_SIZE * TIMEOUT.as_secs() as i64 + 60);
+/// Characters of the response body kept in a failed attempt's error.
+const MAX_ERROR_CHARS: usize = 500;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The signature header value of the body, sent at `timestamp` (seconds since the epoch).
+/// The timestamp is signed too, so receivers can reject old deliveries that are replayed.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("t={},v1={}", timestamp, hex(&mac.finalize().into_bytes()))
+}
+
+/// How long to wait before retrying after the `attempts`-th failed attempt: 30 seconds, doubling with every attempt,
+/// up to 6 hours. Retries span about a day before the delivery fails for good.
+pub fn retry_delay(attempts: i32) -> chrono::Duration {
+    let exponent = attempts.clamp(1, 20) - 1;
+    let delay = chrono::Duration::seconds(30 * 2_i64.pow(exponent as u32));
+    delay.min(chrono::Duration::hours(6))
+}
+
+/// Queues a `job.finished` event for every webhook, once the job's result is stored.
+/// Returns the number of deliveries queued.
+pub async fn enqueue_job_finished(pool: &db::DbPool, job: &JobState, failure: Option<&str>) -> Result<usize, Error> {
+    let mut conn = pool.get().await?;
+    let webhooks = schema::webhooks::table
+        .select(schema::webhooks::webhook_id)
+        .load::<uuid::Uuid>(&mut conn)
+        .await?;
+    if webhooks.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = json!({
+        "event": JOB_FINISHED_EVENT,
+        "job_id": job.job_id,
+        "url": job.url,
+        "kind": job.kind,
+        "status": if failure.is_some() { "Failure" } else { "Success" },
+        "failure": failure,
+    })
+    .to_string();
+    let now = Utc::now();
+    let deliveries: Vec<WebhookDelivery> = webhooks
+        .into_iter()
+        .map(|webhook_id| WebhookDelivery::new(webhook_id, JOB_FINISHED_EVENT, payload.clone(), now))
+        .collect();
+    let queued = diesel::insert_into(schema::webhook_deliveries::table)
+        .values(&deliveries)
+        .execute(&mut conn)
+        .await?;
+    Ok(queued)
+}
+
+/// Claims the pending deliveries that are due and attempts each of them once.
+/// Returns the number of attempts made.
+///
+/// Claiming pushes their `next_attempt_at` back by a lease, so other workers don't attempt them at the same time.
+/// A worker that dies mid-attempt leaves the delivery to be retried once the lease is over. Should a worker still be
+/// attempting a delivery when its lease is over, only the first attempt recorded counts (see `record_attempt`).
+pub async fn deliver_due_webhooks(pool: &db::DbPool, client: &Client, keyring: &Keyring) -> Result<usize, Error> {
+    let mut conn = pool.get().await?;
+    let now = Utc::now();
+    let claimed: Vec<(WebhookDelivery, Webhook)> = conn
+        .transaction::<_, Error, _>(|conn| {
+            Box::pin(async move {
+                let due = schema::webhook_deliveries::table
+                    .filter(schema::webhook_deliveries::status.eq(DeliveryStatus::Pending.as_str()))
+                    .filter(schema::webhook_deliveries::next_attempt_at.le(now))
+                    .order(schema::webhook_deliveries::next_attempt_at.asc())
+                    .limit(BATCH_SIZE)
+                    .select(WebhookDelivery::as_select())
+                    .for_update()
+                    .skip_locked()
+                    .load::<WebhookDelivery>(conn)
+                    .await?;
+                if due.is_empty() {
+                    return Ok(Vec::new());
+                }
+                let ids: Vec<uuid::Uuid> = due.iter().map(|delivery| delivery.delivery_id).collect();
+                diesel::update(
+                    schema::webhook_deliveries::table.filter(schema::webhook_deliveries::delivery_id.eq_any(&ids)),
+                )
+                .set(schema::webhook_deliveries::next_attempt_at.eq(now + CLAIM_LEASE))
+                .execute(conn)
+                .await?;
+
+                let webhook_ids: Vec<uuid::Uuid> = due.iter().map(|delivery| delivery.webhook_id).collect();
+                let webhooks = schema::webhooks::table
+                    .filter(schema::webhooks::webhook_id.eq_any(&webhook_ids))
+                    .select(Webhook::as_select())
+                    .load::<Webhook>(conn)
+                    .await?;
+                Ok(due
+                    .into_iter()
+                    .filter_map(|delivery| {
+                        let webhook = webhooks
+                            .iter()
+                            .find(|webhook| webhook.webhook_id == delivery.webhook_id)?;
+                        Some((delivery, webhook.clone()))
+                    })
+                    .collect())
+            })
+        })
+        .await?;
+
+    let attempted = claimed.len();
+    for (delivery, webhook) in claimed {
+        let attempt = attempt_delivery(client, keyring, &webhook, &delivery).await;
+        match record_attempt(pool, &delivery, attempt).await {
+            Ok(true) => {}
+            Ok(false) => tracing::warn!(
+                "Delivery {} to '{}' was attempted by another worker meanwhile: not recording this attempt",
+                delivery.delivery_id,
+                webhook.url
+            ),
+            Err(error) => tracing::error!(
+                "[SKIP] Failed to record the attempt at delivery {} to '{}': {}",
+                delivery.delivery_id,
+                webhook.url,
+                error
+            ),
+        }
+    }
+    Ok(attempted)
+}
+
+/// POSTs the delivery's payload to the webhook, signed with its secret.
+/// Returns the response's status code (None without a response) and what went wrong, if anything.
+async fn attempt_delivery(
+    client: &Client,
+    keyring: &Keyring,
+    webhook: &Webhook,
+    delivery: &WebhookDelivery,
+) -> (Option<i32>, Option<String>) {
+    let secret = match webhook.decrypt_secret(keyring) {
+        Ok(secret) => secret,
+        Err(error) => return (None, Some(format!("Can't decrypt the webhook's secret: {}", error))),
+    };
+    let signature = sign(&secret, Utc::now().timestamp(), &delivery.payload);
+    let response = client
+        .post(&webhook.url)
+        .timeout(TIMEOUT)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(SIGNATURE_HEADER, signature)
+        .header(EVENT_HEADER, &delivery.event)
+        .header(DELIVERY_HEADER, delivery.delivery_id.to_string())
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i32), None),
+        Ok(response) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            let error = if body.trim().is_empty() {
+                format!("HTTP {}", status)
+            } else {
+                format!(
+                    "HTTP {}: {}",
+                    status,
+                    body.chars().take(MAX_ERROR_CHARS).collect::<String>()
+                )
+            };
+            (Some(status.as_u16() as i32), Some(error))
+        }
+        Err(error) => (None, Some(error.to_string())),
+    }
+}
+
+/// Stores the attempt, then marks the delivery delivered, schedules its retry, or gives up on it.
+///
+/// `delivery` is the delivery as it was claimed. Returns false, without storing anything, when another worker recorded
+/// an attempt at it since: its lease was over, so the other worker claimed it too.
+pub async fn record_attempt(
+    pool: &db::DbPool,
+    delivery: &WebhookDelivery,
+    (status_code, error): (Option<i32>, Option<String>),
+) -> Result<bool, Error> {
+    let now = Utc::now();
+    let attempts = delivery.attempts + 1;
+    let (status, next_attempt_at, delivered_at) = next_state(attempts, error.is_none(), now);
+    match &error {
+        None => tracing::debug!("Delivered {} to webhook {}", delivery.delivery_id, delivery.webhook_id),
+        Some(error) if status == DeliveryStatus::Failed => tracing::error!(
+            "Giving up on delivery {} to webhook {} after {} attempts: {}",
+            delivery.delivery_id,
+            delivery.webhook_id,
+            attempts,
+            error
+        ),
+        Some(error) => tracing::warn!(
+            "Attempt {} at delivery {} to webhook {} failed, retrying at {}: {}",
+            attempts,
+            delivery.delivery_id,
+            delivery.webhook_id,
+            next_attempt_at,
+            error
+        ),
+    }
+
+    let mut conn = pool.get().await?;
+    let attempt = WebhookDeliveryAttempt {
+        delivery_id: delivery.delivery_id,
+        attempt: attempts,
+        status_code,
+        error,
+        attempted_at: now,
+    };
+    let claimed_attempts = delivery.attempts;
+    conn.transaction::<_, Error, _>(|conn| {
+        Box::pin(async move {
+            // only while no other attempt was recorded since it was claimed
+            let updated = diesel::update(
+                schema::webhook_deliveries::table
+                    .find(attempt.delivery_id)
+                    .filter(schema::webhook_deliveries::attempts.eq(claimed_attempts)),
+            )
+            .set((
+                schema::webhook_deliveries::status.eq(status.as_str()),
+                schema::webhook_deliveries::attempts.eq(attempts),
+                schema::webhook_deliveries::next_attempt_at.eq(next_attempt_at),
+                schema::webhook_deliveries::delivered_at.eq(delivered_at),
+            ))
+            .execute(conn)
+            .await?;
+            if updated == 0 {
+                return Ok(false);
+            }
+            diesel::insert_into(schema::webhook_delivery_attempts::table)
+                .values(&attempt)
+                .execute(conn)
+                .await?;
+            Ok(true)
+        })
+    })
+    .await
+}
+
+/// Status, next attempt time, and delivery time of a delivery after its `attempts`-th attempt.
+fn next_state(
+    attempts: i32,
+    succeeded: bool,
+    now: DateTime<Utc>,
+) -> (DeliveryStatus, DateTime<Utc>, Option<DateTime<Utc>>) {
+    if succeeded {
+        (DeliveryStatus::Delivered, now, Some(now))
+    } else if attempts >= MAX_ATTEMPTS {
+        (DeliveryStatus::Failed, now, None)
+    } else {
+        (DeliveryStatus::Pending, now + retry_delay(attempts), None)
+    }
+}
+
+/// Delivers webhook events until the worker stops, checking for due deliveries every `poll_interval`.
+/// Failures to reach the database are logged: it's checked again on the next poll.
+pub async fn webhook_delivery_loop(pool: db::DbPool, keyring: Keyring, poll_interval: Duration) {
+    let client = Client::new();
+    loop {
+        match deliver_due_webhooks(&pool, &client, &keyring).await {
+            // a full batch: there may be more due already
+            Ok(attempted) if attempted as i64 == BATCH_SIZE => continue,
+            Ok(_) => {}
+            Err(error) => tracing::error!("[SKIP] Failed to deliver webhooks: {}", error),
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        let signature = sign("whsec_test", 1_700_000_000, r#"{"event":"job.finished"}"#);
+        let (timestamp, digest) = signature.split_once(",v1=").unwrap();
+        assert_eq!(timestamp, "t=1700000000");
+        assert_eq!(digest.len(), 64);
+        // the same input signs the same, a different secret, time, or body doesn't
+        assert_eq!(
+            signature,
+            sign("whsec_test", 1_700_000_000, r#"{"event":"job.finished"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign("whsec_other", 1_700_000_000, r#"{"event":"job.finished"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign("whsec_test", 1_700_000_001, r#"{"event":"job.finished"}"#)
+        );
+        assert_ne!(
+            signature,
+            sign("whsec_test", 1_700_000_000, r#"{"event":"job.started"}"#)
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1), chrono::Duration::seconds(30));
+        assert_eq!(retry_delay(2), chrono::Duration::seconds(60));
+        assert_eq!(retry_delay(3), chrono::Duration::seconds(120));
+        assert_eq!(retry_delay(MAX_ATTEMPTS), chrono::Duration::seconds(30 * 128));
+        assert_eq!(retry_delay(30), chrono::Duration::hours(6));
+    }
+
+    #[test]
+    fn test_next_state() {
+        let now = Utc::now();
+        assert_eq!(next_state(1, true, now), (DeliveryStatus::Delivered, now, Some(now)));
+        assert_eq!(
+            next_state(1, false, now),
+            (DeliveryStatus::Pending, now + chrono::Duration::seconds(30), None)
+        );
+        assert_eq!(
+            next_state(MAX_ATTEMPTS, false, now),
+            (DeliveryStatus::Failed, now, None)
+        );
+    }
+}
@@ -1,32 +1,66 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use core_ltx::{
-    compress_string, download, is_valid_url,
-    llms::{LlmProvider, generate_llms_txt, update_llms_txt},
+    Disallowed, FailureKind, JobLog, Page, SiteMetadata, compress_string,
+    crawl::{CrawlOptions, combine_pages, crawl},
+    credentials::set_domain_credentials,
+    domain_policy::{DownloadGuard, private_hosts_allowed, set_download_guard},
+    download, download_page, extract_site_metadata, fetch_origin_llms_txt, find_disallowing_directive, is_valid_url,
+    llms::{LlmProvider, TokenUsage, generate_llms_txt, regenerate_section, track_usage, update_llms_txt},
     normalize_html,
+    notify::{Notification, Notifications, Outage},
+    set_parent_from_trace_context, take_job_log,
     web_html::compute_html_checksum,
+    with_error_context,
 };
 
+use chrono::{NaiveTime, Utc};
 use core_ltx::db;
 use data_model_ltx::{
-    models::{JobKindData, JobState, JobStatus, LlmsTxt, LlmsTxtResult},
+    changes::notify_llms_txt_changed,
+    domain_policy::load_download_guard,
+    models::{
+        DomainCredential, JobKindData, JobLogs, JobState, JobStatus, LlmUsage, LlmsTxt, LlmsTxtResult, LlmsTxtSource,
+    },
     schema,
+    search::index_llms_txt,
+    secrets::{Keyring, SecretsError},
 };
+use diesel::dsl::sql;
 use diesel::prelude::*;
-use diesel_async::{AsyncConnection, RunQueryDsl};
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use diesel::sql_types::{BigInt, Bool};
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, watch};
+use tracing::Instrument;
+use url::Url;
+use uuid::Uuid;
 
+use crate::archive::WarcArchive;
+use crate::cancellation::{RunningJobs, is_job_cancelled, poll_cancelled_jobs};
 use crate::errors::Error;
+use crate::metrics::{JOB_DURATION, JOBS_RUNNING, stage, time_stage};
+use crate::post_process::{Hook, post_process};
+use crate::publish::Publishers;
+use crate::result_data::{Bounded, MAX_LLMS_TXT_BYTES, bound_error, bound_llms_txt};
+use crate::settings::{WorkerSettings, resize_semaphore};
+use crate::shard::Shard;
+use crate::webhooks::enqueue_job_finished;
 
 /// Result of job processing that preserves HTML through error paths
 pub enum JobResult {
     /// Both HTML download and llms.txt generation succeeded.
     /// html_compress contains Brotli-compressed normalized HTML bytes.
     /// html_checksum is the MD5 checksum of the normalized (pre-compression) HTML.
+    /// `source` says whether the LLM generated the llms.txt or it was imported from the website.
+    /// `metadata` is what the page says about itself (title, description, ...).
     Success {
         html_compress: Vec<u8>,
         html_checksum: String,
         llms_txt: core_ltx::LlmsTxt,
+        source: LlmsTxtSource,
+        metadata: SiteMetadata,
     },
     /// HTML downloaded successfully but llms.txt generation failed.
     /// html_compress contains Brotli-compressed normalized HTML bytes.
@@ -40,6 +74,21 @@ pub enum JobResult {
     DownloadFailed { error: Error },
     /// HTML normalization or compression failed (no HTML to store)
     HtmlProcessingFailed { error: Error },
+    /// The publisher asked not to be indexed (no HTML to store)
+    Disallowed { error: Error },
+}
+
+impl JobResult {
+    /// The reason the job failed, if it did.
+    pub fn error(&self) -> Option<&Error> {
+        match self {
+            JobResult::Success { .. } => None,
+            JobResult::GenerationFailed { error, .. }
+            | JobResult::DownloadFailed { error }
+            | JobResult::HtmlProcessingFailed { error }
+            | JobResult::Disallowed { error } => Some(error),
+        }
+    }
 }
 
 /// Query the DB for a job to be performed.
@@ -47,8 +96,20 @@ pub enum JobResult {
 pub async fn next_job_in_queue(
     pool: &db::DbPool,
     semaphore: Arc<Semaphore>,
+) -> Result<(JobState, OwnedSemaphorePermit), Error> {
+    next_job_in_shard(pool, semaphore, None).await
+}
+
+/// Like `next_job_in_queue`, but only claims jobs of the `shard`, if given.
+pub async fn next_job_in_shard(
+    pool: &db::DbPool,
+    semaphore: Arc<Semaphore>,
+    shard: Option<Shard>,
 ) -> Result<(JobState, OwnedSemaphorePermit), Error> {
     let mut conn = pool.get().await?;
+    let shard_filter = shard
+        .map(|shard| shard.sql_filter())
+        .unwrap_or_else(|| "TRUE".to_string());
 
     let job_permit: (JobState, OwnedSemaphorePermit) = conn
         .transaction::<_, Error, _>(|conn| {
@@ -66,18 +127,30 @@ pub async fn next_job_in_queue(
                 // Order by created_at first (oldest first) for FIFO processing, then by job_id for consistent tie-breaking.
                 let job: JobState = schema::job_state::table
                     .filter(schema::job_state::status.eq(JobStatus::Queued))
+                    .filter(sql::<Bool>(&shard_filter))
+                    // a job can expire after `expire_overdue_jobs` ran: it's picked up on the next poll
+                    .filter(
+                        schema::job_state::expires_at
+                            .is_null()
+                            .or(schema::job_state::expires_at.gt(Utc::now())),
+                    )
                     .for_update()
                     .skip_locked()
                     // we order first by created_at, getting oldest first
                     // => this ensures we're doing FIFO processing & that we don't starve-out any jobs
                     // we break ties by sorting on the job ID (which provides a consistent ordering)
                     .order((schema::job_state::created_at.asc(), schema::job_state::job_id.asc()))
+                    .select(JobState::as_select())
                     .first::<JobState>(conn)
                     .await?;
 
                 // if we have such a job, make sure we mark it as running as this worker has claimed it
+                // (since when tells a job whose worker died: see POST /api/admin/jobs/purge_stale)
                 diesel::update(schema::job_state::table.find(job.job_id))
-                    .set(schema::job_state::status.eq(JobStatus::Running))
+                    .set((
+                        schema::job_state::status.eq(JobStatus::Running),
+                        schema::job_state::started_at.eq(Utc::now()),
+                    ))
                     .execute(conn)
                     .await?;
 
@@ -96,68 +169,226 @@ pub async fn next_job_in_queue(
     Ok(job_permit)
 }
 
+/// Marks queued jobs whose deadline (`expires_at`) has passed as Expired, so they're never worked on.
+/// Returns the number of jobs that expired.
+pub async fn expire_overdue_jobs(pool: &db::DbPool) -> Result<usize, Error> {
+    let mut conn = pool.get().await?;
+    let expired = diesel::update(
+        schema::job_state::table
+            .filter(schema::job_state::status.eq(JobStatus::Queued))
+            .filter(schema::job_state::expires_at.lt(Utc::now())),
+    )
+    .set(schema::job_state::status.eq(JobStatus::Expired))
+    .execute(&mut conn)
+    .await?;
+    Ok(expired)
+}
+
+/// Has the downloads authenticate with the credentials of the `domains` table, decrypted with the `keyring`.
+/// Returns the domains whose credential is in use and the ones whose credential couldn't be decrypted (all of them
+/// without a key), in domain order.
+pub async fn load_domain_credentials(
+    pool: &db::DbPool,
+    keyring: Option<&Keyring>,
+) -> Result<(Vec<String>, Vec<String>), Error> {
+    let mut conn = pool.get().await?;
+    let domains = schema::domains::table
+        .order(schema::domains::domain.asc())
+        .select(DomainCredential::as_select())
+        .load::<DomainCredential>(&mut conn)
+        .await?;
+    let mut credentials = HashMap::new();
+    let mut undecryptable = Vec::new();
+    for domain in domains {
+        match keyring.map(|keyring| domain.decrypt(keyring)) {
+            Some(Ok(credential)) => {
+                credentials.insert(domain.domain, credential);
+            }
+            Some(Err(error)) => {
+                tracing::debug!("Can't decrypt the credential of '{}': {}", domain.domain, error);
+                undecryptable.push(domain.domain);
+            }
+            None => undecryptable.push(domain.domain),
+        }
+    }
+    let mut loaded: Vec<String> = credentials.keys().cloned().collect();
+    loaded.sort();
+    set_domain_credentials(credentials);
+    Ok((loaded, undecryptable))
+}
+
+/// Has the downloads check the URL of every request, redirects included, with the `domain_policy` table and
+/// `ALLOW_PRIVATE_HOSTS`. Returns the guard they're checked with.
+pub async fn guard_downloads(pool: &db::DbPool) -> Result<DownloadGuard, Error> {
+    let mut conn = pool.get().await?;
+    let guard = load_download_guard(&mut conn).await?;
+    set_download_guard(guard.clone());
+    Ok(guard)
+}
+
 /// Downloads HTML and attempts to generate llms.txt.
 /// Returns JobResult to preserve HTML even on generation failure.
-pub async fn handle_job<P: LlmProvider>(provider: &P, job: &JobState) -> JobResult {
+///
+/// Pages with a `noindex`, `none`, `noai` or `noimageai` robots directive (meta tag or `X-Robots-Tag` header)
+/// aren't sent to the LLM, unless their domain is in the `settings`' `ignore_noindex_domains`.
+///
+/// If the website publishes a valid llms.txt at its root (`/llms.txt`), it's imported instead of generating one.
+pub async fn handle_job<P: LlmProvider>(provider: &P, job: &JobState, settings: &WorkerSettings) -> JobResult {
+    handle_job_archived(provider, job, settings, None).await
+}
+
+/// Like `handle_job`, but the downloaded page is also stored in the `archive`, if there's one.
+/// Archiving failures are logged: the job goes on without it.
+pub async fn handle_job_archived<P: LlmProvider>(
+    provider: &P,
+    job: &JobState,
+    settings: &WorkerSettings,
+    archive: Option<&WarcArchive>,
+) -> JobResult {
     // Validate URL
+    let validate_timer = time_stage(stage::VALIDATE);
     let url = match is_valid_url(&job.url) {
         Ok(u) => u,
         Err(e) => return JobResult::DownloadFailed { error: e.into() },
     };
+    validate_timer.observe_duration();
     tracing::debug!("[job: {}] Valid URL: {}", job.job_id, url);
 
     // Download HTML - if this fails, return immediately
-    let html = match download(&url).await {
-        Ok(h) => h,
+    let download_timer = time_stage(stage::DOWNLOAD);
+    let page = match download_page(&url).instrument(tracing::info_span!("download")).await {
+        Ok(p) => p,
         Err(e) => return JobResult::DownloadFailed { error: e.into() },
     };
-    tracing::debug!("[job: {}] Downloaded HTML ({} bytes)", job.job_id, html.len());
-
-    // Normalize HTML - if this fails, return immediately
-    let normalized = match normalize_html(&html) {
-        Ok(h) => h,
-        Err(e) => {
-            tracing::error!("[job: {}] Failed to normalize HTML: {}", job.job_id, e);
-            return JobResult::HtmlProcessingFailed { error: e.into() };
-        }
-    };
+    download_timer.observe_duration();
     tracing::debug!(
-        "[job: {}] Normalized HTML ({} bytes -> {} bytes)",
+        "[job: {}] Downloaded HTML ({} bytes)",
         job.job_id,
-        html.len(),
-        normalized.as_str().len()
+        page.response.body.len()
     );
 
-    // Compute checksum of normalized HTML (before compression)
-    let html_checksum = match compute_html_checksum(&normalized) {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::error!("[job: {}] Failed to compute HTML checksum: {}", job.job_id, e);
-            return JobResult::HtmlProcessingFailed { error: e.into() };
+    if let Some(archive) = archive {
+        let _timer = time_stage(stage::ARCHIVE);
+        match archive.store(job.job_id, &page.response).await {
+            Ok(key) => tracing::debug!(
+                "[job: {}] Archived the page to s3://{}/{}",
+                job.job_id,
+                archive.bucket(),
+                key
+            ),
+            Err(e) => tracing::error!("[SKIP] [job: {}] {}", job.job_id, e),
         }
+    }
+    // Normalize, checksum and compress the HTML - if this fails, return immediately
+    let normalize_timer = time_stage(stage::NORMALIZE);
+    let job_id = job.job_id;
+    let page_url = url.clone();
+    let processed = match tokio::task::spawn_blocking(move || process_page(job_id, page, &page_url)).await {
+        Ok(processed) => processed,
+        Err(e) => Err(e.into()),
+    };
+    let ProcessedPage {
+        html,
+        html_checksum,
+        html_compress,
+        metadata,
+        disallowed,
+    } = match processed {
+        Ok(p) => p,
+        Err(e) => return JobResult::HtmlProcessingFailed { error: e.into() },
     };
-    tracing::debug!("[job: {}] Computed HTML checksum: {}", job.job_id, html_checksum);
+    normalize_timer.observe_duration();
 
-    // Compress HTML - if this fails, return immediately
-    let html_compress = match compress_string(normalized.as_str()) {
-        Ok(c) => c,
-        Err(e) => {
-            tracing::error!("[job: {}] Failed to compress HTML: {}", job.job_id, e);
-            return JobResult::HtmlProcessingFailed { error: e.into() };
+    // Honor the publisher's robots directives
+    if let Some(disallowed) = disallowed {
+        if settings.ignores_noindex(&url) {
+            tracing::info!(
+                "[job: {}] Ignoring {} for '{}': domain is in IGNORE_NOINDEX_DOMAINS",
+                job.job_id,
+                disallowed,
+                url
+            );
+        } else {
+            return JobResult::Disallowed {
+                error: Error::DisallowedByPublisher(disallowed),
+            };
+        }
+    }
+
+    // Import the website's own llms.txt, if it publishes a valid one: no need to spend tokens generating one.
+    // Not when a single section is regenerated: the rest of the llms.txt has to stay as it is.
+    // Nor for a crawl: it was asked for to cover the pages a published llms.txt may be missing.
+    if job.section.is_none() && job.crawl().is_none() {
+        let origin_timer = time_stage(stage::ORIGIN);
+        let origin_llms_txt = fetch_origin_llms_txt(&url)
+            .instrument(tracing::info_span!("origin"))
+            .await;
+        origin_timer.observe_duration();
+        match origin_llms_txt {
+            Ok(llms_txt) => {
+                tracing::info!("[job: {}] Imported the llms.txt published by '{}'", job.job_id, url);
+                return JobResult::Success {
+                    html_compress,
+                    html_checksum,
+                    llms_txt,
+                    source: LlmsTxtSource::Origin,
+                    metadata,
+                };
+            }
+            Err(e) => tracing::debug!("[job: {}] No llms.txt to import from '{}': {}", job.job_id, url, e),
+        }
+    }
+
+    // A crawl job generates from every page of the website it finds, not only from the URL's page
+    let crawled = match job.crawl() {
+        Some(limits) => {
+            let crawl_timer = time_stage(stage::CRAWL);
+            let options = CrawlOptions {
+                max_depth: limits.max_depth as usize,
+                max_pages: limits.max_pages as usize,
+            };
+            match crawl(&url, options).instrument(tracing::info_span!("crawl")).await {
+                Ok(pages) => {
+                    crawl_timer.observe_duration();
+                    tracing::debug!("[job: {}] Crawled {} page(s) from '{}'", job.job_id, pages.len(), url);
+                    Some(combine_pages(&pages))
+                }
+                Err(e) => {
+                    tracing::warn!("[job: {}] Failed to crawl '{}': {}", job.job_id, url, e);
+                    return JobResult::GenerationFailed {
+                        html_compress,
+                        html_checksum,
+                        error: e.into(),
+                    };
+                }
+            }
         }
+        None => None,
     };
-    tracing::debug!(
-        "[job: {}] Compressed HTML ({} bytes -> {} bytes)",
-        job.job_id,
-        normalized.as_str().len(),
-        html_compress.len()
-    );
+    let website = crawled.as_deref().unwrap_or(&html);
 
     // Generate or update llms.txt - if this fails, we still have processed HTML
+    let llm_timer = time_stage(stage::LLM);
     let llms_txt_result = match job.to_kind_data() {
-        JobKindData::New => generate_llms_txt(provider, &html).await,
-        JobKindData::Update { llms_txt: old_llms_txt } => update_llms_txt(provider, &old_llms_txt, &html).await,
+        JobKindData::New => {
+            generate_llms_txt(provider, website, job.detail())
+                .instrument(tracing::info_span!("generate"))
+                .await
+        }
+        JobKindData::Update { llms_txt: old_llms_txt } => match &job.section {
+            Some(section) => {
+                regenerate_job_section(provider, job, &old_llms_txt, section, &html)
+                    .instrument(tracing::info_span!("section"))
+                    .await
+            }
+            None => {
+                update_llms_txt(provider, &old_llms_txt, website, job.detail())
+                    .instrument(tracing::info_span!("update"))
+                    .await
+            }
+        },
     };
+    llm_timer.observe_duration();
 
     match llms_txt_result {
         Ok(llms_txt) => {
@@ -166,6 +397,8 @@ pub async fn handle_job<P: LlmProvider>(provider: &P, job: &JobState) -> JobResu
                 html_compress,
                 html_checksum,
                 llms_txt,
+                source: LlmsTxtSource::Generated,
+                metadata,
             }
         }
         Err(e) => {
@@ -179,10 +412,140 @@ pub async fn handle_job<P: LlmProvider>(provider: &P, job: &JobState) -> JobResu
     }
 }
 
+/// The downloaded page, ready to be stored.
+struct ProcessedPage {
+    /// Taken from the response: pages that are valid UTF-8 aren't copied
+    html: String,
+    html_checksum: String,
+    html_compress: Vec<u8>,
+    metadata: SiteMetadata,
+    /// The robots directive that disallows generating an llms.txt for the page, if it has one
+    disallowed: Option<Disallowed>,
+}
+
+/// Normalizes, checksums and Brotli-compresses the page's HTML, and reads its metadata and robots directives.
+/// That's CPU-heavy for a big page: it runs on a blocking thread, so that the other jobs' I/O isn't held up.
+fn process_page(job_id: Uuid, page: Page, url: &Url) -> Result<ProcessedPage, core_ltx::Error> {
+    let x_robots_tags = page.x_robots_tags.clone();
+    let html = page.into_text();
+
+    let normalized = normalize_html(&html).inspect_err(|e| {
+        tracing::error!("[job: {}] Failed to normalize HTML: {}", job_id, e);
+    })?;
+    tracing::debug!(
+        "[job: {}] Normalized HTML ({} bytes -> {} bytes)",
+        job_id,
+        html.len(),
+        normalized.as_str().len()
+    );
+    let disallowed = find_disallowing_directive(normalized.as_str(), &x_robots_tags);
+
+    // Compute checksum of normalized HTML (before compression)
+    let html_checksum = compute_html_checksum(&normalized).inspect_err(|e| {
+        tracing::error!("[job: {}] Failed to compute HTML checksum: {}", job_id, e);
+    })?;
+    tracing::debug!("[job: {}] Computed HTML checksum: {}", job_id, html_checksum);
+
+    let html_compress = compress_string(normalized.as_str()).inspect_err(|e| {
+        tracing::error!("[job: {}] Failed to compress HTML: {}", job_id, e);
+    })?;
+    tracing::debug!(
+        "[job: {}] Compressed HTML ({} bytes -> {} bytes)",
+        job_id,
+        normalized.as_str().len(),
+        html_compress.len()
+    );
+
+    let metadata = extract_site_metadata(&html, url);
+    Ok(ProcessedPage {
+        html,
+        html_checksum,
+        html_compress,
+        metadata,
+        disallowed,
+    })
+}
+
+/// Regenerates one section of the llms.txt from the page at the job's `section_url`, which is downloaded unless it's
+/// the job's URL, whose HTML is `html`.
+async fn regenerate_job_section<P: LlmProvider>(
+    provider: &P,
+    job: &JobState,
+    old_llms_txt: &str,
+    section: &str,
+    html: &str,
+) -> Result<core_ltx::LlmsTxt, core_ltx::Error> {
+    let section_html = match job.section_url.as_deref() {
+        Some(section_url) if section_url != job.url => {
+            tracing::debug!(
+                "[job: {}] Regenerating section '{}' from '{}'",
+                job.job_id,
+                section,
+                section_url
+            );
+            download(&is_valid_url(section_url)?).await?
+        }
+        _ => html.to_string(),
+    };
+    regenerate_section(provider, old_llms_txt, section, &section_html, job.detail()).await
+}
+
+/// The llms.txt as it's stored and published: post-processed by the `hooks` (see `post_process`) unless the website
+/// published it itself, then bounded (see `result_data`).
+fn stored_llms_txt(
+    job_id: Uuid,
+    url: &str,
+    llms_txt: &core_ltx::LlmsTxt,
+    source: LlmsTxtSource,
+    hooks: &[Hook],
+) -> Result<Bounded, Error> {
+    match source {
+        LlmsTxtSource::Generated => bound_llms_txt(&post_process(job_id, &llms_txt.md_content(), hooks, url)),
+        LlmsTxtSource::Origin => bound_llms_txt(&llms_txt.md_content()),
+    }
+}
+
+/// The record of an llms.txt, stored as `stored_llms_txt` says, and compressed. It's a failure when the llms.txt
+/// can't be stored: then the result says why.
+fn llms_txt_record(
+    job_id: Uuid,
+    url: String,
+    llms_txt: &core_ltx::LlmsTxt,
+    source: LlmsTxtSource,
+    hooks: &[Hook],
+    html_compress: Vec<u8>,
+    html_checksum: String,
+) -> (LlmsTxt, Result<Bounded, Error>) {
+    let mut bounded = stored_llms_txt(job_id, &url, llms_txt, source, hooks);
+    let result = match &mut bounded {
+        Ok(bounded) => LlmsTxtResult::Ok {
+            llms_txt: std::mem::take(&mut bounded.text),
+        },
+        Err(error) => LlmsTxtResult::Error {
+            failure_reason: bound_error(&error.to_string()).text,
+        },
+    };
+    let record = LlmsTxt::from_result(job_id, url, result, html_compress, html_checksum);
+    (record, bounded)
+}
+
 /// Inserts the result into the llms_txt table & updates job_state appropriately.
-/// Handles four cases: success, generation failure (with HTML), download failure (no HTML),
-/// and HTML processing failure (no HTML).
+/// The stored llms.txt or failure reason is bounded first (see `result_data`): control characters are removed
+/// and it's truncated to the size limit. An llms.txt with null bytes is stored as a failure.
+/// Handles five cases: success, generation failure (with HTML), download failure (no HTML),
+/// HTML processing failure (no HTML), and pages whose publisher disallows indexing (no HTML).
 pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult) -> Result<(), Error> {
+    handle_result_post_processed(pool, job, result, &[]).await
+}
+
+/// Like `handle_result`, but a generated llms.txt is post-processed by the `hooks` first (see `post_process`).
+pub async fn handle_result_post_processed(
+    pool: &db::DbPool,
+    job: &JobState,
+    result: JobResult,
+    hooks: &[Hook],
+) -> Result<(), Error> {
+    let _timer = time_stage(stage::DB_WRITE);
     let mut conn = pool.get().await?;
 
     match result {
@@ -190,43 +553,60 @@ pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult)
             html_compress,
             html_checksum,
             llms_txt,
+            source,
+            metadata,
         } => {
-            tracing::info!(
-                "[job: {}] Successfully produced llms.txt ({:?} - '{}')",
-                job.job_id,
-                job.kind,
-                job.url
-            );
+            // rendering and compressing a long llms.txt is CPU-heavy: it's done on a blocking thread
+            let (job_id, url, hooks) = (job.job_id, job.url.clone(), hooks.to_vec());
+            let (record, bounded) = tokio::task::spawn_blocking(move || {
+                llms_txt_record(job_id, url, &llms_txt, source, &hooks, html_compress, html_checksum)
+            })
+            .await
+            .map_err(core_ltx::Error::from)?;
+            match bounded {
+                Ok(bounded) => {
+                    tracing::info!(
+                        "[job: {}] Successfully produced llms.txt ({:?} - '{}', {:?})",
+                        job.job_id,
+                        job.kind,
+                        job.url,
+                        source
+                    );
+                    if bounded.truncated_bytes > 0 || bounded.removed_control_chars > 0 {
+                        tracing::warn!(
+                            "[job: {}] Stored llms.txt without {} control character(s), truncated by {} bytes (limit: {} bytes)",
+                            job.job_id,
+                            bounded.removed_control_chars,
+                            bounded.truncated_bytes,
+                            MAX_LLMS_TXT_BYTES
+                        );
+                    }
 
-            let llms_txt_record = LlmsTxt::from_result(
-                job.job_id,
-                job.url.clone(),
-                LlmsTxtResult::Ok {
-                    llms_txt: llms_txt.md_content(),
-                },
-                html_compress,
-                html_checksum,
-            );
+                    let record = record
+                        .with_model(job.model.clone())
+                        .with_detail(job.detail())
+                        .with_source(source)
+                        .with_metadata(metadata);
+                    store_llms_txt(&mut conn, job, record, None).await?;
 
-            conn.transaction::<_, diesel::result::Error, _>(|mut conn| {
-                Box::pin(async move {
-                    diesel::insert_into(schema::llms_txt::table)
-                        .values(&llms_txt_record)
-                        .execute(&mut conn)
-                        .await?;
+                    tracing::debug!("[job: {}] Updated DB", job.job_id);
+                    Ok(())
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "[job: {}] Generated llms.txt can't be stored ({:?} - '{}') Error: {}",
+                        job.job_id,
+                        job.kind,
+                        job.url,
+                        error
+                    );
 
-                    diesel::update(schema::job_state::table.find(job.job_id))
-                        .set(schema::job_state::status.eq(JobStatus::Success))
-                        .execute(&mut conn)
-                        .await?;
+                    store_llms_txt(&mut conn, job, record.with_source(source), Some(error.failure_kind())).await?;
 
+                    tracing::debug!("[job: {}] Updated DB with failure", job.job_id);
                     Ok(())
-                })
-            })
-            .await?;
-
-            tracing::debug!("[job: {}] Updated DB", job.job_id);
-            Ok(())
+                }
+            }
         }
 
         JobResult::GenerationFailed {
@@ -242,32 +622,11 @@ pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult)
                 error
             );
 
-            let llms_txt_record = LlmsTxt::from_result(
-                job.job_id,
-                job.url.clone(),
-                LlmsTxtResult::Error {
-                    failure_reason: error.to_string(),
-                },
-                html_compress,
-                html_checksum,
-            );
-
-            conn.transaction::<_, diesel::result::Error, _>(|mut conn| {
-                Box::pin(async move {
-                    diesel::insert_into(schema::llms_txt::table)
-                        .values(&llms_txt_record)
-                        .execute(&mut conn)
-                        .await?;
-
-                    diesel::update(schema::job_state::table.find(job.job_id))
-                        .set(schema::job_state::status.eq(JobStatus::Failure))
-                        .execute(&mut conn)
-                        .await?;
-
-                    Ok(())
-                })
-            })
-            .await?;
+            let result = LlmsTxtResult::Error {
+                failure_reason: bound_error(&error.to_string()).text,
+            };
+            let record = LlmsTxt::from_result(job.job_id, job.url.clone(), result, html_compress, html_checksum);
+            store_llms_txt(&mut conn, job, record, Some(error.failure_kind())).await?;
 
             tracing::debug!("[job: {}] Updated DB with failure", job.job_id);
             Ok(())
@@ -282,14 +641,22 @@ pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult)
                 error
             );
 
+            let failure_kind = error.failure_kind();
             // No llms_txt record - no HTML to store
             // Only mark job as failed in job_state table
             conn.transaction::<_, diesel::result::Error, _>(|mut conn| {
                 Box::pin(async move {
-                    diesel::update(schema::job_state::table.find(job.job_id))
-                        .set(schema::job_state::status.eq(JobStatus::Failure))
-                        .execute(&mut conn)
-                        .await?;
+                    diesel::update(
+                        schema::job_state::table
+                            .find(job.job_id)
+                            .filter(schema::job_state::status.eq(JobStatus::Running)),
+                    )
+                    .set((
+                        schema::job_state::status.eq(JobStatus::Failure),
+                        schema::job_state::failure_kind.eq(failure_kind.code()),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
 
                     Ok(())
                 })
@@ -309,14 +676,22 @@ pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult)
                 error
             );
 
+            let failure_kind = error.failure_kind();
             // No llms_txt record - HTML processing failed
             // Only mark job as failed in job_state table
             conn.transaction::<_, diesel::result::Error, _>(|mut conn| {
                 Box::pin(async move {
-                    diesel::update(schema::job_state::table.find(job.job_id))
-                        .set(schema::job_state::status.eq(JobStatus::Failure))
-                        .execute(&mut conn)
-                        .await?;
+                    diesel::update(
+                        schema::job_state::table
+                            .find(job.job_id)
+                            .filter(schema::job_state::status.eq(JobStatus::Running)),
+                    )
+                    .set((
+                        schema::job_state::status.eq(JobStatus::Failure),
+                        schema::job_state::failure_kind.eq(failure_kind.code()),
+                    ))
+                    .execute(&mut conn)
+                    .await?;
 
                     Ok(())
                 })
@@ -326,5 +701,454 @@ pub async fn handle_result(pool: &db::DbPool, job: &JobState, result: JobResult)
             tracing::debug!("[job: {}] Marked job as failed (HTML processing error)", job.job_id);
             Ok(())
         }
+
+        JobResult::Disallowed { error } => {
+            // not an error on our side: the publisher opted out
+            tracing::info!(
+                "[job: {}] Not generating llms.txt ({:?} - '{}'): {}",
+                job.job_id,
+                job.kind,
+                job.url,
+                error
+            );
+
+            // No llms_txt record - the page's content isn't kept
+            diesel::update(
+                schema::job_state::table
+                    .find(job.job_id)
+                    .filter(schema::job_state::status.eq(JobStatus::Running)),
+            )
+            .set((
+                schema::job_state::status.eq(JobStatus::Failure),
+                schema::job_state::failure_kind.eq(error.failure_kind().code()),
+            ))
+            .execute(&mut conn)
+            .await?;
+
+            tracing::debug!("[job: {}] Marked job as failed (disallowed by publisher)", job.job_id);
+            Ok(())
+        }
+    }
+}
+
+/// Inserts the llms_txt record of the job's result, indexed for search, and sets the job's final status, in one
+/// transaction. The API servers are notified of it once it commits.
+/// The job succeeded unless there's a `failure_kind`. Nothing is stored once the job isn't running anymore, e.g. when
+/// it was cancelled since it was last checked.
+async fn store_llms_txt(
+    conn: &mut AsyncPgConnection,
+    job: &JobState,
+    llms_txt_record: LlmsTxt,
+    failure_kind: Option<FailureKind>,
+) -> Result<(), diesel::result::Error> {
+    let status = if failure_kind.is_some() {
+        JobStatus::Failure
+    } else {
+        JobStatus::Success
+    };
+
+    let stored = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            Box::pin(async move {
+                // first, so the job's row stays locked until the result is stored
+                let updated = diesel::update(
+                    schema::job_state::table
+                        .find(job.job_id)
+                        .filter(schema::job_state::status.eq(JobStatus::Running)),
+                )
+                .set((
+                    schema::job_state::status.eq(status),
+                    schema::job_state::failure_kind.eq(failure_kind.map(|kind| kind.code())),
+                ))
+                .execute(conn)
+                .await?;
+                if updated == 0 {
+                    return Ok(false);
+                }
+
+                diesel::insert_into(schema::llms_txt::table)
+                    .values(&llms_txt_record)
+                    .execute(conn)
+                    .await?;
+                index_llms_txt(conn, std::slice::from_ref(&llms_txt_record)).await?;
+                notify_llms_txt_changed(conn, Some(&llms_txt_record.url)).await?;
+                Ok(true)
+            })
+        })
+        .await?;
+    if !stored {
+        tracing::info!(
+            "[job: {}] Not storing its result: the job isn't running anymore",
+            job.job_id
+        );
+    }
+    Ok(())
+}
+
+/// Records the tokens a job's LLM calls used, counting them towards today's token budget.
+pub async fn record_usage(pool: &db::DbPool, job: &JobState, usage: TokenUsage) -> Result<(), Error> {
+    let mut conn = pool.get().await?;
+    diesel::insert_into(schema::llm_usage::table)
+        .values(&LlmUsage::new(job.job_id, usage))
+        .execute(&mut conn)
+        .await?;
+    Ok(())
+}
+
+/// Stores what was logged while working on the job, replacing any earlier log of it.
+pub async fn store_job_log(pool: &db::DbPool, job_id: Uuid, log: JobLog) -> Result<(), Error> {
+    let mut conn = pool.get().await?;
+    let logs = JobLogs {
+        job_id,
+        log: log.text,
+        truncated: log.truncated,
+        created_at: Utc::now(),
+    };
+    diesel::insert_into(schema::job_logs::table)
+        .values(&logs)
+        .on_conflict(schema::job_logs::job_id)
+        .do_update()
+        .set((
+            schema::job_logs::log.eq(&logs.log),
+            schema::job_logs::truncated.eq(logs.truncated),
+            schema::job_logs::created_at.eq(logs.created_at),
+        ))
+        .execute(&mut conn)
+        .await?;
+    Ok(())
+}
+
+/// Total tokens used by all jobs since midnight (UTC).
+pub async fn tokens_used_today(pool: &db::DbPool) -> Result<u64, Error> {
+    let mut conn = pool.get().await?;
+    let midnight = Utc::now().date_naive().and_time(NaiveTime::MIN).and_utc();
+    let used: i64 = schema::llm_usage::table
+        .filter(schema::llm_usage::created_at.ge(midnight))
+        .select(sql::<BigInt>(
+            "COALESCE(SUM(prompt_tokens + completion_tokens), 0)::BIGINT",
+        ))
+        .first(&mut conn)
+        .await?;
+    Ok(used.max(0) as u64)
+}
+
+/// Continuously polls the DB for new jobs and spawns tasks to work on them.
+/// Uses a semaphore to limit the maximum number of concurrent tasks to `settings.max_concurrency`.
+///
+/// When the `settings` change, they're picked up before the next poll: the provider is rebuilt with
+/// `make_provider` and the concurrency limit is resized. Jobs that are already running are unaffected.
+///
+/// When a `daily_token_budget` is set, no new jobs are claimed once today's usage reaches it:
+/// jobs stay queued until the budget resets at midnight (UTC). Jobs already running finish,
+/// so the budget can be overshot by their usage.
+///
+/// Failed jobs, losing (and regaining) the database, and running out of budget are sent to `notifications`.
+/// Successfully generated llms.txt files are copied to the `publishers` once they're stored.
+/// Every webhook is sent a `job.finished` event once the result is stored (see `webhook_delivery_loop`).
+/// Downloaded pages are stored in the `archive`, if there's one.
+///
+/// Queued jobs that passed their deadline are marked Expired instead of being claimed.
+///
+/// The credentials of domains behind a login are reloaded on every poll, so changes apply without a restart.
+///
+/// With a `shard`, only that shard's jobs are claimed: other workers are expected to cover the other shards.
+pub async fn worker_polling_loop<P, F>(
+    pool: db::DbPool,
+    make_provider: F,
+    mut settings: watch::Receiver<WorkerSettings>,
+    notifications: Arc<Notifications>,
+    publishers: Arc<Publishers>,
+    archive: Option<Arc<WarcArchive>>,
+    shard: Option<Shard>,
+) where
+    P: LlmProvider + 'static,
+    F: Fn(&WorkerSettings) -> P,
+{
+    let mut current = settings.borrow_and_update().clone();
+    let mut provider = Arc::new(make_provider(&current));
+    let semaphore = Arc::new(Semaphore::new(current.max_concurrency));
+    let mut over_budget = false;
+    let db_outage = Outage::default();
+    let keyring = match Keyring::from_env() {
+        Ok(keyring) => Some(keyring),
+        Err(SecretsError::MissingKey) => None,
+        Err(error) => {
+            tracing::error!("Can't authenticate to any domain: {}", error);
+            None
+        }
+    };
+    let mut domain_credentials = (Vec::new(), Vec::new());
+    // until the domain policy is loaded, the private networks are off limits all the same
+    let mut download_guard = DownloadGuard {
+        private_hosts_allowed: private_hosts_allowed(),
+        ..DownloadGuard::default()
+    };
+    set_download_guard(download_guard.clone());
+    let running = RunningJobs::default();
+    #[allow(clippy::let_underscore_future)]
+    let _ = tokio::spawn(poll_cancelled_jobs(pool.clone(), running.clone(), settings.clone()));
+    loop {
+        if settings.has_changed().unwrap_or(false) {
+            let updated = settings.borrow_and_update().clone();
+            tracing::info!("Applying new worker settings: {:?}", updated);
+            resize_semaphore(&semaphore, current.max_concurrency, updated.max_concurrency);
+            provider = Arc::new(make_provider(&updated));
+            current = updated;
+        }
+        let poll_interval = current.poll_interval;
+
+        if let Some(budget) = current.daily_token_budget {
+            match tokens_used_today(&pool).await {
+                Ok(used) if used >= budget => {
+                    if !over_budget {
+                        // error-level so that it's reported (e.g. to Sentry): someone should look into this
+                        tracing::error!(
+                            "Daily token budget exceeded: {} of {} tokens used today. Not claiming jobs until midnight (UTC).",
+                            used,
+                            budget
+                        );
+                        over_budget = true;
+                        notifications
+                            .send(Notification::new(
+                                "Daily token budget exceeded",
+                                format!(
+                                    "{} of {} tokens used today. Workers won't claim jobs until midnight (UTC).",
+                                    used, budget
+                                ),
+                            ))
+                            .await;
+                    }
+                    tokio::time::sleep(poll_interval).await;
+                    continue;
+                }
+                Ok(used) => {
+                    if over_budget {
+                        tracing::info!(
+                            "Daily token budget reset ({} of {} tokens used). Resuming jobs.",
+                            used,
+                            budget
+                        );
+                        over_budget = false;
+                    }
+                }
+                Err(error) => {
+                    tracing::error!("[SKIP] Failed to check daily token budget: {}", error);
+                }
+            }
+        }
+
+        match expire_overdue_jobs(&pool).await {
+            Ok(0) => {}
+            Ok(expired) => tracing::info!("{} queued job(s) passed their deadline: marked them expired", expired),
+            Err(error) => tracing::error!("[SKIP] Failed to expire overdue jobs: {}", error),
+        }
+
+        // logged when they change, not on every poll
+        match load_domain_credentials(&pool, keyring.as_ref()).await {
+            Ok(loaded) if loaded != domain_credentials => {
+                let (in_use, undecryptable) = &loaded;
+                tracing::info!("Authenticating to {} domain(s): {:?}", in_use.len(), in_use);
+                if !undecryptable.is_empty() {
+                    tracing::error!(
+                        "Can't decrypt the credentials of {:?} with the {} (are the keys they were stored with in it?)",
+                        undecryptable,
+                        data_model_ltx::secrets::SECRETS_KEYS_ENV_VAR
+                    );
+                }
+                domain_credentials = loaded;
+            }
+            Ok(_) => {}
+            Err(error) => tracing::error!("[SKIP] Failed to load domain credentials: {}", error),
+        }
+
+        match guard_downloads(&pool).await {
+            Ok(guard) if guard != download_guard => {
+                tracing::info!("Checking every download with: {:?}", guard);
+                download_guard = guard;
+            }
+            Ok(_) => {}
+            Err(error) => tracing::error!("[SKIP] Failed to load the domain policy: {}", error),
+        }
+
+        match next_job_in_shard(&pool, semaphore.clone(), shard).await {
+            Ok((mut job, permit)) => {
+                // the job's model: the one it asked for, or else the configured one
+                let model = job.model.get_or_insert_with(|| current.model_name.clone()).clone();
+                // continue the trace of the API request that created the job
+                let span = tracing::info_span!("job", job_id = %job.job_id, kind = ?job.kind, url = %job.url);
+                if let Some(trace_context) = job.trace_context.as_deref() {
+                    set_parent_from_trace_context(&span, trace_context);
+                }
+                let (job_id, url) = (job.job_id.to_string(), job.url.clone());
+                let error_tags = [("job_id", job_id.as_str()), ("url", url.as_str())];
+                #[allow(clippy::let_underscore_future)]
+                let _ = tokio::spawn(with_error_context(&error_tags, {
+                    let pool = pool.clone();
+                    let settings = WorkerSettings {
+                        model_name: model.clone(),
+                        ..current.clone()
+                    };
+                    // a job that asks for another model than the configured one gets a provider of its own
+                    let provider = if model == current.model_name {
+                        provider.clone()
+                    } else {
+                        Arc::new(make_provider(&settings))
+                    };
+                    let notifications = notifications.clone();
+                    let publishers = publishers.clone();
+                    let archive = archive.clone();
+                    let running = running.clone();
+                    let mut cancellation = running.start(job.job_id);
+                    async move {
+                        tracing::info!("Received job {} ({:?}) on website '{}'", job.job_id, job.kind, job.url);
+                        let started = Instant::now();
+                        JOBS_RUNNING.inc();
+                        let worked = tokio::select! {
+                            worked = track_usage(handle_job_archived(
+                                provider.as_ref(),
+                                &job,
+                                &settings,
+                                archive.as_deref(),
+                            )) => Some(worked),
+                            () = cancellation.cancelled() => None,
+                        };
+                        let result = match worked {
+                            Some((result, usage)) => {
+                                if usage.total_tokens > 0
+                                    && let Err(error) = record_usage(&pool, &job, usage).await
+                                {
+                                    tracing::error!(
+                                        "[SKIP] Failed to record token usage of job {}: {}",
+                                        job.job_id,
+                                        error
+                                    );
+                                }
+                                // it may have been cancelled since the last poll: its result is dropped then too
+                                match is_job_cancelled(&pool, job.job_id).await {
+                                    Ok(true) => None,
+                                    Ok(false) => Some(result),
+                                    Err(error) => {
+                                        tracing::error!(
+                                            "[SKIP] Failed to check whether job {} was cancelled: {}",
+                                            job.job_id,
+                                            error
+                                        );
+                                        Some(result)
+                                    }
+                                }
+                            }
+                            None => None,
+                        };
+                        if let Some(result) = result {
+                            let is_ok = matches!(result, JobResult::Success { .. });
+                            let failure = result.error().map(|e| e.to_string());
+                            let generated = match &result {
+                                // published as it's stored
+                                JobResult::Success { llms_txt, source, .. } if publishers.is_enabled() => {
+                                    stored_llms_txt(
+                                        job.job_id,
+                                        &job.url,
+                                        llms_txt,
+                                        *source,
+                                        &settings.post_process_hooks,
+                                    )
+                                    .ok()
+                                    .map(|bounded| bounded.text)
+                                }
+                                _ => None,
+                            };
+                            let stored =
+                                handle_result_post_processed(&pool, &job, result, &settings.post_process_hooks).await;
+                            JOBS_RUNNING.dec();
+                            JOB_DURATION
+                                .with_label_values(&[if is_ok { "success" } else { "failure" }])
+                                .observe(started.elapsed().as_secs_f64());
+                            match stored {
+                                Ok(()) => {
+                                    if let Some(llms_txt) = generated {
+                                        publishers.publish(job.job_id, &job.url, &llms_txt).await;
+                                    }
+                                    if let Err(error) = enqueue_job_finished(&pool, &job, failure.as_deref()).await {
+                                        tracing::error!(
+                                            "[SKIP] Failed to queue the webhook events of job {}: {}",
+                                            job.job_id,
+                                            error
+                                        );
+                                    }
+                                }
+                                Err(error) => {
+                                    tracing::error!(
+                                        "[SKIP] Failed to handle result for job {} ({:?} - '{}'). Result was ok?: {} - ERROR: {}",
+                                        job.job_id,
+                                        job.kind,
+                                        job.url,
+                                        is_ok,
+                                        error
+                                    );
+                                }
+                            };
+                            if let Some(failure) = failure {
+                                notifications
+                                    .send(Notification::new(
+                                        format!("llms.txt job failed for {}", job.url),
+                                        format!("Job {} ({:?}) failed: {}", job.job_id, job.kind, failure),
+                                    ))
+                                    .await;
+                            }
+                        } else {
+                            JOBS_RUNNING.dec();
+                            JOB_DURATION
+                                .with_label_values(&["cancelled"])
+                                .observe(started.elapsed().as_secs_f64());
+                            tracing::info!(
+                                "Abandoned job {} ({:?}) on website '{}': it was cancelled",
+                                job.job_id,
+                                job.kind,
+                                job.url
+                            );
+                        }
+                        running.finish(job.job_id);
+                        if let Some(log) = take_job_log(&job.job_id.to_string())
+                            && let Err(error) = store_job_log(&pool, job.job_id, log).await
+                        {
+                            tracing::error!("[SKIP] Failed to store the logs of job {}: {}", job.job_id, error);
+                        }
+                        // We need to:
+                        //   (1) make sure this task owns the semaphore permit
+                        //   (2) release this semaphore permit when the task ends
+                        // It just needs to be owned by the task, so a `let _permit = permit;` would work too,
+                        // but we just explicitly drop it here to move it into the task and make it clear that we release it at the end.
+                        drop(permit);
+                    }
+                    .instrument(span)
+                }));
+            }
+            Err(error) => match error {
+                Error::RecordNotFound => {}
+                Error::DbError(_) | Error::DbPoolError(_) => {
+                    tracing::error!("[SKIP] Error getting next job from DB queue: {}", error);
+                    if db_outage.failed() {
+                        notifications
+                            .send(Notification::new("Worker cannot reach the database", error.to_string()))
+                            .await;
+                    }
+                    tokio::time::sleep(poll_interval).await;
+                    continue;
+                }
+                _ => {
+                    tracing::error!("[SKIP] Error getting next job from DB queue: {}", error);
+                }
+            },
+        }
+        if db_outage.recovered() {
+            notifications
+                .send(Notification::new(
+                    "Worker reconnected to the database",
+                    "The worker is claiming jobs again.",
+                ))
+                .await;
+        }
+        tracing::debug!("Waiting to poll for next job");
+        tokio::time::sleep(poll_interval).await;
     }
 }
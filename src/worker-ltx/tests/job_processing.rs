@@ -8,7 +8,8 @@
 
 use core_ltx::decompress_to_string;
 use core_ltx::llms::mock::{MockLlmProvider, sample_valid_llms_txt};
-use data_model_ltx::models::{JobKindData, JobState, JobStatus};
+use data_model_ltx::models::{Crawl, JobKindData, JobState, JobStatus};
+use worker_ltx::WorkerSettings;
 use worker_ltx::work::{JobResult, handle_job};
 
 /// Helper to create a test job without database
@@ -24,13 +25,14 @@ async fn test_handle_job_success_new() {
     // In a real test environment, you might want to use a local test server
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::Success {
             html_compress,
             html_checksum,
             llms_txt,
+            ..
         } => {
             assert!(!html_compress.is_empty(), "Compressed HTML should not be empty");
             assert!(!html_checksum.is_empty(), "HTML checksum should not be empty");
@@ -56,6 +58,9 @@ async fn test_handle_job_success_new() {
         JobResult::HtmlProcessingFailed { error } => {
             panic!("Expected success but got HTML processing failure: {}", error);
         }
+        JobResult::Disallowed { error } => {
+            panic!("Expected success but the page was disallowed: {}", error);
+        }
     }
 }
 
@@ -70,13 +75,14 @@ async fn test_handle_job_success_update() {
         },
     );
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::Success {
             html_compress,
             html_checksum,
             llms_txt,
+            ..
         } => {
             assert!(!html_compress.is_empty());
             assert!(!html_checksum.is_empty());
@@ -94,7 +100,7 @@ async fn test_handle_job_generation_failed() {
 
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::GenerationFailed {
@@ -121,6 +127,9 @@ async fn test_handle_job_generation_failed() {
         JobResult::HtmlProcessingFailed { .. } => {
             panic!("Expected generation failure but got HTML processing failure");
         }
+        JobResult::Disallowed { .. } => {
+            panic!("Expected generation failure but the page was disallowed");
+        }
     }
 }
 
@@ -131,7 +140,7 @@ async fn test_handle_job_download_failed_invalid_url() {
     // Invalid URL that should fail
     let job = create_test_job_for_processing("not-a-valid-url", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::DownloadFailed { error } => {
@@ -152,7 +161,7 @@ async fn test_handle_job_download_failed_unreachable_host() {
         JobKindData::New,
     );
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::DownloadFailed { error } => {
@@ -170,7 +179,7 @@ async fn test_handle_job_invalid_markdown_from_llm() {
 
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::GenerationFailed {
@@ -194,7 +203,7 @@ async fn test_handle_job_invalid_llms_txt_format() {
 
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::GenerationFailed {
@@ -217,7 +226,7 @@ async fn test_handle_job_preserves_html_on_llm_failure() {
 
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::GenerationFailed {
@@ -270,13 +279,14 @@ async fn test_handle_job_update_with_existing_content() {
         },
     );
 
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     match result {
         JobResult::Success {
             html_compress,
             html_checksum,
             llms_txt,
+            ..
         } => {
             assert!(!html_compress.is_empty());
             assert!(!html_checksum.is_empty());
@@ -290,6 +300,86 @@ async fn test_handle_job_update_with_existing_content() {
     }
 }
 
+#[tokio::test]
+async fn test_handle_job_regenerates_only_the_section() {
+    let provider = MockLlmProvider::with_response(
+        "regenerate one section",
+        r#"## Docs
+
+- [API](https://example.com/docs/api): Every endpoint
+"#,
+    );
+
+    let existing_llms_txt = r#"# Example
+
+> Example description
+
+## Docs
+
+- [Guide](https://example.com/docs/guide): How to get started
+
+## Blog
+
+- [Launch](https://example.com/blog/launch): We launched
+"#;
+
+    let job = JobState {
+        section: Some("Docs".to_string()),
+        ..create_test_job_for_processing(
+            "https://example.com",
+            JobKindData::Update {
+                llms_txt: existing_llms_txt.to_string(),
+            },
+        )
+    };
+
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
+
+    match result {
+        JobResult::Success { llms_txt, .. } => {
+            let content = llms_txt.md_content();
+            assert!(
+                content.contains("https://example.com/docs/api"),
+                "Section should be regenerated"
+            );
+            assert!(
+                !content.contains("https://example.com/docs/guide"),
+                "Old section should be replaced"
+            );
+            assert!(
+                content.contains("https://example.com/blog/launch"),
+                "Other sections should be kept"
+            );
+            assert!(content.contains("Example description"));
+        }
+        _ => panic!("Expected successful section regeneration"),
+    }
+}
+
+#[tokio::test]
+async fn test_handle_job_crawl() {
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let job = create_test_job_for_processing("https://example.com", JobKindData::New).with_crawl(Some(Crawl {
+        max_depth: 0,
+        max_pages: 1,
+    }));
+
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
+
+    match result {
+        JobResult::Success {
+            html_compress,
+            llms_txt,
+            ..
+        } => {
+            assert!(!html_compress.is_empty(), "The URL's page should still be stored");
+            assert!(llms_txt.md_content().contains("# Example"));
+        }
+        _ => panic!("Expected the crawl job to succeed"),
+    }
+}
+
 #[tokio::test]
 async fn test_handle_job_new_vs_update_distinction() {
     let provider = MockLlmProvider::with_valid_llms_txt();
@@ -325,7 +415,7 @@ async fn test_handle_job_with_multiple_responses() {
     ]);
 
     let job = create_test_job_for_processing("https://example.com", JobKindData::New);
-    let result = handle_job(&provider, &job).await;
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
 
     assert!(
         matches!(result, JobResult::Success { .. }),
@@ -5,6 +5,9 @@
 //! - Marking jobs as Running when claimed
 //! - Handling concurrent worker access
 //! - Proper job status transitions
+//! - Only claiming the jobs of a worker's shard
+//! - Expiring queued jobs that passed their deadline
+//! - Never claiming cancelled jobs, and signalling the running ones that were cancelled
 
 use std::sync::Arc;
 
@@ -13,8 +16,11 @@ use data_model_ltx::{
     models::{JobKind, JobKindData, JobState, JobStatus},
     test_helpers::{TestDbGuard, clean_test_db, create_test_job, get_job_by_id, test_db_pool},
 };
+use diesel_async::RunQueryDsl;
 use tokio::sync::{Mutex, Semaphore};
-use worker_ltx::work::next_job_in_queue;
+use worker_ltx::cancellation::signal_cancelled_jobs;
+use worker_ltx::work::{expire_overdue_jobs, next_job_in_queue, next_job_in_shard};
+use worker_ltx::{RunningJobs, Shard};
 
 async fn next_job(pool: &db::DbPool) -> Result<JobState, worker_ltx::Error> {
     next_job_in_queue(pool, Arc::new(Semaphore::new(1))).await.map(|x| x.0)
@@ -285,3 +291,106 @@ async fn test_next_job_in_queue_marks_job_running_atomically() {
         "Job should be marked Running in database"
     );
 }
+
+#[tokio::test]
+async fn test_next_job_in_shard_partitions_jobs() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let urls: Vec<String> = (0..8).map(|i| format!("https://site{}.example.com", i)).collect();
+    for url in urls.iter() {
+        create_test_job(&pool, url, JobKind::New, JobStatus::Queued).await;
+    }
+
+    // each shard drains its own jobs, and together they claim every job exactly once
+    let mut claimed: Vec<String> = Vec::new();
+    for index in 0..2 {
+        let shard = Shard::new(index, 2).unwrap();
+        while let Ok((job, _permit)) = next_job_in_shard(&pool, Arc::new(Semaphore::new(1)), Some(shard)).await {
+            claimed.push(job.url);
+        }
+    }
+    claimed.sort();
+    let mut expected = urls.clone();
+    expected.sort();
+    assert_eq!(claimed, expected);
+
+    assert!(next_job(&pool).await.is_err(), "All jobs should have been claimed");
+}
+
+#[tokio::test]
+async fn test_expire_overdue_jobs() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let now = chrono::Utc::now();
+    let job_with_deadline = |url: &str, expires_at: chrono::DateTime<chrono::Utc>| JobState {
+        expires_at: Some(expires_at),
+        ..JobState::from_kind_data(
+            uuid::Uuid::new_v4(),
+            url.to_string(),
+            JobStatus::Queued,
+            JobKindData::New,
+        )
+    };
+    let overdue = job_with_deadline("https://overdue.com", now - chrono::Duration::minutes(5));
+    let pending = job_with_deadline("https://pending.com", now + chrono::Duration::hours(1));
+    {
+        let mut conn = pool.get().await.unwrap();
+        for job in [&overdue, &pending] {
+            diesel::insert_into(data_model_ltx::schema::job_state::table)
+                .values(job)
+                .execute(&mut conn)
+                .await
+                .unwrap();
+        }
+    }
+
+    assert_eq!(expire_overdue_jobs(&pool).await.unwrap(), 1);
+    let expired = get_job_by_id(&pool, overdue.job_id).await.unwrap();
+    assert_eq!(expired.status, JobStatus::Expired);
+    assert!(expired.status.is_completed());
+
+    // only the job that's still in time is claimed
+    let claimed = next_job(&pool).await.unwrap();
+    assert_eq!(claimed.job_id, pending.job_id);
+    assert!(next_job(&pool).await.is_err());
+}
+
+#[tokio::test]
+async fn test_cancelled_jobs() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    // a cancelled job is never claimed
+    create_test_job(&pool, "https://cancelled.com", JobKind::New, JobStatus::Cancelled).await;
+    assert!(next_job(&pool).await.is_err());
+
+    // a running job is told once it's cancelled
+    let job = create_test_job(&pool, "https://running.com", JobKind::New, JobStatus::Queued).await;
+    let claimed = next_job(&pool).await.unwrap();
+    assert_eq!(claimed.job_id, job.job_id);
+    let running = RunningJobs::default();
+    let cancellation = running.start(job.job_id);
+    assert_eq!(signal_cancelled_jobs(&pool, &running).await.unwrap(), 0);
+    assert!(!cancellation.is_cancelled());
+
+    {
+        use data_model_ltx::schema::job_state;
+        use diesel::prelude::*;
+        let mut conn = pool.get().await.unwrap();
+        diesel::update(job_state::table.find(job.job_id))
+            .set(job_state::status.eq(JobStatus::Cancelled))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+    }
+    assert_eq!(signal_cancelled_jobs(&pool, &running).await.unwrap(), 1);
+    assert!(cancellation.is_cancelled());
+}
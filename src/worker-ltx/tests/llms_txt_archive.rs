@@ -0,0 +1,106 @@
+//! Tests for the archive tier of the llms.txt records
+//!
+//! This module tests archive_superseded_llms_txt(), which moves the superseded llms_txt records that are old enough
+//! to the llms_txt_archive table:
+//! - A website's latest successful record, and the failures after it, stay
+//! - Older records, successful or not, are moved once they're old enough
+//! - Archiving again moves nothing
+//! - Nothing is older than a duration longer than there's been time
+
+use std::time::Duration;
+
+use chrono::Utc;
+use core_ltx::normalize_html;
+use data_model_ltx::{
+    models::{ArchivedLlmsTxt, JobKind, JobStatus, LlmsTxt, LlmsTxtResult},
+    schema,
+    test_helpers::{TestDbGuard, clean_test_db, create_completed_test_job, create_test_job, test_db_pool},
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use worker_ltx::llms_txt_archive::archive_superseded_llms_txt;
+
+static TEST_MUTEX: Mutex<()> = Mutex::const_new(());
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+async fn set_days_old(pool: &core_ltx::db::DbPool, job_id: Uuid, days: i64) {
+    let mut conn = pool.get().await.unwrap();
+    diesel::update(schema::llms_txt::table.find(job_id))
+        .set(schema::llms_txt::created_at.eq(Utc::now() - chrono::Duration::days(days)))
+        .execute(&mut conn)
+        .await
+        .unwrap();
+}
+
+async fn create_failure(pool: &core_ltx::db::DbPool, url: &str) -> Uuid {
+    let job = create_test_job(pool, url, JobKind::New, JobStatus::Failure).await;
+    let record = LlmsTxt::from_result(
+        job.job_id,
+        url.to_string(),
+        LlmsTxtResult::Error {
+            failure_reason: "LLM timed out".to_string(),
+        },
+        vec![],
+        String::new(),
+    );
+    let mut conn = pool.get().await.unwrap();
+    diesel::insert_into(schema::llms_txt::table)
+        .values(&record)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    job.job_id
+}
+
+#[tokio::test]
+async fn test_archive_superseded_llms_txt() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let html = normalize_html("<html><body>Example</body></html>").unwrap();
+    let (oldest, _) = create_completed_test_job(&pool, "https://example.com", "# Example v1", &html).await;
+    set_days_old(&pool, oldest.job_id, 10).await;
+    let old_failure = create_failure(&pool, "https://example.com").await;
+    set_days_old(&pool, old_failure, 9).await;
+    let (latest, _) = create_completed_test_job(&pool, "https://Example.com:443", "# Example v2", &html).await;
+    set_days_old(&pool, latest.job_id, 8).await;
+    let new_failure = create_failure(&pool, "https://example.com").await;
+    // superseded, but not old enough
+    let (recent, _) = create_completed_test_job(&pool, "https://recent.com", "# Recent v1", &html).await;
+    create_completed_test_job(&pool, "https://recent.com", "# Recent v2", &html).await;
+    // the only record of its website
+    let (only, _) = create_completed_test_job(&pool, "https://only.com", "# Only", &html).await;
+    set_days_old(&pool, only.job_id, 30).await;
+
+    assert_eq!(archive_superseded_llms_txt(&pool, DAY).await.unwrap(), 2);
+
+    let mut conn = pool.get().await.unwrap();
+    let mut archived: Vec<ArchivedLlmsTxt> = schema::llms_txt_archive::table
+        .select(ArchivedLlmsTxt::as_select())
+        .load(&mut conn)
+        .await
+        .unwrap();
+    archived.sort_by_key(|record| record.created_at);
+    let archived_ids: Vec<Uuid> = archived.iter().map(|record| record.job_id).collect();
+    assert_eq!(archived_ids, vec![oldest.job_id, old_failure]);
+    assert_eq!(archived[0].result_data.text(), "# Example v1");
+
+    let left: Vec<Uuid> = schema::llms_txt::table
+        .select(schema::llms_txt::job_id)
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(left.len(), 5);
+    for job_id in [latest.job_id, new_failure, recent.job_id, only.job_id] {
+        assert!(left.contains(&job_id));
+    }
+
+    assert_eq!(archive_superseded_llms_txt(&pool, DAY).await.unwrap(), 0);
+    // longer than there's been time: nothing is that old
+    assert_eq!(archive_superseded_llms_txt(&pool, Duration::MAX).await.unwrap(), 0);
+}
@@ -0,0 +1,298 @@
+//! End-to-end tests of the job pipeline that need neither network access nor an API key
+//!
+//! The website is served by a local HTTP server and the LLM is a `MockLlmProvider` or a `ReplayLlmProvider`, so
+//! handle_job() runs every stage (download, normalization, origin import, generation, validation) deterministically.
+
+use axum::Router;
+use axum::http::StatusCode;
+use axum::routing::get;
+use core_ltx::decompress_to_string;
+use core_ltx::fixture_server::FixtureServer;
+use core_ltx::llms::mock::{MockLlmProvider, minimal_llms_txt, sample_html, sample_valid_llms_txt};
+use core_ltx::llms::replay::{ReplayLlmProvider, prompt_hash};
+use data_model_ltx::models::{JobKindData, JobState, JobStatus, LlmsTxtSource};
+use worker_ltx::WorkerSettings;
+use worker_ltx::work::{JobResult, handle_job};
+
+/// Serves `app` on a free local port, and returns its base URL.
+async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind the test server");
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{addr}/")
+}
+
+/// A website whose home page is `sample_html()`, without an llms.txt of its own
+async fn serve_website() -> String {
+    serve(Router::new().route("/", get(|| async { ([("content-type", "text/html")], sample_html()) }))).await
+}
+
+fn create_job(url: &str, kind_data: JobKindData) -> JobState {
+    JobState::from_kind_data(uuid::Uuid::new_v4(), url.to_string(), JobStatus::Queued, kind_data)
+}
+
+#[tokio::test]
+async fn test_pipeline_generates_from_local_website() {
+    let url = serve_website().await;
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let result = handle_job(
+        &provider,
+        &create_job(&url, JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    match result {
+        JobResult::Success {
+            html_compress,
+            html_checksum,
+            llms_txt,
+            source,
+            ..
+        } => {
+            assert_eq!(source, LlmsTxtSource::Generated);
+            assert_eq!(html_checksum.len(), 32);
+            let html = decompress_to_string(&html_compress).unwrap();
+            assert!(
+                html.contains("Welcome to Example.com"),
+                "The stored HTML is the website's: {html}"
+            );
+            assert!(llms_txt.md_content().contains("# Example"));
+        }
+        other => panic!("Expected success, got: {:?}", other.error()),
+    }
+
+    let prompts = provider.received_prompts();
+    assert_eq!(prompts.len(), 1, "A valid response needs no fix prompt");
+    assert!(
+        prompts[0].contains("Welcome to Example.com"),
+        "The prompt has the website's content"
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_replays_recorded_responses() {
+    let url = serve_website().await;
+
+    // Record the prompts once...
+    let recorder = MockLlmProvider::with_valid_llms_txt();
+    let job = create_job(&url, JobKindData::New);
+    let recorded = handle_job(&recorder, &job, &WorkerSettings::default()).await;
+    let JobResult::Success { llms_txt: expected, .. } = recorded else {
+        panic!("Expected success, got: {:?}", recorded.error());
+    };
+
+    // ...then the pipeline sends the very same ones, and gets the same llms.txt from their responses
+    let replay = ReplayLlmProvider::with_responses(
+        recorder
+            .received_prompts()
+            .into_iter()
+            .map(|prompt| (prompt, sample_valid_llms_txt())),
+    );
+    let replayed = handle_job(&replay, &create_job(&url, JobKindData::New), &WorkerSettings::default()).await;
+    match replayed {
+        JobResult::Success { llms_txt, .. } => assert_eq!(llms_txt.md_content(), expected.md_content()),
+        other => panic!("Expected success, got: {:?}", other.error()),
+    }
+    assert!(replay.misses().is_empty(), "Misses: {:?}", replay.misses());
+}
+
+#[tokio::test]
+async fn test_pipeline_replay_miss_keeps_html() {
+    let url = serve_website().await;
+    let replay = ReplayLlmProvider::new();
+
+    let result = handle_job(&replay, &create_job(&url, JobKindData::New), &WorkerSettings::default()).await;
+
+    match result {
+        JobResult::GenerationFailed {
+            html_compress, error, ..
+        } => {
+            assert!(!html_compress.is_empty(), "The HTML is kept when the generation fails");
+            let misses = replay.misses();
+            assert_eq!(misses.len(), 1);
+            assert!(error.to_string().contains(&misses[0]), "{error}");
+        }
+        other => panic!("Expected a generation failure, got: {:?}", other.error()),
+    }
+}
+
+#[tokio::test]
+async fn test_pipeline_updates_from_local_website() {
+    let url = serve_website().await;
+    let provider = MockLlmProvider::with_default(minimal_llms_txt());
+    let old_llms_txt = sample_valid_llms_txt().to_string();
+    let job = create_job(
+        &url,
+        JobKindData::Update {
+            llms_txt: old_llms_txt.clone(),
+        },
+    );
+
+    let result = handle_job(&provider, &job, &WorkerSettings::default()).await;
+
+    assert!(
+        matches!(result, JobResult::Success { .. }),
+        "Expected success, got: {:?}",
+        result.error()
+    );
+    let prompts = provider.received_prompts();
+    assert_eq!(prompts.len(), 1);
+    assert!(
+        prompts[0].contains(old_llms_txt.trim()),
+        "The update prompt has the current llms.txt"
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_imports_published_llms_txt() {
+    let url = serve(
+        Router::new()
+            .route("/", get(|| async { ([("content-type", "text/html")], sample_html()) }))
+            .route("/llms.txt", get(|| async { sample_valid_llms_txt() })),
+    )
+    .await;
+    let provider = MockLlmProvider::with_failure();
+
+    let result = handle_job(
+        &provider,
+        &create_job(&url, JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    match result {
+        JobResult::Success { source, llms_txt, .. } => {
+            assert_eq!(source, LlmsTxtSource::Origin);
+            assert!(llms_txt.md_content().contains("# Example"));
+        }
+        other => panic!(
+            "Expected the published llms.txt to be imported, got: {:?}",
+            other.error()
+        ),
+    }
+    assert!(
+        provider.received_prompts().is_empty(),
+        "No tokens spent on an imported llms.txt"
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_download_failure() {
+    let url = serve(Router::new().route("/", get(|| async { StatusCode::NOT_FOUND }))).await;
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let result = handle_job(
+        &provider,
+        &create_job(&url, JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    assert!(
+        matches!(result, JobResult::DownloadFailed { .. }),
+        "Expected a download failure, got: {:?}",
+        result.error()
+    );
+    assert!(provider.received_prompts().is_empty());
+}
+
+#[tokio::test]
+async fn test_pipeline_replays_from_prompt_hash() {
+    let url = serve_website().await;
+    let recorder = MockLlmProvider::with_valid_llms_txt();
+    handle_job(
+        &recorder,
+        &create_job(&url, JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+    let prompt = recorder.received_prompts().remove(0);
+
+    // A replay directory holds one `<prompt hash>.md` file per response
+    let dir = std::env::temp_dir().join(format!("pipeline-replay-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(format!("{}.md", prompt_hash(&prompt))),
+        sample_valid_llms_txt(),
+    )
+    .unwrap();
+    let replay = ReplayLlmProvider::from_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let result = handle_job(&replay, &create_job(&url, JobKindData::New), &WorkerSettings::default()).await;
+    assert!(
+        matches!(result, JobResult::Success { .. }),
+        "Expected success, got: {:?}",
+        result.error()
+    );
+}
+
+#[tokio::test]
+async fn test_pipeline_follows_redirect_and_retries() {
+    let site = FixtureServer::start().await;
+    site.redirect("/", 301, "/home").await;
+    site.status_then_page("/home", 503, 1, sample_html()).await;
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let result = handle_job(
+        &provider,
+        &create_job(site.base_url().as_str(), JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    assert!(
+        matches!(result, JobResult::Success { .. }),
+        "Expected success, got: {:?}",
+        result.error()
+    );
+    assert_eq!(site.requests_to("/home").await, 2, "The 503 is retried");
+}
+
+#[tokio::test]
+async fn test_pipeline_permanent_download_failure() {
+    let site = FixtureServer::start().await;
+    site.status("/", 406).await;
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let result = handle_job(
+        &provider,
+        &create_job(site.base_url().as_str(), JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    assert!(
+        matches!(result, JobResult::DownloadFailed { .. }),
+        "Expected a download failure, got: {:?}",
+        result.error()
+    );
+    assert_eq!(site.requests_to("/").await, 1, "A 406 isn't retried");
+    assert!(provider.received_prompts().is_empty());
+}
+
+#[tokio::test]
+async fn test_pipeline_honors_x_robots_tag() {
+    let site = FixtureServer::start().await;
+    site.page_with_headers("/", sample_html(), &[("x-robots-tag", "noai")])
+        .await;
+    let provider = MockLlmProvider::with_valid_llms_txt();
+
+    let result = handle_job(
+        &provider,
+        &create_job(site.base_url().as_str(), JobKindData::New),
+        &WorkerSettings::default(),
+    )
+    .await;
+
+    assert!(
+        matches!(result, JobResult::Disallowed { .. }),
+        "Expected the page to be disallowed, got: {:?}",
+        result.error()
+    );
+    assert!(provider.received_prompts().is_empty());
+}
@@ -3,19 +3,24 @@
 //! This module tests the handle_result() function which is responsible for:
 //! - Inserting llms_txt records for successful jobs
 //! - Updating job_state status appropriately
-//! - Handling four result types: Success, GenerationFailed, DownloadFailed, HtmlProcessingFailed
+//! - Handling five result types: Success, GenerationFailed, DownloadFailed, HtmlProcessingFailed, Disallowed
 //! - Ensuring database transactions are atomic
+//! - Storing nothing for a job that isn't running anymore (e.g. cancelled)
+//! - Post-processing generated llms.txt files before they're stored (handle_result_post_processed())
 
 use core_ltx::{
-    compress_string, decompress_to_string, is_valid_markdown, normalize_html, validate_is_llm_txt,
-    web_html::compute_html_checksum,
+    JobLog, SiteMetadata, compress_string, decompress_to_string, is_valid_markdown, llms::prompt_version,
+    normalize_html, validate_is_llm_txt, web_html::compute_html_checksum,
 };
 use data_model_ltx::{
-    models::{JobKind, JobStatus, ResultStatus},
-    test_helpers::{TestDbGuard, clean_test_db, create_test_job, get_job_by_id, get_llms_txt_by_job_id, test_db_pool},
+    models::{JobKind, JobStatus, LlmsTxtSource, ResultStatus},
+    test_helpers::{
+        TestDbGuard, clean_test_db, create_test_job, get_job_by_id, get_job_logs, get_llms_txt_by_job_id, test_db_pool,
+    },
 };
 use tokio::sync::Mutex;
-use worker_ltx::work::{JobResult, handle_result};
+use worker_ltx::post_process::parse_hooks;
+use worker_ltx::work::{JobResult, handle_result, handle_result_post_processed, store_job_log};
 
 /// Helper to create a valid LlmsTxt for testing
 fn create_test_llms_txt(content: &str) -> core_ltx::LlmsTxt {
@@ -45,7 +50,8 @@ async fn test_handle_result_success() {
     let _guard = TEST_MUTEX.lock().await;
     clean_test_db(&pool).await;
 
-    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
+    let mut job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
+    job.model = Some("gpt-5-nano".to_string());
 
     let (html_compress, html_checksum) = compress_html("<html><body><h1>Test</h1></body></html>");
     let llms_txt = create_test_llms_txt("# Test Site\n\n> Test\n\n- [Home](/)");
@@ -54,12 +60,15 @@ async fn test_handle_result_success() {
         html_compress: html_compress.clone(),
         html_checksum: html_checksum.clone(),
         llms_txt,
+        source: LlmsTxtSource::Generated,
+        metadata: SiteMetadata::default(),
     };
 
     handle_result(&pool, &job, result).await.unwrap();
 
     let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
     assert_eq!(updated_job.status, JobStatus::Success);
+    assert_eq!(updated_job.failure_kind, None);
 
     let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await.unwrap();
     assert_eq!(llms_txt_record.job_id, job.job_id);
@@ -67,7 +76,114 @@ async fn test_handle_result_success() {
     assert_eq!(llms_txt_record.result_status, ResultStatus::Ok);
     assert_eq!(llms_txt_record.html_compress, html_compress);
     assert_eq!(llms_txt_record.html_checksum, html_checksum);
-    assert!(llms_txt_record.result_data.contains("# Test Site"));
+    assert!(llms_txt_record.result_data.text().contains("# Test Site"));
+    assert_eq!(llms_txt_record.source, LlmsTxtSource::Generated);
+    assert_eq!(llms_txt_record.prompt_version.as_deref(), Some(prompt_version()));
+    assert_eq!(llms_txt_record.model.as_deref(), Some("gpt-5-nano"));
+}
+
+#[tokio::test]
+async fn test_handle_result_imported_from_origin() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
+
+    let (html_compress, html_checksum) = compress_html("<html><body><h1>Test</h1></body></html>");
+    let result = JobResult::Success {
+        html_compress,
+        html_checksum,
+        llms_txt: create_test_llms_txt("# Published\n\n> By the site itself\n\n- [Home](/)"),
+        source: LlmsTxtSource::Origin,
+        metadata: SiteMetadata {
+            title: Some("Published Site".to_string()),
+            favicon_url: Some("https://example.com/favicon.ico".to_string()),
+            ..SiteMetadata::default()
+        },
+    };
+
+    handle_result(&pool, &job, result).await.unwrap();
+
+    let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
+    assert_eq!(updated_job.status, JobStatus::Success);
+
+    let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await.unwrap();
+    assert_eq!(llms_txt_record.result_status, ResultStatus::Ok);
+    assert_eq!(llms_txt_record.source, LlmsTxtSource::Origin);
+    assert_eq!(llms_txt_record.prompt_version, None);
+    assert_eq!(llms_txt_record.model, None);
+    assert_eq!(llms_txt_record.title.as_deref(), Some("Published Site"));
+    assert_eq!(llms_txt_record.description, None);
+    assert_eq!(
+        llms_txt_record.favicon_url.as_deref(),
+        Some("https://example.com/favicon.ico")
+    );
+    assert!(llms_txt_record.result_data.text().contains("# Published"));
+}
+
+#[tokio::test]
+async fn test_handle_result_post_processed() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let hooks = parse_hooks("absolute_links,strip_tracking").unwrap();
+    let content = "# Test Site\n\n> Test\n\n## Docs\n\n- [Guide](/docs/guide?utm_source=llm&page=2): Start here";
+    let mut stored = Vec::new();
+    for source in [LlmsTxtSource::Generated, LlmsTxtSource::Origin] {
+        let job = create_test_job(&pool, "https://example.com/", JobKind::New, JobStatus::Running).await;
+        let (html_compress, html_checksum) = compress_html("<html><body><h1>Test</h1></body></html>");
+        let result = JobResult::Success {
+            html_compress,
+            html_checksum,
+            llms_txt: create_test_llms_txt(content),
+            source,
+            metadata: SiteMetadata::default(),
+        };
+        handle_result_post_processed(&pool, &job, result, &hooks).await.unwrap();
+        let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await.unwrap();
+        stored.push(llms_txt_record.result_data.text().into_owned());
+    }
+
+    // a generated llms.txt is post-processed, an imported one is stored as its publisher wrote it
+    assert!(
+        stored[0].contains("[Guide](https://example.com/docs/guide?page=2)"),
+        "{}",
+        stored[0]
+    );
+    assert!(
+        stored[1].contains("[Guide](/docs/guide?utm_source=llm&page=2)"),
+        "{}",
+        stored[1]
+    );
+}
+
+#[tokio::test]
+async fn test_handle_result_of_cancelled_job() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    // cancelled after its last check
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Cancelled).await;
+
+    let (html_compress, html_checksum) = compress_html("<html><body><h1>Test</h1></body></html>");
+    let result = JobResult::Success {
+        html_compress,
+        html_checksum,
+        llms_txt: create_test_llms_txt("# Test Site\n\n> Test\n\n- [Home](/)"),
+        source: LlmsTxtSource::Generated,
+        metadata: SiteMetadata::default(),
+    };
+    handle_result(&pool, &job, result).await.unwrap();
+
+    let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
+    assert_eq!(updated_job.status, JobStatus::Cancelled);
+    assert!(get_llms_txt_by_job_id(&pool, job.job_id).await.is_none());
 }
 
 #[tokio::test]
@@ -92,13 +208,14 @@ async fn test_handle_result_generation_failed() {
 
     let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
     assert_eq!(updated_job.status, JobStatus::Failure);
+    assert_eq!(updated_job.failure_kind.as_deref(), Some("invalid_output"));
 
     let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await.unwrap();
     assert_eq!(llms_txt_record.job_id, job.job_id);
     assert_eq!(llms_txt_record.result_status, ResultStatus::Error);
     assert_eq!(llms_txt_record.html_compress, html_compress, "HTML should be preserved");
     assert!(
-        llms_txt_record.result_data.contains("LLM generation failed"),
+        llms_txt_record.result_data.text().contains("LLM generation failed"),
         "Error message should be stored"
     );
 }
@@ -112,7 +229,10 @@ async fn test_handle_result_download_failed() {
 
     let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
 
-    let error = create_test_error("Download failed");
+    let error = worker_ltx::Error::CoreError(core_ltx::Error::HttpError {
+        url: url::Url::parse("https://example.com").unwrap(),
+        status_code: 406,
+    });
 
     let result = JobResult::DownloadFailed { error };
 
@@ -120,6 +240,7 @@ async fn test_handle_result_download_failed() {
 
     let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
     assert_eq!(updated_job.status, JobStatus::Failure);
+    assert_eq!(updated_job.failure_kind.as_deref(), Some("http_status:406"));
 
     let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await;
     assert!(
@@ -153,6 +274,35 @@ async fn test_handle_result_html_processing_failed() {
     );
 }
 
+#[tokio::test]
+async fn test_handle_result_disallowed() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
+
+    let disallowed =
+        core_ltx::find_disallowing_directive(r#"<html><head><meta name="robots" content="noai"></head></html>"#, &[])
+            .expect("noai should disallow indexing");
+    let result = JobResult::Disallowed {
+        error: worker_ltx::Error::DisallowedByPublisher(disallowed),
+    };
+
+    handle_result(&pool, &job, result).await.unwrap();
+
+    let updated_job = get_job_by_id(&pool, job.job_id).await.unwrap();
+    assert_eq!(updated_job.status, JobStatus::Failure);
+    assert_eq!(updated_job.failure_kind.as_deref(), Some("robots_disallowed"));
+
+    let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await;
+    assert!(
+        llms_txt_record.is_none(),
+        "Should not keep the content of pages whose publisher disallows indexing"
+    );
+}
+
 #[tokio::test]
 async fn test_handle_result_preserves_html_on_generation_failure() {
     let _db = TestDbGuard::acquire().await;
@@ -196,6 +346,8 @@ async fn test_handle_result_transaction_atomicity_success() {
         html_compress,
         html_checksum,
         llms_txt: create_test_llms_txt("# Test\n\n> Test\n\n- [Link](/)"),
+        source: LlmsTxtSource::Generated,
+        metadata: SiteMetadata::default(),
     };
 
     handle_result(&pool, &job, result).await.unwrap();
@@ -228,6 +380,8 @@ async fn test_handle_result_multiple_jobs() {
             html_compress: html_compress1,
             html_checksum: html_checksum1,
             llms_txt: create_test_llms_txt("# Job 1\n\n> Test\n\n- [Link](/)"),
+            source: LlmsTxtSource::Generated,
+            metadata: SiteMetadata::default(),
         },
     )
     .await
@@ -289,7 +443,7 @@ async fn test_handle_result_error_message_storage() {
 
     let llms_txt_record = get_llms_txt_by_job_id(&pool, job.job_id).await.unwrap();
     assert!(
-        llms_txt_record.result_data.contains(error_message),
+        llms_txt_record.result_data.text().contains(error_message),
         "Error message should be stored"
     );
 }
@@ -326,6 +480,8 @@ async fn test_handle_result_concurrent_results() {
                 html_compress: html_compress1,
                 html_checksum: html_checksum1,
                 llms_txt: create_test_llms_txt("# Job 1\n\n> Test\n\n- [Link](/)"),
+                source: LlmsTxtSource::Generated,
+                metadata: SiteMetadata::default(),
             },
         )
         .await
@@ -339,6 +495,8 @@ async fn test_handle_result_concurrent_results() {
                 html_compress: html_compress2,
                 html_checksum: html_checksum2,
                 llms_txt: create_test_llms_txt("# Job 2\n\n> Test\n\n- [Link](/)"),
+                source: LlmsTxtSource::Generated,
+                metadata: SiteMetadata::default(),
             },
         )
         .await
@@ -352,6 +510,8 @@ async fn test_handle_result_concurrent_results() {
                 html_compress: html_compress3,
                 html_checksum: html_checksum3,
                 llms_txt: create_test_llms_txt("# Job 3\n\n> Test\n\n- [Link](/)"),
+                source: LlmsTxtSource::Generated,
+                metadata: SiteMetadata::default(),
             },
         )
         .await
@@ -365,3 +525,35 @@ async fn test_handle_result_concurrent_results() {
     assert_eq!(get_job_by_id(&pool, job2_id).await.unwrap().status, JobStatus::Success);
     assert_eq!(get_job_by_id(&pool, job3_id).await.unwrap().status, JobStatus::Success);
 }
+
+#[tokio::test]
+async fn test_store_job_log_replaces_earlier_log() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let job = create_test_job(&pool, "https://example.com", JobKind::New, JobStatus::Running).await;
+    let log = JobLog {
+        text: "INFO worker_ltx::work: Received job\n".to_string(),
+        truncated: false,
+    };
+    store_job_log(&pool, job.job_id, log)
+        .await
+        .expect("Failed to store job log");
+    let stored = get_job_logs(&pool, job.job_id).await.expect("Job log should be stored");
+    assert_eq!(stored.log, "INFO worker_ltx::work: Received job\n");
+    assert!(!stored.truncated);
+
+    // a job that's run again (e.g. after a retry) keeps only its latest log
+    let log = JobLog {
+        text: "WARN worker_ltx::work: Retrying\n".to_string(),
+        truncated: true,
+    };
+    store_job_log(&pool, job.job_id, log)
+        .await
+        .expect("Failed to replace job log");
+    let stored = get_job_logs(&pool, job.job_id).await.expect("Job log should be stored");
+    assert_eq!(stored.log, "WARN worker_ltx::work: Retrying\n");
+    assert!(stored.truncated);
+}
@@ -0,0 +1,73 @@
+//! Tests for recording the attempts at webhook deliveries
+//!
+//! This module tests record_attempt(), which stores an attempt at a delivery and moves the delivery on:
+//! - A failed attempt schedules a retry
+//! - An attempt at a delivery that another worker attempted since it was claimed (its lease was over) isn't recorded
+
+use chrono::Utc;
+use data_model_ltx::{
+    models::{DeliveryStatus, Webhook, WebhookDelivery, WebhookDeliveryAttempt},
+    schema,
+    secrets::Keyring,
+    test_helpers::{TestDbGuard, clean_test_db, test_db_pool},
+};
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use tokio::sync::Mutex;
+use worker_ltx::webhooks::{JOB_FINISHED_EVENT, record_attempt};
+
+static TEST_MUTEX: Mutex<()> = Mutex::const_new(());
+
+#[tokio::test]
+async fn test_record_attempt_once_per_claim() {
+    let _db = TestDbGuard::acquire().await;
+    let pool = test_db_pool().await;
+    let _guard = TEST_MUTEX.lock().await;
+    clean_test_db(&pool).await;
+
+    let keyring = Keyring::parse("k1:MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=").unwrap();
+    let webhook = Webhook::new(
+        "https://hooks.example.com/llms".to_string(),
+        &Webhook::generate_secret(),
+        &keyring,
+        Utc::now(),
+    );
+    let delivery = WebhookDelivery::new(webhook.webhook_id, JOB_FINISHED_EVENT, "{}".to_string(), Utc::now());
+    let mut conn = pool.get().await.unwrap();
+    diesel::insert_into(schema::webhooks::table)
+        .values(&webhook)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+    diesel::insert_into(schema::webhook_deliveries::table)
+        .values(&delivery)
+        .execute(&mut conn)
+        .await
+        .unwrap();
+
+    // two workers attempted the delivery they both claimed
+    let failed = (Some(503), Some("HTTP 503".to_string()));
+    assert!(record_attempt(&pool, &delivery, failed).await.unwrap());
+    assert!(!record_attempt(&pool, &delivery, (Some(200), None)).await.unwrap());
+
+    let stored: WebhookDelivery = schema::webhook_deliveries::table
+        .find(delivery.delivery_id)
+        .select(WebhookDelivery::as_select())
+        .first(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(stored.attempts, 1);
+    assert_eq!(stored.status, DeliveryStatus::Pending.as_str());
+    assert!(stored.next_attempt_at > delivery.next_attempt_at);
+    let attempts: Vec<WebhookDeliveryAttempt> = schema::webhook_delivery_attempts::table
+        .filter(schema::webhook_delivery_attempts::delivery_id.eq(delivery.delivery_id))
+        .select(WebhookDeliveryAttempt::as_select())
+        .load(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(attempts.len(), 1);
+    assert_eq!(attempts[0].status_code, Some(503));
+
+    // the next claim sees the first attempt
+    assert!(record_attempt(&pool, &stored, (Some(200), None)).await.unwrap());
+}